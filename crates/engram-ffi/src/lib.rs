@@ -1,64 +1,411 @@
 #![allow(unsafe_op_in_unsafe_fn)]
+// pyo3 0.20 `#[pymethods]` expands to impls that trip this lint on newer toolchains.
+#![allow(non_local_definitions)]
 
 use chrono::{DateTime, TimeZone, Utc};
 use engram_store::{
-    build_memory_packet, BuildRequest, EpisodeFilter, Event, EventKind, FactFilter, InsightFilter,
-    RecallCues, RecallPolicy, SqliteStore, Store, StoreError, StmState, TimeRangeFilter,
-    WorkingStatePatch, StoreResult,
+    backfill_embeddings, begin_run, build_handoff_packet, build_memory_packet,
+    build_memory_packet_as_of, build_memory_packets_bulk, carry_forward_insights, check_grounding,
+    checkpoint_run, chunk_text, decode_wire, encode_wire, end_run, enforce_memory_budget,
+    estimate_packet_size, expire_tenant_facts, export_tenant_archive, get_preferences,
+    import_tenant_archive_with, insight_lineage, list_tenant_procedures, memory_footprint,
+    merge_similar_episodes, pin_fact, pin_tenant_procedure, rebuild_derived_memory,
+    rebuild_vector_index, render_packet, replay_from_checkpoint, replay_working_state,
+    resolve_provenance, run_retention, set_preference, stream_events, tenant_stats, unpin_fact,
+    upsert_fact_checked, validate_json, vector_index_stats, verify_archive, AgentAccessPolicy,
+    BackfillOptions, BuildRequest, Change, ChangeLogStore, ChunkOptions, ConflictPolicy,
+    ConsolidationOptions, Consolidator, CorrelatedStore, DeleteMode, Embedder, EpisodeFilter, Event,
+    EventKind, EventPage, EventStream, FactFilter, ImportOptions, InputLimits, InsightFilter,
+    IsolatingStore, LanguageTaggingStore, Lease, MemoryBudget, MemoryRef, MeteredStore,
+    MeteringOptions, PayloadSchemaRegistry, PolicyConfig, PolicyRegistry, ReadPreference,
+    RecallCues, RecallPolicy, RecordRef, RetentionPolicy, RunEndOptions, RunOutcome, SchemaTarget,
+    ScopeHasher, ScopeHashingStore, ScriptDetector, SlotSchema, SlotSchemaRegistry,
+    SourceCredibility, SqliteStore, StatePatchJournal, StatsOptions, StmState, Store, StoreError,
+    StoreResult, TextQuery, TimeRangeFilter, UserLocale, ValidatingStore, ValidationMode,
+    WireFormat, WorkingStatePatch, WriteQueue, WriteQueueOptions, EVENT_STREAM_CHUNK,
 };
 #[cfg(feature = "mysql")]
 use engram_store::MySqlStore;
 #[cfg(feature = "postgres")]
 use engram_store::PostgresStore;
 use engram_types::{
-    Budget, Episode, Fact, FactStatus, InsightItem, JsonMap, KeyQuote, MemoryPacket, Procedure,
-    Purpose, Scope, ValidationState,
+    Budget, ContentHash, Episode, Fact, FactStatus, InsightItem, JsonMap, KeyQuote, MemoryPacket,
+    Procedure, Purpose, Scope, ValidationState, WireType, WorkingState, json_schema, new_ulid,
 };
-use pyo3::exceptions::PyValueError;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyBlockingIOError, PyConnectionError, PyTimeoutError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 #[pyclass]
 struct EngramStore {
     inner: Arc<dyn Store>,
+    schemas: Arc<PayloadSchemaRegistry>,
+    /// Checks working state patches and fills packets' open loops per task type.
+    slot_schemas: Arc<SlotSchemaRegistry>,
+    /// Recall policy presets that requests' `policy_id` resolves against.
+    policies: Arc<PolicyRegistry>,
+    /// Started by the first `try_append_event`.
+    writes: Arc<OnceLock<WriteQueue>>,
+    /// Counts backend calls per tenant when opened with `metering=True`.
+    metering: Option<Arc<MeteredStore<Arc<dyn Store>>>>,
+    /// Encoding of returned packets and events.
+    wire_format: WireFormat,
+}
+
+#[derive(Clone, Default)]
+struct WrapOptions {
+    strict: bool,
+    changelog: bool,
+    state_journal: bool,
+    detect_lang: bool,
+    agent_access: Option<AgentAccessPolicy>,
+    metering: bool,
+    scope_key: Option<Vec<u8>>,
+    wire_format: WireFormat,
+}
+
+impl WrapOptions {
+    fn new(
+        strict: bool,
+        changelog: bool,
+        state_journal: bool,
+        detect_lang: bool,
+        agent_access: Option<&str>,
+        metering: bool,
+        scope_key: Option<&[u8]>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            strict,
+            changelog,
+            state_journal,
+            detect_lang,
+            agent_access: agent_access.map(parse_json).transpose()?,
+            metering,
+            scope_key: scope_key.map(<[u8]>::to_vec),
+            wire_format: WireFormat::default(),
+        })
+    }
+}
+
+impl EngramStore {
+    fn wrap(store: Box<dyn Store>, options: WrapOptions) -> PyResult<Self> {
+        let schemas = Arc::new(PayloadSchemaRegistry::new());
+        let slot_schemas = Arc::new(SlotSchemaRegistry::new());
+        let mut inner: Arc<dyn Store> = Arc::from(store);
+        if let Some(key) = &options.scope_key {
+            let hasher = ScopeHasher::new(key).map_err(store_error)?;
+            inner = Arc::new(ScopeHashingStore::new(inner, hasher));
+        }
+        // Innermost, so it counts what actually reaches the backend.
+        let metering = options
+            .metering
+            .then(|| Arc::new(MeteredStore::new(inner.clone(), MeteringOptions::default())));
+        if let Some(metered) = &metering {
+            inner = metered.clone();
+        }
+        if options.state_journal {
+            inner = Arc::new(StatePatchJournal::new(inner));
+        }
+        if options.changelog {
+            inner = Arc::new(ChangeLogStore::new(inner));
+        }
+        if options.detect_lang {
+            inner = Arc::new(LanguageTaggingStore::new(inner, Arc::new(ScriptDetector)));
+        }
+        if let Some(policy) = options.agent_access {
+            inner = Arc::new(IsolatingStore::new(inner, policy));
+        }
+        let mut validating = ValidatingStore::new(inner, schemas.clone())
+            .with_slot_schemas(slot_schemas.clone());
+        if options.strict {
+            validating = validating.with_input_limits(InputLimits::default());
+        }
+        Ok(Self {
+            inner: Arc::new(validating),
+            schemas,
+            slot_schemas,
+            policies: Arc::new(PolicyRegistry::new()),
+            writes: Arc::new(OnceLock::new()),
+            metering,
+            wire_format: options.wire_format,
+        })
+    }
+
+    fn metered(&self) -> PyResult<Arc<MeteredStore<Arc<dyn Store>>>> {
+        self.metering
+            .clone()
+            .ok_or_else(|| PyValueError::new_err("metering is not enabled for this store"))
+    }
+
+    fn write_queue(&self) -> &WriteQueue {
+        self.writes
+            .get_or_init(|| WriteQueue::new(self.inner.clone(), WriteQueueOptions::default()))
+    }
 }
 
 #[pymethods]
 impl EngramStore {
     #[new]
-    #[pyo3(signature = (path=None, backend=None, dsn=None, database=None, in_memory=false))]
+    #[pyo3(signature = (
+        path=None,
+        backend=None,
+        dsn=None,
+        database=None,
+        in_memory=false,
+        strict=false,
+        changelog=false,
+        state_journal=false,
+        detect_lang=false,
+        agent_access=None,
+        metering=false,
+        scope_key=None,
+        wire_format="json",
+        statement_timeout_ms=None,
+        schema=None,
+        table_prefix=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         path: Option<String>,
         backend: Option<String>,
         dsn: Option<String>,
         database: Option<String>,
         in_memory: bool,
+        strict: bool,
+        changelog: bool,
+        state_journal: bool,
+        detect_lang: bool,
+        agent_access: Option<&str>,
+        metering: bool,
+        scope_key: Option<&[u8]>,
+        wire_format: &str,
+        statement_timeout_ms: Option<u64>,
+        schema: Option<String>,
+        table_prefix: Option<String>,
     ) -> PyResult<Self> {
-        let store = open_store(path, backend, dsn, database, in_memory).map_err(store_error)?;
-        Ok(Self { inner: Arc::from(store) })
+        let mut options = WrapOptions::new(
+            strict,
+            changelog,
+            state_journal,
+            detect_lang,
+            agent_access,
+            metering,
+            scope_key,
+        )?;
+        options.wire_format = wire_format.parse().map_err(store_error)?;
+        let statement_timeout = statement_timeout_ms.map(Duration::from_millis);
+        let store = open_store(
+            path,
+            backend,
+            dsn,
+            database,
+            in_memory,
+            statement_timeout,
+            schema,
+            table_prefix,
+        )
+        .map_err(store_error)?;
+        Self::wrap(store, options)
     }
 
     #[staticmethod]
-    fn in_memory() -> PyResult<Self> {
+    #[pyo3(signature = (
+        strict=false,
+        changelog=false,
+        state_journal=false,
+        detect_lang=false,
+        agent_access=None,
+        metering=false,
+        scope_key=None,
+        wire_format="json"
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn in_memory(
+        strict: bool,
+        changelog: bool,
+        state_journal: bool,
+        detect_lang: bool,
+        agent_access: Option<&str>,
+        metering: bool,
+        scope_key: Option<&[u8]>,
+        wire_format: &str,
+    ) -> PyResult<Self> {
+        let mut options = WrapOptions::new(
+            strict,
+            changelog,
+            state_journal,
+            detect_lang,
+            agent_access,
+            metering,
+            scope_key,
+        )?;
+        options.wire_format = wire_format.parse().map_err(store_error)?;
         let inner: Box<dyn Store> =
             Box::new(SqliteStore::new_in_memory().map_err(store_error)?);
-        Ok(Self { inner: Arc::from(inner) })
+        Self::wrap(inner, options)
+    }
+
+    /// A handle on the same store whose calls, sync or async, run under
+    /// `correlation_id`, which tags their tracing spans and change log entries.
+    fn with_correlation_id(&self, correlation_id: &str) -> Self {
+        Self {
+            inner: Arc::new(CorrelatedStore::new(self.inner.clone(), correlation_id)),
+            schemas: self.schemas.clone(),
+            slot_schemas: self.slot_schemas.clone(),
+            policies: self.policies.clone(),
+            // Queued writes run on the queue's thread, so this handle gets its own.
+            writes: Arc::new(OnceLock::new()),
+            metering: self.metering.clone(),
+            wire_format: self.wire_format,
+        }
+    }
+
+    #[pyo3(signature = (after_seq=0, limit=None))]
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> PyResult<String> {
+        let changes = self
+            .inner
+            .list_changes(after_seq, limit)
+            .map_err(store_error)?;
+        to_json(&changes)
+    }
+
+    #[pyo3(signature = (after_seq=0, limit=None))]
+    fn async_list_changes<'p>(
+        &self,
+        py: Python<'p>,
+        after_seq: u64,
+        limit: Option<usize>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || {
+                let changes = store
+                    .list_changes(after_seq, limit)
+                    .map_err(store_error)?;
+                to_json(&changes)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn apply_changes(&self, batch_json: &str) -> PyResult<()> {
+        let batch: Vec<Change> = parse_json(batch_json)?;
+        self.inner.apply_changes(&batch).map_err(store_error)
+    }
+
+    fn async_apply_changes<'p>(&self, py: Python<'p>, batch_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let batch: Vec<Change> = parse_json(&batch_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.apply_changes(&batch).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (schema_json, kind=None, tag=None, mode="strict"))]
+    fn register_payload_schema(
+        &self,
+        schema_json: &str,
+        kind: Option<&str>,
+        tag: Option<&str>,
+        mode: &str,
+    ) -> PyResult<()> {
+        let schema: JsonValue = parse_json(schema_json)?;
+        let target = match (kind, tag) {
+            (Some(kind), None) => {
+                SchemaTarget::Kind(kind.parse::<EventKind>().map_err(store_error)?)
+            }
+            (None, Some(tag)) => SchemaTarget::Tag(tag.to_string()),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "exactly one of kind or tag is required",
+                ))
+            }
+        };
+        let mode = parse_validation_mode(mode)?;
+        self.schemas.register(target, &schema, mode).map_err(store_error)
+    }
+
+    /// Registers JSON Schemas for the `slots` and `constraints` of `task_type`'s
+    /// working state, given as `{"slots": ..., "constraints": ...}`.
+    #[pyo3(signature = (task_type, schema_json, mode="strict"))]
+    fn register_slot_schema(&self, task_type: &str, schema_json: &str, mode: &str) -> PyResult<()> {
+        let schema: SlotSchema = parse_json(schema_json)?;
+        let mode = parse_validation_mode(mode)?;
+        self.slot_schemas.register(task_type, &schema, mode).map_err(store_error)
     }
 
-    fn append_event(&self, event_json: &str) -> PyResult<()> {
-        let input: EventInput = parse_json(event_json)?;
-        let event = input.to_event()?;
+    /// Registers the recall policy preset `policy_id`, given as policy overrides like
+    /// a build request's `policy`.
+    fn register_policy(&self, policy_id: &str, policy_json: &str) -> PyResult<()> {
+        let config: PolicyConfig = parse_json(policy_json)?;
+        let policy = config.apply_to(RecallPolicy::default());
+        self.policies.register(policy_id, policy).map_err(store_error)
+    }
+
+    /// Registers the presets of a JSON or TOML policy file and returns their ids.
+    fn load_policies(&self, path: &str) -> PyResult<Vec<String>> {
+        self.policies.load_file(path).map_err(store_error)
+    }
+
+    /// Takes the event as a JSON `str` or CBOR `bytes`.
+    fn append_event(&self, event: &PyAny) -> PyResult<()> {
+        let input: EventInput = decode_input(event)?;
+        let event = input.into_event()?;
         self.inner.append_event(event).map_err(store_error)
     }
 
-    fn async_append_event<'p>(&self, py: Python<'p>, event_json: String) -> PyResult<&'p PyAny> {
+    /// Appends a list of events, in one transaction on SQL backends.
+    fn append_events_bulk(&self, events: &PyAny) -> PyResult<()> {
+        let inputs: Vec<EventInput> = decode_input(events)?;
+        let events = inputs
+            .into_iter()
+            .map(EventInput::into_event)
+            .collect::<PyResult<Vec<_>>>()?;
+        self.inner.append_events_bulk(&events).map_err(store_error)
+    }
+
+    /// Queues the event for a background writer instead of writing it in this call.
+    /// Raises `BlockingIOError` when the queue is full, so the caller can drop the
+    /// write rather than stall.
+    fn try_append_event(&self, event: &PyAny) -> PyResult<()> {
+        let input: EventInput = decode_input(event)?;
+        let event = input.into_event()?;
+        self.write_queue().try_append_event(event).map_err(store_error)
+    }
+
+    /// Waits until every queued event is written.
+    fn flush_writes(&self) -> PyResult<()> {
+        match self.writes.get() {
+            Some(queue) => queue.flush().map_err(store_error),
+            None => Ok(()),
+        }
+    }
+
+    fn async_flush_writes<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let writes = self.writes.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            tokio::task::spawn_blocking(move || match writes.get() {
+                Some(queue) => queue.flush().map_err(store_error),
+                None => Ok(()),
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn async_append_event<'p>(&self, py: Python<'p>, event: &PyAny) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
+        let input: EventInput = decode_input(event)?;
         pyo3_asyncio::tokio::future_into_py(py, async move {
-            let input: EventInput = parse_json(&event_json)?;
-            let event = input.to_event()?;
+            let event = input.into_event()?;
             tokio::task::spawn_blocking(move || {
                 store.append_event(event).map_err(store_error)
             }).await.map_err(py_error)??;
@@ -66,15 +413,81 @@ impl EngramStore {
         })
     }
 
+    fn async_append_events_bulk<'p>(&self, py: Python<'p>, events: &PyAny) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let inputs: Vec<EventInput> = decode_input(events)?;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let events = inputs
+                .into_iter()
+                .map(EventInput::into_event)
+                .collect::<PyResult<Vec<_>>>()?;
+            tokio::task::spawn_blocking(move || {
+                store.append_events_bulk(&events).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (tenant_id, range_json=None, k_anonymity=5, epsilon=None))]
+    fn tenant_stats(
+        &self,
+        tenant_id: &str,
+        range_json: Option<&str>,
+        k_anonymity: usize,
+        epsilon: Option<f64>,
+    ) -> PyResult<String> {
+        let range = match range_json {
+            Some(payload) => parse_json::<TimeRangeInput>(payload)?.into_filter()?,
+            None => TimeRangeFilter::default(),
+        };
+        let options = StatsOptions {
+            k_anonymity,
+            epsilon,
+            ..StatsOptions::default()
+        };
+        let stats = tenant_stats(self.inner.as_ref(), tenant_id, range, &options)
+            .map_err(store_error)?;
+        to_json(&stats)
+    }
+
+    #[pyo3(signature = (tenant_id, range_json=None, k_anonymity=5, epsilon=None))]
+    fn async_tenant_stats<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+        range_json: Option<String>,
+        k_anonymity: usize,
+        epsilon: Option<f64>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let range = match range_json {
+                Some(payload) => parse_json::<TimeRangeInput>(&payload)?.into_filter()?,
+                None => TimeRangeFilter::default(),
+            };
+            let options = StatsOptions {
+                k_anonymity,
+                epsilon,
+                ..StatsOptions::default()
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let stats = tenant_stats(store.as_ref(), &tenant_id, range, &options)
+                    .map_err(store_error)?;
+                to_json(&stats)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
     fn list_events(
         &self,
         scope_json: &str,
         range_json: Option<&str>,
         limit: Option<usize>,
-    ) -> PyResult<String> {
+    ) -> PyResult<Encoded> {
         let scope: Scope = parse_json(scope_json)?;
         let range = match range_json {
-            Some(payload) => parse_json::<TimeRangeInput>(payload)?.to_filter()?,
+            Some(payload) => parse_json::<TimeRangeInput>(payload)?.into_filter()?,
             None => TimeRangeFilter::default(),
         };
         let events = self
@@ -82,7 +495,7 @@ impl EngramStore {
             .list_events(&scope, range, limit)
             .map_err(store_error)?;
         let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
-        to_json(&output)
+        encode(&output, self.wire_format)
     }
 
     fn async_list_events<'p>(
@@ -93,10 +506,11 @@ impl EngramStore {
         limit: Option<usize>,
     ) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
+        let format = self.wire_format;
         pyo3_asyncio::tokio::future_into_py(py, async move {
             let scope: Scope = parse_json(&scope_json)?;
             let range = match range_json {
-                Some(payload) => parse_json::<TimeRangeInput>(&payload)?.to_filter()?,
+                Some(payload) => parse_json::<TimeRangeInput>(&payload)?.into_filter()?,
                 None => TimeRangeFilter::default(),
             };
             let json = tokio::task::spawn_blocking(move || {
@@ -104,7 +518,135 @@ impl EngramStore {
                     .list_events(&scope, range, limit)
                     .map_err(store_error)?;
                 let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
-                to_json(&output)
+                encode(&output, format)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, cursor=None, limit=100))]
+    fn list_events_page(
+        &self,
+        scope_json: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PyResult<Encoded> {
+        let scope: Scope = parse_json(scope_json)?;
+        let page = self
+            .inner
+            .list_events_page(&scope, cursor, limit)
+            .map_err(store_error)?;
+        encode(&EventPageOutput::from(page), self.wire_format)
+    }
+
+    #[pyo3(signature = (scope_json, cursor=None, limit=100))]
+    fn async_list_events_page<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let page = store
+                    .list_events_page(&scope, cursor.as_deref(), limit)
+                    .map_err(store_error)?;
+                encode(&EventPageOutput::from(page), format)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, range_json=None, chunk_size=EVENT_STREAM_CHUNK))]
+    fn stream_events(
+        &self,
+        scope_json: &str,
+        range_json: Option<&str>,
+        chunk_size: usize,
+    ) -> PyResult<EventChunks> {
+        let scope: Scope = parse_json(scope_json)?;
+        let range = match range_json {
+            Some(payload) => parse_json::<TimeRangeInput>(payload)?.into_filter()?,
+            None => TimeRangeFilter::default(),
+        };
+        let stream = stream_events(self.inner.clone(), &scope, range).chunk_size(chunk_size);
+        Ok(EventChunks {
+            stream: Arc::new(Mutex::new(stream)),
+            wire_format: self.wire_format,
+        })
+    }
+
+    fn search_events(&self, scope_json: &str, query_json: &str) -> PyResult<Encoded> {
+        let scope: Scope = parse_json(scope_json)?;
+        let query = parse_json::<TextQueryInput>(query_json)?.into_query()?;
+        let events = self
+            .inner
+            .search_events(&scope, &query)
+            .map_err(store_error)?;
+        let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+        encode(&output, self.wire_format)
+    }
+
+    fn async_search_events<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        query_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let query = parse_json::<TextQueryInput>(&query_json)?.into_query()?;
+            let json = tokio::task::spawn_blocking(move || {
+                let events = store
+                    .search_events(&scope, &query)
+                    .map_err(store_error)?;
+                let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+                encode(&output, format)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, seq=0, limit=None))]
+    fn get_events_since(
+        &self,
+        scope_json: &str,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> PyResult<Encoded> {
+        let scope: Scope = parse_json(scope_json)?;
+        let events = self
+            .inner
+            .get_events_since(&scope, seq, limit)
+            .map_err(store_error)?;
+        let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+        encode(&output, self.wire_format)
+    }
+
+    #[pyo3(signature = (scope_json, seq=0, limit=None))]
+    fn async_get_events_since<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let events = store
+                    .get_events_since(&scope, seq, limit)
+                    .map_err(store_error)?;
+                let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+                encode(&output, format)
             }).await.map_err(py_error)??;
             Ok(json)
         })
@@ -134,146 +676,1362 @@ impl EngramStore {
         })
     }
 
+    #[pyo3(signature = (scope_json, up_to_seq=None))]
+    fn replay_working_state(
+        &self,
+        scope_json: &str,
+        up_to_seq: Option<u64>,
+    ) -> PyResult<Option<String>> {
+        let scope: Scope = parse_json(scope_json)?;
+        replayed_state_json(self.inner.as_ref(), &scope, up_to_seq)
+    }
+
+    #[pyo3(signature = (scope_json, up_to_seq=None))]
+    fn async_replay_working_state<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        up_to_seq: Option<u64>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                replayed_state_json(store.as_ref(), &scope, up_to_seq)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn list_working_states(&self, scope_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let states = self.inner.list_working_states(&scope).map_err(store_error)?;
+        to_json(&states)
+    }
+
+    fn async_list_working_states<'p>(&self, py: Python<'p>, scope_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let states = store.list_working_states(&scope).map_err(store_error)?;
+                to_json(&states)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
     fn patch_working_state(&self, scope_json: &str, patch_json: &str) -> PyResult<String> {
         let scope: Scope = parse_json(scope_json)?;
         let patch_input: WorkingStatePatchInput = parse_json(patch_json)?;
-        let patch = patch_input.to_patch();
+        let patch = patch_input.into_patch();
         let state = self
             .inner
             .patch_working_state(&scope, patch)
             .map_err(store_error)?;
-        to_json(&state)
+        to_json(&state)
+    }
+
+    fn async_patch_working_state<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        patch_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let patch_input: WorkingStatePatchInput = parse_json(&patch_json)?;
+            let patch = patch_input.into_patch();
+            let json = tokio::task::spawn_blocking(move || {
+                let state = store
+                    .patch_working_state(&scope, patch)
+                    .map_err(store_error)?;
+                to_json(&state)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn patch_working_states(&self, patches_json: &str) -> PyResult<String> {
+        let patches = parse_scoped_patches(patches_json)?;
+        let states = self
+            .inner
+            .patch_working_states(patches)
+            .map_err(store_error)?;
+        to_json(&states)
+    }
+
+    fn async_patch_working_states<'p>(
+        &self,
+        py: Python<'p>,
+        patches_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let patches = parse_scoped_patches(&patches_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let states = store
+                    .patch_working_states(patches)
+                    .map_err(store_error)?;
+                to_json(&states)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn merge_working_state(&self, scope_json: &str, state_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let remote: WorkingState = parse_json(state_json)?;
+        let state = self
+            .inner
+            .merge_working_state(&scope, remote)
+            .map_err(store_error)?;
+        to_json(&state)
+    }
+
+    fn async_merge_working_state<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        state_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let remote: WorkingState = parse_json(&state_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let state = store
+                    .merge_working_state(&scope, remote)
+                    .map_err(store_error)?;
+                to_json(&state)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn checkpoint_run(&self, scope_json: &str, label: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let checkpoint = checkpoint_run(self.inner.as_ref(), &scope, label).map_err(store_error)?;
+        to_json(&checkpoint)
+    }
+
+    fn async_checkpoint_run<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        label: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let checkpoint =
+                    checkpoint_run(store.as_ref(), &scope, &label).map_err(store_error)?;
+                to_json(&checkpoint)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn replay_from_checkpoint(
+        &self,
+        scope_json: &str,
+        label: &str,
+        target_json: &str,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let target: Scope = parse_json(target_json)?;
+        let checkpoint = replay_from_checkpoint(self.inner.as_ref(), &scope, label, &target)
+            .map_err(store_error)?;
+        to_json(&checkpoint)
+    }
+
+    fn async_replay_from_checkpoint<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        label: String,
+        target_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let target: Scope = parse_json(&target_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let checkpoint = replay_from_checkpoint(store.as_ref(), &scope, &label, &target)
+                    .map_err(store_error)?;
+                to_json(&checkpoint)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn begin_run(&self, scope_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let event = begin_run(self.inner.as_ref(), &scope).map_err(store_error)?;
+        to_json(&EventOutput::from(event))
+    }
+
+    fn async_begin_run<'p>(&self, py: Python<'p>, scope_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let event = begin_run(store.as_ref(), &scope).map_err(store_error)?;
+                to_json(&EventOutput::from(event))
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, options_json=None))]
+    fn consolidate_run(&self, scope_json: &str, options_json: Option<&str>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let options = match options_json {
+            Some(payload) => parse_json::<ConsolidationOptionsInput>(payload)?.into_options(),
+            None => ConsolidationOptions::default(),
+        };
+        let consolidator = Consolidator::new(self.inner.as_ref());
+        let report = consolidator.consolidate_run(&scope, &options).map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (scope_json, options_json=None))]
+    fn async_consolidate_run<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        options_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let options = match options_json {
+                Some(payload) => parse_json::<ConsolidationOptionsInput>(&payload)?.into_options(),
+                None => ConsolidationOptions::default(),
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let consolidator = Consolidator::new(store);
+                let report = consolidator.consolidate_run(&scope, &options).map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, outcome, options_json=None))]
+    fn end_run(&self, scope_json: &str, outcome: &str, options_json: Option<&str>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let options = match options_json {
+            Some(payload) => parse_json::<RunEndOptionsInput>(payload)?.into_options(),
+            None => RunEndOptions::default(),
+        };
+        let report = end_run(self.inner.as_ref(), &scope, outcome, &options).map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (scope_json, outcome, options_json=None))]
+    fn async_end_run<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        outcome: String,
+        options_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let options = match options_json {
+                Some(payload) => parse_json::<RunEndOptionsInput>(&payload)?.into_options(),
+                None => RunEndOptions::default(),
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let report =
+                    end_run(store.as_ref(), &scope, &outcome, &options).map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, options_json=None))]
+    fn rebuild_derived_memory(&self, scope_json: &str, options_json: Option<&str>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let options = match options_json {
+            Some(payload) => parse_json::<RunEndOptionsInput>(payload)?.into_options(),
+            None => RunEndOptions::default(),
+        };
+        let report =
+            rebuild_derived_memory(self.inner.as_ref(), &scope, &options).map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (scope_json, options_json=None))]
+    fn async_rebuild_derived_memory<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        options_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let options = match options_json {
+                Some(payload) => parse_json::<RunEndOptionsInput>(&payload)?.into_options(),
+                None => RunEndOptions::default(),
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let report =
+                    rebuild_derived_memory(store.as_ref(), &scope, &options).map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn enforce_memory_budget(&self, scope_json: &str, budget_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let budget: MemoryBudget = parse_json(budget_json)?;
+        let report =
+            enforce_memory_budget(self.inner.as_ref(), &scope, &budget).map_err(store_error)?;
+        to_json(&report)
+    }
+
+    fn async_enforce_memory_budget<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        budget_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let budget: MemoryBudget = parse_json(&budget_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let report =
+                    enforce_memory_budget(store.as_ref(), &scope, &budget).map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn run_retention(&self, scope_json: &str, policy_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let policy: RetentionPolicy = parse_json(policy_json)?;
+        let report = run_retention(self.inner.as_ref(), &scope, &policy, Utc::now())
+            .map_err(store_error)?;
+        to_json(&report)
+    }
+
+    fn async_run_retention<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        policy_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let policy: RetentionPolicy = parse_json(&policy_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let report = run_retention(store.as_ref(), &scope, &policy, Utc::now())
+                    .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn memory_footprint(&self, scope_json: &str) -> PyResult<u64> {
+        let scope: Scope = parse_json(scope_json)?;
+        memory_footprint(self.inner.as_ref(), &scope).map_err(store_error)
+    }
+
+    fn async_memory_footprint<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let bytes = tokio::task::spawn_blocking(move || {
+                memory_footprint(store.as_ref(), &scope).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(bytes)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, model, embed, options_json=None))]
+    fn backfill_embeddings(
+        &self,
+        scope_json: &str,
+        model: String,
+        embed: PyObject,
+        options_json: Option<&str>,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let options = match options_json {
+            Some(payload) => parse_json::<BackfillOptionsInput>(payload)?.into_options(),
+            None => BackfillOptions::default(),
+        };
+        let embedder = PyEmbedder { model, embed };
+        let report = backfill_embeddings(self.inner.as_ref(), &scope, &embedder, &options)
+            .map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (scope_json, model, embed, options_json=None))]
+    fn async_backfill_embeddings<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        model: String,
+        embed: PyObject,
+        options_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let options = match options_json {
+                Some(payload) => parse_json::<BackfillOptionsInput>(&payload)?.into_options(),
+                None => BackfillOptions::default(),
+            };
+            let embedder = PyEmbedder { model, embed };
+            let json = tokio::task::spawn_blocking(move || {
+                let report = backfill_embeddings(store.as_ref(), &scope, &embedder, &options)
+                    .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn merge_similar_episodes(
+        &self,
+        scope_json: &str,
+        model: &str,
+        threshold: f32,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let report = merge_similar_episodes(self.inner.as_ref(), &scope, model, threshold)
+            .map_err(store_error)?;
+        to_json(&report)
+    }
+
+    fn async_merge_similar_episodes<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        model: String,
+        threshold: f32,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let report = merge_similar_episodes(store.as_ref(), &scope, &model, threshold)
+                    .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    /// Per-tenant reads, writes and bytes since the store opened or the last reset.
+    #[pyo3(signature = (reset=false))]
+    fn tenant_usage(&self, reset: bool) -> PyResult<String> {
+        let metered = self.metered()?;
+        let report = if reset {
+            metered.take_usage_report()
+        } else {
+            metered.usage_report()
+        };
+        to_json(&report.map_err(store_error)?)
+    }
+
+    #[pyo3(signature = (reset=false))]
+    fn async_tenant_usage<'p>(&self, py: Python<'p>, reset: bool) -> PyResult<&'p PyAny> {
+        let metered = self.metered()?;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || {
+                let report = if reset {
+                    metered.take_usage_report()
+                } else {
+                    metered.usage_report()
+                };
+                to_json(&report.map_err(store_error)?)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (model, scope_json=None))]
+    fn vector_index_stats(&self, model: &str, scope_json: Option<&str>) -> PyResult<String> {
+        let scope: Option<Scope> = scope_json.map(parse_json).transpose()?;
+        let stats = vector_index_stats(self.inner.as_ref(), model, scope.as_ref())
+            .map_err(store_error)?;
+        to_json(&stats)
+    }
+
+    #[pyo3(signature = (model, scope_json=None))]
+    fn async_vector_index_stats<'p>(
+        &self,
+        py: Python<'p>,
+        model: String,
+        scope_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Option<Scope> = scope_json.as_deref().map(parse_json).transpose()?;
+            let json = tokio::task::spawn_blocking(move || {
+                let stats = vector_index_stats(store.as_ref(), &model, scope.as_ref())
+                    .map_err(store_error)?;
+                to_json(&stats)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (model, embed, scope_json=None, options_json=None))]
+    fn rebuild_vector_index(
+        &self,
+        model: String,
+        embed: PyObject,
+        scope_json: Option<&str>,
+        options_json: Option<&str>,
+    ) -> PyResult<String> {
+        let scope: Option<Scope> = scope_json.map(parse_json).transpose()?;
+        let options = match options_json {
+            Some(payload) => parse_json::<BackfillOptionsInput>(payload)?.into_options(),
+            None => BackfillOptions::default(),
+        };
+        let embedder = PyEmbedder { model, embed };
+        let report =
+            rebuild_vector_index(self.inner.as_ref(), scope.as_ref(), &embedder, &options)
+                .map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (model, embed, scope_json=None, options_json=None))]
+    fn async_rebuild_vector_index<'p>(
+        &self,
+        py: Python<'p>,
+        model: String,
+        embed: PyObject,
+        scope_json: Option<String>,
+        options_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Option<Scope> = scope_json.as_deref().map(parse_json).transpose()?;
+            let options = match options_json {
+                Some(payload) => parse_json::<BackfillOptionsInput>(&payload)?.into_options(),
+                None => BackfillOptions::default(),
+            };
+            let embedder = PyEmbedder { model, embed };
+            let json = tokio::task::spawn_blocking(move || {
+                let report =
+                    rebuild_vector_index(store.as_ref(), scope.as_ref(), &embedder, &options)
+                        .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn build_handoff_packet(&self, from_json: &str, target_json: &str) -> PyResult<String> {
+        let from: Scope = parse_json(from_json)?;
+        let to: Scope = parse_json(target_json)?;
+        let packet = build_handoff_packet(self.inner.as_ref(), &from, &to).map_err(store_error)?;
+        to_json(&packet)
+    }
+
+    fn async_build_handoff_packet<'p>(
+        &self,
+        py: Python<'p>,
+        from_json: String,
+        target_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let from: Scope = parse_json(&from_json)?;
+            let to: Scope = parse_json(&target_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let packet =
+                    build_handoff_packet(store.as_ref(), &from, &to).map_err(store_error)?;
+                to_json(&packet)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn get_stm(&self, scope_json: &str) -> PyResult<Option<String>> {
+        let scope: Scope = parse_json(scope_json)?;
+        let state = self.inner.get_stm(&scope).map_err(store_error)?;
+        match state {
+            Some(state) => Ok(Some(to_json(&StmStateOutput::from(state))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn async_get_stm<'p>(&self, py: Python<'p>, scope_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || -> PyResult<Option<String>> {
+                let state = store.get_stm(&scope).map_err(store_error)?;
+                match state {
+                    Some(state) => Ok(Some(to_json(&StmStateOutput::from(state))?)),
+                    None => Ok(None),
+                }
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn update_stm(&self, scope_json: &str, stm_json: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let input: StmStateInput = parse_json(stm_json)?;
+        let stm = StmState {
+            rolling_summary: input.rolling_summary,
+            key_quotes: input.key_quotes,
+        };
+        self.inner.update_stm(&scope, stm).map_err(store_error)
+    }
+
+    fn async_update_stm<'p>(&self, py: Python<'p>, scope_json: String, stm_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let input: StmStateInput = parse_json(&stm_json)?;
+            let stm = StmState {
+                rolling_summary: input.rolling_summary,
+                key_quotes: input.key_quotes,
+            };
+            tokio::task::spawn_blocking(move || {
+                store.update_stm(&scope, stm).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn list_facts(&self, scope_json: &str, filter_json: Option<&str>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let filter = match filter_json {
+            Some(payload) => parse_json::<FactFilterInput>(payload)?.into_filter()?,
+            None => FactFilter::default(),
+        };
+        let facts = self
+            .inner
+            .list_facts(&scope, filter)
+            .map_err(store_error)?;
+        to_json(&facts)
+    }
+
+    fn async_list_facts<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        filter_json: Option<String>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let filter = match filter_json {
+                Some(payload) => parse_json::<FactFilterInput>(&payload)?.into_filter()?,
+                None => FactFilter::default(),
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let facts = store
+                    .list_facts(&scope, filter)
+                    .map_err(store_error)?;
+                to_json(&facts)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn upsert_fact(&self, scope_json: &str, fact_json: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let fact: Fact = parse_json(fact_json)?;
+        self.inner.upsert_fact(&scope, fact).map_err(store_error)
+    }
+
+    fn async_upsert_fact<'p>(&self, py: Python<'p>, scope_json: String, fact_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let fact: Fact = parse_json(&fact_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.upsert_fact(&scope, fact).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (scope_json, fact_json, conflict_policy="keep_both_disputed"))]
+    fn upsert_fact_checked(
+        &self,
+        scope_json: &str,
+        fact_json: &str,
+        conflict_policy: &str,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let fact: Fact = parse_json(fact_json)?;
+        let policy: ConflictPolicy = conflict_policy.parse().map_err(store_error)?;
+        let upsert =
+            upsert_fact_checked(self.inner.as_ref(), &scope, fact, policy).map_err(store_error)?;
+        to_json(&upsert)
+    }
+
+    #[pyo3(signature = (scope_json, fact_json, conflict_policy="keep_both_disputed".to_string()))]
+    fn async_upsert_fact_checked<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        fact_json: String,
+        conflict_policy: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let fact: Fact = parse_json(&fact_json)?;
+            let policy: ConflictPolicy = conflict_policy.parse().map_err(store_error)?;
+            let upsert = tokio::task::spawn_blocking(move || {
+                upsert_fact_checked(store.as_ref(), &scope, fact, policy).map_err(store_error)
+            }).await.map_err(py_error)??;
+            to_json(&upsert)
+        })
+    }
+
+    fn upsert_facts_bulk(&self, scope_json: &str, facts_json: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let facts: Vec<Fact> = parse_json(facts_json)?;
+        self.inner.upsert_facts_bulk(&scope, &facts).map_err(store_error)
+    }
+
+    fn async_upsert_facts_bulk<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        facts_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let facts: Vec<Fact> = parse_json(&facts_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.upsert_facts_bulk(&scope, &facts).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn list_fact_history(&self, scope_json: &str, fact_key: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let history = self
+            .inner
+            .list_fact_history(&scope, fact_key)
+            .map_err(store_error)?;
+        to_json(&history)
+    }
+
+    fn async_list_fact_history<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        fact_key: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let history = store
+                    .list_fact_history(&scope, &fact_key)
+                    .map_err(store_error)?;
+                to_json(&history)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, fact_id, pinned=true))]
+    fn pin_fact(&self, scope_json: &str, fact_id: &str, pinned: bool) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let fact = if pinned {
+            pin_fact(self.inner.as_ref(), &scope, fact_id)
+        } else {
+            unpin_fact(self.inner.as_ref(), &scope, fact_id)
+        }
+        .map_err(store_error)?;
+        to_json(&fact)
+    }
+
+    #[pyo3(signature = (scope_json, fact_id, pinned=true))]
+    fn async_pin_fact<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        fact_id: String,
+        pinned: bool,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let fact = if pinned {
+                    pin_fact(store.as_ref(), &scope, &fact_id)
+                } else {
+                    unpin_fact(store.as_ref(), &scope, &fact_id)
+                }
+                .map_err(store_error)?;
+                to_json(&fact)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn set_preference(
+        &self,
+        scope_json: &str,
+        key: &str,
+        value_json: &str,
+        confidence: f64,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let value: JsonValue = parse_json(value_json)?;
+        let fact = set_preference(self.inner.as_ref(), &scope, key, value, confidence)
+            .map_err(store_error)?;
+        to_json(&fact)
+    }
+
+    fn async_set_preference<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        key: String,
+        value_json: String,
+        confidence: f64,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let value: JsonValue = parse_json(&value_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let fact = set_preference(store.as_ref(), &scope, &key, value, confidence)
+                    .map_err(store_error)?;
+                to_json(&fact)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn get_preferences(&self, scope_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let preferences = get_preferences(self.inner.as_ref(), &scope).map_err(store_error)?;
+        to_json(&preferences)
+    }
+
+    fn async_get_preferences<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let preferences = get_preferences(store.as_ref(), &scope).map_err(store_error)?;
+                to_json(&preferences)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn expire_facts(&self, tenant_id: &str) -> PyResult<String> {
+        let report =
+            expire_tenant_facts(self.inner.as_ref(), tenant_id, Utc::now()).map_err(store_error)?;
+        to_json(&report)
+    }
+
+    fn async_expire_facts<'p>(&self, py: Python<'p>, tenant_id: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || {
+                let report = expire_tenant_facts(store.as_ref(), &tenant_id, Utc::now())
+                    .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn export_tenant_archive(&self, tenant_id: &str) -> PyResult<Encoded> {
+        let archive = export_tenant_archive(self.inner.as_ref(), tenant_id).map_err(store_error)?;
+        Ok(Encoded::Binary(archive))
+    }
+
+    fn async_export_tenant_archive<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let archive = tokio::task::spawn_blocking(move || {
+                export_tenant_archive(store.as_ref(), &tenant_id).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(Encoded::Binary(archive))
+        })
+    }
+
+    #[pyo3(signature = (archive, tenant_id=None, user_ids_json=None, regenerate_ids=false))]
+    fn import_tenant_archive(
+        &self,
+        archive: &[u8],
+        tenant_id: Option<String>,
+        user_ids_json: Option<&str>,
+        regenerate_ids: bool,
+    ) -> PyResult<String> {
+        let options = ImportOptions {
+            tenant_id,
+            user_ids: user_ids_json.map(parse_json).transpose()?.unwrap_or_default(),
+            regenerate_ids,
+        };
+        let report = import_tenant_archive_with(self.inner.as_ref(), archive, &options)
+            .map_err(store_error)?;
+        to_json(&report)
+    }
+
+    #[pyo3(signature = (archive, tenant_id=None, user_ids_json=None, regenerate_ids=false))]
+    fn async_import_tenant_archive<'p>(
+        &self,
+        py: Python<'p>,
+        archive: Vec<u8>,
+        tenant_id: Option<String>,
+        user_ids_json: Option<String>,
+        regenerate_ids: bool,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let options = ImportOptions {
+                tenant_id,
+                user_ids: user_ids_json.as_deref().map(parse_json).transpose()?.unwrap_or_default(),
+                regenerate_ids,
+            };
+            let json = tokio::task::spawn_blocking(move || {
+                let report = import_tenant_archive_with(store.as_ref(), &archive, &options)
+                    .map_err(store_error)?;
+                to_json(&report)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn verify_archive(&self, archive: &[u8]) -> PyResult<String> {
+        to_json(&verify_archive(archive).map_err(store_error)?)
+    }
+
+    fn metrics_snapshot(&self) -> PyResult<String> {
+        to_json(&self.inner.metrics_snapshot())
+    }
+
+    fn capabilities(&self) -> PyResult<String> {
+        to_json(&self.inner.capabilities())
+    }
+
+    #[pyo3(signature = (scope_json, item_json, reason=""))]
+    fn suppress_memory(&self, scope_json: &str, item_json: &str, reason: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let item: MemoryRef = parse_json(item_json)?;
+        self.inner
+            .suppress_memory(&scope, item, reason)
+            .map_err(store_error)
+    }
+
+    #[pyo3(signature = (scope_json, item_json, reason=String::new()))]
+    fn async_suppress_memory<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        item_json: String,
+        reason: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let item: MemoryRef = parse_json(&item_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.suppress_memory(&scope, item, &reason).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn unsuppress_memory(&self, scope_json: &str, item_json: &str) -> PyResult<bool> {
+        let scope: Scope = parse_json(scope_json)?;
+        let item: MemoryRef = parse_json(item_json)?;
+        self.inner
+            .unsuppress_memory(&scope, &item)
+            .map_err(store_error)
+    }
+
+    fn async_unsuppress_memory<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        item_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let item: MemoryRef = parse_json(&item_json)?;
+            let removed = tokio::task::spawn_blocking(move || {
+                store.unsuppress_memory(&scope, &item).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(removed)
+        })
+    }
+
+    fn list_suppressions(&self, scope_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let suppressions = self.inner.list_suppressions(&scope).map_err(store_error)?;
+        to_json(&suppressions)
+    }
+
+    fn async_list_suppressions<'p>(&self, py: Python<'p>, scope_json: String) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let suppressions = store.list_suppressions(&scope).map_err(store_error)?;
+                to_json(&suppressions)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, item_json, soft=false))]
+    fn delete_record(&self, scope_json: &str, item_json: &str, soft: bool) -> PyResult<bool> {
+        let scope: Scope = parse_json(scope_json)?;
+        let item: RecordRef = parse_json(item_json)?;
+        self.inner
+            .delete_record(&scope, &item, delete_mode(soft))
+            .map_err(store_error)
+    }
+
+    #[pyo3(signature = (scope_json, item_json, soft=false))]
+    fn async_delete_record<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        item_json: String,
+        soft: bool,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let item: RecordRef = parse_json(&item_json)?;
+            let removed = tokio::task::spawn_blocking(move || {
+                store.delete_record(&scope, &item, delete_mode(soft)).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(removed)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, soft=false))]
+    fn clear_scope(&self, scope_json: &str, soft: bool) -> PyResult<usize> {
+        let scope: Scope = parse_json(scope_json)?;
+        self.inner.clear_scope(&scope, delete_mode(soft)).map_err(store_error)
+    }
+
+    #[pyo3(signature = (scope_json, soft=false))]
+    fn async_clear_scope<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        soft: bool,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let removed = tokio::task::spawn_blocking(move || {
+                store.clear_scope(&scope, delete_mode(soft)).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(removed)
+        })
+    }
+
+    #[pyo3(signature = (scope_json, limit=None))]
+    fn list_tombstones(&self, scope_json: &str, limit: Option<usize>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let tombstones = self.inner.list_tombstones(&scope, limit).map_err(store_error)?;
+        to_json(&tombstones)
+    }
+
+    #[pyo3(signature = (scope_json, limit=None))]
+    fn async_list_tombstones<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        limit: Option<usize>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let tombstones = store.list_tombstones(&scope, limit).map_err(store_error)?;
+                to_json(&tombstones)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn record_run_outcome(&self, scope_json: &str, outcome_json: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let outcome: RunOutcome = parse_json(outcome_json)?;
+        self.inner
+            .record_run_outcome(&scope, outcome)
+            .map_err(store_error)
+    }
+
+    fn async_record_run_outcome<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        outcome_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let outcome: RunOutcome = parse_json(&outcome_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.record_run_outcome(&scope, outcome).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (scope_json, limit=None))]
+    fn list_run_outcomes(&self, scope_json: &str, limit: Option<usize>) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let outcomes = self
+            .inner
+            .list_run_outcomes(&scope, limit)
+            .map_err(store_error)?;
+        to_json(&outcomes)
+    }
+
+    #[pyo3(signature = (scope_json, limit=None))]
+    fn async_list_run_outcomes<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        limit: Option<usize>,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let outcomes = store.list_run_outcomes(&scope, limit).map_err(store_error)?;
+                to_json(&outcomes)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn set_source_credibility(&self, tenant_id: &str, credibility_json: &str) -> PyResult<()> {
+        let credibility: SourceCredibility = parse_json(credibility_json)?;
+        self.inner
+            .set_source_credibility(tenant_id, credibility)
+            .map_err(store_error)
+    }
+
+    fn async_set_source_credibility<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+        credibility_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let credibility: SourceCredibility = parse_json(&credibility_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.set_source_credibility(&tenant_id, credibility).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> PyResult<Option<String>> {
+        let credibility = self.inner.get_source_credibility(tenant_id).map_err(store_error)?;
+        credibility.map(|credibility| to_json(&credibility)).transpose()
+    }
+
+    fn async_get_source_credibility<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || -> PyResult<Option<String>> {
+                let credibility = store.get_source_credibility(&tenant_id).map_err(store_error)?;
+                credibility.map(|credibility| to_json(&credibility)).transpose()
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn set_user_locale(&self, tenant_id: &str, user_id: &str, locale_json: &str) -> PyResult<()> {
+        let locale: UserLocale = parse_json(locale_json)?;
+        self.inner
+            .set_user_locale(tenant_id, user_id, locale)
+            .map_err(store_error)
+    }
+
+    fn async_set_user_locale<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+        user_id: String,
+        locale_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let locale: UserLocale = parse_json(&locale_json)?;
+            tokio::task::spawn_blocking(move || {
+                store.set_user_locale(&tenant_id, &user_id, locale).map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> PyResult<Option<String>> {
+        let locale = self.inner.get_user_locale(tenant_id, user_id).map_err(store_error)?;
+        locale.map(|locale| to_json(&locale)).transpose()
+    }
+
+    fn async_get_user_locale<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+        user_id: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || -> PyResult<Option<String>> {
+                let locale = store.get_user_locale(&tenant_id, &user_id).map_err(store_error)?;
+                locale.map(|locale| to_json(&locale)).transpose()
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn acquire_lease(&self, scope_json: &str, ttl_ms: u64) -> PyResult<Option<String>> {
+        let scope: Scope = parse_json(scope_json)?;
+        let lease = self
+            .inner
+            .acquire_lease(&scope, Duration::from_millis(ttl_ms))
+            .map_err(store_error)?;
+        lease.map(|lease| to_json(&lease)).transpose()
     }
 
-    fn async_patch_working_state<'p>(
+    fn async_acquire_lease<'p>(
         &self,
         py: Python<'p>,
         scope_json: String,
-        patch_json: String,
+        ttl_ms: u64,
     ) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
             let scope: Scope = parse_json(&scope_json)?;
-            let patch_input: WorkingStatePatchInput = parse_json(&patch_json)?;
-            let patch = patch_input.to_patch();
-            let json = tokio::task::spawn_blocking(move || {
-                let state = store
-                    .patch_working_state(&scope, patch)
+            let json = tokio::task::spawn_blocking(move || -> PyResult<Option<String>> {
+                let lease = store
+                    .acquire_lease(&scope, Duration::from_millis(ttl_ms))
                     .map_err(store_error)?;
-                to_json(&state)
+                lease.map(|lease| to_json(&lease)).transpose()
             }).await.map_err(py_error)??;
             Ok(json)
         })
     }
 
-    fn get_stm(&self, scope_json: &str) -> PyResult<Option<String>> {
-        let scope: Scope = parse_json(scope_json)?;
-        let state = self.inner.get_stm(&scope).map_err(store_error)?;
-        match state {
-            Some(state) => Ok(Some(to_json(&StmStateOutput::from(state))?)),
-            None => Ok(None),
-        }
+    fn renew_lease(&self, lease_json: &str, ttl_ms: u64) -> PyResult<Option<String>> {
+        let lease: Lease = parse_json(lease_json)?;
+        let renewed = self
+            .inner
+            .renew_lease(&lease, Duration::from_millis(ttl_ms))
+            .map_err(store_error)?;
+        renewed.map(|lease| to_json(&lease)).transpose()
     }
 
-    fn async_get_stm<'p>(&self, py: Python<'p>, scope_json: String) -> PyResult<&'p PyAny> {
+    fn async_renew_lease<'p>(
+        &self,
+        py: Python<'p>,
+        lease_json: String,
+        ttl_ms: u64,
+    ) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
-            let scope: Scope = parse_json(&scope_json)?;
+            let lease: Lease = parse_json(&lease_json)?;
             let json = tokio::task::spawn_blocking(move || -> PyResult<Option<String>> {
-                let state = store.get_stm(&scope).map_err(store_error)?;
-                match state {
-                    Some(state) => Ok(Some(to_json(&StmStateOutput::from(state))?)),
-                    None => Ok(None),
-                }
+                let renewed = store
+                    .renew_lease(&lease, Duration::from_millis(ttl_ms))
+                    .map_err(store_error)?;
+                renewed.map(|lease| to_json(&lease)).transpose()
             }).await.map_err(py_error)??;
             Ok(json)
         })
     }
 
-    fn update_stm(&self, scope_json: &str, stm_json: &str) -> PyResult<()> {
-        let scope: Scope = parse_json(scope_json)?;
-        let input: StmStateInput = parse_json(stm_json)?;
-        let stm = StmState {
-            rolling_summary: input.rolling_summary,
-            key_quotes: input.key_quotes,
-        };
-        self.inner.update_stm(&scope, stm).map_err(store_error)
+    fn release_lease(&self, lease_json: &str) -> PyResult<bool> {
+        let lease: Lease = parse_json(lease_json)?;
+        self.inner.release_lease(&lease).map_err(store_error)
     }
 
-    fn async_update_stm<'p>(&self, py: Python<'p>, scope_json: String, stm_json: String) -> PyResult<&'p PyAny> {
+    fn async_release_lease<'p>(&self, py: Python<'p>, lease_json: String) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
-            let scope: Scope = parse_json(&scope_json)?;
-            let input: StmStateInput = parse_json(&stm_json)?;
-            let stm = StmState {
-                rolling_summary: input.rolling_summary,
-                key_quotes: input.key_quotes,
-            };
-            tokio::task::spawn_blocking(move || {
-                store.update_stm(&scope, stm).map_err(store_error)
+            let lease: Lease = parse_json(&lease_json)?;
+            let released = tokio::task::spawn_blocking(move || {
+                store.release_lease(&lease).map_err(store_error)
             }).await.map_err(py_error)??;
-            Ok(())
+            Ok(released)
         })
     }
 
-    fn list_facts(&self, scope_json: &str, filter_json: Option<&str>) -> PyResult<String> {
+    fn resolve_provenance(&self, scope_json: &str, fact_id: &str) -> PyResult<String> {
         let scope: Scope = parse_json(scope_json)?;
-        let filter = match filter_json {
-            Some(payload) => parse_json::<FactFilterInput>(payload)?.to_filter()?,
-            None => FactFilter::default(),
-        };
-        let facts = self
-            .inner
-            .list_facts(&scope, filter)
-            .map_err(store_error)?;
-        to_json(&facts)
+        let provenance =
+            resolve_provenance(self.inner.as_ref(), &scope, fact_id).map_err(store_error)?;
+        to_json(&provenance)
     }
 
-    fn async_list_facts<'p>(
+    fn async_resolve_provenance<'p>(
         &self,
         py: Python<'p>,
         scope_json: String,
-        filter_json: Option<String>,
+        fact_id: String,
     ) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
             let scope: Scope = parse_json(&scope_json)?;
-            let filter = match filter_json {
-                Some(payload) => parse_json::<FactFilterInput>(&payload)?.to_filter()?,
-                None => FactFilter::default(),
-            };
             let json = tokio::task::spawn_blocking(move || {
-                let facts = store
-                    .list_facts(&scope, filter)
+                let provenance = resolve_provenance(store.as_ref(), &scope, &fact_id)
                     .map_err(store_error)?;
-                to_json(&facts)
+                to_json(&provenance)
             }).await.map_err(py_error)??;
             Ok(json)
         })
     }
 
-    fn upsert_fact(&self, scope_json: &str, fact_json: &str) -> PyResult<()> {
-        let scope: Scope = parse_json(scope_json)?;
-        let fact: Fact = parse_json(fact_json)?;
-        self.inner.upsert_fact(&scope, fact).map_err(store_error)
-    }
-
-    fn async_upsert_fact<'p>(&self, py: Python<'p>, scope_json: String, fact_json: String) -> PyResult<&'p PyAny> {
-        let store = self.inner.clone();
-        pyo3_asyncio::tokio::future_into_py(py, async move {
-            let scope: Scope = parse_json(&scope_json)?;
-            let fact: Fact = parse_json(&fact_json)?;
-            tokio::task::spawn_blocking(move || {
-                store.upsert_fact(&scope, fact).map_err(store_error)
-            }).await.map_err(py_error)??;
-            Ok(())
-        })
-    }
-
     fn list_episodes(&self, scope_json: &str, filter_json: Option<&str>) -> PyResult<String> {
         let scope: Scope = parse_json(scope_json)?;
         let filter = match filter_json {
-            Some(payload) => parse_json::<EpisodeFilterInput>(payload)?.to_filter()?,
+            Some(payload) => parse_json::<EpisodeFilterInput>(payload)?.into_filter()?,
             None => EpisodeFilter::default(),
         };
         let episodes = self
@@ -293,7 +2051,7 @@ impl EngramStore {
         pyo3_asyncio::tokio::future_into_py(py, async move {
             let scope: Scope = parse_json(&scope_json)?;
             let filter = match filter_json {
-                Some(payload) => parse_json::<EpisodeFilterInput>(&payload)?.to_filter()?,
+                Some(payload) => parse_json::<EpisodeFilterInput>(&payload)?.into_filter()?,
                 None => EpisodeFilter::default(),
             };
             let json = tokio::task::spawn_blocking(move || {
@@ -306,6 +2064,36 @@ impl EngramStore {
         })
     }
 
+    fn search_episodes(&self, scope_json: &str, query_json: &str) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        let query = parse_json::<TextQueryInput>(query_json)?.into_query()?;
+        let episodes = self
+            .inner
+            .search_episodes(&scope, &query)
+            .map_err(store_error)?;
+        to_json(&episodes)
+    }
+
+    fn async_search_episodes<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        query_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let query = parse_json::<TextQueryInput>(&query_json)?.into_query()?;
+            let json = tokio::task::spawn_blocking(move || {
+                let episodes = store
+                    .search_episodes(&scope, &query)
+                    .map_err(store_error)?;
+                to_json(&episodes)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
     fn append_episode(&self, scope_json: &str, episode_json: &str) -> PyResult<()> {
         let scope: Scope = parse_json(scope_json)?;
         let episode: Episode = parse_json(episode_json)?;
@@ -328,6 +2116,33 @@ impl EngramStore {
         })
     }
 
+    fn append_episodes_bulk(&self, scope_json: &str, episodes_json: &str) -> PyResult<()> {
+        let scope: Scope = parse_json(scope_json)?;
+        let episodes: Vec<Episode> = parse_json(episodes_json)?;
+        self.inner
+            .append_episodes_bulk(&scope, &episodes)
+            .map_err(store_error)
+    }
+
+    fn async_append_episodes_bulk<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        episodes_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let episodes: Vec<Episode> = parse_json(&episodes_json)?;
+            tokio::task::spawn_blocking(move || {
+                store
+                    .append_episodes_bulk(&scope, &episodes)
+                    .map_err(store_error)
+            }).await.map_err(py_error)??;
+            Ok(())
+        })
+    }
+
     fn list_procedures(
         &self,
         scope_json: &str,
@@ -389,10 +2204,57 @@ impl EngramStore {
         })
     }
 
+    fn pin_tenant_procedure(&self, tenant_id: &str, procedure_json: &str) -> PyResult<String> {
+        let procedure: Procedure = parse_json(procedure_json)?;
+        let procedure = pin_tenant_procedure(self.inner.as_ref(), tenant_id, procedure)
+            .map_err(store_error)?;
+        to_json(&procedure)
+    }
+
+    fn async_pin_tenant_procedure<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+        procedure_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let procedure: Procedure = parse_json(&procedure_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let procedure = pin_tenant_procedure(store.as_ref(), &tenant_id, procedure)
+                    .map_err(store_error)?;
+                to_json(&procedure)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn list_tenant_procedures(&self, tenant_id: &str) -> PyResult<String> {
+        let procedures =
+            list_tenant_procedures(self.inner.as_ref(), tenant_id).map_err(store_error)?;
+        to_json(&procedures)
+    }
+
+    fn async_list_tenant_procedures<'p>(
+        &self,
+        py: Python<'p>,
+        tenant_id: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let json = tokio::task::spawn_blocking(move || {
+                let procedures =
+                    list_tenant_procedures(store.as_ref(), &tenant_id).map_err(store_error)?;
+                to_json(&procedures)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
     fn list_insights(&self, scope_json: &str, filter_json: Option<&str>) -> PyResult<String> {
         let scope: Scope = parse_json(scope_json)?;
         let filter = match filter_json {
-            Some(payload) => parse_json::<InsightFilterInput>(payload)?.to_filter()?,
+            Some(payload) => parse_json::<InsightFilterInput>(payload)?.into_filter()?,
             None => InsightFilter::default(),
         };
         let insights = self
@@ -412,7 +2274,7 @@ impl EngramStore {
         pyo3_asyncio::tokio::future_into_py(py, async move {
             let scope: Scope = parse_json(&scope_json)?;
             let filter = match filter_json {
-                Some(payload) => parse_json::<InsightFilterInput>(&payload)?.to_filter()?,
+                Some(payload) => parse_json::<InsightFilterInput>(&payload)?.into_filter()?,
                 None => InsightFilter::default(),
             };
             let json = tokio::task::spawn_blocking(move || {
@@ -452,6 +2314,57 @@ impl EngramStore {
         })
     }
 
+    fn carry_forward_insights(&self, from_json: &str, target_json: &str) -> PyResult<String> {
+        let from: Scope = parse_json(from_json)?;
+        let to: Scope = parse_json(target_json)?;
+        let copies =
+            carry_forward_insights(self.inner.as_ref(), &from, &to).map_err(store_error)?;
+        to_json(&copies)
+    }
+
+    fn async_carry_forward_insights<'p>(
+        &self,
+        py: Python<'p>,
+        from_json: String,
+        target_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let from: Scope = parse_json(&from_json)?;
+            let to: Scope = parse_json(&target_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let copies =
+                    carry_forward_insights(store.as_ref(), &from, &to).map_err(store_error)?;
+                to_json(&copies)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn insight_lineage(&self, runs_json: &str, insight_id: &str) -> PyResult<String> {
+        let runs: Vec<Scope> = parse_json(runs_json)?;
+        let chain = insight_lineage(self.inner.as_ref(), &runs, insight_id).map_err(store_error)?;
+        to_json(&chain)
+    }
+
+    fn async_insight_lineage<'p>(
+        &self,
+        py: Python<'p>,
+        runs_json: String,
+        insight_id: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let runs: Vec<Scope> = parse_json(&runs_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let chain =
+                    insight_lineage(store.as_ref(), &runs, &insight_id).map_err(store_error)?;
+                to_json(&chain)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
     fn write_context_build(&self, scope_json: &str, packet_json: &str) -> PyResult<()> {
         let scope: Scope = parse_json(scope_json)?;
         let packet: MemoryPacket = parse_json(packet_json)?;
@@ -475,97 +2388,249 @@ impl EngramStore {
                     .write_context_build(&scope, packet)
                     .map_err(store_error)
             }).await.map_err(py_error)??;
-            Ok(())
+            Ok(())
+        })
+    }
+
+    #[pyo3(signature = (scope_json, limit=None, metadata_only=false))]
+    fn list_context_builds(
+        &self,
+        scope_json: &str,
+        limit: Option<usize>,
+        metadata_only: bool,
+    ) -> PyResult<String> {
+        let scope: Scope = parse_json(scope_json)?;
+        context_builds_json(self.inner.as_ref(), &scope, limit, metadata_only)
+    }
+
+    #[pyo3(signature = (scope_json, limit=None, metadata_only=false))]
+    fn async_list_context_builds<'p>(
+        &self,
+        py: Python<'p>,
+        scope_json: String,
+        limit: Option<usize>,
+        metadata_only: bool,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let scope: Scope = parse_json(&scope_json)?;
+            let json = tokio::task::spawn_blocking(move || {
+                context_builds_json(store.as_ref(), &scope, limit, metadata_only)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn build_memory_packet(&self, request_json: &str) -> PyResult<Encoded> {
+        let input: BuildRequestInput = parse_json(request_json)?;
+        let request = input.into_request(&self.slot_schemas, &self.policies)?;
+        let packet = build_memory_packet(self.inner.as_ref(), request).map_err(store_error)?;
+        packet_output(&packet, self.wire_format)
+    }
+
+    fn async_build_memory_packet<'p>(
+        &self,
+        py: Python<'p>,
+        request_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let slot_schemas = self.slot_schemas.clone();
+        let policies = self.policies.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let input: BuildRequestInput = parse_json(&request_json)?;
+            let request = input.into_request(&slot_schemas, &policies)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let packet = build_memory_packet(store.as_ref(), request).map_err(store_error)?;
+                packet_output(&packet, format)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    /// Builds the packet as of `at`, an RFC 3339 timestamp; see
+    /// `engram_store::build_memory_packet_as_of`.
+    fn build_memory_packet_as_of(&self, request_json: &str, at: &str) -> PyResult<Encoded> {
+        let input: BuildRequestInput = parse_json(request_json)?;
+        let request = input.into_request(&self.slot_schemas, &self.policies)?;
+        let at = parse_rfc3339(at)?;
+        let packet = build_memory_packet_as_of(self.inner.as_ref(), request, at)
+            .map_err(store_error)?;
+        packet_output(&packet, self.wire_format)
+    }
+
+    fn async_build_memory_packet_as_of<'p>(
+        &self,
+        py: Python<'p>,
+        request_json: String,
+        at: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let slot_schemas = self.slot_schemas.clone();
+        let policies = self.policies.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let input: BuildRequestInput = parse_json(&request_json)?;
+            let request = input.into_request(&slot_schemas, &policies)?;
+            let at = parse_rfc3339(&at)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let packet = build_memory_packet_as_of(store.as_ref(), request, at)
+                    .map_err(store_error)?;
+                packet_output(&packet, format)
+            }).await.map_err(py_error)??;
+            Ok(json)
+        })
+    }
+
+    fn estimate_packet_size(&self, request_json: &str) -> PyResult<String> {
+        let input: BuildRequestInput = parse_json(request_json)?;
+        let request = input.into_request(&self.slot_schemas, &self.policies)?;
+        let estimate = estimate_packet_size(self.inner.as_ref(), &request).map_err(store_error)?;
+        to_json(&estimate)
+    }
+
+    fn async_estimate_packet_size<'p>(
+        &self,
+        py: Python<'p>,
+        request_json: String,
+    ) -> PyResult<&'p PyAny> {
+        let store = self.inner.clone();
+        let slot_schemas = self.slot_schemas.clone();
+        let policies = self.policies.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let input: BuildRequestInput = parse_json(&request_json)?;
+            let request = input.into_request(&slot_schemas, &policies)?;
+            let json = tokio::task::spawn_blocking(move || {
+                let estimate =
+                    estimate_packet_size(store.as_ref(), &request).map_err(store_error)?;
+                to_json(&estimate)
+            }).await.map_err(py_error)??;
+            Ok(json)
         })
     }
 
-    fn list_context_builds(&self, scope_json: &str, limit: Option<usize>) -> PyResult<String> {
-        let scope: Scope = parse_json(scope_json)?;
-        let packets = self
-            .inner
-            .list_context_builds(&scope, limit)
-            .map_err(store_error)?;
-        to_json(&packets)
+    #[pyo3(signature = (requests_json, concurrency=0))]
+    fn build_memory_packets_bulk(
+        &self,
+        requests_json: &str,
+        concurrency: usize,
+    ) -> PyResult<Encoded> {
+        let requests = parse_build_requests(requests_json, &self.slot_schemas, &self.policies)?;
+        let results = build_memory_packets_bulk(self.inner.as_ref(), requests, concurrency);
+        bulk_results_output(results, self.wire_format)
     }
 
-    fn async_list_context_builds<'p>(
+    #[pyo3(signature = (requests_json, concurrency=0))]
+    fn async_build_memory_packets_bulk<'p>(
         &self,
         py: Python<'p>,
-        scope_json: String,
-        limit: Option<usize>,
+        requests_json: String,
+        concurrency: usize,
     ) -> PyResult<&'p PyAny> {
         let store = self.inner.clone();
+        let slot_schemas = self.slot_schemas.clone();
+        let policies = self.policies.clone();
+        let format = self.wire_format;
         pyo3_asyncio::tokio::future_into_py(py, async move {
-            let scope: Scope = parse_json(&scope_json)?;
+            let requests = parse_build_requests(&requests_json, &slot_schemas, &policies)?;
             let json = tokio::task::spawn_blocking(move || {
-                let packets = store
-                    .list_context_builds(&scope, limit)
-                    .map_err(store_error)?;
-                to_json(&packets)
+                let results = build_memory_packets_bulk(store.as_ref(), requests, concurrency);
+                bulk_results_output(results, format)
             }).await.map_err(py_error)??;
             Ok(json)
         })
     }
+}
 
-    fn build_memory_packet(&self, request_json: &str) -> PyResult<String> {
-        let input: BuildRequestInput = parse_json(request_json)?;
-        let mut request = BuildRequest::new(input.scope, input.purpose);
+#[pyfunction]
+fn new_id() -> String {
+    new_ulid()
+}
 
-        if let Some(task_type) = input.task_type {
-            request.task_type = Some(task_type);
-        }
-        if let Some(cues) = input.cues {
-            request.cues = cues.to_cues()?;
-        }
-        if let Some(budget) = input.budget {
-            request.budget = budget;
-        }
-        if let Some(policy_id) = input.policy_id {
-            request.policy_id = policy_id;
-        }
-        if let Some(policy) = input.policy {
-            request.policy = policy.apply_to(RecallPolicy::default());
-        }
-        if let Some(persist) = input.persist {
-            request.persist = persist;
-        }
+#[pyfunction]
+#[pyo3(name = "check_grounding")]
+fn check_grounding_py(response: &str, packet_json: &str) -> PyResult<String> {
+    let packet: MemoryPacket = parse_json(packet_json)?;
+    to_json(&check_grounding(response, &packet))
+}
 
-        let packet = build_memory_packet(self.inner.as_ref(), request).map_err(store_error)?;
-        to_json(&packet)
+#[pyfunction]
+#[pyo3(name = "chunk_text", signature = (source_id, text, options_json=None))]
+fn chunk_text_py(source_id: &str, text: &str, options_json: Option<&str>) -> PyResult<String> {
+    let options: ChunkOptions = match options_json {
+        Some(payload) => parse_json(payload)?,
+        None => ChunkOptions::default(),
+    };
+    to_json(&chunk_text(source_id, text, &options))
+}
+
+#[pyfunction]
+#[pyo3(name = "json_schema")]
+fn json_schema_py(kind: &str) -> PyResult<String> {
+    to_json(&json_schema(wire_type(kind)?))
+}
+
+#[pyfunction]
+#[pyo3(name = "validate_json")]
+fn validate_json_py(kind: &str, payload_json: &str) -> PyResult<()> {
+    let payload: JsonValue = parse_json(payload_json)?;
+    validate_json(wire_type(kind)?, &payload).map_err(store_error)
+}
+
+/// Hex SHA-256 of a fact, episode or procedure's canonical JSON, ids left out.
+#[pyfunction]
+#[pyo3(name = "content_hash")]
+fn content_hash_py(kind: &str, item_json: &str) -> PyResult<String> {
+    match kind {
+        "fact" => Ok(parse_json::<Fact>(item_json)?.content_hash()),
+        "episode" => Ok(parse_json::<Episode>(item_json)?.content_hash()),
+        "procedure" => Ok(parse_json::<Procedure>(item_json)?.content_hash()),
+        _ => Err(PyValueError::new_err(format!("invalid item kind: {}", kind))),
     }
+}
 
-    fn async_build_memory_packet<'p>(
-        &self,
-        py: Python<'p>,
-        request_json: String,
-    ) -> PyResult<&'p PyAny> {
-        let store = self.inner.clone();
-        pyo3_asyncio::tokio::future_into_py(py, async move {
-            let input: BuildRequestInput = parse_json(&request_json)?;
-            let mut request = BuildRequest::new(input.scope, input.purpose);
+fn wire_type(kind: &str) -> PyResult<WireType> {
+    kind.parse().map_err(PyValueError::new_err)
+}
 
-            if let Some(task_type) = input.task_type {
-                request.task_type = Some(task_type);
-            }
-            if let Some(cues) = input.cues {
-                request.cues = cues.to_cues()?;
-            }
-            if let Some(budget) = input.budget {
-                request.budget = budget;
-            }
-            if let Some(policy_id) = input.policy_id {
-                request.policy_id = policy_id;
-            }
-            if let Some(policy) = input.policy {
-                request.policy = policy.apply_to(RecallPolicy::default());
-            }
-            if let Some(persist) = input.persist {
-                request.persist = persist;
-            }
+create_exception!(
+    _core,
+    VersionConflictError,
+    PyValueError,
+    "A working state patch expected a state_version the stored state is no longer at."
+);
+
+create_exception!(
+    _core,
+    FactConflictError,
+    PyValueError,
+    "A fact upsert contradicted a stored fact under the reject conflict policy."
+);
+
+/// The chunks of an `EngramStore.stream_events` call, read on demand.
+#[pyclass]
+struct EventChunks {
+    stream: Arc<Mutex<EventStream<Arc<dyn Store>>>>,
+    wire_format: WireFormat,
+}
 
+#[pymethods]
+impl EventChunks {
+    /// The next chunk of events; an empty list once the range is read.
+    fn next_chunk(&self) -> PyResult<Encoded> {
+        let events = self.stream.lock().map_err(py_error)?.next_chunk().map_err(store_error)?;
+        let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+        encode(&output, self.wire_format)
+    }
+
+    fn async_next_chunk<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let stream = self.stream.clone();
+        let format = self.wire_format;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
             let json = tokio::task::spawn_blocking(move || {
-                let packet = build_memory_packet(store.as_ref(), request).map_err(store_error)?;
-                to_json(&packet)
+                let events = stream.lock().map_err(py_error)?.next_chunk().map_err(store_error)?;
+                let output: Vec<EventOutput> = events.into_iter().map(EventOutput::from).collect();
+                encode(&output, format)
             }).await.map_err(py_error)??;
             Ok(json)
         })
@@ -573,14 +2638,24 @@ impl EngramStore {
 }
 
 #[pymodule]
-fn _core(_py: Python, module: &PyModule) -> PyResult<()> {
+fn _core(py: Python, module: &PyModule) -> PyResult<()> {
     pyo3_log::init();
     module.add_class::<EngramStore>()?;
+    module.add_class::<EventChunks>()?;
+    module.add("VersionConflictError", py.get_type::<VersionConflictError>())?;
+    module.add("FactConflictError", py.get_type::<FactConflictError>())?;
+    module.add_function(wrap_pyfunction!(new_id, module)?)?;
+    module.add_function(wrap_pyfunction!(check_grounding_py, module)?)?;
+    module.add_function(wrap_pyfunction!(chunk_text_py, module)?)?;
+    module.add_function(wrap_pyfunction!(json_schema_py, module)?)?;
+    module.add_function(wrap_pyfunction!(validate_json_py, module)?)?;
+    module.add_function(wrap_pyfunction!(content_hash_py, module)?)?;
     Ok(())
 }
 
 #[derive(Deserialize)]
 struct EventInput {
+    #[serde(default = "new_ulid")]
     event_id: String,
     scope: Scope,
     #[serde(default)]
@@ -593,12 +2668,14 @@ struct EventInput {
     tags: Vec<String>,
     #[serde(default)]
     entities: Vec<String>,
+    #[serde(default)]
+    lang: Option<String>,
 }
 
 impl EventInput {
-    fn to_event(self) -> PyResult<Event> {
+    fn into_event(self) -> PyResult<Event> {
         let ts = parse_timestamp(self.ts_ms, self.ts)?;
-        let kind = parse_event_kind(&self.kind)?;
+        let kind = self.kind.parse::<EventKind>().map_err(store_error)?;
         Ok(Event {
             event_id: self.event_id,
             scope: self.scope,
@@ -607,6 +2684,8 @@ impl EventInput {
             payload: self.payload,
             tags: self.tags,
             entities: self.entities,
+            seq: 0,
+            lang: self.lang,
         })
     }
 }
@@ -620,6 +2699,8 @@ struct EventOutput {
     payload: JsonValue,
     tags: Vec<String>,
     entities: Vec<String>,
+    seq: u64,
+    lang: Option<String>,
 }
 
 impl From<Event> for EventOutput {
@@ -628,10 +2709,27 @@ impl From<Event> for EventOutput {
             event_id: event.event_id,
             scope: event.scope,
             ts: event.ts.to_rfc3339(),
-            kind: event_kind_to_str(&event.kind).to_string(),
+            kind: event.kind.as_str().to_string(),
             payload: event.payload,
             tags: event.tags,
             entities: event.entities,
+            seq: event.seq,
+            lang: event.lang,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventPageOutput {
+    events: Vec<EventOutput>,
+    next_cursor: Option<String>,
+}
+
+impl From<EventPage> for EventPageOutput {
+    fn from(page: EventPage) -> Self {
+        Self {
+            events: page.events.into_iter().map(EventOutput::from).collect(),
+            next_cursor: page.next_cursor,
         }
     }
 }
@@ -649,7 +2747,7 @@ struct TimeRangeInput {
 }
 
 impl TimeRangeInput {
-    fn to_filter(self) -> PyResult<TimeRangeFilter> {
+    fn into_filter(self) -> PyResult<TimeRangeFilter> {
         Ok(TimeRangeFilter {
             start: parse_optional_timestamp(self.start_ms, self.start)?,
             end: parse_optional_timestamp(self.end_ms, self.end)?,
@@ -667,14 +2765,26 @@ struct FactFilterInput {
     valid_at_ms: Option<i64>,
     #[serde(default)]
     limit: Option<usize>,
+    #[serde(default)]
+    include_shared: bool,
+    #[serde(default)]
+    pinned: Option<bool>,
+    #[serde(default)]
+    preferences: Option<bool>,
+    #[serde(default)]
+    keys: Option<Vec<String>>,
 }
 
 impl FactFilterInput {
-    fn to_filter(self) -> PyResult<FactFilter> {
+    fn into_filter(self) -> PyResult<FactFilter> {
         Ok(FactFilter {
             status: self.status,
             valid_at: parse_optional_timestamp(self.valid_at_ms, self.valid_at)?,
             limit: self.limit,
+            include_shared: self.include_shared,
+            pinned: self.pinned,
+            preferences: self.preferences,
+            keys: self.keys,
         })
     }
 }
@@ -692,10 +2802,10 @@ struct EpisodeFilterInput {
 }
 
 impl EpisodeFilterInput {
-    fn to_filter(self) -> PyResult<EpisodeFilter> {
+    fn into_filter(self) -> PyResult<EpisodeFilter> {
         Ok(EpisodeFilter {
             time_range: match self.time_range {
-                Some(range) => Some(range.to_filter()?),
+                Some(range) => Some(range.into_filter()?),
                 None => None,
             },
             tags: self.tags,
@@ -705,6 +2815,28 @@ impl EpisodeFilterInput {
     }
 }
 
+#[derive(Deserialize, Default)]
+struct TextQueryInput {
+    terms: Vec<String>,
+    #[serde(default)]
+    time_range: Option<TimeRangeInput>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+impl TextQueryInput {
+    fn into_query(self) -> PyResult<TextQuery> {
+        Ok(TextQuery {
+            terms: self.terms,
+            time_range: match self.time_range {
+                Some(range) => Some(range.into_filter()?),
+                None => None,
+            },
+            limit: self.limit,
+        })
+    }
+}
+
 #[derive(Deserialize, Default)]
 struct InsightFilterInput {
     #[serde(default)]
@@ -714,7 +2846,7 @@ struct InsightFilterInput {
 }
 
 impl InsightFilterInput {
-    fn to_filter(self) -> PyResult<InsightFilter> {
+    fn into_filter(self) -> PyResult<InsightFilter> {
         Ok(InsightFilter {
             validation_state: self.validation_state,
             limit: self.limit,
@@ -722,6 +2854,102 @@ impl InsightFilterInput {
     }
 }
 
+#[derive(Deserialize, Default)]
+struct RunEndOptionsInput {
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    promote_decisions: bool,
+    #[serde(default)]
+    decision_key_prefix: Option<String>,
+    #[serde(default)]
+    period_summaries: bool,
+}
+
+impl RunEndOptionsInput {
+    fn into_options(self) -> RunEndOptions {
+        let defaults = RunEndOptions::default();
+        RunEndOptions {
+            tags: self.tags,
+            promote_decisions: self.promote_decisions,
+            decision_key_prefix: self.decision_key_prefix.unwrap_or(defaults.decision_key_prefix),
+            period_summaries: self.period_summaries,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct ConsolidationOptionsInput {
+    #[serde(default)]
+    max_gap_secs: Option<i64>,
+    #[serde(default)]
+    split_on_tag_change: Option<bool>,
+    #[serde(default)]
+    max_raw_events: Option<usize>,
+    #[serde(default)]
+    summary_tokens: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl ConsolidationOptionsInput {
+    fn into_options(self) -> ConsolidationOptions {
+        let defaults = ConsolidationOptions::default();
+        ConsolidationOptions {
+            max_gap: self.max_gap_secs.map(chrono::Duration::seconds).unwrap_or(defaults.max_gap),
+            split_on_tag_change: self.split_on_tag_change.unwrap_or(defaults.split_on_tag_change),
+            max_raw_events: self.max_raw_events.unwrap_or(defaults.max_raw_events),
+            summary_tokens: self.summary_tokens.unwrap_or(defaults.summary_tokens),
+            tags: self.tags,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct BackfillOptionsInput {
+    #[serde(default)]
+    batch_size: Option<usize>,
+    #[serde(default)]
+    max_items_per_second: Option<f64>,
+    #[serde(default)]
+    max_items: Option<usize>,
+}
+
+impl BackfillOptionsInput {
+    fn into_options(self) -> BackfillOptions {
+        let defaults = BackfillOptions::default();
+        BackfillOptions {
+            batch_size: self.batch_size.unwrap_or(defaults.batch_size),
+            max_items_per_second: self
+                .max_items_per_second
+                .unwrap_or(defaults.max_items_per_second),
+            max_items: self.max_items,
+        }
+    }
+}
+
+/// [`Embedder`] backed by a Python callable that maps a list of texts to a list of
+/// vectors.
+struct PyEmbedder {
+    model: String,
+    embed: PyObject,
+}
+
+impl Embedder for PyEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[String]) -> StoreResult<Vec<Vec<f32>>> {
+        Python::with_gil(|py| {
+            self.embed
+                .call1(py, (texts.to_vec(),))?
+                .extract::<Vec<Vec<f32>>>(py)
+        })
+        .map_err(|err| StoreError::Storage(format!("embedder failed: {}", err)))
+    }
+}
+
 #[derive(Deserialize, Default)]
 struct StmStateInput {
     #[serde(default)]
@@ -763,10 +2991,15 @@ struct WorkingStatePatchInput {
     risks: Option<Vec<String>>,
     #[serde(default)]
     state_version: Option<u32>,
+    #[serde(default)]
+    expected_state_version: Option<u32>,
+    /// Task type whose slot schema the patch is checked against.
+    #[serde(default)]
+    task_type: Option<String>,
 }
 
 impl WorkingStatePatchInput {
-    fn to_patch(self) -> WorkingStatePatch {
+    fn into_patch(self) -> WorkingStatePatch {
         WorkingStatePatch {
             goal: self.goal,
             plan: self.plan,
@@ -776,10 +3009,28 @@ impl WorkingStatePatchInput {
             decisions: self.decisions,
             risks: self.risks,
             state_version: self.state_version,
+            expected_state_version: self.expected_state_version,
+            clock: None,
+            task_type: self.task_type,
         }
     }
 }
 
+/// One entry of a `patch_working_states` batch.
+#[derive(Deserialize)]
+struct ScopedPatchInput {
+    scope: Scope,
+    patch: WorkingStatePatchInput,
+}
+
+fn parse_scoped_patches(patches_json: &str) -> PyResult<Vec<(Scope, WorkingStatePatch)>> {
+    let inputs: Vec<ScopedPatchInput> = parse_json(patches_json)?;
+    Ok(inputs
+        .into_iter()
+        .map(|input| (input.scope, input.patch.into_patch()))
+        .collect())
+}
+
 #[derive(Deserialize)]
 struct BuildRequestInput {
     scope: Scope,
@@ -793,9 +3044,130 @@ struct BuildRequestInput {
     #[serde(default)]
     policy_id: Option<String>,
     #[serde(default)]
-    policy: Option<RecallPolicyInput>,
+    policy: Option<PolicyConfig>,
     #[serde(default)]
     persist: Option<bool>,
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+    #[serde(default)]
+    read_preference: Option<ReadPreference>,
+}
+
+impl BuildRequestInput {
+    fn into_request(
+        self,
+        slot_schemas: &Arc<SlotSchemaRegistry>,
+        policies: &PolicyRegistry,
+    ) -> PyResult<BuildRequest> {
+        let mut request = BuildRequest::new(self.scope, self.purpose);
+        if let Some(task_type) = self.task_type {
+            request.task_type = Some(task_type);
+        }
+        if let Some(cues) = self.cues {
+            request.cues = cues.into_cues()?;
+        }
+        if let Some(budget) = self.budget {
+            request.budget = budget;
+        }
+        if let Some(policy_id) = self.policy_id {
+            request.policy_id = policy_id;
+            // Ids without a registered preset are only recorded in the packet's meta.
+            policies.resolve(&mut request).map_err(store_error)?;
+        }
+        if let Some(policy) = self.policy {
+            request.policy = policy.apply_to(request.policy);
+        }
+        request.policy.slot_schemas = Some(slot_schemas.clone());
+        if let Some(persist) = self.persist {
+            request.persist = persist;
+        }
+        request.deadline = self.deadline_ms.map(Duration::from_millis);
+        if let Some(preference) = self.read_preference {
+            request.read_preference = preference;
+        }
+        Ok(request)
+    }
+}
+
+fn parse_build_requests(
+    requests_json: &str,
+    slot_schemas: &Arc<SlotSchemaRegistry>,
+    policies: &PolicyRegistry,
+) -> PyResult<Vec<BuildRequest>> {
+    let inputs: Vec<BuildRequestInput> = parse_json(requests_json)?;
+    inputs
+        .into_iter()
+        .map(|input| input.into_request(slot_schemas, policies))
+        .collect()
+}
+
+fn parse_validation_mode(mode: &str) -> PyResult<ValidationMode> {
+    match mode {
+        "strict" => Ok(ValidationMode::Strict),
+        "warn" => Ok(ValidationMode::Warn),
+        _ => Err(PyValueError::new_err(format!("invalid mode: {}", mode))),
+    }
+}
+
+/// The packet with its timestamps in the user's timezone, when they set one.
+fn packet_output(packet: &MemoryPacket, format: WireFormat) -> PyResult<Encoded> {
+    encode(&render_packet(packet).map_err(store_error)?, format)
+}
+
+/// One entry per request: the packet, or `{"error": ...}` for a failed build.
+fn bulk_results_output(
+    results: Vec<StoreResult<MemoryPacket>>,
+    format: WireFormat,
+) -> PyResult<Encoded> {
+    let entries = results
+        .into_iter()
+        .map(|result| match result {
+            Ok(packet) => render_packet(&packet).map_err(store_error),
+            Err(err) => Ok(serde_json::json!({ "error": err.to_string() })),
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    encode(&entries, format)
+}
+
+fn context_builds_json(
+    store: &dyn Store,
+    scope: &Scope,
+    limit: Option<usize>,
+    metadata_only: bool,
+) -> PyResult<String> {
+    if metadata_only {
+        let summaries = store
+            .list_context_build_summaries(scope, limit)
+            .map_err(store_error)?;
+        return to_json(&summaries);
+    }
+    let packets = store.list_context_builds(scope, limit).map_err(store_error)?;
+    to_json(&packets)
+}
+
+/// Working state rebuilt from the run's `state_patch` events with `seq` up to
+/// `up_to_seq`, or from all of them.
+fn replayed_state_json(
+    store: &dyn Store,
+    scope: &Scope,
+    up_to_seq: Option<u64>,
+) -> PyResult<Option<String>> {
+    let mut events = store.get_events_since(scope, 0, None).map_err(store_error)?;
+    if let Some(up_to_seq) = up_to_seq {
+        events.retain(|event| event.seq <= up_to_seq);
+    }
+    match replay_working_state(&events).map_err(store_error)? {
+        Some(state) => Ok(Some(to_json(&state)?)),
+        None => Ok(None),
+    }
+}
+
+/// A cue `time_range`: one window, or a list of them recalled together.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CueTimeRangeInput {
+    Many(Vec<TimeRangeInput>),
+    One(TimeRangeInput),
 }
 
 #[derive(Deserialize, Default)]
@@ -807,99 +3179,77 @@ struct RecallCuesInput {
     #[serde(default)]
     keywords: Vec<String>,
     #[serde(default)]
-    time_range: Option<TimeRangeInput>,
+    time_range: Option<CueTimeRangeInput>,
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    valid_at: Option<String>,
+    #[serde(default)]
+    valid_at_ms: Option<i64>,
 }
 
 impl RecallCuesInput {
-    fn to_cues(self) -> PyResult<RecallCues> {
+    fn into_cues(self) -> PyResult<RecallCues> {
+        let (time_range, time_ranges) = match self.time_range {
+            Some(CueTimeRangeInput::Many(ranges)) => {
+                let ranges = ranges.into_iter().map(TimeRangeInput::into_filter);
+                (None, ranges.collect::<PyResult<_>>()?)
+            }
+            Some(CueTimeRangeInput::One(range)) => (Some(range.into_filter()?), Vec::new()),
+            None => (None, Vec::new()),
+        };
         Ok(RecallCues {
             tags: self.tags,
             entities: self.entities,
             keywords: self.keywords,
-            time_range: match self.time_range {
-                Some(range) => Some(range.to_filter()?),
-                None => None,
-            },
+            time_range,
+            time_ranges,
+            lang: self.lang,
+            valid_at: parse_optional_timestamp(self.valid_at_ms, self.valid_at)?,
         })
     }
 }
 
-#[derive(Deserialize, Default)]
-struct RecallPolicyInput {
-    #[serde(default)]
-    max_total_candidates: Option<usize>,
-    #[serde(default)]
-    max_facts: Option<usize>,
-    #[serde(default)]
-    max_procedures: Option<usize>,
-    #[serde(default)]
-    max_episodes: Option<usize>,
-    #[serde(default)]
-    max_insights: Option<usize>,
-    #[serde(default)]
-    max_key_quotes: Option<usize>,
-    #[serde(default)]
-    conversation_window: Option<usize>,
-    #[serde(default)]
-    episode_time_window_days: Option<i64>,
-    #[serde(default)]
-    last_tool_evidence_limit: Option<usize>,
-    #[serde(default)]
-    include_conversation_window: Option<bool>,
-    #[serde(default)]
-    include_insights_in_tool: Option<bool>,
-    #[serde(default)]
-    allow_insights_in_responder: Option<bool>,
+fn parse_json<T: DeserializeOwned>(payload: &str) -> PyResult<T> {
+    serde_json::from_str(payload).map_err(py_error)
 }
 
-impl RecallPolicyInput {
-    fn apply_to(self, mut policy: RecallPolicy) -> RecallPolicy {
-        if let Some(value) = self.max_total_candidates {
-            policy.max_total_candidates = value;
-        }
-        if let Some(value) = self.max_facts {
-            policy.max_facts = value;
-        }
-        if let Some(value) = self.max_procedures {
-            policy.max_procedures = value;
-        }
-        if let Some(value) = self.max_episodes {
-            policy.max_episodes = value;
-        }
-        if let Some(value) = self.max_insights {
-            policy.max_insights = value;
-        }
-        if let Some(value) = self.max_key_quotes {
-            policy.max_key_quotes = value;
-        }
-        if let Some(value) = self.conversation_window {
-            policy.conversation_window = value;
-        }
-        if let Some(value) = self.episode_time_window_days {
-            policy.episode_time_window_days = value;
-        }
-        if let Some(value) = self.last_tool_evidence_limit {
-            policy.last_tool_evidence_limit = value;
-        }
-        if let Some(value) = self.include_conversation_window {
-            policy.include_conversation_window = value;
-        }
-        if let Some(value) = self.include_insights_in_tool {
-            policy.include_insights_in_tool = value;
-        }
-        if let Some(value) = self.allow_insights_in_responder {
-            policy.allow_insights_in_responder = value;
+fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(py_error)
+}
+
+/// A payload in the store's wire format: `str` for JSON, `bytes` for CBOR.
+enum Encoded {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl IntoPy<PyObject> for Encoded {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Encoded::Text(text) => text.into_py(py),
+            Encoded::Binary(bytes) => PyBytes::new(py, &bytes).into(),
         }
-        policy
     }
 }
 
-fn parse_json<T: DeserializeOwned>(payload: &str) -> PyResult<T> {
-    serde_json::from_str(payload).map_err(py_error)
+fn encode<T: Serialize>(value: &T, format: WireFormat) -> PyResult<Encoded> {
+    match format {
+        WireFormat::Json => Ok(Encoded::Text(to_json(value)?)),
+        _ => Ok(Encoded::Binary(encode_wire(value, format).map_err(store_error)?)),
+    }
 }
 
-fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
-    serde_json::to_string(value).map_err(py_error)
+/// Parses `str` input as JSON and `bytes` as CBOR.
+fn decode_input<T: DeserializeOwned>(payload: &PyAny) -> PyResult<T> {
+    if let Ok(bytes) = payload.downcast::<PyBytes>() {
+        return decode_wire(bytes.as_bytes(), WireFormat::Cbor).map_err(store_error);
+    }
+    parse_json(payload.extract::<&str>()?)
+}
+
+fn delete_mode(soft: bool) -> DeleteMode {
+    if soft { DeleteMode::Soft } else { DeleteMode::Hard }
 }
 
 fn parse_timestamp(ts_ms: Option<i64>, ts: Option<String>) -> PyResult<DateTime<Utc>> {
@@ -933,55 +3283,73 @@ fn parse_rfc3339(value: &str) -> PyResult<DateTime<Utc>> {
         .map_err(py_error)
 }
 
-fn parse_event_kind(value: &str) -> PyResult<EventKind> {
-    match value {
-        "message" => Ok(EventKind::Message),
-        "tool_result" => Ok(EventKind::ToolResult),
-        "state_patch" => Ok(EventKind::StatePatch),
-        "system" => Ok(EventKind::System),
-        _ => Err(PyValueError::new_err("invalid event kind")),
-    }
-}
-
-fn event_kind_to_str(kind: &EventKind) -> &'static str {
-    match kind {
-        EventKind::Message => "message",
-        EventKind::ToolResult => "tool_result",
-        EventKind::StatePatch => "state_patch",
-        EventKind::System => "system",
-    }
-}
-
 fn store_error(err: StoreError) -> PyErr {
-    PyValueError::new_err(match err {
-        StoreError::NotFound => "store item not found".to_string(),
-        StoreError::Poisoned => "store lock poisoned".to_string(),
-        StoreError::InvalidInput(message) => format!("invalid input: {}", message),
-        StoreError::Storage(message) => format!("storage error: {}", message),
-    })
+    match err {
+        StoreError::NotFound => PyValueError::new_err("store item not found"),
+        StoreError::Poisoned => PyValueError::new_err("store lock poisoned"),
+        StoreError::InvalidInput(message) => {
+            PyValueError::new_err(format!("invalid input: {}", message))
+        }
+        StoreError::Storage(message) => PyValueError::new_err(format!("storage error: {}", message)),
+        StoreError::Unavailable(message) => {
+            PyConnectionError::new_err(format!("store unavailable: {}", message))
+        }
+        StoreError::Timeout(message) => PyTimeoutError::new_err(format!("timed out: {}", message)),
+        StoreError::Overloaded(message) => {
+            PyBlockingIOError::new_err(format!("overloaded: {}", message))
+        }
+        err @ StoreError::VersionConflict { .. } => VersionConflictError::new_err(err.to_string()),
+        err @ StoreError::FactConflict { .. } => FactConflictError::new_err(err.to_string()),
+    }
 }
 
 fn py_error<E: std::fmt::Display>(err: E) -> PyErr {
     PyValueError::new_err(err.to_string())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn open_store(
     path: Option<String>,
     backend: Option<String>,
     dsn: Option<String>,
     database: Option<String>,
     in_memory: bool,
+    statement_timeout: Option<Duration>,
+    schema: Option<String>,
+    table_prefix: Option<String>,
 ) -> StoreResult<Box<dyn Store>> {
     let backend = backend
         .unwrap_or_else(|| "sqlite".to_string())
         .to_lowercase();
+    if schema.is_some() && backend != "postgres" {
+        return Err(StoreError::InvalidInput(
+            "schema only supported for postgres; use database for mysql".to_string(),
+        ));
+    }
+    if table_prefix.is_some() && backend == "postgres" {
+        return Err(StoreError::InvalidInput(
+            "table_prefix only supported for sqlite and mysql; use schema for postgres"
+                .to_string(),
+        ));
+    }
     match backend.as_str() {
         "sqlite" => {
             if in_memory {
-                Ok(Box::new(SqliteStore::new_in_memory()?))
+                let store = match table_prefix {
+                    Some(prefix) => SqliteStore::with_table_prefix(":memory:", &prefix)?,
+                    None => SqliteStore::new_in_memory()?,
+                };
+                Ok(Box::new(store))
             } else {
                 let path = path.unwrap_or_else(|| "data/engram.db".to_string());
-                Ok(Box::new(SqliteStore::new(path)?))
+                let mut store = match table_prefix {
+                    Some(prefix) => SqliteStore::with_table_prefix(path, &prefix)?,
+                    None => SqliteStore::new(path)?,
+                };
+                if let Some(timeout) = statement_timeout {
+                    store = store.with_statement_timeout(timeout);
+                }
+                Ok(Box::new(store))
             }
         }
         "postgres" => {
@@ -996,14 +3364,21 @@ fn open_store(
             #[cfg(feature = "postgres")]
             {
                 let dsn = apply_database_to_dsn(&dsn, database.as_deref());
-                return Ok(Box::new(PostgresStore::new(&dsn)?));
+                let mut store = match schema {
+                    Some(schema) => PostgresStore::in_schema(&dsn, &schema)?,
+                    None => PostgresStore::new(&dsn)?,
+                };
+                if let Some(timeout) = statement_timeout {
+                    store = store.with_statement_timeout(timeout);
+                }
+                Ok(Box::new(store))
             }
             #[cfg(not(feature = "postgres"))]
             {
-                let _ = (dsn, database);
-                return Err(StoreError::InvalidInput(
+                let _ = (dsn, database, statement_timeout, schema);
+                Err(StoreError::InvalidInput(
                     "postgres feature not enabled".to_string(),
-                ));
+                ))
             }
         }
         "mysql" => {
@@ -1017,14 +3392,21 @@ fn open_store(
             #[cfg(feature = "mysql")]
             {
                 let dsn = apply_database_to_dsn(&dsn, database.as_deref());
-                return Ok(Box::new(MySqlStore::new(&dsn)?));
+                let mut store = match table_prefix {
+                    Some(prefix) => MySqlStore::with_table_prefix(&dsn, &prefix)?,
+                    None => MySqlStore::new(&dsn)?,
+                };
+                if let Some(timeout) = statement_timeout {
+                    store = store.with_statement_timeout(timeout);
+                }
+                Ok(Box::new(store))
             }
             #[cfg(not(feature = "mysql"))]
             {
-                let _ = (dsn, database);
-                return Err(StoreError::InvalidInput(
+                let _ = (dsn, database, statement_timeout, table_prefix);
+                Err(StoreError::InvalidInput(
                     "mysql feature not enabled".to_string(),
-                ));
+                ))
             }
         }
         _ => Err(StoreError::InvalidInput(format!(
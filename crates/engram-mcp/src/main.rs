@@ -0,0 +1,110 @@
+//! MCP server exposing an engram store to LLM agent frameworks as tools:
+//! `memory.recall`, `memory.append_event` and `memory.write_fact`.
+//!
+//! ```text
+//! engram-mcp [--tenant ID] [--user ID] [--agent ID] [--session ID] [--run ID] [TARGET]
+//! ```
+//!
+//! Speaks JSON-RPC over stdin and stdout, one message per line, as MCP clients
+//! launch local servers; logs go to stderr. `TARGET` is a SQLite path (default
+//! `data/engram.db`) or a `postgres://` / `mysql://` DSN when the matching feature
+//! is enabled. Without `--session` every connection starts a new session, and the
+//! run id defaults to the session id.
+
+mod server;
+
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use engram_store::{SqliteStore, Store, StoreResult};
+#[cfg(feature = "mysql")]
+use engram_store::MySqlStore;
+#[cfg(feature = "postgres")]
+use engram_store::PostgresStore;
+use engram_types::Scope;
+
+use crate::server::McpServer;
+
+const USAGE: &str = "usage: engram-mcp [--tenant ID] [--user ID] [--agent ID] [--session ID] \
+                     [--run ID] [SQLITE_PATH | postgres://... | mysql://...]";
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    let mut scope = Scope {
+        tenant_id: "default".to_string(),
+        user_id: "default".to_string(),
+        agent_id: "default".to_string(),
+        session_id: String::new(),
+        run_id: String::new(),
+    };
+    let mut target = "data/engram.db".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let field = match arg.as_str() {
+            "--tenant" => &mut scope.tenant_id,
+            "--user" => &mut scope.user_id,
+            "--agent" => &mut scope.agent_id,
+            "--session" => &mut scope.session_id,
+            "--run" => &mut scope.run_id,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                target = arg;
+                continue;
+            }
+        };
+        *field = args.next().unwrap_or_default();
+    }
+
+    let store = match open_store(&target) {
+        Ok(store) => store,
+        Err(err) => {
+            eprintln!("engram-mcp: cannot open {}: {}", target, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut server = McpServer::new(store, scope);
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("engram-mcp: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_line(&line)
+            && writeln!(stdout, "{}", response).and_then(|()| stdout.flush()).is_err()
+        {
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn open_store(target: &str) -> StoreResult<Box<dyn Store>> {
+    if target.starts_with("postgres://") || target.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Box::new(PostgresStore::new(target)?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(engram_store::StoreError::InvalidInput(
+            "postgres feature not enabled".to_string(),
+        ));
+    }
+    if target.starts_with("mysql://") {
+        #[cfg(feature = "mysql")]
+        return Ok(Box::new(MySqlStore::new(target)?));
+        #[cfg(not(feature = "mysql"))]
+        return Err(engram_store::StoreError::InvalidInput(
+            "mysql feature not enabled".to_string(),
+        ));
+    }
+    let path = target.strip_prefix("sqlite://").unwrap_or(target);
+    Ok(Box::new(SqliteStore::new(path)?))
+}
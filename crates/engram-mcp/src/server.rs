@@ -0,0 +1,358 @@
+//! MCP (Model Context Protocol) request handling: JSON-RPC 2.0 messages in, responses
+//! out. Tool calls are scoped to the session negotiated by `initialize`.
+
+use engram_store::{
+    build_memory_packet, render_packet, BuildRequest, Event, EventKind, FactFilter, RecallCues,
+    Store, StoreError, StoreResult,
+};
+use engram_types::{new_ulid, Fact, FactStatus, Purpose, Scope};
+use serde_json::{json, Value};
+
+/// Protocol revisions this server speaks, newest first. A client asking for another
+/// one is offered the newest.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// `_meta` key of `initialize` params through which a client can set the session's
+/// scope, e.g. `{"engram/scope": {"user_id": "u1", "run_id": "r7"}}`.
+pub const SCOPE_META_KEY: &str = "engram/scope";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serves one MCP session. The scope starts from the command line; `initialize`
+/// fills in the session and run ids (a fresh session, unless given) and applies any
+/// scope the client passes under [`SCOPE_META_KEY`]; the resolved scope is returned
+/// under the same key of the result's `_meta`.
+pub struct McpServer {
+    store: Box<dyn Store>,
+    defaults: Scope,
+    scope: Option<Scope>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl McpServer {
+    pub fn new(store: Box<dyn Store>, defaults: Scope) -> Self {
+        Self {
+            store,
+            defaults,
+            scope: None,
+        }
+    }
+
+    /// Handles one line of input; returns the response line, or `None` for
+    /// notifications.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(message) => self.handle(message)?,
+            Err(err) => error_response(Value::Null, RpcError::new(PARSE_ERROR, err.to_string())),
+        };
+        Some(response.to_string())
+    }
+
+    /// Handles one JSON-RPC message; returns `None` for notifications.
+    pub fn handle(&mut self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+        let result = match method {
+            "initialize" => self.initialize(&params),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(&params),
+            "" => Err(RpcError::new(INVALID_REQUEST, "missing method")),
+            method if method.starts_with("notifications/") => return None,
+            method => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => error_response(id, err),
+        })
+    }
+
+    fn initialize(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let mut scope = self.defaults.clone();
+        if scope.session_id.is_empty() {
+            scope.session_id = new_ulid();
+        }
+        let overrides = params.get("_meta").and_then(|meta| meta.get(SCOPE_META_KEY));
+        if let Some(overrides) = overrides {
+            let field =
+                |name: &str| overrides.get(name).and_then(Value::as_str).map(str::to_string);
+            scope.tenant_id = field("tenant_id").unwrap_or(scope.tenant_id);
+            scope.user_id = field("user_id").unwrap_or(scope.user_id);
+            scope.agent_id = field("agent_id").unwrap_or(scope.agent_id);
+            scope.session_id = field("session_id").unwrap_or(scope.session_id);
+            scope.run_id = field("run_id").unwrap_or(scope.run_id);
+        }
+        if scope.run_id.is_empty() {
+            scope.run_id = scope.session_id.clone();
+        }
+        tracing::info!(
+            "session {}/{}/{}/{}/{}",
+            scope.tenant_id,
+            scope.user_id,
+            scope.agent_id,
+            scope.session_id,
+            scope.run_id
+        );
+        let resolved = serde_json::to_value(&scope).unwrap_or_default();
+        self.scope = Some(scope);
+
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = requested
+            .filter(|version| PROTOCOL_VERSIONS.contains(version))
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        Ok(json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "engram", "version": env!("CARGO_PKG_VERSION") },
+            "_meta": { SCOPE_META_KEY: resolved },
+        }))
+    }
+
+    fn call_tool(&self, params: &Value) -> Result<Value, RpcError> {
+        let scope = self
+            .scope
+            .as_ref()
+            .ok_or_else(|| RpcError::new(INVALID_REQUEST, "session is not initialized"))?;
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing tool name"))?;
+        let args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+        let store = self.store.as_ref();
+        let result = match name {
+            "memory.recall" => recall(store, scope, &args),
+            "memory.append_event" => append_event(store, scope, &args),
+            "memory.write_fact" => write_fact(store, scope, &args),
+            name => return Err(RpcError::new(INVALID_PARAMS, format!("unknown tool {}", name))),
+        };
+        // Store failures are tool results the model can read, not protocol errors.
+        Ok(match result {
+            Ok(value) => json!({
+                "content": [{ "type": "text", "text": value.to_string() }],
+                "structuredContent": value,
+                "isError": false,
+            }),
+            Err(err) => json!({
+                "content": [{ "type": "text", "text": err.to_string() }],
+                "isError": true,
+            }),
+        })
+    }
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": err.code, "message": err.message },
+    })
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "memory.recall",
+            "description": "Recall what is known for the current task: working state, recent \
+                conversation, facts, preferences, episodes and procedures, as a memory packet.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "purpose": { "type": "string", "enum": ["planner", "tool", "responder"] },
+                    "task_type": { "type": "string" },
+                    "query": { "type": "string", "description": "Keywords to recall by." },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "entities": { "type": "array", "items": { "type": "string" } },
+                    "max_tokens": { "type": "integer", "minimum": 1 },
+                },
+            },
+        },
+        {
+            "name": "memory.append_event",
+            "description": "Record something that happened in this run: a message, a tool \
+                call or result, or an application-defined event.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "kind": { "type": "string", "description": "Defaults to message." },
+                    "payload": {},
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "entities": { "type": "array", "items": { "type": "string" } },
+                },
+                "required": ["payload"],
+            },
+        },
+        {
+            "name": "memory.write_fact",
+            "description": "Remember a fact about the user, replacing the current value of \
+                the same key.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "key": { "type": "string" },
+                    "value": {},
+                    "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "sources": { "type": "array", "items": { "type": "string" } },
+                },
+                "required": ["key", "value"],
+            },
+        },
+    ])
+}
+
+fn string_list(args: &Value, name: &str) -> Vec<String> {
+    args.get(name)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn recall(store: &dyn Store, scope: &Scope, args: &Value) -> StoreResult<Value> {
+    let purpose = match args.get("purpose") {
+        Some(purpose) => serde_json::from_value(purpose.clone())?,
+        None => Purpose::Responder,
+    };
+    let mut request = BuildRequest::new(scope.clone(), purpose);
+    request.task_type = args.get("task_type").and_then(Value::as_str).map(str::to_string);
+    request.cues = RecallCues {
+        tags: string_list(args, "tags"),
+        entities: string_list(args, "entities"),
+        keywords: args
+            .get("query")
+            .and_then(Value::as_str)
+            .map(|query| query.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        ..RecallCues::default()
+    };
+    if let Some(max_tokens) = args.get("max_tokens").and_then(Value::as_u64) {
+        request.budget.max_tokens = max_tokens.min(u32::MAX as u64) as u32;
+    }
+    render_packet(&build_memory_packet(store, request)?)
+}
+
+fn append_event(store: &dyn Store, scope: &Scope, args: &Value) -> StoreResult<Value> {
+    let kind = match args.get("kind").and_then(Value::as_str) {
+        Some(kind) => kind.parse()?,
+        None => EventKind::Message,
+    };
+    let payload = args
+        .get("payload")
+        .cloned()
+        .ok_or_else(|| StoreError::InvalidInput("payload is required".to_string()))?;
+    let mut event = Event::new(scope.clone(), kind, payload);
+    event.tags = string_list(args, "tags");
+    event.entities = string_list(args, "entities");
+    let event_id = event.event_id.clone();
+    store.append_event(event)?;
+    Ok(json!({ "event_id": event_id }))
+}
+
+/// Upserts into the user's own fact pool, reusing the id of the active fact with the
+/// same key so the new value replaces it.
+fn write_fact(store: &dyn Store, scope: &Scope, args: &Value) -> StoreResult<Value> {
+    let key = args.get("key").and_then(Value::as_str).unwrap_or_default().trim();
+    if key.is_empty() {
+        return Err(StoreError::InvalidInput("key must not be empty".to_string()));
+    }
+    let value = args
+        .get("value")
+        .cloned()
+        .ok_or_else(|| StoreError::InvalidInput("value is required".to_string()))?;
+    let mut fact = Fact::new(key, value);
+    if let Some(confidence) = args.get("confidence").and_then(Value::as_f64) {
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(StoreError::InvalidInput("confidence must be in [0, 1]".to_string()));
+        }
+        fact.confidence = confidence;
+    }
+    fact.sources = string_list(args, "sources");
+
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Active]),
+        ..FactFilter::default()
+    };
+    if let Some(existing) = store
+        .list_facts(scope, filter)?
+        .into_iter()
+        .find(|existing| existing.fact_key == key)
+    {
+        fact.fact_id = existing.fact_id;
+    }
+    store.upsert_fact(scope, fact.clone())?;
+    Ok(serde_json::to_value(fact)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_store::InMemoryStore;
+
+    fn call(server: &mut McpServer, id: u64, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        server.handle(request).unwrap()
+    }
+
+    #[test]
+    fn tools_read_and_write_the_session_scope() {
+        let defaults = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: String::new(),
+            run_id: String::new(),
+        };
+        let mut server = McpServer::new(Box::new(InMemoryStore::new()), defaults);
+        let early = call(&mut server, 1, "tools/call", json!({ "name": "memory.recall" }));
+        assert_eq!(early["error"]["code"], INVALID_REQUEST);
+
+        let params = json!({
+            "protocolVersion": "2025-03-26",
+            "_meta": { "engram/scope": { "session_id": "s1" } },
+        });
+        let init = call(&mut server, 2, "initialize", params);
+        assert_eq!(init["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(init["result"]["_meta"][SCOPE_META_KEY]["run_id"], "s1");
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle(initialized).is_none());
+        let listed = call(&mut server, 3, "tools/list", json!({}));
+        assert_eq!(listed["result"]["tools"].as_array().unwrap().len(), 3);
+
+        let args = json!({ "payload": "booked the lisbon flight", "tags": ["travel"] });
+        let event = json!({ "name": "memory.append_event", "arguments": args });
+        assert_eq!(call(&mut server, 4, "tools/call", event)["result"]["isError"], false);
+        for value in ["Lisbon", "Porto"] {
+            let args = json!({ "key": "user.city", "value": value });
+            let fact = json!({ "name": "memory.write_fact", "arguments": args });
+            assert_eq!(call(&mut server, 5, "tools/call", fact)["result"]["isError"], false);
+        }
+        let recall = json!({ "name": "memory.recall", "arguments": { "tags": ["travel"] } });
+        let packet = &call(&mut server, 6, "tools/call", recall)["result"]["structuredContent"];
+        let facts = packet["long_term"]["facts"].as_array().unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0]["value"], "Porto");
+        let quotes = packet["short_term"]["key_quotes"].as_array().unwrap();
+        assert_eq!(quotes[0]["quote"], "booked the lisbon flight");
+
+        let bad = json!({ "name": "memory.write_fact", "arguments": { "value": 1 } });
+        assert_eq!(call(&mut server, 7, "tools/call", bad)["result"]["isError"], true);
+        let unknown = call(&mut server, 8, "resources/list", json!({}));
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
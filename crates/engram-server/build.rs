@@ -0,0 +1,7 @@
+// Compiles proto/engram.proto with protox, so building does not need `protoc`.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/engram.proto");
+    let descriptors = protox::compile(["engram.proto"], ["proto"])?;
+    tonic_prost_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
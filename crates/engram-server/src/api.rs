@@ -0,0 +1,750 @@
+//! REST routes over a [`Store`]. Scopes come from the path:
+//!
+//! ```text
+//! /v1/tenants/{tenant_id}/users/{user_id}/agents/{agent_id}
+//!     GET  /facts?status=active,disputed&include_shared=true&limit=N
+//!     PUT  /facts                         upsert a fact
+//!     GET  /episodes?tags=a,b&limit=N
+//!     POST /episodes
+//!     GET  /procedures?task_type=T&limit=N
+//!     PUT  /procedures
+//!     GET  /insights?limit=N
+//!     POST /insights
+//!     /sessions/{session_id}/runs/{run_id}
+//!         POST  /events                   {"kind", "payload", "tags"?, "entities"?, "ts"?}
+//!         GET   /events?start=RFC3339&end=RFC3339&limit=N
+//!         GET   /working-state
+//!         PATCH /working-state
+//!         GET   /stm
+//!         PUT   /stm
+//!         POST  /memory-packet            {"purpose", "task_type"?, "cues"?, "budget"?}
+//! ```
+//!
+//! Bodies and responses are the JSON forms of the engram types. The event and packet
+//! routes also take CBOR bodies sent as `Content-Type: application/cbor`, and answer in
+//! CBOR when the request's `Accept` lists it (see [`engram_store::WireFormat`]); errors
+//! stay JSON. Store errors map to status codes in [`ApiError`]. Behind
+//! [`router_with_tokens`], every route needs a bearer token whose grant covers the
+//! path's tenant, user and agent. A request's `x-correlation-id` header is echoed
+//! back and recorded with the store calls it makes (see
+//! [`engram_store::with_correlation_id`]).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Path, Query, Request, State};
+use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use engram_store::{
+    build_memory_packet, decode_wire, encode_wire, upsert_fact_checked, with_correlation_id,
+    BuildRequest, ConflictPolicy, EpisodeFilter, Event, EventKind, FactFilter, InsightFilter,
+    RecallCues, StmState, Store, StoreError, StoreResult, TimeRangeFilter, WireFormat,
+    WorkingStatePatch,
+};
+use engram_types::{
+    Budget, Episode, EvidenceRef, Fact, FactStatus, InsightItem, JsonMap, MemoryPacket, Procedure,
+    Purpose, Scope, WorkingState, new_ulid,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::auth::ApiTokens;
+use crate::error::ApiError;
+
+type StoreState = State<Arc<dyn Store>>;
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Header (gRPC metadata key) naming the caller's request id.
+pub const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// Media type of CBOR bodies on the event and packet routes.
+pub const CBOR_MEDIA_TYPE: &str = "application/cbor";
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+pub fn router(store: Arc<dyn Store>) -> Router {
+    routes(store, None)
+}
+
+/// [`router`] with every route checked against `tokens` before the store is called;
+/// a missing or unknown token is `401`, a scope outside the token's grant is `403`.
+pub fn router_with_tokens(store: Arc<dyn Store>, tokens: ApiTokens) -> Router {
+    routes(store, Some(Arc::new(tokens)))
+}
+
+fn routes(store: Arc<dyn Store>, tokens: Option<Arc<ApiTokens>>) -> Router {
+    let run = Router::new()
+        .route("/events", post(append_event).get(list_events))
+        .route("/working-state", get(get_working_state).patch(patch_working_state))
+        .route("/stm", get(get_stm).put(update_stm))
+        .route("/memory-packet", post(build_packet));
+    let agent = Router::new()
+        .route("/facts", get(list_facts).put(upsert_fact))
+        .route("/episodes", get(list_episodes).post(append_episode))
+        .route("/procedures", get(list_procedures).put(upsert_procedure))
+        .route("/insights", get(list_insights).post(append_insight))
+        .nest("/sessions/{session_id}/runs/{run_id}", run);
+    let agent = match tokens {
+        Some(tokens) => agent.route_layer(middleware::from_fn_with_state(tokens, require_token)),
+        None => agent,
+    };
+    Router::new()
+        .nest("/v1/tenants/{tenant_id}/users/{user_id}/agents/{agent_id}", agent)
+        .layer(middleware::from_fn(correlate))
+        .with_state(store)
+}
+
+/// Runs the request under its correlation id, for [`blocking`] to pass to the store.
+async fn correlate(request: Request, next: Next) -> Response {
+    let Some(value) = request.headers().get(CORRELATION_HEADER).cloned() else {
+        return next.run(request).await;
+    };
+    let Ok(correlation_id) = value.to_str().map(str::to_string) else {
+        return next.run(request).await;
+    };
+    let mut response = CORRELATION_ID.scope(correlation_id, next.run(request)).await;
+    response.headers_mut().insert(CORRELATION_HEADER, value);
+    response
+}
+
+/// Refuses requests whose bearer token does not cover the path's scope.
+async fn require_token(
+    State(tokens): State<Arc<ApiTokens>>,
+    Path(path): Path<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let param = |name: &str| path.get(name).cloned().unwrap_or_default();
+    let scope = Scope {
+        tenant_id: param("tenant_id"),
+        user_id: param("user_id"),
+        agent_id: param("agent_id"),
+        session_id: param("session_id"),
+        run_id: param("run_id"),
+    };
+    let header = request.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok());
+    tokens.authorize(header, &scope)?;
+    Ok(next.run(request).await)
+}
+
+/// Runs a store call off the async runtime; stores block on I/O.
+async fn blocking<T, F>(call: F) -> ApiResult<T>
+where
+    F: FnOnce() -> StoreResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let correlation_id = CORRELATION_ID.try_with(Clone::clone).ok();
+    tokio::task::spawn_blocking(move || match correlation_id {
+        Some(correlation_id) => with_correlation_id(&correlation_id, call),
+        None => call(),
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::from)
+}
+
+#[derive(Deserialize)]
+struct AgentPath {
+    tenant_id: String,
+    user_id: String,
+    agent_id: String,
+}
+
+impl AgentPath {
+    /// Long-term memory is keyed by tenant, user and agent; the run is left empty.
+    fn scope(self) -> Scope {
+        Scope {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            agent_id: self.agent_id,
+            session_id: String::new(),
+            run_id: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RunPath {
+    tenant_id: String,
+    user_id: String,
+    agent_id: String,
+    session_id: String,
+    run_id: String,
+}
+
+impl RunPath {
+    fn scope(self) -> Scope {
+        Scope {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            agent_id: self.agent_id,
+            session_id: self.session_id,
+            run_id: self.run_id,
+        }
+    }
+}
+
+/// Whether a `Content-Type` or `Accept` header names CBOR.
+fn names_cbor(headers: &HeaderMap, name: impl axum::http::header::AsHeaderName) -> bool {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.split(',').any(|range| {
+                let media = range.split(';').next().unwrap_or_default();
+                media.trim().eq_ignore_ascii_case(CBOR_MEDIA_TYPE)
+            })
+        })
+}
+
+/// A request body in the format its `Content-Type` names: CBOR for
+/// [`CBOR_MEDIA_TYPE`], JSON otherwise.
+struct WireBody<T>(T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for WireBody<T> {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> ApiResult<Self> {
+        let format = if names_cbor(request.headers(), CONTENT_TYPE) {
+            WireFormat::Cbor
+        } else {
+            WireFormat::Json
+        };
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(|err| ApiError::bad_request(err.body_text()))?;
+        let value = decode_wire(&bytes, format)
+            .map_err(|err| ApiError::bad_request(format!("invalid {} body: {}", format, err)))?;
+        Ok(WireBody(value))
+    }
+}
+
+/// The response format a request accepts: CBOR when its `Accept` lists
+/// [`CBOR_MEDIA_TYPE`], JSON otherwise.
+struct Accept(WireFormat);
+
+impl<S: Send + Sync> FromRequestParts<S> for Accept {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> ApiResult<Self> {
+        Ok(Accept(if names_cbor(&parts.headers, ACCEPT) {
+            WireFormat::Cbor
+        } else {
+            WireFormat::Json
+        }))
+    }
+}
+
+/// A response body encoded with [`encode_wire`] in the format the request accepts.
+struct Wire<T>(WireFormat, T);
+
+impl<T: Serialize> IntoResponse for Wire<T> {
+    fn into_response(self) -> Response {
+        let content_type = match self.0 {
+            WireFormat::Json => "application/json",
+            WireFormat::Cbor => CBOR_MEDIA_TYPE,
+        };
+        match encode_wire(&self.1, self.0) {
+            Ok(bytes) => ([(CONTENT_TYPE, HeaderValue::from_static(content_type))], bytes)
+                .into_response(),
+            Err(err) => ApiError::internal(err).into_response(),
+        }
+    }
+}
+
+/// Splits a comma-separated query value.
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct EventInput {
+    #[serde(default = "new_ulid")]
+    event_id: String,
+    #[serde(default)]
+    ts: Option<DateTime<Utc>>,
+    kind: String,
+    payload: Value,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    entities: Vec<String>,
+    #[serde(default)]
+    lang: Option<String>,
+}
+
+async fn append_event(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+    Accept(format): Accept,
+    WireBody(input): WireBody<EventInput>,
+) -> ApiResult<(StatusCode, Wire<Value>)> {
+    let kind = input.kind.parse::<EventKind>()?;
+    let mut event = Event::new(path.scope(), kind, input.payload);
+    event.event_id = input.event_id;
+    event.ts = input.ts.unwrap_or(event.ts);
+    event.tags = input.tags;
+    event.entities = input.entities;
+    event.lang = input.lang;
+    let event_id = event.event_id.clone();
+    blocking(move || store.append_event(event)).await?;
+    Ok((StatusCode::CREATED, Wire(format, json!({ "event_id": event_id }))))
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+}
+
+async fn list_events(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+    Query(query): Query<EventsQuery>,
+    Accept(format): Accept,
+) -> ApiResult<Wire<Vec<Event>>> {
+    let scope = path.scope();
+    let range = TimeRangeFilter {
+        start: query.start,
+        end: query.end,
+    };
+    let events = blocking(move || store.list_events(&scope, range, query.limit)).await?;
+    Ok(Wire(format, events))
+}
+
+async fn get_working_state(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+) -> ApiResult<Json<WorkingState>> {
+    let scope = path.scope();
+    let state = blocking(move || store.get_working_state(&scope)).await?;
+    state.map(Json).ok_or(ApiError::from(StoreError::NotFound))
+}
+
+#[derive(Deserialize)]
+struct WorkingStatePatchInput {
+    #[serde(default)]
+    goal: Option<String>,
+    #[serde(default)]
+    plan: Option<Vec<String>>,
+    #[serde(default)]
+    slots: Option<JsonMap>,
+    #[serde(default)]
+    constraints: Option<JsonMap>,
+    #[serde(default)]
+    tool_evidence: Option<Vec<EvidenceRef>>,
+    #[serde(default)]
+    decisions: Option<Vec<String>>,
+    #[serde(default)]
+    risks: Option<Vec<String>>,
+    #[serde(default)]
+    state_version: Option<u32>,
+    #[serde(default)]
+    expected_state_version: Option<u32>,
+    /// Task type whose slot schema the patch is checked against.
+    #[serde(default)]
+    task_type: Option<String>,
+}
+
+impl WorkingStatePatchInput {
+    fn into_patch(self) -> WorkingStatePatch {
+        WorkingStatePatch {
+            goal: self.goal,
+            plan: self.plan,
+            slots: self.slots,
+            constraints: self.constraints,
+            tool_evidence: self.tool_evidence,
+            decisions: self.decisions,
+            risks: self.risks,
+            state_version: self.state_version,
+            expected_state_version: self.expected_state_version,
+            clock: None,
+            task_type: self.task_type,
+        }
+    }
+}
+
+async fn patch_working_state(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+    Json(input): Json<WorkingStatePatchInput>,
+) -> ApiResult<Json<WorkingState>> {
+    let scope = path.scope();
+    let patch = input.into_patch();
+    let state = blocking(move || store.patch_working_state(&scope, patch)).await?;
+    Ok(Json(state))
+}
+
+async fn get_stm(State(store): StoreState, Path(path): Path<RunPath>) -> ApiResult<Json<StmState>> {
+    let scope = path.scope();
+    let stm = blocking(move || store.get_stm(&scope)).await?;
+    stm.map(Json).ok_or(ApiError::from(StoreError::NotFound))
+}
+
+async fn update_stm(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+    Json(stm): Json<StmState>,
+) -> ApiResult<StatusCode> {
+    let scope = path.scope();
+    blocking(move || store.update_stm(&scope, stm)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, Default)]
+struct CuesInput {
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    entities: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    valid_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct PacketInput {
+    purpose: Purpose,
+    #[serde(default)]
+    task_type: Option<String>,
+    #[serde(default)]
+    cues: CuesInput,
+    #[serde(default)]
+    budget: Option<Budget>,
+    #[serde(default)]
+    persist: Option<bool>,
+}
+
+async fn build_packet(
+    State(store): StoreState,
+    Path(path): Path<RunPath>,
+    Accept(format): Accept,
+    WireBody(input): WireBody<PacketInput>,
+) -> ApiResult<Wire<MemoryPacket>> {
+    let mut request = BuildRequest::new(path.scope(), input.purpose);
+    request.task_type = input.task_type;
+    request.cues = RecallCues {
+        tags: input.cues.tags,
+        entities: input.cues.entities,
+        keywords: input.cues.keywords,
+        lang: input.cues.lang,
+        valid_at: input.cues.valid_at,
+        ..RecallCues::default()
+    };
+    if let Some(budget) = input.budget {
+        request.budget = budget;
+    }
+    if let Some(persist) = input.persist {
+        request.persist = persist;
+    }
+    let packet = blocking(move || build_memory_packet(store.as_ref(), request)).await?;
+    Ok(Wire(format, packet))
+}
+
+#[derive(Deserialize)]
+struct FactsQuery {
+    status: Option<String>,
+    #[serde(default)]
+    include_shared: bool,
+    limit: Option<usize>,
+}
+
+async fn list_facts(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Query(query): Query<FactsQuery>,
+) -> ApiResult<Json<Vec<Fact>>> {
+    let statuses = split_list(query.status)
+        .into_iter()
+        .map(|status| {
+            serde_json::from_value::<FactStatus>(Value::String(status.clone()))
+                .map_err(|_| ApiError::bad_request(format!("unknown fact status: {}", status)))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+    let filter = FactFilter {
+        status: (!statuses.is_empty()).then_some(statuses),
+        include_shared: query.include_shared,
+        limit: query.limit,
+        ..FactFilter::default()
+    };
+    let scope = path.scope();
+    let facts = blocking(move || store.list_facts(&scope, filter)).await?;
+    Ok(Json(facts))
+}
+
+#[derive(Deserialize)]
+struct UpsertFactQuery {
+    conflict_policy: Option<ConflictPolicy>,
+}
+
+async fn upsert_fact(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Query(query): Query<UpsertFactQuery>,
+    Json(fact): Json<Fact>,
+) -> ApiResult<Json<Fact>> {
+    let scope = path.scope();
+    let Some(policy) = query.conflict_policy else {
+        let stored = fact.clone();
+        blocking(move || store.upsert_fact(&scope, stored)).await?;
+        return Ok(Json(fact));
+    };
+    let upsert = blocking(move || upsert_fact_checked(store.as_ref(), &scope, fact, policy)).await?;
+    Ok(Json(upsert.fact))
+}
+
+#[derive(Deserialize)]
+struct EpisodesQuery {
+    tags: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn list_episodes(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Query(query): Query<EpisodesQuery>,
+) -> ApiResult<Json<Vec<Episode>>> {
+    let filter = EpisodeFilter {
+        tags: split_list(query.tags),
+        limit: query.limit,
+        ..EpisodeFilter::default()
+    };
+    let scope = path.scope();
+    let episodes = blocking(move || store.list_episodes(&scope, filter)).await?;
+    Ok(Json(episodes))
+}
+
+async fn append_episode(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Json(episode): Json<Episode>,
+) -> ApiResult<(StatusCode, Json<Episode>)> {
+    let scope = path.scope();
+    let stored = episode.clone();
+    blocking(move || store.append_episode(&scope, stored)).await?;
+    Ok((StatusCode::CREATED, Json(episode)))
+}
+
+#[derive(Deserialize)]
+struct ProceduresQuery {
+    task_type: String,
+    limit: Option<usize>,
+}
+
+async fn list_procedures(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Query(query): Query<ProceduresQuery>,
+) -> ApiResult<Json<Vec<Procedure>>> {
+    let scope = path.scope();
+    let procedures =
+        blocking(move || store.list_procedures(&scope, &query.task_type, query.limit)).await?;
+    Ok(Json(procedures))
+}
+
+async fn upsert_procedure(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Json(procedure): Json<Procedure>,
+) -> ApiResult<Json<Procedure>> {
+    let scope = path.scope();
+    let stored = procedure.clone();
+    blocking(move || store.upsert_procedure(&scope, stored)).await?;
+    Ok(Json(procedure))
+}
+
+#[derive(Deserialize)]
+struct InsightsQuery {
+    limit: Option<usize>,
+}
+
+async fn list_insights(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Query(query): Query<InsightsQuery>,
+) -> ApiResult<Json<Vec<InsightItem>>> {
+    let filter = InsightFilter {
+        limit: query.limit,
+        ..InsightFilter::default()
+    };
+    let scope = path.scope();
+    let insights = blocking(move || store.list_insights(&scope, filter)).await?;
+    Ok(Json(insights))
+}
+
+async fn append_insight(
+    State(store): StoreState,
+    Path(path): Path<AgentPath>,
+    Json(insight): Json<InsightItem>,
+) -> ApiResult<(StatusCode, Json<InsightItem>)> {
+    let scope = path.scope();
+    let stored = insight.clone();
+    blocking(move || store.append_insight(&scope, stored)).await?;
+    Ok((StatusCode::CREATED, Json(insight)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use engram_store::{ChangeLogStore, InMemoryStore};
+    use tower::ServiceExt;
+
+    const AGENT: &str = "/v1/tenants/acme/users/user1/agents/agent1";
+    const RUN: &str = "/v1/tenants/acme/users/user1/agents/agent1/sessions/s1/runs/r1";
+
+    async fn call(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.map(|body| body.to_string()).unwrap_or_default()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn serves_the_store_over_rest() {
+        let app = router(Arc::new(InMemoryStore::new()));
+
+        let message = json!({"kind": "message", "payload": {"role": "user", "content": "hi"}});
+        let (status, created) = call(&app, "POST", &format!("{RUN}/events"), Some(message)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, events) = call(&app, "GET", &format!("{RUN}/events?limit=10"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(events[0]["event_id"], created["event_id"]);
+        assert_eq!(events[0]["scope"]["run_id"], "r1");
+
+        let (status, _) = call(&app, "GET", &format!("{RUN}/working-state"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let patch = json!({"goal": "book a flight"});
+        let (status, state) =
+            call(&app, "PATCH", &format!("{RUN}/working-state"), Some(patch)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state["goal"], "book a flight");
+
+        let fact = json!({"fact_key": "user.city", "value": "Lisbon"});
+        let (status, _) = call(&app, "PUT", &format!("{AGENT}/facts"), Some(fact)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, facts) =
+            call(&app, "GET", &format!("{AGENT}/facts?status=active"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(facts.as_array().unwrap().len(), 1);
+        let (status, _) = call(&app, "GET", &format!("{AGENT}/facts?status=maybe"), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let request = json!({"purpose": "planner", "persist": false});
+        let (status, packet) =
+            call(&app, "POST", &format!("{RUN}/memory-packet"), Some(request)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(packet["short_term"]["working_state"]["goal"], "book a flight");
+        assert_eq!(packet["long_term"]["facts"][0]["value"], "Lisbon");
+
+        let unknown = json!({"kind": "", "payload": {}});
+        let (status, error) = call(&app, "POST", &format!("{RUN}/events"), Some(unknown)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].is_string());
+    }
+
+    /// Sends `body` as CBOR, asking for CBOR back; returns the status and raw body.
+    async fn call_cbor(app: &Router, method: &str, uri: &str, body: Value) -> (StatusCode, Bytes) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", CBOR_MEDIA_TYPE)
+            .header("accept", "application/json;q=0.5, application/cbor")
+            .body(Body::from(encode_wire(&body, WireFormat::Cbor).unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let content_type = response.headers()[CONTENT_TYPE].clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        if status.is_success() {
+            assert_eq!(content_type, CBOR_MEDIA_TYPE);
+        }
+        (status, bytes)
+    }
+
+    #[tokio::test]
+    async fn negotiates_cbor_for_events_and_packets() {
+        let app = router(Arc::new(InMemoryStore::new()));
+
+        let message = json!({"kind": "message", "payload": {"role": "user", "content": "hi"}});
+        let (status, created) = call_cbor(&app, "POST", &format!("{RUN}/events"), message).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created: Value = decode_wire(&created, WireFormat::Cbor).unwrap();
+        let (status, events) = call_cbor(&app, "GET", &format!("{RUN}/events"), Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let events: Vec<Event> = decode_wire(&events, WireFormat::Cbor).unwrap();
+        assert_eq!(events[0].event_id, created["event_id"]);
+        // Without CBOR in `Accept` the same route answers in JSON.
+        let (_, events) = call(&app, "GET", &format!("{RUN}/events"), None).await;
+        assert_eq!(events[0]["event_id"], created["event_id"]);
+
+        let request = json!({"purpose": "responder", "persist": false});
+        let (status, packet) =
+            call_cbor(&app, "POST", &format!("{RUN}/memory-packet"), request).await;
+        assert_eq!(status, StatusCode::OK);
+        let packet: MemoryPacket = decode_wire(&packet, WireFormat::Cbor).unwrap();
+        assert!(matches!(packet.meta.purpose, Purpose::Responder));
+        assert_eq!(packet.meta.scope.run_id, "r1");
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("{RUN}/memory-packet"))
+            .header("content-type", CBOR_MEDIA_TYPE)
+            .body(Body::from("not cbor"))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn records_the_correlation_id_of_writes() {
+        let store = Arc::new(ChangeLogStore::new(InMemoryStore::new()));
+        let app = router(store.clone());
+        let fact = json!({"fact_key": "user.city", "value": "Lisbon"});
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("{AGENT}/facts"))
+            .header("content-type", "application/json")
+            .header(CORRELATION_HEADER, "req-42")
+            .body(Body::from(fact.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CORRELATION_HEADER], "req-42");
+
+        let changes = store.list_changes(0, None).unwrap();
+        assert_eq!(changes[0].correlation_id.as_deref(), Some("req-42"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Bearer tokens bound to a scope prefix. With [`ApiTokens`] configured, every REST
+//! route and RPC needs an `authorization: Bearer <token>` header naming a token whose
+//! [`TokenGrant`] covers the request's scope, checked before the store is called, so
+//! a leaked token only exposes its own tenant, user or agent.
+
+use std::collections::HashMap;
+
+use engram_types::Scope;
+use serde::{Deserialize, Serialize};
+
+/// The scope prefix a token may act in: a tenant, optionally narrowed to one user
+/// and one agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenGrant {
+    pub tenant_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+}
+
+impl TokenGrant {
+    pub fn tenant(tenant_id: impl Into<String>) -> Self {
+        Self {
+            tenant_id: tenant_id.into(),
+            user_id: None,
+            agent_id: None,
+        }
+    }
+
+    pub fn user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    /// Whether `scope` lies under the grant's tenant, user and agent.
+    pub fn allows(&self, scope: &Scope) -> bool {
+        scope.tenant_id == self.tenant_id
+            && self.user_id.as_ref().is_none_or(|user_id| *user_id == scope.user_id)
+            && self.agent_id.as_ref().is_none_or(|agent_id| *agent_id == scope.agent_id)
+    }
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    /// No bearer token, or one that is not configured.
+    Unauthenticated,
+    /// The token is valid but its grant does not cover the scope.
+    Forbidden,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Unauthenticated => write!(f, "missing or unknown bearer token"),
+            AuthError::Forbidden => write!(f, "token is not allowed to access this scope"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Configured tokens, read from JSON of the form
+/// `{"<token>": {"tenant_id": "acme", "user_id": "u1"?, "agent_id": "a1"?}}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApiTokens {
+    grants: HashMap<String, TokenGrant>,
+}
+
+impl ApiTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn grant(mut self, token: impl Into<String>, grant: TokenGrant) -> Self {
+        self.grants.insert(token.into(), grant);
+        self
+    }
+
+    pub fn from_json(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+
+    /// Checks an `authorization` header value against the tokens and `scope`.
+    pub fn authorize(&self, header: Option<&str>, scope: &Scope) -> Result<(), AuthError> {
+        let token = header.and_then(|value| value.strip_prefix("Bearer "));
+        let grant = token.and_then(|token| self.grants.get(token.trim()));
+        match grant {
+            None => Err(AuthError::Unauthenticated),
+            Some(grant) if grant.allows(scope) => Ok(()),
+            Some(_) => Err(AuthError::Forbidden),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api;
+    use crate::grpc::proto::memory_server::Memory;
+    use crate::grpc::{proto, MemoryService};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use engram_store::InMemoryStore;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn tokens_only_reach_their_scope() {
+        let tokens = ApiTokens::new()
+            .grant("acme-key", TokenGrant::tenant("acme"))
+            .grant("bob-key", TokenGrant::tenant("acme").user("bob").agent("helper"));
+        let app = api::router_with_tokens(Arc::new(InMemoryStore::new()), tokens);
+        let call = |uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {token}"));
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        let bob = "/v1/tenants/acme/users/bob/agents/helper/facts";
+        let alice = "/v1/tenants/acme/users/alice/agents/helper/facts";
+        let other = "/v1/tenants/globex/users/bob/agents/helper/facts";
+        assert_eq!(call(bob, None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(call(bob, Some("stolen")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(call(bob, Some("bob-key")).await, StatusCode::OK);
+        assert_eq!(call(alice, Some("bob-key")).await, StatusCode::FORBIDDEN);
+        assert_eq!(call(alice, Some("acme-key")).await, StatusCode::OK);
+        assert_eq!(call(other, Some("acme-key")).await, StatusCode::FORBIDDEN);
+        let run = "/v1/tenants/acme/users/alice/agents/helper/sessions/s1/runs/r1/stm";
+        assert_eq!(call(run, Some("bob-key")).await, StatusCode::FORBIDDEN);
+
+        // gRPC checks the scope in the message the same way.
+        let tokens = ApiTokens::from_json(r#"{"k": {"tenant_id": "acme", "user_id": "bob"}}"#);
+        let service = MemoryService::new(Arc::new(InMemoryStore::new()));
+        let service = service.with_tokens(tokens.unwrap());
+        let rpc = |user_id: &str| {
+            let scope = proto::Scope {
+                tenant_id: "acme".to_string(),
+                user_id: user_id.to_string(),
+                agent_id: "any".to_string(),
+                session_id: "s1".to_string(),
+                run_id: "r1".to_string(),
+            };
+            let mut request = tonic::Request::new(proto::ScopeRequest { scope: Some(scope) });
+            request.metadata_mut().insert("authorization", "Bearer k".parse().unwrap());
+            request
+        };
+        let code = |result: Result<_, tonic::Status>| result.map(|_| ()).unwrap_err().code();
+        assert_eq!(code(service.get_stm(rpc("bob")).await), tonic::Code::NotFound);
+        assert_eq!(code(service.get_stm(rpc("alice")).await), tonic::Code::PermissionDenied);
+    }
+}
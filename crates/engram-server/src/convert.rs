@@ -0,0 +1,426 @@
+//! Conversions between the engram types and the generated protobuf messages. Input
+//! that does not parse fails with `INVALID_ARGUMENT`, naming the field.
+
+use chrono::{DateTime, Utc};
+use engram_store::{Event, StmState};
+use engram_types::{
+    Citation, Episode, EvidenceRef, Fact, InsightItem, JsonMap, KeyQuote, MemoryPacket,
+    Procedure, Scope, TimeRange, Validity, WorkingState, new_ulid,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tonic::Status;
+
+use crate::grpc::proto;
+
+type ConvertResult<T> = Result<T, Status>;
+
+pub fn timestamp(field: &str, value: &str) -> ConvertResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.with_timezone(&Utc))
+        .map_err(|err| invalid(field, err))
+}
+
+pub fn optional_timestamp(
+    field: &str,
+    value: Option<String>,
+) -> ConvertResult<Option<DateTime<Utc>>> {
+    value.map(|value| timestamp(field, &value)).transpose()
+}
+
+/// Parses an enum from its snake_case wire name.
+pub fn enum_value<T: DeserializeOwned>(field: &str, name: &str) -> ConvertResult<T> {
+    serde_json::from_value(Value::String(name.to_string())).map_err(|err| invalid(field, err))
+}
+
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// Parses a `*_json` field; an empty string is `null`.
+pub fn json(field: &str, text: &str) -> ConvertResult<Value> {
+    if text.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(text).map_err(|err| invalid(field, err))
+}
+
+/// Parses a `*_json` field holding an object; an empty string is an empty object.
+pub fn json_map(field: &str, text: &str) -> ConvertResult<JsonMap> {
+    if text.is_empty() {
+        return Ok(JsonMap::new());
+    }
+    serde_json::from_str(text).map_err(|err| invalid(field, err))
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn invalid(field: &str, err: impl std::fmt::Display) -> Status {
+    Status::invalid_argument(format!("{}: {}", field, err))
+}
+
+fn non_empty_or(value: String, default: impl FnOnce() -> String) -> String {
+    if value.is_empty() { default() } else { value }
+}
+
+pub fn scope(scope: Option<proto::Scope>) -> ConvertResult<Scope> {
+    let scope = scope.ok_or_else(|| Status::invalid_argument("scope is required"))?;
+    Ok(Scope {
+        tenant_id: non_empty_or(scope.tenant_id, || "default".to_string()),
+        user_id: scope.user_id,
+        agent_id: scope.agent_id,
+        session_id: scope.session_id,
+        run_id: scope.run_id,
+    })
+}
+
+impl From<Scope> for proto::Scope {
+    fn from(scope: Scope) -> Self {
+        Self {
+            tenant_id: scope.tenant_id,
+            user_id: scope.user_id,
+            agent_id: scope.agent_id,
+            session_id: scope.session_id,
+            run_id: scope.run_id,
+        }
+    }
+}
+
+impl From<Event> for proto::Event {
+    fn from(event: Event) -> Self {
+        Self {
+            event_id: event.event_id,
+            scope: Some(event.scope.into()),
+            ts: event.ts.to_rfc3339(),
+            kind: event.kind.to_string(),
+            payload_json: to_json(&event.payload),
+            tags: event.tags,
+            entities: event.entities,
+            seq: event.seq,
+            lang: event.lang,
+        }
+    }
+}
+
+impl TryFrom<proto::Event> for Event {
+    type Error = Status;
+
+    fn try_from(event: proto::Event) -> ConvertResult<Self> {
+        let kind = event.kind.parse().map_err(|err| invalid("kind", err))?;
+        let payload = json("payload_json", &event.payload_json)?;
+        let mut out = Event::new(scope(event.scope)?, kind, payload);
+        out.event_id = non_empty_or(event.event_id, new_ulid);
+        if !event.ts.is_empty() {
+            out.ts = timestamp("ts", &event.ts)?;
+        }
+        out.tags = event.tags;
+        out.entities = event.entities;
+        out.lang = event.lang;
+        Ok(out)
+    }
+}
+
+impl From<Fact> for proto::Fact {
+    fn from(fact: Fact) -> Self {
+        Self {
+            fact_id: fact.fact_id,
+            fact_key: fact.fact_key,
+            value_json: to_json(&fact.value),
+            status: enum_name(&fact.status),
+            valid_from: fact.validity.valid_from.map(|ts| ts.to_rfc3339()),
+            valid_to: fact.validity.valid_to.map(|ts| ts.to_rfc3339()),
+            confidence: fact.confidence,
+            sources: fact.sources,
+            scope_level: enum_name(&fact.scope_level),
+            notes: fact.notes,
+            pinned: fact.pinned,
+            lang: fact.lang,
+        }
+    }
+}
+
+impl TryFrom<proto::Fact> for Fact {
+    type Error = Status;
+
+    /// Empty enum fields and a zero confidence take the defaults of [`Fact::new`].
+    fn try_from(fact: proto::Fact) -> ConvertResult<Self> {
+        let defaults = Fact::new(fact.fact_key, json("value_json", &fact.value_json)?);
+        Ok(Fact {
+            fact_id: non_empty_or(fact.fact_id, || defaults.fact_id.clone()),
+            status: match fact.status.as_str() {
+                "" => defaults.status,
+                status => enum_value("status", status)?,
+            },
+            validity: Validity {
+                valid_from: optional_timestamp("valid_from", fact.valid_from)?,
+                valid_to: optional_timestamp("valid_to", fact.valid_to)?,
+            },
+            confidence: if fact.confidence == 0.0 { defaults.confidence } else { fact.confidence },
+            sources: fact.sources,
+            scope_level: match fact.scope_level.as_str() {
+                "" => defaults.scope_level,
+                level => enum_value("scope_level", level)?,
+            },
+            notes: fact.notes,
+            pinned: fact.pinned,
+            lang: fact.lang,
+            ..defaults
+        })
+    }
+}
+
+impl From<Episode> for proto::Episode {
+    fn from(episode: Episode) -> Self {
+        Self {
+            episode_id: episode.episode_id,
+            start: episode.time_range.start.to_rfc3339(),
+            end: episode.time_range.end.map(|ts| ts.to_rfc3339()),
+            summary: episode.summary,
+            highlights: episode.highlights,
+            tags: episode.tags,
+            entities: episode.entities,
+            sources: episode.sources,
+            compression_level: enum_name(&episode.compression_level),
+            recency_score: episode.recency_score,
+            lang: episode.lang,
+        }
+    }
+}
+
+impl TryFrom<proto::Episode> for Episode {
+    type Error = Status;
+
+    fn try_from(episode: proto::Episode) -> ConvertResult<Self> {
+        let defaults = Episode::new(episode.summary);
+        Ok(Episode {
+            episode_id: non_empty_or(episode.episode_id, || defaults.episode_id.clone()),
+            time_range: TimeRange {
+                start: match episode.start.as_str() {
+                    "" => defaults.time_range.start,
+                    start => timestamp("start", start)?,
+                },
+                end: optional_timestamp("end", episode.end)?,
+            },
+            highlights: episode.highlights,
+            tags: episode.tags,
+            entities: episode.entities,
+            sources: episode.sources,
+            compression_level: match episode.compression_level.as_str() {
+                "" => defaults.compression_level.clone(),
+                level => enum_value("compression_level", level)?,
+            },
+            recency_score: episode.recency_score,
+            lang: episode.lang,
+            ..defaults
+        })
+    }
+}
+
+impl From<Procedure> for proto::Procedure {
+    fn from(procedure: Procedure) -> Self {
+        Self {
+            procedure_id: procedure.procedure_id,
+            task_type: procedure.task_type,
+            content_json: to_json(&procedure.content),
+            priority: procedure.priority,
+            sources: procedure.sources,
+            applicability_json: to_json(&procedure.applicability),
+        }
+    }
+}
+
+impl TryFrom<proto::Procedure> for Procedure {
+    type Error = Status;
+
+    fn try_from(procedure: proto::Procedure) -> ConvertResult<Self> {
+        let content = json("content_json", &procedure.content_json)?;
+        let defaults = Procedure::new(procedure.task_type, content);
+        Ok(Procedure {
+            procedure_id: non_empty_or(procedure.procedure_id, || defaults.procedure_id.clone()),
+            priority: procedure.priority,
+            sources: procedure.sources,
+            applicability: json_map("applicability_json", &procedure.applicability_json)?,
+            ..defaults
+        })
+    }
+}
+
+impl From<InsightItem> for proto::InsightItem {
+    fn from(insight: InsightItem) -> Self {
+        Self {
+            id: insight.id,
+            r#type: enum_name(&insight.kind),
+            statement: insight.statement,
+            trigger: enum_name(&insight.trigger),
+            confidence: insight.confidence,
+            validation_state: enum_name(&insight.validation_state),
+            tests_suggested: insight.tests_suggested,
+            expires_at: insight.expires_at,
+            sources: insight.sources,
+            parent_insight_id: insight.parent_insight_id,
+        }
+    }
+}
+
+impl TryFrom<proto::InsightItem> for InsightItem {
+    type Error = Status;
+
+    /// Goes through the JSON form so omitted fields take its serde defaults.
+    fn try_from(insight: proto::InsightItem) -> ConvertResult<Self> {
+        let mut value = serde_json::json!({
+            "type": insight.r#type,
+            "statement": insight.statement,
+            "tests_suggested": insight.tests_suggested,
+            "expires_at": insight.expires_at,
+            "sources": insight.sources,
+            "parent_insight_id": insight.parent_insight_id,
+        });
+        let optional = [
+            ("id", Value::from(insight.id)),
+            ("trigger", Value::from(insight.trigger)),
+            ("validation_state", Value::from(insight.validation_state)),
+        ];
+        for (key, field) in optional {
+            if field.as_str() != Some("") {
+                value[key] = field;
+            }
+        }
+        if insight.confidence != 0.0 {
+            value["confidence"] = Value::from(insight.confidence);
+        }
+        serde_json::from_value(value).map_err(|err| invalid("insight", err))
+    }
+}
+
+impl From<EvidenceRef> for proto::EvidenceRef {
+    fn from(evidence: EvidenceRef) -> Self {
+        Self {
+            evidence_id: evidence.evidence_id,
+            summary: evidence.summary,
+            kind: evidence.kind,
+        }
+    }
+}
+
+impl From<proto::EvidenceRef> for EvidenceRef {
+    fn from(evidence: proto::EvidenceRef) -> Self {
+        Self {
+            evidence_id: evidence.evidence_id,
+            summary: evidence.summary,
+            kind: evidence.kind,
+        }
+    }
+}
+
+impl From<WorkingState> for proto::WorkingState {
+    fn from(state: WorkingState) -> Self {
+        Self {
+            goal: state.goal,
+            plan: state.plan,
+            slots_json: to_json(&state.slots),
+            constraints_json: to_json(&state.constraints),
+            tool_evidence: state.tool_evidence.into_iter().map(Into::into).collect(),
+            decisions: state.decisions,
+            risks: state.risks,
+            state_version: state.state_version,
+        }
+    }
+}
+
+impl From<KeyQuote> for proto::KeyQuote {
+    fn from(quote: KeyQuote) -> Self {
+        Self {
+            evidence_id: quote.evidence_id,
+            quote: quote.quote,
+            role: enum_name(&quote.role),
+            ts: quote.ts.map(|ts| ts.to_rfc3339()),
+        }
+    }
+}
+
+impl TryFrom<proto::KeyQuote> for KeyQuote {
+    type Error = Status;
+
+    fn try_from(quote: proto::KeyQuote) -> ConvertResult<Self> {
+        Ok(KeyQuote {
+            evidence_id: quote.evidence_id,
+            quote: quote.quote,
+            role: enum_value("role", non_empty_or(quote.role, || "user".to_string()).as_str())?,
+            ts: optional_timestamp("ts", quote.ts)?,
+        })
+    }
+}
+
+impl From<StmState> for proto::StmState {
+    fn from(stm: StmState) -> Self {
+        Self {
+            rolling_summary: stm.rolling_summary,
+            key_quotes: stm.key_quotes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::StmState> for StmState {
+    type Error = Status;
+
+    fn try_from(stm: proto::StmState) -> ConvertResult<Self> {
+        Ok(StmState {
+            rolling_summary: stm.rolling_summary,
+            key_quotes: stm
+                .key_quotes
+                .into_iter()
+                .map(KeyQuote::try_from)
+                .collect::<ConvertResult<_>>()?,
+        })
+    }
+}
+
+impl From<Citation> for proto::Citation {
+    fn from(citation: Citation) -> Self {
+        Self {
+            id: citation.id,
+            r#type: enum_name(&citation.kind),
+            ts: citation.ts.map(|ts| ts.to_rfc3339()),
+            summary: citation.summary,
+        }
+    }
+}
+
+impl From<MemoryPacket> for proto::MemoryPacket {
+    fn from(packet: MemoryPacket) -> Self {
+        let packet_json = to_json(&packet);
+        let meta = packet.meta;
+        let long_term = packet.long_term;
+        Self {
+            meta: Some(proto::PacketMeta {
+                schema_version: meta.schema_version,
+                scope: Some(meta.scope.into()),
+                generated_at: meta.generated_at.to_rfc3339(),
+                purpose: enum_name(&meta.purpose),
+                task_type: meta.task_type,
+                policy_id: meta.policy_id,
+                timezone: meta.timezone,
+                locale: meta.locale,
+            }),
+            working_state: Some(packet.short_term.working_state.into()),
+            rolling_summary: packet.short_term.rolling_summary,
+            open_loops: packet.short_term.open_loops,
+            facts: long_term.facts.into_iter().map(Into::into).collect(),
+            preferences: long_term.preferences.into_iter().map(Into::into).collect(),
+            procedures: long_term.procedures.into_iter().map(Into::into).collect(),
+            episodes: long_term.episodes.into_iter().map(Into::into).collect(),
+            citations: packet.citations.into_iter().map(Into::into).collect(),
+            budget_report: Some(proto::BudgetReport {
+                max_tokens: packet.budget_report.max_tokens,
+                used_tokens_est: packet.budget_report.used_tokens_est,
+            }),
+            packet_json,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use engram_store::StoreError;
+use serde_json::json;
+
+use crate::auth::AuthError;
+
+/// A failed request, rendered as `{"error": "..."}` with a matching status code.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(err: impl std::fmt::Display) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(err: AuthError) -> Self {
+        let status = match err {
+            AuthError::Unauthenticated => StatusCode::UNAUTHORIZED,
+            AuthError::Forbidden => StatusCode::FORBIDDEN,
+        };
+        Self {
+            status,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(err: StoreError) -> Self {
+        let status = match &err {
+            StoreError::NotFound => StatusCode::NOT_FOUND,
+            StoreError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            StoreError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            StoreError::Unavailable(_) | StoreError::Overloaded(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            StoreError::VersionConflict { .. } | StoreError::FactConflict { .. } => {
+                StatusCode::CONFLICT
+            }
+            StoreError::Poisoned | StoreError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            tracing::warn!("request failed: {}", self.message);
+        }
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
@@ -0,0 +1,460 @@
+//! gRPC service over a [`Store`], described by `proto/engram.proto`. The generated
+//! client and server stubs are in [`proto`]; Go or TypeScript clients can be generated
+//! from the same file.
+
+use std::sync::Arc;
+
+use engram_store::{
+    build_memory_packet, with_correlation_id, BuildRequest, EpisodeFilter, Event, FactFilter,
+    InsightFilter, RecallCues, StmState, Store, StoreError, StoreResult, TimeRangeFilter,
+    WorkingStatePatch,
+};
+use engram_types::{Episode, EvidenceRef, Fact, InsightItem, Procedure, Scope};
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status};
+
+use crate::api::CORRELATION_HEADER;
+use crate::auth::{ApiTokens, AuthError};
+use crate::convert;
+
+pub mod proto {
+    tonic::include_proto!("engram.v1");
+}
+
+use proto::memory_server::{Memory, MemoryServer};
+
+type RpcResult<T> = Result<Response<T>, Status>;
+
+/// Serves a store through the generated [`MemoryServer`].
+pub struct MemoryService {
+    store: Arc<dyn Store>,
+    tokens: Option<ApiTokens>,
+}
+
+impl MemoryService {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self {
+            store,
+            tokens: None,
+        }
+    }
+
+    /// Requires every RPC to carry an `authorization: Bearer <token>` header whose
+    /// grant covers the request's scope; refused calls never reach the store.
+    pub fn with_tokens(mut self, tokens: ApiTokens) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    pub fn into_server(self) -> MemoryServer<Self> {
+        MemoryServer::new(self)
+    }
+
+    /// Runs a store call off the async runtime; stores block on I/O. The call runs
+    /// under the request's `x-correlation-id`, if it sent one.
+    async fn blocking<T, F>(&self, metadata: &MetadataMap, call: F) -> Result<T, Status>
+    where
+        F: FnOnce(&dyn Store) -> StoreResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let store = self.store.clone();
+        let correlation_id = metadata
+            .get(CORRELATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        tokio::task::spawn_blocking(move || match correlation_id {
+            Some(correlation_id) => with_correlation_id(&correlation_id, || call(store.as_ref())),
+            None => call(store.as_ref()),
+        })
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?
+        .map_err(status)
+    }
+
+    fn authorize(&self, metadata: &MetadataMap, scope: &Scope) -> Result<(), Status> {
+        let Some(tokens) = &self.tokens else {
+            return Ok(());
+        };
+        let header = metadata.get("authorization").and_then(|value| value.to_str().ok());
+        tokens.authorize(header, scope).map_err(|err| match err {
+            AuthError::Unauthenticated => Status::unauthenticated(err.to_string()),
+            AuthError::Forbidden => Status::permission_denied(err.to_string()),
+        })
+    }
+
+    /// Reads a request's scope, refusing it unless the caller may act in it.
+    fn scope(&self, metadata: &MetadataMap, scope: Option<proto::Scope>) -> Result<Scope, Status> {
+        let scope = convert::scope(scope)?;
+        self.authorize(metadata, &scope)?;
+        Ok(scope)
+    }
+}
+
+/// Maps store errors to gRPC codes, as the REST API maps them to HTTP statuses.
+pub fn status(err: StoreError) -> Status {
+    let message = err.to_string();
+    match err {
+        StoreError::NotFound => Status::not_found(message),
+        StoreError::InvalidInput(_) => Status::invalid_argument(message),
+        StoreError::Timeout(_) => Status::deadline_exceeded(message),
+        StoreError::Unavailable(_) | StoreError::Overloaded(_) => Status::unavailable(message),
+        StoreError::VersionConflict { .. } => Status::aborted(message),
+        StoreError::FactConflict { .. } => Status::failed_precondition(message),
+        StoreError::Poisoned | StoreError::Storage(_) => {
+            tracing::warn!("request failed: {}", message);
+            Status::internal(message)
+        }
+    }
+}
+
+fn limit(limit: Option<u32>) -> Option<usize> {
+    limit.map(|limit| limit as usize)
+}
+
+#[tonic::async_trait]
+impl Memory for MemoryService {
+    async fn append_event(
+        &self,
+        request: Request<proto::AppendEventRequest>,
+    ) -> RpcResult<proto::AppendEventResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let event = request.event.ok_or_else(|| Status::invalid_argument("event is required"))?;
+        let event = Event::try_from(event)?;
+        self.authorize(&metadata, &event.scope)?;
+        let event_id = event.event_id.clone();
+        self.blocking(&metadata, move |store| store.append_event(event)).await?;
+        Ok(Response::new(proto::AppendEventResponse { event_id }))
+    }
+
+    async fn list_events(
+        &self,
+        request: Request<proto::ListEventsRequest>,
+    ) -> RpcResult<proto::ListEventsResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let range = TimeRangeFilter {
+            start: convert::optional_timestamp("start", request.start)?,
+            end: convert::optional_timestamp("end", request.end)?,
+        };
+        let limit = limit(request.limit);
+        let events = self
+            .blocking(&metadata, move |store| store.list_events(&scope, range, limit))
+            .await?;
+        Ok(Response::new(proto::ListEventsResponse {
+            events: events.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_working_state(
+        &self,
+        request: Request<proto::ScopeRequest>,
+    ) -> RpcResult<proto::WorkingState> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let state = self.blocking(&metadata, move |store| store.get_working_state(&scope)).await?;
+        let state = state.ok_or_else(|| status(StoreError::NotFound))?;
+        Ok(Response::new(state.into()))
+    }
+
+    async fn patch_working_state(
+        &self,
+        request: Request<proto::PatchWorkingStateRequest>,
+    ) -> RpcResult<proto::WorkingState> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let patch = WorkingStatePatch {
+            goal: request.goal,
+            plan: request.plan.map(|list| list.items),
+            slots: request
+                .slots_json
+                .map(|text| convert::json_map("slots_json", &text))
+                .transpose()?,
+            constraints: request
+                .constraints_json
+                .map(|text| convert::json_map("constraints_json", &text))
+                .transpose()?,
+            tool_evidence: request
+                .tool_evidence
+                .map(|list| list.items.into_iter().map(EvidenceRef::from).collect()),
+            decisions: request.decisions.map(|list| list.items),
+            risks: request.risks.map(|list| list.items),
+            state_version: request.state_version,
+            expected_state_version: request.expected_state_version,
+            clock: None,
+            task_type: None,
+        };
+        let state = self
+            .blocking(&metadata, move |store| store.patch_working_state(&scope, patch))
+            .await?;
+        Ok(Response::new(state.into()))
+    }
+
+    async fn get_stm(&self, request: Request<proto::ScopeRequest>) -> RpcResult<proto::StmState> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let stm = self.blocking(&metadata, move |store| store.get_stm(&scope)).await?;
+        let stm = stm.ok_or_else(|| status(StoreError::NotFound))?;
+        Ok(Response::new(stm.into()))
+    }
+
+    async fn update_stm(
+        &self,
+        request: Request<proto::UpdateStmRequest>,
+    ) -> RpcResult<proto::Empty> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let stm = StmState::try_from(request.stm.unwrap_or_default())?;
+        self.blocking(&metadata, move |store| store.update_stm(&scope, stm)).await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn list_facts(
+        &self,
+        request: Request<proto::ListFactsRequest>,
+    ) -> RpcResult<proto::ListFactsResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let statuses = request
+            .status
+            .iter()
+            .map(|status| convert::enum_value("status", status))
+            .collect::<Result<Vec<_>, Status>>()?;
+        let filter = FactFilter {
+            status: (!statuses.is_empty()).then_some(statuses),
+            include_shared: request.include_shared,
+            limit: limit(request.limit),
+            ..FactFilter::default()
+        };
+        let facts = self.blocking(&metadata, move |store| store.list_facts(&scope, filter)).await?;
+        Ok(Response::new(proto::ListFactsResponse {
+            facts: facts.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn upsert_fact(
+        &self,
+        request: Request<proto::UpsertFactRequest>,
+    ) -> RpcResult<proto::Fact> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let fact = request.fact.ok_or_else(|| Status::invalid_argument("fact is required"))?;
+        let fact = Fact::try_from(fact)?;
+        let stored = fact.clone();
+        self.blocking(&metadata, move |store| store.upsert_fact(&scope, stored)).await?;
+        Ok(Response::new(fact.into()))
+    }
+
+    async fn list_episodes(
+        &self,
+        request: Request<proto::ListEpisodesRequest>,
+    ) -> RpcResult<proto::ListEpisodesResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let filter = EpisodeFilter {
+            tags: request.tags,
+            limit: limit(request.limit),
+            ..EpisodeFilter::default()
+        };
+        let episodes = self
+            .blocking(&metadata, move |store| store.list_episodes(&scope, filter))
+            .await?;
+        Ok(Response::new(proto::ListEpisodesResponse {
+            episodes: episodes.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn append_episode(
+        &self,
+        request: Request<proto::AppendEpisodeRequest>,
+    ) -> RpcResult<proto::Episode> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let episode = request
+            .episode
+            .ok_or_else(|| Status::invalid_argument("episode is required"))?;
+        let episode = Episode::try_from(episode)?;
+        let stored = episode.clone();
+        self.blocking(&metadata, move |store| store.append_episode(&scope, stored)).await?;
+        Ok(Response::new(episode.into()))
+    }
+
+    async fn list_procedures(
+        &self,
+        request: Request<proto::ListProceduresRequest>,
+    ) -> RpcResult<proto::ListProceduresResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let task_type = request.task_type;
+        let limit = limit(request.limit);
+        let procedures = self
+            .blocking(&metadata, move |store| store.list_procedures(&scope, &task_type, limit))
+            .await?;
+        Ok(Response::new(proto::ListProceduresResponse {
+            procedures: procedures.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn upsert_procedure(
+        &self,
+        request: Request<proto::UpsertProcedureRequest>,
+    ) -> RpcResult<proto::Procedure> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let procedure = request
+            .procedure
+            .ok_or_else(|| Status::invalid_argument("procedure is required"))?;
+        let procedure = Procedure::try_from(procedure)?;
+        let stored = procedure.clone();
+        self.blocking(&metadata, move |store| store.upsert_procedure(&scope, stored)).await?;
+        Ok(Response::new(procedure.into()))
+    }
+
+    async fn list_insights(
+        &self,
+        request: Request<proto::ListInsightsRequest>,
+    ) -> RpcResult<proto::ListInsightsResponse> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let filter = InsightFilter {
+            limit: limit(request.limit),
+            ..InsightFilter::default()
+        };
+        let insights = self
+            .blocking(&metadata, move |store| store.list_insights(&scope, filter))
+            .await?;
+        Ok(Response::new(proto::ListInsightsResponse {
+            insights: insights.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn append_insight(
+        &self,
+        request: Request<proto::AppendInsightRequest>,
+    ) -> RpcResult<proto::InsightItem> {
+        let (metadata, _, request) = request.into_parts();
+        let scope = self.scope(&metadata, request.scope)?;
+        let insight = request
+            .insight
+            .ok_or_else(|| Status::invalid_argument("insight is required"))?;
+        let insight = InsightItem::try_from(insight)?;
+        let stored = insight.clone();
+        self.blocking(&metadata, move |store| store.append_insight(&scope, stored)).await?;
+        Ok(Response::new(insight.into()))
+    }
+
+    async fn build_memory_packet(
+        &self,
+        request: Request<proto::BuildMemoryPacketRequest>,
+    ) -> RpcResult<proto::MemoryPacket> {
+        let (metadata, _, request) = request.into_parts();
+        let purpose = convert::enum_value("purpose", &request.purpose)?;
+        let mut build = BuildRequest::new(self.scope(&metadata, request.scope)?, purpose);
+        build.task_type = request.task_type;
+        if let Some(cues) = request.cues {
+            build.cues = RecallCues {
+                tags: cues.tags,
+                entities: cues.entities,
+                keywords: cues.keywords,
+                lang: cues.lang,
+                valid_at: convert::optional_timestamp("valid_at", cues.valid_at)?,
+                ..RecallCues::default()
+            };
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            build.budget.max_tokens = max_tokens;
+        }
+        if let Some(persist) = request.persist {
+            build.persist = persist;
+        }
+        let packet = self
+            .blocking(&metadata, move |store| build_memory_packet(store, build))
+            .await?;
+        Ok(Response::new(packet.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_store::InMemoryStore;
+    use proto::memory_client::MemoryClient;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    #[tokio::test]
+    async fn serves_the_store_over_grpc() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let service = MemoryService::new(Arc::new(InMemoryStore::new())).into_server();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let mut client = MemoryClient::connect(format!("http://{}", addr)).await.unwrap();
+        let scope = proto::Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+
+        let event = proto::Event {
+            scope: Some(scope.clone()),
+            kind: "message".to_string(),
+            payload_json: r#"{"text": "I moved to Lisbon"}"#.to_string(),
+            ..proto::Event::default()
+        };
+        client
+            .append_event(proto::AppendEventRequest { event: Some(event) })
+            .await
+            .unwrap();
+        let fact = proto::Fact {
+            fact_key: "user.city".to_string(),
+            value_json: r#""Lisbon""#.to_string(),
+            ..proto::Fact::default()
+        };
+        let stored = client
+            .upsert_fact(proto::UpsertFactRequest {
+                scope: Some(scope.clone()),
+                fact: Some(fact),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(stored.status, "active");
+
+        let packet = client
+            .build_memory_packet(proto::BuildMemoryPacketRequest {
+                scope: Some(scope.clone()),
+                purpose: "responder".to_string(),
+                ..proto::BuildMemoryPacketRequest::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(packet.facts.len(), 1);
+        assert_eq!(packet.facts[0].fact_key, "user.city");
+        assert!(packet.packet_json.contains("Lisbon"));
+
+        let missing = client
+            .get_working_state(proto::ScopeRequest {
+                scope: Some(proto::Scope {
+                    run_id: "other".to_string(),
+                    ..scope
+                }),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+        let invalid = client
+            .build_memory_packet(proto::BuildMemoryPacketRequest {
+                purpose: "nobody".to_string(),
+                ..proto::BuildMemoryPacketRequest::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,9 @@
+//! Network front ends for an engram store: a REST API ([`api`]) and a gRPC service
+//! ([`grpc`]), both served by the `engram-server` binary, optionally behind scoped
+//! bearer tokens ([`auth`]).
+
+pub mod api;
+pub mod auth;
+mod convert;
+mod error;
+pub mod grpc;
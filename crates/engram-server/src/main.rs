@@ -0,0 +1,132 @@
+//! HTTP server exposing an engram store as a REST API, for clients that cannot load
+//! the Python bindings.
+//!
+//! ```text
+//! engram-server [--listen ADDR] [--grpc ADDR] [--tokens PATH] [TARGET]
+//! ```
+//!
+//! `TARGET` is a SQLite path (default `data/engram.db`) or a `postgres://` /
+//! `mysql://` DSN when the matching feature is enabled. The REST API listens on
+//! `--listen` (default `127.0.0.1:8080`); `--grpc` also serves the gRPC service on
+//! another address. Routes and RPCs are listed in [`engram_server::api`] and
+//! `proto/engram.proto`. `--tokens` names a JSON file of scoped bearer tokens (see
+//! [`engram_server::auth`]) that both require.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use engram_server::api;
+use engram_server::auth::ApiTokens;
+use engram_server::grpc::MemoryService;
+use engram_store::{SqliteStore, Store, StoreResult};
+#[cfg(feature = "mysql")]
+use engram_store::MySqlStore;
+#[cfg(feature = "postgres")]
+use engram_store::PostgresStore;
+
+const USAGE: &str = "usage: engram-server [--listen ADDR] [--grpc ADDR] [--tokens PATH] \
+                     [SQLITE_PATH | postgres://... | mysql://...]";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt::init();
+
+    let mut listen = "127.0.0.1:8080".to_string();
+    let mut grpc = None;
+    let mut tokens = None;
+    let mut target = "data/engram.db".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().unwrap_or(listen),
+            "--grpc" => grpc = args.next(),
+            "--tokens" => tokens = args.next(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => target = arg,
+        }
+    }
+
+    let tokens = match tokens.as_deref().map(read_tokens).transpose() {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            eprintln!("engram-server: cannot read tokens: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let store = match open_store(&target) {
+        Ok(store) => store,
+        Err(err) => {
+            eprintln!("engram-server: cannot open {}: {}", target, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let listener = match tokio::net::TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("engram-server: cannot listen on {}: {}", listen, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(grpc) = grpc {
+        let addr = match grpc.parse() {
+            Ok(addr) => addr,
+            Err(err) => {
+                eprintln!("engram-server: invalid gRPC address {}: {}", grpc, err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let mut service = MemoryService::new(store.clone());
+        if let Some(tokens) = tokens.clone() {
+            service = service.with_tokens(tokens);
+        }
+        let service = service.into_server();
+        tracing::info!("serving gRPC on {}", grpc);
+        tokio::spawn(async move {
+            let served = tonic::transport::Server::builder().add_service(service).serve(addr);
+            if let Err(err) = served.await {
+                eprintln!("engram-server: gRPC: {}", err);
+            }
+        });
+    }
+    tracing::info!("serving {} on {}", target, listen);
+    let router = match tokens {
+        Some(tokens) => api::router_with_tokens(store, tokens),
+        None => api::router(store),
+    };
+    match axum::serve(listener, router).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("engram-server: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read_tokens(path: &str) -> Result<ApiTokens, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    ApiTokens::from_json(&text).map_err(|err| format!("{}: {}", path, err))
+}
+
+fn open_store(target: &str) -> StoreResult<Arc<dyn Store>> {
+    if target.starts_with("postgres://") || target.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Arc::new(PostgresStore::new(target)?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(engram_store::StoreError::InvalidInput(
+            "postgres feature not enabled".to_string(),
+        ));
+    }
+    if target.starts_with("mysql://") {
+        #[cfg(feature = "mysql")]
+        return Ok(Arc::new(MySqlStore::new(target)?));
+        #[cfg(not(feature = "mysql"))]
+        return Err(engram_store::StoreError::InvalidInput(
+            "mysql feature not enabled".to_string(),
+        ));
+    }
+    let path = target.strip_prefix("sqlite://").unwrap_or(target);
+    Ok(Arc::new(SqliteStore::new(path)?))
+}
@@ -10,9 +10,9 @@ use chrono::{Duration as ChronoDuration, Utc};
 use criterion::measurement::WallTime;
 use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
 use engram_store::{
-    build_memory_packet, BuildRequest, EpisodeFilter, Event, EventKind, FactFilter, InMemoryStore,
-    InsightFilter, RecallCues, RecallPolicy, SqliteStore, Store, StmState, TimeRangeFilter,
-    WorkingStatePatch,
+    build_memory_packet, BuildRequest, CueExpansion, EpisodeFilter, Event, EventKind, FactFilter,
+    InMemoryStore, InsightFilter, LangMode, PurposeFilter, RecallCues, RecallPolicy, SqliteStore,
+    StmState, Store, SummaryCondensing, TimeRangeFilter, WorkingStatePatch,
 };
 #[cfg(feature = "mysql")]
 use engram_store::MySqlStore;
@@ -121,20 +121,36 @@ fn main_request(scope: Scope) -> BuildRequest {
         entities: vec!["entity1".to_string()],
         keywords: vec!["engram".to_string()],
         time_range: None,
+        time_ranges: Vec::new(),
+        lang: None,
+        valid_at: None,
     };
     request.policy = RecallPolicy {
         max_total_candidates: 100,
         max_facts: 30,
+        max_preferences: 10,
         max_procedures: 5,
+        max_pinned_procedures: 10,
         max_episodes: 20,
         max_insights: 10,
         max_key_quotes: 10,
         conversation_window: 5,
+        conversation_window_tokens: 0,
         episode_time_window_days: 30,
+        max_daily_summaries: 0,
+        max_weekly_summaries: 0,
         last_tool_evidence_limit: 3,
-        include_conversation_window: false,
-        include_insights_in_tool: false,
-        allow_insights_in_responder: false,
+        max_quote_tokens: 120,
+        max_highlight_tokens: 60,
+        include_shared_facts: true,
+        outcome_weight: 0.0,
+        lang_mode: LangMode::Prefer,
+        filter: PurposeFilter::default(),
+        cue_expansion: CueExpansion::default(),
+        summary_condensing: SummaryCondensing::default(),
+        slot_schemas: None,
+        ranker: None,
+        external_context: Vec::new(),
     };
     request.persist = false;
     request
@@ -176,6 +192,8 @@ fn seed_store_common<S: Store + ?Sized>(store: &S, size: DatasetSize, scope: &Sc
             sources: vec!["e0".to_string()],
             scope_level: ScopeLevel::User,
             notes: String::new(),
+            pinned: false,
+            lang: None,
         };
         store.upsert_fact(scope, fact).unwrap();
     }
@@ -194,6 +212,7 @@ fn seed_store_common<S: Store + ?Sized>(store: &S, size: DatasetSize, scope: &Sc
             sources: vec!["e0".to_string()],
             compression_level: CompressionLevel::Raw,
             recency_score: None,
+            lang: None,
         };
         store.append_episode(scope, episode).unwrap();
     }
@@ -229,6 +248,7 @@ fn seed_store_common<S: Store + ?Sized>(store: &S, size: DatasetSize, scope: &Sc
             tests_suggested: vec![],
             expires_at: "run_end".to_string(),
             sources: vec![],
+            parent_insight_id: None,
         };
         store.append_insight(scope, insight).unwrap();
     }
@@ -245,6 +265,8 @@ fn seed_events_in_memory(store: &InMemoryStore, size: DatasetSize, scope: &Scope
             payload: json!({ "role": "user", "content": format!("message {}", idx) }),
             tags: vec![if idx % 2 == 0 { "alpha" } else { "beta" }.to_string()],
             entities: vec!["entity1".to_string()],
+            seq: 0,
+            lang: None,
         };
         store.append_event(event).unwrap();
     }
@@ -263,6 +285,8 @@ fn seed_events_sqlite(store: &SqliteStore, size: DatasetSize, scope: &Scope, pre
             payload: json!({ "role": "user", "content": format!("message {}", idx) }),
             tags: vec![if idx % 2 == 0 { "alpha" } else { "beta" }.to_string()],
             entities: vec!["entity1".to_string()],
+            seq: 0,
+            lang: None,
         });
         if buffer.len() >= chunk_size {
             store.append_events_bulk(&buffer).unwrap();
@@ -291,6 +315,8 @@ fn seed_events_store<S: Store + ?Sized>(
             payload: json!({ "role": "user", "content": format!("message {}", idx) }),
             tags: vec![if idx % 2 == 0 { "alpha" } else { "beta" }.to_string()],
             entities: vec!["entity1".to_string()],
+            seq: 0,
+            lang: None,
         };
         store.append_event(event).unwrap();
     }
@@ -1548,6 +1574,8 @@ fn build_write_event(scope: &Scope, event_id: String, ts: chrono::DateTime<Utc>)
         payload: json!({ "role": "user", "content": "bench write" }),
         tags: vec![],
         entities: vec![],
+        seq: 0,
+        lang: None,
     }
 }
 
@@ -1800,6 +1828,8 @@ fn bench_upsert_fact_in_memory(group: &mut BenchmarkGroup<'_, WallTime>, size: D
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 };
                 store.upsert_fact(&scope, fact).unwrap();
             })
@@ -1833,6 +1863,8 @@ fn bench_upsert_fact_sqlite(group: &mut BenchmarkGroup<'_, WallTime>, size: Data
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 };
                 store.upsert_fact(&scope, fact).unwrap();
             })
@@ -1869,6 +1901,8 @@ fn bench_upsert_fact_mysql(group: &mut BenchmarkGroup<'_, WallTime>, size: Datas
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 };
                 store.upsert_fact(&scope, fact).unwrap();
             })
@@ -1903,6 +1937,8 @@ fn bench_upsert_fact_postgres(group: &mut BenchmarkGroup<'_, WallTime>, size: Da
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 };
                 store.upsert_fact(&scope, fact).unwrap();
             })
@@ -2004,19 +2040,19 @@ fn is_extreme() -> bool {
 }
 
 fn max_in_memory_events() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_INMEMORY_MAX_EVENTS") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed;
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_INMEMORY_MAX_EVENTS")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed;
     }
     MAX_IN_MEMORY_EVENTS
 }
 
 fn max_sqlite_events() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_SQLITE_MAX_EVENTS") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed;
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_SQLITE_MAX_EVENTS")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed;
     }
     MAX_SQLITE_EVENTS
 }
@@ -2096,19 +2132,19 @@ fn cleanup_sqlite_file(path: Option<PathBuf>) {
 }
 
 fn sqlite_event_chunk() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_SQLITE_EVENT_CHUNK") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed.max(1);
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_SQLITE_EVENT_CHUNK")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed.max(1);
     }
     SQLITE_EVENT_CHUNK
 }
 
 fn bulk_event_batch_size() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_BULK_EVENT_BATCH") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed.max(1);
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_BULK_EVENT_BATCH")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed.max(1);
     }
     500
 }
@@ -2217,20 +2253,20 @@ fn postgres_dsn() -> Option<String> {
 
 #[cfg(feature = "mysql")]
 fn max_mysql_events() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_MYSQL_MAX_EVENTS") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed;
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_MYSQL_MAX_EVENTS")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed;
     }
     MAX_MYSQL_EVENTS
 }
 
 #[cfg(feature = "postgres")]
 fn max_postgres_events() -> usize {
-    if let Ok(value) = env::var("ENGRAM_BENCH_POSTGRES_MAX_EVENTS") {
-        if let Ok(parsed) = value.parse::<usize>() {
-            return parsed;
-        }
+    if let Ok(value) = env::var("ENGRAM_BENCH_POSTGRES_MAX_EVENTS")
+        && let Ok(parsed) = value.parse::<usize>()
+    {
+        return parsed;
     }
     MAX_POSTGRES_EVENTS
 }
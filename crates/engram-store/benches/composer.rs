@@ -0,0 +1,190 @@
+//! Composer CPU cost on pre-fetched candidates: episode ranking, citation dedup and
+//! budget trimming, with no storage reads inside the measured loop. Compare against
+//! `build_memory_packet` to separate composer regressions from backend ones.
+
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use engram_store::{
+    apply_budget, build_memory_packet, collect_citations, rank_episodes, BuildRequest,
+    EpisodeFilter, InMemoryStore, RecallPolicy, Store,
+};
+use engram_types::{
+    Budget, CompressionLevel, Episode, Fact, InsightItem, InsightType, JsonMap, KeyQuote,
+    MemoryPacket, Procedure, Purpose, Role, Scope, TimeRange,
+};
+use serde_json::json;
+
+#[derive(Clone, Copy, Debug)]
+struct CandidateSize {
+    facts: usize,
+    episodes: usize,
+    procedures: usize,
+    insights: usize,
+}
+
+impl CandidateSize {
+    fn label(&self) -> String {
+        format!(
+            "facts{}_episodes{}_procedures{}_insights{}",
+            self.facts, self.episodes, self.procedures, self.insights
+        )
+    }
+
+    fn total(&self) -> usize {
+        self.facts + self.episodes + self.procedures + self.insights
+    }
+}
+
+fn bench_scope() -> Scope {
+    Scope {
+        tenant_id: "default".to_string(),
+        user_id: "bench-user".to_string(),
+        agent_id: "bench-agent".to_string(),
+        session_id: "bench-session".to_string(),
+        run_id: "bench-run".to_string(),
+    }
+}
+
+fn seed_candidates(store: &InMemoryStore, scope: &Scope, size: CandidateSize) {
+    let now = Utc::now();
+    for idx in 0..size.facts {
+        let mut fact = Fact::new(format!("pref.key.{}", idx), json!({ "value": idx }));
+        fact.fact_id = format!("f{}", idx);
+        // Every fourth fact shares its source, so citation dedup has work to do.
+        fact.sources = vec![format!("e{}", idx / 4)];
+        store.upsert_fact(scope, fact).unwrap();
+    }
+    for idx in 0..size.episodes {
+        let episode = Episode {
+            episode_id: format!("ep{}", idx),
+            time_range: TimeRange {
+                start: now - ChronoDuration::hours(idx as i64 % 720),
+                end: None,
+            },
+            summary: format!("episode {} about the deployment pipeline", idx),
+            highlights: vec!["the rollout was paused after the canary failed. ".repeat(4)],
+            tags: vec!["alpha".to_string()],
+            entities: vec!["entity1".to_string()],
+            sources: vec![format!("e{}", idx / 2)],
+            compression_level: CompressionLevel::Raw,
+            recency_score: None,
+            lang: None,
+        };
+        store.append_episode(scope, episode).unwrap();
+    }
+    for idx in 0..size.procedures {
+        let procedure = Procedure {
+            procedure_id: format!("p{}", idx),
+            task_type: "summary".to_string(),
+            content: json!({ "step": idx }),
+            priority: idx as i32,
+            sources: vec![],
+            applicability: JsonMap::new(),
+        };
+        store.upsert_procedure(scope, procedure).unwrap();
+    }
+    for idx in 0..size.insights {
+        let mut insight = InsightItem::new(InsightType::Hypothesis, format!("insight {}", idx));
+        insight.id = format!("i{}", idx);
+        insight.sources = vec![format!("e{}", idx)];
+        store.append_insight(scope, insight).unwrap();
+    }
+}
+
+/// A packet holding every candidate, unclipped and untrimmed.
+fn candidate_packet(store: &InMemoryStore, scope: &Scope, size: CandidateSize) -> MemoryPacket {
+    let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+    request.task_type = Some("summary".to_string());
+    request.persist = false;
+    request.budget = Budget {
+        max_tokens: 0,
+        per_section: JsonMap::new(),
+        model: None,
+    };
+    request.policy = RecallPolicy {
+        max_total_candidates: size.total(),
+        max_facts: size.facts,
+        max_procedures: size.procedures,
+        max_episodes: size.episodes,
+        max_insights: size.insights,
+        max_quote_tokens: 0,
+        max_highlight_tokens: 0,
+        ..RecallPolicy::default()
+    };
+    let mut packet = build_memory_packet(store, request).unwrap();
+    packet.short_term.key_quotes = (0..10)
+        .map(|idx| KeyQuote {
+            evidence_id: format!("e{}", idx),
+            quote: "we agreed to ship behind a flag and revisit next week. ".repeat(6),
+            role: Role::User,
+            ts: Some(Utc::now()),
+        })
+        .collect();
+    packet
+}
+
+fn composer_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("composer");
+    group.sample_size(20);
+    group.warm_up_time(Duration::from_secs(2));
+    group.measurement_time(Duration::from_secs(4));
+
+    let sizes = [
+        CandidateSize {
+            facts: 50,
+            episodes: 40,
+            procedures: 10,
+            insights: 20,
+        },
+        CandidateSize {
+            facts: 200,
+            episodes: 200,
+            procedures: 30,
+            insights: 50,
+        },
+        CandidateSize {
+            facts: 1000,
+            episodes: 1000,
+            procedures: 80,
+            insights: 200,
+        },
+    ];
+
+    for size in sizes {
+        let store = InMemoryStore::new();
+        let scope = bench_scope();
+        seed_candidates(&store, &scope, size);
+        let episodes = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        let packet = candidate_packet(&store, &scope, size);
+        let now = Utc::now();
+        let budget_request = BuildRequest::new(scope.clone(), Purpose::Planner);
+
+        group.bench_with_input(BenchmarkId::new("rank_episodes", size.label()), &size, |b, _| {
+            b.iter(|| {
+                let mut ranked = episodes.clone();
+                rank_episodes(&mut ranked, now, 20);
+                ranked
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("collect_citations", size.label()),
+            &size,
+            |b, _| {
+                b.iter(|| collect_citations(&packet.short_term, &packet.long_term, &packet.insight))
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("apply_budget", size.label()), &size, |b, _| {
+            b.iter(|| {
+                let mut trimmed = packet.clone();
+                apply_budget(&budget_request, &mut trimmed);
+                trimmed
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, composer_bench);
+criterion_main!(benches);
@@ -0,0 +1,268 @@
+use chrono::{DateTime, NaiveDate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Store, StoreError, StoreResult, TimeRangeFilter};
+
+const DAY_MILLIS: i64 = 86_400_000;
+
+/// Raw per-user activity of a tenant, as returned by [`Store::tenant_activity`].
+/// Events are bucketed by UTC day.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UserActivity {
+    pub user_id: String,
+    pub events_by_day: BTreeMap<NaiveDate, u64>,
+    pub facts: u64,
+    pub episodes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// Buckets (and the whole tenant) with fewer distinct users are suppressed.
+    pub k_anonymity: usize,
+    /// Privacy parameter for Laplace noise, applied to every released count. `None`
+    /// releases exact counts.
+    pub epsilon: Option<f64>,
+    /// Per-user contribution caps, which bound the sensitivity of each count.
+    pub max_events_per_user_day: u64,
+    pub max_items_per_user: u64,
+    /// Seeds the noise generator; only useful for reproducible tests.
+    pub seed: Option<u64>,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            k_anonymity: 5,
+            epsilon: None,
+            max_events_per_user_day: 100,
+            max_items_per_user: 1_000,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailyActivity {
+    pub day: NaiveDate,
+    pub active_users: u64,
+    pub events: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TenantStats {
+    pub tenant_id: String,
+    pub users: u64,
+    pub events: u64,
+    pub facts: u64,
+    pub episodes: u64,
+    pub daily: Vec<DailyActivity>,
+    /// Days dropped because fewer than `k_anonymity` users were active.
+    pub suppressed_days: usize,
+    /// Set when the tenant itself has fewer than `k_anonymity` users; all counts are
+    /// then zero.
+    pub suppressed: bool,
+    pub noised: bool,
+}
+
+/// Aggregates a tenant's memory activity across users without exposing any single
+/// user: per-user contributions are capped, small buckets are suppressed and, with
+/// `epsilon` set, every count gets Laplace noise scaled to its sensitivity.
+///
+/// Noise is drawn per released count, so the total privacy cost of one call is
+/// roughly `epsilon` times the number of counts released.
+pub fn tenant_stats<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+    range: TimeRangeFilter,
+    options: &StatsOptions,
+) -> StoreResult<TenantStats> {
+    if let Some(epsilon) = options.epsilon
+        && !(epsilon > 0.0 && epsilon.is_finite())
+    {
+        return Err(StoreError::InvalidInput(format!(
+            "epsilon must be a positive number, got {}",
+            epsilon
+        )));
+    }
+
+    let activity = store.tenant_activity(tenant_id, range)?;
+    let mut stats = TenantStats {
+        tenant_id: tenant_id.to_string(),
+        noised: options.epsilon.is_some(),
+        ..TenantStats::default()
+    };
+    if activity.len() < options.k_anonymity.max(1) {
+        stats.suppressed = true;
+        stats.noised = false;
+        return Ok(stats);
+    }
+
+    let mut noise = Noise::new(options);
+    let item_cap = options.max_items_per_user;
+    let event_cap = options.max_events_per_user_day;
+
+    stats.users = noise.count(activity.len() as u64, 1);
+    stats.facts = noise.count(activity.iter().map(|user| user.facts.min(item_cap)).sum(), item_cap);
+    stats.episodes = noise.count(
+        activity.iter().map(|user| user.episodes.min(item_cap)).sum(),
+        item_cap,
+    );
+
+    let mut days: BTreeMap<NaiveDate, (u64, u64)> = BTreeMap::new();
+    for user in &activity {
+        for (day, events) in &user.events_by_day {
+            let bucket = days.entry(*day).or_default();
+            bucket.0 += 1;
+            bucket.1 += (*events).min(event_cap);
+        }
+    }
+    for (day, (active_users, events)) in days {
+        if (active_users as usize) < options.k_anonymity {
+            stats.suppressed_days += 1;
+            continue;
+        }
+        stats.daily.push(DailyActivity {
+            day,
+            active_users: noise.count(active_users, 1),
+            events: noise.count(events, event_cap),
+        });
+    }
+    stats.events = stats.daily.iter().map(|day| day.events).sum();
+    Ok(stats)
+}
+
+struct Noise {
+    epsilon: Option<f64>,
+    rng: StdRng,
+}
+
+impl Noise {
+    fn new(options: &StatsOptions) -> Self {
+        let rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            epsilon: options.epsilon,
+            rng,
+        }
+    }
+
+    fn count(&mut self, value: u64, sensitivity: u64) -> u64 {
+        let Some(epsilon) = self.epsilon else {
+            return value;
+        };
+        let scale = sensitivity as f64 / epsilon;
+        // Inverse CDF of the Laplace distribution.
+        let u: f64 = self.rng.gen_range(-0.5..0.5);
+        let noise = -scale * u.signum() * (1.0 - 2.0 * u.abs()).ln();
+        (value as f64 + noise).round().max(0.0) as u64
+    }
+}
+
+/// Builds [`UserActivity`] rows from the grouped counts the SQL backends return:
+/// `(user_id, day index, events)`, `(user_id, facts)` and `(user_id, episodes)`.
+pub(crate) fn collect_user_activity(
+    event_rows: Vec<(String, i64, i64)>,
+    fact_rows: Vec<(String, i64)>,
+    episode_rows: Vec<(String, i64)>,
+) -> Vec<UserActivity> {
+    let mut users: HashMap<String, UserActivity> = HashMap::new();
+    for (user_id, day, events) in event_rows {
+        if let Some(day) = DateTime::from_timestamp_millis(day * DAY_MILLIS) {
+            *user_entry(&mut users, user_id)
+                .events_by_day
+                .entry(day.date_naive())
+                .or_default() += events as u64;
+        }
+    }
+    for (user_id, facts) in fact_rows {
+        user_entry(&mut users, user_id).facts += facts as u64;
+    }
+    for (user_id, episodes) in episode_rows {
+        user_entry(&mut users, user_id).episodes += episodes as u64;
+    }
+
+    let mut users: Vec<UserActivity> = users.into_values().collect();
+    users.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+    users
+}
+
+fn user_entry(users: &mut HashMap<String, UserActivity>, user_id: String) -> &mut UserActivity {
+    users.entry(user_id.clone()).or_insert_with(|| UserActivity {
+        user_id,
+        ..UserActivity::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, EventKind, InMemoryStore};
+    use engram_types::{Fact, Scope};
+    use serde_json::json;
+
+    fn scope_for(user_id: &str) -> Scope {
+        Scope {
+            tenant_id: "acme".to_string(),
+            user_id: user_id.to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn suppresses_small_cohorts_and_noises_counts() {
+        let store = InMemoryStore::new();
+        for user in 0..6 {
+            let scope = scope_for(&format!("user{}", user));
+            for _ in 0..=user {
+                let mut event = Event::new(scope.clone(), EventKind::Message, json!({}));
+                event.ts = DateTime::from_timestamp_millis(1_767_225_600_000).unwrap();
+                store.append_event(event).unwrap();
+            }
+            store.upsert_fact(&scope, Fact::new("user.name", json!("x"))).unwrap();
+        }
+
+        let exact = tenant_stats(&store, "acme", TimeRangeFilter::default(), &StatsOptions::default())
+            .unwrap();
+        assert!(!exact.suppressed);
+        assert_eq!(exact.users, 6);
+        assert_eq!(exact.facts, 6);
+        assert_eq!(exact.events, 21);
+        assert_eq!(exact.daily.len(), 1);
+        assert_eq!(exact.daily[0].active_users, 6);
+
+        let strict = StatsOptions {
+            k_anonymity: 7,
+            ..StatsOptions::default()
+        };
+        let hidden = tenant_stats(&store, "acme", TimeRangeFilter::default(), &strict).unwrap();
+        assert!(hidden.suppressed);
+        assert_eq!(hidden.users, 0);
+
+        let noisy = StatsOptions {
+            epsilon: Some(0.5),
+            seed: Some(7),
+            ..StatsOptions::default()
+        };
+        let first = tenant_stats(&store, "acme", TimeRangeFilter::default(), &noisy).unwrap();
+        let second = tenant_stats(&store, "acme", TimeRangeFilter::default(), &noisy).unwrap();
+        assert!(first.noised);
+        assert_eq!(first.users, second.users);
+        assert!(tenant_stats(
+            &store,
+            "acme",
+            TimeRangeFilter::default(),
+            &StatsOptions {
+                epsilon: Some(0.0),
+                ..StatsOptions::default()
+            }
+        )
+        .is_err());
+    }
+}
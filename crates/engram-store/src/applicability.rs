@@ -0,0 +1,174 @@
+use engram_types::{JsonMap, Procedure, Purpose};
+use serde_json::{json, Value};
+
+use crate::RecallCues;
+
+/// Operators of a slot predicate such as `{"gte": 100}`; an object with other keys is
+/// compared as a plain value.
+const SLOT_OPERATORS: [&str; 8] = ["eq", "ne", "in", "exists", "gt", "gte", "lt", "lte"];
+
+/// What a procedure's `applicability` conditions are checked against.
+#[derive(Debug, Clone, Copy)]
+pub struct ApplicabilityContext<'a> {
+    pub purpose: &'a Purpose,
+    pub cues: &'a RecallCues,
+    /// Slots of the run's working state.
+    pub slots: &'a JsonMap,
+}
+
+/// Whether every condition in `procedure.applicability` holds in `context`; a
+/// procedure without conditions always applies. `build_memory_packet` leaves out the
+/// procedures that don't apply. The conditions are:
+///
+/// - `purposes`: a purpose or list of purposes the request must have.
+/// - `tags`, `entities`, `keywords`: cue terms, ignoring case. A term or list of terms
+///   of which any must be a cue, or an object of such lists under `any`, `all` and
+///   `none`.
+/// - `slots`: working state slots by name, each equal to a value or meeting an
+///   object of operators: `eq`, `ne`, `in` (a list), `exists` (a bool) and the
+///   numeric `gt`, `gte`, `lt` and `lte`.
+///
+/// Other keys are descriptive and ignored. A malformed condition never holds.
+pub fn procedure_applies(procedure: &Procedure, context: &ApplicabilityContext<'_>) -> bool {
+    procedure
+        .applicability
+        .iter()
+        .all(|(key, condition)| match key.as_str() {
+            "purposes" => purpose_matches(condition, context.purpose),
+            "tags" => terms_match(condition, &context.cues.tags),
+            "entities" => terms_match(condition, &context.cues.entities),
+            "keywords" => terms_match(condition, &context.cues.keywords),
+            "slots" => slots_match(condition, context.slots),
+            _ => true,
+        })
+}
+
+fn purpose_matches(condition: &Value, purpose: &Purpose) -> bool {
+    let purpose = json!(purpose);
+    match condition {
+        Value::String(_) => *condition == purpose,
+        Value::Array(purposes) => purposes.contains(&purpose),
+        _ => false,
+    }
+}
+
+fn terms_match(condition: &Value, cues: &[String]) -> bool {
+    let cued = |term: &Value| {
+        term.as_str().is_some_and(|term| {
+            let term = term.trim().to_lowercase();
+            cues.iter().any(|cue| cue.trim().to_lowercase() == term)
+        })
+    };
+    match condition {
+        Value::String(_) => cued(condition),
+        Value::Array(terms) => terms.iter().any(cued),
+        Value::Object(ops) => ops.iter().all(|(op, terms)| {
+            let Some(terms) = terms.as_array() else {
+                return false;
+            };
+            match op.as_str() {
+                "any" => terms.iter().any(cued),
+                "all" => terms.iter().all(cued),
+                "none" => !terms.iter().any(cued),
+                _ => false,
+            }
+        }),
+        _ => false,
+    }
+}
+
+fn slots_match(condition: &Value, slots: &JsonMap) -> bool {
+    let Some(predicates) = condition.as_object() else {
+        return false;
+    };
+    predicates
+        .iter()
+        .all(|(name, predicate)| slot_matches(slots.get(name).filter(|v| !v.is_null()), predicate))
+}
+
+fn slot_matches(value: Option<&Value>, predicate: &Value) -> bool {
+    let is_operator = |op: &String| SLOT_OPERATORS.contains(&op.as_str());
+    let ops = match predicate.as_object() {
+        Some(ops) if !ops.is_empty() && ops.keys().all(is_operator) => ops,
+        _ => return value == Some(predicate),
+    };
+    ops.iter().all(|(op, operand)| match op.as_str() {
+        "eq" => value == Some(operand),
+        "ne" => value != Some(operand),
+        "in" => operand
+            .as_array()
+            .is_some_and(|values| value.is_some_and(|value| values.contains(value))),
+        "exists" => operand.as_bool() == Some(value.is_some()),
+        _ => match (value.and_then(Value::as_f64), operand.as_f64()) {
+            (Some(value), Some(operand)) => match op.as_str() {
+                "gt" => value > operand,
+                "gte" => value >= operand,
+                "lt" => value < operand,
+                _ => value <= operand,
+            },
+            _ => false,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore, Store, WorkingStatePatch};
+    use engram_types::Scope;
+
+    fn procedure(id: &str, applicability: Value) -> Procedure {
+        Procedure {
+            procedure_id: id.to_string(),
+            task_type: "support".to_string(),
+            content: json!({ "steps": [id] }),
+            priority: 0,
+            sources: Vec::new(),
+            applicability: serde_json::from_value(applicability).unwrap(),
+        }
+    }
+
+    #[test]
+    fn packets_only_include_procedures_whose_conditions_hold() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let procedures = [
+            procedure("always", json!({ "note": "no conditions" })),
+            procedure("refunds", json!({ "tags": ["Billing"], "purposes": "planner" })),
+            procedure("large_refunds", json!({ "slots": { "amount": { "gt": 500 } } })),
+            procedure("eu_only", json!({ "slots": { "region": { "in": ["eu", "uk"] } } })),
+            procedure("no_escalation", json!({ "entities": { "none": ["manager"] } })),
+            procedure("malformed", json!({ "tags": 3 })),
+        ];
+        for procedure in procedures {
+            store.upsert_procedure(&scope, procedure).unwrap();
+        }
+        let patch = WorkingStatePatch {
+            slots: Some(serde_json::from_value(json!({ "amount": 120, "region": "eu" })).unwrap()),
+            ..WorkingStatePatch::default()
+        };
+        store.patch_working_state(&scope, patch).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        request.task_type = Some("support".to_string());
+        request.cues.tags = vec!["billing".to_string()];
+        request.cues.entities = vec!["manager".to_string()];
+        let packet = build_memory_packet(&store, request).unwrap();
+
+        let mut ids: Vec<&str> = packet
+            .long_term
+            .procedures
+            .iter()
+            .map(|procedure| procedure.procedure_id.as_str())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["always", "eu_only", "refunds"]);
+    }
+}
@@ -0,0 +1,622 @@
+use chrono::{DateTime, Utc};
+use engram_types::{new_ulid, Scope, ScopeLevel};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use tracing::debug;
+
+use crate::changelog::apply_change;
+use crate::pinned_procedures::pinned_procedure_scope;
+use crate::{
+    shared_fact_scope, ChangeOp, EpisodeFilter, FactFilter, InsightFilter,
+    ProcedureCandidateFilter, Store, StoreError, StoreResult, PINNED_TASK_TYPE,
+};
+
+/// Value of [`ArchiveManifest::format`].
+pub const ARCHIVE_FORMAT: &str = "engram-archive";
+/// Layout version written by [`export_tenant_archive`]; newer archives are refused.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const ARCHIVE_ZSTD_LEVEL: i32 = 9;
+
+/// Sections in the order they are written and restored: tenant settings first, and
+/// long-term memory before the suppressions that point at it.
+const SECTIONS: [&str; 12] = [
+    "source_credibility",
+    "user_locales",
+    "facts",
+    "episodes",
+    "procedures",
+    "procedure_candidates",
+    "suppressions",
+    "run_outcomes",
+    "events",
+    "working_states",
+    "stm",
+    "insights",
+];
+
+/// First line of an archive, describing the records that follow it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub sections: Vec<ArchiveSection>,
+}
+
+impl ArchiveManifest {
+    /// Records across all sections.
+    pub fn record_count(&self) -> usize {
+        self.sections.iter().map(|section| section.count).sum()
+    }
+}
+
+/// One kind of record in an archive: how many there are and the SHA-256, in hex, of
+/// their lines, each including its trailing newline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveSection {
+    pub name: String,
+    pub count: usize,
+    pub sha256: String,
+}
+
+/// Writes the tenant's memory to a single zstd-compressed archive for backups: a
+/// JSON [`ArchiveManifest`] line followed by one [`ChangeOp`] per line, section by
+/// section, so [`import_tenant_archive`] restores it by replaying the writes.
+///
+/// Runs are found through [`Store::list_scopes`], so only users and agents with
+/// events are covered, along with the agent and tenant fact pools above them.
+/// Procedures are listed per task type, taken from the tenant's procedure candidates
+/// and context builds. Embeddings and context builds themselves are left out; the
+/// former can be recomputed and the latter are an audit trail, not memory.
+pub fn export_tenant_archive<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+) -> StoreResult<Vec<u8>> {
+    let records = collect_tenant(store, tenant_id)?;
+
+    let mut body = Vec::new();
+    let mut sections = Vec::with_capacity(SECTIONS.len());
+    for name in SECTIONS {
+        let ops = records.get(name).map(Vec::as_slice).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        for op in ops {
+            let mut line = serde_json::to_vec(op)?;
+            line.push(b'\n');
+            hasher.update(&line);
+            body.extend_from_slice(&line);
+        }
+        sections.push(ArchiveSection {
+            name: name.to_string(),
+            count: ops.len(),
+            sha256: to_hex(&hasher.finalize()),
+        });
+    }
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        tenant_id: tenant_id.to_string(),
+        created_at: Utc::now(),
+        sections,
+    };
+
+    let mut encoder = zstd::Encoder::new(Vec::new(), ARCHIVE_ZSTD_LEVEL).map_err(io_error)?;
+    serde_json::to_writer(&mut encoder, &manifest)?;
+    encoder.write_all(b"\n").map_err(io_error)?;
+    encoder.write_all(&body).map_err(io_error)?;
+    let archive = encoder.finish().map_err(io_error)?;
+    debug!(
+        "exported {} records of tenant {} in {} bytes",
+        manifest.record_count(),
+        tenant_id,
+        archive.len()
+    );
+    Ok(archive)
+}
+
+/// Checks an archive without restoring it: the manifest's format and version, each
+/// section's count and checksum, and that every record belongs to the manifest's
+/// tenant. Returns the manifest.
+pub fn verify_archive(archive: &[u8]) -> StoreResult<ArchiveManifest> {
+    read_archive(archive).map(|(manifest, _)| manifest)
+}
+
+/// Restores an archive written by [`export_tenant_archive`] into `store`, meant to
+/// be empty for the tenant. Nothing is written unless the whole archive passes
+/// [`verify_archive`]. Returns the manifest.
+pub fn import_tenant_archive<S: Store + ?Sized>(
+    store: &S,
+    archive: &[u8],
+) -> StoreResult<ArchiveManifest> {
+    import_tenant_archive_with(store, archive, &ImportOptions::default())
+        .map(|report| report.manifest)
+}
+
+/// How [`import_tenant_archive_with`] rewrites an archive on the way in, e.g. to
+/// reproduce a production tenant in staging under other ids.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Tenant to restore into instead of the archive's own.
+    pub tenant_id: Option<String>,
+    /// User ids to replace, old to new; other users keep theirs.
+    pub user_ids: BTreeMap<String, String>,
+    /// Gives every event, fact, episode, procedure, procedure candidate and insight a
+    /// new id, and rewrites the references between them (sources, suppressions,
+    /// evidence ids) to match.
+    pub regenerate_ids: bool,
+}
+
+/// What [`import_tenant_archive_with`] restored, and under which ids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub manifest: ArchiveManifest,
+    /// Tenant the records were written to.
+    pub tenant_id: String,
+    /// User ids that were replaced, old to new.
+    pub user_ids: BTreeMap<String, String>,
+    /// Record ids that were regenerated, old to new.
+    pub ids: BTreeMap<String, String>,
+}
+
+/// Like [`import_tenant_archive`], but moves the records to another tenant or
+/// users and optionally regenerates their ids first; see [`ImportOptions`]. The
+/// report maps every old id to the one it was restored under.
+pub fn import_tenant_archive_with<S: Store + ?Sized>(
+    store: &S,
+    archive: &[u8],
+    options: &ImportOptions,
+) -> StoreResult<ImportReport> {
+    let (manifest, mut ops) = read_archive(archive)?;
+    let tenant_id = options.tenant_id.clone().unwrap_or_else(|| manifest.tenant_id.clone());
+    if tenant_id.trim().is_empty() {
+        return Err(invalid("import tenant id must not be empty".to_string()));
+    }
+
+    let mut ids = BTreeMap::new();
+    if options.regenerate_ids {
+        for op in &ops {
+            if let Some(id) = record_id(op) {
+                ids.entry(id.to_string()).or_insert_with(new_ulid);
+            }
+        }
+    }
+    let mut user_ids = BTreeMap::new();
+    for op in &mut ops {
+        remap_scope(op, &tenant_id, &options.user_ids, &mut user_ids);
+        if !ids.is_empty() {
+            remap_ids(op, &ids);
+        }
+    }
+
+    for op in ops {
+        apply_change(store, op)?;
+    }
+    debug!(
+        "imported {} records of tenant {} into tenant {}, {} ids regenerated",
+        manifest.record_count(),
+        manifest.tenant_id,
+        tenant_id,
+        ids.len()
+    );
+    Ok(ImportReport {
+        manifest,
+        tenant_id,
+        user_ids,
+        ids,
+    })
+}
+
+fn collect_tenant<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+) -> StoreResult<BTreeMap<&'static str, Vec<ChangeOp>>> {
+    let runs = store.list_scopes(Some(tenant_id), None)?;
+    let mut sessions = BTreeMap::new();
+    let mut owners = BTreeMap::new();
+    let mut pools = BTreeMap::new();
+    let mut task_types = BTreeSet::new();
+    for run in &runs {
+        let ltm = Scope {
+            session_id: String::new(),
+            run_id: String::new(),
+            ..run.clone()
+        };
+        let session = Scope {
+            run_id: String::new(),
+            ..run.clone()
+        };
+        sessions.entry(session_key(&session)).or_insert(session);
+        for level in [ScopeLevel::User, ScopeLevel::Agent, ScopeLevel::Tenant] {
+            let pool = shared_fact_scope(&ltm, &level);
+            pools.entry((pool.user_id.clone(), pool.agent_id.clone())).or_insert(pool);
+        }
+        owners.entry((ltm.user_id.clone(), ltm.agent_id.clone())).or_insert(ltm);
+        for build in store.list_context_build_summaries(run, None)? {
+            task_types.insert(build.task_type);
+        }
+    }
+
+    let mut records: BTreeMap<&'static str, Vec<ChangeOp>> = BTreeMap::new();
+    if let Some(credibility) = store.get_source_credibility(tenant_id)? {
+        records.entry("source_credibility").or_default().push(ChangeOp::SetSourceCredibility {
+            tenant_id: tenant_id.to_string(),
+            credibility,
+        });
+    }
+    let users: BTreeSet<&str> = runs.iter().map(|run| run.user_id.as_str()).collect();
+    for user_id in users {
+        if let Some(locale) = store.get_user_locale(tenant_id, user_id)? {
+            records.entry("user_locales").or_default().push(ChangeOp::SetUserLocale {
+                tenant_id: tenant_id.to_string(),
+                user_id: user_id.to_string(),
+                locale,
+            });
+        }
+    }
+    for pool in pools.values() {
+        for fact in store.list_facts(pool, FactFilter::default())? {
+            let scope = pool.clone();
+            records.entry("facts").or_default().push(ChangeOp::UpsertFact { scope, fact });
+        }
+    }
+    let pinned = pinned_procedure_scope(tenant_id);
+    for procedure in store.list_procedures(&pinned, PINNED_TASK_TYPE, None)? {
+        let op = ChangeOp::UpsertProcedure {
+            scope: pinned.clone(),
+            procedure,
+        };
+        records.entry("procedures").or_default().push(op);
+    }
+    for ltm in owners.values() {
+        let filter = ProcedureCandidateFilter::default();
+        for candidate in store.list_procedure_candidates(ltm, filter)? {
+            task_types.insert(candidate.procedure.task_type.clone());
+            let scope = ltm.clone();
+            let op = ChangeOp::UpsertProcedureCandidate { scope, candidate };
+            records.entry("procedure_candidates").or_default().push(op);
+        }
+    }
+    for ltm in owners.values() {
+        for episode in store.list_episodes(ltm, EpisodeFilter::default())? {
+            let scope = ltm.clone();
+            records.entry("episodes").or_default().push(ChangeOp::AppendEpisode { scope, episode });
+        }
+        for task_type in &task_types {
+            for procedure in store.list_procedures(ltm, task_type, None)? {
+                let scope = ltm.clone();
+                let op = ChangeOp::UpsertProcedure { scope, procedure };
+                records.entry("procedures").or_default().push(op);
+            }
+        }
+        for suppression in store.list_suppressions(ltm)? {
+            records.entry("suppressions").or_default().push(ChangeOp::SuppressMemory {
+                scope: ltm.clone(),
+                item: suppression.item,
+                reason: suppression.reason,
+            });
+        }
+        let mut outcomes = store.list_run_outcomes(ltm, None)?;
+        outcomes.reverse();
+        for outcome in outcomes {
+            let scope = Scope {
+                session_id: outcome.session_id.clone(),
+                run_id: outcome.run_id.clone(),
+                ..ltm.clone()
+            };
+            let op = ChangeOp::RecordRunOutcome { scope, outcome };
+            records.entry("run_outcomes").or_default().push(op);
+        }
+    }
+    for run in &runs {
+        for event in store.get_events_since(run, 0, None)? {
+            records.entry("events").or_default().push(ChangeOp::AppendEvent { event });
+        }
+        if let Some(state) = store.get_working_state(run)? {
+            let scope = run.clone();
+            let state = Box::new(state);
+            let op = ChangeOp::PatchWorkingState { scope, state };
+            records.entry("working_states").or_default().push(op);
+        }
+        for insight in store.list_insights(run, InsightFilter::default())? {
+            let scope = run.clone();
+            records.entry("insights").or_default().push(ChangeOp::AppendInsight { scope, insight });
+        }
+    }
+    for session in sessions.values() {
+        if let Some(stm) = store.get_stm(session)? {
+            let scope = session.clone();
+            records.entry("stm").or_default().push(ChangeOp::UpdateStm { scope, stm });
+        }
+    }
+    Ok(records)
+}
+
+fn session_key(scope: &Scope) -> (String, String, String) {
+    (scope.user_id.clone(), scope.agent_id.clone(), scope.session_id.clone())
+}
+
+fn read_archive(archive: &[u8]) -> StoreResult<(ArchiveManifest, Vec<ChangeOp>)> {
+    let data = zstd::decode_all(archive)
+        .map_err(|err| invalid(format!("archive is not zstd-compressed: {}", err)))?;
+    let mut lines = data.split_inclusive(|byte| *byte == b'\n');
+    let manifest: ArchiveManifest = match lines.next() {
+        Some(line) => serde_json::from_slice(line)
+            .map_err(|err| invalid(format!("unreadable archive manifest: {}", err)))?,
+        None => return Err(invalid("archive is empty".to_string())),
+    };
+    if manifest.format != ARCHIVE_FORMAT {
+        return Err(invalid(format!("unknown archive format {:?}", manifest.format)));
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(invalid(format!(
+            "archive version {} is newer than supported version {}",
+            manifest.version, ARCHIVE_VERSION
+        )));
+    }
+
+    let mut ops = Vec::with_capacity(manifest.record_count());
+    for section in &manifest.sections {
+        let section_lines: Vec<&[u8]> = lines.by_ref().take(section.count).collect();
+        if section_lines.len() < section.count {
+            return Err(invalid(format!("archive is truncated in section {}", section.name)));
+        }
+        let mut hasher = Sha256::new();
+        for line in &section_lines {
+            hasher.update(line);
+        }
+        if to_hex(&hasher.finalize()) != section.sha256 {
+            return Err(invalid(format!("archive section {} fails its checksum", section.name)));
+        }
+        for line in section_lines {
+            let mut op: ChangeOp = serde_json::from_slice(line)?;
+            if record_tenant(&mut op) != manifest.tenant_id {
+                return Err(invalid(format!(
+                    "archive section {} has records of another tenant",
+                    section.name
+                )));
+            }
+            ops.push(op);
+        }
+    }
+    if lines.next().is_some() {
+        return Err(invalid("archive has records past its manifest".to_string()));
+    }
+    Ok((manifest, ops))
+}
+
+/// The id a record is stored under, for records that have one.
+fn record_id(op: &ChangeOp) -> Option<&str> {
+    match op {
+        ChangeOp::AppendEvent { event } => Some(&event.event_id),
+        ChangeOp::UpsertFact { fact, .. } => Some(&fact.fact_id),
+        ChangeOp::AppendEpisode { episode, .. } => Some(&episode.episode_id),
+        ChangeOp::UpsertProcedure { procedure, .. } => Some(&procedure.procedure_id),
+        ChangeOp::UpsertProcedureCandidate { candidate, .. } => Some(&candidate.candidate_id),
+        ChangeOp::AppendInsight { insight, .. } => Some(&insight.id),
+        _ => None,
+    }
+}
+
+fn remap_scope(
+    op: &mut ChangeOp,
+    tenant: &str,
+    users: &BTreeMap<String, String>,
+    replaced: &mut BTreeMap<String, String>,
+) {
+    let mut remap_user = |user_id: &mut String| {
+        if let Some(new_id) = users.get(user_id.as_str()) {
+            replaced.insert(user_id.clone(), new_id.clone());
+            *user_id = new_id.clone();
+        }
+    };
+    match op {
+        ChangeOp::SetSourceCredibility { tenant_id, .. } => *tenant_id = tenant.to_string(),
+        ChangeOp::SetUserLocale {
+            tenant_id, user_id, ..
+        } => {
+            *tenant_id = tenant.to_string();
+            remap_user(user_id);
+        }
+        op => {
+            if let Some(scope) = op.scope_mut() {
+                scope.tenant_id = tenant.to_string();
+                remap_user(&mut scope.user_id);
+            }
+        }
+    }
+}
+
+/// Rewrites record ids, and the references to them, through `ids`.
+fn remap_ids(op: &mut ChangeOp, ids: &BTreeMap<String, String>) {
+    let remap = |id: &mut String| {
+        if let Some(new_id) = ids.get(id.as_str()) {
+            *id = new_id.clone();
+        }
+    };
+    let remap_all = |list: &mut Vec<String>| list.iter_mut().for_each(remap);
+    match op {
+        ChangeOp::AppendEvent { event } => remap(&mut event.event_id),
+        ChangeOp::UpsertFact { fact, .. } => {
+            remap(&mut fact.fact_id);
+            remap_all(&mut fact.sources);
+        }
+        ChangeOp::AppendEpisode { episode, .. } => {
+            remap(&mut episode.episode_id);
+            remap_all(&mut episode.sources);
+        }
+        ChangeOp::UpsertProcedure { procedure, .. } => {
+            remap(&mut procedure.procedure_id);
+            remap_all(&mut procedure.sources);
+        }
+        ChangeOp::UpsertProcedureCandidate { candidate, .. } => {
+            remap(&mut candidate.candidate_id);
+            remap(&mut candidate.procedure.procedure_id);
+            remap_all(&mut candidate.procedure.sources);
+            remap_all(&mut candidate.source_episodes);
+            remap_all(&mut candidate.evidence);
+        }
+        ChangeOp::AppendInsight { insight, .. } => {
+            remap(&mut insight.id);
+            remap_all(&mut insight.sources);
+            if let Some(parent) = &mut insight.parent_insight_id {
+                remap(parent);
+            }
+        }
+        ChangeOp::SuppressMemory { item, .. } => remap(&mut item.id),
+        ChangeOp::PatchWorkingState { state, .. } => {
+            state.tool_evidence.iter_mut().for_each(|evidence| remap(&mut evidence.evidence_id));
+        }
+        ChangeOp::UpdateStm { stm, .. } => {
+            stm.key_quotes.iter_mut().for_each(|quote| remap(&mut quote.evidence_id));
+        }
+        _ => {}
+    }
+}
+
+fn record_tenant(op: &mut ChangeOp) -> &str {
+    match op {
+        ChangeOp::SetSourceCredibility { tenant_id, .. }
+        | ChangeOp::SetUserLocale { tenant_id, .. } => tenant_id,
+        op => op.scope_mut().map(|scope| scope.tenant_id.as_str()).unwrap_or_default(),
+    }
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn invalid(message: String) -> StoreError {
+    StoreError::InvalidInput(message)
+}
+
+fn io_error(err: std::io::Error) -> StoreError {
+    StoreError::Storage(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        list_tenant_procedures, pin_tenant_procedure, set_preference, Event, EventKind,
+        InMemoryStore, MemoryKind, MemoryRef, SqliteStore, StmState,
+    };
+    use engram_types::{CandidateStatus, Episode, Fact, Procedure, ProcedureCandidate};
+    use serde_json::json;
+
+    #[test]
+    fn archives_roundtrip_and_reject_tampering() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        let agent_pool = shared_fact_scope(&scope, &ScopeLevel::Agent);
+        store.upsert_fact(&agent_pool, Fact::new("agent.tone", json!("terse"))).unwrap();
+        set_preference(&store, &scope, "units", json!("metric"), 1.0).unwrap();
+        store.append_episode(&scope, Episode::new("planned a trip")).unwrap();
+        let procedure = Procedure::new("travel", json!({"steps": ["book"]}));
+        store.upsert_procedure(&scope, procedure.clone()).unwrap();
+        let candidate = ProcedureCandidate {
+            candidate_id: "cand1".to_string(),
+            procedure,
+            source_episodes: Vec::new(),
+            evidence: Vec::new(),
+            status: CandidateStatus::Approved,
+            created_at: Utc::now(),
+            reviewed_at: None,
+            reviewer: String::new(),
+            review_note: String::new(),
+        };
+        store.upsert_procedure_candidate(&scope, candidate).unwrap();
+        let rule = Procedure::new("", json!("Never share card numbers."));
+        pin_tenant_procedure(&store, "default", rule).unwrap();
+        store.update_stm(&scope, StmState::default()).unwrap();
+
+        let archive = export_tenant_archive(&store, "default").unwrap();
+        let manifest = verify_archive(&archive).unwrap();
+        let count = |name: &str| {
+            manifest.sections.iter().find(|section| section.name == name).unwrap().count
+        };
+        assert_eq!(count("facts"), 3);
+        assert_eq!(count("episodes"), 1);
+        assert_eq!(count("procedures"), 2);
+        assert_eq!(count("events"), 1);
+
+        let restored = SqliteStore::new_in_memory().unwrap();
+        assert_eq!(import_tenant_archive(&restored, &archive).unwrap(), manifest);
+        assert_eq!(restored.list_facts(&scope, FactFilter::default()).unwrap().len(), 2);
+        assert_eq!(restored.list_facts(&agent_pool, FactFilter::default()).unwrap().len(), 1);
+        assert_eq!(restored.list_procedures(&scope, "travel", None).unwrap().len(), 1);
+        assert_eq!(list_tenant_procedures(&restored, "default").unwrap().len(), 1);
+        assert_eq!(restored.get_events_since(&scope, 0, None).unwrap().len(), 1);
+        assert!(restored.get_stm(&scope).unwrap().is_some());
+
+        let mut data = zstd::decode_all(archive.as_slice()).unwrap();
+        let at = data.windows(6).rposition(|window| window == b"Lisbon").unwrap();
+        data[at] = b'l';
+        let tampered = zstd::encode_all(data.as_slice(), 3).unwrap();
+        let err = import_tenant_archive(&SqliteStore::new_in_memory().unwrap(), &tampered);
+        assert!(matches!(err, Err(StoreError::InvalidInput(message)) if message.contains("facts")));
+        assert!(verify_archive(b"not an archive").is_err());
+    }
+
+    #[test]
+    fn import_remaps_scope_and_regenerates_ids() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "prod".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut event = Event::new(scope.clone(), EventKind::Message, json!("I live in Lisbon"));
+        event.event_id = "e1".to_string();
+        store.append_event(event).unwrap();
+        let fact = Fact {
+            fact_id: "f1".to_string(),
+            sources: vec!["e1".to_string()],
+            ..Fact::new("user.city", json!("Lisbon"))
+        };
+        store.upsert_fact(&scope, fact).unwrap();
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: "f1".to_string(),
+        };
+        store.suppress_memory(&scope, item, "outdated").unwrap();
+
+        let archive = export_tenant_archive(&store, "prod").unwrap();
+        let options = ImportOptions {
+            tenant_id: Some("staging".to_string()),
+            user_ids: BTreeMap::from([("user1".to_string(), "tester".to_string())]),
+            regenerate_ids: true,
+        };
+        let report = import_tenant_archive_with(&store, &archive, &options).unwrap();
+        assert_eq!(report.tenant_id, "staging");
+        assert_eq!(report.user_ids["user1"], "tester");
+        assert_eq!(report.ids.len(), 2);
+
+        let staged = Scope {
+            tenant_id: "staging".to_string(),
+            user_id: "tester".to_string(),
+            ..scope.clone()
+        };
+        let events = store.get_events_since(&staged, 0, None).unwrap();
+        assert_eq!(events[0].event_id, report.ids["e1"]);
+        let facts = store.list_facts(&staged, FactFilter::default()).unwrap();
+        assert_eq!(facts[0].fact_id, report.ids["f1"]);
+        assert_eq!(facts[0].sources, vec![report.ids["e1"].clone()]);
+        assert_eq!(store.list_suppressions(&staged).unwrap()[0].item.id, report.ids["f1"]);
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap()[0].fact_id, "f1");
+    }
+}
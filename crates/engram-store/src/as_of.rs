@@ -0,0 +1,555 @@
+use chrono::{DateTime, Utc};
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::composer::compose_packet;
+use crate::lifecycle::run_stm;
+use crate::shared_facts::list_facts_with_shared;
+use crate::{
+    apply_limit, is_preference, replay_working_state, with_read_preference, BuildRequest, Change,
+    ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+/// Builds the packet `request` would have produced at `at`, from what the store knew
+/// then, to reconstruct what an agent was told before an incident:
+///
+/// - events are those logged by `at`, and STM is rebuilt from the run's messages
+///   among them, as [`rebuild_derived_memory`](crate::rebuild_derived_memory) does;
+/// - the working state is replayed from the run's `state_patch` events if it was
+///   journaled (see [`StatePatchJournal`](crate::StatePatchJournal)), otherwise it is
+///   the current one;
+/// - facts take the latest value recorded by `at` in their history (see
+///   [`FactVersion`]) and are recalled as valid at `at`, unless `cues.valid_at` asks
+///   for another instant; facts created later are left out;
+/// - episodes are those whose time range ended by `at`, and suppressions and run
+///   outcomes those recorded by then;
+/// - recency and episode windows are measured from `at`.
+///
+/// Procedures and insights carry no timestamps and are recalled as they are now. The
+/// build is never persisted, and `explain.as_of` records `at`.
+pub fn build_memory_packet_as_of<S: Store + ?Sized>(
+    store: &S,
+    mut request: BuildRequest,
+    at: DateTime<Utc>,
+) -> StoreResult<MemoryPacket> {
+    request.cues.valid_at = request.cues.valid_at.or(Some(at));
+    request.persist = false;
+    let view = AsOfStore { inner: store, at };
+    let mut packet =
+        with_read_preference(request.read_preference, || compose_packet(&view, request, at))?;
+    packet.explain.insert("as_of".to_string(), json!(at));
+    Ok(packet)
+}
+
+/// Read view of `inner` as it was at `at`, for [`build_memory_packet_as_of`]. Writes
+/// go through to `inner`.
+struct AsOfStore<'a, S: ?Sized> {
+    inner: &'a S,
+    at: DateTime<Utc>,
+}
+
+impl<S: Store + ?Sized> AsOfStore<'_, S> {
+    /// `range` cut off at `at`.
+    fn until(&self, range: Option<TimeRangeFilter>) -> TimeRangeFilter {
+        let range = range.unwrap_or_default();
+        TimeRangeFilter {
+            start: range.start,
+            end: Some(range.end.map_or(self.at, |end| end.min(self.at))),
+        }
+    }
+
+    fn ended(&self, episode: &Episode) -> bool {
+        episode.time_range.end.unwrap_or(episode.time_range.start) <= self.at
+    }
+
+    /// The run's events logged by `at`.
+    fn run_events(&self, scope: &Scope) -> StoreResult<Vec<Event>> {
+        let mut events = self.inner.get_events_since(scope, 0, None)?;
+        events.retain(|event| event.ts <= self.at);
+        Ok(events)
+    }
+}
+
+fn fact_matches(filter: &FactFilter, fact: &Fact) -> bool {
+    filter.status.as_ref().is_none_or(|statuses| statuses.contains(&fact.status))
+        && filter.pinned.is_none_or(|pinned| fact.pinned == pinned)
+        && filter.preferences.is_none_or(|wanted| is_preference(fact) == wanted)
+        && filter.keys.as_ref().is_none_or(|keys| keys.contains(&fact.fact_key))
+        && filter.valid_at.is_none_or(|at| {
+            fact.validity.valid_from.is_none_or(|from| from <= at)
+                && fact.validity.valid_to.is_none_or(|to| to >= at)
+        })
+}
+
+impl<S: Store + ?Sized> Store for AsOfStore<'_, S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.inner.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, self.until(Some(range)), limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let mut page = self.inner.list_events_page(scope, cursor, limit)?;
+        if page.events.iter().any(|event| event.ts > self.at) {
+            // Pages run in time order, so no later page has older events.
+            page.events.retain(|event| event.ts <= self.at);
+            page.next_cursor = None;
+        }
+        Ok(page)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let mut events = self.inner.get_events_since(scope, seq, limit)?;
+        events.retain(|event| event.ts <= self.at);
+        Ok(events)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        let mut events = self.inner.get_events_by_ids(scope, event_ids)?;
+        events.retain(|event| event.ts <= self.at);
+        Ok(events)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let filter = EventFilter {
+            time_range: Some(self.until(filter.time_range)),
+            ..filter
+        };
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let query = TextQuery {
+            time_range: Some(self.until(query.time_range.clone())),
+            ..query.clone()
+        };
+        self.inner.search_events(scope, &query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        let mut episodes = self.inner.search_episodes(scope, query)?;
+        episodes.retain(|episode| self.ended(episode));
+        Ok(episodes)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        match replay_working_state(&self.run_events(scope)?)? {
+            Some(state) => Ok(Some(state)),
+            None => self.inner.get_working_state(scope),
+        }
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.inner.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.inner.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        Ok(Some(run_stm(&self.run_events(scope)?)))
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        if filter.include_shared {
+            return list_facts_with_shared(self, scope, filter);
+        }
+        let current = self.inner.list_facts(scope, FactFilter::default())?;
+        let mut keys: Vec<&str> = current.iter().map(|fact| fact.fact_key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut facts = Vec::new();
+        for key in keys {
+            let history = self.inner.list_fact_history(scope, key)?;
+            for fact in current.iter().filter(|fact| fact.fact_key == key) {
+                let mut versions = history
+                    .iter()
+                    .filter(|version| version.fact.fact_id == fact.fact_id)
+                    .peekable();
+                if versions.peek().is_none() {
+                    // Written before the store kept history; taken as it is now.
+                    facts.push(fact.clone());
+                    continue;
+                }
+                let known = versions
+                    .filter(|version| version.recorded_at <= self.at)
+                    .max_by_key(|version| version.version);
+                facts.extend(known.map(|version| version.fact.clone()));
+            }
+        }
+        facts.retain(|fact| fact_matches(&filter, fact));
+        apply_limit(&mut facts, filter.limit);
+        Ok(facts)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.inner.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let mut history = self.inner.list_fact_history(scope, fact_key)?;
+        history.retain(|version| version.recorded_at <= self.at);
+        Ok(history)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        let limit = filter.limit;
+        let filter = EpisodeFilter {
+            limit: None,
+            ..filter
+        };
+        let mut episodes = self.inner.list_episodes(scope, filter)?;
+        episodes.retain(|episode| self.ended(episode));
+        apply_limit(&mut episodes, limit);
+        Ok(episodes)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.inner.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        let mut suppressions = self.inner.list_suppressions(scope)?;
+        suppressions.retain(|suppression| suppression.suppressed_at <= self.at);
+        Ok(suppressions)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        let mut outcomes = self.inner.list_run_outcomes(scope, None)?;
+        outcomes.retain(|outcome| outcome.recorded_at <= self.at);
+        apply_limit(&mut outcomes, limit);
+        Ok(outcomes)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, EventKind, InMemoryStore};
+    use engram_types::{Purpose, TimeRange};
+    use serde_json::json;
+
+    #[test]
+    fn recalls_what_the_store_knew_at_the_time() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let say = |content: &str| {
+            let payload = json!({"role": "user", "content": content});
+            store.append_event(Event::new(scope.clone(), EventKind::Message, payload)).unwrap();
+        };
+        let episode = |summary: &str| {
+            let mut episode = Episode::new(summary);
+            episode.time_range = TimeRange {
+                start: Utc::now(),
+                end: Some(Utc::now()),
+            };
+            store.append_episode(&scope, episode).unwrap();
+        };
+
+        let mut plan = Fact::new("user.plan", json!("basic"));
+        store.upsert_fact(&scope, plan.clone()).unwrap();
+        say("please cancel my order");
+        episode("Order cancellation requested");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let at = Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        plan.value = json!("pro");
+        store.upsert_fact(&scope, plan).unwrap();
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        say("actually, upgrade me instead");
+        episode("Upgrade to pro");
+
+        let request = BuildRequest::new(scope.clone(), Purpose::Responder);
+        let then = build_memory_packet_as_of(&store, request.clone(), at).unwrap();
+        let facts: Vec<_> = then.long_term.facts.iter().map(|fact| &fact.value).collect();
+        assert_eq!(facts, vec![&json!("basic")]);
+        let quotes: Vec<_> = then.short_term.key_quotes.iter().map(|q| q.quote.as_str()).collect();
+        assert_eq!(quotes, vec!["please cancel my order"]);
+        let episodes = &then.long_term.episodes;
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].summary, "Order cancellation requested");
+        assert_eq!(then.explain["as_of"], json!(at));
+        assert!(store.list_context_builds(&scope, None).unwrap().is_empty());
+
+        let now = build_memory_packet(&store, request).unwrap();
+        assert_eq!(now.long_term.facts.len(), 2);
+        assert_eq!(now.long_term.episodes.len(), 2);
+    }
+}
@@ -0,0 +1,290 @@
+use chrono::Utc;
+use engram_types::{FactStatus, Scope};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use crate::composer::compute_recency_score;
+use crate::{EpisodeFilter, FactFilter, MemoryKind, MemoryRef, Store, StoreResult};
+
+/// How much long-term memory a user may keep under one agent, for
+/// [`enforce_memory_budget`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBudget {
+    /// Bytes the scope's facts and episodes may take, measured as their JSON encoding.
+    pub max_bytes: u64,
+    /// Most recently active runs of the scope whose persisted context builds count as
+    /// usage.
+    #[serde(default = "default_usage_runs")]
+    pub usage_runs: usize,
+    /// Persisted context builds read per run.
+    #[serde(default = "default_usage_builds")]
+    pub usage_builds: usize,
+}
+
+fn default_usage_runs() -> usize {
+    20
+}
+
+fn default_usage_builds() -> usize {
+    20
+}
+
+impl MemoryBudget {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            usage_runs: default_usage_runs(),
+            usage_builds: default_usage_builds(),
+        }
+    }
+}
+
+/// What [`enforce_memory_budget`] did to the scope.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvictionReport {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Evicted items, least valuable first.
+    pub evicted: Vec<MemoryRef>,
+}
+
+struct Candidate {
+    item: MemoryRef,
+    bytes: u64,
+    value: f64,
+}
+
+/// Bytes the scope's facts and episodes take, measured as their JSON encoding.
+pub fn memory_footprint<S: Store + ?Sized>(store: &S, scope: &Scope) -> StoreResult<u64> {
+    Ok(candidates(store, scope, &HashMap::new())?
+        .iter()
+        .map(|candidate| candidate.bytes)
+        .sum())
+}
+
+/// Deletes the scope's least valuable facts and episodes until the rest fit
+/// `budget`, so storage stays bounded for users on a quota.
+///
+/// An item's value is its confidence (facts, halved when disputed and cut to a tenth
+/// when deprecated) or recency (episodes, as recall scores it), times one plus the
+/// number of persisted context builds it appeared in. Suppressed items have no value
+/// and go first; pinned facts are never evicted, even if they alone exceed the budget.
+pub fn enforce_memory_budget<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    budget: &MemoryBudget,
+) -> StoreResult<EvictionReport> {
+    let usage = recalled_counts(store, scope, budget)?;
+    let mut candidates = candidates(store, scope, &usage)?;
+    let bytes_before: u64 = candidates.iter().map(|candidate| candidate.bytes).sum();
+    let mut report = EvictionReport {
+        bytes_before,
+        bytes_after: bytes_before,
+        evicted: Vec::new(),
+    };
+    if bytes_before <= budget.max_bytes {
+        return Ok(report);
+    }
+
+    let suppressed: HashSet<MemoryRef> = store
+        .list_suppressions(scope)?
+        .into_iter()
+        .map(|suppression| suppression.item)
+        .collect();
+    for candidate in candidates.iter_mut() {
+        if suppressed.contains(&candidate.item) {
+            candidate.value = 0.0;
+        }
+    }
+    candidates.retain(|candidate| candidate.value.is_finite());
+    // Among equally valuable items the larger ones go first.
+    candidates.sort_by(|a, b| {
+        a.value
+            .partial_cmp(&b.value)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.bytes.cmp(&a.bytes))
+            .then_with(|| a.item.id.cmp(&b.item.id))
+    });
+    for candidate in candidates {
+        if report.bytes_after <= budget.max_bytes {
+            break;
+        }
+        report.bytes_after -= candidate.bytes;
+        report.evicted.push(candidate.item);
+    }
+    store.evict_memory(scope, &report.evicted)?;
+    debug!(
+        "evicted {} items of {}/{}/{}: {} -> {} bytes",
+        report.evicted.len(),
+        scope.tenant_id,
+        scope.user_id,
+        scope.agent_id,
+        report.bytes_before,
+        report.bytes_after
+    );
+    Ok(report)
+}
+
+/// The scope's facts and episodes with their size and value. Pinned facts are
+/// valued at infinity.
+fn candidates<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    usage: &HashMap<MemoryRef, usize>,
+) -> StoreResult<Vec<Candidate>> {
+    let uses = |item: &MemoryRef| 1.0 + usage.get(item).copied().unwrap_or_default() as f64;
+    let mut out = Vec::new();
+    for fact in store.list_facts(scope, FactFilter::default())? {
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: fact.fact_id.clone(),
+        };
+        let status_weight = match fact.status {
+            FactStatus::Active => 1.0,
+            FactStatus::Disputed => 0.5,
+            FactStatus::Deprecated => 0.1,
+        };
+        let value = if fact.pinned {
+            f64::INFINITY
+        } else {
+            fact.confidence * status_weight * uses(&item)
+        };
+        out.push(Candidate {
+            bytes: serde_json::to_vec(&fact)?.len() as u64,
+            item,
+            value,
+        });
+    }
+    let now = Utc::now();
+    for episode in store.list_episodes(scope, EpisodeFilter::default())? {
+        let item = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode.episode_id.clone(),
+        };
+        let value = compute_recency_score(&episode, now) * uses(&item);
+        out.push(Candidate {
+            bytes: serde_json::to_vec(&episode)?.len() as u64,
+            item,
+            value,
+        });
+    }
+    Ok(out)
+}
+
+/// How many persisted context builds of the scope's recent runs included each item.
+fn recalled_counts<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    budget: &MemoryBudget,
+) -> StoreResult<HashMap<MemoryRef, usize>> {
+    let mut counts = HashMap::new();
+    if budget.usage_runs == 0 || budget.usage_builds == 0 {
+        return Ok(counts);
+    }
+    let runs = store
+        .list_scopes(Some(&scope.tenant_id), None)?
+        .into_iter()
+        .filter(|run| run.user_id == scope.user_id && run.agent_id == scope.agent_id)
+        .take(budget.usage_runs);
+    for run in runs {
+        for packet in store.list_context_builds(&run, Some(budget.usage_builds))? {
+            let facts = packet.long_term.facts.iter().chain(&packet.long_term.preferences);
+            let facts = facts.map(|fact| MemoryRef {
+                kind: MemoryKind::Fact,
+                id: fact.fact_id.clone(),
+            });
+            let episodes = packet.long_term.episodes.iter().map(|episode| MemoryRef {
+                kind: MemoryKind::Episode,
+                id: episode.episode_id.clone(),
+            });
+            for item in facts.chain(episodes) {
+                *counts.entry(item).or_default() += 1;
+            }
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, Event, EventKind, InMemoryStore};
+    use engram_types::{CompressionLevel, Episode, Fact, Purpose, TimeRange};
+    use serde_json::json;
+
+    #[test]
+    fn evicts_least_valuable_memory_first() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        let fact = |id: &str, confidence: f64| {
+            let mut fact = Fact::new(format!("key.{}", id), json!("x".repeat(200)));
+            fact.fact_id = id.to_string();
+            fact.confidence = confidence;
+            fact
+        };
+        let mut pinned = fact("pinned", 0.1);
+        pinned.pinned = true;
+        store.upsert_fact(&scope, pinned).unwrap();
+        store.upsert_fact(&scope, fact("used", 0.3)).unwrap();
+        store.upsert_fact(&scope, fact("unsure", 0.4)).unwrap();
+        store.upsert_fact(&scope, fact("sure", 0.9)).unwrap();
+        store
+            .append_episode(
+                &scope,
+                Episode {
+                    episode_id: "old".to_string(),
+                    time_range: TimeRange {
+                        start: Utc::now() - chrono::Duration::days(400),
+                        end: None,
+                    },
+                    summary: "y".repeat(20),
+                    highlights: Vec::new(),
+                    tags: Vec::new(),
+                    entities: Vec::new(),
+                    sources: Vec::new(),
+                    compression_level: CompressionLevel::Raw,
+                    recency_score: None,
+                    lang: None,
+                },
+            )
+            .unwrap();
+
+        // A persisted build that recalled "used" makes it worth more than "unsure".
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        let mut packet = build_memory_packet(&store, request).unwrap();
+        packet.long_term.facts.retain(|fact| fact.fact_id == "used");
+        store.write_context_build(&scope, packet).unwrap();
+
+        let before = memory_footprint(&store, &scope).unwrap();
+        let fact_bytes = serde_json::to_vec(&fact("used", 0.3)).unwrap().len() as u64;
+        let report =
+            enforce_memory_budget(&store, &scope, &MemoryBudget::new(before - fact_bytes - 1))
+                .unwrap();
+        let evicted: Vec<&str> = report.evicted.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(evicted, vec!["old", "unsure"]);
+        assert_eq!(report.bytes_before, before);
+        assert_eq!(report.bytes_after, memory_footprint(&store, &scope).unwrap());
+
+        let report = enforce_memory_budget(&store, &scope, &MemoryBudget::new(0)).unwrap();
+        assert_eq!(report.evicted.len(), 2);
+        let facts = store.list_facts(&scope, FactFilter::default()).unwrap();
+        let kept: Vec<&str> = facts.iter().map(|fact| fact.fact_id.as_str()).collect();
+        assert_eq!(kept, vec!["pinned"]);
+        assert!(store
+            .list_episodes(&scope, EpisodeFilter::default())
+            .unwrap()
+            .is_empty());
+    }
+}
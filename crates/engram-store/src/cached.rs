@@ -0,0 +1,941 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::outbox::{deliverable, warn_skipped, SkippedGaps};
+use crate::{
+    read_preference, Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event,
+    EventFilter, EventPage, FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding,
+    MemoryRef, ProcedureCandidateFilter, ReadPreference, RecordKind, RecordRef, RunKey, RunOutcome,
+    RunWorkingState, SessionKey, SourceCredibility, StmState, Store, StoreCapabilities, StoreError,
+    StoreMetrics, StoreResult, Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity,
+    UserLocale, WorkingStatePatch, WriteNotifications,
+};
+
+const CHANGE_BATCH: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    /// How far cached reads may lag writes made by other processes. Before serving
+    /// from the cache, the change log is polled if it was last read longer ago than
+    /// this; `Duration::ZERO` polls on every read.
+    pub max_staleness: Duration,
+    /// Entries per cache; a full cache is cleared rather than evicted piecemeal.
+    pub capacity: usize,
+    /// How long a missing `seq` holds the cursor back, as
+    /// [`OutboxOptions::gap_timeout`](crate::OutboxOptions::gap_timeout) does. Changes
+    /// after the gap still evict as soon as they are read; the held cursor makes sure the
+    /// missing one is read too once it commits.
+    pub gap_timeout: Duration,
+    /// How long a gap skipped after `gap_timeout` is still looked for on each poll, as
+    /// [`OutboxOptions::late_change_window`](crate::OutboxOptions::late_change_window)
+    /// does; a change that commits in it by then still evicts.
+    pub late_change_window: Duration,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            max_staleness: Duration::from_secs(1),
+            capacity: 10_000,
+            gap_timeout: Duration::from_secs(10),
+            late_change_window: Duration::from_secs(600),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    cursor: u64,
+    polled_at: Option<Instant>,
+    /// Changes after `cursor` already read, past a gap that holds it back.
+    seen: BTreeSet<u64>,
+    /// First missing `seq` of each gap after `cursor`, and when it was first seen.
+    gaps: HashMap<u64, Instant>,
+    skipped: SkippedGaps,
+    /// `None` values cache a miss, so unknown runs do not hit the backend each time.
+    working_states: HashMap<RunKey, Option<WorkingState>>,
+    stm: HashMap<SessionKey, Option<StmState>>,
+    /// Bumped per key on every eviction, so a read that raced one does not cache what
+    /// it read. `epoch` is bumped instead when the counts are cleared.
+    run_generations: HashMap<RunKey, u64>,
+    session_generations: HashMap<SessionKey, u64>,
+    epoch: u64,
+}
+
+impl CacheState {
+    fn invalidate(&mut self, op: &ChangeOp) {
+        match op {
+            ChangeOp::PatchWorkingState { scope, .. } => self.evict_run(&RunKey::from(scope)),
+            ChangeOp::UpdateStm { scope, .. } => self.evict_session(&SessionKey::from(scope)),
+            _ => {}
+        }
+    }
+
+    fn evict_run(&mut self, key: &RunKey) {
+        self.working_states.remove(key);
+        *self.run_generations.entry(key.clone()).or_default() += 1;
+    }
+
+    fn evict_session(&mut self, key: &SessionKey) {
+        self.stm.remove(key);
+        *self.session_generations.entry(key.clone()).or_default() += 1;
+    }
+
+    fn evict_scope(&mut self, scope: &Scope) {
+        let within = |session_id: &str, run_id: Option<&str>| {
+            (scope.session_id.is_empty() || scope.session_id == session_id)
+                && (scope.run_id.is_empty() || run_id.is_none_or(|run_id| scope.run_id == run_id))
+        };
+        let runs: Vec<RunKey> = self
+            .working_states
+            .keys()
+            .filter(|key| {
+                key.tenant_id == scope.tenant_id
+                    && key.user_id == scope.user_id
+                    && key.agent_id == scope.agent_id
+                    && within(&key.session_id, Some(&key.run_id))
+            })
+            .cloned()
+            .collect();
+        let sessions: Vec<SessionKey> = self
+            .stm
+            .keys()
+            .filter(|key| {
+                key.tenant_id == scope.tenant_id
+                    && key.user_id == scope.user_id
+                    && key.agent_id == scope.agent_id
+                    && within(&key.session_id, None)
+            })
+            .cloned()
+            .collect();
+        for key in &runs {
+            self.evict_run(key);
+        }
+        for key in &sessions {
+            self.evict_session(key);
+        }
+        self.evict_run(&RunKey::from(scope));
+        self.evict_session(&SessionKey::from(scope));
+        if scope.session_id.is_empty() || scope.run_id.is_empty() {
+            // Reads of keys under the scope that are not cached yet may be in flight.
+            self.epoch += 1;
+        }
+    }
+
+    fn run_generation(&self, key: &RunKey) -> (u64, u64) {
+        (self.epoch, self.run_generations.get(key).copied().unwrap_or(0))
+    }
+
+    fn session_generation(&self, key: &SessionKey) -> (u64, u64) {
+        (self.epoch, self.session_generations.get(key).copied().unwrap_or(0))
+    }
+
+    fn clear(&mut self) {
+        self.working_states.clear();
+        self.stm.clear();
+        self.run_generations.clear();
+        self.session_generations.clear();
+        self.epoch += 1;
+    }
+}
+
+/// Store wrapper that caches working state and STM reads in process, and keeps the
+/// cache coherent by tailing the inner store's change log: every write recorded
+/// there, by this process or another one sharing the backend, evicts the entries it
+/// touched. Writes through this wrapper evict immediately.
+///
+/// The inner store (in every process) should be a [`ChangeLogStore`], or writes
+/// made elsewhere are only seen once the entry is evicted for another reason.
+///
+/// Calls under [`ReadPreference::CacheOnly`] get cached entries however stale and
+/// treat misses as absent without asking the inner store; calls under
+/// [`ReadPreference::BypassCache`] read through and refill the cache.
+///
+/// [`ChangeLogStore`]: crate::ChangeLogStore
+#[derive(Debug)]
+pub struct CachedStore<S> {
+    inner: S,
+    options: CacheOptions,
+    state: Mutex<CacheState>,
+}
+
+impl<S: Store> CachedStore<S> {
+    /// Wraps `inner`, starting a batch behind the current end of its change log: an
+    /// empty cache has nothing older to evict, but a write still committing may hold
+    /// a `seq` below the end.
+    pub fn new(inner: S, options: CacheOptions) -> StoreResult<Self> {
+        let cursor = inner.last_change_seq()?.saturating_sub(CHANGE_BATCH as u64);
+        Ok(Self {
+            inner,
+            options,
+            state: Mutex::new(CacheState {
+                cursor,
+                ..CacheState::default()
+            }),
+        })
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Reads the change log up to its end and evicts what it touched. Returns how
+    /// many changes were read for the first time.
+    pub fn poll_changes(&self) -> StoreResult<usize> {
+        let mut state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        self.poll_locked(&mut state)
+    }
+
+    /// Waits up to `timeout` for writes from `notifications`, such as a Postgres
+    /// `PgListener`, and evicts the entries of each write's scope. Returns how many
+    /// writes were taken. Called in a loop, it evicts as soon as another process
+    /// commits, and `max_staleness` only bounds writes the notifications miss.
+    pub fn evict_notified(
+        &self,
+        notifications: &mut dyn WriteNotifications,
+        timeout: Duration,
+    ) -> StoreResult<usize> {
+        let mut taken = 0;
+        let mut wait = timeout;
+        while let Some(scope) = notifications.next_write(wait)? {
+            self.evict_scope(&scope)?;
+            taken += 1;
+            wait = Duration::ZERO;
+        }
+        Ok(taken)
+    }
+
+    /// Evicts the entries of `scope`, and those under it when its session or run id
+    /// is empty.
+    pub fn evict_scope(&self, scope: &Scope) -> StoreResult<()> {
+        self.evict(|cache| cache.evict_scope(scope))
+    }
+
+    pub fn invalidate_all(&self) -> StoreResult<()> {
+        let mut state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        state.clear();
+        Ok(())
+    }
+
+    /// Evicts for every change not read before. The cursor stops before a gap in `seq`
+    /// until the missing change is read or [`CacheOptions::gap_timeout`] passes, so a
+    /// write that commits after a later one still evicts what it touched; a gap passed
+    /// over is looked for again for [`CacheOptions::late_change_window`].
+    fn poll_locked(&self, state: &mut CacheState) -> StoreResult<usize> {
+        let late = state
+            .skipped
+            .late_changes(&self.inner, self.options.late_change_window)?;
+        for change in &late {
+            state.invalidate(&change.op);
+        }
+        state.skipped.found(&late);
+        let mut read = late.len();
+        let mut after = state.cursor;
+        let mut held = false;
+        loop {
+            let changes = self.inner.list_changes(after, Some(CHANGE_BATCH))?;
+            let Some(last) = changes.last() else {
+                break;
+            };
+            for change in &changes {
+                if state.seen.insert(change.seq) {
+                    state.invalidate(&change.op);
+                    read += 1;
+                }
+            }
+            if !held {
+                let mut passed = Vec::new();
+                let timeout = self.options.gap_timeout;
+                let ready = deliverable(after, &changes, &mut state.gaps, timeout, &mut passed);
+                if let Some(ready_last) = changes[..ready].last() {
+                    state.cursor = ready_last.seq;
+                }
+                held = ready < changes.len();
+                warn_skipped("cache", &passed, timeout);
+                state.skipped.skip(passed);
+            }
+            after = last.seq;
+        }
+        let cursor = state.cursor;
+        state.seen.retain(|&seq| seq > cursor);
+        state.gaps.retain(|&seq, _| seq > cursor);
+        state.polled_at = Some(Instant::now());
+        if read > 0 {
+            debug!("cache read {} changes, cursor at {}", read, state.cursor);
+        }
+        Ok(read)
+    }
+
+    /// Looks an entry up, polling the change log first when it is due. Under
+    /// [`ReadPreference::CacheOnly`] the entry is served without polling; under
+    /// [`ReadPreference::BypassCache`] the lookup misses.
+    fn cached<T>(&self, lookup: impl FnOnce(&CacheState) -> Option<T>) -> StoreResult<Option<T>> {
+        let mut state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        match read_preference() {
+            ReadPreference::BypassCache => return Ok(None),
+            ReadPreference::CacheOnly => {}
+            ReadPreference::Primary | ReadPreference::ReplicaOk => {
+                let due = state
+                    .polled_at
+                    .is_none_or(|at| at.elapsed() >= self.options.max_staleness);
+                if due {
+                    self.poll_locked(&mut state)?;
+                }
+            }
+        }
+        Ok(lookup(&state))
+    }
+
+    fn fill(&self, insert: impl FnOnce(&mut CacheState)) -> StoreResult<()> {
+        let mut state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        let capacity = self.options.capacity.max(1);
+        if state.working_states.len() >= capacity || state.stm.len() >= capacity {
+            state.working_states.clear();
+            state.stm.clear();
+        }
+        if state.run_generations.len() >= capacity || state.session_generations.len() >= capacity
+        {
+            state.clear();
+        }
+        insert(&mut state);
+        Ok(())
+    }
+
+    /// The eviction count of a key, taken before reading it from the inner store;
+    /// [`CachedStore::fill`] only caches the read if it is unchanged.
+    fn generation(&self, read: impl FnOnce(&CacheState) -> (u64, u64)) -> StoreResult<(u64, u64)> {
+        let state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(read(&state))
+    }
+
+    fn evict(&self, remove: impl FnOnce(&mut CacheState)) -> StoreResult<()> {
+        let mut state = self.state.lock().map_err(|_| StoreError::Poisoned)?;
+        remove(&mut state);
+        Ok(())
+    }
+}
+
+impl<S: Store> Store for CachedStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.inner.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.inner.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        let key = RunKey::from(scope);
+        if let Some(state) = self.cached(|cache| cache.working_states.get(&key).cloned())? {
+            return Ok(state);
+        }
+        if read_preference() == ReadPreference::CacheOnly {
+            return Ok(None);
+        }
+        let generation = self.generation(|cache| cache.run_generation(&key))?;
+        let state = self.inner.get_working_state(scope)?;
+        self.fill(|cache| {
+            if cache.run_generation(&key) == generation {
+                cache.working_states.insert(key, state.clone());
+            }
+        })?;
+        Ok(state)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        let state = self.inner.patch_working_state(scope, patch)?;
+        self.evict(|cache| {
+            cache.evict_run(&RunKey::from(scope));
+        })?;
+        Ok(state)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let keys: Vec<RunKey> = patches.iter().map(|(scope, _)| RunKey::from(scope)).collect();
+        let states = self.inner.patch_working_states(patches)?;
+        self.evict(|cache| {
+            for key in &keys {
+                cache.evict_run(key);
+            }
+        })?;
+        Ok(states)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        let key = SessionKey::from(scope);
+        if let Some(stm) = self.cached(|cache| cache.stm.get(&key).cloned())? {
+            return Ok(stm);
+        }
+        if read_preference() == ReadPreference::CacheOnly {
+            return Ok(None);
+        }
+        let generation = self.generation(|cache| cache.session_generation(&key))?;
+        let stm = self.inner.get_stm(scope)?;
+        self.fill(|cache| {
+            if cache.session_generation(&key) == generation {
+                cache.stm.insert(key, stm.clone());
+            }
+        })?;
+        Ok(stm)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(scope, stm)?;
+        self.evict(|cache| {
+            cache.evict_session(&SessionKey::from(scope));
+        })
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.inner.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<Episode>> {
+        self.inner.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.inner.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn merge_working_state(&self, scope: &Scope, remote: WorkingState) -> StoreResult<WorkingState> {
+        // The default reads the local side through the cache; merge against the
+        // backend instead so a stale entry cannot drop a concurrent write.
+        let state = self.inner.merge_working_state(scope, remote)?;
+        self.evict(|cache| {
+            cache.evict_run(&RunKey::from(scope));
+        })?;
+        Ok(state)
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_read_preference, ChangeLogStore, InMemoryStore};
+    use std::sync::Arc;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    fn set_goal(goal: &str) -> WorkingStatePatch {
+        WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            ..WorkingStatePatch::default()
+        }
+    }
+
+    #[test]
+    fn change_log_invalidates_other_processes_caches() {
+        let scope = sample_scope();
+        let shared = Arc::new(ChangeLogStore::new(InMemoryStore::new()));
+        shared.patch_working_state(&scope, set_goal("draft")).unwrap();
+
+        let lazy = CachedStore::new(
+            shared.clone(),
+            CacheOptions {
+                max_staleness: Duration::from_secs(3600),
+                ..CacheOptions::default()
+            },
+        )
+        .unwrap();
+        let eager = CachedStore::new(
+            shared.clone(),
+            CacheOptions {
+                max_staleness: Duration::ZERO,
+                ..CacheOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(lazy.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+        assert_eq!(eager.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+
+        shared.patch_working_state(&scope, set_goal("ship")).unwrap();
+        assert_eq!(eager.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+        assert_eq!(lazy.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+        assert_eq!(lazy.poll_changes().unwrap(), 1);
+        assert_eq!(lazy.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+
+        lazy.patch_working_state(&scope, set_goal("done")).unwrap();
+        assert_eq!(lazy.get_working_state(&scope).unwrap().unwrap().goal, "done");
+        assert_eq!(eager.get_working_state(&scope).unwrap().unwrap().goal, "done");
+
+        // Cache-only reads serve stale entries and never look misses up; bypassing reads
+        // through and refills the cache.
+        shared.patch_working_state(&scope, set_goal("reopened")).unwrap();
+        let stale = with_read_preference(ReadPreference::CacheOnly, || {
+            eager.get_working_state(&scope)
+        });
+        assert_eq!(stale.unwrap().unwrap().goal, "done");
+        let other = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+        shared.patch_working_state(&other, set_goal("elsewhere")).unwrap();
+        let missed = with_read_preference(ReadPreference::CacheOnly, || {
+            eager.get_working_state(&other)
+        });
+        assert!(missed.unwrap().is_none());
+        let fresh = with_read_preference(ReadPreference::BypassCache, || {
+            lazy.get_working_state(&scope)
+        });
+        assert_eq!(fresh.unwrap().unwrap().goal, "reopened");
+        assert_eq!(lazy.get_working_state(&scope).unwrap().unwrap().goal, "reopened");
+    }
+
+    #[test]
+    fn changes_committed_behind_a_later_one_still_evict() {
+        let scope = sample_scope();
+        let other = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+        let shared = Arc::new(InMemoryStore::new());
+        for _ in 0..CHANGE_BATCH + 500 {
+            shared
+                .append_change(ChangeOp::UpdateStm {
+                    scope: other.clone(),
+                    stm: StmState::default(),
+                })
+                .unwrap();
+        }
+        let cache = CachedStore::new(shared.clone(), CacheOptions::default()).unwrap();
+        // Only the last batch is read, not the whole history.
+        assert_eq!(cache.poll_changes().unwrap(), CHANGE_BATCH);
+
+        shared.patch_working_state(&scope, set_goal("draft")).unwrap();
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+
+        // The write to `scope` takes the lower seq but commits after the one to `other`.
+        let state = shared.patch_working_state(&scope, set_goal("ship")).unwrap();
+        let late = ChangeOp::PatchWorkingState {
+            scope: scope.clone(),
+            state: Box::new(state),
+        };
+        let seq = shared.append_change(late).unwrap();
+        shared
+            .append_change(ChangeOp::PatchWorkingState {
+                scope: other.clone(),
+                state: Box::default(),
+            })
+            .unwrap();
+        let pending = {
+            let mut changes = shared.changes.write().unwrap();
+            let idx = changes.iter().position(|change| change.seq == seq).unwrap();
+            changes.remove(idx)
+        };
+        assert_eq!(cache.poll_changes().unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+
+        {
+            let mut changes = shared.changes.write().unwrap();
+            let idx = changes.iter().position(|change| change.seq > seq).unwrap();
+            changes.insert(idx, pending);
+        }
+        assert_eq!(cache.poll_changes().unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+        assert_eq!(cache.poll_changes().unwrap(), 0);
+    }
+
+    #[test]
+    fn changes_committed_in_a_skipped_gap_still_evict() {
+        let scope = sample_scope();
+        let shared = Arc::new(InMemoryStore::new());
+        let cache = CachedStore::new(
+            shared.clone(),
+            CacheOptions {
+                gap_timeout: Duration::ZERO,
+                ..CacheOptions::default()
+            },
+        )
+        .unwrap();
+        shared.patch_working_state(&scope, set_goal("draft")).unwrap();
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+
+        let state = shared.patch_working_state(&scope, set_goal("ship")).unwrap();
+        let late = shared
+            .append_change(ChangeOp::PatchWorkingState {
+                scope: scope.clone(),
+                state: Box::new(state),
+            })
+            .unwrap();
+        shared
+            .append_change(ChangeOp::UpdateStm {
+                scope: scope.clone(),
+                stm: StmState::default(),
+            })
+            .unwrap();
+        let pending = {
+            let mut changes = shared.changes.write().unwrap();
+            let idx = changes.iter().position(|change| change.seq == late).unwrap();
+            changes.remove(idx)
+        };
+        // The gap times out at once, so the cursor moves past it.
+        assert_eq!(cache.poll_changes().unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+
+        shared.changes.write().unwrap().push(pending);
+        shared.changes.write().unwrap().sort_by_key(|change| change.seq);
+        assert_eq!(cache.poll_changes().unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+        assert_eq!(cache.poll_changes().unwrap(), 0);
+    }
+
+    /// Hands out queued scopes as writes, then times out.
+    struct QueuedWrites(Vec<Scope>);
+
+    impl WriteNotifications for QueuedWrites {
+        fn next_write(&mut self, _timeout: Duration) -> StoreResult<Option<Scope>> {
+            Ok(self.0.pop())
+        }
+    }
+
+    #[test]
+    fn notified_writes_evict_without_polling() {
+        let scope = sample_scope();
+        let other = Scope {
+            session_id: "session2".to_string(),
+            ..scope.clone()
+        };
+        let shared = Arc::new(InMemoryStore::new());
+        let cache = CachedStore::new(
+            shared.clone(),
+            CacheOptions {
+                max_staleness: Duration::from_secs(3600),
+                ..CacheOptions::default()
+            },
+        )
+        .unwrap();
+        for (run, goal) in [(&scope, "draft"), (&other, "plan")] {
+            shared.patch_working_state(run, set_goal(goal)).unwrap();
+            cache.get_working_state(run).unwrap();
+        }
+
+        // The inner store keeps no change log, so only the notifications evict.
+        shared.patch_working_state(&scope, set_goal("ship")).unwrap();
+        shared.patch_working_state(&other, set_goal("review")).unwrap();
+        let mut writes = QueuedWrites(vec![scope.clone()]);
+        assert_eq!(cache.evict_notified(&mut writes, Duration::ZERO).unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+        assert_eq!(cache.get_working_state(&other).unwrap().unwrap().goal, "plan");
+
+        // A write to the whole user, e.g. clearing it, evicts every session under it.
+        let user = Scope {
+            session_id: String::new(),
+            run_id: String::new(),
+            ..scope.clone()
+        };
+        let mut writes = QueuedWrites(vec![user]);
+        assert_eq!(cache.evict_notified(&mut writes, Duration::ZERO).unwrap(), 1);
+        assert_eq!(cache.get_working_state(&other).unwrap().unwrap().goal, "review");
+    }
+}
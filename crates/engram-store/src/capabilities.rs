@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// Optional features a store supports, from
+/// [`Store::capabilities`](crate::Store::capabilities). Every store answers every
+/// [`Store`](crate::Store) call; these say which calls are backed by an index or a
+/// database feature, so callers can pick a code path at run time rather than by
+/// which backend features were compiled in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreCapabilities {
+    /// Embeddings are stored, so [`Store::list_embeddings`](crate::Store::list_embeddings)
+    /// can back similarity recall.
+    pub vector_search: bool,
+    /// [`Store::search_events`](crate::Store::search_events) and
+    /// [`Store::search_episodes`](crate::Store::search_episodes) match whole words
+    /// through the database's full-text search instead of scanning.
+    pub full_text_search: bool,
+    /// Tag and entity filters are answered from index tables instead of scanning.
+    pub indexed_filters: bool,
+    /// Other processes can be notified of writes, e.g. Postgres `LISTEN`.
+    pub subscriptions: bool,
+    /// Bulk writes are all-or-nothing.
+    pub transactions: bool,
+    /// Bulk writes go to the database as one batch instead of a call per record.
+    pub bulk_ops: bool,
+}
+
+impl StoreCapabilities {
+    /// The features both stores have, as for a wrapper that may send a call to
+    /// either of them.
+    pub fn intersect(self, other: StoreCapabilities) -> Self {
+        Self {
+            vector_search: self.vector_search && other.vector_search,
+            full_text_search: self.full_text_search && other.full_text_search,
+            indexed_filters: self.indexed_filters && other.indexed_filters,
+            subscriptions: self.subscriptions && other.subscriptions,
+            transactions: self.transactions && other.transactions,
+            bulk_ops: self.bulk_ops && other.bulk_ops,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InMemoryStore, ReadReplicaStore, SqliteStore, Store};
+
+    #[test]
+    fn wrappers_report_what_their_stores_share() {
+        let memory = InMemoryStore::new();
+        let sqlite = SqliteStore::new_in_memory().unwrap();
+        let caps = sqlite.capabilities();
+        assert!(caps.full_text_search && caps.indexed_filters && caps.transactions);
+        assert!(!caps.subscriptions);
+        assert!(memory.capabilities().vector_search);
+        assert!(!memory.capabilities().transactions);
+
+        let shared = caps.intersect(memory.capabilities());
+        let replicated = ReadReplicaStore::new(sqlite, memory);
+        assert_eq!(replicated.capabilities(), shared);
+        assert!(shared.vector_search && !shared.full_text_search);
+    }
+}
@@ -0,0 +1,897 @@
+use chrono::{DateTime, Utc};
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+
+use crate::{
+    ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage, FactFilter,
+    FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef, ProcedureCandidateFilter,
+    RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility, StmState, Store,
+    StoreCapabilities, StoreError, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+/// A single write, in a form that can be replayed against another store.
+///
+/// Working state is recorded as the full state produced by the patch, including its
+/// merge clock, so replaying it does not depend on what the follower held before.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ChangeOp {
+    AppendEvent { event: Event },
+    PatchWorkingState { scope: Scope, state: Box<WorkingState> },
+    UpdateStm { scope: Scope, stm: StmState },
+    UpsertFact { scope: Scope, fact: Fact },
+    AppendEpisode { scope: Scope, episode: Episode },
+    UpsertProcedure { scope: Scope, procedure: Procedure },
+    UpsertProcedureCandidate { scope: Scope, candidate: ProcedureCandidate },
+    AppendInsight { scope: Scope, insight: InsightItem },
+    WriteContextBuild { scope: Scope, packet: Box<MemoryPacket> },
+    SuppressMemory { scope: Scope, item: MemoryRef, reason: String },
+    UnsuppressMemory { scope: Scope, item: MemoryRef },
+    EvictMemory { scope: Scope, items: Vec<MemoryRef> },
+    DeleteRecord { scope: Scope, item: RecordRef, mode: DeleteMode },
+    ClearScope { scope: Scope, mode: DeleteMode },
+    UpsertEmbeddings { scope: Scope, embeddings: Vec<MemoryEmbedding> },
+    DeleteEmbeddings { scope: Scope, model: String, items: Vec<MemoryRef> },
+    ExpireInsights { scope: Scope, expires_at: String },
+    RecordRunOutcome { scope: Scope, outcome: RunOutcome },
+    SetSourceCredibility { tenant_id: String, credibility: SourceCredibility },
+    SetUserLocale { tenant_id: String, user_id: String, locale: UserLocale },
+}
+
+impl ChangeOp {
+    /// The scope the change writes to, `None` for tenant-wide settings.
+    pub(crate) fn scope_mut(&mut self) -> Option<&mut Scope> {
+        match self {
+            ChangeOp::AppendEvent { event } => Some(&mut event.scope),
+            ChangeOp::PatchWorkingState { scope, .. }
+            | ChangeOp::UpdateStm { scope, .. }
+            | ChangeOp::UpsertFact { scope, .. }
+            | ChangeOp::AppendEpisode { scope, .. }
+            | ChangeOp::UpsertProcedure { scope, .. }
+            | ChangeOp::UpsertProcedureCandidate { scope, .. }
+            | ChangeOp::AppendInsight { scope, .. }
+            | ChangeOp::WriteContextBuild { scope, .. }
+            | ChangeOp::SuppressMemory { scope, .. }
+            | ChangeOp::UnsuppressMemory { scope, .. }
+            | ChangeOp::EvictMemory { scope, .. }
+            | ChangeOp::DeleteRecord { scope, .. }
+            | ChangeOp::ClearScope { scope, .. }
+            | ChangeOp::UpsertEmbeddings { scope, .. }
+            | ChangeOp::DeleteEmbeddings { scope, .. }
+            | ChangeOp::ExpireInsights { scope, .. }
+            | ChangeOp::RecordRunOutcome { scope, .. } => Some(scope),
+            ChangeOp::SetSourceCredibility { .. } | ChangeOp::SetUserLocale { .. } => None,
+        }
+    }
+}
+
+/// An entry of the change log. `seq` is store-wide and strictly increasing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub seq: u64,
+    pub ts: DateTime<Utc>,
+    /// Id of the request the write was made for; see
+    /// [`with_correlation_id`](crate::with_correlation_id).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    #[serde(flatten)]
+    pub op: ChangeOp,
+}
+
+pub(crate) fn apply_changes<S: Store + ?Sized>(store: &S, batch: &[Change]) -> StoreResult<()> {
+    let mut last_seq = 0;
+    for change in batch {
+        if change.seq <= last_seq {
+            return Err(StoreError::InvalidInput(format!(
+                "change {} is out of order after {}",
+                change.seq, last_seq
+            )));
+        }
+        last_seq = change.seq;
+        apply_change(store, change.op.clone())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn apply_change<S: Store + ?Sized>(store: &S, op: ChangeOp) -> StoreResult<()> {
+    match op {
+        ChangeOp::AppendEvent { event } => store.append_event(event),
+        ChangeOp::PatchWorkingState { scope, state } => {
+            store.patch_working_state(&scope, (*state).into()).map(|_| ())
+        }
+        ChangeOp::UpdateStm { scope, stm } => store.update_stm(&scope, stm),
+        ChangeOp::UpsertFact { scope, fact } => store.upsert_fact(&scope, fact),
+        ChangeOp::AppendEpisode { scope, episode } => store.append_episode(&scope, episode),
+        ChangeOp::UpsertProcedure { scope, procedure } => store.upsert_procedure(&scope, procedure),
+        ChangeOp::UpsertProcedureCandidate { scope, candidate } => {
+            store.upsert_procedure_candidate(&scope, candidate)
+        }
+        ChangeOp::AppendInsight { scope, insight } => store.append_insight(&scope, insight),
+        ChangeOp::WriteContextBuild { scope, packet } => store.write_context_build(&scope, *packet),
+        ChangeOp::SuppressMemory {
+            scope,
+            item,
+            reason,
+        } => store.suppress_memory(&scope, item, &reason),
+        ChangeOp::UnsuppressMemory { scope, item } => {
+            store.unsuppress_memory(&scope, &item).map(|_| ())
+        }
+        ChangeOp::EvictMemory { scope, items } => store.evict_memory(&scope, &items).map(|_| ()),
+        ChangeOp::DeleteRecord { scope, item, mode } => {
+            store.delete_record(&scope, &item, mode).map(|_| ())
+        }
+        ChangeOp::ClearScope { scope, mode } => store.clear_scope(&scope, mode).map(|_| ()),
+        ChangeOp::UpsertEmbeddings { scope, embeddings } => {
+            store.upsert_embeddings(&scope, embeddings)
+        }
+        ChangeOp::DeleteEmbeddings {
+            scope,
+            model,
+            items,
+        } => store.delete_embeddings(&scope, &model, &items).map(|_| ()),
+        ChangeOp::ExpireInsights { scope, expires_at } => {
+            store.expire_insights(&scope, &expires_at).map(|_| ())
+        }
+        ChangeOp::RecordRunOutcome { scope, outcome } => store.record_run_outcome(&scope, outcome),
+        ChangeOp::SetSourceCredibility {
+            tenant_id,
+            credibility,
+        } => store.set_source_credibility(&tenant_id, credibility),
+        ChangeOp::SetUserLocale {
+            tenant_id,
+            user_id,
+            locale,
+        } => store.set_user_locale(&tenant_id, &user_id, locale),
+    }
+}
+
+/// Applies `op` when an earlier attempt may already have: an append whose record is
+/// already stored is skipped instead of failing on its id (or, for a context build
+/// with no id, being stored twice). Every other op is safe to apply again.
+pub(crate) fn reapply_change<S: Store + ?Sized>(store: &S, op: ChangeOp) -> StoreResult<()> {
+    let stored = match &op {
+        ChangeOp::AppendEvent { event } => !store
+            .get_events_by_ids(&event.scope, std::slice::from_ref(&event.event_id))?
+            .is_empty(),
+        ChangeOp::AppendEpisode { scope, episode } => {
+            let filter = EpisodeFilter {
+                time_range: Some(TimeRangeFilter {
+                    start: Some(episode.time_range.start),
+                    end: None,
+                }),
+                ..EpisodeFilter::default()
+            };
+            let episodes = store.list_episodes(scope, filter)?;
+            episodes.iter().any(|stored| stored.episode_id == episode.episode_id)
+        }
+        ChangeOp::AppendInsight { scope, insight } => store
+            .list_insights(scope, InsightFilter::default())?
+            .iter()
+            .any(|stored| stored.id == insight.id),
+        ChangeOp::WriteContextBuild { scope, packet } => {
+            let written = ContextBuildSummary::from(packet.as_ref());
+            store
+                .list_context_build_summaries(scope, None)?
+                .iter()
+                .any(|stored| {
+                    stored.generated_at == written.generated_at
+                        && stored.policy_id == written.policy_id
+                        && stored.task_type == written.task_type
+                })
+        }
+        _ => false,
+    };
+    if stored {
+        return Ok(());
+    }
+    apply_change(store, op)
+}
+
+/// Store wrapper that records every successful write in the inner store's change
+/// log, where replicators can tail it with [`Store::list_changes`].
+///
+/// Writes go through [`Store::write_logged`]: the SQL backends append the change in
+/// the write's own transaction, so the log has an entry exactly when the write
+/// commits. In-memory stores append it right after the write.
+#[derive(Debug)]
+pub struct ChangeLogStore<S> {
+    inner: S,
+}
+
+impl<S: Store> ChangeLogStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Runs `write` through [`Store::write_logged`] and returns its value; `write`
+    /// also returns the changes to log for it.
+    fn logged<T>(
+        &self,
+        write: impl FnOnce() -> StoreResult<(T, Vec<ChangeOp>)>,
+    ) -> StoreResult<T> {
+        let mut write = Some(write);
+        let mut value = None;
+        self.inner.write_logged(&mut || {
+            let write = write.take().ok_or_else(|| {
+                StoreError::Storage("logged write was run more than once".to_string())
+            })?;
+            let (written, changes) = write()?;
+            value = Some(written);
+            Ok(changes)
+        })?;
+        value.ok_or_else(|| StoreError::Storage("logged write was not run".to_string()))
+    }
+}
+
+impl<S: Store> Store for ChangeLogStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.append_event(event.clone())?;
+            Ok(((), vec![ChangeOp::AppendEvent { event }]))
+        })
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.append_events_bulk(events)?;
+            let changes = events
+                .iter()
+                .map(|event| ChangeOp::AppendEvent {
+                    event: event.clone(),
+                })
+                .collect();
+            Ok(((), changes))
+        })
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.inner.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.logged(|| {
+            let state = self.inner.patch_working_state(scope, patch)?;
+            let change = ChangeOp::PatchWorkingState {
+                scope: scope.clone(),
+                state: Box::new(state.clone()),
+            };
+            Ok((state, vec![change]))
+        })
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let scopes: Vec<Scope> = patches.iter().map(|(scope, _)| scope.clone()).collect();
+        self.logged(|| {
+            let states = self.inner.patch_working_states(patches)?;
+            let changes = scopes
+                .into_iter()
+                .zip(&states)
+                .map(|(scope, state)| ChangeOp::PatchWorkingState {
+                    scope,
+                    state: Box::new(state.clone()),
+                })
+                .collect();
+            Ok((states, changes))
+        })
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.update_stm(scope, stm.clone())?;
+            Ok(((), vec![ChangeOp::UpdateStm {
+                scope: scope.clone(),
+                stm,
+            }]))
+        })
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.upsert_fact(scope, fact.clone())?;
+            Ok(((), vec![ChangeOp::UpsertFact {
+                scope: scope.clone(),
+                fact,
+            }]))
+        })
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.upsert_facts_bulk(scope, facts)?;
+            let changes = facts
+                .iter()
+                .map(|fact| ChangeOp::UpsertFact {
+                    scope: scope.clone(),
+                    fact: fact.clone(),
+                })
+                .collect();
+            Ok(((), changes))
+        })
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.inner.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.append_episode(scope, episode.clone())?;
+            Ok(((), vec![ChangeOp::AppendEpisode {
+                scope: scope.clone(),
+                episode,
+            }]))
+        })
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.append_episodes_bulk(scope, episodes)?;
+            let changes = episodes
+                .iter()
+                .map(|episode| ChangeOp::AppendEpisode {
+                    scope: scope.clone(),
+                    episode: episode.clone(),
+                })
+                .collect();
+            Ok(((), changes))
+        })
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.upsert_procedure(scope, procedure.clone())?;
+            Ok(((), vec![ChangeOp::UpsertProcedure {
+                scope: scope.clone(),
+                procedure,
+            }]))
+        })
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.upsert_procedure_candidate(scope, candidate.clone())?;
+            Ok(((), vec![ChangeOp::UpsertProcedureCandidate {
+                scope: scope.clone(),
+                candidate,
+            }]))
+        })
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.append_insight(scope, insight.clone())?;
+            Ok(((), vec![ChangeOp::AppendInsight {
+                scope: scope.clone(),
+                insight,
+            }]))
+        })
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.logged(|| {
+            let expired = self.inner.expire_insights(scope, expires_at)?;
+            let changes = Vec::from_iter((expired > 0).then(|| ChangeOp::ExpireInsights {
+                scope: scope.clone(),
+                expires_at: expires_at.to_string(),
+            }));
+            Ok((expired, changes))
+        })
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.write_context_build(scope, packet.clone())?;
+            Ok(((), vec![ChangeOp::WriteContextBuild {
+                scope: scope.clone(),
+                packet: Box::new(packet),
+            }]))
+        })
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.suppress_memory(scope, item.clone(), reason)?;
+            Ok(((), vec![ChangeOp::SuppressMemory {
+                scope: scope.clone(),
+                item,
+                reason: reason.to_string(),
+            }]))
+        })
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.logged(|| {
+            let existed = self.inner.unsuppress_memory(scope, item)?;
+            let changes = Vec::from_iter(existed.then(|| ChangeOp::UnsuppressMemory {
+                scope: scope.clone(),
+                item: item.clone(),
+            }));
+            Ok((existed, changes))
+        })
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.logged(|| {
+            let evicted = self.inner.evict_memory(scope, items)?;
+            let changes = Vec::from_iter((evicted > 0).then(|| ChangeOp::EvictMemory {
+                scope: scope.clone(),
+                items: items.to_vec(),
+            }));
+            Ok((evicted, changes))
+        })
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.logged(|| {
+            let existed = self.inner.delete_record(scope, item, mode)?;
+            let changes = Vec::from_iter(existed.then(|| ChangeOp::DeleteRecord {
+                scope: scope.clone(),
+                item: item.clone(),
+                mode,
+            }));
+            Ok((existed, changes))
+        })
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.logged(|| {
+            let removed = self.inner.clear_scope(scope, mode)?;
+            let changes = Vec::from_iter((removed > 0).then(|| ChangeOp::ClearScope {
+                scope: scope.clone(),
+                mode,
+            }));
+            Ok((removed, changes))
+        })
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.upsert_embeddings(scope, embeddings.clone())?;
+            Ok(((), vec![ChangeOp::UpsertEmbeddings {
+                scope: scope.clone(),
+                embeddings,
+            }]))
+        })
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.logged(|| {
+            let deleted = self.inner.delete_embeddings(scope, model, items)?;
+            let changes = Vec::from_iter((deleted > 0).then(|| ChangeOp::DeleteEmbeddings {
+                scope: scope.clone(),
+                model: model.to_string(),
+                items: items.to_vec(),
+            }));
+            Ok((deleted, changes))
+        })
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.record_run_outcome(scope, outcome.clone())?;
+            Ok(((), vec![ChangeOp::RecordRunOutcome {
+                scope: scope.clone(),
+                outcome,
+            }]))
+        })
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.set_source_credibility(tenant_id, credibility.clone())?;
+            Ok(((), vec![ChangeOp::SetSourceCredibility {
+                tenant_id: tenant_id.to_string(),
+                credibility,
+            }]))
+        })
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.logged(|| {
+            self.inner.set_user_locale(tenant_id, user_id, locale.clone())?;
+            Ok(((), vec![ChangeOp::SetUserLocale {
+                tenant_id: tenant_id.to_string(),
+                user_id: user_id.to_string(),
+                locale,
+            }]))
+        })
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+/// Connections the SQL backends hold in a transaction for [`Store::write_logged`], by
+/// thread, so every call the write makes on that thread joins the transaction.
+pub(crate) struct PinnedConnections<C> {
+    held: Mutex<HashMap<ThreadId, Option<C>>>,
+}
+
+impl<C> Default for PinnedConnections<C> {
+    fn default() -> Self {
+        Self {
+            held: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<C> PinnedConnections<C> {
+    /// Whether the calling thread is inside a logged write, whose connection is
+    /// already in a transaction.
+    #[cfg(any(feature = "postgres", feature = "mysql"))]
+    pub(crate) fn is_pinned(&self) -> StoreResult<bool> {
+        let held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(held.contains_key(&thread::current().id()))
+    }
+
+    /// Runs `f` on the calling thread's pinned connection, or on one from `connect`
+    /// outside a logged write.
+    pub(crate) fn checkout<T>(
+        &self,
+        connect: impl FnOnce() -> StoreResult<C>,
+        f: impl FnOnce(&mut C) -> StoreResult<T>,
+    ) -> StoreResult<T> {
+        let id = thread::current().id();
+        let pinned = {
+            let mut held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+            match held.get_mut(&id) {
+                Some(conn) => Some(conn.take().ok_or_else(|| {
+                    StoreError::Storage("nested call inside a logged write".to_string())
+                })?),
+                None => None,
+            }
+        };
+        let Some(mut conn) = pinned else {
+            return f(&mut connect()?);
+        };
+        let result = f(&mut conn);
+        let mut held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+        held.insert(id, Some(conn));
+        result
+    }
+
+    /// Runs `write` with `conn` pinned to the calling thread, then hands `conn` back
+    /// to commit or roll back.
+    pub(crate) fn run<T>(
+        &self,
+        conn: C,
+        write: impl FnOnce() -> StoreResult<T>,
+    ) -> StoreResult<(C, StoreResult<T>)> {
+        let id = thread::current().id();
+        self.held
+            .lock()
+            .map_err(|_| StoreError::Poisoned)?
+            .insert(id, Some(conn));
+        let result = write();
+        let conn = self
+            .held
+            .lock()
+            .map_err(|_| StoreError::Poisoned)?
+            .remove(&id)
+            .flatten()
+            .ok_or_else(|| StoreError::Storage("logged write lost its connection".to_string()))?;
+        Ok((conn, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore, SqliteStore};
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn replicates_primary_writes_to_follower() {
+        let scope = sample_scope();
+        let primary = ChangeLogStore::new(SqliteStore::new_in_memory().unwrap());
+        primary
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!({ "content": "hi" })))
+            .unwrap();
+        primary
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("ship".to_string()),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        primary
+            .upsert_fact(&scope, Fact::new("user.name", json!("Ada")))
+            .unwrap();
+
+        let changes = primary.list_changes(0, None).unwrap();
+        let seqs: Vec<u64> = changes.iter().map(|change| change.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert_eq!(primary.list_changes(2, None).unwrap().len(), 1);
+
+        let encoded = serde_json::to_string(&changes).unwrap();
+        let decoded: Vec<Change> = serde_json::from_str(&encoded).unwrap();
+        assert!(matches!(decoded[1].op, ChangeOp::PatchWorkingState { .. }));
+
+        let follower = InMemoryStore::new();
+        follower.apply_changes(&decoded).unwrap();
+        let events = follower
+            .list_events(&scope, TimeRangeFilter::default(), None)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(follower.get_working_state(&scope).unwrap().unwrap().goal, "ship");
+        assert_eq!(follower.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+
+        let mut reversed = decoded.clone();
+        reversed.reverse();
+        assert!(InMemoryStore::new().apply_changes(&reversed).is_err());
+    }
+
+    #[test]
+    fn sqlite_logs_changes_in_the_write_transaction() {
+        let scope = sample_scope();
+        let store = ChangeLogStore::new(SqliteStore::new_in_memory().unwrap());
+        let failed = store.inner().write_logged(&mut || {
+            store.inner().upsert_fact(&scope, Fact::new("user.name", json!("Ada")))?;
+            Err(StoreError::Storage("crashed before the change was logged".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(store.list_facts(&scope, FactFilter::default()).unwrap().is_empty());
+        assert!(store.list_changes(0, None).unwrap().is_empty());
+
+        let fact = Fact::new("user.name", json!("Ada"));
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        let item = MemoryRef {
+            kind: crate::MemoryKind::Fact,
+            id: fact.fact_id.clone(),
+        };
+        assert_eq!(store.evict_memory(&scope, &[item]).unwrap(), 1);
+        assert_eq!(store.clear_scope(&scope, DeleteMode::Hard).unwrap(), 0);
+        let changes = store.list_changes(0, None).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(changes[1].op, ChangeOp::EvictMemory { .. }));
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Utc};
+use engram_types::{new_ulid, Scope, WorkingState};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{scope_matches, Event, EventKind, StmState, Store, StoreError, StoreResult};
+
+/// Event kind under which checkpoints are journaled in the run they capture.
+pub const CHECKPOINT_EVENT_KIND: &str = "checkpoint";
+
+/// The memory view of a run at one point: how far its event log reached plus the
+/// working state and STM at that moment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub label: String,
+    pub scope: Scope,
+    pub created_at: DateTime<Utc>,
+    /// Events with `seq` up to and including this value belong to the checkpoint.
+    pub event_seq: u64,
+    #[serde(default)]
+    pub working_state: Option<WorkingState>,
+    #[serde(default)]
+    pub stm: Option<StmState>,
+}
+
+/// Captures the current memory view of `scope` and journals it as a `checkpoint`
+/// event in the same run. Reusing a label shadows the earlier checkpoint.
+pub fn checkpoint_run<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    label: &str,
+) -> StoreResult<Checkpoint> {
+    if label.trim().is_empty() {
+        return Err(StoreError::InvalidInput(
+            "checkpoint label must not be empty".to_string(),
+        ));
+    }
+
+    let event_seq = store
+        .get_events_since(scope, 0, None)?
+        .last()
+        .map(|event| event.seq)
+        .unwrap_or(0);
+    let checkpoint = Checkpoint {
+        label: label.to_string(),
+        scope: scope.clone(),
+        created_at: Utc::now(),
+        event_seq,
+        working_state: store.get_working_state(scope)?,
+        stm: store.get_stm(scope)?,
+    };
+
+    let payload = serde_json::to_value(&checkpoint)
+        .map_err(|err| StoreError::Storage(err.to_string()))?;
+    let mut event = Event::new(scope.clone(), checkpoint_kind(), payload);
+    event.ts = checkpoint.created_at;
+    event.tags = vec![format!("{}:{}", CHECKPOINT_EVENT_KIND, label)];
+    store.append_event(event)?;
+    debug!("checkpoint {} captured at event seq {}", label, event_seq);
+    Ok(checkpoint)
+}
+
+/// Returns the latest checkpoint of `scope` with the given label.
+pub fn get_checkpoint<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    label: &str,
+) -> StoreResult<Option<Checkpoint>> {
+    let kind = checkpoint_kind();
+    let mut found = None;
+    for event in store.get_events_since(scope, 0, None)? {
+        if event.kind != kind || event.payload.get("label").and_then(|v| v.as_str()) != Some(label)
+        {
+            continue;
+        }
+        let checkpoint: Checkpoint = serde_json::from_value(event.payload)
+            .map_err(|err| StoreError::Storage(err.to_string()))?;
+        found = Some(checkpoint);
+    }
+    Ok(found)
+}
+
+/// Restores the memory view saved by [`checkpoint_run`] into `target`, which should
+/// be a fresh run: events up to the checkpoint are copied under new ids, and the
+/// working state and STM are written back as they were.
+///
+/// STM is session-scoped, so replaying into the checkpoint's own session overwrites
+/// that session's current STM.
+pub fn replay_from_checkpoint<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    label: &str,
+    target: &Scope,
+) -> StoreResult<Checkpoint> {
+    if scope_matches(target, scope) {
+        return Err(StoreError::InvalidInput(
+            "replay target must differ from the checkpointed run".to_string(),
+        ));
+    }
+    let checkpoint = get_checkpoint(store, scope, label)?.ok_or(StoreError::NotFound)?;
+
+    let mut copied = 0;
+    for event in store.get_events_since(scope, 0, None)? {
+        if event.seq > checkpoint.event_seq {
+            break;
+        }
+        store.append_event(Event {
+            event_id: new_ulid(),
+            scope: target.clone(),
+            seq: 0,
+            ..event
+        })?;
+        copied += 1;
+    }
+    if let Some(state) = checkpoint.working_state.clone() {
+        store.patch_working_state(target, state.into())?;
+    }
+    if let Some(stm) = checkpoint.stm.clone() {
+        store.update_stm(target, stm)?;
+    }
+    debug!("replayed checkpoint {} with {} events", label, copied);
+    Ok(checkpoint)
+}
+
+fn checkpoint_kind() -> EventKind {
+    EventKind::Custom(CHECKPOINT_EVENT_KIND.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, TimeRangeFilter, WorkingStatePatch};
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn replays_checkpointed_view_into_new_run() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let message = |content: &str| {
+            Event::new(scope.clone(), EventKind::Message, json!({ "content": content }))
+        };
+        store.append_event(message("first")).unwrap();
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("before".to_string()),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        let checkpoint = checkpoint_run(&store, &scope, "step-1").unwrap();
+        assert_eq!(checkpoint.event_seq, 1);
+
+        store.append_event(message("second")).unwrap();
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("after".to_string()),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+
+        let target = Scope {
+            session_id: "debug".to_string(),
+            run_id: "replay1".to_string(),
+            ..scope.clone()
+        };
+        replay_from_checkpoint(&store, &scope, "step-1", &target).unwrap();
+        let events = store
+            .list_events(&target, TimeRangeFilter::default(), None)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].payload["content"], "first");
+        assert_eq!(store.get_working_state(&target).unwrap().unwrap().goal, "before");
+        assert_eq!(store.get_working_state(&scope).unwrap().unwrap().goal, "after");
+
+        assert!(matches!(
+            replay_from_checkpoint(&store, &scope, "missing", &target),
+            Err(StoreError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+use crate::provenance::event_quote;
+use crate::Event;
+
+/// Separates the source id from the character range in a chunk id, e.g.
+/// `01HX…#chunk:120-940`.
+pub const CHUNK_ID_SEPARATOR: &str = "#chunk:";
+
+/// Characters per token, the estimate used for packet budgets as well.
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkOptions {
+    /// Upper bound on estimated tokens per chunk.
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+    /// Estimated tokens of trailing sentences repeated at the start of the next chunk,
+    /// so a statement split across chunks is whole in at least one of them.
+    #[serde(default = "default_overlap_tokens")]
+    pub overlap_tokens: usize,
+}
+
+fn default_max_tokens() -> usize {
+    256
+}
+
+fn default_overlap_tokens() -> usize {
+    32
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: default_max_tokens(),
+            overlap_tokens: default_overlap_tokens(),
+        }
+    }
+}
+
+/// A piece of a longer text. `start` and `end` are character offsets into the source
+/// text, so `chunk_id` pins a citation to the exact passage; see [`parse_chunk_id`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextChunk {
+    pub chunk_id: String,
+    pub source_id: String,
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub tokens: usize,
+}
+
+/// Splits `text` into chunks of at most `max_tokens` estimated tokens, breaking between
+/// sentences where possible, between words otherwise, and mid-word only for words
+/// longer than a whole chunk. Long tool results and transcripts are chunked before they
+/// are embedded or summarized; evidence and sources that cite a chunk's id resolve to
+/// its passage in [`resolve_provenance`](crate::resolve_provenance).
+pub fn chunk_text(source_id: &str, text: &str, options: &ChunkOptions) -> Vec<TextChunk> {
+    let chars: Vec<char> = text.chars().collect();
+    let max_chars = options.max_tokens.max(1) * CHARS_PER_TOKEN;
+    let overlap_chars = options.overlap_tokens * CHARS_PER_TOKEN;
+    let pieces: Vec<(usize, usize)> = sentences(&chars)
+        .into_iter()
+        .flat_map(|span| split_span(&chars, span, max_chars))
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < pieces.len() {
+        let start = pieces[first].0;
+        let mut last = first;
+        while last + 1 < pieces.len() && pieces[last + 1].1 - start <= max_chars {
+            last += 1;
+        }
+        let end = pieces[last].1;
+        let text: String = chars[start..end].iter().collect();
+        chunks.push(TextChunk {
+            chunk_id: chunk_id(source_id, start, end),
+            source_id: source_id.to_string(),
+            index: chunks.len(),
+            start,
+            end,
+            tokens: end.saturating_sub(start).div_ceil(CHARS_PER_TOKEN).max(1),
+            text,
+        });
+        if last + 1 == pieces.len() {
+            break;
+        }
+        // Restart at the earliest piece that fits the overlap, but always move forward.
+        first = (first + 1..=last)
+            .find(|piece| end - pieces[*piece].0 <= overlap_chars)
+            .unwrap_or(last + 1);
+    }
+    chunks
+}
+
+/// Chunks the event's text: the `content` or `text` of a message payload, the payload
+/// JSON otherwise. Chunk ids derive from the event id.
+pub fn chunk_event(event: &Event, options: &ChunkOptions) -> Vec<TextChunk> {
+    chunk_text(&event.event_id, &event_quote(event), options)
+}
+
+/// Id of the chunk of `source_id` spanning characters `start..end`.
+pub fn chunk_id(source_id: &str, start: usize, end: usize) -> String {
+    format!("{}{}{}-{}", source_id, CHUNK_ID_SEPARATOR, start, end)
+}
+
+/// The source id and character range of a chunk id, or `None` for other ids.
+pub fn parse_chunk_id(id: &str) -> Option<(&str, usize, usize)> {
+    let (source_id, range) = id.rsplit_once(CHUNK_ID_SEPARATOR)?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+    (start <= end).then_some((source_id, start, end))
+}
+
+/// The passage of `text` a chunk id points at.
+pub(crate) fn chunk_passage(text: &str, start: usize, end: usize) -> String {
+    text.chars().skip(start).take(end - start).collect()
+}
+
+/// Sentence spans of `chars` without surrounding whitespace. A sentence ends after
+/// `.`, `!` or `?` (or their full-width forms) followed by whitespace, and at every
+/// line break.
+fn sentences(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (i, c) in chars.iter().enumerate() {
+        let boundary = *c == '\n'
+            || (matches!(c, '.' | '!' | '?' | '。' | '！' | '？')
+                && chars.get(i + 1).is_none_or(|next| next.is_whitespace()));
+        if boundary {
+            spans.extend(trim(chars, start, i + 1));
+            start = i + 1;
+        }
+    }
+    spans.extend(trim(chars, start, chars.len()));
+    spans
+}
+
+/// Splits a span longer than `max_chars` at word boundaries, and words longer than
+/// `max_chars` every `max_chars` characters.
+fn split_span(chars: &[char], span: (usize, usize), max_chars: usize) -> Vec<(usize, usize)> {
+    if span.1 - span.0 <= max_chars {
+        return vec![span];
+    }
+    let mut pieces = Vec::new();
+    let mut start = span.0;
+    for i in span.0..=span.1 {
+        if i == span.1 || chars[i].is_whitespace() {
+            if let Some((word_start, word_end)) = trim(chars, start, i) {
+                let mut offset = word_start;
+                while offset < word_end {
+                    pieces.push((offset, (offset + max_chars).min(word_end)));
+                    offset += max_chars;
+                }
+            }
+            start = i + 1;
+        }
+    }
+    pieces
+}
+
+fn trim(chars: &[char], mut start: usize, mut end: usize) -> Option<(usize, usize)> {
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    (start < end).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_on_sentences_with_overlap_and_exact_offsets() {
+        let text = "The deploy failed. Disk was full on node 3!\n\
+                    We freed 20 GB and retried. The second attempt passed. \
+                    All green now";
+        let options = ChunkOptions {
+            max_tokens: 14,
+            overlap_tokens: 7,
+        };
+        let chunks = chunk_text("ev1", text, &options);
+        let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "The deploy failed. Disk was full on node 3!",
+                "Disk was full on node 3!\nWe freed 20 GB and retried.",
+                "We freed 20 GB and retried. The second attempt passed.",
+                "The second attempt passed. All green now",
+            ]
+        );
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, index);
+            assert!(chunk.tokens <= options.max_tokens);
+            assert_eq!(chunk_passage(text, chunk.start, chunk.end), chunk.text);
+            assert_eq!(
+                parse_chunk_id(&chunk.chunk_id),
+                Some(("ev1", chunk.start, chunk.end))
+            );
+        }
+
+        // Runs without sentence breaks fall back to words, then to characters.
+        let chunks = chunk_text("ev2", &format!("{} tail", "x".repeat(10)), &ChunkOptions {
+            max_tokens: 1,
+            overlap_tokens: 0,
+        });
+        let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, vec!["xxxx", "xxxx", "xx", "tail"]);
+        assert!(chunk_text("ev3", "  \n ", &ChunkOptions::default()).is_empty());
+        assert_eq!(parse_chunk_id("ev1"), None);
+        assert_eq!(parse_chunk_id("ev1#chunk:9-3"), None);
+    }
+}
@@ -0,0 +1,106 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, StoreResult};
+
+/// How packets and events are encoded when they cross a process or language
+/// boundary. CBOR is a binary encoding of the same data model as JSON: smaller, and
+/// cheaper to produce and parse for large packets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl WireFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireFormat::Json => "json",
+            WireFormat::Cbor => "cbor",
+        }
+    }
+}
+
+impl std::fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for WireFormat {
+    type Err = StoreError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(WireFormat::Json),
+            "cbor" => Ok(WireFormat::Cbor),
+            _ => Err(StoreError::InvalidInput(format!("invalid wire format: {}", value))),
+        }
+    }
+}
+
+pub fn encode_wire<T: Serialize + ?Sized>(value: &T, format: WireFormat) -> StoreResult<Vec<u8>> {
+    match format {
+        WireFormat::Json => Ok(serde_json::to_vec(value)?),
+        WireFormat::Cbor => {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(value, &mut bytes)
+                .map_err(|err| StoreError::InvalidInput(format!("cbor encoding: {}", err)))?;
+            Ok(bytes)
+        }
+    }
+}
+
+pub fn decode_wire<T: DeserializeOwned>(bytes: &[u8], format: WireFormat) -> StoreResult<T> {
+    match format {
+        WireFormat::Json => Ok(serde_json::from_slice(bytes)?),
+        WireFormat::Cbor => ciborium::from_reader(bytes)
+            .map_err(|err| StoreError::InvalidInput(format!("cbor decoding: {}", err))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, Event, EventKind, InMemoryStore, Store};
+    use engram_types::{Fact, MemoryPacket, Purpose, Scope};
+    use serde_json::json;
+
+    #[test]
+    fn packets_and_events_roundtrip_through_cbor() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut event = Event::new(
+            scope.clone(),
+            EventKind::Custom("observation".to_string()),
+            json!({"content": "disk at 91%", "sizes": [1, -2, 3.5], "ok": null}),
+        );
+        event.tags = vec!["ops".to_string()];
+        store.append_event(event.clone()).unwrap();
+        store.upsert_fact(&scope, Fact::new("disk.alert", json!(0.9))).unwrap();
+        let packet = build_memory_packet(&store, BuildRequest::new(scope, Purpose::Planner))
+            .unwrap();
+
+        let bytes = encode_wire(&packet, WireFormat::Cbor).unwrap();
+        assert!(bytes.len() < encode_wire(&packet, WireFormat::Json).unwrap().len());
+        let decoded: MemoryPacket = decode_wire(&bytes, WireFormat::Cbor).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&packet).unwrap());
+
+        let bytes = encode_wire(&event, WireFormat::Cbor).unwrap();
+        let decoded: Event = decode_wire(&bytes, WireFormat::Cbor).unwrap();
+        assert_eq!(decoded.kind, event.kind);
+        assert_eq!(decoded.payload, event.payload);
+        assert_eq!(decoded.ts, event.ts);
+
+        assert!(decode_wire::<Event>(b"\xff", WireFormat::Cbor).is_err());
+        assert_eq!("cbor".parse::<WireFormat>().unwrap(), WireFormat::Cbor);
+    }
+}
@@ -1,42 +1,113 @@
 use chrono::{DateTime, Duration, Utc};
 use engram_types::{
-    Budget, BudgetReport, Citation, CitationType, ConversationTurn, Episode, Fact, FactStatus,
-    Insight, InsightItem, JsonMap, KeyQuote, LongTerm, MemoryPacket, Meta, Purpose, Scope,
-    ShortTerm, UsagePolicy,
+    Budget, BudgetReport, Citation, CitationType, CompressionLevel, ConversationTurn, Episode,
+    ExternalContext, Fact, FactStatus, Insight, InsightItem, JsonMap, KeyQuote, LongTerm,
+    MemoryPacket, Meta, Purpose, Scope, ShortTerm, UsagePolicy,
 };
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::cmp::Ordering;
-use std::collections::HashMap;
-
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration as StdDuration, Instant};
+
+use crate::credibility::{apply_source_credibility, keep_most_confident};
+use crate::external_context::fetch_external_context;
+use crate::lang::{lang_matches, select_facts_by_lang};
+use crate::outcome::{episode_run, run_signals};
+use crate::pinned_procedures::{is_pinned_procedure, list_tenant_procedures};
+use crate::ranking::sort_by_score;
+use crate::timeout::with_timeout_cap;
+use crate::tokens::count_tokens;
 use crate::{
-    EpisodeFilter, Event, EventKind, FactFilter, InsightFilter, Store, StoreResult, StmState,
-    TimeRangeFilter,
+    flag_contradictions, is_period_summary, procedure_applies, token_counter, with_read_preference,
+    with_token_counter, ApplicabilityContext, Candidate, CueExpansion, DefaultRanker, EpisodeFilter,
+    Event, EventFilter, EventKind, ExternalContextProvider, ExternalContextQuery, FactFilter,
+    InsightFilter, LangMode, MemoryKind, MemoryRef, Ranker, ReadPreference, RunKey, RunOutcome,
+    SlotSchemaRegistry, StmState, Store, StoreError, StoreResult, SummaryCondensing, TextQuery,
+    TimeRangeFilter, UserLocale, DAILY_SUMMARY_TAG, WEEKLY_SUMMARY_TAG,
 };
 use tracing::{debug, info, instrument, warn};
 
+/// Payload key under which stores record a message event's estimated token count.
+pub const TOKEN_COUNT_KEY: &str = "token_count";
+
 #[derive(Debug, Clone, Default)]
 pub struct RecallCues {
     pub tags: Vec<String>,
     pub entities: Vec<String>,
     pub keywords: Vec<String>,
     pub time_range: Option<TimeRangeFilter>,
+    /// More windows recalled alongside `time_range`, for cues such as "last Tuesday and
+    /// two weeks ago": episodes and events in any of them are recalled.
+    pub time_ranges: Vec<TimeRangeFilter>,
+    /// Language of the conversation, e.g. `en`; facts, episodes and cue quotes in
+    /// other languages are handled per [`RecallPolicy::lang_mode`].
+    pub lang: Option<String>,
+    /// Recall the facts that were valid at this instant instead of now, including
+    /// those deprecated since because their validity ended.
+    pub valid_at: Option<DateTime<Utc>>,
+}
+
+impl RecallCues {
+    /// Every cue window: `time_range`, then `time_ranges`.
+    pub fn time_windows(&self) -> Vec<TimeRangeFilter> {
+        self.time_range.iter().chain(&self.time_ranges).cloned().collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RecallPolicy {
     pub max_total_candidates: usize,
     pub max_facts: usize,
+    /// User preferences put in `long_term.preferences`; see
+    /// [`set_preference`](crate::set_preference). They don't count against `max_facts`.
+    pub max_preferences: usize,
     pub max_procedures: usize,
+    /// Tenant procedures put ahead of the task's; see
+    /// [`pin_tenant_procedure`](crate::pin_tenant_procedure). They don't count against
+    /// `max_procedures` and are never trimmed with the task's procedures.
+    pub max_pinned_procedures: usize,
     pub max_episodes: usize,
     pub max_insights: usize,
     pub max_key_quotes: usize,
     pub conversation_window: usize,
+    /// When non-zero, the conversation window takes the most recent turns that fit
+    /// this many tokens instead of `conversation_window` turns.
+    pub conversation_window_tokens: usize,
     pub episode_time_window_days: i64,
+    /// Most recent daily and weekly summaries put in `short_term.period_summaries`;
+    /// see [`refresh_period_summaries`](crate::refresh_period_summaries).
+    pub max_daily_summaries: usize,
+    pub max_weekly_summaries: usize,
     pub last_tool_evidence_limit: usize,
-    pub include_conversation_window: bool,
-    pub include_insights_in_tool: bool,
-    pub allow_insights_in_responder: bool,
+    /// Longer key quotes and episode highlights are clipped at a sentence boundary;
+    /// 0 disables the cap. Over-budget packets clip further before dropping items.
+    pub max_quote_tokens: usize,
+    pub max_highlight_tokens: usize,
+    /// Recall agent and tenant fact pools beneath the user's own facts. Off by default.
+    pub include_shared_facts: bool,
+    /// How far recorded run outcomes move an episode's recency score, from 0 (ignored)
+    /// to 1: an episode of a run with signal 1 scores up to twice as high, one with
+    /// signal 0 drops to nothing. Episodes find their run through the `run:` tag.
+    pub outcome_weight: f64,
+    /// What to do with memories in another language than [`RecallCues::lang`].
+    pub lang_mode: LangMode,
+    pub filter: PurposeFilter,
+    /// Widens `RecallCues::keywords` with synonyms and similar terms before matching.
+    pub cue_expansion: CueExpansion,
+    /// Shortens rolling summaries that outgrew a token cap, e.g. with an LLM.
+    pub summary_condensing: SummaryCondensing,
+    /// Required slots and constraints of the task type that the working state has
+    /// yet to fill are listed in `short_term.open_loops`.
+    pub slot_schemas: Option<Arc<SlotSchemaRegistry>>,
+    /// Scores facts, procedures, episodes and insights in place of the built-in
+    /// ordering. With a ranker every fact is a candidate, not only the ones the
+    /// backend lists first, and unpinned facts are listed best first rather than by key.
+    pub ranker: Option<Arc<dyn Ranker>>,
+    /// Sources of non-memory context, asked in order once memory has loaded, with
+    /// the tokens the budget has left; see [`ExternalContextProvider`].
+    pub external_context: Vec<Arc<dyn ExternalContextProvider>>,
 }
 
 impl Default for RecallPolicy {
@@ -44,16 +115,96 @@ impl Default for RecallPolicy {
         Self {
             max_total_candidates: 100,
             max_facts: 30,
+            max_preferences: 10,
             max_procedures: 5,
+            max_pinned_procedures: 10,
             max_episodes: 20,
             max_insights: 10,
             max_key_quotes: 10,
             conversation_window: 5,
+            conversation_window_tokens: 0,
             episode_time_window_days: 30,
+            max_daily_summaries: 0,
+            max_weekly_summaries: 0,
             last_tool_evidence_limit: 3,
-            include_conversation_window: false,
-            include_insights_in_tool: false,
-            allow_insights_in_responder: false,
+            max_quote_tokens: 120,
+            max_highlight_tokens: 60,
+            include_shared_facts: false,
+            outcome_weight: 0.0,
+            lang_mode: LangMode::default(),
+            filter: PurposeFilter::default(),
+            cue_expansion: CueExpansion::default(),
+            summary_condensing: SummaryCondensing::default(),
+            slot_schemas: None,
+            ranker: None,
+            external_context: Vec::new(),
+        }
+    }
+}
+
+/// What a packet built for one purpose may contain.
+#[derive(Debug, Clone, Default)]
+pub struct PurposeRules {
+    pub conversation_window: bool,
+    pub insights: bool,
+    /// When insights are included, whether unvalidated ones are too.
+    pub unvalidated_insights: bool,
+    /// Facts whose key starts with any of these prefixes are left out.
+    pub excluded_fact_prefixes: Vec<String>,
+}
+
+impl PurposeRules {
+    fn allows_fact(&self, fact: &Fact) -> bool {
+        !self
+            .excluded_fact_prefixes
+            .iter()
+            .any(|prefix| fact.fact_key.starts_with(prefix.as_str()))
+    }
+}
+
+/// Purpose-specific exclusion rules, applied by the composer for every packet.
+///
+/// By default planners see insights, tool packets leave out PII facts (keys under
+/// `pii.`) and responders see no insights; no purpose gets the conversation window.
+#[derive(Debug, Clone)]
+pub struct PurposeFilter {
+    pub planner: PurposeRules,
+    pub tool: PurposeRules,
+    pub responder: PurposeRules,
+}
+
+impl Default for PurposeFilter {
+    fn default() -> Self {
+        Self {
+            planner: PurposeRules {
+                insights: true,
+                unvalidated_insights: true,
+                ..PurposeRules::default()
+            },
+            tool: PurposeRules {
+                unvalidated_insights: true,
+                excluded_fact_prefixes: vec!["pii.".to_string()],
+                ..PurposeRules::default()
+            },
+            responder: PurposeRules::default(),
+        }
+    }
+}
+
+impl PurposeFilter {
+    pub fn rules(&self, purpose: &Purpose) -> &PurposeRules {
+        match purpose {
+            Purpose::Planner => &self.planner,
+            Purpose::Tool => &self.tool,
+            Purpose::Responder => &self.responder,
+        }
+    }
+
+    pub fn rules_mut(&mut self, purpose: &Purpose) -> &mut PurposeRules {
+        match purpose {
+            Purpose::Planner => &mut self.planner,
+            Purpose::Tool => &mut self.tool,
+            Purpose::Responder => &mut self.responder,
         }
     }
 }
@@ -68,6 +219,18 @@ pub struct BuildRequest {
     pub policy_id: String,
     pub policy: RecallPolicy,
     pub persist: bool,
+    /// Best-effort latency bound for the build. Skippable sections load in the order
+    /// procedures, episodes, `period_summaries`, `conversation_window`, `cue_events`,
+    /// insights, `external_context`, and each is skipped once the time left is shorter
+    /// than the slowest section load so far, so the later ones are dropped first. Their
+    /// store calls are bounded by the time left (see [`with_timeout`](crate::with_timeout));
+    /// skips are reported in `budget_report.degradations`. Working state, STM, facts,
+    /// preferences and pinned procedures always load without a bound, so a slow backend
+    /// can still overrun the deadline.
+    pub deadline: Option<StdDuration>,
+    /// Where the build's reads may be served from; store wrappers act on it, e.g. a
+    /// latency-critical responder build can read from a cache or replica.
+    pub read_preference: ReadPreference,
 }
 
 impl BuildRequest {
@@ -81,6 +244,8 @@ impl BuildRequest {
             policy_id: "default".to_string(),
             policy: RecallPolicy::default(),
             persist: true,
+            deadline: None,
+            read_preference: ReadPreference::default(),
         }
     }
 }
@@ -90,38 +255,123 @@ pub fn build_memory_packet<S: Store + ?Sized>(
     store: &S,
     request: BuildRequest,
 ) -> StoreResult<MemoryPacket> {
-    let now = Utc::now();
+    with_read_preference(request.read_preference, || compose_packet(store, request, Utc::now()))
+}
+
+/// Builds the packet as if it were `now`, counting tokens for the budget's model.
+pub(crate) fn compose_packet<S: Store + ?Sized>(
+    store: &S,
+    request: BuildRequest,
+    now: DateTime<Utc>,
+) -> StoreResult<MemoryPacket> {
+    let counter = token_counter(request.budget.model.as_deref());
+    with_token_counter(counter, || compose(store, request, now))
+}
+
+/// Recency, episode windows and fact validity are measured from `now`.
+fn compose<S: Store + ?Sized>(
+    store: &S,
+    mut request: BuildRequest,
+    now: DateTime<Utc>,
+) -> StoreResult<MemoryPacket> {
+    request.cues.keywords = request.policy.cue_expansion.expand(&request.cues.keywords);
     let task_type = request
         .task_type
         .clone()
         .unwrap_or_else(|| "generic".to_string());
     
     debug!("Starting build_memory_packet");
+    let mut deadline = DeadlineGuard::new(request.deadline);
+
+    let (working_state, stm_state) = deadline.time(|| {
+        let mut working_state = store
+            .get_working_state(&request.scope)?
+            .unwrap_or_default();
+        // Merge metadata is bookkeeping for replicas, not context for the model.
+        working_state.clock = Default::default();
+        Ok((working_state, store.get_stm(&request.scope)?.unwrap_or_default()))
+    })?;
+    let locale = store
+        .get_user_locale(&request.scope.tenant_id, &request.scope.user_id)?
+        .filter(|locale| locale.check().is_ok());
+    let mut short_term = build_short_term(working_state, stm_state, &request);
+    if let Some(slot_schemas) = &request.policy.slot_schemas {
+        short_term.open_loops = slot_schemas.open_loops(&task_type, &short_term.working_state)?;
+    }
 
-    let working_state = store
-        .get_working_state(&request.scope)?
+    let rules = request.policy.filter.rules(&request.purpose);
+    let (suppressed, facts, preferences) = deadline.time(|| {
+        let suppressed: HashSet<MemoryRef> = store
+            .list_suppressions(&request.scope)?
+            .into_iter()
+            .map(|suppression| suppression.item)
+            .collect();
+        let facts = load_facts(store, &request, now, &suppressed)?;
+        let preferences = load_preferences(store, &request, now, &suppressed)?;
+        Ok((suppressed, facts, preferences))
+    })?;
+    let applicability = ApplicabilityContext {
+        purpose: &request.purpose,
+        cues: &request.cues,
+        slots: &short_term.working_state.slots,
+    };
+    // Pinned procedures are the tenant's rules, so they load even when time runs short.
+    let mut procedures =
+        deadline.time(|| load_pinned_procedures(store, &request, &applicability))?;
+    procedures.extend(
+        deadline
+            .load("procedures", || {
+                load_procedures(store, &request, &task_type, &applicability)
+            })?
+            .unwrap_or_default(),
+    );
+    let episodes = deadline
+        .load("episodes", || {
+            load_episodes(store, &request.scope, &request, now, locale.as_ref(), &suppressed)
+        })?
         .unwrap_or_default();
-    let stm_state = store.get_stm(&request.scope)?.unwrap_or_default();
-
-    let short_term = build_short_term(working_state, stm_state, store, &request)?;
-
-    let facts = load_facts(store, &request.scope, now, request.policy.max_facts)?;
-    let procedures =
-        load_procedures(store, &request.scope, &task_type, request.policy.max_procedures)?;
-    let episodes = load_episodes(store, &request.scope, &request, now)?;
-    let mut insight = load_insights(store, &request.scope, &request)?;
+    if request.policy.max_daily_summaries > 0 || request.policy.max_weekly_summaries > 0 {
+        short_term.period_summaries = deadline
+            .load("period_summaries", || {
+                load_period_summaries(store, &request.scope, &request.policy, &suppressed)
+            })?
+            .unwrap_or_default();
+    }
+    if rules.conversation_window {
+        short_term.conversation_window = deadline
+            .load("conversation_window", || {
+                let events = store.list_events(&request.scope, TimeRangeFilter::default(), None)?;
+                Ok(build_conversation_window(events, &request.policy))
+            })?
+            .unwrap_or_default();
+    }
+    if !request.cues.tags.is_empty()
+        || !request.cues.entities.is_empty()
+        || !request.cues.keywords.is_empty()
+    {
+        let quotes = deadline
+            .load("cue_events", || load_cue_quotes(store, &request))?
+            .unwrap_or_default();
+        merge_key_quotes(&mut short_term.key_quotes, quotes, request.policy.max_key_quotes);
+    }
+    let mut insight = bucket_insights(Vec::new(), request.policy.filter.responder.insights);
+    if rules.insights
+        && let Some(loaded) =
+            deadline.load("insights", || load_insights(store, &request.scope, &request))?
+    {
+        insight = loaded;
+    }
 
     let mut long_term = LongTerm {
         facts,
-        preferences: Vec::new(),
+        preferences,
         procedures,
         episodes,
     };
 
     enforce_total_candidate_limit(&request.policy, &mut long_term, &mut insight);
 
-    let mut citations = collect_citations(&short_term, &long_term, &insight);
-    citations.sort_by(|a, b| citation_sort_key(a).cmp(&citation_sort_key(b)));
+    let citations = collect_citations(&short_term, &long_term, &insight);
 
     let meta = Meta {
         schema_version: "v1".to_string(),
@@ -132,6 +382,11 @@ pub fn build_memory_packet<S: Store + ?Sized>(
         cues: cues_to_json(&request.cues),
         budget: request.budget.clone(),
         policy_id: request.policy_id.clone(),
+        timezone: locale.as_ref().map(|locale| locale.timezone.clone()),
+        locale: locale
+            .as_ref()
+            .map(|locale| locale.locale.clone())
+            .filter(|tag| !tag.is_empty()),
     };
 
     let mut packet = MemoryPacket {
@@ -140,16 +395,49 @@ pub fn build_memory_packet<S: Store + ?Sized>(
         long_term,
         insight,
         citations,
+        external_context: Vec::new(),
         budget_report: BudgetReport::default(),
         explain: JsonMap::new(),
     };
 
+    let mut provider_failures = Vec::new();
+    if !request.policy.external_context.is_empty() {
+        let query = ExternalContextQuery {
+            scope: &request.scope,
+            purpose: &request.purpose,
+            cues: &request.cues,
+            remaining_tokens: remaining_tokens(&request, &packet),
+        };
+        let providers = &request.policy.external_context;
+        if let Some(items) = deadline.load("external_context", || {
+            Ok(fetch_external_context(providers, &query, &mut provider_failures))
+        })? {
+            collect_citations_from_external(&items, &mut packet.citations);
+            packet.external_context = items;
+        }
+    }
+
     apply_budget(&request, &mut packet);
+    // Sections skipped for the deadline come before those degraded for the budget,
+    // and failed external context providers come last.
+    let ladder = std::mem::replace(&mut packet.budget_report.degradations, deadline.skipped);
+    packet.budget_report.degradations.extend(ladder);
+    packet.budget_report.degradations.extend(provider_failures);
+    flag_contradictions(store, &mut packet)?;
+    if !request.cues.keywords.is_empty() {
+        // Without full-text search, keyword cues matched substrings of a scan.
+        let search = if store.capabilities().full_text_search {
+            "full_text"
+        } else {
+            "scan"
+        };
+        packet.explain.insert("keyword_search".to_string(), json!(search));
+    }
 
-    if request.persist {
-        if let Err(e) = store.write_context_build(&request.scope, packet.clone()) {
-            warn!("Failed to persist context build: {}", e);
-        }
+    if request.persist
+        && let Err(e) = store.write_context_build(&request.scope, packet.clone())
+    {
+        warn!("Failed to persist context build: {}", e);
     }
     
     info!(
@@ -161,20 +449,99 @@ pub fn build_memory_packet<S: Store + ?Sized>(
     Ok(packet)
 }
 
-fn build_short_term<S: Store + ?Sized>(
+/// Builds one packet per request on up to `concurrency` threads (the machine's
+/// parallelism when 0), for batch jobs that precompute context for many users.
+///
+/// Requests for the same run are built by one worker in their original order, so
+/// persisted builds of a run never race on its STM. Results come back in request
+/// order; one failed build does not stop the others.
+pub fn build_memory_packets_bulk<S: Store + ?Sized>(
+    store: &S,
+    requests: Vec<BuildRequest>,
+    concurrency: usize,
+) -> Vec<StoreResult<MemoryPacket>> {
+    let total = requests.len();
+    let mut groups: Vec<Vec<(usize, BuildRequest)>> = Vec::new();
+    let mut group_of: HashMap<RunKey, usize> = HashMap::new();
+    for (idx, request) in requests.into_iter().enumerate() {
+        let group = *group_of
+            .entry(RunKey::from(&request.scope))
+            .or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+        groups[group].push((idx, request));
+    }
+
+    let concurrency = if concurrency == 0 {
+        std::thread::available_parallelism().map_or(1, usize::from)
+    } else {
+        concurrency
+    };
+    let workers = concurrency.min(groups.len()).max(1);
+    debug!(
+        "building {} packets for {} runs on {} workers",
+        total,
+        groups.len(),
+        workers
+    );
+    let queue = Mutex::new(groups.into_iter());
+    let mut results: Vec<Option<StoreResult<MemoryPacket>>> = (0..total).map(|_| None).collect();
+    std::thread::scope(|threads| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                threads.spawn(|| {
+                    let mut built = Vec::new();
+                    loop {
+                        let next = match queue.lock() {
+                            Ok(mut queue) => queue.next(),
+                            Err(_) => None,
+                        };
+                        let Some(group) = next else {
+                            break;
+                        };
+                        for (idx, request) in group {
+                            built.push((idx, build_memory_packet(store, request)));
+                        }
+                    }
+                    built
+                })
+            })
+            .collect();
+        for handle in handles {
+            if let Ok(built) = handle.join() {
+                for (idx, result) in built {
+                    results[idx] = Some(result);
+                }
+            }
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or(Err(StoreError::Poisoned)))
+        .collect()
+}
+
+fn build_short_term(
     working_state: engram_types::WorkingState,
     stm_state: StmState,
-    store: &S,
     request: &BuildRequest,
-) -> StoreResult<ShortTerm> {
-    let mut short_term = ShortTerm::default();
-    
+) -> ShortTerm {
     // Pass by value optimization: move fields instead of cloning
-    short_term.last_tool_evidence = working_state.tool_evidence.clone();
-    short_term.working_state = working_state;
-    short_term.rolling_summary = stm_state.rolling_summary;
-
-    short_term.key_quotes = stm_state.key_quotes;
+    let mut short_term = ShortTerm {
+        last_tool_evidence: working_state.tool_evidence.clone(),
+        working_state,
+        rolling_summary: request.policy.summary_condensing.condense(stm_state.rolling_summary),
+        key_quotes: stm_state.key_quotes,
+        ..ShortTerm::default()
+    };
+    if !request.cues.keywords.is_empty() {
+        // Quotes mentioning a cue keyword go first, so they outlast the quote cap
+        // and budget trimming, which drops quotes from the back.
+        short_term.key_quotes.sort_by_key(|quote| {
+            std::cmp::Reverse(keyword_hits(&quote.quote, &request.cues.keywords))
+        });
+    }
     if short_term.key_quotes.len() > request.policy.max_key_quotes {
         short_term.key_quotes.truncate(request.policy.max_key_quotes);
     }
@@ -185,36 +552,174 @@ fn build_short_term<S: Store + ?Sized>(
             .truncate(request.policy.last_tool_evidence_limit);
     }
 
-    if request.policy.include_conversation_window {
-        let events = store.list_events(&request.scope, TimeRangeFilter::default(), None)?;
-        short_term.conversation_window =
-            build_conversation_window(events, request.policy.conversation_window);
+    short_term
+}
+
+/// Tracks [`BuildRequest::deadline`] while packet sections load.
+struct DeadlineGuard {
+    started: Instant,
+    deadline: Option<StdDuration>,
+    slowest: StdDuration,
+    skipped: Vec<Value>,
+}
+
+impl DeadlineGuard {
+    fn new(deadline: Option<StdDuration>) -> Self {
+        Self {
+            started: Instant::now(),
+            deadline,
+            slowest: StdDuration::ZERO,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Loads a section that may be skipped: returns `None` when the time left is
+    /// shorter than the slowest load so far. Each store call of the section is bounded
+    /// by the time left, as a statement timeout on SQL backends; a section that runs
+    /// out of time is skipped too.
+    fn load<T>(
+        &mut self,
+        section: &str,
+        load: impl FnOnce() -> StoreResult<T>,
+    ) -> StoreResult<Option<T>> {
+        let elapsed = self.started.elapsed();
+        let Some(deadline) = self.deadline else {
+            return self.time(load).map(Some);
+        };
+        if elapsed + self.slowest >= deadline {
+            debug!("Skipping {} after {:?}, deadline {:?}", section, elapsed, deadline);
+            self.skip(section, elapsed);
+            return Ok(None);
+        }
+        match self.time(|| with_timeout_cap(deadline - elapsed, load)) {
+            Err(StoreError::Timeout(e)) => {
+                debug!("Skipping {} after it ran out of time: {}", section, e);
+                self.skip(section, self.started.elapsed());
+                Ok(None)
+            }
+            result => result.map(Some),
+        }
     }
 
-    Ok(short_term)
+    fn skip(&mut self, section: &str, elapsed: StdDuration) {
+        self.skipped.push(json!({
+            "section": section,
+            "reason": "deadline",
+            "elapsed_ms": elapsed.as_millis() as u64,
+        }));
+    }
+
+    /// Loads a section that is never skipped, timing it like the others.
+    fn time<T>(&mut self, load: impl FnOnce() -> StoreResult<T>) -> StoreResult<T> {
+        let started = Instant::now();
+        let value = load()?;
+        self.slowest = self.slowest.max(started.elapsed());
+        Ok(value)
+    }
 }
 
 fn load_facts<S: Store + ?Sized>(
     store: &S,
-    scope: &Scope,
+    request: &BuildRequest,
     now: DateTime<Utc>,
-    max_facts: usize,
+    suppressed: &HashSet<MemoryRef>,
 ) -> StoreResult<Vec<Fact>> {
-    let mut facts = store.list_facts(
-        scope,
-        FactFilter {
-            status: Some(vec![FactStatus::Active]),
-            valid_at: Some(now),
-            limit: Some(max_facts),
-        },
-    )?;
-
-    facts.sort_by(|a, b| {
-        a.fact_key
-            .cmp(&b.fact_key)
-            .then_with(|| a.fact_id.cmp(&b.fact_id))
+    let scope = &request.scope;
+    let policy = &request.policy;
+    let rules = policy.filter.rules(&request.purpose);
+    let lang = request.cues.lang.as_deref();
+    let max_facts = policy.max_facts;
+    // Facts the purpose leaves out are dropped before the limit, like suppressed ones.
+    let is_left_out = |fact: &Fact| {
+        !rules.allows_fact(fact)
+            || suppressed.contains(&MemoryRef {
+                kind: MemoryKind::Fact,
+                id: fact.fact_id.clone(),
+            })
+    };
+    // Over-fetch by the number of suppressed facts so they don't eat into `max_facts`.
+    let suppressed_facts = suppressed
+        .iter()
+        .filter(|item| item.kind == MemoryKind::Fact)
+        .count();
+    // Facts outside their validity window are left out even before
+    // `expire_facts` deprecates them. Looking back, facts that expired since count as
+    // active, but not those deprecated for another reason.
+    let (statuses, valid_at) = match request.cues.valid_at {
+        Some(at) => (vec![FactStatus::Active, FactStatus::Deprecated], at),
+        None => (vec![FactStatus::Active], now),
+    };
+    let is_retracted = |fact: &Fact| {
+        fact.status == FactStatus::Deprecated
+            && fact.validity.valid_to.is_none_or(|valid_to| valid_to > now)
+    };
+    let filter = FactFilter {
+        status: Some(statuses),
+        valid_at: Some(valid_at),
+        limit: None,
+        include_shared: policy.include_shared_facts,
+        pinned: None,
+        preferences: Some(false),
+        keys: None,
+    };
+    // With source credibility or a conversation language set, every fact is a
+    // candidate: the most credible ones in that language are kept, whatever order the
+    // backend lists them in. Looking back, retracted facts must not eat into the limit,
+    // nor may the facts the purpose excludes.
+    let credibility = store.get_source_credibility(&scope.tenant_id)?;
+    let rank_all = credibility.is_some()
+        || lang.is_some()
+        || request.cues.valid_at.is_some()
+        || policy.ranker.is_some()
+        || !rules.excluded_fact_prefixes.is_empty();
+    // Pinned facts are always recalled and don't count against `max_facts`. With
+    // shared facts, precedence across levels holds between the two lists too: a pinned
+    // pool fact does not hide the user's own fact with the same key.
+    let pinned = FactFilter {
+        pinned: Some(true),
+        ..filter.clone()
+    };
+    let unpinned = FactFilter {
+        limit: (!rank_all).then_some(max_facts + suppressed_facts),
+        pinned: Some(false),
+        ..filter
+    };
+    let (mut pinned, mut facts) =
+        (store.list_facts(scope, pinned)?, store.list_facts(scope, unpinned)?);
+    pinned.retain(|fact| !is_left_out(fact) && !is_retracted(fact));
+    facts.retain(|fact| {
+        !is_left_out(fact)
+            && !is_retracted(fact)
+            && !pinned.iter().any(|p| p.fact_key == fact.fact_key)
     });
 
+    if let Some(credibility) = &credibility {
+        apply_source_credibility(store, scope, credibility, &mut pinned)?;
+        apply_source_credibility(store, scope, credibility, &mut facts)?;
+        keep_most_confident(&mut facts);
+        facts.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    }
+    if let Some(ranker) = &policy.ranker {
+        sort_by_score(&mut facts, |fact| {
+            ranker.score(Candidate::Fact(fact), &request.cues, None)
+        });
+    }
+    if let Some(lang) = lang {
+        select_facts_by_lang(&mut facts, lang, policy.lang_mode);
+    }
+    if rank_all {
+        facts.truncate(max_facts);
+    }
+
+    // A ranker's facts stay best first; otherwise they are listed by key.
+    if policy.ranker.is_none() {
+        facts.sort_by(|a, b| {
+            a.fact_key
+                .cmp(&b.fact_key)
+                .then_with(|| a.fact_id.cmp(&b.fact_id))
+        });
+    }
+
     if facts.len() > max_facts {
         debug!(
             "Trimming facts from {} to limit {}",
@@ -224,20 +729,83 @@ fn load_facts<S: Store + ?Sized>(
         facts.truncate(max_facts);
     }
 
-    Ok(facts)
+    pinned.sort_by(|a, b| {
+        a.fact_key
+            .cmp(&b.fact_key)
+            .then_with(|| a.fact_id.cmp(&b.fact_id))
+    });
+    pinned.extend(facts);
+    Ok(pinned)
+}
+
+/// The scope's active preferences, ordered by key like facts. Unlike facts they are
+/// always recalled as they are now, whatever `RecallCues::valid_at` asks for.
+fn load_preferences<S: Store + ?Sized>(
+    store: &S,
+    request: &BuildRequest,
+    now: DateTime<Utc>,
+    suppressed: &HashSet<MemoryRef>,
+) -> StoreResult<Vec<Fact>> {
+    if request.policy.max_preferences == 0 {
+        return Ok(Vec::new());
+    }
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Active]),
+        valid_at: Some(now),
+        include_shared: request.policy.include_shared_facts,
+        preferences: Some(true),
+        ..FactFilter::default()
+    };
+    let rules = request.policy.filter.rules(&request.purpose);
+    let mut preferences = store.list_facts(&request.scope, filter)?;
+    preferences.retain(|fact| {
+        rules.allows_fact(fact)
+            && !suppressed.contains(&MemoryRef {
+                kind: MemoryKind::Fact,
+                id: fact.fact_id.clone(),
+            })
+    });
+    preferences.truncate(request.policy.max_preferences);
+    Ok(preferences)
+}
+
+/// The tenant's pinned procedures, highest priority first, up to the policy's limit.
+fn load_pinned_procedures<S: Store + ?Sized>(
+    store: &S,
+    request: &BuildRequest,
+    applicability: &ApplicabilityContext<'_>,
+) -> StoreResult<Vec<engram_types::Procedure>> {
+    if request.policy.max_pinned_procedures == 0 {
+        return Ok(Vec::new());
+    }
+    let mut procedures = list_tenant_procedures(store, &request.scope.tenant_id)?;
+    procedures.retain(|procedure| procedure_applies(procedure, applicability));
+    procedures.truncate(request.policy.max_pinned_procedures);
+    Ok(procedures)
 }
 
 fn load_procedures<S: Store + ?Sized>(
     store: &S,
-    scope: &Scope,
+    request: &BuildRequest,
     task_type: &str,
-    max_procedures: usize,
+    applicability: &ApplicabilityContext<'_>,
 ) -> StoreResult<Vec<engram_types::Procedure>> {
-    let mut procedures = store.list_procedures(scope, task_type, Some(max_procedures))?;
-    procedures.sort_by(|a, b| {
-        b.priority
-            .cmp(&a.priority)
-            .then_with(|| a.procedure_id.cmp(&b.procedure_id))
+    let max_procedures = request.policy.max_procedures;
+    let ranker = request.policy.ranker.as_deref();
+    // A ranker may prefer procedures the backend would not list first.
+    let limit = ranker.is_none().then_some(max_procedures);
+    let mut procedures = store.list_procedures(&request.scope, task_type, limit)?;
+    let listed = procedures.len();
+    procedures.retain(|procedure| procedure_applies(procedure, applicability));
+    // The backend's first procedures may not apply, leaving room for later ones.
+    if limit == Some(listed) && procedures.len() < listed {
+        procedures = store.list_procedures(&request.scope, task_type, None)?;
+        procedures.retain(|procedure| procedure_applies(procedure, applicability));
+    }
+    procedures.sort_by(|a, b| a.procedure_id.cmp(&b.procedure_id));
+    let ranker = ranker.unwrap_or(&DefaultRanker);
+    sort_by_score(&mut procedures, |procedure| {
+        ranker.score(Candidate::Procedure(procedure), &request.cues, None)
     });
     if procedures.len() > max_procedures {
         procedures.truncate(max_procedures);
@@ -250,40 +818,285 @@ fn load_episodes<S: Store + ?Sized>(
     scope: &Scope,
     request: &BuildRequest,
     now: DateTime<Utc>,
+    locale: Option<&UserLocale>,
+    suppressed: &HashSet<MemoryRef>,
 ) -> StoreResult<Vec<Episode>> {
-    let mut filter = EpisodeFilter::default();
-    if let Some(range) = &request.cues.time_range {
-        filter.time_range = Some(range.clone());
-    } else {
-        let start = now - Duration::days(request.policy.episode_time_window_days);
-        filter.time_range = Some(TimeRangeFilter {
+    let mut windows = request.cues.time_windows();
+    if windows.is_empty() {
+        // With a timezone preference the window covers whole local calendar days.
+        let days = request.policy.episode_time_window_days;
+        let start = match locale {
+            Some(locale) => locale.days_ago_midnight(now, days)?,
+            None => now - Duration::days(days),
+        };
+        windows.push(TimeRangeFilter {
             start: Some(start),
             end: Some(now),
         });
     }
-    filter.tags = request.cues.tags.clone();
-    filter.entities = request.cues.entities.clone();
 
-    let mut episodes = store.list_episodes(scope, filter)?;
-    for episode in &mut episodes {
-        episode.recency_score = Some(compute_recency_score(episode, now));
+    let lang = request.cues.lang.as_deref();
+    let mut episodes: Vec<Episode> = Vec::new();
+    // Keyword matches join the episodes the tag and entity cues found, in every window.
+    let mut matched = HashSet::new();
+    for window in windows {
+        let filter = EpisodeFilter {
+            time_range: Some(window.clone()),
+            tags: request.cues.tags.clone(),
+            entities: request.cues.entities.clone(),
+            limit: None,
+        };
+        let listed = store.list_episodes(scope, filter)?;
+        let searched = if request.cues.keywords.is_empty() {
+            Vec::new()
+        } else {
+            let query = TextQuery {
+                terms: request.cues.keywords.clone(),
+                time_range: Some(window),
+                limit: None,
+            };
+            store.search_episodes(scope, &query)?
+        };
+        matched.extend(searched.iter().map(|episode| episode.episode_id.clone()));
+        for episode in listed.into_iter().chain(searched) {
+            if !episodes.iter().any(|listed| listed.episode_id == episode.episode_id) {
+                episodes.push(episode);
+            }
+        }
     }
-    episodes.sort_by(|a, b| {
-        b.recency_score
-            .unwrap_or(0.0)
-            .partial_cmp(&a.recency_score.unwrap_or(0.0))
-            .unwrap_or(Ordering::Equal)
-            .then_with(|| a.episode_id.cmp(&b.episode_id))
+    episodes.retain(|episode| {
+        let item = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode.episode_id.clone(),
+        };
+        !is_period_summary(episode)
+            && !suppressed.contains(&item)
+            && (request.policy.lang_mode != LangMode::Filter
+                || lang.is_none_or(|lang| lang_matches(episode.lang.as_deref(), lang)))
     });
-    if episodes.len() > request.policy.max_episodes {
+    let outcomes = if request.policy.outcome_weight > 0.0 {
+        store.list_run_outcomes(scope, None)?
+    } else {
+        Vec::new()
+    };
+    // Preferring a language or matching keywords ranks every episode, then moves the
+    // ones in that language, and after them the keyword matches, ahead before trimming
+    // to `max_episodes`.
+    let prefer = lang.filter(|_| request.policy.lang_mode == LangMode::Prefer);
+    let reorder = prefer.is_some() || !matched.is_empty();
+    rank_episodes_by(
+        &mut episodes,
+        now,
+        if reorder {
+            usize::MAX
+        } else {
+            request.policy.max_episodes
+        },
+        &outcomes,
+        request,
+    );
+    if reorder {
+        episodes.sort_by_key(|episode| {
+            (
+                prefer.is_some_and(|lang| !lang_matches(episode.lang.as_deref(), lang)),
+                !matched.contains(&episode.episode_id),
+            )
+        });
+        episodes.truncate(request.policy.max_episodes);
+    }
+    Ok(episodes)
+}
+
+/// The newest daily and weekly summaries of the scope, daily ones first.
+fn load_period_summaries<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    policy: &RecallPolicy,
+    suppressed: &HashSet<MemoryRef>,
+) -> StoreResult<Vec<Episode>> {
+    let mut summaries = Vec::new();
+    for (tag, limit) in [
+        (DAILY_SUMMARY_TAG, policy.max_daily_summaries),
+        (WEEKLY_SUMMARY_TAG, policy.max_weekly_summaries),
+    ] {
+        if limit == 0 {
+            continue;
+        }
+        let filter = EpisodeFilter {
+            tags: vec![tag.to_string()],
+            ..EpisodeFilter::default()
+        };
+        let mut period = store.list_episodes(scope, filter)?;
+        period.retain(|summary| {
+            !suppressed.contains(&MemoryRef {
+                kind: MemoryKind::Episode,
+                id: summary.episode_id.clone(),
+            })
+        });
+        period.sort_by_key(|summary| std::cmp::Reverse(summary.time_range.start));
+        summaries.extend(period.into_iter().take(limit));
+    }
+    Ok(summaries)
+}
+
+/// Quotes the run's events tagged with the request's cue tags or entities, plus the
+/// `max_key_quotes` best matches of its cue keywords, most recent last. The lookups go
+/// through the backend's event tag, entity and full-text indexes.
+fn load_cue_quotes<S: Store + ?Sized>(
+    store: &S,
+    request: &BuildRequest,
+) -> StoreResult<Vec<KeyQuote>> {
+    let cues = &request.cues;
+    let mut windows: Vec<Option<TimeRangeFilter>> =
+        cues.time_windows().into_iter().map(Some).collect();
+    if windows.is_empty() {
+        windows.push(None);
+    }
+    let mut events: Vec<Event> = Vec::new();
+    for time_range in windows {
+        let mut found = Vec::new();
+        if !cues.tags.is_empty() || !cues.entities.is_empty() {
+            found = store.find_events(
+                &request.scope,
+                EventFilter {
+                    time_range: time_range.clone(),
+                    tags: cues.tags.clone(),
+                    entities: cues.entities.clone(),
+                    limit: None,
+                },
+            )?;
+        }
+        if !cues.keywords.is_empty() {
+            let query = TextQuery {
+                terms: cues.keywords.clone(),
+                time_range,
+                limit: Some(request.policy.max_key_quotes),
+            };
+            found.extend(store.search_events(&request.scope, &query)?);
+        }
+        for event in found {
+            if !events.iter().any(|seen| seen.event_id == event.event_id) {
+                events.push(event);
+            }
+        }
+    }
+    events.sort_by_key(|event| (event.ts, event.seq));
+    let lang = request.cues.lang.as_deref();
+    let mut quotes: Vec<KeyQuote> = events
+        .iter()
+        .filter(|event| {
+            request.policy.lang_mode != LangMode::Filter
+                || lang.is_none_or(|lang| lang_matches(event.lang.as_deref(), lang))
+        })
+        .filter_map(|event| {
+            let (quote, role) = parse_event_payload(&event.payload)?;
+            Some(KeyQuote {
+                evidence_id: event.event_id.clone(),
+                quote,
+                role,
+                ts: Some(event.ts),
+            })
+        })
+        .collect();
+    if quotes.len() > request.policy.max_key_quotes {
+        quotes = quotes.split_off(quotes.len() - request.policy.max_key_quotes);
+    }
+    Ok(quotes)
+}
+
+/// Appends cue-matched quotes after the STM's own, skipping events already quoted,
+/// until `max_key_quotes` is reached.
+fn merge_key_quotes(
+    key_quotes: &mut Vec<KeyQuote>,
+    quotes: Vec<KeyQuote>,
+    max_key_quotes: usize,
+) {
+    for quote in quotes {
+        if key_quotes.len() >= max_key_quotes {
+            break;
+        }
+        if !key_quotes.iter().any(|existing| existing.evidence_id == quote.evidence_id) {
+            key_quotes.push(quote);
+        }
+    }
+}
+
+/// Scores episodes by recency, orders them best first and keeps the top
+/// `max_episodes`. Public so composer CPU cost can be benchmarked apart from reads.
+pub fn rank_episodes(episodes: &mut Vec<Episode>, now: DateTime<Utc>, max_episodes: usize) {
+    rank_episodes_with_outcomes(episodes, now, max_episodes, &[], 0.0);
+}
+
+/// [`rank_episodes`], with each recency score scaled by the outcome of the episode's
+/// run as described for [`RecallPolicy::outcome_weight`].
+pub fn rank_episodes_with_outcomes(
+    episodes: &mut Vec<Episode>,
+    now: DateTime<Utc>,
+    max_episodes: usize,
+    outcomes: &[RunOutcome],
+    outcome_weight: f64,
+) {
+    score_episodes(
+        episodes,
+        now,
+        max_episodes,
+        outcomes,
+        outcome_weight,
+        &DefaultRanker,
+        &RecallCues::default(),
+    );
+}
+
+/// Ranks episodes with the request's ranker and outcome weight.
+fn rank_episodes_by(
+    episodes: &mut Vec<Episode>,
+    now: DateTime<Utc>,
+    max_episodes: usize,
+    outcomes: &[RunOutcome],
+    request: &BuildRequest,
+) {
+    score_episodes(
+        episodes,
+        now,
+        max_episodes,
+        outcomes,
+        request.policy.outcome_weight,
+        request.policy.ranker.as_deref().unwrap_or(&DefaultRanker),
+        &request.cues,
+    );
+}
+
+/// Sets each episode's `recency_score` to its `ranker` score, scaled by its run's
+/// outcome, then orders them best first and keeps the top `max_episodes`.
+fn score_episodes(
+    episodes: &mut Vec<Episode>,
+    now: DateTime<Utc>,
+    max_episodes: usize,
+    outcomes: &[RunOutcome],
+    outcome_weight: f64,
+    ranker: &dyn Ranker,
+    cues: &RecallCues,
+) {
+    let signals = run_signals(outcomes);
+    let weight = outcome_weight.clamp(0.0, 1.0);
+    for episode in episodes.iter_mut() {
+        let recency = compute_recency_score(episode, now);
+        let mut score = ranker.score(Candidate::Episode(episode), cues, Some(recency));
+        if let Some(signal) = episode_run(episode).and_then(|run_id| signals.get(run_id)) {
+            score *= 1.0 + weight * (2.0 * signal - 1.0);
+        }
+        episode.recency_score = Some(score);
+    }
+    episodes.sort_by(|a, b| a.episode_id.cmp(&b.episode_id));
+    sort_by_score(episodes, |episode| episode.recency_score.unwrap_or(0.0));
+    if episodes.len() > max_episodes {
         debug!(
             "Trimming episodes from {} to limit {}",
             episodes.len(),
-            request.policy.max_episodes
+            max_episodes
         );
-        episodes.truncate(request.policy.max_episodes);
+        episodes.truncate(max_episodes);
     }
-    Ok(episodes)
 }
 
 fn load_insights<S: Store + ?Sized>(
@@ -291,40 +1104,42 @@ fn load_insights<S: Store + ?Sized>(
     scope: &Scope,
     request: &BuildRequest,
 ) -> StoreResult<Insight> {
-    let include_insights = match request.purpose {
-        Purpose::Planner => true,
-        Purpose::Tool => request.policy.include_insights_in_tool,
-        Purpose::Responder => request.policy.allow_insights_in_responder,
-    };
+    let rules = request.policy.filter.rules(&request.purpose);
+    let allow_in_responder = request.policy.filter.responder.insights;
 
-    if !include_insights {
+    if !rules.insights {
         return Ok(Insight {
-            usage_policy: UsagePolicy {
-                allow_in_responder: request.policy.allow_insights_in_responder,
-            },
+            usage_policy: UsagePolicy { allow_in_responder },
             hypotheses: Vec::new(),
             strategy_sketches: Vec::new(),
             patterns: Vec::new(),
         });
     }
 
+    let mut validation_states = vec![
+        engram_types::ValidationState::Validated,
+        engram_types::ValidationState::Testing,
+    ];
+    if rules.unvalidated_insights {
+        validation_states.push(engram_types::ValidationState::Unvalidated);
+    }
     let mut items = store.list_insights(
         scope,
         InsightFilter {
-            validation_state: Some(vec![
-                engram_types::ValidationState::Validated,
-                engram_types::ValidationState::Testing,
-                engram_types::ValidationState::Unvalidated,
-            ]),
+            validation_state: Some(validation_states),
             limit: None,
         },
     )?;
-    items.sort_by(|a, b| insight_sort_key(b).cmp(&insight_sort_key(a)));
+    items.sort_by(|a, b| b.id.cmp(&a.id));
+    let ranker = request.policy.ranker.as_deref().unwrap_or(&DefaultRanker);
+    sort_by_score(&mut items, |item| {
+        ranker.score(Candidate::Insight(item), &request.cues, None)
+    });
     if items.len() > request.policy.max_insights {
         items.truncate(request.policy.max_insights);
     }
 
-    Ok(bucket_insights(items, request.policy.allow_insights_in_responder))
+    Ok(bucket_insights(items, allow_in_responder))
 }
 
 fn bucket_insights(items: Vec<InsightItem>, allow_in_responder: bool) -> Insight {
@@ -350,37 +1165,62 @@ fn bucket_insights(items: Vec<InsightItem>, allow_in_responder: bool) -> Insight
     }
 }
 
-fn insight_sort_key(item: &InsightItem) -> (u8, i32, String) {
-    let state_rank = match item.validation_state {
-        engram_types::ValidationState::Validated => 3,
-        engram_types::ValidationState::Testing => 2,
-        engram_types::ValidationState::Unvalidated => 1,
-        engram_types::ValidationState::Rejected => 0,
-    };
-    let confidence_rank = (item.confidence * 1000.0) as i32;
-    (state_rank, confidence_rank, item.id.clone())
-}
-
-fn compute_recency_score(episode: &Episode, now: DateTime<Utc>) -> f64 {
+pub(crate) fn compute_recency_score(episode: &Episode, now: DateTime<Utc>) -> f64 {
     let elapsed = now - episode.time_range.start;
     let days = elapsed.num_seconds().max(0) as f64 / 86_400.0;
     1.0 / (1.0 + days)
 }
 
-fn build_conversation_window(events: Vec<Event>, limit: usize) -> Vec<ConversationTurn> {
-    let mut turns: Vec<ConversationTurn> = events
+fn build_conversation_window(events: Vec<Event>, policy: &RecallPolicy) -> Vec<ConversationTurn> {
+    let mut turns: Vec<(ConversationTurn, u32)> = events
         .into_iter()
-        .filter_map(|event| event_to_turn(&event))
+        .filter_map(|event| {
+            let turn = event_to_turn(&event)?;
+            let tokens = event
+                .token_count()
+                .unwrap_or_else(|| estimate_tokens(&turn.content));
+            Some((turn, tokens))
+        })
         .collect();
 
-    turns.sort_by(|a, b| a.ts.cmp(&b.ts));
-    if turns.len() > limit {
-        turns = turns.split_off(turns.len() - limit);
+    turns.sort_by_key(|(turn, _)| turn.ts);
+    let keep = if policy.conversation_window_tokens > 0 {
+        let mut used = 0usize;
+        turns
+            .iter()
+            .rev()
+            .take_while(|(_, tokens)| {
+                used += *tokens as usize;
+                used <= policy.conversation_window_tokens
+            })
+            .count()
+    } else {
+        policy.conversation_window
+    };
+    if turns.len() > keep {
+        turns = turns.split_off(turns.len() - keep);
     }
-    turns
+    turns.into_iter().map(|(turn, _)| turn).collect()
 }
 
-fn event_to_turn(event: &Event) -> Option<ConversationTurn> {
+/// `event`'s payload as stores persist it: an object payload of a message event gains
+/// [`TOKEN_COUNT_KEY`], the estimated tokens of its content, unless it already has one.
+pub(crate) fn stored_payload(event: &Event) -> Cow<'_, Value> {
+    if event.kind != EventKind::Message || event.payload.get(TOKEN_COUNT_KEY).is_some() {
+        return Cow::Borrowed(&event.payload);
+    }
+    let Some((content, _)) = parse_event_payload(&event.payload) else {
+        return Cow::Borrowed(&event.payload);
+    };
+    let Value::Object(map) = &event.payload else {
+        return Cow::Borrowed(&event.payload);
+    };
+    let mut map = map.clone();
+    map.insert(TOKEN_COUNT_KEY.to_string(), json!(estimate_tokens(&content)));
+    Cow::Owned(Value::Object(map))
+}
+
+pub(crate) fn event_to_turn(event: &Event) -> Option<ConversationTurn> {
     if !matches!(event.kind, EventKind::Message) {
         return None;
     }
@@ -394,7 +1234,7 @@ fn event_to_turn(event: &Event) -> Option<ConversationTurn> {
     })
 }
 
-fn parse_event_payload(payload: &Value) -> Option<(String, engram_types::Role)> {
+pub(crate) fn parse_event_payload(payload: &Value) -> Option<(String, engram_types::Role)> {
     match payload {
         Value::String(text) => Some((text.clone(), engram_types::Role::User)),
         Value::Object(map) => {
@@ -438,10 +1278,27 @@ fn cues_to_json(cues: &RecallCues) -> JsonMap {
         let end = range.end.map(|e| e.to_rfc3339());
         map.insert("time_range".to_string(), json!({ "start": start, "end": end }));
     }
+    if !cues.time_ranges.is_empty() {
+        let ranges: Vec<Value> = cues
+            .time_ranges
+            .iter()
+            .map(|range| {
+                let start = range.start.map(|s| s.to_rfc3339());
+                let end = range.end.map(|e| e.to_rfc3339());
+                json!({ "start": start, "end": end })
+            })
+            .collect();
+        map.insert("time_ranges".to_string(), json!(ranges));
+    }
+    if let Some(lang) = &cues.lang {
+        map.insert("lang".to_string(), json!(lang));
+    }
     map
 }
 
-fn collect_citations(
+/// Gathers one citation per distinct evidence id across the packet sections,
+/// sorted by kind and id.
+pub fn collect_citations(
     short_term: &ShortTerm,
     long_term: &LongTerm,
     insight: &Insight,
@@ -451,12 +1308,45 @@ fn collect_citations(
     collect_citations_from_key_quotes(&short_term.key_quotes, &mut citations);
     collect_citations_from_evidence(&short_term.last_tool_evidence, &mut citations);
     collect_citations_from_facts(&long_term.facts, &mut citations);
+    collect_citations_from_facts(&long_term.preferences, &mut citations);
     collect_citations_from_episodes(&long_term.episodes, &mut citations);
+    collect_citations_from_episodes(&short_term.period_summaries, &mut citations);
     collect_citations_from_procedures(&long_term.procedures, &mut citations);
     collect_citations_from_insights(insight, &mut citations);
     collect_citations_from_conversation_window(&short_term.conversation_window, &mut citations);
 
-    citations.into_values().collect()
+    let mut citations: Vec<Citation> = citations.into_values().collect();
+    citations.sort_by_key(citation_sort_key);
+    citations
+}
+
+/// Adds a citation per external context item to `citations`, keeping them sorted.
+fn collect_citations_from_external(items: &[ExternalContext], citations: &mut Vec<Citation>) {
+    for item in items {
+        let cited = citations.iter().any(|citation| {
+            citation.id == item.id && matches!(citation.kind, CitationType::External)
+        });
+        if !cited {
+            citations.push(Citation {
+                id: item.id.clone(),
+                kind: CitationType::External,
+                ts: None,
+                summary: item.title.clone(),
+            });
+        }
+    }
+    citations.sort_by_key(citation_sort_key);
+}
+
+/// Tokens `request`'s budget leaves for external context after `packet`'s memory
+/// sections, capped by the section's own allowance; `None` when neither bounds it.
+fn remaining_tokens(request: &BuildRequest, packet: &MemoryPacket) -> Option<u32> {
+    let section = per_section_limit(&request.budget, EXTERNAL_CONTEXT);
+    if request.budget.max_tokens == 0 {
+        return section;
+    }
+    let remaining = request.budget.max_tokens.saturating_sub(estimate_packet_tokens(packet));
+    Some(section.map_or(remaining, |limit| limit.min(remaining)))
 }
 
 fn collect_citations_from_key_quotes(
@@ -575,11 +1465,13 @@ fn citation_key(id: &str, kind: &CitationType) -> String {
     format!("{}|{}", id, citation_kind_label(kind))
 }
 
-fn citation_kind_label(kind: &CitationType) -> &'static str {
+fn citation_kind_label(kind: &CitationType) -> &str {
     match kind {
         CitationType::Message => "message",
         CitationType::ToolResult => "tool_result",
         CitationType::StatePatch => "state_patch",
+        CitationType::External => "external",
+        CitationType::Custom(name) => name,
     }
 }
 
@@ -588,9 +1480,10 @@ fn citation_sort_key(citation: &Citation) -> (String, String) {
 }
 
 fn evidence_kind_to_citation(kind: &str) -> CitationType {
-    match kind {
-        "tool_result" => CitationType::ToolResult,
-        "state_patch" => CitationType::StatePatch,
+    match kind.parse::<EventKind>() {
+        Ok(EventKind::ToolResult) => CitationType::ToolResult,
+        Ok(EventKind::StatePatch) => CitationType::StatePatch,
+        Ok(EventKind::Custom(name)) => CitationType::Custom(name),
         _ => CitationType::Message,
     }
 }
@@ -610,9 +1503,9 @@ fn enforce_total_candidate_limit(policy: &RecallPolicy, long_term: &mut LongTerm
             insight.patterns.pop();
         } else if !long_term.episodes.is_empty() {
             long_term.episodes.pop();
-        } else if !long_term.procedures.is_empty() {
+        } else if long_term.procedures.last().is_some_and(|p| !is_pinned_procedure(p)) {
             long_term.procedures.pop();
-        } else if !long_term.facts.is_empty() {
+        } else if long_term.facts.last().is_some_and(|fact| !fact.pinned) {
             long_term.facts.pop();
         } else {
             break;
@@ -628,16 +1521,46 @@ fn insight_total(insight: &Insight) -> usize {
     insight.hypotheses.len() + insight.strategy_sketches.len() + insight.patterns.len()
 }
 
-fn apply_budget(request: &BuildRequest, packet: &mut MemoryPacket) {
+/// Clips and trims `packet` to the request's budget and fills in its budget report
+/// and explain section. This is the last step of [`build_memory_packet`].
+pub fn apply_budget(request: &BuildRequest, packet: &mut MemoryPacket) {
+    let counter = token_counter(request.budget.model.as_deref());
+    with_token_counter(counter, || fit_to_budget(request, packet))
+}
+
+fn fit_to_budget(request: &BuildRequest, packet: &mut MemoryPacket) {
     let mut report = BudgetReport {
         max_tokens: request.budget.max_tokens,
         ..BudgetReport::default()
     };
 
     let mut omissions = Vec::new();
-    trim_to_budget(request, packet, &mut omissions);
+    clip_long_texts(
+        packet,
+        request.policy.max_quote_tokens,
+        request.policy.max_highlight_tokens,
+        &mut omissions,
+    );
+    trim_to_budget(request, packet, &mut omissions, &mut report.degradations);
     report.omissions = omissions;
 
+    let pinned: Vec<&Fact> = packet.long_term.facts.iter().filter(|fact| fact.pinned).collect();
+    if !pinned.is_empty() {
+        report.pinned_fact_ids = pinned.iter().map(|fact| fact.fact_id.clone()).collect();
+        report.pinned_tokens_est = estimate_tokens(&pinned);
+    }
+    let pinned: Vec<&engram_types::Procedure> = packet
+        .long_term
+        .procedures
+        .iter()
+        .filter(|procedure| is_pinned_procedure(procedure))
+        .collect();
+    if !pinned.is_empty() {
+        report.pinned_procedure_ids =
+            pinned.iter().map(|procedure| procedure.procedure_id.clone()).collect();
+        report.pinned_procedure_tokens_est = estimate_tokens(&pinned);
+    }
+
     let section_usage = compute_section_usage(packet);
     let used_tokens_est = section_usage.values().filter_map(|v| v.as_u64()).sum::<u64>() as u32;
     report.used_tokens_est = used_tokens_est;
@@ -647,8 +1570,13 @@ fn apply_budget(request: &BuildRequest, packet: &mut MemoryPacket) {
     packet.explain = build_explain(request, packet);
 }
 
-fn trim_to_budget(request: &BuildRequest, packet: &mut MemoryPacket, omissions: &mut Vec<Value>) {
-    apply_per_section_budgets(request, packet, omissions);
+fn trim_to_budget(
+    request: &BuildRequest,
+    packet: &mut MemoryPacket,
+    omissions: &mut Vec<Value>,
+    degradations: &mut Vec<Value>,
+) {
+    apply_per_section_budgets(request, packet, omissions, degradations);
 
     if request.budget.max_tokens == 0 {
         return;
@@ -660,21 +1588,28 @@ fn trim_to_budget(request: &BuildRequest, packet: &mut MemoryPacket, omissions:
     }
 
     while total_tokens > request.budget.max_tokens {
-        let dropped = if drop_last_insight(&mut packet.insight, omissions) {
-            true
-        } else if drop_last_episode(&mut packet.long_term.episodes, omissions) {
-            true
-        } else if drop_oldest_turn(&mut packet.short_term.conversation_window, omissions) {
-            true
-        } else if drop_last_procedure(&mut packet.long_term.procedures, omissions) {
-            true
-        } else if drop_last_fact(&mut packet.long_term.facts, omissions) {
-            true
-        } else if drop_last_key_quote(&mut packet.short_term.key_quotes, omissions) {
-            true
-        } else {
-            false
-        };
+        // Shorten the longest quotes and highlights before dropping whole items.
+        let longest = longest_clippable_text(packet);
+        if longest > MIN_CLIP_TOKENS {
+            let cap = (longest / 2).max(MIN_CLIP_TOKENS);
+            clip_long_texts(packet, cap, cap, omissions);
+            total_tokens = estimate_packet_tokens(packet);
+            continue;
+        }
+
+        let dropped = drop_last_insight(&mut packet.insight, omissions)
+            || drop_last_external(&mut packet.external_context, omissions)
+            || drop_last_episode(
+                &mut packet.short_term.period_summaries,
+                PERIOD_SUMMARIES,
+                omissions,
+            )
+            || drop_last_episode(&mut packet.long_term.episodes, "episodes", omissions)
+            || drop_oldest_turn(&mut packet.short_term.conversation_window, omissions)
+            || drop_last_procedure(&mut packet.long_term.procedures, omissions)
+            || drop_last_fact(&mut packet.long_term.facts, "facts", omissions)
+            || drop_last_fact(&mut packet.long_term.preferences, PREFERENCES, omissions)
+            || drop_last_key_quote(&mut packet.short_term.key_quotes, omissions);
 
         if !dropped {
             warn!("Unable to trim packet further, stopping at {} tokens", total_tokens);
@@ -684,30 +1619,173 @@ fn trim_to_budget(request: &BuildRequest, packet: &mut MemoryPacket, omissions:
     }
 }
 
+const MIN_CLIP_TOKENS: usize = 16;
+const PERIOD_SUMMARIES: &str = "period_summaries";
+const PREFERENCES: &str = "preferences";
+const OPEN_LOOPS: &str = "open_loops";
+const EXTERNAL_CONTEXT: &str = "external_context";
+const PINNED_PROCEDURES: &str = "pinned_procedures";
+const CLIP_MARKER: &str = " …";
+/// Facts below this confidence are the first to go from an over-allowance section.
+const LOW_CONFIDENCE: f64 = 0.5;
+
+fn clip_long_texts(
+    packet: &mut MemoryPacket,
+    quote_tokens: usize,
+    highlight_tokens: usize,
+    omissions: &mut Vec<Value>,
+) {
+    for quote in &mut packet.short_term.key_quotes {
+        if let Some(clipped) = clip_to_tokens(&quote.quote, quote_tokens) {
+            quote.quote = clipped;
+            note_truncation(omissions, "key_quotes", &quote.evidence_id);
+        }
+    }
+    for episode in &mut packet.long_term.episodes {
+        let mut clipped_any = false;
+        for highlight in &mut episode.highlights {
+            if let Some(clipped) = clip_to_tokens(highlight, highlight_tokens) {
+                *highlight = clipped;
+                clipped_any = true;
+            }
+        }
+        if clipped_any {
+            note_truncation(omissions, "episodes.highlights", &episode.episode_id);
+        }
+    }
+}
+
+fn longest_clippable_text(packet: &MemoryPacket) -> usize {
+    let quotes = packet.short_term.key_quotes.iter().map(|quote| quote.quote.as_str());
+    let highlights = packet
+        .long_term
+        .episodes
+        .iter()
+        .flat_map(|episode| episode.highlights.iter().map(String::as_str));
+    quotes
+        .chain(highlights)
+        .map(count_tokens)
+        .max()
+        .unwrap_or(0)
+}
+
+fn note_truncation(omissions: &mut Vec<Value>, section: &str, id: &str) {
+    let recorded = omissions.iter().any(|entry| {
+        entry["section"] == section && entry["id"] == id && entry["reason"] == "truncated"
+    });
+    if !recorded {
+        omissions.push(json!({ "section": section, "id": id, "reason": "truncated" }));
+    }
+}
+
+/// Clips `text` to `max_tokens` by the build's counter, like [`estimate_tokens`],
+/// preferring the last sentence end in the second half of the kept text, then the
+/// last word break. Returns `None` when the text already fits or the cap is 0.
+fn clip_to_tokens(text: &str, max_tokens: usize) -> Option<String> {
+    if max_tokens == 0 || count_tokens(text) <= max_tokens {
+        return None;
+    }
+    // Start from 4 chars per token and shrink while the model's count is over.
+    let mut keep = (max_tokens * 4).saturating_sub(CLIP_MARKER.chars().count());
+    loop {
+        let clipped = clip_chars(text, keep);
+        let tokens = count_tokens(&clipped);
+        if tokens <= max_tokens || keep <= 1 {
+            return Some(clipped);
+        }
+        keep = (keep * max_tokens / tokens).min(keep - 1);
+    }
+}
+
+fn clip_chars(text: &str, keep: usize) -> String {
+    let prefix: String = text.chars().take(keep).collect();
+    let cut = sentence_end(&prefix)
+        .or_else(|| prefix.rfind(char::is_whitespace))
+        .filter(|&cut| cut > 0)
+        .unwrap_or(prefix.len());
+
+    let mut clipped = prefix[..cut].trim_end().to_string();
+    clipped.push_str(CLIP_MARKER);
+    clipped
+}
+
+fn sentence_end(prefix: &str) -> Option<usize> {
+    let min = prefix.len() / 2;
+    prefix
+        .char_indices()
+        .rev()
+        .filter_map(|(index, ch)| {
+            let end = index + ch.len_utf8();
+            let closes = match ch {
+                '.' | '!' | '?' => prefix[end..].is_empty() || prefix[end..].starts_with(char::is_whitespace),
+                '。' | '！' | '？' => true,
+                _ => false,
+            };
+            (closes && end >= min).then_some(end)
+        })
+        .next()
+}
+
+/// Enforces `budget.per_section`. A section over its allowance first goes down the
+/// degradation ladder, each step recorded in `degradations` with its `action`:
+/// facts below [`LOW_CONFIDENCE`] are dropped (`drop_low_confidence_facts`), episodes
+/// are compressed to milestone level, keeping the first summary sentence and no
+/// highlights (`compress_episodes`), and insights are dropped (`drop_insights`).
+/// Items still over the allowance are then dropped from the end of the section.
 fn apply_per_section_budgets(
     request: &BuildRequest,
     packet: &mut MemoryPacket,
     omissions: &mut Vec<Value>,
+    degradations: &mut Vec<Value>,
 ) {
     if let Some(limit) = per_section_limit(&request.budget, "facts") {
+        let pinned = packet.long_term.facts.iter().take_while(|fact| fact.pinned).count();
+        let mut unpinned = packet.long_term.facts.split_off(pinned);
+        let pinned_tokens = match pinned {
+            0 => 0,
+            _ => estimate_tokens(&packet.long_term.facts),
+        };
+        let allowance = limit.saturating_sub(pinned_tokens);
+        drop_low_confidence_facts(&mut unpinned, allowance, omissions, degradations);
+        trim_vec_to_budget(&mut unpinned, allowance, omissions, "facts", |item| {
+            item.fact_id.clone()
+        });
+        packet.long_term.facts.extend(unpinned);
+    }
+    if let Some(limit) = per_section_limit(&request.budget, PREFERENCES) {
         trim_vec_to_budget(
-            &mut packet.long_term.facts,
+            &mut packet.long_term.preferences,
             limit,
             omissions,
-            "facts",
+            PREFERENCES,
             |item| item.fact_id.clone(),
         );
     }
-    if let Some(limit) = per_section_limit(&request.budget, "procedures") {
+    // Pinned procedures lead the section and have a budget of their own.
+    let pinned = packet
+        .long_term
+        .procedures
+        .iter()
+        .take_while(|procedure| is_pinned_procedure(procedure))
+        .count();
+    let mut scoped = packet.long_term.procedures.split_off(pinned);
+    if let Some(limit) = per_section_limit(&request.budget, PINNED_PROCEDURES) {
         trim_vec_to_budget(
             &mut packet.long_term.procedures,
             limit,
             omissions,
-            "procedures",
+            PINNED_PROCEDURES,
             |item| item.procedure_id.clone(),
         );
     }
+    if let Some(limit) = per_section_limit(&request.budget, "procedures") {
+        trim_vec_to_budget(&mut scoped, limit, omissions, "procedures", |item| {
+            item.procedure_id.clone()
+        });
+    }
+    packet.long_term.procedures.extend(scoped);
     if let Some(limit) = per_section_limit(&request.budget, "episodes") {
+        compress_episodes(&mut packet.long_term.episodes, limit, omissions, degradations);
         trim_vec_to_budget(
             &mut packet.long_term.episodes,
             limit,
@@ -716,8 +1794,31 @@ fn apply_per_section_budgets(
             |item| item.episode_id.clone(),
         );
     }
+    if let Some(limit) = per_section_limit(&request.budget, PERIOD_SUMMARIES) {
+        trim_vec_to_budget(
+            &mut packet.short_term.period_summaries,
+            limit,
+            omissions,
+            PERIOD_SUMMARIES,
+            |item| item.episode_id.clone(),
+        );
+    }
     if let Some(limit) = per_section_limit(&request.budget, "insight") {
+        let before = estimate_tokens(&packet.insight);
         trim_insight_to_budget(&mut packet.insight, limit, omissions);
+        let after = estimate_tokens(&packet.insight);
+        if after < before {
+            degradations.push(degradation("insight", "drop_insights", before, after));
+        }
+    }
+    if let Some(limit) = per_section_limit(&request.budget, EXTERNAL_CONTEXT) {
+        trim_vec_to_budget(
+            &mut packet.external_context,
+            limit,
+            omissions,
+            EXTERNAL_CONTEXT,
+            |item| item.id.clone(),
+        );
     }
     if let Some(limit) = per_section_limit(&request.budget, "key_quotes") {
         trim_vec_to_budget(
@@ -733,6 +1834,80 @@ fn apply_per_section_budgets(
     }
 }
 
+fn degradation(section: &str, action: &str, tokens_before: u32, tokens_after: u32) -> Value {
+    json!({
+        "section": section,
+        "reason": "section_budget",
+        "action": action,
+        "tokens_before": tokens_before,
+        "tokens_after": tokens_after,
+    })
+}
+
+/// Drops facts below [`LOW_CONFIDENCE`], least confident first, until the section
+/// fits `max_tokens` or none are left.
+fn drop_low_confidence_facts(
+    facts: &mut Vec<Fact>,
+    max_tokens: u32,
+    omissions: &mut Vec<Value>,
+    degradations: &mut Vec<Value>,
+) {
+    let before = estimate_tokens(facts);
+    let mut total = before;
+    while total > max_tokens {
+        let Some(index) = facts
+            .iter()
+            .enumerate()
+            .filter(|(_, fact)| fact.confidence < LOW_CONFIDENCE)
+            .min_by(|(_, a), (_, b)| a.confidence.total_cmp(&b.confidence))
+            .map(|(index, _)| index)
+        else {
+            break;
+        };
+        let fact = facts.remove(index);
+        omissions.push(json!({
+            "section": "facts",
+            "id": fact.fact_id,
+            "reason": "section_budget"
+        }));
+        total = estimate_tokens(facts);
+    }
+    if total < before {
+        degradations.push(degradation("facts", "drop_low_confidence_facts", before, total));
+    }
+}
+
+/// Compresses episodes to milestone level, the last first, until the section fits
+/// `max_tokens`: the summary keeps its first sentence and highlights are dropped.
+fn compress_episodes(
+    episodes: &mut [Episode],
+    max_tokens: u32,
+    omissions: &mut Vec<Value>,
+    degradations: &mut Vec<Value>,
+) {
+    let before = estimate_tokens(&episodes);
+    let mut total = before;
+    for index in (0..episodes.len()).rev() {
+        if total <= max_tokens {
+            break;
+        }
+        let episode = &mut episodes[index];
+        let first = summary_sentences(&episode.summary).first().map(|s| s.to_string());
+        let summary = first.unwrap_or_default();
+        if summary == episode.summary && episode.highlights.is_empty() {
+            continue;
+        }
+        episode.summary = summary;
+        episode.highlights.clear();
+        episode.compression_level = CompressionLevel::Milestone;
+        note_truncation(omissions, "episodes", &episode.episode_id);
+        total = estimate_tokens(&episodes);
+    }
+    if total < before {
+        degradations.push(degradation("episodes", "compress_episodes", before, total));
+    }
+}
+
 fn per_section_limit(budget: &Budget, key: &str) -> Option<u32> {
     budget
         .per_section
@@ -783,7 +1958,7 @@ fn trim_turns_to_budget(
 fn trim_insight_to_budget(insight: &mut Insight, max_tokens: u32, omissions: &mut Vec<Value>) {
     let mut total = estimate_tokens(insight);
     while total > max_tokens {
-        let mut dropped_tokens = 0;
+        let dropped_tokens;
         if let Some(item) = insight.hypotheses.pop() {
             omissions.push(json!({
                 "section": "insight.hypotheses",
@@ -828,9 +2003,21 @@ fn drop_last_insight(insight: &mut Insight, omissions: &mut Vec<Value>) -> bool
     false
 }
 
-fn drop_last_episode(episodes: &mut Vec<Episode>, omissions: &mut Vec<Value>) -> bool {
+fn drop_last_external(items: &mut Vec<ExternalContext>, omissions: &mut Vec<Value>) -> bool {
+    if let Some(item) = items.pop() {
+        omissions.push(json!({ "section": EXTERNAL_CONTEXT, "id": item.id, "reason": "budget" }));
+        return true;
+    }
+    false
+}
+
+fn drop_last_episode(
+    episodes: &mut Vec<Episode>,
+    section: &str,
+    omissions: &mut Vec<Value>,
+) -> bool {
     if let Some(item) = episodes.pop() {
-        omissions.push(json!({ "section": "episodes", "id": item.episode_id, "reason": "budget" }));
+        omissions.push(json!({ "section": section, "id": item.episode_id, "reason": "budget" }));
         return true;
     }
     false
@@ -853,16 +2040,22 @@ fn drop_last_procedure(
     procedures: &mut Vec<engram_types::Procedure>,
     omissions: &mut Vec<Value>,
 ) -> bool {
-    if let Some(item) = procedures.pop() {
+    // Pinned procedures lead the section, so this never reaches them.
+    if procedures.last().is_some_and(|procedure| !is_pinned_procedure(procedure))
+        && let Some(item) = procedures.pop()
+    {
         omissions.push(json!({ "section": "procedures", "id": item.procedure_id, "reason": "budget" }));
         return true;
     }
     false
 }
 
-fn drop_last_fact(facts: &mut Vec<Fact>, omissions: &mut Vec<Value>) -> bool {
-    if let Some(item) = facts.pop() {
-        omissions.push(json!({ "section": "facts", "id": item.fact_id, "reason": "budget" }));
+fn drop_last_fact(facts: &mut Vec<Fact>, section: &str, omissions: &mut Vec<Value>) -> bool {
+    // Pinned facts sort first, so this never reaches them.
+    if facts.last().is_some_and(|fact| !fact.pinned)
+        && let Some(item) = facts.pop()
+    {
+        omissions.push(json!({ "section": section, "id": item.fact_id, "reason": "budget" }));
         return true;
     }
     false
@@ -880,12 +2073,24 @@ fn estimate_packet_tokens(packet: &MemoryPacket) -> u32 {
     let mut total = 0;
     total += estimate_tokens(&packet.short_term.working_state);
     total += estimate_tokens(&packet.short_term.rolling_summary);
+    if !packet.short_term.period_summaries.is_empty() {
+        total += estimate_tokens(&packet.short_term.period_summaries);
+    }
+    if !packet.short_term.open_loops.is_empty() {
+        total += estimate_tokens(&packet.short_term.open_loops);
+    }
     total += estimate_tokens(&packet.short_term.key_quotes);
     total += estimate_tokens(&packet.short_term.conversation_window);
     total += estimate_tokens(&packet.long_term.facts);
+    if !packet.long_term.preferences.is_empty() {
+        total += estimate_tokens(&packet.long_term.preferences);
+    }
     total += estimate_tokens(&packet.long_term.procedures);
     total += estimate_tokens(&packet.long_term.episodes);
     total += estimate_tokens(&packet.insight);
+    if !packet.external_context.is_empty() {
+        total += estimate_tokens(&packet.external_context);
+    }
     total
 }
 
@@ -899,6 +2104,18 @@ fn compute_section_usage(packet: &MemoryPacket) -> JsonMap {
         "rolling_summary".to_string(),
         json!(estimate_tokens(&packet.short_term.rolling_summary)),
     );
+    if !packet.short_term.period_summaries.is_empty() {
+        usage.insert(
+            PERIOD_SUMMARIES.to_string(),
+            json!(estimate_tokens(&packet.short_term.period_summaries)),
+        );
+    }
+    if !packet.short_term.open_loops.is_empty() {
+        usage.insert(
+            OPEN_LOOPS.to_string(),
+            json!(estimate_tokens(&packet.short_term.open_loops)),
+        );
+    }
     usage.insert(
         "key_quotes".to_string(),
         json!(estimate_tokens(&packet.short_term.key_quotes)),
@@ -911,6 +2128,12 @@ fn compute_section_usage(packet: &MemoryPacket) -> JsonMap {
         "facts".to_string(),
         json!(estimate_tokens(&packet.long_term.facts)),
     );
+    if !packet.long_term.preferences.is_empty() {
+        usage.insert(
+            PREFERENCES.to_string(),
+            json!(estimate_tokens(&packet.long_term.preferences)),
+        );
+    }
     usage.insert(
         "procedures".to_string(),
         json!(estimate_tokens(&packet.long_term.procedures)),
@@ -920,13 +2143,37 @@ fn compute_section_usage(packet: &MemoryPacket) -> JsonMap {
         json!(estimate_tokens(&packet.long_term.episodes)),
     );
     usage.insert("insight".to_string(), json!(estimate_tokens(&packet.insight)));
+    if !packet.external_context.is_empty() {
+        usage.insert(
+            EXTERNAL_CONTEXT.to_string(),
+            json!(estimate_tokens(&packet.external_context)),
+        );
+    }
     usage
 }
 
-fn estimate_tokens<T: Serialize>(value: &T) -> u32 {
+/// Tokens in `value` as JSON, by the counter of the build (see [`token_counter`]).
+pub(crate) fn estimate_tokens<T: Serialize>(value: &T) -> u32 {
     let text = serde_json::to_string(value).unwrap_or_default();
-    let chars = text.chars().count();
-    ((chars as f64 / 4.0).ceil() as u32).max(1)
+    (count_tokens(&text) as u32).max(1)
+}
+
+/// Number of distinct `keywords` that occur in `text`, ignoring case.
+fn keyword_hits(text: &str, keywords: &[String]) -> usize {
+    let text = text.to_lowercase();
+    keywords
+        .iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .filter(|keyword| !keyword.is_empty() && text.contains(keyword.as_str()))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+pub(crate) fn summary_sentences(text: &str) -> Vec<&str> {
+    text.split_inclusive(['.', '!', '?', '。', '！', '？'])
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
 }
 
 fn build_explain(request: &BuildRequest, packet: &MemoryPacket) -> JsonMap {
@@ -936,6 +2183,7 @@ fn build_explain(request: &BuildRequest, packet: &MemoryPacket) -> JsonMap {
         "candidate_counts".to_string(),
         json!({
             "facts": packet.long_term.facts.len(),
+            "preferences": packet.long_term.preferences.len(),
             "procedures": packet.long_term.procedures.len(),
             "episodes": packet.long_term.episodes.len(),
             "insights": insight_total(&packet.insight),
@@ -946,7 +2194,9 @@ fn build_explain(request: &BuildRequest, packet: &MemoryPacket) -> JsonMap {
         json!({
             "max_total": request.policy.max_total_candidates,
             "facts": request.policy.max_facts,
+            "preferences": request.policy.max_preferences,
             "procedures": request.policy.max_procedures,
+            "pinned_procedures": request.policy.max_pinned_procedures,
             "episodes": request.policy.max_episodes,
             "insights": request.policy.max_insights,
         }),
@@ -955,10 +2205,29 @@ fn build_explain(request: &BuildRequest, packet: &MemoryPacket) -> JsonMap {
         "time_window_days".to_string(),
         json!(request.policy.episode_time_window_days),
     );
+    if !request.cues.keywords.is_empty() {
+        explain.insert(
+            "keyword_matches".to_string(),
+            json!({
+                "rolling_summary": summary_sentences(&packet.short_term.rolling_summary)
+                    .into_iter()
+                    .filter(|sentence| keyword_hits(sentence, &request.cues.keywords) > 0)
+                    .collect::<Vec<_>>(),
+                "key_quotes": packet
+                    .short_term
+                    .key_quotes
+                    .iter()
+                    .filter(|quote| keyword_hits(&quote.quote, &request.cues.keywords) > 0)
+                    .map(|quote| quote.evidence_id.as_str())
+                    .collect::<Vec<_>>(),
+            }),
+        );
+    }
     explain.insert(
         "determinism".to_string(),
         json!({
             "facts": "fact_key, fact_id",
+            "preferences": "fact_key, fact_id",
             "procedures": "priority desc, procedure_id",
             "episodes": "recency_score desc, episode_id",
             "insights": "validation_state desc, confidence desc, id",
@@ -971,6 +2240,7 @@ fn default_budget() -> Budget {
     Budget {
         max_tokens: 2048,
         per_section: JsonMap::new(),
+        model: None,
     }
 }
 
@@ -1034,6 +2304,8 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     scope_level: engram_types::ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1051,6 +2323,8 @@ mod tests {
                     sources: vec![],
                     scope_level: engram_types::ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1071,6 +2345,7 @@ mod tests {
                     sources: vec![],
                     compression_level: engram_types::CompressionLevel::Raw,
                     recency_score: None,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1088,17 +2363,444 @@ mod tests {
                     tests_suggested: vec![],
                     expires_at: "run_end".to_string(),
                     sources: vec![],
+                    parent_insight_id: None,
                 },
             )
             .unwrap();
 
+        let mut tagged = Event::new(
+            scope.clone(),
+            EventKind::Message,
+            json!({ "role": "user", "content": "alpha launch is friday" }),
+        );
+        tagged.tags = vec!["alpha".to_string()];
+        store.append_event(tagged.clone()).unwrap();
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("untagged")))
+            .unwrap();
+
         let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
         request.cues.tags = vec!["alpha".to_string()];
         let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.short_term.key_quotes.len(), 2);
+        assert_eq!(packet.short_term.key_quotes[1].evidence_id, tagged.event_id);
 
         assert_eq!(packet.long_term.facts.len(), 1);
         assert_eq!(packet.long_term.episodes.len(), 1);
         assert_eq!(packet.insight.hypotheses.len(), 1);
         assert_eq!(packet.short_term.working_state.goal, "ship v1");
     }
+
+    #[test]
+    fn purpose_filter_excludes_facts_before_the_fact_limit() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        for key in ["pii.email", "pii.phone", "user.city"] {
+            store.upsert_fact(&scope, Fact::new(key, json!("x"))).unwrap();
+        }
+        let mut pinned = Fact::new("pii.passport", json!("x"));
+        pinned.pinned = true;
+        store.upsert_fact(&scope, pinned).unwrap();
+        store
+            .append_insight(&scope, InsightItem::new(InsightType::Hypothesis, "maybe"))
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        request.policy.max_facts = 1;
+        let planner = build_memory_packet(&store, request.clone()).unwrap();
+        let keys: Vec<&str> = planner.long_term.facts.iter().map(|f| f.fact_key.as_str()).collect();
+        assert_eq!(keys, ["pii.passport", "pii.email"]);
+
+        // The excluded facts sort first, yet the tool packet still gets its one fact.
+        request.purpose = Purpose::Tool;
+        let tool = build_memory_packet(&store, request.clone()).unwrap();
+        let keys: Vec<&str> = tool.long_term.facts.iter().map(|f| f.fact_key.as_str()).collect();
+        assert_eq!(keys, ["user.city"]);
+
+        request.purpose = Purpose::Responder;
+        request.policy.filter.responder.insights = true;
+        let responder = build_memory_packet(&store, request).unwrap();
+        assert!(responder.insight.usage_policy.allow_in_responder);
+        assert!(responder.insight.hypotheses.is_empty());
+    }
+
+    #[test]
+    fn clips_long_quotes_at_sentence_boundaries() {
+        let text = "The deploy failed twice. Rolling back fixed it. Then the cache was warmed again.";
+        let clipped = clip_to_tokens(text, 13).unwrap();
+        assert_eq!(clipped, "The deploy failed twice. Rolling back fixed it. …");
+        assert!(clip_to_tokens(text, 0).is_none());
+        assert!(clip_to_tokens("short", 4).is_none());
+
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        store
+            .update_stm(
+                &scope,
+                StmState {
+                    rolling_summary: String::new(),
+                    key_quotes: vec![KeyQuote {
+                        evidence_id: "e1".to_string(),
+                        quote: text.repeat(8),
+                        role: engram_types::Role::User,
+                        ts: None,
+                    }],
+                },
+            )
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        request.budget.max_tokens = 250;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let quote = &packet.short_term.key_quotes[0].quote;
+        assert!(quote.ends_with(". …"));
+        assert!(quote.chars().count() < 480);
+        assert!(packet
+            .budget_report
+            .omissions
+            .iter()
+            .any(|entry| entry["id"] == "e1" && entry["reason"] == "truncated"));
+    }
+
+    #[test]
+    fn keyword_cues_surface_matching_stm_quotes() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let quote = |id: &str, text: &str| KeyQuote {
+            evidence_id: id.to_string(),
+            quote: text.to_string(),
+            role: engram_types::Role::User,
+            ts: None,
+        };
+        store
+            .update_stm(
+                &scope,
+                StmState {
+                    rolling_summary: "User ordered a lamp. It arrived broken; they want a Refund."
+                        .to_string(),
+                    key_quotes: vec![
+                        quote("e1", "the lamp is blue"),
+                        quote("e2", "delivery took a week"),
+                        quote("e3", "I expect a full refund"),
+                    ],
+                },
+            )
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Responder);
+        request.cues.keywords = vec!["refund".to_string()];
+        request.policy.max_key_quotes = 1;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.short_term.key_quotes.len(), 1);
+        assert_eq!(packet.short_term.key_quotes[0].evidence_id, "e3");
+        assert_eq!(
+            packet.explain["keyword_matches"],
+            json!({
+                "rolling_summary": ["It arrived broken; they want a Refund."],
+                "key_quotes": ["e3"],
+            })
+        );
+        assert_eq!(packet.explain["keyword_search"], "scan");
+    }
+
+    #[test]
+    fn recalls_from_every_cue_window() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let now = Utc::now();
+        let day = |days_ago: i64| TimeRangeFilter {
+            start: Some(now - Duration::days(days_ago) - Duration::hours(12)),
+            end: Some(now - Duration::days(days_ago) + Duration::hours(12)),
+        };
+        for days_ago in [1, 8, 14] {
+            let mut episode = Episode::new(format!("{} days ago", days_ago));
+            episode.episode_id = format!("ep{}", days_ago);
+            episode.tags = vec!["lamp".to_string()];
+            episode.time_range.start = now - Duration::days(days_ago);
+            episode.time_range.end = Some(episode.time_range.start + Duration::minutes(5));
+            store.append_episode(&scope, episode).unwrap();
+            let payload = json!({"role": "user", "content": format!("lamp, day {}", days_ago)});
+            let mut event = Event::new(scope.clone(), EventKind::Message, payload);
+            event.event_id = format!("e{}", days_ago);
+            event.ts = now - Duration::days(days_ago);
+            event.tags = vec!["lamp".to_string()];
+            store.append_event(event).unwrap();
+        }
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Responder);
+        request.cues.time_range = Some(day(1));
+        request.cues.time_ranges = vec![day(14), day(1)];
+        request.cues.tags = vec!["lamp".to_string()];
+        let packet = build_memory_packet(&store, request).unwrap();
+        let mut episodes: Vec<&str> = packet
+            .long_term
+            .episodes
+            .iter()
+            .map(|episode| episode.episode_id.as_str())
+            .collect();
+        episodes.sort();
+        assert_eq!(episodes, ["ep1", "ep14"]);
+        let quotes: Vec<&str> = packet
+            .short_term
+            .key_quotes
+            .iter()
+            .map(|quote| quote.evidence_id.as_str())
+            .collect();
+        assert_eq!(quotes, ["e14", "e1"]);
+    }
+
+    #[test]
+    fn conversation_window_fills_token_budget() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        for content in ["hi", &"long message ".repeat(20), "short one", "ok"] {
+            store
+                .append_event(Event::new(
+                    scope.clone(),
+                    EventKind::Message,
+                    json!({ "role": "user", "content": content }),
+                ))
+                .unwrap();
+        }
+        let events = store.list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(events[2].token_count(), Some(estimate_tokens(&"short one")));
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.policy.filter.planner.conversation_window = true;
+        request.policy.conversation_window_tokens = 10;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let window: Vec<&str> = packet
+            .short_term
+            .conversation_window
+            .iter()
+            .map(|turn| turn.content.as_str())
+            .collect();
+        assert_eq!(window, vec!["short one", "ok"]);
+    }
+
+    #[test]
+    fn builds_packets_in_bulk_in_request_order() {
+        let store = InMemoryStore::new();
+        let requests: Vec<BuildRequest> = ["u1", "u2", "u1", "u3"]
+            .iter()
+            .map(|user| {
+                let scope = Scope {
+                    user_id: user.to_string(),
+                    ..sample_scope()
+                };
+                store
+                    .upsert_fact(&scope, Fact::new("name", json!(user)))
+                    .unwrap();
+                BuildRequest::new(scope, Purpose::Planner)
+            })
+            .collect();
+
+        let packets = build_memory_packets_bulk(&store, requests, 2);
+        let users: Vec<String> = packets
+            .into_iter()
+            .map(|packet| packet.unwrap().meta.scope.user_id)
+            .collect();
+        assert_eq!(users, vec!["u1", "u2", "u1", "u3"]);
+        assert!(build_memory_packets_bulk(&store, Vec::new(), 0).is_empty());
+    }
+
+    #[test]
+    fn pinned_facts_survive_limits_and_budget() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        for idx in 0..5 {
+            store
+                .upsert_fact(&scope, Fact::new(format!("a.{}", idx), json!("x".repeat(200))))
+                .unwrap();
+        }
+        let allergy = Fact::new("z.allergy", json!("peanuts"));
+        store.upsert_fact(&scope, allergy.clone()).unwrap();
+        crate::pin_fact(&store, &scope, &allergy.fact_id).unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        request.policy.max_facts = 2;
+        request.budget.max_tokens = 120;
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        assert_eq!(packet.long_term.facts[0].fact_id, allergy.fact_id);
+        assert!(packet.long_term.facts.len() < 3);
+        assert_eq!(packet.budget_report.pinned_fact_ids, vec![allergy.fact_id.clone()]);
+        assert!(packet.budget_report.pinned_tokens_est > 0);
+
+        crate::unpin_fact(&store, &scope, &allergy.fact_id).unwrap();
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert!(packet.long_term.facts.iter().all(|fact| fact.fact_id != allergy.fact_id));
+        assert!(packet.budget_report.pinned_fact_ids.is_empty());
+    }
+
+    #[test]
+    fn pinned_pool_facts_do_not_hide_the_users_own_fact() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let pool = crate::shared_fact_scope(&scope, &engram_types::ScopeLevel::Tenant);
+        let org_tone = Fact::new("style.tone", json!("formal"));
+        store.upsert_fact(&pool, org_tone.clone()).unwrap();
+        crate::pin_fact(&store, &pool, &org_tone.fact_id).unwrap();
+        let own_tone = Fact::new("style.tone", json!("casual"));
+        store.upsert_fact(&scope, own_tone.clone()).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let tones: Vec<&Value> = packet
+            .long_term
+            .facts
+            .iter()
+            .filter(|fact| fact.fact_key == "style.tone")
+            .map(|fact| &fact.value)
+            .collect();
+        assert_eq!(tones, vec![&json!("casual")]);
+        assert!(packet.budget_report.pinned_fact_ids.is_empty());
+    }
+
+    #[test]
+    fn suppressed_memories_stay_out_of_packets() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let ex = Fact::new("user.ex_partner", json!("Sam"));
+        let city = Fact::new("user.home_city", json!("Lisbon"));
+        store.upsert_fact(&scope, ex.clone()).unwrap();
+        store.upsert_fact(&scope, city.clone()).unwrap();
+        let episode = Episode::new("Talked about the breakup");
+        store.append_episode(&scope, episode.clone()).unwrap();
+
+        let fact_ref = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: ex.fact_id.clone(),
+        };
+        store.suppress_memory(&scope, fact_ref.clone(), "stop bringing this up").unwrap();
+        store
+            .suppress_memory(
+                &scope,
+                MemoryRef {
+                    kind: MemoryKind::Episode,
+                    id: episode.episode_id.clone(),
+                },
+                "painful",
+            )
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        request.policy.max_facts = 1;
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        let fact_ids: Vec<&str> =
+            packet.long_term.facts.iter().map(|fact| fact.fact_id.as_str()).collect();
+        assert_eq!(fact_ids, [city.fact_id.as_str()]);
+        assert!(packet.long_term.episodes.is_empty());
+        // Suppressed items are kept for audit.
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 2);
+        assert_eq!(store.list_suppressions(&scope).unwrap().len(), 2);
+
+        assert!(store.unsuppress_memory(&scope, &fact_ref).unwrap());
+        assert!(!store.unsuppress_memory(&scope, &fact_ref).unwrap());
+        request.policy.max_facts = 5;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.long_term.facts.len(), 2);
+        assert!(packet.long_term.episodes.is_empty());
+    }
+
+    #[test]
+    fn deadline_skips_lower_priority_sections() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        store.append_episode(&scope, Episode::new("Moved to Lisbon")).unwrap();
+        store
+            .append_insight(&scope, InsightItem::new(InsightType::Hypothesis, "likes the sea"))
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        assert_eq!(packet.long_term.episodes.len(), 1);
+        assert!(packet.budget_report.degradations.is_empty());
+
+        request.deadline = Some(StdDuration::ZERO);
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.long_term.facts.len(), 1);
+        assert!(packet.long_term.episodes.is_empty());
+        assert!(packet.insight.hypotheses.is_empty());
+        let skipped: Vec<&str> = packet
+            .budget_report
+            .degradations
+            .iter()
+            .filter_map(|entry| entry["section"].as_str())
+            .collect();
+        assert_eq!(skipped, ["procedures", "episodes", "insights"]);
+    }
+
+    #[test]
+    fn deadline_bounds_the_store_calls_of_skippable_sections() {
+        use crate::timeout::effective_timeout;
+
+        let store_timeout = Some(StdDuration::from_secs(600));
+        let mut deadline = DeadlineGuard::new(Some(StdDuration::from_secs(60)));
+        let bound = deadline
+            .load("episodes", || Ok(effective_timeout(store_timeout)))
+            .unwrap()
+            .unwrap();
+        assert!(bound.is_some_and(|bound| bound <= StdDuration::from_secs(60)));
+        assert_eq!(effective_timeout(store_timeout), store_timeout);
+
+        let timed_out = deadline
+            .load("insights", || -> StoreResult<()> {
+                Err(StoreError::Timeout("canceling statement".to_string()))
+            })
+            .unwrap();
+        assert!(timed_out.is_none());
+        assert_eq!(deadline.skipped[0]["section"], "insights");
+        assert_eq!(deadline.skipped[0]["reason"], "deadline");
+    }
+
+    #[test]
+    fn sections_over_allowance_degrade_before_dropping_items() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let mut guess = Fact::new("user.employer", json!("Acme"));
+        guess.confidence = 0.2;
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        store.upsert_fact(&scope, guess).unwrap();
+        let mut episode = Episode::new("Moved to Lisbon. Found a flat near the river.");
+        episode.highlights = vec!["Signed the lease on a two-bedroom flat".to_string()];
+        store.append_episode(&scope, episode).unwrap();
+        store
+            .append_insight(&scope, InsightItem::new(InsightType::Hypothesis, "likes the sea"))
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.persist = false;
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        assert!(packet.budget_report.degradations.is_empty());
+        let usage = &packet.budget_report.section_usage;
+        let per_section = &mut request.budget.per_section;
+        per_section.insert("facts".to_string(), json!(usage["facts"].as_u64().unwrap() - 1));
+        per_section.insert("episodes".to_string(), json!(usage["episodes"].as_u64().unwrap() - 1));
+        per_section.insert("insight".to_string(), json!(1));
+
+        let packet = build_memory_packet(&store, request).unwrap();
+        let facts: Vec<&str> =
+            packet.long_term.facts.iter().map(|fact| fact.fact_key.as_str()).collect();
+        assert_eq!(facts, ["user.city"]);
+        let episode = &packet.long_term.episodes[0];
+        assert_eq!(episode.summary, "Moved to Lisbon.");
+        assert!(episode.highlights.is_empty());
+        assert!(matches!(episode.compression_level, CompressionLevel::Milestone));
+        assert!(packet.insight.hypotheses.is_empty());
+        let actions: Vec<&str> = packet
+            .budget_report
+            .degradations
+            .iter()
+            .inspect(|entry| assert_eq!(entry["reason"], "section_budget"))
+            .filter_map(|entry| entry["action"].as_str())
+            .collect();
+        assert_eq!(actions, ["drop_low_confidence_facts", "compress_episodes", "drop_insights"]);
+    }
 }
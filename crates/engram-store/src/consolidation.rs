@@ -0,0 +1,305 @@
+use chrono::Duration;
+use engram_types::{CompressionLevel, Episode, Scope, TimeRange};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::composer::parse_event_payload;
+use crate::lifecycle::{clip, is_marker};
+use crate::{
+    stream_events, EpisodeFilter, Event, EventKind, ExtractiveSummarizer, MemoryKind, MemoryRef,
+    Store, StoreResult, Summarizer, TimeRangeFilter, RUN_TAG_PREFIX,
+};
+
+/// Tag of the episodes [`Consolidator::consolidate_run`] writes, next to `run:<run_id>`.
+pub const CONSOLIDATED_TAG: &str = "consolidated";
+
+const MAX_HIGHLIGHTS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct ConsolidationOptions {
+    /// A pause between two events longer than this starts a new episode.
+    pub max_gap: Duration,
+    /// Whether a tagged event whose tags differ from the previous tagged event's
+    /// starts a new episode. Untagged events always join the current one.
+    pub split_on_tag_change: bool,
+    /// Segments of at most this many events are kept verbatim as
+    /// [`CompressionLevel::Raw`]; longer ones are summarized as
+    /// [`CompressionLevel::PhaseSummary`].
+    pub max_raw_events: usize,
+    /// Token budget the summarizer gets for a summarized episode.
+    pub summary_tokens: usize,
+    /// Extra tags for every episode.
+    pub tags: Vec<String>,
+}
+
+impl Default for ConsolidationOptions {
+    fn default() -> Self {
+        Self {
+            max_gap: Duration::minutes(30),
+            split_on_tag_change: true,
+            max_raw_events: 4,
+            summary_tokens: 64,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// What [`Consolidator::consolidate_run`] wrote.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsolidationReport {
+    /// The run's episodes, oldest first.
+    pub episodes: Vec<Episode>,
+    /// Events read, run markers excluded.
+    pub events: usize,
+    /// Episodes of an earlier consolidation of the run, evicted in favour of these.
+    #[serde(default)]
+    pub replaced_episodes: Vec<String>,
+}
+
+/// Turns a run's raw events into [`Episode`]s, one per phase of the run. Unlike
+/// [`end_run`](crate::end_run), which writes a single episode when the run closes, it
+/// can run at any time and again: each pass replaces the previous one's episodes.
+/// `store` is a reference or an `Arc` to the store.
+pub struct Consolidator<S> {
+    store: S,
+    summarizer: Arc<dyn Summarizer>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for Consolidator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Consolidator").field("store", &self.store).finish_non_exhaustive()
+    }
+}
+
+impl<S> Consolidator<S>
+where
+    S: Deref,
+    S::Target: Store,
+{
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            summarizer: Arc::new(ExtractiveSummarizer),
+        }
+    }
+
+    /// Summarizes long segments with `summarizer`, e.g. an LLM, instead of
+    /// [`ExtractiveSummarizer`]. If it fails, the segment is summarized extractively.
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = summarizer;
+        self
+    }
+
+    /// Reads the run's events in `(ts, event_id)` order through [`stream_events`] and
+    /// starts a new episode at every pause longer than `options.max_gap` and, if
+    /// `options.split_on_tag_change`, at every change of tags. Each episode covers its
+    /// events' time range, cites them as sources, and collects their tags and entities;
+    /// its summary is the segment's messages, or for long segments what the summarizer
+    /// makes of them in `options.summary_tokens`. Run markers (`run_start`, `run_end`,
+    /// checkpoints) are skipped.
+    pub fn consolidate_run(
+        &self,
+        scope: &Scope,
+        options: &ConsolidationOptions,
+    ) -> StoreResult<ConsolidationReport> {
+        let mut report = ConsolidationReport::default();
+        let mut segments: Vec<Vec<Event>> = Vec::new();
+        let mut current: Vec<Event> = Vec::new();
+        let mut topic: Vec<String> = Vec::new();
+        let mut events = stream_events(&*self.store, scope, TimeRangeFilter::default());
+        loop {
+            let chunk = events.next_chunk()?;
+            if chunk.is_empty() {
+                break;
+            }
+            for event in chunk.into_iter().filter(|event| !is_marker(event)) {
+                report.events += 1;
+                let gap = current.last().is_some_and(|last| event.ts - last.ts > options.max_gap);
+                let retagged = options.split_on_tag_change
+                    && !event.tags.is_empty()
+                    && !topic.is_empty()
+                    && sorted(&event.tags) != topic;
+                if gap || retagged {
+                    segments.push(std::mem::take(&mut current));
+                }
+                if !event.tags.is_empty() {
+                    topic = sorted(&event.tags);
+                }
+                current.push(event);
+            }
+        }
+        if !current.is_empty() {
+            segments.push(current);
+        }
+
+        let run_tag = format!("{}{}", RUN_TAG_PREFIX, scope.run_id);
+        let filter = EpisodeFilter {
+            tags: vec![run_tag.clone()],
+            ..EpisodeFilter::default()
+        };
+        let previous: Vec<MemoryRef> = self
+            .store
+            .list_episodes(scope, filter)?
+            .into_iter()
+            .filter(|episode| episode.tags.iter().any(|tag| tag == CONSOLIDATED_TAG))
+            .map(|episode| MemoryRef {
+                kind: MemoryKind::Episode,
+                id: episode.episode_id,
+            })
+            .collect();
+        if !previous.is_empty() {
+            self.store.evict_memory(scope, &previous)?;
+        }
+        report.replaced_episodes = previous.into_iter().map(|item| item.id).collect();
+
+        for segment in &segments {
+            let episode =
+                segment_episode(scope, segment, &run_tag, options, &*self.summarizer);
+            self.store.append_episode(scope, episode.clone())?;
+            report.episodes.push(episode);
+        }
+        debug!(
+            "consolidated {} events of run {} into {} episodes",
+            report.events,
+            scope.run_id,
+            report.episodes.len()
+        );
+        Ok(report)
+    }
+}
+
+fn sorted(tags: &[String]) -> Vec<String> {
+    let mut tags = tags.to_vec();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn segment_episode(
+    scope: &Scope,
+    events: &[Event],
+    run_tag: &str,
+    options: &ConsolidationOptions,
+    summarizer: &dyn Summarizer,
+) -> Episode {
+    let messages: Vec<String> = events
+        .iter()
+        .filter(|event| event.kind == EventKind::Message)
+        .filter_map(|event| parse_event_payload(&event.payload))
+        .map(|(content, _)| clip(&content))
+        .collect();
+    let raw = events.len() <= options.max_raw_events;
+    let summary = if messages.is_empty() {
+        format!("{} events of run {}", events.len(), scope.run_id)
+    } else if raw {
+        messages.join("\n")
+    } else {
+        let summary = summarizer
+            .summarize(&messages, options.summary_tokens)
+            .unwrap_or_else(|err| {
+                warn!("summarizer failed, summarizing extractively: {}", err);
+                ExtractiveSummarizer
+                    .summarize(&messages, options.summary_tokens)
+                    .unwrap_or_default()
+            });
+        format!("{} ({} events)", summary, events.len())
+    };
+
+    let mut episode = Episode::new(summary);
+    episode.time_range = TimeRange {
+        start: events[0].ts,
+        end: Some(events[events.len() - 1].ts),
+    };
+    episode.compression_level = if raw {
+        CompressionLevel::Raw
+    } else {
+        CompressionLevel::PhaseSummary
+    };
+    if !raw {
+        episode.highlights = messages[messages.len().saturating_sub(MAX_HIGHLIGHTS)..].to_vec();
+    }
+    episode.tags = vec![CONSOLIDATED_TAG.to_string(), run_tag.to_string()];
+    for tag in options.tags.iter().chain(events.iter().flat_map(|event| event.tags.iter())) {
+        if !episode.tags.contains(tag) {
+            episode.tags.push(tag.clone());
+        }
+    }
+    for entity in events.iter().flat_map(|event| event.entities.iter()) {
+        if !episode.entities.contains(entity) {
+            episode.entities.push(entity.clone());
+        }
+    }
+    episode.sources = events.iter().map(|event| event.event_id.clone()).collect();
+    let first_lang = events[0].lang.clone();
+    if events.iter().all(|event| event.lang == first_lang) {
+        episode.lang = first_lang;
+    }
+    episode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{begin_run, InMemoryStore};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn segments_a_run_by_gaps_and_tags() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        begin_run(&store, &scope).unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap();
+        let append = |minute: i64, content: &str, tags: &[&str]| {
+            let payload = json!({"role": "user", "content": content});
+            let mut event = Event::new(scope.clone(), EventKind::Message, payload);
+            event.ts = start + Duration::minutes(minute);
+            event.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            store.append_event(event).unwrap();
+        };
+        // Billing questions, then a switch to shipping, then a return after lunch.
+        append(0, "my invoice is wrong", &["billing"]);
+        append(1, "it charged me twice", &[]);
+        append(2, "refund issued", &["billing"]);
+        append(3, "where is my parcel", &["shipping"]);
+        for minute in 4..10 {
+            append(minute, &format!("tracking update {minute}"), &["shipping"]);
+        }
+        append(90, "thanks, all sorted", &[]);
+
+        let consolidator = Consolidator::new(&store);
+        let options = ConsolidationOptions {
+            summary_tokens: 10,
+            ..ConsolidationOptions::default()
+        };
+        let report = consolidator.consolidate_run(&scope, &options).unwrap();
+        assert_eq!(report.events, 11);
+        let episodes = &report.episodes;
+        assert_eq!(episodes.len(), 3);
+        assert_eq!(episodes[0].summary, "my invoice is wrong\nit charged me twice\nrefund issued");
+        assert!(matches!(episodes[0].compression_level, CompressionLevel::Raw));
+        assert_eq!(episodes[0].tags, vec![CONSOLIDATED_TAG, "run:run1", "billing"]);
+        assert_eq!(episodes[1].sources.len(), 7);
+        assert!(matches!(episodes[1].compression_level, CompressionLevel::PhaseSummary));
+        assert_eq!(
+            episodes[1].summary,
+            "where is my parcel … tracking update 9 (7 events)"
+        );
+        assert_eq!(episodes[2].time_range.start, start + Duration::minutes(90));
+
+        // Consolidating again replaces the earlier episodes.
+        let again = consolidator.consolidate_run(&scope, &options).unwrap();
+        assert_eq!(again.replaced_episodes.len(), 3);
+        let stored = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        assert_eq!(stored.len(), 3);
+    }
+}
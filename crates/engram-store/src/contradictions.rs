@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use engram_types::{Fact, FactStatus, MemoryPacket};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::{FactFilter, Store, StoreResult};
+
+/// Explain key under which [`flag_contradictions`] lists its [`Contradiction`]s.
+pub const CONTRADICTIONS_KEY: &str = "contradictions";
+
+/// A recalled fact that a newer disputed fact with the same key contradicts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contradiction {
+    pub fact_key: String,
+    /// The fact in the packet, from `long_term.facts` or `long_term.preferences`.
+    pub fact_id: String,
+    pub value: Value,
+    /// The newest disputed fact with another value.
+    pub disputed_fact_id: String,
+    pub disputed_value: Value,
+    #[serde(default)]
+    pub disputed_sources: Vec<String>,
+}
+
+/// Looks up disputed facts sharing a key with the packet's facts and preferences,
+/// and lists those newer than the recalled fact under `explain.contradictions`, so
+/// the responder can hedge instead of stating the fact as settled.
+///
+/// A disputed fact is newer when its `validity.valid_from` is later, or equal and
+/// its id sorts after the recalled fact's (generated ids are ULIDs, ordered by
+/// creation). [`build_memory_packet`](crate::build_memory_packet) calls this after
+/// trimming the packet to budget. Returns the contradictions found.
+pub fn flag_contradictions<S: Store + ?Sized>(
+    store: &S,
+    packet: &mut MemoryPacket,
+) -> StoreResult<Vec<Contradiction>> {
+    let long_term = &packet.long_term;
+    if long_term.facts.is_empty() && long_term.preferences.is_empty() {
+        return Ok(Vec::new());
+    }
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Disputed]),
+        include_shared: true,
+        ..FactFilter::default()
+    };
+    let disputed = store.list_facts(&packet.meta.scope, filter)?;
+
+    let mut contradictions = Vec::new();
+    for fact in long_term.facts.iter().chain(&long_term.preferences) {
+        let newest = disputed
+            .iter()
+            .filter(|other| {
+                other.fact_key == fact.fact_key
+                    && other.value != fact.value
+                    && recency(other) > recency(fact)
+            })
+            .max_by(|a, b| recency(a).cmp(&recency(b)));
+        if let Some(newest) = newest {
+            contradictions.push(Contradiction {
+                fact_key: fact.fact_key.clone(),
+                fact_id: fact.fact_id.clone(),
+                value: fact.value.clone(),
+                disputed_fact_id: newest.fact_id.clone(),
+                disputed_value: newest.value.clone(),
+                disputed_sources: newest.sources.clone(),
+            });
+        }
+    }
+
+    if !contradictions.is_empty() {
+        debug!("{} recalled facts contradicted by newer disputed facts", contradictions.len());
+        packet.explain.insert(CONTRADICTIONS_KEY.to_string(), json!(contradictions));
+    }
+    Ok(contradictions)
+}
+
+fn recency(fact: &Fact) -> (Option<DateTime<Utc>>, &str) {
+    (fact.validity.valid_from, fact.fact_id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore};
+    use engram_types::{Purpose, Scope};
+
+    fn fact(fact_id: &str, fact_key: &str, value: Value, status: FactStatus) -> Fact {
+        Fact {
+            fact_id: fact_id.to_string(),
+            status,
+            ..Fact::new(fact_key, value)
+        }
+    }
+
+    #[test]
+    fn newer_disputed_facts_are_flagged_in_explain() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let facts = [
+            fact("f1", "user.city", json!("Lisbon"), FactStatus::Active),
+            fact("f2", "user.city", json!("Porto"), FactStatus::Disputed),
+            fact("f3", "user.diet", json!("vegan"), FactStatus::Disputed),
+            fact("f4", "user.diet", json!("vegetarian"), FactStatus::Active),
+            fact("f5", "user.pet", json!("cat"), FactStatus::Active),
+            fact("f6", "user.pet", json!("cat"), FactStatus::Disputed),
+        ];
+        for fact in facts {
+            store.upsert_fact(&scope, fact).unwrap();
+        }
+
+        let packet = build_memory_packet(&store, BuildRequest::new(scope, Purpose::Responder))
+            .unwrap();
+        assert_eq!(packet.long_term.facts.len(), 3);
+        let flagged: Vec<Contradiction> =
+            serde_json::from_value(packet.explain[CONTRADICTIONS_KEY].clone()).unwrap();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].fact_id, "f1");
+        assert_eq!(flagged[0].disputed_fact_id, "f2");
+        assert_eq!(flagged[0].disputed_value, json!("Porto"));
+    }
+}
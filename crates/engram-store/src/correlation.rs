@@ -0,0 +1,439 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use std::cell::RefCell;
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+thread_local! {
+    static CALL_CORRELATION_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` on behalf of the request `correlation_id`, e.g. the id an agent or HTTP
+/// client sent: `f` runs inside a `request` tracing span carrying the id, and every
+/// change log entry it writes records it in [`Change::correlation_id`], so a memory
+/// change can be traced back to the request that caused it. Calls nest; the innermost
+/// id wins.
+pub fn with_correlation_id<T>(correlation_id: &str, f: impl FnOnce() -> T) -> T {
+    let span = tracing::info_span!("request", correlation_id);
+    let _entered = span.enter();
+    let previous = CALL_CORRELATION_ID.with(|cell| cell.replace(Some(correlation_id.to_string())));
+    let result = f();
+    CALL_CORRELATION_ID.with(|cell| cell.replace(previous));
+    result
+}
+
+/// The correlation id of the call running on this thread, for backends to record.
+pub fn correlation_id() -> Option<String> {
+    CALL_CORRELATION_ID.with(|cell| cell.borrow().clone())
+}
+
+/// Store wrapper that runs every call under one correlation id (see
+/// [`with_correlation_id`]), for handing a store to code that serves a single request,
+/// such as a binding call.
+#[derive(Debug)]
+pub struct CorrelatedStore<S> {
+    inner: S,
+    correlation_id: String,
+}
+
+impl<S: Store> CorrelatedStore<S> {
+    pub fn new(inner: S, correlation_id: impl Into<String>) -> Self {
+        Self {
+            inner,
+            correlation_id: correlation_id.into(),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    fn call<T>(&self, f: impl FnOnce() -> T) -> T {
+        with_correlation_id(&self.correlation_id, f)
+    }
+}
+
+impl<S: Store> Store for CorrelatedStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.call(|| self.inner.append_event(event))
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.call(|| self.inner.append_events_bulk(events))
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.call(|| self.inner.list_events(scope, range, limit))
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.call(|| self.inner.list_events_page(scope, cursor, limit))
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.call(|| self.inner.get_events_since(scope, seq, limit))
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.call(|| self.inner.get_events_by_ids(scope, event_ids))
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.call(|| self.inner.find_events(scope, filter))
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.call(|| self.inner.search_events(scope, query))
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.call(|| self.inner.search_episodes(scope, query))
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.call(|| self.inner.acquire_lease(scope, ttl))
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.call(|| self.inner.renew_lease(lease, ttl))
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.call(|| self.inner.release_lease(lease))
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.call(|| self.inner.get_working_state(scope))
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.call(|| self.inner.patch_working_state(scope, patch))
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.call(|| self.inner.patch_working_states(patches))
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.call(|| self.inner.list_working_states(scope))
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.call(|| self.inner.get_stm(scope))
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.call(|| self.inner.update_stm(scope, stm))
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.call(|| self.inner.list_facts(scope, filter))
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.call(|| self.inner.upsert_fact(scope, fact))
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.call(|| self.inner.upsert_facts_bulk(scope, facts))
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.call(|| self.inner.list_fact_history(scope, fact_key))
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.call(|| self.inner.list_episodes(scope, filter))
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.call(|| self.inner.append_episode(scope, episode))
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.call(|| self.inner.append_episodes_bulk(scope, episodes))
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.call(|| self.inner.list_procedures(scope, task_type, limit))
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.call(|| self.inner.upsert_procedure(scope, procedure))
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.call(|| self.inner.list_procedure_candidates(scope, filter))
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.call(|| self.inner.get_procedure_candidate(scope, candidate_id))
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.call(|| self.inner.upsert_procedure_candidate(scope, candidate))
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.call(|| self.inner.list_insights(scope, filter))
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.call(|| self.inner.append_insight(scope, insight))
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.call(|| self.inner.expire_insights(scope, expires_at))
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.call(|| self.inner.write_context_build(scope, packet))
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.call(|| self.inner.list_context_builds(scope, limit))
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.call(|| self.inner.list_context_build_summaries(scope, limit))
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.call(|| self.inner.tenant_activity(tenant_id, range))
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        self.call(|| self.inner.list_scopes(tenant_id, limit))
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.call(|| self.inner.suppress_memory(scope, item, reason))
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.call(|| self.inner.unsuppress_memory(scope, item))
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.call(|| self.inner.list_suppressions(scope))
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.call(|| self.inner.evict_memory(scope, items))
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.call(|| self.inner.delete_record(scope, item, mode))
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.call(|| self.inner.clear_scope(scope, mode))
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.call(|| self.inner.count_records(scope, kind))
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.call(|| self.inner.list_tombstones(scope, limit))
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.call(|| self.inner.upsert_embeddings(scope, embeddings))
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.call(|| self.inner.list_embeddings(scope, model))
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.call(|| self.inner.delete_embeddings(scope, model, items))
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.call(|| self.inner.record_run_outcome(scope, outcome))
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.call(|| self.inner.list_run_outcomes(scope, limit))
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.call(|| self.inner.set_source_credibility(tenant_id, credibility))
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.call(|| self.inner.get_source_credibility(tenant_id))
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.call(|| self.inner.set_user_locale(tenant_id, user_id, locale))
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.call(|| self.inner.get_user_locale(tenant_id, user_id))
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.call(|| self.inner.append_change(op))
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.call(|| self.inner.load_change_cursor(name))
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.call(|| self.inner.save_change_cursor(name, seq))
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.call(|| self.inner.write_logged(write))
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.call(|| self.inner.list_changes(after_seq, limit))
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.call(|| self.inner.last_change_seq())
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.call(|| self.inner.metrics_snapshot())
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChangeLogStore, EventKind, InMemoryStore};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn change_log_records_the_request_correlation_id() {
+        let store = Arc::new(ChangeLogStore::new(InMemoryStore::new()));
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let message = json!({"role": "user", "content": "hi"});
+        store.append_event(Event::new(scope.clone(), EventKind::Message, message)).unwrap();
+        with_correlation_id("req-1", || {
+            assert_eq!(correlation_id().as_deref(), Some("req-1"));
+            store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        });
+        assert_eq!(correlation_id(), None);
+        let correlated = CorrelatedStore::new(store.clone(), "req-2");
+        correlated.update_stm(&scope, StmState::default()).unwrap();
+
+        let changes = store.list_changes(0, None).unwrap();
+        let ids: Vec<Option<&str>> =
+            changes.iter().map(|change| change.correlation_id.as_deref()).collect();
+        assert_eq!(ids, vec![None, Some("req-1"), Some("req-2")]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+
+use engram_types::{Fact, Scope};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Event, EventKind, Store, StoreError, StoreResult};
+
+/// How far a tenant trusts each kind of evidence, set with
+/// [`Store::set_source_credibility`](crate::Store::set_source_credibility).
+///
+/// Sources are classified from the events a fact cites: `tool:<name>` and then `tool`
+/// for tool results (the name comes from the payload's `tool` field), the message role
+/// (`user`, `assistant`, ...) for messages, and the event kind otherwise. A fact's
+/// effective confidence is its own confidence times the credibility of its most
+/// credible source; recall ranks facts by it and, when several active facts share a
+/// key, keeps only the most credible one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceCredibility {
+    /// Credibility in `[0, 1]` by source class, e.g. `{"tool:weather": 0.95, "user": 0.6}`.
+    #[serde(default)]
+    pub scores: BTreeMap<String, f64>,
+    /// Credibility of sources no class matches, and of facts without resolvable sources.
+    #[serde(default = "default_score")]
+    pub default_score: f64,
+}
+
+fn default_score() -> f64 {
+    1.0
+}
+
+impl Default for SourceCredibility {
+    fn default() -> Self {
+        Self {
+            scores: BTreeMap::new(),
+            default_score: default_score(),
+        }
+    }
+}
+
+impl SourceCredibility {
+    pub fn with_score(mut self, source: impl Into<String>, score: f64) -> Self {
+        self.scores.insert(source.into(), score);
+        self
+    }
+
+    /// Credibility of the most specific class in `classes` that has a score.
+    pub fn score_for(&self, classes: &[String]) -> f64 {
+        classes
+            .iter()
+            .find_map(|class| self.scores.get(class).copied())
+            .unwrap_or(self.default_score)
+    }
+
+    /// Rejects scores outside `[0, 1]`; backends call this before storing.
+    pub(crate) fn check(&self) -> StoreResult<()> {
+        let scores = self
+            .scores
+            .iter()
+            .map(|(source, score)| (source.as_str(), *score))
+            .chain(std::iter::once(("default", self.default_score)));
+        for (source, score) in scores {
+            if !(0.0..=1.0).contains(&score) {
+                return Err(StoreError::InvalidInput(format!(
+                    "credibility of {} must be between 0 and 1, got {}",
+                    source, score
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Source classes of an event, most specific first.
+pub fn source_classes(event: &Event) -> Vec<String> {
+    match &event.kind {
+        EventKind::ToolResult => match event.payload.get("tool").and_then(Value::as_str) {
+            Some(tool) => vec![format!("tool:{}", tool), "tool".to_string()],
+            None => vec!["tool".to_string()],
+        },
+        EventKind::Message => {
+            let role = event.payload.get("role").and_then(Value::as_str);
+            vec![role.unwrap_or("user").to_string()]
+        }
+        kind => vec![kind.as_str().to_string()],
+    }
+}
+
+/// Sets each fact's confidence to its effective confidence under `credibility`. Source
+/// events are looked up across the scope's sessions and runs.
+pub(crate) fn apply_source_credibility<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    credibility: &SourceCredibility,
+    facts: &mut [Fact],
+) -> StoreResult<()> {
+    let mut source_ids: Vec<String> = facts
+        .iter()
+        .flat_map(|fact| fact.sources.iter().cloned())
+        .collect();
+    source_ids.sort();
+    source_ids.dedup();
+    let events = store.get_events_by_ids(scope, &source_ids)?;
+    let classes: HashMap<&str, Vec<String>> = events
+        .iter()
+        .map(|event| (event.event_id.as_str(), source_classes(event)))
+        .collect();
+
+    for fact in facts.iter_mut() {
+        let score = fact
+            .sources
+            .iter()
+            .filter_map(|source| classes.get(source.as_str()))
+            .map(|classes| credibility.score_for(classes))
+            .reduce(f64::max)
+            .unwrap_or(credibility.default_score);
+        fact.confidence *= score;
+    }
+    Ok(())
+}
+
+/// Resolves conflicting facts: of the facts sharing a key, only the one with the
+/// highest confidence is kept.
+pub(crate) fn keep_most_confident(facts: &mut Vec<Fact>) {
+    let mut best: HashMap<String, (f64, String)> = HashMap::new();
+    for fact in facts.iter() {
+        let entry = best
+            .entry(fact.fact_key.clone())
+            .or_insert_with(|| (fact.confidence, fact.fact_id.clone()));
+        if fact.confidence > entry.0 {
+            *entry = (fact.confidence, fact.fact_id.clone());
+        }
+    }
+    facts.retain(|fact| best.get(&fact.fact_key).is_some_and(|(_, id)| *id == fact.fact_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore};
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    #[test]
+    fn credible_sources_win_recall_conflicts() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "acme".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut said = Event::new(
+            scope.clone(),
+            EventKind::Message,
+            json!({ "role": "user", "content": "I live in Porto" }),
+        );
+        said.event_id = "e-user".to_string();
+        store.append_event(said).unwrap();
+        let mut looked_up = Event::new(
+            scope.clone(),
+            EventKind::ToolResult,
+            json!({ "tool": "crm", "city": "Lisbon" }),
+        );
+        looked_up.event_id = "e-crm".to_string();
+        store.append_event(looked_up).unwrap();
+
+        let mut claimed = Fact::new("home.city", json!("Porto"));
+        claimed.sources = vec!["e-user".to_string()];
+        store.upsert_fact(&scope, claimed).unwrap();
+        let mut recorded = Fact::new("home.city", json!("Lisbon"));
+        recorded.confidence = 0.9;
+        recorded.sources = vec!["e-crm".to_string()];
+        store.upsert_fact(&scope, recorded).unwrap();
+        store
+            .upsert_fact(&scope, Fact::new("diet", json!("vegetarian")))
+            .unwrap();
+
+        let invalid = SourceCredibility::default().with_score("user", 1.5);
+        assert!(store.set_source_credibility("acme", invalid).is_err());
+        let credibility = SourceCredibility {
+            default_score: 0.8,
+            ..SourceCredibility::default()
+        }
+        .with_score("tool:crm", 0.95)
+        .with_score("user", 0.5);
+        store.set_source_credibility("acme", credibility.clone()).unwrap();
+        assert_eq!(store.get_source_credibility("acme").unwrap(), Some(credibility));
+        assert!(store.get_source_credibility("other").unwrap().is_none());
+
+        let request = BuildRequest::new(scope, Purpose::Planner);
+        let packet = build_memory_packet(&store, request).unwrap();
+        let facts = &packet.long_term.facts;
+        assert_eq!(facts.len(), 2);
+        let city = facts.iter().find(|fact| fact.fact_key == "home.city").unwrap();
+        assert_eq!(city.value, json!("Lisbon"));
+        assert!((city.confidence - 0.855).abs() < 1e-9);
+        let diet = facts.iter().find(|fact| fact.fact_key == "diet").unwrap();
+        assert!((diet.confidence - 0.4).abs() < 1e-9);
+    }
+}
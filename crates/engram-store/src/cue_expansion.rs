@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+use crate::{cosine_similarity, Embedder, StoreError, StoreResult};
+
+/// How the composer widens [`RecallCues::keywords`](crate::RecallCues::keywords) before
+/// matching, so paraphrased queries still hit: configured synonyms first, then the
+/// vocabulary terms whose embeddings lie nearest each keyword. Empty by default.
+#[derive(Clone)]
+pub struct CueExpansion {
+    /// Terms added for a keyword, looked up ignoring case, e.g. `refund` ->
+    /// `["chargeback", "money back"]`.
+    pub synonyms: HashMap<String, Vec<String>>,
+    /// Embeds keywords and vocabulary terms; without one only synonyms are added.
+    pub embedder: Option<Arc<dyn Embedder>>,
+    /// Candidate terms for embedding neighbours, e.g. the tags and entities in use. Set
+    /// it with [`with_embedder`](Self::with_embedder), which resets the cached embeddings.
+    pub vocabulary: Vec<String>,
+    /// Neighbours added per keyword.
+    pub max_neighbors: usize,
+    pub min_similarity: f32,
+    /// Vocabulary embeddings, computed on first use and shared by clones.
+    term_vectors: Arc<RwLock<HashMap<String, Vec<f32>>>>,
+}
+
+impl Default for CueExpansion {
+    fn default() -> Self {
+        Self {
+            synonyms: HashMap::new(),
+            embedder: None,
+            vocabulary: Vec::new(),
+            max_neighbors: 3,
+            min_similarity: 0.8,
+            term_vectors: Arc::default(),
+        }
+    }
+}
+
+impl fmt::Debug for CueExpansion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CueExpansion")
+            .field("synonyms", &self.synonyms)
+            .field("embedder", &self.embedder.as_ref().map(|embedder| embedder.model()))
+            .field("vocabulary", &self.vocabulary.len())
+            .field("max_neighbors", &self.max_neighbors)
+            .field("min_similarity", &self.min_similarity)
+            .finish()
+    }
+}
+
+impl CueExpansion {
+    pub fn with_synonyms(synonyms: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            synonyms,
+            ..Self::default()
+        }
+    }
+
+    /// Adds embedding neighbours from `vocabulary`; replaces any earlier vocabulary.
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>, vocabulary: Vec<String>) -> Self {
+        self.embedder = Some(embedder);
+        self.vocabulary = vocabulary;
+        self.term_vectors = Arc::default();
+        self
+    }
+
+    /// `keywords` followed by their synonyms and embedding neighbours, without
+    /// duplicates (ignoring case). A failing embedder is logged and skipped so recall
+    /// goes on with the synonyms.
+    pub fn expand(&self, keywords: &[String]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut expanded = Vec::new();
+        let mut push = |term: &str| {
+            let term = term.trim();
+            if !term.is_empty() && seen.insert(term.to_lowercase()) {
+                expanded.push(term.to_string());
+            }
+        };
+        for keyword in keywords {
+            push(keyword);
+        }
+        let synonyms: HashMap<String, &Vec<String>> = self
+            .synonyms
+            .iter()
+            .map(|(term, synonyms)| (term.trim().to_lowercase(), synonyms))
+            .collect();
+        for keyword in keywords {
+            let key = keyword.trim().to_lowercase();
+            for synonym in synonyms.get(&key).copied().into_iter().flatten() {
+                push(synonym);
+            }
+        }
+        match self.neighbors(keywords) {
+            Ok(neighbors) => neighbors.iter().for_each(|term| push(term)),
+            Err(err) => warn!("Cue expansion skipped embedding neighbours: {}", err),
+        }
+        expanded
+    }
+
+    fn neighbors(&self, keywords: &[String]) -> StoreResult<Vec<String>> {
+        let Some(embedder) = &self.embedder else {
+            return Ok(Vec::new());
+        };
+        if keywords.is_empty() || self.vocabulary.is_empty() || self.max_neighbors == 0 {
+            return Ok(Vec::new());
+        }
+        let terms = self.term_vectors()?;
+        let queries = embedder.embed(keywords)?;
+        let mut neighbors = Vec::new();
+        for query in &queries {
+            let mut scored: Vec<(f32, &String)> = terms
+                .iter()
+                .map(|(term, vector)| (cosine_similarity(query, vector), term))
+                .filter(|(score, _)| *score >= self.min_similarity)
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+            neighbors.extend(
+                scored
+                    .into_iter()
+                    .take(self.max_neighbors)
+                    .map(|(_, term)| term.clone()),
+            );
+        }
+        Ok(neighbors)
+    }
+
+    fn term_vectors(&self) -> StoreResult<HashMap<String, Vec<f32>>> {
+        let cached = self.term_vectors.read().map_err(|_| StoreError::Poisoned)?;
+        if !cached.is_empty() {
+            return Ok(cached.clone());
+        }
+        drop(cached);
+        let embedder = self.embedder.as_ref().expect("checked by caller");
+        let vectors = embedder.embed(&self.vocabulary)?;
+        if vectors.len() != self.vocabulary.len() {
+            return Err(StoreError::Storage(format!(
+                "embedder returned {} vectors for {} texts",
+                vectors.len(),
+                self.vocabulary.len()
+            )));
+        }
+        let terms: HashMap<String, Vec<f32>> =
+            self.vocabulary.iter().cloned().zip(vectors).collect();
+        *self.term_vectors.write().map_err(|_| StoreError::Poisoned)? = terms.clone();
+        Ok(terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Maps a few words onto two axes: money and travel.
+    struct AxisEmbedder {
+        calls: Mutex<usize>,
+    }
+
+    impl Embedder for AxisEmbedder {
+        fn model(&self) -> &str {
+            "axis"
+        }
+
+        fn embed(&self, texts: &[String]) -> StoreResult<Vec<Vec<f32>>> {
+            *self.calls.lock().unwrap() += 1;
+            Ok(texts
+                .iter()
+                .map(|text| match text.as_str() {
+                    "refund" | "reimbursement" => vec![1.0, 0.0],
+                    "invoice" => vec![0.9, 0.2],
+                    "flight" => vec![0.0, 1.0],
+                    _ => vec![0.5, 0.5],
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn expands_keywords_with_synonyms_and_nearest_terms() {
+        let embedder = Arc::new(AxisEmbedder {
+            calls: Mutex::new(0),
+        });
+        let expansion = CueExpansion::with_synonyms(HashMap::from([(
+            "Refund".to_string(),
+            vec!["chargeback".to_string(), "REFUND".to_string()],
+        )]))
+        .with_embedder(
+            embedder.clone(),
+            vec!["invoice".to_string(), "reimbursement".to_string(), "flight".to_string()],
+        );
+
+        let keywords = vec!["refund".to_string()];
+        assert_eq!(
+            expansion.expand(&keywords),
+            vec!["refund", "chargeback", "reimbursement", "invoice"]
+        );
+        let mut narrow = expansion.clone();
+        narrow.max_neighbors = 1;
+        assert_eq!(narrow.expand(&keywords), vec!["refund", "chargeback", "reimbursement"]);
+        // The vocabulary was embedded once, then once per expansion for the keywords.
+        assert_eq!(*embedder.calls.lock().unwrap(), 3);
+        assert_eq!(CueExpansion::default().expand(&keywords), keywords);
+    }
+}
@@ -0,0 +1,256 @@
+use engram_types::{new_ulid, Episode, Scope, TimeRange};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use crate::{
+    cosine_similarity, is_period_summary, EpisodeFilter, FactFilter, MemoryKind, MemoryRef, Store,
+    StoreError, StoreResult,
+};
+
+/// One group of episodes [`merge_similar_episodes`] consolidated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeMerge {
+    /// Id of the consolidated episode.
+    pub episode_id: String,
+    /// Ids of the episodes it replaced, oldest first.
+    pub merged: Vec<String>,
+}
+
+/// What [`merge_similar_episodes`] did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpisodeMergeReport {
+    /// Episodes with an embedding under the model that were compared.
+    pub compared: usize,
+    pub merges: Vec<EpisodeMerge>,
+    /// Facts whose sources were repointed from a replaced episode to its replacement.
+    pub facts_updated: usize,
+}
+
+/// Consolidates the scope's episodes that describe the same activity, judged by the
+/// cosine similarity of their stored `model` embeddings reaching `threshold`.
+///
+/// Episodes are taken oldest first and each joins the first group whose oldest
+/// episode it is similar enough to, so similarity does not chain through a group.
+/// A group is replaced by one episode spanning all of its time ranges, with the most
+/// detailed summary and the union of highlights, tags, entities and sources; facts
+/// citing a replaced episode cite the new one instead. Episodes without an embedding
+/// (see [`backfill_embeddings`](crate::backfill_embeddings)), suppressed episodes and
+/// period summaries are left alone.
+pub fn merge_similar_episodes<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    model: &str,
+    threshold: f32,
+) -> StoreResult<EpisodeMergeReport> {
+    if !(-1.0..=1.0).contains(&threshold) {
+        return Err(StoreError::InvalidInput(format!(
+            "similarity threshold must be between -1 and 1, got {}",
+            threshold
+        )));
+    }
+    let vectors: HashMap<String, Vec<f32>> = store
+        .list_embeddings(scope, model)?
+        .into_iter()
+        .filter(|embedding| embedding.item.kind == MemoryKind::Episode)
+        .map(|embedding| (embedding.item.id, embedding.vector))
+        .collect();
+    let suppressed: HashSet<String> = store
+        .list_suppressions(scope)?
+        .into_iter()
+        .filter(|suppression| suppression.item.kind == MemoryKind::Episode)
+        .map(|suppression| suppression.item.id)
+        .collect();
+    let mut episodes: Vec<Episode> = store
+        .list_episodes(scope, EpisodeFilter::default())?
+        .into_iter()
+        .filter(|episode| {
+            vectors.contains_key(&episode.episode_id)
+                && !suppressed.contains(&episode.episode_id)
+                && !is_period_summary(episode)
+        })
+        .collect();
+    episodes.sort_by(|a, b| {
+        a.time_range
+            .start
+            .cmp(&b.time_range.start)
+            .then_with(|| a.episode_id.cmp(&b.episode_id))
+    });
+
+    let mut groups: Vec<Vec<Episode>> = Vec::new();
+    for episode in episodes.iter() {
+        let vector = &vectors[&episode.episode_id];
+        let group = groups.iter_mut().find(|group| {
+            cosine_similarity(&vectors[&group[0].episode_id], vector) >= threshold
+        });
+        match group {
+            Some(group) => group.push(episode.clone()),
+            None => groups.push(vec![episode.clone()]),
+        }
+    }
+
+    let mut report = EpisodeMergeReport {
+        compared: episodes.len(),
+        ..EpisodeMergeReport::default()
+    };
+    let mut replaced: HashMap<String, String> = HashMap::new();
+    for group in groups.into_iter().filter(|group| group.len() > 1) {
+        let merged = merge_group(&group);
+        // The replacement is stored before the originals go, so an interruption leaves a
+        // duplicate rather than losing the episode.
+        store.append_episode(scope, merged.clone())?;
+        let ids: Vec<String> = group.iter().map(|episode| episode.episode_id.clone()).collect();
+        for id in &ids {
+            replaced.insert(id.clone(), merged.episode_id.clone());
+        }
+        report.merges.push(EpisodeMerge {
+            episode_id: merged.episode_id,
+            merged: ids,
+        });
+    }
+    if replaced.is_empty() {
+        return Ok(report);
+    }
+
+    for mut fact in store.list_facts(scope, FactFilter::default())? {
+        if !fact.sources.iter().any(|source| replaced.contains_key(source)) {
+            continue;
+        }
+        let sources = std::mem::take(&mut fact.sources)
+            .into_iter()
+            .map(|source| replaced.get(&source).cloned().unwrap_or(source));
+        fact.sources = dedup(sources);
+        store.upsert_fact(scope, fact)?;
+        report.facts_updated += 1;
+    }
+    let evict: Vec<MemoryRef> = replaced
+        .into_keys()
+        .map(|id| MemoryRef {
+            kind: MemoryKind::Episode,
+            id,
+        })
+        .collect();
+    store.evict_memory(scope, &evict)?;
+    debug!(
+        "merged {} episodes of {}/{}/{} into {}",
+        evict.len(),
+        scope.tenant_id,
+        scope.user_id,
+        scope.agent_id,
+        report.merges.len()
+    );
+    Ok(report)
+}
+
+/// One episode standing for all of `group`, which is sorted oldest first.
+fn merge_group(group: &[Episode]) -> Episode {
+    let detailed = group
+        .iter()
+        .max_by_key(|episode| episode.summary.chars().count())
+        .expect("non-empty group");
+    let start = group[0].time_range.start;
+    let end = group
+        .iter()
+        .map(|episode| episode.time_range.end.unwrap_or(episode.time_range.start))
+        .max()
+        .filter(|end| *end > start);
+    let union = |field: fn(&Episode) -> &Vec<String>| {
+        dedup(group.iter().flat_map(|episode| field(episode).iter().cloned()))
+    };
+    Episode {
+        episode_id: new_ulid(),
+        time_range: TimeRange { start, end },
+        summary: detailed.summary.clone(),
+        highlights: union(|episode| &episode.highlights),
+        tags: union(|episode| &episode.tags),
+        entities: union(|episode| &episode.entities),
+        sources: union(|episode| &episode.sources),
+        compression_level: detailed.compression_level.clone(),
+        recency_score: None,
+        lang: detailed.lang.clone(),
+    }
+}
+
+fn dedup(items: impl Iterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    items.filter(|item| seen.insert(item.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, MemoryEmbedding};
+    use chrono::{Duration, Utc};
+    use engram_types::Fact;
+    use serde_json::json;
+
+    #[test]
+    fn merges_similar_episodes_and_repoints_facts() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let now = Utc::now();
+        let mut embeddings = Vec::new();
+        for (id, summary, minutes, tags, vector) in [
+            ("deploy-a", "Deployed", 30, vec!["deploy"], vec![1.0, 0.0]),
+            ("deploy-b", "Deployed api v2 to prod", 20, vec!["prod"], vec![0.95, 0.1]),
+            ("lunch", "Ordered lunch", 10, vec!["food"], vec![0.0, 1.0]),
+            ("hidden", "Deployed again", 5, vec!["deploy"], vec![1.0, 0.0]),
+        ] {
+            let mut episode = Episode::new(summary);
+            episode.episode_id = id.to_string();
+            episode.time_range.start = now - Duration::minutes(minutes);
+            episode.tags = tags.into_iter().map(String::from).collect();
+            episode.sources = vec![format!("ev-{}", id)];
+            store.append_episode(&scope, episode).unwrap();
+            embeddings.push(MemoryEmbedding {
+                item: MemoryRef {
+                    kind: MemoryKind::Episode,
+                    id: id.to_string(),
+                },
+                model: "m".to_string(),
+                vector,
+                digest: String::new(),
+                embedded_at: now,
+            });
+        }
+        store.upsert_embeddings(&scope, embeddings).unwrap();
+        let hidden = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: "hidden".to_string(),
+        };
+        store.suppress_memory(&scope, hidden, "").unwrap();
+        let mut fact = Fact::new("release.api", json!("v2"));
+        fact.sources = vec!["deploy-b".to_string(), "ev-other".to_string()];
+        store.upsert_fact(&scope, fact).unwrap();
+
+        assert!(merge_similar_episodes(&store, &scope, "m", 1.5).is_err());
+        let report = merge_similar_episodes(&store, &scope, "m", 0.9).unwrap();
+        assert_eq!(report.compared, 3);
+        assert_eq!(report.merges.len(), 1);
+        assert_eq!(report.merges[0].merged, vec!["deploy-a", "deploy-b"]);
+        assert_eq!(report.facts_updated, 1);
+
+        let episodes = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        let mut ids: Vec<&str> = episodes.iter().map(|e| e.episode_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&"lunch") && ids.contains(&"hidden"));
+        let merged = episodes
+            .iter()
+            .find(|e| e.episode_id == report.merges[0].episode_id)
+            .unwrap();
+        assert_eq!(merged.summary, "Deployed api v2 to prod");
+        assert_eq!(merged.tags, vec!["deploy", "prod"]);
+        assert_eq!(merged.sources, vec!["ev-deploy-a", "ev-deploy-b"]);
+        assert_eq!(merged.time_range.start, now - Duration::minutes(30));
+        assert_eq!(merged.time_range.end, Some(now - Duration::minutes(20)));
+        let facts = store.list_facts(&scope, FactFilter::default()).unwrap();
+        assert_eq!(facts[0].sources, vec![merged.episode_id.clone(), "ev-other".to_string()]);
+    }
+}
@@ -0,0 +1,272 @@
+use chrono::{DateTime, Utc};
+use engram_types::{Episode, Fact, Scope};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::{EpisodeFilter, FactFilter, MemoryKind, MemoryRef, Store, StoreError, StoreResult};
+
+/// Turns memory text into vectors, e.g. by calling an embedding API.
+pub trait Embedder: Send + Sync {
+    /// Name of the model; embeddings of different models are stored side by side.
+    fn model(&self) -> &str;
+    /// One vector per text, in order.
+    fn embed(&self, texts: &[String]) -> StoreResult<Vec<Vec<f32>>>;
+}
+
+/// The embedding of one fact or episode under one model, stored with
+/// [`Store::upsert_embeddings`](crate::Store::upsert_embeddings).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEmbedding {
+    pub item: MemoryRef,
+    pub model: String,
+    pub vector: Vec<f32>,
+    /// Digest of the embedded text (see [`text_digest`]); an item whose text changed
+    /// since is embedded again by [`backfill_embeddings`].
+    #[serde(default)]
+    pub digest: String,
+    #[serde(default = "Utc::now")]
+    pub embedded_at: DateTime<Utc>,
+}
+
+/// Text of a fact as it is embedded: `key: value`.
+pub fn fact_text(fact: &Fact) -> String {
+    match &fact.value {
+        Value::String(value) => format!("{}: {}", fact.fact_key, value),
+        value => format!("{}: {}", fact.fact_key, value),
+    }
+}
+
+/// Text of an episode as it is embedded: its summary, then one highlight per line.
+pub fn episode_text(episode: &Episode) -> String {
+    let mut text = episode.summary.clone();
+    for highlight in &episode.highlights {
+        text.push('\n');
+        text.push_str(highlight);
+    }
+    text
+}
+
+/// Stable 64-bit FNV-1a digest of `text`, in hex.
+pub fn text_digest(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Cosine similarity of two vectors in `[-1, 1]`; 0 when their lengths differ or
+/// either is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    /// Texts sent to the embedder per call.
+    pub batch_size: usize,
+    /// Upper bound on texts embedded per second; 0 disables the limit.
+    pub max_items_per_second: f64,
+    /// Items embedded by one call before it returns, so a large backlog can be worked
+    /// off across several runs of the job; `None` embeds everything.
+    pub max_items: Option<usize>,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 32,
+            max_items_per_second: 0.0,
+            max_items: None,
+        }
+    }
+}
+
+/// What [`backfill_embeddings`] did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackfillReport {
+    pub embedded: usize,
+    pub batches: usize,
+    /// Items still lacking a current embedding when the job stopped at `max_items`.
+    pub remaining: usize,
+}
+
+/// Embeds the scope's facts and episodes that have no embedding under the
+/// embedder's model, or whose text changed since they were embedded. Meant for
+/// deployments that start using embeddings with memory already stored.
+///
+/// Items are embedded in batches of `batch_size`, pausing between batches to stay
+/// under `max_items_per_second`, and each batch is stored before the next one is
+/// requested, so an interrupted job resumes where it stopped.
+pub fn backfill_embeddings<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    embedder: &dyn Embedder,
+    options: &BackfillOptions,
+) -> StoreResult<BackfillReport> {
+    let model = embedder.model().to_string();
+    let current: HashMap<MemoryRef, String> = store
+        .list_embeddings(scope, &model)?
+        .into_iter()
+        .map(|embedding| (embedding.item, embedding.digest))
+        .collect();
+    let mut pending = memory_texts(store, scope)?;
+    pending.retain(|(item, text)| current.get(item) != Some(&text_digest(text)));
+
+    let limit = options.max_items.unwrap_or(usize::MAX).min(pending.len());
+    let mut report = BackfillReport {
+        remaining: pending.len() - limit,
+        ..BackfillReport::default()
+    };
+    for batch in pending[..limit].chunks(options.batch_size.max(1)) {
+        let started = Instant::now();
+        let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = embedder.embed(&texts)?;
+        if vectors.len() != batch.len() {
+            return Err(StoreError::Storage(format!(
+                "embedder returned {} vectors for {} texts",
+                vectors.len(),
+                batch.len()
+            )));
+        }
+        let embeddings = batch
+            .iter()
+            .zip(vectors)
+            .map(|((item, text), vector)| MemoryEmbedding {
+                item: item.clone(),
+                model: model.clone(),
+                vector,
+                digest: text_digest(text),
+                embedded_at: Utc::now(),
+            })
+            .collect();
+        store.upsert_embeddings(scope, embeddings)?;
+        report.embedded += batch.len();
+        report.batches += 1;
+
+        if options.max_items_per_second > 0.0 && report.embedded < limit {
+            let budget = Duration::from_secs_f64(batch.len() as f64 / options.max_items_per_second);
+            std::thread::sleep(budget.saturating_sub(started.elapsed()));
+        }
+    }
+    debug!(
+        "embedded {} items of {}/{}/{} with {} in {} batches, {} remaining",
+        report.embedded,
+        scope.tenant_id,
+        scope.user_id,
+        scope.agent_id,
+        model,
+        report.batches,
+        report.remaining
+    );
+    Ok(report)
+}
+
+/// The scope's facts and episodes with the text they are embedded from.
+pub(crate) fn memory_texts<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+) -> StoreResult<Vec<(MemoryRef, String)>> {
+    let mut texts = Vec::new();
+    for fact in store.list_facts(scope, FactFilter::default())? {
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: fact.fact_id.clone(),
+        };
+        texts.push((item, fact_text(&fact)));
+    }
+    for episode in store.list_episodes(scope, EpisodeFilter::default())? {
+        let item = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode.episode_id.clone(),
+        };
+        texts.push((item, episode_text(&episode)));
+    }
+    Ok(texts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Embeds each text as its length and counts the calls.
+    struct LengthEmbedder {
+        calls: Mutex<Vec<usize>>,
+    }
+
+    impl Embedder for LengthEmbedder {
+        fn model(&self) -> &str {
+            "length-v1"
+        }
+
+        fn embed(&self, texts: &[String]) -> StoreResult<Vec<Vec<f32>>> {
+            self.calls.lock().unwrap().push(texts.len());
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+    }
+
+    #[test]
+    fn backfills_missing_and_stale_embeddings_in_batches() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        for (id, city) in [("f1", "Lisbon"), ("f2", "Porto"), ("f3", "Faro")] {
+            let mut fact = Fact::new(format!("trip.{}", id), json!(city));
+            fact.fact_id = id.to_string();
+            store.upsert_fact(&scope, fact).unwrap();
+        }
+        store
+            .append_episode(&scope, Episode::new("planned a trip"))
+            .unwrap();
+        let embedder = LengthEmbedder {
+            calls: Mutex::new(Vec::new()),
+        };
+        let options = BackfillOptions {
+            batch_size: 2,
+            max_items_per_second: 1_000.0,
+            max_items: Some(3),
+        };
+
+        let report = backfill_embeddings(&store, &scope, &embedder, &options).unwrap();
+        assert_eq!((report.embedded, report.batches, report.remaining), (3, 2, 1));
+        let report = backfill_embeddings(&store, &scope, &embedder, &options).unwrap();
+        assert_eq!((report.embedded, report.remaining), (1, 0));
+        assert_eq!(*embedder.calls.lock().unwrap(), vec![2, 1, 1]);
+
+        // Only the fact whose value changed is embedded again.
+        let mut moved = Fact::new("trip.f2", json!("Braga"));
+        moved.fact_id = "f2".to_string();
+        store.upsert_fact(&scope, moved).unwrap();
+        let report = backfill_embeddings(&store, &scope, &embedder, &options).unwrap();
+        assert_eq!(report.embedded, 1);
+        let embeddings = store.list_embeddings(&scope, "length-v1").unwrap();
+        assert_eq!(embeddings.len(), 4);
+        let f2 = embeddings.iter().find(|e| e.item.id == "f2").unwrap();
+        assert_eq!(f2.vector, vec!["trip.f2: Braga".len() as f32]);
+        assert!(store.list_embeddings(&scope, "other").unwrap().is_empty());
+    }
+}
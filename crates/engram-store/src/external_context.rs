@@ -0,0 +1,155 @@
+use engram_types::{ExternalContext, Purpose, Scope};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{RecallCues, StoreResult};
+
+/// What a build asks an [`ExternalContextProvider`] for.
+#[derive(Debug, Clone, Copy)]
+pub struct ExternalContextQuery<'a> {
+    pub scope: &'a Scope,
+    pub purpose: &'a Purpose,
+    pub cues: &'a RecallCues,
+    /// Tokens the budget has left after the memory sections, capped by
+    /// `budget.per_section["external_context"]`; `None` when the build is unbounded.
+    pub remaining_tokens: Option<u32>,
+}
+
+/// Fetches context from outside memory, such as RAG documents or tickets, for
+/// `build_memory_packet` to put in `external_context`. Set providers on
+/// [`RecallPolicy::external_context`](crate::RecallPolicy::external_context); their
+/// items are cited, counted and trimmed with the rest of the packet.
+pub trait ExternalContextProvider: Send + Sync {
+    /// Name of the provider, used as the `source` of items that leave it empty.
+    fn name(&self) -> &str;
+
+    /// Items for `query`, best first. A provider that fails doesn't fail the build:
+    /// the error is reported in `budget_report.degradations`.
+    fn fetch(&self, query: &ExternalContextQuery<'_>) -> StoreResult<Vec<ExternalContext>>;
+}
+
+impl fmt::Debug for dyn ExternalContextProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalContextProvider")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Items of every provider in order; each failure is pushed to `failures`.
+pub(crate) fn fetch_external_context(
+    providers: &[Arc<dyn ExternalContextProvider>],
+    query: &ExternalContextQuery<'_>,
+    failures: &mut Vec<Value>,
+) -> Vec<ExternalContext> {
+    let mut items = Vec::new();
+    for provider in providers {
+        match provider.fetch(query) {
+            Ok(fetched) => items.extend(fetched.into_iter().map(|mut item| {
+                if item.source.is_empty() {
+                    item.source = provider.name().to_string();
+                }
+                item
+            })),
+            Err(e) => {
+                warn!("External context provider {} failed: {}", provider.name(), e);
+                failures.push(json!({
+                    "section": "external_context",
+                    "reason": "provider_error",
+                    "provider": provider.name(),
+                    "error": e.to_string(),
+                }));
+            }
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore, Store, StoreError};
+    use engram_types::{CitationType, Fact, JsonMap};
+
+    /// Returns one document per keyword cue while the budget has room for it.
+    struct Docs;
+
+    impl ExternalContextProvider for Docs {
+        fn name(&self) -> &str {
+            "docs"
+        }
+
+        fn fetch(&self, query: &ExternalContextQuery<'_>) -> StoreResult<Vec<ExternalContext>> {
+            let room = query.remaining_tokens.unwrap_or(u32::MAX) as usize;
+            Ok(query
+                .cues
+                .keywords
+                .iter()
+                .take(room / 50)
+                .map(|keyword| ExternalContext {
+                    id: format!("doc-{}", keyword),
+                    source: String::new(),
+                    title: format!("About {}", keyword),
+                    content: format!("{} is covered in the handbook.", keyword),
+                    score: None,
+                    metadata: JsonMap::new(),
+                })
+                .collect())
+        }
+    }
+
+    struct Tickets;
+
+    impl ExternalContextProvider for Tickets {
+        fn name(&self) -> &str {
+            "tickets"
+        }
+
+        fn fetch(&self, _query: &ExternalContextQuery<'_>) -> StoreResult<Vec<ExternalContext>> {
+            Err(StoreError::Storage("ticket service unavailable".to_string()))
+        }
+    }
+
+    #[test]
+    fn providers_fill_a_cited_section_within_the_remaining_budget() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        request.cues.keywords = vec!["refunds".to_string(), "shipping".to_string()];
+        request.policy.external_context = vec![Arc::new(Docs), Arc::new(Tickets)];
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+
+        let ids: Vec<&str> = packet.external_context.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["doc-refunds", "doc-shipping"]);
+        assert!(packet.external_context.iter().all(|item| item.source == "docs"));
+        assert!(packet
+            .citations
+            .iter()
+            .any(|citation| citation.id == "doc-refunds"
+                && matches!(citation.kind, CitationType::External)));
+        assert!(packet.budget_report.section_usage.contains_key("external_context"));
+        let failure = packet
+            .budget_report
+            .degradations
+            .iter()
+            .find(|entry| entry["reason"] == "provider_error")
+            .unwrap();
+        assert_eq!(failure["provider"], "tickets");
+
+        // The provider only gets what the memory sections leave of the budget.
+        request.budget.per_section.insert("external_context".to_string(), json!(60));
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.external_context.len(), 1);
+    }
+}
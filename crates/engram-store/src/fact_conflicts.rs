@@ -0,0 +1,200 @@
+use engram_types::{Fact, FactStatus, InsightItem, InsightTrigger, InsightType, Scope};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{FactFilter, Store, StoreError, StoreResult};
+
+/// What [`upsert_fact_checked`] does when the fact contradicts the scope's facts: an
+/// active or disputed fact with the same key, another value and an overlapping
+/// validity window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// The new fact wins and the facts it contradicts are deprecated.
+    Overwrite,
+    /// Both sides are kept as [`FactStatus::Disputed`] and the run gets an
+    /// [`InsightTrigger::Conflict`] insight naming them, so recall can flag them.
+    #[default]
+    KeepBothDisputed,
+    /// The write fails with [`StoreError::FactConflict`].
+    Reject,
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = StoreError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "keep_both_disputed" => Ok(ConflictPolicy::KeepBothDisputed),
+            "reject" => Ok(ConflictPolicy::Reject),
+            _ => Err(StoreError::InvalidInput(format!("invalid conflict policy: {}", value))),
+        }
+    }
+}
+
+/// What [`upsert_fact_checked`] wrote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactUpsert {
+    /// The fact as stored, disputed if the policy kept both sides.
+    pub fact: Fact,
+    /// The facts it contradicted, as stored after the write.
+    pub conflicts: Vec<Fact>,
+    /// The conflict insight recorded for the run, if any.
+    pub insight: Option<InsightItem>,
+}
+
+/// Upserts `fact` after checking it against the scope's active and disputed facts
+/// with the same key, resolving contradictions per `policy`. Rewriting a fact under
+/// its own id, or writing a deprecated fact, never conflicts.
+pub fn upsert_fact_checked<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    mut fact: Fact,
+    policy: ConflictPolicy,
+) -> StoreResult<FactUpsert> {
+    let mut conflicts = Vec::new();
+    if matches!(fact.status, FactStatus::Active | FactStatus::Disputed) {
+        let filter = FactFilter {
+            status: Some(vec![FactStatus::Active, FactStatus::Disputed]),
+            ..FactFilter::default()
+        };
+        conflicts = store.list_facts(scope, filter)?;
+        conflicts.retain(|existing| contradicts(existing, &fact));
+    }
+    if conflicts.is_empty() {
+        store.upsert_fact(scope, fact.clone())?;
+        return Ok(FactUpsert {
+            fact,
+            conflicts,
+            insight: None,
+        });
+    }
+    debug!("fact {} contradicts {} facts under {:?}", fact.fact_id, conflicts.len(), policy);
+
+    let status = match policy {
+        ConflictPolicy::Reject => {
+            return Err(StoreError::FactConflict {
+                fact_key: fact.fact_key,
+                fact_id: conflicts[0].fact_id.clone(),
+            });
+        }
+        ConflictPolicy::Overwrite => FactStatus::Deprecated,
+        ConflictPolicy::KeepBothDisputed => {
+            fact.status = FactStatus::Disputed;
+            FactStatus::Disputed
+        }
+    };
+    for existing in &mut conflicts {
+        existing.status = status.clone();
+    }
+    let mut written = conflicts.clone();
+    written.push(fact.clone());
+    store.upsert_facts_bulk(scope, &written)?;
+
+    let mut insight = None;
+    if policy == ConflictPolicy::KeepBothDisputed {
+        let values: Vec<String> = conflicts.iter().map(|other| other.value.to_string()).collect();
+        let mut item = InsightItem::new(
+            InsightType::Hypothesis,
+            format!(
+                "{} has conflicting values: {} versus {}",
+                fact.fact_key,
+                fact.value,
+                values.join(", ")
+            ),
+        );
+        item.trigger = InsightTrigger::Conflict;
+        item.sources = written.iter().rev().map(|fact| fact.fact_id.clone()).collect();
+        store.append_insight(scope, item.clone())?;
+        insight = Some(item);
+    }
+    Ok(FactUpsert {
+        fact,
+        conflicts,
+        insight,
+    })
+}
+
+/// Whether `existing` says something else about `fact`'s key for part of its validity.
+fn contradicts(existing: &Fact, fact: &Fact) -> bool {
+    let (a, b) = (&existing.validity, &fact.validity);
+    let overlaps = a.valid_from.is_none_or(|from| b.valid_to.is_none_or(|to| from <= to))
+        && b.valid_from.is_none_or(|from| a.valid_to.is_none_or(|to| from <= to));
+    existing.fact_id != fact.fact_id
+        && existing.fact_key == fact.fact_key
+        && existing.value != fact.value
+        && overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, InsightFilter};
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn contradictory_values_follow_the_policy() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let lisbon = Fact::new("user.city", json!("Lisbon"));
+        let policy = ConflictPolicy::KeepBothDisputed;
+        let first = upsert_fact_checked(&store, &scope, lisbon.clone(), policy).unwrap();
+        assert!(first.conflicts.is_empty() && first.insight.is_none());
+        // The same value under another id, or the same fact rewritten, agrees.
+        upsert_fact_checked(&store, &scope, lisbon.clone(), policy).unwrap();
+        let again = Fact::new("user.city", json!("Lisbon"));
+        assert!(upsert_fact_checked(&store, &scope, again, policy).unwrap().conflicts.is_empty());
+
+        let porto = Fact::new("user.city", json!("Porto"));
+        let err = upsert_fact_checked(&store, &scope, porto.clone(), ConflictPolicy::Reject);
+        match err {
+            Err(StoreError::FactConflict { fact_key, fact_id }) => {
+                assert_eq!((fact_key.as_str(), fact_id), ("user.city", lisbon.fact_id.clone()));
+            }
+            other => panic!("expected a fact conflict, got {other:?}"),
+        }
+
+        let disputed = upsert_fact_checked(&store, &scope, porto.clone(), policy).unwrap();
+        assert_eq!(disputed.fact.status, FactStatus::Disputed);
+        assert_eq!(disputed.conflicts.len(), 2);
+        assert!(disputed.conflicts.iter().all(|fact| fact.status == FactStatus::Disputed));
+        let insights = store.list_insights(&scope, InsightFilter::default()).unwrap();
+        assert_eq!(insights.len(), 1);
+        assert!(matches!(insights[0].trigger, InsightTrigger::Conflict));
+        assert_eq!(insights[0].sources[0], porto.fact_id);
+
+        let braga = Fact::new("user.city", json!("Braga"));
+        let overwritten =
+            upsert_fact_checked(&store, &scope, braga, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(overwritten.fact.status, FactStatus::Active);
+        assert_eq!(overwritten.conflicts.len(), 3);
+        let active = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    status: Some(vec![FactStatus::Active, FactStatus::Disputed]),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(active.len(), 1);
+
+        // A value for a window that ended before the current one began is history.
+        let mut before = Fact::new("user.city", json!("Faro"));
+        before.validity.valid_to = Some(Utc::now() - Duration::days(400));
+        let mut current = overwritten.fact;
+        current.validity.valid_from = Some(Utc::now() - Duration::days(30));
+        store.upsert_fact(&scope, current).unwrap();
+        let past = upsert_fact_checked(&store, &scope, before, ConflictPolicy::Reject).unwrap();
+        assert!(past.conflicts.is_empty());
+        assert_eq!("reject".parse::<ConflictPolicy>().unwrap(), ConflictPolicy::Reject);
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use engram_types::{ContentHash, Fact};
+use serde::{Deserialize, Serialize};
+
+/// One value a fact was written with, from [`Store::list_fact_history`]. History is
+/// bitemporal: `recorded_at` is when the store learned the value (transaction time)
+/// and the fact's own `validity` is when the value held (valid time). Who or what
+/// wrote it is in the fact's `sources`.
+///
+/// [`Store::list_fact_history`]: crate::Store::list_fact_history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactVersion {
+    /// Counts the writes of the fact's `fact_id` from 1.
+    pub version: u32,
+    pub recorded_at: DateTime<Utc>,
+    pub fact: Fact,
+}
+
+/// The value `history` says held at `valid_at`, as known at `known_at`: of the
+/// versions recorded by then, the latest of each fact whose validity covers
+/// `valid_at`, and of those the latest recorded. Answers "what did we believe on
+/// March 1st about the user's plan in January?".
+pub fn fact_as_of(
+    history: &[FactVersion],
+    valid_at: DateTime<Utc>,
+    known_at: DateTime<Utc>,
+) -> Option<&FactVersion> {
+    let known: Vec<&FactVersion> =
+        history.iter().filter(|version| version.recorded_at <= known_at).collect();
+    known
+        .iter()
+        .filter(|version| {
+            // Only the fact's latest known version speaks for it.
+            !known.iter().any(|later| {
+                later.fact.fact_id == version.fact.fact_id && later.version > version.version
+            })
+        })
+        .filter(|version| {
+            let validity = &version.fact.validity;
+            validity.valid_from.is_none_or(|from| from <= valid_at)
+                && validity.valid_to.is_none_or(|to| to >= valid_at)
+        })
+        .max_by_key(|version| (version.recorded_at, version.version))
+        .copied()
+}
+
+/// The version a write of `fact` records after `latest`, the fact's last recorded
+/// version; `None` when the write repeats it.
+pub(crate) fn next_fact_version(
+    latest: Option<&FactVersion>,
+    fact: &Fact,
+    recorded_at: DateTime<Utc>,
+) -> Option<FactVersion> {
+    if latest.is_some_and(|latest| same_fact(&latest.fact, fact)) {
+        return None;
+    }
+    Some(FactVersion {
+        version: latest.map_or(1, |latest| latest.version + 1),
+        recorded_at,
+        fact: fact.clone(),
+    })
+}
+
+fn same_fact(a: &Fact, b: &Fact) -> bool {
+    a.fact_id == b.fact_id && a.content_hash() == b.content_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeleteMode, InMemoryStore, RecordKind, RecordRef, Store};
+    use chrono::Duration;
+    use engram_types::Scope;
+    use serde_json::json;
+
+    #[test]
+    fn keeps_every_value_of_a_fact() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let jan = Utc::now() - Duration::days(90);
+        let mut fact = Fact::new("plan", json!("basic"));
+        fact.validity.valid_from = Some(jan);
+        fact.sources = vec!["signup_form".to_string()];
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        let mar = jan + Duration::days(60);
+        fact.validity.valid_to = Some(mar);
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        let mut upgrade = Fact::new("plan", json!("pro"));
+        upgrade.validity.valid_from = Some(mar);
+        upgrade.sources = vec!["billing_webhook".to_string()];
+        store.upsert_fact(&scope, upgrade.clone()).unwrap();
+        store.upsert_fact(&scope, Fact::new("locale", json!("en"))).unwrap();
+
+        let history = store.list_fact_history(&scope, "plan").unwrap();
+        let versions: Vec<_> = history
+            .iter()
+            .map(|version| (version.fact.value.clone(), version.version))
+            .collect();
+        assert_eq!(versions, [(json!("basic"), 1), (json!("basic"), 2), (json!("pro"), 1)]);
+        assert_eq!(history[2].fact.sources, ["billing_webhook"]);
+
+        let feb = jan + Duration::days(30);
+        let now = Utc::now();
+        let believed = fact_as_of(&history, feb, now).unwrap();
+        assert_eq!(believed.fact.value, json!("basic"));
+        let upgraded = fact_as_of(&history, now, now).unwrap();
+        assert_eq!(upgraded.fact.value, json!("pro"));
+        assert!(fact_as_of(&history, now, history[0].recorded_at).is_some());
+        assert!(fact_as_of(&history, jan - Duration::days(1), now).is_none());
+
+        let item = RecordRef::new(RecordKind::Fact, &upgrade.fact_id);
+        store.delete_record(&scope, &item, DeleteMode::Soft).unwrap();
+        assert_eq!(store.list_fact_history(&scope, "plan").unwrap().len(), 3);
+        let item = RecordRef::new(RecordKind::Fact, &fact.fact_id);
+        store.delete_record(&scope, &item, DeleteMode::Hard).unwrap();
+        let history = store.list_fact_history(&scope, "plan").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].fact.fact_id, upgrade.fact_id);
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use engram_types::{Fact, FactStatus, Scope, ScopeLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+use crate::{shared_fact_scope, FactFilter, Store, StoreError, StoreResult};
+
+/// What [`expire_tenant_facts`] did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExpiryReport {
+    /// User, agent and tenant fact pools swept.
+    pub pools: usize,
+    /// Ids of the facts deprecated.
+    pub expired: Vec<String>,
+}
+
+/// Pins a fact so every packet built for its scope includes it, regardless of
+/// ranking, `max_facts` or budget trimming. Returns the updated fact.
+pub fn pin_fact<S: Store + ?Sized>(store: &S, scope: &Scope, fact_id: &str) -> StoreResult<Fact> {
+    set_pinned(store, scope, fact_id, true)
+}
+
+/// Returns a pinned fact to normal ranking.
+pub fn unpin_fact<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    fact_id: &str,
+) -> StoreResult<Fact> {
+    set_pinned(store, scope, fact_id, false)
+}
+
+fn set_pinned<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    fact_id: &str,
+    pinned: bool,
+) -> StoreResult<Fact> {
+    let mut fact = store
+        .list_facts(scope, FactFilter::default())?
+        .into_iter()
+        .find(|fact| fact.fact_id == fact_id)
+        .ok_or(StoreError::NotFound)?;
+    if fact.pinned != pinned {
+        fact.pinned = pinned;
+        store.upsert_fact(scope, fact.clone())?;
+        debug!("fact {} pinned: {}", fact_id, pinned);
+    }
+    Ok(fact)
+}
+
+/// Deprecates the scope's active and disputed facts whose `validity.valid_to` lies
+/// before `now`, so backends and exports agree with what recall already leaves out.
+/// Shared pools are not touched; see [`expire_tenant_facts`]. Returns the expired facts.
+pub fn expire_facts<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    now: DateTime<Utc>,
+) -> StoreResult<Vec<Fact>> {
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Active, FactStatus::Disputed]),
+        ..FactFilter::default()
+    };
+    let mut expired = Vec::new();
+    for mut fact in store.list_facts(scope, filter)? {
+        if fact.validity.valid_to.is_none_or(|valid_to| valid_to >= now) {
+            continue;
+        }
+        fact.status = FactStatus::Deprecated;
+        store.upsert_fact(scope, fact.clone())?;
+        expired.push(fact);
+    }
+    if !expired.is_empty() {
+        debug!(
+            "expired {} facts of {}/{}/{}",
+            expired.len(),
+            scope.tenant_id,
+            scope.user_id,
+            scope.agent_id
+        );
+    }
+    Ok(expired)
+}
+
+/// Runs [`expire_facts`] over every user fact pool of the tenant with recorded
+/// activity, and over the agent and tenant pools above them. Meant for a periodic
+/// maintenance job.
+pub fn expire_tenant_facts<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+    now: DateTime<Utc>,
+) -> StoreResult<ExpiryReport> {
+    let mut pools = BTreeMap::new();
+    for scope in store.list_scopes(Some(tenant_id), None)? {
+        for level in [ScopeLevel::User, ScopeLevel::Agent, ScopeLevel::Tenant] {
+            let pool = shared_fact_scope(&scope, &level);
+            pools.entry((pool.user_id.clone(), pool.agent_id.clone())).or_insert(pool);
+        }
+    }
+    let mut report = ExpiryReport::default();
+    for pool in pools.into_values() {
+        report.pools += 1;
+        let expired = expire_facts(store, &pool, now)?;
+        report.expired.extend(expired.into_iter().map(|fact| fact.fact_id));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, Event, EventKind, InMemoryStore};
+    use chrono::Duration;
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    fn fact_keys(store: &InMemoryStore, request: BuildRequest) -> Vec<String> {
+        let packet = build_memory_packet(store, request).unwrap();
+        packet.long_term.facts.into_iter().map(|fact| fact.fact_key).collect()
+    }
+
+    #[test]
+    fn expired_facts_are_deprecated_and_only_recalled_looking_back() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!({})))
+            .unwrap();
+        let now = Utc::now();
+        let last_week = now - Duration::days(7);
+        let mut trial = Fact::new("plan.trial", json!(true));
+        trial.validity.valid_from = Some(now - Duration::days(30));
+        trial.validity.valid_to = Some(now - Duration::days(1));
+        let mut plan = Fact::new("plan.tier", json!("pro"));
+        plan.validity.valid_to = Some(now + Duration::days(30));
+        let mut retracted = Fact::new("plan.discount", json!(10));
+        retracted.status = FactStatus::Deprecated;
+        store.upsert_fact(&scope, trial.clone()).unwrap();
+        store.upsert_fact(&scope, plan).unwrap();
+        store.upsert_fact(&scope, retracted).unwrap();
+        let mut promo = Fact::new("promo.spring", json!("20% off"));
+        promo.validity.valid_to = Some(now - Duration::hours(1));
+        let tenant_pool = shared_fact_scope(&scope, &ScopeLevel::Tenant);
+        store.upsert_fact(&tenant_pool, promo.clone()).unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.policy.include_shared_facts = true;
+        assert_eq!(fact_keys(&store, request.clone()), vec!["plan.tier"]);
+
+        let report = expire_tenant_facts(&store, "default", now).unwrap();
+        assert_eq!(report.pools, 3);
+        assert_eq!(report.expired, vec![promo.fact_id, trial.fact_id.clone()]);
+        let stored = store.list_facts(&scope, FactFilter::default()).unwrap();
+        let stored = stored.iter().find(|fact| fact.fact_id == trial.fact_id).unwrap();
+        assert_eq!(stored.status, FactStatus::Deprecated);
+        assert!(expire_facts(&store, &scope, now).unwrap().is_empty());
+
+        assert_eq!(fact_keys(&store, request.clone()), vec!["plan.tier"]);
+        let mut looking_back = request;
+        looking_back.cues.valid_at = Some(last_week);
+        assert_eq!(
+            fact_keys(&store, looking_back),
+            vec!["plan.tier", "plan.trial", "promo.spring"]
+        );
+    }
+}
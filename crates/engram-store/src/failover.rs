@@ -0,0 +1,829 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::changelog::reapply_change;
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult, Suppression,
+    TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+#[derive(Debug, Clone)]
+pub struct FailoverOptions {
+    /// How long after a failure the primary is left alone; the first call after that
+    /// probes it again. [`FailoverStore::check_primary`] probes it right away.
+    pub check_interval: Duration,
+    /// Writes buffered on the standby for replay before further writes are refused
+    /// with [`StoreError::Overloaded`].
+    pub buffer_capacity: usize,
+}
+
+impl Default for FailoverOptions {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(5),
+            buffer_capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FailoverState {
+    /// Set while the standby serves calls.
+    down_since: Option<Instant>,
+    last_check: Option<Instant>,
+    /// Writes made on the standby since failing over, oldest first.
+    buffered: VecDeque<ChangeOp>,
+}
+
+/// Store wrapper that serves calls from `primary` and fails over to a warm `standby`
+/// when the primary stops answering, e.g. a Postgres primary with a promoted replica
+/// or a local SQLite fallback.
+///
+/// A call failing with [`StoreError::Unavailable`] or [`StoreError::Timeout`] marks
+/// the primary down and is retried on the standby; any other error, such as a
+/// constraint violation, is the call's own and returned as is. While the primary is
+/// down, reads and writes go to the standby and every write is buffered. Once
+/// `check_interval` has passed, the next call probes the primary; when it answers,
+/// the buffered writes are replayed on it in order and calls fail back. A write that
+/// timed out may have committed on the primary anyway, so replay skips appends the
+/// primary already holds. Writes the primary rejects during replay are logged and
+/// dropped.
+///
+/// Keeping the standby current while the primary is up is left to replication. Leases
+/// and the change log are served by whichever store is active and not replayed.
+#[derive(Debug)]
+pub struct FailoverStore<P, S> {
+    primary: P,
+    standby: S,
+    options: FailoverOptions,
+    state: Mutex<FailoverState>,
+}
+
+impl<P: Store, S: Store> FailoverStore<P, S> {
+    pub fn new(primary: P, standby: S, options: FailoverOptions) -> Self {
+        Self {
+            primary,
+            standby,
+            options,
+            state: Mutex::new(FailoverState::default()),
+        }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn standby(&self) -> &S {
+        &self.standby
+    }
+
+    /// Whether calls are currently served by the standby.
+    pub fn is_failed_over(&self) -> StoreResult<bool> {
+        Ok(self.lock()?.down_since.is_some())
+    }
+
+    /// Writes waiting to be replayed on the primary.
+    pub fn buffered_writes(&self) -> StoreResult<usize> {
+        Ok(self.lock()?.buffered.len())
+    }
+
+    /// Probes a failed primary now, replaying the buffered writes and failing back
+    /// when it answers. Returns whether the primary serves calls again.
+    pub fn check_primary(&self) -> StoreResult<bool> {
+        let mut state = self.lock()?;
+        if state.down_since.is_none() {
+            return Ok(true);
+        }
+        self.recover(&mut state)
+    }
+
+    fn lock(&self) -> StoreResult<MutexGuard<'_, FailoverState>> {
+        self.state.lock().map_err(|_| StoreError::Poisoned)
+    }
+
+    /// Whether calls go to the primary, probing a failed one when it is due.
+    fn primary_up(&self) -> StoreResult<bool> {
+        let mut state = self.lock()?;
+        if state.down_since.is_none() {
+            return Ok(true);
+        }
+        if state
+            .last_check
+            .is_some_and(|checked| checked.elapsed() < self.options.check_interval)
+        {
+            return Ok(false);
+        }
+        self.recover(&mut state)
+    }
+
+    fn recover(&self, state: &mut FailoverState) -> StoreResult<bool> {
+        state.last_check = Some(Instant::now());
+        if let Err(err) = self.primary.list_scopes(None, Some(1)) {
+            debug!("primary store still down: {}", err);
+            return Ok(false);
+        }
+        let replayed = state.buffered.len();
+        while let Some(op) = state.buffered.front() {
+            match reapply_change(&self.primary, op.clone()) {
+                Ok(()) => {}
+                Err(err) if is_outage(&err) => {
+                    debug!("replay on primary store failed, staying on standby: {}", err);
+                    return Ok(false);
+                }
+                Err(err) => warn!("primary store rejected a buffered write, dropping it: {}", err),
+            }
+            state.buffered.pop_front();
+        }
+        if let Some(down_since) = state.down_since.take() {
+            info!(
+                "primary store back after {:?}, replayed {} writes",
+                down_since.elapsed(),
+                replayed
+            );
+        }
+        Ok(true)
+    }
+
+    fn fail_over(&self, err: &StoreError) -> StoreResult<()> {
+        let mut state = self.lock()?;
+        if state.down_since.is_none() {
+            warn!("primary store failed, failing over to standby: {}", err);
+            state.down_since = Some(Instant::now());
+        }
+        state.last_check = Some(Instant::now());
+        Ok(())
+    }
+
+    fn read<T>(&self, read: impl Fn(&dyn Store) -> StoreResult<T>) -> StoreResult<T> {
+        if self.primary_up()? {
+            match read(&self.primary) {
+                Err(err) if is_outage(&err) => self.fail_over(&err)?,
+                result => return result,
+            }
+        }
+        read(&self.standby)
+    }
+
+    /// Runs `write` on the active store. On the standby, the writes `change` describes
+    /// (given the write's result) is buffered for replay.
+    fn write<T, C: IntoIterator<Item = ChangeOp>>(
+        &self,
+        write: impl Fn(&dyn Store) -> StoreResult<T>,
+        change: impl FnOnce(&T) -> C,
+    ) -> StoreResult<T> {
+        loop {
+            if self.primary_up()? {
+                match write(&self.primary) {
+                    Err(err) if is_outage(&err) => self.fail_over(&err)?,
+                    result => return result,
+                }
+            }
+            let mut state = self.lock()?;
+            if state.down_since.is_none() {
+                // Another call failed back in the meantime.
+                continue;
+            }
+            if state.buffered.len() >= self.options.buffer_capacity {
+                return Err(StoreError::Overloaded(format!(
+                    "failover buffer is full ({} writes pending)",
+                    state.buffered.len()
+                )));
+            }
+            let value = write(&self.standby)?;
+            state.buffered.extend(change(&value));
+            return Ok(value);
+        }
+    }
+}
+
+/// Errors that suggest the backend is unreachable rather than the call invalid.
+fn is_outage(err: &StoreError) -> bool {
+    matches!(err, StoreError::Unavailable(_) | StoreError::Timeout(_))
+}
+
+impl<P: Store, S: Store> Store for FailoverStore<P, S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.write(
+            |store| store.append_event(event.clone()),
+            |_| Some(ChangeOp::AppendEvent { event: event.clone() }),
+        )
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.write(
+            |store| store.append_events_bulk(events),
+            |_| {
+                let op = |event: &Event| ChangeOp::AppendEvent {
+                    event: event.clone(),
+                };
+                events.iter().map(op).collect::<Vec<_>>()
+            },
+        )
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.read(|store| store.list_events(scope, range.clone(), limit))
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.read(|store| store.list_events_page(scope, cursor, limit))
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.read(|store| store.get_events_since(scope, seq, limit))
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.read(|store| store.get_events_by_ids(scope, event_ids))
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.read(|store| store.find_events(scope, filter.clone()))
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.read(|store| store.search_events(scope, query))
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.read(|store| store.search_episodes(scope, query))
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.read(|store| store.acquire_lease(scope, ttl))
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.read(|store| store.renew_lease(lease, ttl))
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.read(|store| store.release_lease(lease))
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.read(|store| store.get_working_state(scope))
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.write(
+            |store| store.patch_working_state(scope, patch.clone()),
+            |state| {
+                Some(ChangeOp::PatchWorkingState {
+                    scope: scope.clone(),
+                    state: Box::new(state.clone()),
+                })
+            },
+        )
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let scopes: Vec<Scope> = patches.iter().map(|(scope, _)| scope.clone()).collect();
+        self.write(
+            |store| store.patch_working_states(patches.clone()),
+            |states| {
+                let op = |(scope, state): (&Scope, &WorkingState)| ChangeOp::PatchWorkingState {
+                    scope: scope.clone(),
+                    state: Box::new(state.clone()),
+                };
+                scopes.iter().zip(states).map(op).collect::<Vec<_>>()
+            },
+        )
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.read(|store| store.list_working_states(scope))
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.read(|store| store.get_stm(scope))
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.write(
+            |store| store.update_stm(scope, stm.clone()),
+            |_| {
+                Some(ChangeOp::UpdateStm {
+                    scope: scope.clone(),
+                    stm: stm.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.read(|store| store.list_facts(scope, filter.clone()))
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.write(
+            |store| store.upsert_fact(scope, fact.clone()),
+            |_| {
+                Some(ChangeOp::UpsertFact {
+                    scope: scope.clone(),
+                    fact: fact.clone(),
+                })
+            },
+        )
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.write(
+            |store| store.upsert_facts_bulk(scope, facts),
+            |_| {
+                let op = |fact: &Fact| ChangeOp::UpsertFact {
+                    scope: scope.clone(),
+                    fact: fact.clone(),
+                };
+                facts.iter().map(op).collect::<Vec<_>>()
+            },
+        )
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.read(|store| store.list_fact_history(scope, fact_key))
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.read(|store| store.list_episodes(scope, filter.clone()))
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.write(
+            |store| store.append_episode(scope, episode.clone()),
+            |_| {
+                Some(ChangeOp::AppendEpisode {
+                    scope: scope.clone(),
+                    episode: episode.clone(),
+                })
+            },
+        )
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.write(
+            |store| store.append_episodes_bulk(scope, episodes),
+            |_| {
+                let op = |episode: &Episode| ChangeOp::AppendEpisode {
+                    scope: scope.clone(),
+                    episode: episode.clone(),
+                };
+                episodes.iter().map(op).collect::<Vec<_>>()
+            },
+        )
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.read(|store| store.list_procedures(scope, task_type, limit))
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.write(
+            |store| store.upsert_procedure(scope, procedure.clone()),
+            |_| {
+                Some(ChangeOp::UpsertProcedure {
+                    scope: scope.clone(),
+                    procedure: procedure.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.read(|store| store.list_procedure_candidates(scope, filter.clone()))
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.read(|store| store.get_procedure_candidate(scope, candidate_id))
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.write(
+            |store| store.upsert_procedure_candidate(scope, candidate.clone()),
+            |_| {
+                Some(ChangeOp::UpsertProcedureCandidate {
+                    scope: scope.clone(),
+                    candidate: candidate.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.read(|store| store.list_insights(scope, filter.clone()))
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.write(
+            |store| store.append_insight(scope, insight.clone()),
+            |_| {
+                Some(ChangeOp::AppendInsight {
+                    scope: scope.clone(),
+                    insight: insight.clone(),
+                })
+            },
+        )
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.write(
+            |store| store.expire_insights(scope, expires_at),
+            |&expired| {
+                (expired > 0).then(|| ChangeOp::ExpireInsights {
+                    scope: scope.clone(),
+                    expires_at: expires_at.to_string(),
+                })
+            },
+        )
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.write(
+            |store| store.write_context_build(scope, packet.clone()),
+            |_| {
+                Some(ChangeOp::WriteContextBuild {
+                    scope: scope.clone(),
+                    packet: Box::new(packet.clone()),
+                })
+            },
+        )
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.read(|store| store.list_context_builds(scope, limit))
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.read(|store| store.list_context_build_summaries(scope, limit))
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.read(|store| store.tenant_activity(tenant_id, range.clone()))
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.read(|store| store.list_scopes(tenant_id, limit))
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.write(
+            |store| store.suppress_memory(scope, item.clone(), reason),
+            |_| {
+                Some(ChangeOp::SuppressMemory {
+                    scope: scope.clone(),
+                    item: item.clone(),
+                    reason: reason.to_string(),
+                })
+            },
+        )
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.write(
+            |store| store.unsuppress_memory(scope, item),
+            |&existed| {
+                existed.then(|| ChangeOp::UnsuppressMemory {
+                    scope: scope.clone(),
+                    item: item.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.read(|store| store.list_suppressions(scope))
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.write(
+            |store| store.evict_memory(scope, items),
+            |&evicted| {
+                (evicted > 0).then(|| ChangeOp::EvictMemory {
+                    scope: scope.clone(),
+                    items: items.to_vec(),
+                })
+            },
+        )
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.write(
+            |store| store.delete_record(scope, item, mode),
+            |&existed| {
+                existed.then(|| ChangeOp::DeleteRecord {
+                    scope: scope.clone(),
+                    item: item.clone(),
+                    mode,
+                })
+            },
+        )
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.write(
+            |store| store.clear_scope(scope, mode),
+            |&removed| {
+                (removed > 0).then(|| ChangeOp::ClearScope {
+                    scope: scope.clone(),
+                    mode,
+                })
+            },
+        )
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.read(|store| store.count_records(scope, kind))
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.read(|store| store.list_tombstones(scope, limit))
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.write(
+            |store| store.upsert_embeddings(scope, embeddings.clone()),
+            |_| {
+                Some(ChangeOp::UpsertEmbeddings {
+                    scope: scope.clone(),
+                    embeddings: embeddings.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.read(|store| store.list_embeddings(scope, model))
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.write(
+            |store| store.delete_embeddings(scope, model, items),
+            |&deleted| {
+                (deleted > 0).then(|| ChangeOp::DeleteEmbeddings {
+                    scope: scope.clone(),
+                    model: model.to_string(),
+                    items: items.to_vec(),
+                })
+            },
+        )
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.write(
+            |store| store.record_run_outcome(scope, outcome.clone()),
+            |_| {
+                Some(ChangeOp::RecordRunOutcome {
+                    scope: scope.clone(),
+                    outcome: outcome.clone(),
+                })
+            },
+        )
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.read(|store| store.list_run_outcomes(scope, limit))
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.write(
+            |store| store.set_source_credibility(tenant_id, credibility.clone()),
+            |_| {
+                Some(ChangeOp::SetSourceCredibility {
+                    tenant_id: tenant_id.to_string(),
+                    credibility: credibility.clone(),
+                })
+            },
+        )
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.read(|store| store.get_source_credibility(tenant_id))
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.write(
+            |store| store.set_user_locale(tenant_id, user_id, locale.clone()),
+            |_| {
+                Some(ChangeOp::SetUserLocale {
+                    tenant_id: tenant_id.to_string(),
+                    user_id: user_id.to_string(),
+                    locale: locale.clone(),
+                })
+            },
+        )
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.read(|store| store.get_user_locale(tenant_id, user_id))
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.read(|store| store.append_change(op.clone()))
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.read(|store| store.load_change_cursor(name))
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.read(|store| store.save_change_cursor(name, seq))
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.read(|store| store.list_changes(after_seq, limit))
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.read(|store| store.last_change_seq())
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.primary.metrics_snapshot().merge(self.standby.metrics_snapshot())
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.primary.capabilities().intersect(self.standby.capabilities())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore, SqliteStore};
+    use rusqlite::Connection;
+    use serde_json::json;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    /// A failover store over a SQLite primary in a fresh directory, returned with it.
+    fn sqlite_failover() -> (std::path::PathBuf, FailoverStore<SqliteStore, InMemoryStore>) {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("engram-failover-{}", nanos));
+        let primary = SqliteStore::new(dir.join("engram.db"))
+            .unwrap()
+            .with_statement_timeout(Duration::from_millis(50));
+        let options = FailoverOptions {
+            check_interval: Duration::ZERO,
+            ..FailoverOptions::default()
+        };
+        (dir, FailoverStore::new(primary, InMemoryStore::new(), options))
+    }
+
+    #[test]
+    fn fails_over_and_replays_buffered_writes() {
+        let (dir, store) = sqlite_failover();
+        let scope = sample_scope();
+
+        // Holding the write lock makes every primary write time out.
+        let writer = Connection::open(dir.join("engram.db")).unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        assert!(store.is_failed_over().unwrap());
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Lisbon"))).unwrap();
+        assert_eq!(store.buffered_writes().unwrap(), 2);
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+        assert!(!store.check_primary().unwrap());
+
+        writer.execute_batch("ROLLBACK").unwrap();
+        assert!(store.check_primary().unwrap());
+        assert!(!store.is_failed_over().unwrap());
+        assert_eq!(store.buffered_writes().unwrap(), 0);
+        let events = store.primary().list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(store.primary().list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn call_errors_do_not_fail_over() {
+        let (dir, store) = sqlite_failover();
+        let event = Event::new(sample_scope(), EventKind::Message, json!("hi"));
+        store.append_event(event.clone()).unwrap();
+
+        let duplicate = store.append_event(event);
+        assert!(matches!(duplicate, Err(StoreError::Storage(_))));
+        assert!(!store.is_failed_over().unwrap());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn replay_skips_writes_the_primary_committed_before_timing_out() {
+        let (dir, store) = sqlite_failover();
+        let scope = sample_scope();
+        let event = Event::new(scope.clone(), EventKind::Message, json!("hi"));
+
+        let writer = Connection::open(dir.join("engram.db")).unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        store.append_event(event.clone()).unwrap();
+        assert!(store.is_failed_over().unwrap());
+        writer.execute_batch("ROLLBACK").unwrap();
+        // As if the timed-out append had committed after all.
+        store.primary().append_event(event).unwrap();
+
+        assert!(store.check_primary().unwrap());
+        assert_eq!(store.buffered_writes().unwrap(), 0);
+        let events = store.primary().list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(events.len(), 1);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+
+use engram_types::MemoryPacket;
+use serde::{Deserialize, Serialize};
+
+/// Share of a claim's content words that must appear in one piece of packet memory
+/// for the claim to count as backed by it.
+const MIN_OVERLAP: f64 = 0.5;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "are", "was", "were", "but", "not", "you", "your", "our", "with",
+    "this", "that", "these", "those", "from", "have", "has", "had", "will", "would", "can",
+    "could", "should", "its", "it's", "they", "them", "their", "there", "then", "than", "which",
+    "what", "when", "who", "also", "into", "about", "been", "being", "some", "any", "all",
+    "sure", "okay", "yes", "thanks", "here", "let",
+];
+
+/// One sentence of a response and the packet evidence it maps to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroundedClaim {
+    pub text: String,
+    /// Byte offsets of the claim in the response.
+    pub start: usize,
+    pub end: usize,
+    /// Evidence ids backing the claim: explicit `[id]` markers that name packet
+    /// evidence, then the sources of packet memory the claim restates.
+    #[serde(default)]
+    pub citations: Vec<String>,
+}
+
+/// A span of the response that no packet evidence backs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncitedSpan {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What [`check_grounding`] found in a response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroundingReport {
+    pub claims: Vec<GroundedClaim>,
+    pub uncited: Vec<UncitedSpan>,
+    /// Share of claims with at least one citation; `1.0` for a response that makes
+    /// no claims.
+    pub grounded_ratio: f64,
+}
+
+impl GroundingReport {
+    pub fn is_grounded(&self) -> bool {
+        self.uncited.is_empty()
+    }
+}
+
+/// Checks an LLM response against the packet it was built from, so the caller can
+/// refuse or revise an answer before it reaches the user.
+///
+/// The response is split into sentences. A sentence is cited when it carries an
+/// `[id]` marker naming evidence in the packet, or when most of its content words
+/// appear in one quote, conversation turn, tool result, fact, episode, procedure or
+/// insight of the packet. Sentences without content words ("Sure!") are not claims.
+pub fn check_grounding(response: &str, packet: &MemoryPacket) -> GroundingReport {
+    let sources = packet_sources(packet);
+    let known_ids: HashSet<&str> = packet
+        .citations
+        .iter()
+        .map(|citation| citation.id.as_str())
+        .chain(sources.iter().flat_map(|source| source.ids.iter().map(String::as_str)))
+        .collect();
+
+    let mut claims: Vec<GroundedClaim> = Vec::new();
+    for (start, end) in sentence_spans(response) {
+        let text = &response[start..end];
+        let mut citations: Vec<String> = Vec::new();
+        for id in citation_markers(text) {
+            if known_ids.contains(id) && !citations.iter().any(|known| known == id) {
+                citations.push(id.to_string());
+            }
+        }
+        let words = content_words(&strip_markers(text));
+        if words.is_empty() {
+            // A trailing "[e1]" after the full stop belongs to the sentence before it.
+            if let Some(previous) = claims.last_mut() {
+                for id in citations {
+                    if !previous.citations.contains(&id) {
+                        previous.citations.push(id);
+                    }
+                }
+            }
+            continue;
+        }
+        for source in &sources {
+            let shared = words.iter().filter(|word| source.words.contains(*word)).count();
+            if shared as f64 / words.len() as f64 >= MIN_OVERLAP {
+                for id in &source.ids {
+                    if !citations.contains(id) {
+                        citations.push(id.clone());
+                    }
+                }
+            }
+        }
+        claims.push(GroundedClaim {
+            text: text.to_string(),
+            start,
+            end,
+            citations,
+        });
+    }
+
+    let uncited: Vec<UncitedSpan> = claims
+        .iter()
+        .filter(|claim| claim.citations.is_empty())
+        .map(|claim| UncitedSpan {
+            text: claim.text.clone(),
+            start: claim.start,
+            end: claim.end,
+        })
+        .collect();
+    let grounded_ratio = if claims.is_empty() {
+        1.0
+    } else {
+        (claims.len() - uncited.len()) as f64 / claims.len() as f64
+    };
+    GroundingReport {
+        claims,
+        uncited,
+        grounded_ratio,
+    }
+}
+
+struct Source {
+    ids: Vec<String>,
+    words: HashSet<String>,
+}
+
+fn packet_sources(packet: &MemoryPacket) -> Vec<Source> {
+    let mut sources = Vec::new();
+    let mut push = |ids: Vec<String>, text: &str| {
+        let words = content_words(text);
+        if !ids.is_empty() && !words.is_empty() {
+            sources.push(Source { ids, words });
+        }
+    };
+    let or_own = |sources: &[String], own: &str| {
+        if sources.is_empty() {
+            vec![own.to_string()]
+        } else {
+            sources.to_vec()
+        }
+    };
+
+    let short_term = &packet.short_term;
+    for quote in &short_term.key_quotes {
+        push(vec![quote.evidence_id.clone()], &quote.quote);
+    }
+    for turn in &short_term.conversation_window {
+        if let Some(evidence_id) = &turn.evidence_id {
+            push(vec![evidence_id.clone()], &turn.content);
+        }
+    }
+    for evidence in short_term
+        .last_tool_evidence
+        .iter()
+        .chain(short_term.working_state.tool_evidence.iter())
+    {
+        push(vec![evidence.evidence_id.clone()], &evidence.summary);
+    }
+    for citation in &packet.citations {
+        push(vec![citation.id.clone()], &citation.summary);
+    }
+
+    let long_term = &packet.long_term;
+    for fact in long_term.facts.iter().chain(long_term.preferences.iter()) {
+        let value = match &fact.value {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        push(
+            or_own(&fact.sources, &fact.fact_id),
+            &format!("{} {}", fact.fact_key, value),
+        );
+    }
+    for episode in &long_term.episodes {
+        let mut text = episode.summary.clone();
+        for highlight in &episode.highlights {
+            text.push(' ');
+            text.push_str(highlight);
+        }
+        push(or_own(&episode.sources, &episode.episode_id), &text);
+    }
+    for procedure in &long_term.procedures {
+        push(
+            or_own(&procedure.sources, &procedure.procedure_id),
+            &procedure.content.to_string(),
+        );
+    }
+
+    let insight = &packet.insight;
+    for item in insight
+        .hypotheses
+        .iter()
+        .chain(insight.strategy_sketches.iter())
+        .chain(insight.patterns.iter())
+    {
+        push(or_own(&item.sources, &item.id), &item.statement);
+    }
+    sources
+}
+
+/// Byte ranges of the response's sentences, trimmed of surrounding whitespace.
+fn sentence_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        let end = idx + ch.len_utf8();
+        let boundary = match ch {
+            '\n' | '。' | '！' | '？' => true,
+            '.' | '!' | '?' => chars.peek().is_none_or(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if boundary {
+            push_trimmed(text, start, end, &mut spans);
+            start = end;
+        }
+    }
+    push_trimmed(text, start, text.len(), &mut spans);
+    spans
+}
+
+fn push_trimmed(text: &str, start: usize, end: usize, spans: &mut Vec<(usize, usize)>) {
+    let segment = &text[start..end];
+    let trimmed = segment.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    let offset = start + (segment.len() - segment.trim_start().len());
+    spans.push((offset, offset + trimmed.len()));
+}
+
+/// Ids inside `[...]` markers, e.g. `e1` and `e2` in "shipped [e1][e2]".
+fn citation_markers(text: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find(']') else {
+            break;
+        };
+        for id in rest[open + 1..open + close].split(',') {
+            let id = id.trim();
+            if !id.is_empty() {
+                ids.push(id);
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    ids
+}
+
+fn strip_markers(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    for ch in text.chars() {
+        match ch {
+            '[' => depth += 1,
+            ']' if depth > 0 => depth -= 1,
+            _ if depth == 0 => stripped.push(ch),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+fn content_words(text: &str) -> HashSet<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| {
+            (word.chars().count() >= 3 || word.chars().any(|ch| ch.is_ascii_digit()))
+                && !STOPWORDS.contains(&word.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, Event, EventKind, InMemoryStore, Store};
+    use engram_types::{Fact, Purpose, Scope};
+    use serde_json::json;
+
+    #[test]
+    fn flags_claims_without_packet_evidence() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut event = Event::new(
+            scope.clone(),
+            EventKind::Message,
+            json!({ "role": "user", "content": "Please book the Lisbon flight for Friday" }),
+        );
+        event.event_id = "e1".to_string();
+        store.append_event(event).unwrap();
+        let mut fact = Fact::new("travel.seat", json!("aisle seat preferred"));
+        fact.sources = vec!["e0".to_string()];
+        store.upsert_fact(&scope, fact).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.policy.filter.planner.conversation_window = true;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let response = "Sure! I will book the Lisbon flight for Friday. \
+                        You prefer an aisle seat. Your hotel is the Ritz. [x9]";
+        let report = check_grounding(response, &packet);
+
+        assert_eq!(report.claims.len(), 3);
+        assert!(report.claims[0].citations.contains(&"e1".to_string()));
+        assert_eq!(report.claims[1].citations, vec!["e0"]);
+        assert_eq!(report.uncited.len(), 1);
+        let uncited = &report.uncited[0];
+        assert_eq!(&response[uncited.start..uncited.end], "Your hotel is the Ritz.");
+        assert!(!report.is_grounded());
+        assert!((report.grounded_ratio - 2.0 / 3.0).abs() < 1e-9);
+
+        let cited = check_grounding("Your hotel is the Ritz [e1].", &packet);
+        assert!(cited.is_grounded());
+    }
+}
@@ -0,0 +1,221 @@
+use chrono::{DateTime, Utc};
+use engram_types::{EvidenceRef, Scope, ValidationState};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{scope_matches, Event, EventKind, InsightFilter, Store, StoreError, StoreResult};
+
+/// Event kind under which hand-offs are journaled in the receiving run.
+pub const HANDOFF_EVENT_KIND: &str = "handoff";
+
+const MAX_HANDOFF_DECISIONS: usize = 10;
+const MAX_HANDOFF_EVIDENCE: usize = 10;
+const MAX_HANDOFF_OPEN_LOOPS: usize = 10;
+const MAX_HANDOFF_TEXT_CHARS: usize = 280;
+
+/// A compact summary of one run, passed to the agent that picks up its work.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffPacket {
+    pub from: Scope,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub goal: String,
+    /// The source session's rolling summary, if it had one.
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+    #[serde(default)]
+    pub tool_evidence: Vec<EvidenceRef>,
+    /// Work the source run left unfinished: its plan, its risks and its insights
+    /// that were never validated.
+    #[serde(default)]
+    pub open_loops: Vec<String>,
+}
+
+impl HandoffPacket {
+    /// Renders the packet as plain text, the form it takes in the receiving run's
+    /// conversation.
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("Hand-off from agent {}", self.from.agent_id)];
+        if !self.goal.is_empty() {
+            lines.push(format!("Goal: {}", self.goal));
+        }
+        if !self.summary.is_empty() {
+            lines.push(format!("Summary: {}", self.summary));
+        }
+        let mut section = |title: &str, items: Vec<String>| {
+            if !items.is_empty() {
+                lines.push(format!("{}:", title));
+                lines.extend(items.into_iter().map(|item| format!("- {}", item)));
+            }
+        };
+        section("Decisions", self.decisions.clone());
+        section(
+            "Evidence",
+            self.tool_evidence
+                .iter()
+                .map(|evidence| format!("[{}] {}", evidence.evidence_id, evidence.summary))
+                .collect(),
+        );
+        section("Open loops", self.open_loops.clone());
+        lines.join("\n")
+    }
+}
+
+/// Summarizes run `from` into a [`HandoffPacket`] and journals it as a `handoff`
+/// event in run `to`, whose `content` is the packet's text so that packets built for
+/// the receiving run quote it like any other message.
+///
+/// Each list keeps its most recent entries only, and long texts are clipped, so the
+/// hand-off stays small however long the source run was.
+pub fn build_handoff_packet<S: Store + ?Sized>(
+    store: &S,
+    from: &Scope,
+    to: &Scope,
+) -> StoreResult<HandoffPacket> {
+    if scope_matches(from, to) {
+        return Err(StoreError::InvalidInput(
+            "hand-off target must differ from the source run".to_string(),
+        ));
+    }
+
+    let state = store.get_working_state(from)?.unwrap_or_default();
+    let summary = store
+        .get_stm(from)?
+        .map(|stm| clip(&stm.rolling_summary))
+        .unwrap_or_default();
+    let unresolved = store.list_insights(
+        from,
+        InsightFilter {
+            validation_state: Some(vec![ValidationState::Unvalidated, ValidationState::Testing]),
+            limit: None,
+        },
+    )?;
+
+    let mut open_loops: Vec<String> = state.plan.iter().map(|step| clip(step)).collect();
+    open_loops.extend(state.risks.iter().map(|risk| clip(&format!("risk: {}", risk))));
+    open_loops.extend(
+        unresolved
+            .iter()
+            .map(|insight| clip(&format!("unverified: {}", insight.statement))),
+    );
+    open_loops.truncate(MAX_HANDOFF_OPEN_LOOPS);
+
+    let packet = HandoffPacket {
+        from: from.clone(),
+        created_at: Utc::now(),
+        goal: clip(&state.goal),
+        summary,
+        decisions: most_recent(&state.decisions, MAX_HANDOFF_DECISIONS)
+            .iter()
+            .map(|decision| clip(decision))
+            .collect(),
+        tool_evidence: most_recent(&state.tool_evidence, MAX_HANDOFF_EVIDENCE)
+            .iter()
+            .map(|evidence| EvidenceRef {
+                summary: clip(&evidence.summary),
+                ..evidence.clone()
+            })
+            .collect(),
+        open_loops,
+    };
+
+    let mut payload = serde_json::to_value(&packet)
+        .map_err(|err| StoreError::Storage(err.to_string()))?;
+    payload["content"] = packet.to_text().into();
+    let mut event = Event::new(
+        to.clone(),
+        EventKind::Custom(HANDOFF_EVENT_KIND.to_string()),
+        payload,
+    );
+    event.ts = packet.created_at;
+    event.tags = vec![format!("{}:{}", HANDOFF_EVENT_KIND, from.agent_id)];
+    store.append_event(event)?;
+    debug!(
+        "handed off run {} to run {} with {} open loops",
+        from.run_id,
+        to.run_id,
+        packet.open_loops.len()
+    );
+    Ok(packet)
+}
+
+fn most_recent<T>(items: &[T], limit: usize) -> &[T] {
+    &items[items.len().saturating_sub(limit)..]
+}
+
+fn clip(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_HANDOFF_TEXT_CHARS {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(MAX_HANDOFF_TEXT_CHARS).collect();
+    clipped.push('…');
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, WorkingStatePatch};
+    use engram_types::{InsightItem, InsightType};
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "researcher".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn hands_off_run_summary_into_target_run() {
+        let store = InMemoryStore::new();
+        let from = sample_scope();
+        let to = Scope {
+            agent_id: "writer".to_string(),
+            run_id: "run2".to_string(),
+            ..from.clone()
+        };
+        store
+            .patch_working_state(
+                &from,
+                WorkingStatePatch {
+                    goal: Some("Compare vendors".to_string()),
+                    plan: Some(vec!["draft the report".to_string()]),
+                    decisions: Some((0..15).map(|idx| format!("decision {}", idx)).collect()),
+                    tool_evidence: Some(vec![EvidenceRef {
+                        evidence_id: "e1".to_string(),
+                        summary: "x".repeat(1000),
+                        kind: "tool_result".to_string(),
+                    }]),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        store
+            .append_insight(&from, InsightItem::new(InsightType::Hypothesis, "vendor B is cheaper"))
+            .unwrap();
+
+        let packet = build_handoff_packet(&store, &from, &to).unwrap();
+        assert_eq!(packet.goal, "Compare vendors");
+        assert_eq!(packet.decisions.len(), MAX_HANDOFF_DECISIONS);
+        assert_eq!(packet.decisions.last().unwrap(), "decision 14");
+        assert_eq!(packet.tool_evidence[0].summary.chars().count(), MAX_HANDOFF_TEXT_CHARS + 1);
+        assert_eq!(
+            packet.open_loops,
+            vec!["draft the report", "unverified: vendor B is cheaper"]
+        );
+
+        let events = store.get_events_since(&to, 0, None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Custom(HANDOFF_EVENT_KIND.to_string()));
+        let content = events[0].payload["content"].as_str().unwrap();
+        assert!(content.starts_with("Hand-off from agent researcher\nGoal: Compare vendors"));
+
+        assert!(build_handoff_packet(&store, &from, &from).is_err());
+    }
+}
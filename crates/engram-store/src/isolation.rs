@@ -0,0 +1,552 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+/// Which agents of a user may read each other's long-term memory. Facts, episodes
+/// and events are always keyed by agent, so agents are isolated unless granted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentAccessPolicy {
+    /// Reader agent id to the agent ids whose facts and episodes it may read.
+    #[serde(default)]
+    pub grants: BTreeMap<String, BTreeSet<String>>,
+    /// Agents nobody else may read, whatever `grants` says.
+    #[serde(default)]
+    pub sealed: BTreeSet<String>,
+}
+
+impl AgentAccessPolicy {
+    pub fn grant(mut self, reader: impl Into<String>, owner: impl Into<String>) -> Self {
+        self.grants.entry(reader.into()).or_default().insert(owner.into());
+        self
+    }
+
+    pub fn seal(mut self, agent: impl Into<String>) -> Self {
+        self.sealed.insert(agent.into());
+        self
+    }
+
+    /// Agents other than `reader` whose memory `reader` may read.
+    pub fn readable_agents(&self, reader: &str) -> Vec<&str> {
+        self.grants
+            .get(reader)
+            .into_iter()
+            .flatten()
+            .filter(|owner| owner.as_str() != reader && !self.sealed.contains(*owner))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Store wrapper that enforces an [`AgentAccessPolicy`]: reads of facts, episodes
+/// and events by id also see the granted agents of the same user, with the reader's
+/// own facts winning on `fact_key`. Writes always stay in the writer's scope.
+#[derive(Debug)]
+pub struct IsolatingStore<S> {
+    inner: S,
+    policy: AgentAccessPolicy,
+}
+
+impl<S: Store> IsolatingStore<S> {
+    pub fn new(inner: S, policy: AgentAccessPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn policy(&self) -> &AgentAccessPolicy {
+        &self.policy
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn peer_scopes(&self, scope: &Scope) -> Vec<Scope> {
+        self.policy
+            .readable_agents(&scope.agent_id)
+            .into_iter()
+            .map(|agent_id| Scope {
+                agent_id: agent_id.to_string(),
+                ..scope.clone()
+            })
+            .collect()
+    }
+}
+
+impl<S: Store> Store for IsolatingStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.inner.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        let mut events = self.inner.get_events_by_ids(scope, event_ids)?;
+        for peer in self.peer_scopes(scope) {
+            let missing: Vec<String> = event_ids
+                .iter()
+                .filter(|id| !events.iter().any(|event| &event.event_id == *id))
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                break;
+            }
+            events.extend(self.inner.get_events_by_ids(&peer, &missing)?);
+        }
+        Ok(events)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    /// The reader's own matches come first, then each granted agent's; match scores
+    /// of different searches are not comparable.
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        let mut episodes = self.inner.search_episodes(scope, query)?;
+        for peer in self.peer_scopes(scope) {
+            episodes.extend(self.inner.search_episodes(&peer, query)?);
+        }
+        if let Some(limit) = query.limit {
+            episodes.truncate(limit);
+        }
+        Ok(episodes)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.inner.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.inner.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        let peers = self.peer_scopes(scope);
+        if peers.is_empty() {
+            return self.inner.list_facts(scope, filter);
+        }
+
+        let peer_filter = FactFilter {
+            limit: None,
+            include_shared: false,
+            ..filter.clone()
+        };
+        let mut facts = self.inner.list_facts(
+            scope,
+            FactFilter {
+                limit: None,
+                ..filter.clone()
+            },
+        )?;
+        let mut seen_keys: HashSet<String> = facts.iter().map(|f| f.fact_key.clone()).collect();
+        for peer in peers {
+            let peer_facts = self.inner.list_facts(&peer, peer_filter.clone())?;
+            let peer_keys: Vec<String> = peer_facts.iter().map(|f| f.fact_key.clone()).collect();
+            facts.extend(
+                peer_facts
+                    .into_iter()
+                    .filter(|fact| !seen_keys.contains(&fact.fact_key)),
+            );
+            seen_keys.extend(peer_keys);
+        }
+
+        facts.sort_by(|a, b| {
+            a.fact_key
+                .cmp(&b.fact_key)
+                .then_with(|| a.fact_id.cmp(&b.fact_id))
+        });
+        if let Some(limit) = filter.limit {
+            facts.truncate(limit);
+        }
+        Ok(facts)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.inner.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        let peers = self.peer_scopes(scope);
+        if peers.is_empty() {
+            return self.inner.list_episodes(scope, filter);
+        }
+
+        let unlimited = EpisodeFilter {
+            limit: None,
+            ..filter.clone()
+        };
+        let mut episodes = self.inner.list_episodes(scope, unlimited.clone())?;
+        for peer in peers {
+            episodes.extend(self.inner.list_episodes(&peer, unlimited.clone())?);
+        }
+
+        episodes.sort_by(|a, b| {
+            a.time_range
+                .start
+                .cmp(&b.time_range.start)
+                .then_with(|| a.episode_id.cmp(&b.episode_id))
+        });
+        if let Some(limit) = filter.limit {
+            episodes.truncate(limit);
+        }
+        Ok(episodes)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.inner.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use serde_json::json;
+
+    fn scope_for(agent_id: &str) -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: agent_id.to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_only_granted_agents() {
+        let policy = AgentAccessPolicy::default()
+            .grant("planner", "finance")
+            .grant("planner", "health")
+            .grant("finance", "health")
+            .seal("health");
+        let store = IsolatingStore::new(InMemoryStore::new(), policy);
+        for (agent, key) in [("planner", "plan.step"), ("finance", "budget"), ("health", "diagnosis")] {
+            store
+                .upsert_fact(&scope_for(agent), Fact::new(key, json!(agent)))
+                .unwrap();
+        }
+        store
+            .upsert_fact(&scope_for("finance"), Fact::new("plan.step", json!("finance")))
+            .unwrap();
+
+        let keys = |agent: &str| -> Vec<(String, serde_json::Value)> {
+            store
+                .list_facts(&scope_for(agent), FactFilter::default())
+                .unwrap()
+                .into_iter()
+                .map(|fact| (fact.fact_key, fact.value))
+                .collect()
+        };
+        assert_eq!(
+            keys("planner"),
+            vec![
+                ("budget".to_string(), json!("finance")),
+                ("plan.step".to_string(), json!("planner")),
+            ]
+        );
+        assert_eq!(keys("finance").len(), 2);
+        assert_eq!(keys("health"), vec![("diagnosis".to_string(), json!("health"))]);
+    }
+}
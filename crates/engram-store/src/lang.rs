@@ -0,0 +1,586 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::composer::parse_event_payload;
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+/// Detects the language of memory text for [`LanguageTaggingStore`].
+pub trait LanguageDetector: Send + Sync {
+    /// A language tag such as `en` or `pt-BR`, or `None` when the text doesn't tell.
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+/// Detects languages written in their own script (Chinese, Japanese, Korean, Russian,
+/// Greek, Arabic, Hebrew, Thai, Hindi) from the script most letters are in. Text in
+/// Latin script is left undetected; plug in a statistical detector to tell those apart.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScriptDetector;
+
+impl LanguageDetector for ScriptDetector {
+    fn detect(&self, text: &str) -> Option<String> {
+        let mut counts = [0usize; 10];
+        let mut letters = 0usize;
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            letters += 1;
+            let script = match c as u32 {
+                0x3040..=0x30FF => 0,
+                0x4E00..=0x9FFF | 0x3400..=0x4DBF => 1,
+                0xAC00..=0xD7AF | 0x1100..=0x11FF => 2,
+                0x0400..=0x04FF => 3,
+                0x0370..=0x03FF => 4,
+                0x0600..=0x06FF => 5,
+                0x0590..=0x05FF => 6,
+                0x0E00..=0x0E7F => 7,
+                0x0900..=0x097F => 8,
+                _ => 9,
+            };
+            counts[script] += 1;
+        }
+        if letters == 0 {
+            return None;
+        }
+        // Japanese mixes kana with Han characters; any kana makes Han text Japanese.
+        if counts[0] > 0 && counts[0] + counts[1] > letters / 2 {
+            return Some("ja".to_string());
+        }
+        let (script, count) = counts[1..9]
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, count)| **count)?;
+        if *count * 2 <= letters {
+            return None;
+        }
+        let lang = ["zh", "ko", "ru", "el", "ar", "he", "th", "hi"][script];
+        Some(lang.to_string())
+    }
+}
+
+/// What recall does with memories in another language than
+/// [`RecallCues::lang`](crate::RecallCues::lang).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LangMode {
+    /// Recall them after memories in the conversation language, and drop a fact when
+    /// another fact with the same key is in the conversation language.
+    #[default]
+    Prefer,
+    /// Leave them out.
+    Filter,
+}
+
+/// Whether a memory tagged `lang` suits a conversation in `wanted`. Tags compare by
+/// their primary subtag, ignoring case (`pt-BR` suits `pt`); untagged memories suit
+/// every language.
+pub fn lang_matches(lang: Option<&str>, wanted: &str) -> bool {
+    let primary = |tag: &str| tag.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    lang.is_none_or(|lang| primary(lang) == primary(wanted))
+}
+
+/// Applies `mode` to recalled facts for a conversation in `lang`. Under
+/// [`LangMode::Prefer`] the facts are reordered, matching ones first, but otherwise kept
+/// in order.
+pub(crate) fn select_facts_by_lang(facts: &mut Vec<Fact>, lang: &str, mode: LangMode) {
+    let matches = |fact: &Fact| lang_matches(fact.lang.as_deref(), lang);
+    match mode {
+        LangMode::Filter => facts.retain(matches),
+        LangMode::Prefer => {
+            let covered: HashSet<String> = facts
+                .iter()
+                .filter(|fact| matches(fact))
+                .map(|fact| fact.fact_key.clone())
+                .collect();
+            facts.retain(|fact| matches(fact) || !covered.contains(&fact.fact_key));
+            facts.sort_by_key(|fact| !matches(fact));
+        }
+    }
+}
+
+/// Store wrapper that tags events, facts and episodes with the language a
+/// [`LanguageDetector`] finds in their text, unless the writer already set `lang`.
+///
+/// The text is a message's content (or a string payload) for events, a string value
+/// for facts and the summary for episodes; memories without text stay untagged.
+pub struct LanguageTaggingStore<S> {
+    inner: S,
+    detector: Arc<dyn LanguageDetector>,
+}
+
+impl<S> std::fmt::Debug for LanguageTaggingStore<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanguageTaggingStore").finish_non_exhaustive()
+    }
+}
+
+impl<S: Store> LanguageTaggingStore<S> {
+    pub fn new(inner: S, detector: Arc<dyn LanguageDetector>) -> Self {
+        Self { inner, detector }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn detect(&self, lang: &mut Option<String>, text: Option<&str>) {
+        if lang.is_none()
+            && let Some(text) = text.filter(|text| !text.trim().is_empty())
+        {
+            *lang = self.detector.detect(text);
+        }
+    }
+}
+
+impl<S: Store> Store for LanguageTaggingStore<S> {
+    fn append_event(&self, mut event: Event) -> StoreResult<()> {
+        let content = match parse_event_payload(&event.payload) {
+            Some((content, _)) => Some(content),
+            None => event.payload.as_str().map(str::to_string),
+        };
+        self.detect(&mut event.lang, content.as_deref());
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        let mut events = events.to_vec();
+        for event in &mut events {
+            let content = match parse_event_payload(&event.payload) {
+                Some((content, _)) => Some(content),
+                None => event.payload.as_str().map(str::to_string),
+            };
+            self.detect(&mut event.lang, content.as_deref());
+        }
+        self.inner.append_events_bulk(&events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.inner.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.inner.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.inner.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, mut fact: Fact) -> StoreResult<()> {
+        self.detect(&mut fact.lang, fact.value.as_str());
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        let mut facts = facts.to_vec();
+        for fact in &mut facts {
+            self.detect(&mut fact.lang, fact.value.as_str());
+        }
+        self.inner.upsert_facts_bulk(scope, &facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.inner.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, mut episode: Episode) -> StoreResult<()> {
+        self.detect(&mut episode.lang, Some(&episode.summary));
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        let mut episodes = episodes.to_vec();
+        for episode in &mut episodes {
+            self.detect(&mut episode.lang, Some(&episode.summary));
+        }
+        self.inner.append_episodes_bulk(scope, &episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, EventKind, InMemoryStore, RecallCues};
+    use engram_types::Purpose;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn detects_languages_and_recalls_the_conversation_language() {
+        let store = LanguageTaggingStore::new(InMemoryStore::new(), Arc::new(ScriptDetector));
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let message = |content: &str| {
+            Event::new(
+                scope.clone(),
+                EventKind::Message,
+                json!({ "role": "user", "content": content }),
+            )
+        };
+        store.append_event(message("Привет, как дела?")).unwrap();
+        store.append_event(message("hello there")).unwrap();
+        let events = store.list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        let langs: Vec<Option<&str>> = events.iter().map(|event| event.lang.as_deref()).collect();
+        assert_eq!(langs, vec![Some("ru"), None]);
+        assert_eq!(ScriptDetector.detect("東京へ行きます").as_deref(), Some("ja"));
+        assert_eq!(ScriptDetector.detect("我住在北京").as_deref(), Some("zh"));
+
+        store.upsert_fact(&scope, Fact::new("greeting", json!("Добрый день"))).unwrap();
+        let mut english = Fact::new("greeting", json!("Good afternoon"));
+        english.lang = Some("en-GB".to_string());
+        store.upsert_fact(&scope, english).unwrap();
+        store.upsert_fact(&scope, Fact::new("city", json!("Москва"))).unwrap();
+        store.upsert_fact(&scope, Fact::new("age", json!(41))).unwrap();
+        store.append_episode(&scope, Episode::new("Обсудили поездку")).unwrap();
+        store.append_episode(&scope, Episode::new("Planned the trip")).unwrap();
+        let mut german = Episode::new("Reise geplant");
+        german.lang = Some("de".to_string());
+        store.append_episode(&scope, german).unwrap();
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.cues = RecallCues {
+            lang: Some("en".to_string()),
+            ..RecallCues::default()
+        };
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        let facts: Vec<(&str, &Value)> = packet
+            .long_term
+            .facts
+            .iter()
+            .map(|fact| (fact.fact_key.as_str(), &fact.value))
+            .collect();
+        assert_eq!(
+            facts,
+            vec![
+                ("age", &json!(41)),
+                ("city", &json!("Москва")),
+                ("greeting", &json!("Good afternoon")),
+            ]
+        );
+        let summaries: Vec<&str> = packet
+            .long_term
+            .episodes
+            .iter()
+            .map(|episode| episode.summary.as_str())
+            .collect();
+        assert_eq!(summaries[0], "Planned the trip");
+        assert_eq!(summaries.len(), 3);
+
+        request.policy.lang_mode = LangMode::Filter;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let keys: Vec<&str> = packet
+            .long_term
+            .facts
+            .iter()
+            .map(|fact| fact.fact_key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["age", "greeting"]);
+        assert_eq!(packet.long_term.episodes.len(), 1);
+        assert_eq!(packet.meta.cues.get("lang"), Some(&json!("en")));
+    }
+}
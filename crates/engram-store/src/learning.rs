@@ -0,0 +1,407 @@
+use chrono::Utc;
+use engram_types::{CandidateStatus, Episode, JsonMap, Procedure, ProcedureCandidate, Scope};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
+
+use crate::outcome::{episode_run, run_signals};
+use crate::{EpisodeFilter, Store, StoreError, StoreResult, Summarizer};
+
+#[derive(Debug, Clone)]
+pub struct ProcedureLearningOptions {
+    /// Task type of the learned procedure; episodes must carry it as a tag.
+    pub task_type: String,
+    /// An episode counts as successful when it carries any of these tags.
+    pub success_tags: Vec<String>,
+    /// When the episode's run has a recorded [`RunOutcome`](crate::RunOutcome), its
+    /// signal must also reach this; a run scored poorly after the fact is not learned
+    /// from even if its episode says `outcome:success`.
+    pub min_outcome_signal: f64,
+    pub min_episodes: usize,
+    pub max_episodes: usize,
+    pub summary_budget: usize,
+    pub max_steps: usize,
+    pub priority: i32,
+}
+
+impl ProcedureLearningOptions {
+    pub fn new(task_type: impl Into<String>) -> Self {
+        Self {
+            task_type: task_type.into(),
+            success_tags: vec!["outcome:success".to_string()],
+            min_outcome_signal: 0.7,
+            min_episodes: 2,
+            max_episodes: 20,
+            summary_budget: 256,
+            max_steps: 10,
+            priority: 0,
+        }
+    }
+}
+
+/// Returns the most recent episodes tagged with the task type and a success tag whose
+/// run outcome, if recorded, reaches `min_outcome_signal`, oldest first.
+pub fn select_successful_episodes<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    options: &ProcedureLearningOptions,
+) -> StoreResult<Vec<Episode>> {
+    let outcomes = store.list_run_outcomes(scope, None)?;
+    let signals = run_signals(&outcomes);
+    let mut episodes: Vec<Episode> = store
+        .list_episodes(
+            scope,
+            EpisodeFilter {
+                tags: vec![options.task_type.clone()],
+                ..EpisodeFilter::default()
+            },
+        )?
+        .into_iter()
+        .filter(|episode| is_successful(episode, options))
+        .filter(|episode| {
+            episode_run(episode)
+                .and_then(|run_id| signals.get(run_id))
+                .is_none_or(|signal| *signal >= options.min_outcome_signal)
+        })
+        .collect();
+
+    episodes.sort_by(|a, b| {
+        a.time_range
+            .start
+            .cmp(&b.time_range.start)
+            .then_with(|| a.episode_id.cmp(&b.episode_id))
+    });
+    if episodes.len() > options.max_episodes {
+        episodes = episodes.split_off(episodes.len() - options.max_episodes);
+    }
+    Ok(episodes)
+}
+
+/// Builds a pending candidate from `episodes` without touching any store.
+pub fn synthesize_procedure_candidate(
+    episodes: &[Episode],
+    summarizer: &dyn Summarizer,
+    options: &ProcedureLearningOptions,
+) -> StoreResult<ProcedureCandidate> {
+    if options.task_type.is_empty() {
+        return Err(StoreError::InvalidInput("task_type is required".to_string()));
+    }
+    if episodes.len() < options.min_episodes.max(1) {
+        return Err(StoreError::InvalidInput(format!(
+            "procedure learning needs at least {} successful episodes, got {}",
+            options.min_episodes.max(1),
+            episodes.len()
+        )));
+    }
+    if let Some(episode) = episodes.iter().find(|e| !is_successful(e, options)) {
+        return Err(StoreError::InvalidInput(format!(
+            "episode {} is not tagged as a successful {} episode",
+            episode.episode_id, options.task_type
+        )));
+    }
+
+    let inputs: Vec<String> = episodes.iter().map(episode_to_input).collect();
+    let summary = summarizer.summarize(&inputs, options.summary_budget)?;
+    let summary = summary.trim().to_string();
+    if summary.is_empty() {
+        return Err(StoreError::InvalidInput(
+            "summarizer returned an empty summary".to_string(),
+        ));
+    }
+
+    let source_episodes: Vec<String> = episodes.iter().map(|e| e.episode_id.clone()).collect();
+    let mut evidence = Vec::new();
+    let mut seen = HashSet::new();
+    for source in episodes.iter().flat_map(|e| e.sources.iter()) {
+        if seen.insert(source.as_str()) {
+            evidence.push(source.clone());
+        }
+    }
+
+    let candidate_id = candidate_id_for(&options.task_type, &source_episodes);
+    let mut applicability = JsonMap::new();
+    applicability.insert("task_type".to_string(), json!(options.task_type));
+    applicability.insert("success_tags".to_string(), json!(options.success_tags));
+
+    Ok(ProcedureCandidate {
+        candidate_id: candidate_id.clone(),
+        procedure: Procedure {
+            procedure_id: candidate_id,
+            task_type: options.task_type.clone(),
+            content: json!({
+                "summary": summary,
+                "steps": common_steps(episodes, options.max_steps),
+                "learned_from": source_episodes.len(),
+            }),
+            priority: options.priority,
+            sources: source_episodes.clone(),
+            applicability,
+        },
+        source_episodes,
+        evidence,
+        status: CandidateStatus::Pending,
+        created_at: Utc::now(),
+        reviewed_at: None,
+        reviewer: String::new(),
+        review_note: String::new(),
+    })
+}
+
+/// Synthesizes a candidate from the scope's successful episodes and queues it for review.
+///
+/// Returns `None` when fewer than `min_episodes` successful episodes exist. A candidate
+/// already reviewed for the same episode set is returned as-is instead of being re-queued.
+pub fn propose_procedure<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    summarizer: &dyn Summarizer,
+    options: &ProcedureLearningOptions,
+) -> StoreResult<Option<ProcedureCandidate>> {
+    let episodes = select_successful_episodes(store, scope, options)?;
+    if episodes.len() < options.min_episodes.max(1) {
+        debug!(
+            "Only {} successful episodes for task type {}, skipping procedure learning",
+            episodes.len(),
+            options.task_type
+        );
+        return Ok(None);
+    }
+
+    let source_episodes: Vec<String> = episodes.iter().map(|e| e.episode_id.clone()).collect();
+    let candidate_id = candidate_id_for(&options.task_type, &source_episodes);
+    if let Some(existing) = store.get_procedure_candidate(scope, &candidate_id)?
+        && existing.status != CandidateStatus::Pending
+    {
+        return Ok(Some(existing));
+    }
+
+    let candidate = synthesize_procedure_candidate(&episodes, summarizer, options)?;
+    store.upsert_procedure_candidate(scope, candidate.clone())?;
+    info!(
+        "Queued procedure candidate {} from {} episodes",
+        candidate.candidate_id,
+        candidate.source_episodes.len()
+    );
+    Ok(Some(candidate))
+}
+
+/// Upserts the candidate's procedure and marks the candidate approved.
+pub fn approve_procedure_candidate<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    candidate_id: &str,
+    reviewer: &str,
+    note: &str,
+) -> StoreResult<Procedure> {
+    let mut candidate = load_pending_candidate(store, scope, candidate_id)?;
+    store.upsert_procedure(scope, candidate.procedure.clone())?;
+
+    candidate.status = CandidateStatus::Approved;
+    candidate.reviewed_at = Some(Utc::now());
+    candidate.reviewer = reviewer.to_string();
+    candidate.review_note = note.to_string();
+    let procedure = candidate.procedure.clone();
+    store.upsert_procedure_candidate(scope, candidate)?;
+    Ok(procedure)
+}
+
+pub fn reject_procedure_candidate<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    candidate_id: &str,
+    reviewer: &str,
+    note: &str,
+) -> StoreResult<ProcedureCandidate> {
+    let mut candidate = load_pending_candidate(store, scope, candidate_id)?;
+    candidate.status = CandidateStatus::Rejected;
+    candidate.reviewed_at = Some(Utc::now());
+    candidate.reviewer = reviewer.to_string();
+    candidate.review_note = note.to_string();
+    store.upsert_procedure_candidate(scope, candidate.clone())?;
+    Ok(candidate)
+}
+
+fn load_pending_candidate<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    candidate_id: &str,
+) -> StoreResult<ProcedureCandidate> {
+    let candidate = store
+        .get_procedure_candidate(scope, candidate_id)?
+        .ok_or(StoreError::NotFound)?;
+    if candidate.status != CandidateStatus::Pending {
+        return Err(StoreError::InvalidInput(format!(
+            "procedure candidate {} was already reviewed",
+            candidate_id
+        )));
+    }
+    Ok(candidate)
+}
+
+fn is_successful(episode: &Episode, options: &ProcedureLearningOptions) -> bool {
+    episode.tags.iter().any(|t| t == &options.task_type)
+        && episode.tags.iter().any(|t| options.success_tags.contains(t))
+}
+
+fn episode_to_input(episode: &Episode) -> String {
+    let mut input = episode.summary.clone();
+    for highlight in &episode.highlights {
+        input.push_str("\n- ");
+        input.push_str(highlight);
+    }
+    input
+}
+
+// Highlights shared by the most episodes come first; ties keep first-seen order.
+fn common_steps(episodes: &[Episode], max_steps: usize) -> Vec<Value> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for episode in episodes {
+        let mut seen = HashSet::new();
+        for highlight in &episode.highlights {
+            let highlight = highlight.trim();
+            if highlight.is_empty() || !seen.insert(highlight) {
+                continue;
+            }
+            let order = counts.len();
+            counts.entry(highlight).or_insert((0, order)).0 += 1;
+        }
+    }
+
+    let mut steps: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+    steps.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.1 .1.cmp(&b.1 .1)));
+    steps
+        .into_iter()
+        .take(max_steps)
+        .map(|(step, _)| json!(step))
+        .collect()
+}
+
+// Stable id so re-proposing the same episode set updates the same candidate.
+fn candidate_id_for(task_type: &str, episode_ids: &[String]) -> String {
+    let mut ids: Vec<&str> = episode_ids.iter().map(String::as_str).collect();
+    ids.sort_unstable();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for id in ids {
+        for byte in id.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("learned-{}-{:016x}", task_type, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, ProcedureCandidateFilter};
+    use chrono::Duration;
+    use engram_types::{CompressionLevel, TimeRange};
+
+    struct JoinSummarizer;
+
+    impl Summarizer for JoinSummarizer {
+        fn summarize(&self, inputs: &[String], _budget: usize) -> StoreResult<String> {
+            Ok(inputs
+                .iter()
+                .filter_map(|input| input.lines().next())
+                .collect::<Vec<_>>()
+                .join("; "))
+        }
+    }
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    fn episode(id: &str, minutes_ago: i64, tags: &[&str], highlights: &[&str]) -> Episode {
+        Episode {
+            episode_id: id.to_string(),
+            time_range: TimeRange {
+                start: Utc::now() - Duration::minutes(minutes_ago),
+                end: None,
+            },
+            summary: format!("summary {}", id),
+            highlights: highlights.iter().map(|h| h.to_string()).collect(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            entities: Vec::new(),
+            sources: vec![format!("evt-{}", id)],
+            compression_level: CompressionLevel::Raw,
+            recency_score: None,
+            lang: None,
+        }
+    }
+
+    #[test]
+    fn learns_procedure_through_approval_queue() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let options = ProcedureLearningOptions::new("deploy");
+
+        for ep in [
+            episode("ep1", 30, &["deploy", "outcome:success"], &["run tests", "tag release"]),
+            episode("ep2", 20, &["deploy", "outcome:failure"], &["skip tests"]),
+            episode("ep3", 10, &["deploy", "outcome:success"], &["run tests", "notify"]),
+            episode("ep4", 5, &["triage", "outcome:success"], &["label issue"]),
+        ] {
+            store.append_episode(&scope, ep).unwrap();
+        }
+
+        let candidate = propose_procedure(&store, &scope, &JoinSummarizer, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(candidate.status, CandidateStatus::Pending);
+        assert_eq!(candidate.source_episodes, vec!["ep1", "ep3"]);
+        assert_eq!(candidate.evidence, vec!["evt-ep1", "evt-ep3"]);
+        assert_eq!(candidate.procedure.sources, candidate.source_episodes);
+        assert_eq!(candidate.procedure.content["summary"], "summary ep1; summary ep3");
+        assert_eq!(candidate.procedure.content["steps"][0], "run tests");
+        assert!(store.list_procedures(&scope, "deploy", None).unwrap().is_empty());
+
+        let pending = store
+            .list_procedure_candidates(
+                &scope,
+                ProcedureCandidateFilter {
+                    status: Some(vec![CandidateStatus::Pending]),
+                    ..ProcedureCandidateFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let procedure =
+            approve_procedure_candidate(&store, &scope, &candidate.candidate_id, "ops", "lgtm")
+                .unwrap();
+        assert_eq!(procedure.procedure_id, candidate.candidate_id);
+        assert_eq!(store.list_procedures(&scope, "deploy", None).unwrap().len(), 1);
+
+        let reviewed = store
+            .get_procedure_candidate(&scope, &candidate.candidate_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reviewed.status, CandidateStatus::Approved);
+        assert_eq!(reviewed.reviewer, "ops");
+        assert!(
+            reject_procedure_candidate(&store, &scope, &candidate.candidate_id, "ops", "")
+                .is_err()
+        );
+
+        let again = propose_procedure(&store, &scope, &JoinSummarizer, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.status, CandidateStatus::Approved);
+
+        let too_strict = ProcedureLearningOptions {
+            min_episodes: 3,
+            ..ProcedureLearningOptions::new("deploy")
+        };
+        assert!(propose_procedure(&store, &scope, &JoinSummarizer, &too_strict)
+            .unwrap()
+            .is_none());
+    }
+}
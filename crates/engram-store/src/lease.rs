@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use engram_types::{new_ulid, Scope};
+use serde::{Deserialize, Serialize};
+
+use crate::{RunKey, StoreError, StoreResult};
+
+/// The exclusive right to run consolidation or end-of-run processing for one run
+/// scope, granted by [`Store::acquire_lease`](crate::Store::acquire_lease).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub scope: Scope,
+    /// Identifies this grant; renewing or releasing with another token does nothing.
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Leases held by this process. `H` is whatever keeps the backend's own lock alive,
+/// such as the connection holding a Postgres advisory lock; in-process stores need
+/// nothing beyond the table itself.
+pub(crate) struct LeaseTable<H = ()> {
+    held: Mutex<HashMap<RunKey, Held<H>>>,
+}
+
+struct Held<H> {
+    token: String,
+    expires_at: DateTime<Utc>,
+    holder: H,
+}
+
+impl<H> Default for LeaseTable<H> {
+    fn default() -> Self {
+        Self {
+            held: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<H> std::fmt::Debug for LeaseTable<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let held = self.held.lock().map(|held| held.len()).unwrap_or_default();
+        f.debug_struct("LeaseTable").field("held", &held).finish()
+    }
+}
+
+impl<H> LeaseTable<H> {
+    /// Grants a lease on `scope` unless this process holds an unexpired one. Expired
+    /// leases are released through `unlock` first. `lock` takes the backend's lock
+    /// and returns its holder, or `None` when another process has it.
+    pub(crate) fn acquire(
+        &self,
+        scope: &Scope,
+        ttl: Duration,
+        lock: impl FnOnce() -> StoreResult<Option<H>>,
+        mut unlock: impl FnMut(H) -> StoreResult<()>,
+    ) -> StoreResult<Option<Lease>> {
+        let expires_at = expiry(ttl)?;
+        let mut held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+        let now = Utc::now();
+        let expired: Vec<RunKey> = held
+            .iter()
+            .filter(|(_, lease)| lease.expires_at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            if let Some(lease) = held.remove(&key) {
+                unlock(lease.holder)?;
+            }
+        }
+
+        let key = RunKey::from(scope);
+        if held.contains_key(&key) {
+            return Ok(None);
+        }
+        let Some(holder) = lock()? else {
+            return Ok(None);
+        };
+        let token = new_ulid();
+        held.insert(
+            key,
+            Held {
+                token: token.clone(),
+                expires_at,
+                holder,
+            },
+        );
+        Ok(Some(Lease {
+            scope: scope.clone(),
+            token,
+            expires_at,
+        }))
+    }
+
+    /// Extends a lease this process still holds to `ttl` from now. A lease that
+    /// expired but was not yet taken over can still be renewed.
+    pub(crate) fn renew(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let expires_at = expiry(ttl)?;
+        let mut held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+        match held.get_mut(&RunKey::from(&lease.scope)) {
+            Some(current) if current.token == lease.token => {
+                current.expires_at = expires_at;
+                Ok(Some(Lease {
+                    expires_at,
+                    ..lease.clone()
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Forgets the lease and returns its holder for the backend to unlock, or `None`
+    /// when the lease is no longer held.
+    pub(crate) fn release(&self, lease: &Lease) -> StoreResult<Option<H>> {
+        let mut held = self.held.lock().map_err(|_| StoreError::Poisoned)?;
+        let key = RunKey::from(&lease.scope);
+        if held.get(&key).is_none_or(|current| current.token != lease.token) {
+            return Ok(None);
+        }
+        Ok(held.remove(&key).map(|current| current.holder))
+    }
+}
+
+fn expiry(ttl: Duration) -> StoreResult<DateTime<Utc>> {
+    if ttl.is_zero() {
+        return Err(StoreError::InvalidInput(
+            "lease ttl must be positive".to_string(),
+        ));
+    }
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+        .ok_or_else(|| StoreError::InvalidInput("lease ttl is too long".to_string()))
+}
+
+/// A stable 64-bit key for the scope's database lock, the same in every process.
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+pub(crate) fn lease_lock_key(scope: &Scope) -> i64 {
+    // FNV-1a: std's hashers are not guaranteed to agree across builds.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [
+        &scope.tenant_id,
+        &scope.user_id,
+        &scope.agent_id,
+        &scope.session_id,
+        &scope.run_id,
+    ] {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, Store};
+
+    #[test]
+    fn one_lease_per_scope_until_released_or_expired() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let other = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+
+        let lease = store.acquire_lease(&scope, Duration::from_secs(60)).unwrap().unwrap();
+        assert!(store.acquire_lease(&scope, Duration::from_secs(60)).unwrap().is_none());
+        assert!(store.acquire_lease(&other, Duration::from_secs(60)).unwrap().is_some());
+
+        let renewed = store.renew_lease(&lease, Duration::from_millis(1)).unwrap().unwrap();
+        assert_eq!(renewed.token, lease.token);
+        std::thread::sleep(Duration::from_millis(5));
+        let next = store.acquire_lease(&scope, Duration::from_secs(60)).unwrap().unwrap();
+        assert!(store.renew_lease(&lease, Duration::from_secs(60)).unwrap().is_none());
+        assert!(!store.release_lease(&lease).unwrap());
+        assert!(store.release_lease(&next).unwrap());
+        assert!(store.acquire_lease(&scope, Duration::ZERO).is_err());
+    }
+}
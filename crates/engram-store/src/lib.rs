@@ -1,25 +1,198 @@
 use chrono::{DateTime, Utc};
 use engram_types::{
-    EvidenceRef, Fact, FactStatus, InsightItem, JsonMap, KeyQuote, MemoryPacket, Procedure, Scope,
-    ValidationState, WorkingState,
+    CandidateStatus, EvidenceRef, Fact, FactStatus, InsightItem, JsonMap, KeyQuote, MemoryPacket,
+    Procedure, ProcedureCandidate, Purpose, Scope, ValidationState, WorkingState,
+    WorkingStateClock,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::warn;
 
+mod analytics;
+mod applicability;
+mod archive;
+mod as_of;
+mod budget;
+mod cached;
+mod capabilities;
+mod changelog;
+mod checkpoint;
+mod chunk;
+mod codec;
 mod composer;
+mod consolidation;
+mod contradictions;
+mod correlation;
+mod credibility;
+mod cue_expansion;
+mod dedup;
+mod embedding;
+mod external_context;
+mod fact_conflicts;
+mod fact_history;
+mod facts;
+mod failover;
+mod grounding;
+mod handoff;
+mod isolation;
+mod lang;
+mod learning;
+mod lease;
+mod lifecycle;
+mod lineage;
+mod locale;
+mod metering;
+mod metrics;
+mod outbox;
+mod outcome;
+mod packet_estimate;
+mod pagination;
+mod payload_schema;
+mod pinned_procedures;
+mod policy_registry;
+mod preferences;
+mod provenance;
+mod ranking;
+mod read_preference;
+mod retention;
+mod scope_hash;
+mod search;
+mod shared_facts;
+mod slot_schema;
+mod sql_context;
 mod sqlite;
+mod state_journal;
+mod summaries;
+mod summarizer;
+mod sync;
+mod table_prefix;
+mod tags;
+mod timeout;
+mod tokens;
+mod validation;
+mod vector_index;
+mod wire_schema;
+mod write_queue;
 #[cfg(feature = "mysql")]
 mod mysql;
 #[cfg(feature = "postgres")]
+mod pg_ingest;
+#[cfg(feature = "postgres")]
 mod postgres;
 
-pub use composer::{build_memory_packet, BuildRequest, RecallCues, RecallPolicy};
+pub use analytics::{tenant_stats, DailyActivity, StatsOptions, TenantStats, UserActivity};
+pub use applicability::{procedure_applies, ApplicabilityContext};
+pub use archive::{
+    export_tenant_archive, import_tenant_archive, import_tenant_archive_with, verify_archive,
+    ArchiveManifest, ArchiveSection, ImportOptions, ImportReport, ARCHIVE_FORMAT, ARCHIVE_VERSION,
+};
+pub use as_of::build_memory_packet_as_of;
+pub use budget::{enforce_memory_budget, memory_footprint, EvictionReport, MemoryBudget};
+pub use cached::{CacheOptions, CachedStore};
+pub use capabilities::StoreCapabilities;
+pub use changelog::{Change, ChangeLogStore, ChangeOp};
+pub use checkpoint::{
+    checkpoint_run, get_checkpoint, replay_from_checkpoint, Checkpoint, CHECKPOINT_EVENT_KIND,
+};
+pub use chunk::{
+    chunk_event, chunk_id, chunk_text, parse_chunk_id, ChunkOptions, TextChunk, CHUNK_ID_SEPARATOR,
+};
+pub use codec::{decode_wire, encode_wire, WireFormat};
+pub use composer::{
+    apply_budget, build_memory_packet, build_memory_packets_bulk, collect_citations,
+    rank_episodes, rank_episodes_with_outcomes, BuildRequest, PurposeFilter, PurposeRules,
+    RecallCues, RecallPolicy, TOKEN_COUNT_KEY,
+};
+pub use consolidation::{
+    ConsolidationOptions, ConsolidationReport, Consolidator, CONSOLIDATED_TAG,
+};
+pub use contradictions::{flag_contradictions, Contradiction, CONTRADICTIONS_KEY};
+pub use correlation::{correlation_id, with_correlation_id, CorrelatedStore};
+pub use credibility::{source_classes, SourceCredibility};
+pub use cue_expansion::CueExpansion;
+pub use dedup::{merge_similar_episodes, EpisodeMerge, EpisodeMergeReport};
+pub use embedding::{
+    backfill_embeddings, cosine_similarity, episode_text, fact_text, text_digest, BackfillOptions,
+    BackfillReport, Embedder, MemoryEmbedding,
+};
+pub use external_context::{ExternalContextProvider, ExternalContextQuery};
+pub use fact_conflicts::{upsert_fact_checked, ConflictPolicy, FactUpsert};
+pub use fact_history::{fact_as_of, FactVersion};
+pub use facts::{expire_facts, expire_tenant_facts, pin_fact, unpin_fact, ExpiryReport};
+pub use failover::{FailoverOptions, FailoverStore};
+pub use grounding::{check_grounding, GroundedClaim, GroundingReport, UncitedSpan};
+pub use handoff::{build_handoff_packet, HandoffPacket, HANDOFF_EVENT_KIND};
+pub use isolation::{AgentAccessPolicy, IsolatingStore};
+pub use lang::{lang_matches, LangMode, LanguageDetector, LanguageTaggingStore, ScriptDetector};
+pub use learning::{
+    approve_procedure_candidate, propose_procedure, reject_procedure_candidate,
+    select_successful_episodes, synthesize_procedure_candidate, ProcedureLearningOptions,
+};
+pub use lease::Lease;
+use lease::LeaseTable;
+pub use lifecycle::{
+    begin_run, end_run, rebuild_derived_memory, RebuildReport, RunEndOptions, RunEndReport,
+    RUN_END_EVENT_KIND, RUN_END_EXPIRY, RUN_START_EVENT_KIND,
+};
+pub use lineage::{carry_forward_insights, insight_lineage};
+pub use locale::{render_packet, UserLocale};
+pub use metering::{MeteredStore, MeteringOptions, OperationCounts, TenantUsage};
+pub use metrics::{OperationMetrics, StoreMetrics};
+pub use outbox::{ChangeSink, OutboxDispatcher, OutboxOptions, WriteNotifications};
+pub use outcome::{RunOutcome, RunStatus, RUN_TAG_PREFIX};
+pub use packet_estimate::{
+    estimate_packet_size, PacketSizeEstimate, SectionEstimate, ESTIMATE_SAMPLE_SIZE,
+};
+pub use pagination::{stream_events, EventPage, EventStream, EVENT_STREAM_CHUNK};
+use fact_history::next_fact_version;
+use pagination::page_events_unindexed;
+use tags::{normalize_event_tags, tag_forms};
+pub use payload_schema::{PayloadSchemaRegistry, SchemaTarget, ValidatingStore, ValidationMode};
+pub use pinned_procedures::{
+    is_pinned_procedure, list_tenant_procedures, pin_tenant_procedure, PINNED_TASK_TYPE,
+};
+pub use policy_registry::{PolicyConfig, PolicyRegistry, PurposeFilterConfig, PurposeRulesConfig};
+pub use preferences::{get_preferences, is_preference, set_preference, PREFERENCE_KEY_PREFIX};
+pub use provenance::{resolve_provenance, EvidenceKind, EvidenceLink, Provenance};
+pub use ranking::{Candidate, DefaultRanker, Ranker};
+pub use read_preference::{read_preference, with_read_preference, ReadPreference, ReadReplicaStore};
+pub use retention::{run_retention, RetentionPolicy, RetentionReport};
+pub use scope_hash::{ScopeHasher, ScopeHashingStore, HASHED_ID_PREFIX, MIN_SCOPE_KEY_BYTES};
+pub use table_prefix::MAX_TABLE_PREFIX_LEN;
+pub use search::TextQuery;
+pub use shared_facts::{shared_fact_scope, SHARED_SCOPE_ID};
+pub use slot_schema::{SlotSchema, SlotSchemaRegistry};
 pub use sqlite::SqliteStore;
+pub use state_journal::{replay_working_state, StatePatchJournal};
+pub use summaries::{
+    is_period_summary, refresh_period_summaries, DAILY_SUMMARY_TAG, WEEKLY_SUMMARY_TAG,
+};
+pub use summarizer::{ExtractiveSummarizer, Summarizer, SummaryCondensing};
+pub use sync::{SyncOptions, SyncingStore};
+pub use tags::{
+    normalize_tag, normalize_tags, MAX_TAG_LEN, ORIGINAL_ENTITIES_KEY, ORIGINAL_TAGS_KEY,
+};
+pub use timeout::with_timeout;
+#[cfg(feature = "tiktoken")]
+pub use tokens::BpeTokenCounter;
+pub use tokens::{
+    register_token_counter, token_counter, with_token_counter, HeuristicTokenCounter, TokenCounter,
+};
+pub use validation::{check_id_len, InputLimits, MAX_ID_LEN};
+pub use vector_index::{
+    rebuild_vector_index, vector_index_stats, RebuildIndexReport, VectorIndexStats,
+};
+pub use wire_schema::validate_json;
+pub use write_queue::{WriteQueue, WriteQueueOptions};
 #[cfg(feature = "mysql")]
 pub use mysql::MySqlStore;
 #[cfg(feature = "postgres")]
-pub use postgres::PostgresStore;
+pub use pg_ingest::{IngestReport, IngestTarget, ReplicationIngestor, RowOperation, TableMapping};
+#[cfg(feature = "postgres")]
+pub use postgres::{notify_channel, PgListener, PgNotification, PostgresStore};
 
 pub type StoreResult<T> = Result<T, StoreError>;
 
@@ -29,6 +202,19 @@ pub enum StoreError {
     Poisoned,
     InvalidInput(String),
     Storage(String),
+    /// The backend could not be reached: no connection could be checked out, or the
+    /// connection dropped mid-call.
+    Unavailable(String),
+    /// A statement ran past its store's or call's timeout; see [`with_timeout`].
+    Timeout(String),
+    /// The write was refused because the backend is saturated; see [`WriteQueue`].
+    Overloaded(String),
+    /// A working state patch expected `state_version` `expected`, but the stored
+    /// state is at `actual`; see [`WorkingStatePatch::expected_state_version`].
+    VersionConflict { expected: u32, actual: u32 },
+    /// An upsert of `fact_key` contradicts the stored fact `fact_id` under
+    /// [`ConflictPolicy::Reject`]; see [`upsert_fact_checked`].
+    FactConflict { fact_key: String, fact_id: String },
 }
 
 impl std::fmt::Display for StoreError {
@@ -38,6 +224,17 @@ impl std::fmt::Display for StoreError {
             StoreError::Poisoned => write!(f, "lock poisoned"),
             StoreError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
             StoreError::Storage(msg) => write!(f, "storage error: {}", msg),
+            StoreError::Unavailable(msg) => write!(f, "store unavailable: {}", msg),
+            StoreError::Timeout(msg) => write!(f, "timed out: {}", msg),
+            StoreError::Overloaded(msg) => write!(f, "overloaded: {}", msg),
+            StoreError::VersionConflict { expected, actual } => write!(
+                f,
+                "version conflict: expected state_version {}, found {}",
+                expected, actual
+            ),
+            StoreError::FactConflict { fact_key, fact_id } => {
+                write!(f, "fact conflict: {} contradicts fact {}", fact_key, fact_id)
+            }
         }
     }
 }
@@ -46,7 +243,15 @@ impl std::error::Error for StoreError {}
 
 impl From<rusqlite::Error> for StoreError {
     fn from(err: rusqlite::Error) -> Self {
-        StoreError::Storage(err.to_string())
+        match err.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                StoreError::Timeout(err.to_string())
+            }
+            Some(rusqlite::ErrorCode::CannotOpen | rusqlite::ErrorCode::SystemIoFailure) => {
+                StoreError::Unavailable(err.to_string())
+            }
+            _ => StoreError::Storage(err.to_string()),
+        }
     }
 }
 
@@ -56,23 +261,131 @@ impl From<serde_json::Error> for StoreError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     pub event_id: String,
     pub scope: Scope,
     pub ts: DateTime<Utc>,
     pub kind: EventKind,
     pub payload: Value,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
     pub entities: Vec<String>,
+    /// Per-scope sequence number assigned by the store on append, starting at 1.
+    /// Ignored on input; `0` means the event has not been stored yet.
+    #[serde(default)]
+    pub seq: u64,
+    /// Language tag of the event's text (`en`, `pt-BR`, ...); `None` if unknown.
+    #[serde(default)]
+    pub lang: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+impl Event {
+    pub fn new(scope: Scope, kind: EventKind, payload: Value) -> Self {
+        Self {
+            event_id: engram_types::new_ulid(),
+            scope,
+            ts: Utc::now(),
+            kind,
+            payload,
+            tags: Vec::new(),
+            entities: Vec::new(),
+            seq: 0,
+            lang: None,
+        }
+    }
+
+    /// Estimated tokens of a message event's content, as recorded by the store on
+    /// append under [`TOKEN_COUNT_KEY`].
+    pub fn token_count(&self) -> Option<u32> {
+        self.payload
+            .get(TOKEN_COUNT_KEY)
+            .and_then(Value::as_u64)
+            .map(|count| count as u32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum EventKind {
     Message,
     ToolResult,
     StatePatch,
     System,
+    /// Application-defined kind such as `observation` or `plan_step`.
+    Custom(String),
+}
+
+const MAX_CUSTOM_KIND_LEN: usize = 32;
+
+impl EventKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventKind::Message => "message",
+            EventKind::ToolResult => "tool_result",
+            EventKind::StatePatch => "state_patch",
+            EventKind::System => "system",
+            EventKind::Custom(name) => name,
+        }
+    }
+
+    pub fn is_custom(&self) -> bool {
+        matches!(self, EventKind::Custom(_))
+    }
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Built-in names map to their variants; anything else becomes `Custom` if it is
+/// 1-32 characters of lowercase ASCII letters, digits, `_`, `-`, `.` or `:` and
+/// starts with a letter.
+impl std::str::FromStr for EventKind {
+    type Err = StoreError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "message" => Ok(EventKind::Message),
+            "tool_result" => Ok(EventKind::ToolResult),
+            "state_patch" => Ok(EventKind::StatePatch),
+            "system" => Ok(EventKind::System),
+            _ => {
+                let valid = value.len() <= MAX_CUSTOM_KIND_LEN
+                    && value.starts_with(|c: char| c.is_ascii_lowercase())
+                    && value.chars().all(|c| {
+                        c.is_ascii_lowercase()
+                            || c.is_ascii_digit()
+                            || matches!(c, '_' | '-' | '.' | ':')
+                    });
+                if valid {
+                    Ok(EventKind::Custom(value.to_string()))
+                } else {
+                    Err(StoreError::InvalidInput(format!(
+                        "invalid event kind: {}",
+                        value
+                    )))
+                }
+            }
+        }
+    }
+}
+
+impl From<EventKind> for String {
+    fn from(kind: EventKind) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for EventKind {
+    type Error = StoreError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -86,6 +399,14 @@ pub struct FactFilter {
     pub status: Option<Vec<FactStatus>>,
     pub valid_at: Option<DateTime<Utc>>,
     pub limit: Option<usize>,
+    /// Overlays the agent and tenant fact pools beneath the user's facts; see
+    /// [`shared_fact_scope`].
+    pub include_shared: bool,
+    pub pinned: Option<bool>,
+    /// Only user preferences, or none of them; see [`set_preference`].
+    pub preferences: Option<bool>,
+    /// Only facts with one of these keys.
+    pub keys: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -96,6 +417,66 @@ pub struct EpisodeFilter {
     pub limit: Option<usize>,
 }
 
+impl EpisodeFilter {
+    fn matches(&self, episode: &engram_types::Episode) -> bool {
+        let time_ok = match &self.time_range {
+            Some(range) => {
+                let start_ok = range.start.map(|s| episode.time_range.start >= s).unwrap_or(true);
+                let end_ok = range
+                    .end
+                    .map(|end| {
+                        let episode_end = episode.time_range.end.unwrap_or(episode.time_range.start);
+                        episode_end <= end
+                    })
+                    .unwrap_or(true);
+                start_ok && end_ok
+            }
+            None => true,
+        };
+        time_ok
+            && (self.tags.is_empty() || episode.tags.iter().any(|t| self.tags.contains(t)))
+            && (self.entities.is_empty()
+                || episode.entities.iter().any(|t| self.entities.contains(t)))
+    }
+
+    /// The filter matching each tag and entity both as given and normalized.
+    pub(crate) fn with_tag_forms(self) -> Self {
+        Self {
+            tags: tag_forms(&self.tags),
+            entities: tag_forms(&self.entities),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub time_range: Option<TimeRangeFilter>,
+    pub tags: Vec<String>,
+    pub entities: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        let range = self.time_range.clone().unwrap_or_default();
+        range.start.map(|start| event.ts >= start).unwrap_or(true)
+            && range.end.map(|end| event.ts <= end).unwrap_or(true)
+            && (self.tags.is_empty() || event.tags.iter().any(|t| self.tags.contains(t)))
+            && (self.entities.is_empty()
+                || event.entities.iter().any(|t| self.entities.contains(t)))
+    }
+
+    /// The filter matching each tag and entity both as given and normalized.
+    pub(crate) fn with_tag_forms(self) -> Self {
+        Self {
+            tags: tag_forms(&self.tags),
+            entities: tag_forms(&self.entities),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct InsightFilter {
     pub validation_state: Option<Vec<ValidationState>>,
@@ -103,12 +484,232 @@ pub struct InsightFilter {
 }
 
 #[derive(Debug, Clone, Default)]
+pub struct ProcedureCandidateFilter {
+    pub task_type: Option<String>,
+    pub status: Option<Vec<CandidateStatus>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryKind {
+    Fact,
+    Episode,
+}
+
+/// Points at one long-term memory item of a scope.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MemoryRef {
+    pub kind: MemoryKind,
+    pub id: String,
+}
+
+/// The kinds of record [`Store::delete_record`] removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Event,
+    Fact,
+    Episode,
+    Procedure,
+    Insight,
+}
+
+impl RecordKind {
+    pub(crate) const ALL: [RecordKind; 5] = [
+        RecordKind::Event,
+        RecordKind::Fact,
+        RecordKind::Episode,
+        RecordKind::Procedure,
+        RecordKind::Insight,
+    ];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            RecordKind::Event => "event",
+            RecordKind::Fact => "fact",
+            RecordKind::Episode => "episode",
+            RecordKind::Procedure => "procedure",
+            RecordKind::Insight => "insight",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        RecordKind::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    /// Whether records of this kind belong to a run rather than to the user and agent.
+    pub(crate) fn run_level(&self) -> bool {
+        matches!(self, RecordKind::Event | RecordKind::Insight)
+    }
+
+    /// Table and id column of the SQL backends.
+    pub(crate) fn table(&self) -> (&'static str, &'static str) {
+        match self {
+            RecordKind::Event => ("events", "event_id"),
+            RecordKind::Fact => ("facts", "fact_id"),
+            RecordKind::Episode => ("episodes", "episode_id"),
+            RecordKind::Procedure => ("procedures", "procedure_id"),
+            RecordKind::Insight => ("insights", "insight_id"),
+        }
+    }
+
+    /// The kind suppressions and embeddings know the record by, if any.
+    pub(crate) fn memory_kind(&self) -> Option<MemoryKind> {
+        match self {
+            RecordKind::Fact => Some(MemoryKind::Fact),
+            RecordKind::Episode => Some(MemoryKind::Episode),
+            _ => None,
+        }
+    }
+
+    /// The part of `scope` records of this kind are keyed by: the whole run scope,
+    /// or its user and agent with empty session and run ids.
+    pub(crate) fn record_scope(&self, scope: &Scope) -> Scope {
+        if self.run_level() {
+            return scope.clone();
+        }
+        Scope {
+            session_id: String::new(),
+            run_id: String::new(),
+            ..scope.clone()
+        }
+    }
+}
+
+/// Points at one event, fact, episode, procedure or insight of a scope.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RecordRef {
+    pub kind: RecordKind,
+    pub id: String,
+}
+
+impl RecordRef {
+    pub fn new(kind: RecordKind, id: impl Into<String>) -> Self {
+        Self { kind, id: id.into() }
+    }
+}
+
+/// How [`Store::delete_record`] and [`Store::clear_scope`] remove records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Removes records without a trace.
+    #[default]
+    Hard,
+    /// Removes records from every read but keeps a [`Tombstone`] with their content,
+    /// listed by [`Store::list_tombstones`].
+    Soft,
+}
+
+/// A soft-deleted record, kept for audit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// The scope the record was stored under; facts, episodes and procedures belong
+    /// to the user and agent, so their session and run ids are empty.
+    pub scope: Scope,
+    #[serde(flatten)]
+    pub item: RecordRef,
+    /// The record as it was when deleted.
+    pub record: Value,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A memory item the user asked not to be brought up again. The item itself is
+/// kept for audit; only recall skips it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suppression {
+    #[serde(flatten)]
+    pub item: MemoryRef,
+    pub reason: String,
+    pub suppressed_at: DateTime<Utc>,
+}
+
+/// One run's working state, as returned by [`Store::list_working_states`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunWorkingState {
+    pub run_id: String,
+    pub state: WorkingState,
+}
+
+/// What dashboards show for a stored context build, as returned by
+/// [`Store::list_context_build_summaries`]. Backends read it without deserializing the
+/// packet's memory sections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextBuildSummary {
+    pub generated_at: DateTime<Utc>,
+    pub purpose: Purpose,
+    pub task_type: String,
+    pub policy_id: String,
+    pub max_tokens: u32,
+    pub used_tokens_est: u32,
+    pub section_usage: JsonMap,
+}
+
+impl ContextBuildSummary {
+    /// Reads the summary out of stored packet JSON; serde skips every other field
+    /// without building it.
+    pub(crate) fn from_packet_json(packet_json: &str) -> StoreResult<Self> {
+        #[derive(Deserialize)]
+        struct Header {
+            meta: MetaHeader,
+            #[serde(default)]
+            budget_report: BudgetHeader,
+        }
+        #[derive(Deserialize)]
+        struct MetaHeader {
+            #[serde(default = "Utc::now")]
+            generated_at: DateTime<Utc>,
+            purpose: Purpose,
+            #[serde(default)]
+            task_type: String,
+            #[serde(default)]
+            policy_id: String,
+        }
+        #[derive(Deserialize, Default)]
+        struct BudgetHeader {
+            #[serde(default)]
+            max_tokens: u32,
+            #[serde(default)]
+            used_tokens_est: u32,
+            #[serde(default)]
+            section_usage: JsonMap,
+        }
+
+        let header: Header = serde_json::from_str(packet_json)?;
+        Ok(Self {
+            generated_at: header.meta.generated_at,
+            purpose: header.meta.purpose,
+            task_type: header.meta.task_type,
+            policy_id: header.meta.policy_id,
+            max_tokens: header.budget_report.max_tokens,
+            used_tokens_est: header.budget_report.used_tokens_est,
+            section_usage: header.budget_report.section_usage,
+        })
+    }
+}
+
+impl From<&MemoryPacket> for ContextBuildSummary {
+    fn from(packet: &MemoryPacket) -> Self {
+        Self {
+            generated_at: packet.meta.generated_at,
+            purpose: packet.meta.purpose.clone(),
+            task_type: packet.meta.task_type.clone(),
+            policy_id: packet.meta.policy_id.clone(),
+            max_tokens: packet.budget_report.max_tokens,
+            used_tokens_est: packet.budget_report.used_tokens_est,
+            section_usage: packet.budget_report.section_usage.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StmState {
     pub rolling_summary: String,
     pub key_quotes: Vec<KeyQuote>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct WorkingStatePatch {
     pub goal: Option<String>,
     pub plan: Option<Vec<String>>,
@@ -118,16 +719,175 @@ pub struct WorkingStatePatch {
     pub decisions: Option<Vec<String>>,
     pub risks: Option<Vec<String>>,
     pub state_version: Option<u32>,
+    /// Applies the patch only if the stored state is at this `state_version` (0 for a
+    /// run without one); otherwise it fails with [`StoreError::VersionConflict`] and
+    /// nothing is written. Unset, the patch is last-writer-wins.
+    pub expected_state_version: Option<u32>,
+    /// Replaces the merge clock instead of stamping the patched fields. Only set
+    /// when writing back a state produced by [`WorkingState::merge`] or a replica.
+    pub clock: Option<WorkingStateClock>,
+    /// Task type whose [`SlotSchema`] a [`ValidatingStore`] checks the slots and
+    /// constraints against. Not stored.
+    pub task_type: Option<String>,
+}
+
+impl From<WorkingState> for WorkingStatePatch {
+    fn from(state: WorkingState) -> Self {
+        Self {
+            goal: Some(state.goal),
+            plan: Some(state.plan),
+            slots: Some(state.slots),
+            constraints: Some(state.constraints),
+            tool_evidence: Some(state.tool_evidence),
+            decisions: Some(state.decisions),
+            risks: Some(state.risks),
+            state_version: Some(state.state_version),
+            expected_state_version: None,
+            clock: Some(state.clock),
+            task_type: None,
+        }
+    }
+}
+
+/// Times [`Store::merge_working_state`] redoes a merge that lost to a concurrent write.
+const MERGE_ATTEMPTS: usize = 8;
+
+/// Fails with [`StoreError::VersionConflict`] unless `current` is at the version the
+/// patch expects, if any.
+pub(crate) fn check_state_version(
+    current: &WorkingState,
+    patch: &WorkingStatePatch,
+) -> StoreResult<()> {
+    match patch.expected_state_version {
+        Some(expected) if expected != current.state_version => Err(StoreError::VersionConflict {
+            expected,
+            actual: current.state_version,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The conflict for a patch expecting `expected` that lost its compare-and-swap to
+/// another writer; `stored` is the state that writer left.
+pub(crate) fn state_version_conflict(expected: u32, stored: Option<WorkingState>) -> StoreError {
+    StoreError::VersionConflict {
+        expected,
+        actual: stored.map_or(0, |state| state.state_version),
+    }
+}
+
+/// Applies a patch on top of `current`, stamping the merge clock of every field it
+/// touches. Register stamps never move backwards, even if the wall clock does.
+pub(crate) fn apply_working_state_patch(
+    current: &WorkingState,
+    patch: WorkingStatePatch,
+) -> WorkingState {
+    let now = Utc::now();
+    let stamp = |previous: Option<DateTime<Utc>>| {
+        Some(previous.map_or(now, |previous| now.max(previous + chrono::Duration::milliseconds(1))))
+    };
+    let mut next = current.clone();
+
+    let mut touched = false;
+    if let Some(goal) = patch.goal {
+        next.goal = goal;
+        next.clock.goal = stamp(current.clock.goal);
+        touched = true;
+    }
+    if let Some(plan) = patch.plan {
+        next.clock.plan.update(&current.plan, &plan);
+        next.plan = plan;
+        touched = true;
+    }
+    if let Some(slots) = patch.slots {
+        next.slots = slots;
+        next.clock.slots = stamp(current.clock.slots);
+        touched = true;
+    }
+    if let Some(constraints) = patch.constraints {
+        next.constraints = constraints;
+        next.clock.constraints = stamp(current.clock.constraints);
+        touched = true;
+    }
+    if let Some(tool_evidence) = patch.tool_evidence {
+        next.tool_evidence = tool_evidence;
+        next.clock.tool_evidence = stamp(current.clock.tool_evidence);
+        touched = true;
+    }
+    if let Some(decisions) = patch.decisions {
+        next.clock.decisions.update(&current.decisions, &decisions);
+        next.decisions = decisions;
+        touched = true;
+    }
+    if let Some(risks) = patch.risks {
+        next.clock.risks.update(&current.risks, &risks);
+        next.risks = risks;
+        touched = true;
+    }
+    if let Some(clock) = patch.clock {
+        next.clock = clock;
+    }
+
+    if let Some(state_version) = patch.state_version {
+        next.state_version = state_version;
+    } else if touched {
+        next.state_version = next.state_version.saturating_add(1);
+    }
+    next
 }
 
 pub trait Store: Send + Sync {
     fn append_event(&self, event: Event) -> StoreResult<()>;
+    /// Appends `events` in order. SQL backends write the batch in one transaction, so
+    /// either every event is stored or none is; other stores append them one by one.
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        events.iter().try_for_each(|event| self.append_event(event.clone()))
+    }
     fn list_events(
         &self,
         scope: &Scope,
         range: TimeRangeFilter,
         limit: Option<usize>,
     ) -> StoreResult<Vec<Event>>;
+    /// Up to `limit` events of the run scope in `(ts, event_id)` order, starting after
+    /// `cursor` (from the first event if `None`). Pass the page's `next_cursor` back to
+    /// read the next page; it is `None` after the last one. SQL backends seek straight
+    /// to the cursor, so paging through a long run costs the same for every page.
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        page_events_unindexed(self, scope, cursor, limit)
+    }
+    /// Events of the run scope with a sequence number greater than `seq`, in sequence order.
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>>;
+
+    /// Looks up events by id anywhere under the scope's tenant, user and agent,
+    /// regardless of session or run. Unknown ids are skipped.
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>>;
+    /// Events of the run scope carrying any of `filter.tags` and any of
+    /// `filter.entities`, in time order. SQL backends answer from the `event_tags`
+    /// and `event_entities` indexes instead of decoding every event of the run.
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>>;
+    /// Events of the run scope whose text matches any of `query.terms`, best match
+    /// first; an event's text is every string in its payload. SQL backends match whole
+    /// words through a full-text index (SQLite FTS5, a Postgres `tsvector` index,
+    /// MySQL `FULLTEXT`); the in-memory store matches substrings, ignoring case.
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>>;
+    /// Episodes of the scope's user and agent whose summary or highlights match any
+    /// of `query.terms`, best match first, matched as by [`Store::search_events`].
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>>;
 
     fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>>;
     fn patch_working_state(
@@ -135,12 +895,37 @@ pub trait Store: Send + Sync {
         scope: &Scope,
         patch: WorkingStatePatch,
     ) -> StoreResult<WorkingState>;
+    /// Applies each patch to the working state of its scope's run, in order, and
+    /// returns the new states. SQL backends and the in-memory store apply the batch
+    /// atomically: if one patch fails, e.g. on a state version conflict, none lands.
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        patches
+            .into_iter()
+            .map(|(scope, patch)| self.patch_working_state(&scope, patch))
+            .collect()
+    }
+
+    /// Working states of every run in the scope's session, ordered by run id.
+    /// `scope.run_id` is ignored.
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>>;
 
     fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>>;
     fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()>;
 
     fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>>;
     fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()>;
+    /// Upserts `facts` into the scope, transactionally like
+    /// [`append_events_bulk`](Store::append_events_bulk).
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        facts.iter().try_for_each(|fact| self.upsert_fact(scope, fact.clone()))
+    }
+    /// Every value the scope's facts with `fact_key` were written with, oldest first;
+    /// see [`FactVersion`]. Rewriting a fact unchanged records nothing, and deleting
+    /// it hard drops its history.
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>>;
 
     fn list_episodes(
         &self,
@@ -148,6 +933,17 @@ pub trait Store: Send + Sync {
         filter: EpisodeFilter,
     ) -> StoreResult<Vec<engram_types::Episode>>;
     fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()>;
+    /// Appends `episodes` to the scope, transactionally like
+    /// [`append_events_bulk`](Store::append_events_bulk).
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        episodes
+            .iter()
+            .try_for_each(|episode| self.append_episode(scope, episode.clone()))
+    }
 
     fn list_procedures(
         &self,
@@ -157,8 +953,27 @@ pub trait Store: Send + Sync {
     ) -> StoreResult<Vec<Procedure>>;
     fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()>;
 
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>>;
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>>;
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()>;
+
     fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>>;
     fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()>;
+    /// Removes the run's insights whose `expires_at` equals the given marker, such as
+    /// `run_end`. Returns how many were removed.
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize>;
 
     fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()>;
     fn list_context_builds(
@@ -166,29 +981,665 @@ pub trait Store: Send + Sync {
         scope: &Scope,
         limit: Option<usize>,
     ) -> StoreResult<Vec<MemoryPacket>>;
+    /// Like [`list_context_builds`](Store::list_context_builds), but only each build's
+    /// metadata and budget usage.
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>>;
+
+    /// Per-user activity counts for a tenant, the input of [`tenant_stats`]. Only
+    /// events are filtered by `range`.
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>>;
+
+    /// Run scopes that have events, most recently active first, optionally for one
+    /// tenant only.
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>>;
+
+    /// Excludes a fact or episode from recall without deleting it. Suppressing an
+    /// item again replaces the reason.
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()>;
+    /// Lifts a suppression; returns whether one existed.
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool>;
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>>;
+    /// Deletes the scope's facts and episodes among `items` for good, along with their
+    /// suppressions and embeddings. Returns how many items were deleted; see
+    /// [`enforce_memory_budget`](crate::enforce_memory_budget).
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize>;
+    /// Deletes an event or insight of the run scope, or a fact, episode or procedure
+    /// of its user and agent, along with its index rows, suppression and embeddings.
+    /// Returns whether the record existed.
+    fn delete_record(&self, scope: &Scope, item: &RecordRef, mode: DeleteMode)
+    -> StoreResult<bool>;
+    fn delete_event(&self, scope: &Scope, event_id: &str, mode: DeleteMode) -> StoreResult<bool> {
+        self.delete_record(scope, &RecordRef::new(RecordKind::Event, event_id), mode)
+    }
+    fn delete_fact(&self, scope: &Scope, fact_id: &str, mode: DeleteMode) -> StoreResult<bool> {
+        self.delete_record(scope, &RecordRef::new(RecordKind::Fact, fact_id), mode)
+    }
+    fn delete_episode(&self, scope: &Scope, episode_id: &str, mode: DeleteMode)
+    -> StoreResult<bool> {
+        self.delete_record(scope, &RecordRef::new(RecordKind::Episode, episode_id), mode)
+    }
+    fn delete_procedure(&self, scope: &Scope, procedure_id: &str, mode: DeleteMode)
+    -> StoreResult<bool> {
+        self.delete_record(scope, &RecordRef::new(RecordKind::Procedure, procedure_id), mode)
+    }
+    fn delete_insight(&self, scope: &Scope, insight_id: &str, mode: DeleteMode)
+    -> StoreResult<bool> {
+        self.delete_record(scope, &RecordRef::new(RecordKind::Insight, insight_id), mode)
+    }
+    /// Deletes every event and insight of the run scope and every fact, episode and
+    /// procedure of its user and agent, in one go. Working state, STM and context
+    /// builds are kept. Returns how many records were deleted.
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize>;
+    /// How many records of `kind` the scope holds: the run's events or insights, or
+    /// the facts, episodes or procedures of its user and agent. SQL backends count
+    /// them without reading them; see [`estimate_packet_size`].
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize>;
+    /// Tombstones of the scope's user and agent across sessions, most recent first.
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>)
+    -> StoreResult<Vec<Tombstone>>;
+    /// Stores embeddings of the scope's facts and episodes, replacing earlier ones of
+    /// the same item and model.
+    fn upsert_embeddings(&self, scope: &Scope, embeddings: Vec<MemoryEmbedding>) -> StoreResult<()>;
+    /// Embeddings of the scope's facts and episodes under `model`.
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>>;
+    /// Deletes the embeddings of `items` under `model`, returning how many existed.
+    fn delete_embeddings(&self, scope: &Scope, model: &str, items: &[MemoryRef])
+    -> StoreResult<usize>;
+
+    /// Records how the run scope ended, replacing an earlier record for the run.
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()>;
+    /// Outcomes of the scope's user and agent across sessions, most recent first.
+    fn list_run_outcomes(&self, scope: &Scope, limit: Option<usize>)
+    -> StoreResult<Vec<RunOutcome>>;
+
+    /// Replaces how far the tenant trusts each kind of fact source; see
+    /// [`SourceCredibility`]. Recall weighs fact confidence by it once set.
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()>;
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>>;
+
+    /// Replaces the user's timezone and locale; see [`UserLocale`]. Packets built for
+    /// the user render timestamps and count day windows in that timezone once set.
+    fn set_user_locale(&self, tenant_id: &str, user_id: &str, locale: UserLocale)
+    -> StoreResult<()>;
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>>;
+
+    /// Grants an exclusive lease on the run scope for `ttl`, or `None` while another
+    /// worker holds one, so only one worker consolidates or ends a run at a time.
+    /// Postgres and MySQL back leases with advisory locks (`pg_try_advisory_lock`,
+    /// `GET_LOCK`) held on a dedicated pooled connection, which also frees them if the
+    /// process dies; SQLite and in-memory stores only exclude workers of this process.
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>>;
+    /// Extends a held lease to `ttl` from now; `None` once it has been taken over or
+    /// released.
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>>;
+    /// Returns whether the lease was still held.
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool>;
+
+    /// Appends to the store-wide change log and returns the assigned sequence number.
+    /// Writes are only logged when the store is wrapped in a [`ChangeLogStore`].
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64>;
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>>;
+    /// Highest `seq` in the change log, or 0 while it is empty. The default reads the
+    /// whole log; the backends ask for the maximum instead.
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        let mut seq = 0;
+        loop {
+            match self.list_changes(seq, Some(1000))?.last() {
+                Some(last) => seq = last.seq,
+                None => return Ok(seq),
+            }
+        }
+    }
+    /// Last change log `seq` processed by the named consumer, or 0. Consumers such as
+    /// [`OutboxDispatcher`] and [`SyncingStore`] resume from it after a restart.
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64>;
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()>;
+    /// Runs `write` and appends the changes it returns to the change log atomically
+    /// with it, as [`ChangeLogStore`] does for every write. The SQL backends run the
+    /// write's calls and the appends in one transaction; other stores append once
+    /// `write` succeeds.
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        for op in write()? {
+            self.append_change(op)?;
+        }
+        Ok(())
+    }
+
+    /// Replays a batch read from another store's change log, in order. Sequence
+    /// numbers must be strictly increasing; callers track the last applied `seq`.
+    fn apply_changes(&self, batch: &[Change]) -> StoreResult<()> {
+        changelog::apply_changes(self, batch)
+    }
+
+    /// Merges a working state from another replica into this store's copy (see
+    /// [`WorkingState::merge`]) and returns the result. The write expects the
+    /// `state_version` it merged against and moves past it, and the merge is redone
+    /// against the newer copy when another writer got there first.
+    fn merge_working_state(&self, scope: &Scope, remote: WorkingState) -> StoreResult<WorkingState> {
+        let mut attempt = 1;
+        loop {
+            let local = self.get_working_state(scope)?.unwrap_or_default();
+            let merged = local.merge(&remote);
+            let patch = WorkingStatePatch {
+                state_version: Some(merged.state_version.max(local.state_version.saturating_add(1))),
+                expected_state_version: Some(local.state_version),
+                ..merged.into()
+            };
+            match self.patch_working_state(scope, patch) {
+                Err(StoreError::VersionConflict { .. }) if attempt < MERGE_ATTEMPTS => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Calls, errors and average latency per backend operation since the store was
+    /// opened. The SQL backends keep these; other stores return an empty snapshot, and
+    /// wrappers report the stores they wrap.
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        StoreMetrics::default()
+    }
+
+    /// The optional features this store supports. The default claims none, which
+    /// is always safe; backends report what they detected when they opened, and
+    /// wrappers report the stores they wrap.
+    fn capabilities(&self) -> StoreCapabilities {
+        StoreCapabilities::default()
+    }
+}
+
+impl<S: Store + ?Sized> Store for Arc<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        (**self).append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        (**self).append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        (**self).list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        (**self).list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        (**self).get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        (**self).get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        (**self).find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        (**self).search_events(scope, query)
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        (**self).search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        (**self).acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        (**self).renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        (**self).release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        (**self).get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        (**self).patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        (**self).patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        (**self).list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        (**self).get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        (**self).update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        (**self).list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        (**self).upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        (**self).upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        (**self).list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        (**self).list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()> {
+        (**self).append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        (**self).append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        (**self).list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        (**self).upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        (**self).list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        (**self).get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        (**self).upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        (**self).list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        (**self).append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        (**self).expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        (**self).write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        (**self).list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        (**self).list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        (**self).tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        (**self).list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        (**self).suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        (**self).unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        (**self).list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        (**self).evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        (**self).delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        (**self).clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        (**self).count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        (**self).list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        (**self).upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        (**self).list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        (**self).delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        (**self).record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        (**self).list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        (**self).set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        (**self).get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        (**self).set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        (**self).get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        (**self).append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        (**self).load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        (**self).save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        (**self).write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        (**self).list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        (**self).last_change_seq()
+    }
+
+    fn apply_changes(&self, batch: &[Change]) -> StoreResult<()> {
+        (**self).apply_changes(batch)
+    }
+
+    fn merge_working_state(&self, scope: &Scope, remote: WorkingState) -> StoreResult<WorkingState> {
+        (**self).merge_working_state(scope, remote)
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        (**self).metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        (**self).capabilities()
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct InMemoryStore {
     events: RwLock<Vec<Event>>,
+    event_seqs: RwLock<HashMap<RunKey, u64>>,
     wm_state: RwLock<HashMap<RunKey, WorkingState>>,
     stm_state: RwLock<HashMap<SessionKey, StmState>>,
     facts: RwLock<HashMap<LtmKey, Vec<Fact>>>,
+    fact_versions: RwLock<HashMap<LtmKey, Vec<FactVersion>>>,
     episodes: RwLock<HashMap<LtmKey, Vec<engram_types::Episode>>>,
     procedures: RwLock<HashMap<LtmKey, Vec<Procedure>>>,
+    procedure_candidates: RwLock<HashMap<LtmKey, Vec<ProcedureCandidate>>>,
     insights: RwLock<HashMap<RunKey, Vec<InsightItem>>>,
     context_builds: RwLock<HashMap<RunKey, Vec<MemoryPacket>>>,
+    suppressions: RwLock<HashMap<LtmKey, Vec<Suppression>>>,
+    embeddings: RwLock<HashMap<LtmKey, Vec<MemoryEmbedding>>>,
+    tombstones: RwLock<HashMap<LtmKey, Vec<Tombstone>>>,
+    run_outcomes: RwLock<HashMap<LtmKey, Vec<RunOutcome>>>,
+    source_credibility: RwLock<HashMap<String, SourceCredibility>>,
+    user_locales: RwLock<HashMap<(String, String), UserLocale>>,
+    changes: RwLock<Vec<Change>>,
+    change_cursors: RwLock<HashMap<String, u64>>,
+    leases: LeaseTable,
 }
 
 impl InMemoryStore {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Removes the scope's records of `kind`, or only the one with `id`, keeping
+    /// tombstones of them in soft mode. Returns how many were removed.
+    fn remove_records(
+        &self,
+        scope: &Scope,
+        kind: RecordKind,
+        id: Option<&str>,
+        mode: DeleteMode,
+    ) -> StoreResult<usize> {
+        let key = LtmKey::from(scope);
+        let picked = |record_id: &str| id.is_none_or(|id| id == record_id);
+        let removed = match kind {
+            RecordKind::Event => {
+                let mut guard = self.events.write().map_err(|_| StoreError::Poisoned)?;
+                take_records(
+                    &mut guard,
+                    |event| scope_matches(&event.scope, scope) && picked(&event.event_id),
+                    |event| &event.event_id,
+                )?
+            }
+            RecordKind::Fact => {
+                let mut guard = self.facts.write().map_err(|_| StoreError::Poisoned)?;
+                let facts = guard.entry(key.clone()).or_default();
+                let removed =
+                    take_records(facts, |fact| picked(&fact.fact_id), |fact| &fact.fact_id)?;
+                if mode == DeleteMode::Hard {
+                    let mut guard = self.fact_versions.write().map_err(|_| StoreError::Poisoned)?;
+                    if let Some(versions) = guard.get_mut(&key) {
+                        versions.retain(|version| !picked(&version.fact.fact_id));
+                    }
+                }
+                removed
+            }
+            RecordKind::Episode => {
+                let mut guard = self.episodes.write().map_err(|_| StoreError::Poisoned)?;
+                let episodes = guard.entry(key.clone()).or_default();
+                take_records(
+                    episodes,
+                    |episode| picked(&episode.episode_id),
+                    |episode| &episode.episode_id,
+                )?
+            }
+            RecordKind::Procedure => {
+                let mut guard = self.procedures.write().map_err(|_| StoreError::Poisoned)?;
+                let procedures = guard.entry(key.clone()).or_default();
+                take_records(
+                    procedures,
+                    |procedure| picked(&procedure.procedure_id),
+                    |procedure| &procedure.procedure_id,
+                )?
+            }
+            RecordKind::Insight => {
+                let mut guard = self.insights.write().map_err(|_| StoreError::Poisoned)?;
+                let insights = guard.entry(RunKey::from(scope)).or_default();
+                take_records(insights, |insight| picked(&insight.id), |insight| &insight.id)?
+            }
+        };
+        if let Some(memory_kind) = kind.memory_kind() {
+            let attached = |item: &MemoryRef| item.kind == memory_kind && picked(&item.id);
+            let mut guard = self.suppressions.write().map_err(|_| StoreError::Poisoned)?;
+            if let Some(entries) = guard.get_mut(&key) {
+                entries.retain(|s| !attached(&s.item));
+            }
+            let mut guard = self.embeddings.write().map_err(|_| StoreError::Poisoned)?;
+            if let Some(entries) = guard.get_mut(&key) {
+                entries.retain(|embedding| !attached(&embedding.item));
+            }
+        }
+        let count = removed.len();
+        if mode == DeleteMode::Soft && count > 0 {
+            let deleted_at = Utc::now();
+            let mut guard = self.tombstones.write().map_err(|_| StoreError::Poisoned)?;
+            guard.entry(key).or_default().extend(removed.into_iter().map(|(id, record)| {
+                Tombstone {
+                    scope: kind.record_scope(scope),
+                    item: RecordRef::new(kind, id),
+                    record,
+                    deleted_at,
+                }
+            }));
+        }
+        Ok(count)
+    }
 }
 
 impl Store for InMemoryStore {
     fn append_event(&self, event: Event) -> StoreResult<()> {
+        let mut event = normalize_event_tags(event);
+        event.payload = composer::stored_payload(&event).into_owned();
         let mut guard = self.events.write().map_err(|_| StoreError::Poisoned)?;
+        let mut seqs = self.event_seqs.write().map_err(|_| StoreError::Poisoned)?;
+        let last = seqs.entry(RunKey::from(&event.scope)).or_insert(0);
+        *last += 1;
+        event.seq = *last;
         guard.push(event);
         Ok(())
     }
@@ -218,6 +1669,72 @@ impl Store for InMemoryStore {
         Ok(results)
     }
 
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let guard = self.events.read().map_err(|_| StoreError::Poisoned)?;
+        let mut results: Vec<Event> = guard
+            .iter()
+            .filter(|e| scope_matches(&e.scope, scope) && e.seq > seq)
+            .cloned()
+            .collect();
+        results.sort_by_key(|e| e.seq);
+
+        apply_limit(&mut results, limit);
+        Ok(results)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        let guard = self.events.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard
+            .iter()
+            .filter(|e| LtmKey::from(&e.scope) == LtmKey::from(scope))
+            .filter(|e| event_ids.contains(&e.event_id))
+            .cloned()
+            .collect())
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let filter = filter.with_tag_forms();
+        let guard = self.events.read().map_err(|_| StoreError::Poisoned)?;
+        let mut results: Vec<Event> = guard
+            .iter()
+            .filter(|e| scope_matches(&e.scope, scope) && filter.matches(e))
+            .cloned()
+            .collect();
+        results.sort_by_key(|e| (e.ts, e.seq));
+
+        apply_limit(&mut results, filter.limit);
+        Ok(results)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        search::search_events_unindexed(self, scope, query)
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        search::search_episodes_unindexed(self, scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.acquire(scope, ttl, || Ok(Some(())), |_| Ok(()))
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.renew(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        Ok(self.leases.release(lease)?.is_some())
+    }
+
     fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
         let key = RunKey::from(scope);
         let guard = self.wm_state.read().map_err(|_| StoreError::Poisoned)?;
@@ -231,46 +1748,55 @@ impl Store for InMemoryStore {
     ) -> StoreResult<WorkingState> {
         let key = RunKey::from(scope);
         let mut guard = self.wm_state.write().map_err(|_| StoreError::Poisoned)?;
-        let mut current = guard.get(&key).cloned().unwrap_or_default();
+        let current = guard.get(&key).cloned().unwrap_or_default();
+        check_state_version(&current, &patch)?;
+        let current = apply_working_state_patch(&current, patch);
 
-        let mut touched = false;
-        if let Some(goal) = patch.goal {
-            current.goal = goal;
-            touched = true;
-        }
-        if let Some(plan) = patch.plan {
-            current.plan = plan;
-            touched = true;
-        }
-        if let Some(slots) = patch.slots {
-            current.slots = slots;
-            touched = true;
-        }
-        if let Some(constraints) = patch.constraints {
-            current.constraints = constraints;
-            touched = true;
-        }
-        if let Some(tool_evidence) = patch.tool_evidence {
-            current.tool_evidence = tool_evidence;
-            touched = true;
-        }
-        if let Some(decisions) = patch.decisions {
-            current.decisions = decisions;
-            touched = true;
-        }
-        if let Some(risks) = patch.risks {
-            current.risks = risks;
-            touched = true;
-        }
+        guard.insert(key, current.clone());
+        Ok(current)
+    }
 
-        if let Some(state_version) = patch.state_version {
-            current.state_version = state_version;
-        } else if touched {
-            current.state_version = current.state_version.saturating_add(1);
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let mut guard = self.wm_state.write().map_err(|_| StoreError::Poisoned)?;
+        // Patch copies so that a failing patch leaves every state as it was.
+        let mut next: HashMap<RunKey, WorkingState> = HashMap::new();
+        let mut states = Vec::with_capacity(patches.len());
+        for (scope, patch) in patches {
+            let key = RunKey::from(&scope);
+            let current = match next.get(&key) {
+                Some(state) => state.clone(),
+                None => guard.get(&key).cloned().unwrap_or_default(),
+            };
+            check_state_version(&current, &patch)?;
+            let state = apply_working_state_patch(&current, patch);
+            states.push(state.clone());
+            next.insert(key, state);
         }
+        guard.extend(next);
+        Ok(states)
+    }
 
-        guard.insert(key, current.clone());
-        Ok(current)
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        let key = SessionKey::from(scope);
+        let guard = self.wm_state.read().map_err(|_| StoreError::Poisoned)?;
+        let mut states: Vec<RunWorkingState> = guard
+            .iter()
+            .filter(|(run, _)| {
+                run.tenant_id == key.tenant_id
+                    && run.user_id == key.user_id
+                    && run.agent_id == key.agent_id
+                    && run.session_id == key.session_id
+            })
+            .map(|(run, state)| RunWorkingState {
+                run_id: run.run_id.clone(),
+                state: state.clone(),
+            })
+            .collect();
+        states.sort_by(|a, b| a.run_id.cmp(&b.run_id));
+        Ok(states)
     }
 
     fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
@@ -287,6 +1813,9 @@ impl Store for InMemoryStore {
     }
 
     fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        if filter.include_shared {
+            return shared_facts::list_facts_with_shared(self, scope, filter);
+        }
         let key = LtmKey::from(scope);
         let guard = self.facts.read().map_err(|_| StoreError::Poisoned)?;
         let mut results: Vec<Fact> = guard
@@ -298,6 +1827,9 @@ impl Store for InMemoryStore {
                 Some(statuses) => statuses.contains(&f.status),
                 None => true,
             })
+            .filter(|f| filter.pinned.is_none_or(|pinned| f.pinned == pinned))
+            .filter(|f| filter.preferences.is_none_or(|wanted| is_preference(f) == wanted))
+            .filter(|f| filter.keys.as_ref().is_none_or(|keys| keys.contains(&f.fact_key)))
             .filter(|f| match filter.valid_at {
                 Some(t) => {
                     let from_ok = f.validity.valid_from.map(|v| v <= t).unwrap_or(true);
@@ -308,6 +1840,7 @@ impl Store for InMemoryStore {
             })
             .collect();
 
+        results.sort_by(|a, b| a.fact_key.cmp(&b.fact_key).then_with(|| a.fact_id.cmp(&b.fact_id)));
         apply_limit(&mut results, filter.limit);
         Ok(results)
     }
@@ -315,6 +1848,12 @@ impl Store for InMemoryStore {
     fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
         let key = LtmKey::from(scope);
         let mut guard = self.facts.write().map_err(|_| StoreError::Poisoned)?;
+        let mut versions = self.fact_versions.write().map_err(|_| StoreError::Poisoned)?;
+        let history = versions.entry(key.clone()).or_default();
+        let latest = history.iter().rev().find(|version| version.fact.fact_id == fact.fact_id);
+        if let Some(version) = next_fact_version(latest, &fact, Utc::now()) {
+            history.push(version);
+        }
         let entry = guard.entry(key).or_insert_with(Vec::new);
         match entry.iter().position(|f| f.fact_id == fact.fact_id) {
             Some(idx) => entry[idx] = fact,
@@ -323,11 +1862,23 @@ impl Store for InMemoryStore {
         Ok(())
     }
 
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let guard = self.fact_versions.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard
+            .get(&LtmKey::from(scope))
+            .map(|history| {
+                let of_key = history.iter().filter(|version| version.fact.fact_key == fact_key);
+                of_key.cloned().collect()
+            })
+            .unwrap_or_default())
+    }
+
     fn list_episodes(
         &self,
         scope: &Scope,
         filter: EpisodeFilter,
     ) -> StoreResult<Vec<engram_types::Episode>> {
+        let filter = filter.with_tag_forms();
         let key = LtmKey::from(scope);
         let guard = self.episodes.read().map_err(|_| StoreError::Poisoned)?;
         let mut results: Vec<engram_types::Episode> = guard
@@ -335,34 +1886,7 @@ impl Store for InMemoryStore {
             .cloned()
             .unwrap_or_default()
             .into_iter()
-            .filter(|e| match &filter.time_range {
-                Some(range) => {
-                    let start_ok = range.start.map(|s| e.time_range.start >= s).unwrap_or(true);
-                    let end_ok = range
-                        .end
-                        .map(|end| {
-                            let episode_end = e.time_range.end.unwrap_or(e.time_range.start);
-                            episode_end <= end
-                        })
-                        .unwrap_or(true);
-                    start_ok && end_ok
-                }
-                None => true,
-            })
-            .filter(|e| {
-                if filter.tags.is_empty() {
-                    true
-                } else {
-                    e.tags.iter().any(|t| filter.tags.contains(t))
-                }
-            })
-            .filter(|e| {
-                if filter.entities.is_empty() {
-                    true
-                } else {
-                    e.entities.iter().any(|t| filter.entities.contains(t))
-                }
-            })
+            .filter(|e| filter.matches(e))
             .collect();
 
         apply_limit(&mut results, filter.limit);
@@ -407,6 +1931,74 @@ impl Store for InMemoryStore {
         Ok(())
     }
 
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        let key = LtmKey::from(scope);
+        let guard = self
+            .procedure_candidates
+            .read()
+            .map_err(|_| StoreError::Poisoned)?;
+        let mut results: Vec<ProcedureCandidate> = guard
+            .get(&key)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| match &filter.task_type {
+                Some(task_type) => &c.procedure.task_type == task_type,
+                None => true,
+            })
+            .filter(|c| match &filter.status {
+                Some(statuses) => statuses.contains(&c.status),
+                None => true,
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.candidate_id.cmp(&b.candidate_id))
+        });
+
+        apply_limit(&mut results, filter.limit);
+        Ok(results)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        let key = LtmKey::from(scope);
+        let guard = self
+            .procedure_candidates
+            .read()
+            .map_err(|_| StoreError::Poisoned)?;
+        Ok(guard
+            .get(&key)
+            .and_then(|items| items.iter().find(|c| c.candidate_id == candidate_id))
+            .cloned())
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        let key = LtmKey::from(scope);
+        let mut guard = self
+            .procedure_candidates
+            .write()
+            .map_err(|_| StoreError::Poisoned)?;
+        let entry = guard.entry(key).or_insert_with(Vec::new);
+        match entry.iter().position(|c| c.candidate_id == candidate.candidate_id) {
+            Some(idx) => entry[idx] = candidate,
+            None => entry.push(candidate),
+        }
+        Ok(())
+    }
+
     fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
         let key = RunKey::from(scope);
         let guard = self.insights.read().map_err(|_| StoreError::Poisoned)?;
@@ -432,6 +2024,17 @@ impl Store for InMemoryStore {
         Ok(())
     }
 
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        let key = RunKey::from(scope);
+        let mut guard = self.insights.write().map_err(|_| StoreError::Poisoned)?;
+        let Some(items) = guard.get_mut(&key) else {
+            return Ok(0);
+        };
+        let before = items.len();
+        items.retain(|item| item.expires_at != expires_at);
+        Ok(before - items.len())
+    }
+
     fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
         let key = RunKey::from(scope);
         let mut guard = self.context_builds.write().map_err(|_| StoreError::Poisoned)?;
@@ -450,6 +2053,344 @@ impl Store for InMemoryStore {
         apply_limit(&mut results, limit);
         Ok(results)
     }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        let key = RunKey::from(scope);
+        let guard = self.context_builds.read().map_err(|_| StoreError::Poisoned)?;
+        let mut results: Vec<ContextBuildSummary> = guard
+            .get(&key)
+            .map(|packets| packets.iter().map(ContextBuildSummary::from).collect())
+            .unwrap_or_default();
+        apply_limit(&mut results, limit);
+        Ok(results)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        let events = self.events.read().map_err(|_| StoreError::Poisoned)?;
+        let event_rows = events
+            .iter()
+            .filter(|e| e.scope.tenant_id == tenant_id)
+            .filter(|e| range.start.is_none_or(|start| e.ts >= start))
+            .filter(|e| range.end.is_none_or(|end| e.ts <= end))
+            .map(|e| (e.scope.user_id.clone(), e.ts.timestamp_millis().div_euclid(86_400_000), 1))
+            .collect();
+
+        let facts = self.facts.read().map_err(|_| StoreError::Poisoned)?;
+        let fact_rows = facts
+            .iter()
+            .filter(|(key, _)| key.tenant_id == tenant_id)
+            .map(|(key, facts)| (key.user_id.clone(), facts.len() as i64))
+            .collect();
+
+        let episodes = self.episodes.read().map_err(|_| StoreError::Poisoned)?;
+        let episode_rows = episodes
+            .iter()
+            .filter(|(key, _)| key.tenant_id == tenant_id)
+            .map(|(key, episodes)| (key.user_id.clone(), episodes.len() as i64))
+            .collect();
+
+        Ok(analytics::collect_user_activity(event_rows, fact_rows, episode_rows))
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        let guard = self.events.read().map_err(|_| StoreError::Poisoned)?;
+        let mut last_seen: HashMap<RunKey, (DateTime<Utc>, Scope)> = HashMap::new();
+        for event in guard
+            .iter()
+            .filter(|e| tenant_id.is_none_or(|tenant| e.scope.tenant_id == tenant))
+        {
+            let entry = last_seen
+                .entry(RunKey::from(&event.scope))
+                .or_insert_with(|| (event.ts, event.scope.clone()));
+            entry.0 = entry.0.max(event.ts);
+        }
+        let mut scopes: Vec<(DateTime<Utc>, Scope)> = last_seen.into_values().collect();
+        scopes.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| scope_sort_key(&a.1).cmp(&scope_sort_key(&b.1)))
+        });
+        let mut scopes: Vec<Scope> = scopes.into_iter().map(|(_, scope)| scope).collect();
+        apply_limit(&mut scopes, limit);
+        Ok(scopes)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        let key = LtmKey::from(scope);
+        let mut guard = self.suppressions.write().map_err(|_| StoreError::Poisoned)?;
+        let entries = guard.entry(key).or_default();
+        entries.retain(|s| s.item != item);
+        entries.push(Suppression {
+            item,
+            reason: reason.to_string(),
+            suppressed_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        let key = LtmKey::from(scope);
+        let mut guard = self.suppressions.write().map_err(|_| StoreError::Poisoned)?;
+        let Some(entries) = guard.get_mut(&key) else {
+            return Ok(false);
+        };
+        let before = entries.len();
+        entries.retain(|s| &s.item != item);
+        Ok(entries.len() != before)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        let key = LtmKey::from(scope);
+        let guard = self.suppressions.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.get(&key).cloned().unwrap_or_default())
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        let key = LtmKey::from(scope);
+        let ids = |kind: MemoryKind| -> HashSet<&str> {
+            items
+                .iter()
+                .filter(|item| item.kind == kind)
+                .map(|item| item.id.as_str())
+                .collect()
+        };
+        let (fact_ids, episode_ids) = (ids(MemoryKind::Fact), ids(MemoryKind::Episode));
+        let mut evicted = 0;
+        {
+            let mut guard = self.facts.write().map_err(|_| StoreError::Poisoned)?;
+            if let Some(facts) = guard.get_mut(&key) {
+                let before = facts.len();
+                facts.retain(|fact| !fact_ids.contains(fact.fact_id.as_str()));
+                evicted += before - facts.len();
+            }
+        }
+        {
+            let mut guard = self.episodes.write().map_err(|_| StoreError::Poisoned)?;
+            if let Some(episodes) = guard.get_mut(&key) {
+                let before = episodes.len();
+                episodes.retain(|episode| !episode_ids.contains(episode.episode_id.as_str()));
+                evicted += before - episodes.len();
+            }
+        }
+        let mut guard = self.suppressions.write().map_err(|_| StoreError::Poisoned)?;
+        if let Some(entries) = guard.get_mut(&key) {
+            entries.retain(|s| !items.contains(&s.item));
+        }
+        let mut guard = self.embeddings.write().map_err(|_| StoreError::Poisoned)?;
+        if let Some(entries) = guard.get_mut(&key) {
+            entries.retain(|embedding| !items.contains(&embedding.item));
+        }
+        Ok(evicted)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        Ok(self.remove_records(scope, item.kind, Some(&item.id), mode)? > 0)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        let mut removed = 0;
+        for kind in RecordKind::ALL {
+            removed += self.remove_records(scope, kind, None, mode)?;
+        }
+        Ok(removed)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        let key = LtmKey::from(scope);
+        let count = match kind {
+            RecordKind::Event => {
+                let guard = self.events.read().map_err(|_| StoreError::Poisoned)?;
+                guard.iter().filter(|event| scope_matches(&event.scope, scope)).count()
+            }
+            RecordKind::Fact => {
+                let guard = self.facts.read().map_err(|_| StoreError::Poisoned)?;
+                guard.get(&key).map_or(0, Vec::len)
+            }
+            RecordKind::Episode => {
+                let guard = self.episodes.read().map_err(|_| StoreError::Poisoned)?;
+                guard.get(&key).map_or(0, Vec::len)
+            }
+            RecordKind::Procedure => {
+                let guard = self.procedures.read().map_err(|_| StoreError::Poisoned)?;
+                guard.get(&key).map_or(0, Vec::len)
+            }
+            RecordKind::Insight => {
+                let guard = self.insights.read().map_err(|_| StoreError::Poisoned)?;
+                guard.get(&RunKey::from(scope)).map_or(0, Vec::len)
+            }
+        };
+        Ok(count)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        let guard = self.tombstones.read().map_err(|_| StoreError::Poisoned)?;
+        let mut tombstones = guard.get(&LtmKey::from(scope)).cloned().unwrap_or_default();
+        tombstones.reverse();
+        apply_limit(&mut tombstones, limit);
+        Ok(tombstones)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        let mut guard = self.embeddings.write().map_err(|_| StoreError::Poisoned)?;
+        let entries = guard.entry(LtmKey::from(scope)).or_default();
+        for embedding in embeddings {
+            entries.retain(|e| e.item != embedding.item || e.model != embedding.model);
+            entries.push(embedding);
+        }
+        Ok(())
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        let guard = self.embeddings.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard
+            .get(&LtmKey::from(scope))
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|embedding| embedding.model == model)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        let mut guard = self.embeddings.write().map_err(|_| StoreError::Poisoned)?;
+        let Some(entries) = guard.get_mut(&LtmKey::from(scope)) else {
+            return Ok(0);
+        };
+        let before = entries.len();
+        entries.retain(|embedding| embedding.model != model || !items.contains(&embedding.item));
+        Ok(before - entries.len())
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        let outcome = outcome::outcome_for_run(scope, outcome)?;
+        let key = LtmKey::from(scope);
+        let mut guard = self.run_outcomes.write().map_err(|_| StoreError::Poisoned)?;
+        let entries = guard.entry(key).or_default();
+        entries.retain(|o| o.session_id != outcome.session_id || o.run_id != outcome.run_id);
+        entries.push(outcome);
+        Ok(())
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        let key = LtmKey::from(scope);
+        let guard = self.run_outcomes.read().map_err(|_| StoreError::Poisoned)?;
+        let mut outcomes = guard.get(&key).cloned().unwrap_or_default();
+        outcomes.sort_by(|a, b| {
+            b.recorded_at
+                .cmp(&a.recorded_at)
+                .then_with(|| a.run_id.cmp(&b.run_id))
+        });
+        apply_limit(&mut outcomes, limit);
+        Ok(outcomes)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        credibility.check()?;
+        let mut guard = self.source_credibility.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert(tenant_id.to_string(), credibility);
+        Ok(())
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        let guard = self.source_credibility.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.get(tenant_id).cloned())
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        locale.check()?;
+        let mut guard = self.user_locales.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert((tenant_id.to_string(), user_id.to_string()), locale);
+        Ok(())
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        let guard = self.user_locales.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.get(&(tenant_id.to_string(), user_id.to_string())).cloned())
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        let mut guard = self.changes.write().map_err(|_| StoreError::Poisoned)?;
+        let seq = guard.len() as u64 + 1;
+        guard.push(Change {
+            seq,
+            ts: Utc::now(),
+            correlation_id: correlation_id(),
+            op,
+        });
+        Ok(seq)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        let guard = self.change_cursors.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.get(name).copied().unwrap_or(0))
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        let mut guard = self.change_cursors.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert(name.to_string(), seq);
+        Ok(())
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        let guard = self.changes.read().map_err(|_| StoreError::Poisoned)?;
+        let mut results: Vec<Change> = guard
+            .iter()
+            .filter(|change| change.seq > after_seq)
+            .cloned()
+            .collect();
+        apply_limit(&mut results, limit);
+        Ok(results)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        let guard = self.changes.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.iter().map(|change| change.seq).max().unwrap_or(0))
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        // Embeddings are kept for similarity recall; text search and tag filters scan.
+        StoreCapabilities {
+            vector_search: true,
+            ..StoreCapabilities::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -509,6 +2450,16 @@ impl From<&Scope> for LtmKey {
     }
 }
 
+fn scope_sort_key(scope: &Scope) -> [&str; 5] {
+    [
+        &scope.tenant_id,
+        &scope.user_id,
+        &scope.agent_id,
+        &scope.session_id,
+        &scope.run_id,
+    ]
+}
+
 fn scope_matches(a: &Scope, b: &Scope) -> bool {
     a.tenant_id == b.tenant_id
         && a.user_id == b.user_id
@@ -517,10 +2468,95 @@ fn scope_matches(a: &Scope, b: &Scope) -> bool {
         && a.run_id == b.run_id
 }
 
-fn apply_limit<T>(items: &mut Vec<T>, limit: Option<usize>) {
-    if let Some(n) = limit {
-        if items.len() > n {
-            items.truncate(n);
+/// Index tables a SQL store can work without. A database provisioned by an older
+/// engram, or by a role that may not create tables, can lack them; the store then
+/// skips maintaining them and matches tags and entities in process instead.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OptionalTables {
+    pub(crate) event_index: bool,
+    pub(crate) episode_index: bool,
+    /// Full-text index behind [`Store::search_events`] and [`Store::search_episodes`];
+    /// backends detect it themselves since it is not always a table.
+    pub(crate) text_index: bool,
+}
+
+impl OptionalTables {
+    const EVENT_INDEX: [&str; 2] = ["event_tags", "event_entities"];
+    const EPISODE_INDEX: [&str; 2] = ["episode_tags", "episode_entities"];
+
+    /// Every optional table, in the order backends probe for them.
+    pub(crate) const NAMES: [&str; 4] = [
+        Self::EVENT_INDEX[0],
+        Self::EVENT_INDEX[1],
+        Self::EPISODE_INDEX[0],
+        Self::EPISODE_INDEX[1],
+    ];
+
+    pub(crate) fn from_present(present: &[String]) -> Self {
+        let all = |names: [&str; 2]| names.iter().all(|name| present.iter().any(|p| p == name));
+        let tables = Self {
+            event_index: all(Self::EVENT_INDEX),
+            episode_index: all(Self::EPISODE_INDEX),
+            text_index: false,
+        };
+        if !tables.event_index {
+            warn!("event_tags/event_entities missing; event tag and entity filters scan events");
+        }
+        if !tables.episode_index {
+            warn!("episode_tags/episode_entities missing; episode tag and entity filters scan episodes");
         }
+        tables
+    }
+}
+
+/// Answers a tag or entity filter without the event index tables: `fetch` reads by
+/// time range alone and tags and entities are matched here.
+pub(crate) fn find_events_unindexed(
+    filter: EventFilter,
+    fetch: impl FnOnce(EventFilter) -> StoreResult<Vec<Event>>,
+) -> StoreResult<Vec<Event>> {
+    let mut events = fetch(EventFilter {
+        time_range: filter.time_range.clone(),
+        ..EventFilter::default()
+    })?;
+    events.retain(|event| filter.matches(event));
+    apply_limit(&mut events, filter.limit);
+    Ok(events)
+}
+
+/// The episode counterpart of [`find_events_unindexed`].
+pub(crate) fn list_episodes_unindexed(
+    filter: EpisodeFilter,
+    fetch: impl FnOnce(EpisodeFilter) -> StoreResult<Vec<engram_types::Episode>>,
+) -> StoreResult<Vec<engram_types::Episode>> {
+    let mut episodes = fetch(EpisodeFilter {
+        time_range: filter.time_range.clone(),
+        ..EpisodeFilter::default()
+    })?;
+    episodes.retain(|episode| filter.matches(episode));
+    apply_limit(&mut episodes, filter.limit);
+    Ok(episodes)
+}
+
+/// Removes the `items` that `remove` picks, returning their ids and JSON.
+fn take_records<T: Serialize>(
+    items: &mut Vec<T>,
+    remove: impl Fn(&T) -> bool,
+    id: impl Fn(&T) -> &str,
+) -> StoreResult<Vec<(String, Value)>> {
+    let taken = items
+        .iter()
+        .filter(|item| remove(item))
+        .map(|item| Ok((id(item).to_string(), serde_json::to_value(item)?)))
+        .collect::<StoreResult<Vec<_>>>()?;
+    items.retain(|item| !remove(item));
+    Ok(taken)
+}
+
+fn apply_limit<T>(items: &mut Vec<T>, limit: Option<usize>) {
+    if let Some(n) = limit
+        && items.len() > n
+    {
+        items.truncate(n);
     }
 }
@@ -0,0 +1,543 @@
+use chrono::Utc;
+use engram_types::{CompressionLevel, Episode, Fact, KeyQuote, Scope, TimeRange, WorkingState};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::debug;
+
+use crate::composer::parse_event_payload;
+use crate::{
+    refresh_period_summaries, replay_working_state, EpisodeFilter, Event, EventKind, FactFilter,
+    MemoryKind, MemoryRef, StmState, Store, StoreError, StoreResult, CHECKPOINT_EVENT_KIND,
+    RUN_TAG_PREFIX,
+};
+
+/// Event kind journaled by [`begin_run`].
+pub const RUN_START_EVENT_KIND: &str = "run_start";
+/// Event kind journaled by [`end_run`].
+pub const RUN_END_EVENT_KIND: &str = "run_end";
+/// `expires_at` marker of insights that only live until their run ends.
+pub const RUN_END_EXPIRY: &str = "run_end";
+
+const MAX_HIGHLIGHTS: usize = 3;
+const MAX_HIGHLIGHT_CHARS: usize = 200;
+const MAX_DECISION_KEY_CHARS: usize = 48;
+const MAX_STM_QUOTES: usize = 10;
+const MAX_SUMMARY_LINES: usize = 20;
+const REBUILT_REASON: &str = "rebuilt from the event log";
+
+#[derive(Debug, Clone)]
+pub struct RunEndOptions {
+    /// Extra tags for the run's episode, next to `outcome:<outcome>`.
+    pub tags: Vec<String>,
+    /// Whether the working state's decisions are written as facts.
+    pub promote_decisions: bool,
+    /// Key prefix of promoted decision facts.
+    pub decision_key_prefix: String,
+    /// Whether the daily and weekly summaries covering the run's episode are refreshed;
+    /// see [`refresh_period_summaries`].
+    pub period_summaries: bool,
+}
+
+impl Default for RunEndOptions {
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            promote_decisions: false,
+            decision_key_prefix: "decision.".to_string(),
+            period_summaries: false,
+        }
+    }
+}
+
+/// What [`end_run`] did to the run's memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunEndReport {
+    pub outcome: String,
+    /// The episode consolidated from the run's events, if it had any.
+    #[serde(default)]
+    pub episode: Option<Episode>,
+    #[serde(default)]
+    pub expired_insights: usize,
+    #[serde(default)]
+    pub promoted_facts: Vec<Fact>,
+}
+
+/// What [`rebuild_derived_memory`] regenerated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebuildReport {
+    pub stm: StmState,
+    /// The run's episode, consolidated again when the run has ended.
+    #[serde(default)]
+    pub episode: Option<Episode>,
+    /// Episodes of the run suppressed in favour of `episode`.
+    #[serde(default)]
+    pub replaced_episodes: Vec<String>,
+    #[serde(default)]
+    pub promoted_facts: Vec<Fact>,
+}
+
+/// Journals a `run_start` event in `scope`. A run can only be begun once.
+pub fn begin_run<S: Store + ?Sized>(store: &S, scope: &Scope) -> StoreResult<Event> {
+    let events = store.get_events_since(scope, 0, None)?;
+    if events.iter().any(|event| is_kind(event, RUN_START_EVENT_KIND)) {
+        return Err(StoreError::InvalidInput(format!(
+            "run {} has already begun",
+            scope.run_id
+        )));
+    }
+    let event = Event::new(
+        scope.clone(),
+        EventKind::Custom(RUN_START_EVENT_KIND.to_string()),
+        json!({}),
+    );
+    store.append_event(event.clone())?;
+    Ok(event)
+}
+
+/// Closes a run: expires its `run_end` insights, consolidates its events into one
+/// episode tagged `outcome:<outcome>` and `run:<run_id>`, optionally promotes the working state's
+/// decisions into facts, and journals a `run_end` event. A run can only be ended
+/// once, which keeps the episode and promoted facts from being written twice.
+pub fn end_run<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    outcome: &str,
+    options: &RunEndOptions,
+) -> StoreResult<RunEndReport> {
+    let outcome = outcome.trim();
+    if outcome.is_empty() {
+        return Err(StoreError::InvalidInput(
+            "run outcome must not be empty".to_string(),
+        ));
+    }
+    let events = store.get_events_since(scope, 0, None)?;
+    if events.iter().any(|event| is_kind(event, RUN_END_EVENT_KIND)) {
+        return Err(StoreError::InvalidInput(format!(
+            "run {} has already ended",
+            scope.run_id
+        )));
+    }
+    let state = store.get_working_state(scope)?.unwrap_or_default();
+
+    let expired_insights = store.expire_insights(scope, RUN_END_EXPIRY)?;
+
+    let events: Vec<Event> = events.into_iter().filter(|event| !is_marker(event)).collect();
+    let episode = run_episode(scope, &events, &state.goal, &state.decisions, outcome, options);
+    if let Some(episode) = &episode {
+        store.append_episode(scope, episode.clone())?;
+        if options.period_summaries {
+            refresh_period_summaries(store, scope, episode.time_range.start)?;
+        }
+    }
+
+    let mut promoted_facts = Vec::new();
+    if options.promote_decisions {
+        for decision in &state.decisions {
+            let Some(fact) = decision_fact(scope, decision, &state, options) else {
+                continue;
+            };
+            store.upsert_fact(scope, fact.clone())?;
+            promoted_facts.push(fact);
+        }
+    }
+
+    let mut end = Event::new(
+        scope.clone(),
+        EventKind::Custom(RUN_END_EVENT_KIND.to_string()),
+        json!({
+            "outcome": outcome,
+            "episode_id": episode.as_ref().map(|episode| episode.episode_id.clone()),
+            "expired_insights": expired_insights,
+            "promoted_fact_ids": promoted_facts.iter().map(|fact| fact.fact_id.clone()).collect::<Vec<_>>(),
+        }),
+    );
+    end.tags = vec![format!("outcome:{}", outcome)];
+    store.append_event(end)?;
+    debug!(
+        "ended run {} ({}): {} insights expired, {} decisions promoted",
+        scope.run_id,
+        outcome,
+        expired_insights,
+        promoted_facts.len()
+    );
+
+    Ok(RunEndReport {
+        outcome: outcome.to_string(),
+        episode,
+        expired_insights,
+        promoted_facts,
+    })
+}
+
+/// Regenerates the memory derived from a run's event log, as a recovery path when
+/// that memory got corrupted or the consolidation logic changed: STM is rebuilt from
+/// the run's messages and, if the run has ended, its episode and promoted decisions
+/// are consolidated again the way [`end_run`] did, with the same `options`.
+///
+/// The working state comes from the run's `state_patch` events when it was journaled
+/// (see [`StatePatchJournal`](crate::StatePatchJournal)), otherwise from the store.
+/// Earlier episodes of the run are suppressed with reason "rebuilt from the event log"
+/// rather than evicted; promoted facts keep their ids and are replaced.
+pub fn rebuild_derived_memory<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    options: &RunEndOptions,
+) -> StoreResult<RebuildReport> {
+    let events = store.get_events_since(scope, 0, None)?;
+    let state = match replay_working_state(&events)? {
+        Some(state) => state,
+        None => store.get_working_state(scope)?.unwrap_or_default(),
+    };
+
+    let stm = run_stm(&events);
+    store.update_stm(scope, stm.clone())?;
+
+    let mut report = RebuildReport {
+        stm,
+        episode: None,
+        replaced_episodes: Vec::new(),
+        promoted_facts: Vec::new(),
+    };
+    let Some(end) = events.iter().find(|event| is_kind(event, RUN_END_EVENT_KIND)) else {
+        debug!("rebuilt STM of run {}, which has not ended", scope.run_id);
+        return Ok(report);
+    };
+    let outcome = end.payload["outcome"].as_str().unwrap_or_default();
+
+    let suppressed: Vec<String> = store
+        .list_suppressions(scope)?
+        .into_iter()
+        .filter(|suppression| suppression.item.kind == MemoryKind::Episode)
+        .map(|suppression| suppression.item.id)
+        .collect();
+    let run_tag = format!("{}{}", RUN_TAG_PREFIX, scope.run_id);
+    let mut previous: Vec<String> = store
+        .list_episodes(
+            scope,
+            EpisodeFilter {
+                tags: vec![run_tag],
+                ..EpisodeFilter::default()
+            },
+        )?
+        .into_iter()
+        .map(|episode| episode.episode_id)
+        .collect();
+    // Runs ended before episodes carried the run tag are found through `run_end`.
+    if let Some(episode_id) = end.payload["episode_id"].as_str()
+        && !previous.iter().any(|id| id == episode_id)
+    {
+        previous.push(episode_id.to_string());
+    }
+    for episode_id in previous {
+        if suppressed.contains(&episode_id) {
+            continue;
+        }
+        let item = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode_id.clone(),
+        };
+        store.suppress_memory(scope, item, REBUILT_REASON)?;
+        report.replaced_episodes.push(episode_id);
+    }
+
+    let consolidated: Vec<Event> = events
+        .iter()
+        .filter(|event| event.seq < end.seq && !is_marker(event))
+        .cloned()
+        .collect();
+    report.episode = run_episode(
+        scope,
+        &consolidated,
+        &state.goal,
+        &state.decisions,
+        outcome,
+        options,
+    );
+    if let Some(episode) = &report.episode {
+        store.append_episode(scope, episode.clone())?;
+        if options.period_summaries {
+            refresh_period_summaries(store, scope, episode.time_range.start)?;
+        }
+    }
+
+    if options.promote_decisions {
+        let facts = store.list_facts(scope, FactFilter::default())?;
+        for decision in &state.decisions {
+            let Some(mut fact) = decision_fact(scope, decision, &state, options) else {
+                continue;
+            };
+            if let Some(existing) = facts.iter().find(|known| known.fact_key == fact.fact_key) {
+                fact.fact_id = existing.fact_id.clone();
+            }
+            store.upsert_fact(scope, fact.clone())?;
+            report.promoted_facts.push(fact);
+        }
+    }
+    debug!(
+        "rebuilt derived memory of run {}: {} episodes replaced, {} decisions promoted",
+        scope.run_id,
+        report.replaced_episodes.len(),
+        report.promoted_facts.len()
+    );
+    Ok(report)
+}
+
+/// STM of a run from scratch: its latest messages as key quotes and the earlier ones,
+/// clipped, as the rolling summary.
+pub(crate) fn run_stm(events: &[Event]) -> StmState {
+    let mut quotes: Vec<KeyQuote> = events
+        .iter()
+        .filter(|event| event.kind == EventKind::Message)
+        .filter_map(|event| {
+            let (quote, role) = parse_event_payload(&event.payload)?;
+            Some(KeyQuote {
+                evidence_id: event.event_id.clone(),
+                quote,
+                role,
+                ts: Some(event.ts),
+            })
+        })
+        .collect();
+    let earlier: Vec<KeyQuote> = quotes
+        .drain(..quotes.len().saturating_sub(MAX_STM_QUOTES))
+        .collect();
+    let rolling_summary = earlier[earlier.len().saturating_sub(MAX_SUMMARY_LINES)..]
+        .iter()
+        .map(|quote| clip(&quote.quote))
+        .collect::<Vec<_>>()
+        .join("\n");
+    StmState {
+        rolling_summary,
+        key_quotes: quotes,
+    }
+}
+
+fn run_episode(
+    scope: &Scope,
+    events: &[Event],
+    goal: &str,
+    decisions: &[String],
+    outcome: &str,
+    options: &RunEndOptions,
+) -> Option<Episode> {
+    let first = events.first()?;
+    let last = events.last()?;
+
+    let mut episode = Episode::new(if goal.trim().is_empty() {
+        format!("Run {}", scope.run_id)
+    } else {
+        goal.trim().to_string()
+    });
+    episode.time_range = TimeRange {
+        start: first.ts,
+        end: Some(last.ts),
+    };
+    episode.compression_level = CompressionLevel::Raw;
+    episode.highlights = decisions.to_vec();
+    let messages: Vec<String> = events
+        .iter()
+        .filter(|event| event.kind == EventKind::Message)
+        .filter_map(|event| parse_event_payload(&event.payload))
+        .map(|(content, _)| clip(&content))
+        .collect();
+    episode
+        .highlights
+        .extend(messages[messages.len().saturating_sub(MAX_HIGHLIGHTS)..].iter().cloned());
+
+    episode.tags.push(format!("outcome:{}", outcome));
+    episode.tags.push(format!("{}{}", RUN_TAG_PREFIX, scope.run_id));
+    for tag in options.tags.iter().chain(events.iter().flat_map(|event| event.tags.iter())) {
+        if !episode.tags.contains(tag) {
+            episode.tags.push(tag.clone());
+        }
+    }
+    for entity in events.iter().flat_map(|event| event.entities.iter()) {
+        if !episode.entities.contains(entity) {
+            episode.entities.push(entity.clone());
+        }
+    }
+    episode.sources = events.iter().map(|event| event.event_id.clone()).collect();
+    Some(episode)
+}
+
+fn decision_fact(
+    scope: &Scope,
+    decision: &str,
+    state: &WorkingState,
+    options: &RunEndOptions,
+) -> Option<Fact> {
+    let slug = slugify(decision);
+    if slug.is_empty() {
+        return None;
+    }
+    let mut fact = Fact::new(
+        format!("{}{}", options.decision_key_prefix, slug),
+        json!(decision),
+    );
+    fact.validity.valid_from = Some(Utc::now());
+    fact.sources = state
+        .tool_evidence
+        .iter()
+        .map(|evidence| evidence.evidence_id.clone())
+        .collect();
+    fact.notes = format!("decided in run {}", scope.run_id);
+    Some(fact)
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for ch in text.trim().chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+        if slug.chars().count() >= MAX_DECISION_KEY_CHARS {
+            break;
+        }
+    }
+    slug.trim_end_matches('_').to_string()
+}
+
+pub(crate) fn clip(text: &str) -> String {
+    if text.chars().count() <= MAX_HIGHLIGHT_CHARS {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(MAX_HIGHLIGHT_CHARS).collect();
+    clipped.push('…');
+    clipped
+}
+
+fn is_kind(event: &Event, kind: &str) -> bool {
+    matches!(&event.kind, EventKind::Custom(name) if name == kind)
+}
+
+pub(crate) fn is_marker(event: &Event) -> bool {
+    is_kind(event, RUN_START_EVENT_KIND)
+        || is_kind(event, RUN_END_EVENT_KIND)
+        || is_kind(event, CHECKPOINT_EVENT_KIND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EpisodeFilter, FactFilter, InMemoryStore, InsightFilter, WorkingStatePatch};
+    use engram_types::{InsightItem, InsightType};
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn end_run_consolidates_and_expires() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        begin_run(&store, &scope).unwrap();
+        assert!(begin_run(&store, &scope).is_err());
+
+        store
+            .append_event(Event::new(
+                scope.clone(),
+                EventKind::Message,
+                json!({ "role": "user", "content": "book the Lisbon flight" }),
+            ))
+            .unwrap();
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("Book travel".to_string()),
+                    decisions: Some(vec!["Fly TAP, not Ryanair".to_string()]),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        let mut scratch = InsightItem::new(InsightType::Hypothesis, "user prefers aisle seats");
+        scratch.expires_at = RUN_END_EXPIRY.to_string();
+        store.append_insight(&scope, scratch).unwrap();
+        store
+            .append_insight(&scope, InsightItem::new(InsightType::Pattern, "books early"))
+            .unwrap();
+
+        let report = end_run(
+            &store,
+            &scope,
+            "success",
+            &RunEndOptions {
+                promote_decisions: true,
+                ..RunEndOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.expired_insights, 1);
+        assert_eq!(store.list_insights(&scope, InsightFilter::default()).unwrap().len(), 1);
+
+        let episodes = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].summary, "Book travel");
+        assert!(episodes[0].tags.contains(&"outcome:success".to_string()));
+        assert_eq!(episodes[0].sources.len(), 1);
+
+        let facts = store.list_facts(&scope, FactFilter::default()).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].fact_key, "decision.fly_tap_not_ryanair");
+
+        assert!(end_run(&store, &scope, "success", &RunEndOptions::default()).is_err());
+    }
+
+    #[test]
+    fn rebuild_regenerates_stm_episode_and_facts() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        store
+            .append_event(Event::new(
+                scope.clone(),
+                EventKind::Message,
+                json!({ "role": "user", "content": "book the Lisbon flight" }),
+            ))
+            .unwrap();
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("Book travel".to_string()),
+                    decisions: Some(vec!["Fly TAP".to_string()]),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        let options = RunEndOptions {
+            promote_decisions: true,
+            ..RunEndOptions::default()
+        };
+        let ended = end_run(&store, &scope, "success", &options).unwrap();
+        let original = ended.episode.unwrap().episode_id;
+
+        store.update_stm(&scope, StmState::default()).unwrap();
+        let mut corrupted = ended.promoted_facts[0].clone();
+        corrupted.value = json!(null);
+        store.upsert_fact(&scope, corrupted).unwrap();
+
+        let report = rebuild_derived_memory(&store, &scope, &options).unwrap();
+        assert_eq!(report.stm.key_quotes.len(), 1);
+        let stm = store.get_stm(&scope).unwrap().unwrap();
+        assert_eq!(stm.key_quotes[0].quote, "book the Lisbon flight");
+        assert_eq!(report.replaced_episodes, vec![original]);
+        let rebuilt = report.episode.unwrap();
+        assert_eq!(rebuilt.summary, "Book travel");
+        assert_eq!(rebuilt.sources.len(), 1);
+        let facts = store.list_facts(&scope, FactFilter::default()).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].value, json!("Fly TAP"));
+
+        let again = rebuild_derived_memory(&store, &scope, &options).unwrap();
+        assert_eq!(again.replaced_episodes, vec![rebuilt.episode_id]);
+        assert_eq!(store.list_suppressions(&scope).unwrap().len(), 2);
+    }
+}
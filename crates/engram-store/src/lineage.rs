@@ -0,0 +1,123 @@
+use engram_types::{new_ulid, InsightItem, Scope, ValidationState};
+use std::collections::HashSet;
+use tracing::debug;
+
+use crate::{scope_matches, InsightFilter, Store, StoreError, StoreResult};
+
+/// Copies the unresolved insights of run `from` (still unvalidated or under test)
+/// into run `to` under new ids, each pointing back through `parent_insight_id`.
+///
+/// Insights already carried into `to` are skipped, so calling this again at the
+/// start of the same run is harmless. Returns the copies that were written.
+pub fn carry_forward_insights<S: Store + ?Sized>(
+    store: &S,
+    from: &Scope,
+    to: &Scope,
+) -> StoreResult<Vec<InsightItem>> {
+    if scope_matches(from, to) {
+        return Err(StoreError::InvalidInput(
+            "insights can only be carried into another run".to_string(),
+        ));
+    }
+
+    let carried: HashSet<String> = store
+        .list_insights(to, InsightFilter::default())?
+        .into_iter()
+        .filter_map(|insight| insight.parent_insight_id)
+        .collect();
+    let unresolved = store.list_insights(
+        from,
+        InsightFilter {
+            validation_state: Some(vec![ValidationState::Unvalidated, ValidationState::Testing]),
+            limit: None,
+        },
+    )?;
+
+    let mut copies = Vec::new();
+    for insight in unresolved {
+        if carried.contains(&insight.id) {
+            continue;
+        }
+        let copy = InsightItem {
+            id: new_ulid(),
+            parent_insight_id: Some(insight.id.clone()),
+            ..insight
+        };
+        store.append_insight(to, copy.clone())?;
+        copies.push(copy);
+    }
+    debug!("carried {} insights forward into run {}", copies.len(), to.run_id);
+    Ok(copies)
+}
+
+/// Follows `parent_insight_id` from `insight_id` back through the given runs, newest
+/// first, and returns the chain starting with the insight itself. Stops at the first
+/// ancestor that is not found in any of `runs`.
+pub fn insight_lineage<S: Store + ?Sized>(
+    store: &S,
+    runs: &[Scope],
+    insight_id: &str,
+) -> StoreResult<Vec<InsightItem>> {
+    let mut known = Vec::new();
+    for run in runs {
+        known.extend(store.list_insights(run, InsightFilter::default())?);
+    }
+
+    let mut chain: Vec<InsightItem> = Vec::new();
+    let mut next = Some(insight_id.to_string());
+    while let Some(id) = next {
+        if chain.iter().any(|insight| insight.id == id) {
+            break;
+        }
+        let Some(insight) = known.iter().find(|insight| insight.id == id) else {
+            break;
+        };
+        next = insight.parent_insight_id.clone();
+        chain.push(insight.clone());
+    }
+    if chain.is_empty() {
+        return Err(StoreError::NotFound);
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use engram_types::InsightType;
+
+    fn run_scope(run_id: &str) -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: run_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn carries_unresolved_hypotheses_into_next_runs() {
+        let store = InMemoryStore::new();
+        let (run1, run2, run3) = (run_scope("run1"), run_scope("run2"), run_scope("run3"));
+        let open = InsightItem::new(InsightType::Hypothesis, "cache misses cause the latency");
+        let settled = InsightItem {
+            validation_state: ValidationState::Rejected,
+            ..InsightItem::new(InsightType::Hypothesis, "the network is slow")
+        };
+        store.append_insight(&run1, open.clone()).unwrap();
+        store.append_insight(&run1, settled).unwrap();
+
+        let copies = carry_forward_insights(&store, &run1, &run2).unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].parent_insight_id.as_deref(), Some(open.id.as_str()));
+        assert_eq!(copies[0].statement, open.statement);
+        assert!(carry_forward_insights(&store, &run1, &run2).unwrap().is_empty());
+
+        let grandchild = carry_forward_insights(&store, &run2, &run3).unwrap();
+        let chain = insight_lineage(&store, &[run3, run2, run1], &grandchild[0].id).unwrap();
+        let ids: Vec<&str> = chain.iter().map(|insight| insight.id.as_str()).collect();
+        assert_eq!(ids, [grandchild[0].id.as_str(), copies[0].id.as_str(), open.id.as_str()]);
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, SecondsFormat, TimeZone, Utc};
+use engram_types::MemoryPacket;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{StoreError, StoreResult};
+
+/// Packet fields holding timestamps that [`render_packet`] shows in the user's zone.
+const TIMESTAMP_KEYS: &[&str] = &[
+    "generated_at",
+    "ts",
+    "start",
+    "end",
+    "valid_from",
+    "valid_to",
+    "created_at",
+    "updated_at",
+    "reviewed_at",
+];
+
+/// How a user reads time, set with
+/// [`Store::set_user_locale`](crate::Store::set_user_locale).
+///
+/// `timezone` is a fixed UTC offset (`UTC`, `Z`, `+02:00`, `-0530`); users in zones
+/// with daylight saving time need their preference updated when the offset changes.
+/// Packets built for the user carry both fields in their meta, render timestamps in
+/// the offset, and count `episode_time_window_days` in local calendar days.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserLocale {
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// BCP 47 tag such as `en-US`, passed through to the packet for formatting.
+    #[serde(default)]
+    pub locale: String,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+impl Default for UserLocale {
+    fn default() -> Self {
+        Self {
+            timezone: default_timezone(),
+            locale: String::new(),
+        }
+    }
+}
+
+impl UserLocale {
+    pub fn new(timezone: impl Into<String>, locale: impl Into<String>) -> Self {
+        Self {
+            timezone: timezone.into(),
+            locale: locale.into(),
+        }
+    }
+
+    /// The timezone as an offset from UTC.
+    pub fn utc_offset(&self) -> StoreResult<FixedOffset> {
+        parse_utc_offset(&self.timezone)
+    }
+
+    /// Rejects timezones that are not a UTC offset; backends call this before storing.
+    pub(crate) fn check(&self) -> StoreResult<()> {
+        self.utc_offset().map(|_| ())
+    }
+
+    /// Start of the local calendar day `days` days before the day of `now`.
+    pub fn days_ago_midnight(&self, now: DateTime<Utc>, days: i64) -> StoreResult<DateTime<Utc>> {
+        let offset = self.utc_offset()?;
+        let day = now.with_timezone(&offset).date_naive() - Duration::days(days);
+        let midnight = offset
+            .from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .single()
+            .ok_or_else(|| StoreError::InvalidInput(format!("no midnight on {}", day)))?;
+        Ok(midnight.with_timezone(&Utc))
+    }
+}
+
+fn parse_utc_offset(timezone: &str) -> StoreResult<FixedOffset> {
+    let invalid = || {
+        StoreError::InvalidInput(format!(
+            "timezone must be UTC or an offset like +02:00, got {:?}",
+            timezone
+        ))
+    };
+    let timezone = timezone.trim();
+    if timezone.eq_ignore_ascii_case("utc") || timezone == "Z" {
+        return Ok(FixedOffset::east_opt(0).expect("zero offset"));
+    }
+    let (sign, rest) = match timezone.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes > 59 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+/// The packet as JSON with its timestamps in the timezone recorded in its meta, e.g.
+/// `2024-05-01T09:30:00+02:00`. The result still deserializes into a
+/// [`MemoryPacket`]; packets without a timezone are rendered unchanged.
+pub fn render_packet(packet: &MemoryPacket) -> StoreResult<Value> {
+    let mut value = serde_json::to_value(packet)?;
+    let offset = match packet.meta.timezone.as_deref() {
+        Some(timezone) => parse_utc_offset(timezone)?,
+        None => return Ok(value),
+    };
+    localize_timestamps(&mut value, &offset);
+    Ok(value)
+}
+
+fn localize_timestamps(value: &mut Value, offset: &FixedOffset) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if let Value::String(text) = field
+                    && TIMESTAMP_KEYS.contains(&key.as_str())
+                    && let Ok(ts) = DateTime::parse_from_rfc3339(text)
+                {
+                    *text = ts
+                        .with_timezone(offset)
+                        .to_rfc3339_opts(SecondsFormat::AutoSi, false);
+                } else {
+                    localize_timestamps(field, offset);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                localize_timestamps(item, offset);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore, Store};
+    use engram_types::{CompressionLevel, Episode, Purpose, Scope, TimeRange};
+
+    #[test]
+    fn locale_drives_day_windows_and_rendering() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        assert!(store
+            .set_user_locale("default", "user1", UserLocale::new("Mars/Olympus", "en"))
+            .is_err());
+        let locale = UserLocale::new("+09:00", "ja-JP");
+        store.set_user_locale("default", "user1", locale.clone()).unwrap();
+        assert_eq!(store.get_user_locale("default", "user1").unwrap(), Some(locale.clone()));
+        assert!(store.get_user_locale("default", "user2").unwrap().is_none());
+
+        // Yesterday in Tokyo starts at local midnight, whatever the time is in UTC.
+        let now = Utc::now();
+        let start = locale.days_ago_midnight(now, 1).unwrap();
+        let local = start.with_timezone(&locale.utc_offset().unwrap());
+        assert_eq!(local.time(), NaiveTime::MIN);
+        assert!(now - start >= Duration::days(1) && now - start < Duration::days(2));
+
+        let episode = |id: &str, start: DateTime<Utc>| Episode {
+            episode_id: id.to_string(),
+            time_range: TimeRange { start, end: None },
+            summary: id.to_string(),
+            highlights: Vec::new(),
+            tags: Vec::new(),
+            entities: Vec::new(),
+            sources: Vec::new(),
+            compression_level: CompressionLevel::Raw,
+            recency_score: None,
+            lang: None,
+        };
+        store
+            .append_episode(&scope, episode("before", start - Duration::minutes(1)))
+            .unwrap();
+        store
+            .append_episode(&scope, episode("yesterday", start + Duration::minutes(1)))
+            .unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.policy.episode_time_window_days = 1;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.meta.timezone.as_deref(), Some("+09:00"));
+        assert_eq!(packet.meta.locale.as_deref(), Some("ja-JP"));
+        let ids: Vec<&str> = packet
+            .long_term
+            .episodes
+            .iter()
+            .map(|episode| episode.episode_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["yesterday"]);
+
+        let rendered = render_packet(&packet).unwrap();
+        let generated = rendered["meta"]["generated_at"].as_str().unwrap();
+        assert!(generated.ends_with("+09:00"), "{}", generated);
+        let episode_start = rendered["long_term"]["episodes"][0]["time_range"]["start"]
+            .as_str()
+            .unwrap();
+        assert!(episode_start.ends_with("+09:00"), "{}", episode_start);
+        let parsed: MemoryPacket = serde_json::from_value(rendered).unwrap();
+        assert_eq!(parsed.meta.generated_at, packet.meta.generated_at);
+    }
+}
@@ -0,0 +1,669 @@
+use engram_types::{
+    Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult, Suppression,
+    TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+#[derive(Debug, Clone)]
+pub struct MeteringOptions {
+    /// Bytes are measured on one call in this many and scaled up, which keeps the cost
+    /// of encoding results off most calls; 1 measures every call, 0 none.
+    pub byte_sample_every: u64,
+}
+
+impl Default for MeteringOptions {
+    fn default() -> Self {
+        Self {
+            byte_sample_every: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCounts {
+    pub calls: u64,
+    pub errors: u64,
+    /// Estimated bytes read or written by the calls.
+    pub bytes: u64,
+}
+
+/// What one tenant cost the backend since metering started or was last reset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub reads: u64,
+    pub writes: u64,
+    /// Estimated size of what reads returned, as JSON; a proxy for the rows the
+    /// backend scanned and shipped.
+    pub bytes_scanned: u64,
+    /// Estimated size of what writes sent, as JSON.
+    pub bytes_written: u64,
+    /// Counts per store operation, e.g. `list_facts`.
+    pub operations: BTreeMap<String, OperationCounts>,
+}
+
+/// Store wrapper that counts reads, writes and bytes per tenant, so database cost
+/// can be attributed to tenants and heavy users found before they crowd out others.
+///
+/// Calls are attributed to the tenant of their scope; calls tied to no tenant, such
+/// as change log reads or listing the scopes of all tenants, count under the empty
+/// tenant id. Failed calls count as well, since the backend did the work.
+#[derive(Debug)]
+pub struct MeteredStore<S> {
+    inner: S,
+    options: MeteringOptions,
+    calls: AtomicU64,
+    usage: Mutex<HashMap<String, TenantUsage>>,
+}
+
+impl<S: Store> MeteredStore<S> {
+    pub fn new(inner: S, options: MeteringOptions) -> Self {
+        Self {
+            inner,
+            options,
+            calls: AtomicU64::new(0),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Usage of every tenant seen so far, ordered by tenant id.
+    pub fn usage_report(&self) -> StoreResult<Vec<TenantUsage>> {
+        let usage = self.usage.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut report: Vec<TenantUsage> = usage.values().cloned().collect();
+        report.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+        Ok(report)
+    }
+
+    pub fn tenant_usage(&self, tenant_id: &str) -> StoreResult<Option<TenantUsage>> {
+        let usage = self.usage.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(usage.get(tenant_id).cloned())
+    }
+
+    /// Returns the report and starts counting from zero, for exporting usage per
+    /// billing period.
+    pub fn take_usage_report(&self) -> StoreResult<Vec<TenantUsage>> {
+        let mut usage = self.usage.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut report: Vec<TenantUsage> = usage.drain().map(|(_, usage)| usage).collect();
+        report.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+        Ok(report)
+    }
+
+    /// The JSON size of `value` scaled by the sampling rate when this call is sampled,
+    /// 0 otherwise.
+    fn sampled_bytes<T: Serialize + ?Sized>(&self, value: &T) -> u64 {
+        let every = self.options.byte_sample_every;
+        if every == 0 || !self.calls.fetch_add(1, Ordering::Relaxed).is_multiple_of(every) {
+            return 0;
+        }
+        serde_json::to_vec(value).map_or(0, |bytes| bytes.len() as u64) * every
+    }
+
+    fn read<T: Serialize>(
+        &self,
+        tenant_id: &str,
+        op: &str,
+        result: StoreResult<T>,
+    ) -> StoreResult<T> {
+        let bytes = result.as_ref().map_or(0, |value| self.sampled_bytes(value));
+        self.record(tenant_id, op, false, bytes, result.is_err());
+        result
+    }
+
+    fn wrote<T>(
+        &self,
+        tenant_id: &str,
+        op: &str,
+        bytes: u64,
+        result: StoreResult<T>,
+    ) -> StoreResult<T> {
+        self.record(tenant_id, op, true, bytes, result.is_err());
+        result
+    }
+
+    fn record(&self, tenant_id: &str, op: &str, write: bool, bytes: u64, failed: bool) {
+        // Metering never fails a call; a poisoned lock only loses counts.
+        let Ok(mut usage) = self.usage.lock() else {
+            return;
+        };
+        let tenant = usage
+            .entry(tenant_id.to_string())
+            .or_insert_with(|| TenantUsage {
+                tenant_id: tenant_id.to_string(),
+                ..TenantUsage::default()
+            });
+        if write {
+            tenant.writes += 1;
+            tenant.bytes_written += bytes;
+        } else {
+            tenant.reads += 1;
+            tenant.bytes_scanned += bytes;
+        }
+        let counts = tenant.operations.entry(op.to_string()).or_default();
+        counts.calls += 1;
+        counts.errors += u64::from(failed);
+        counts.bytes += bytes;
+    }
+}
+
+impl<S: Store> Store for MeteredStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&event);
+        let tenant_id = event.scope.tenant_id.clone();
+        let result = self.inner.append_event(event);
+        self.wrote(&tenant_id, "append_event", bytes, result)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        let bytes: Vec<u64> = events.iter().map(|event| self.sampled_bytes(event)).collect();
+        let result = self.inner.append_events_bulk(events);
+        for (event, bytes) in events.iter().zip(bytes) {
+            let op = "append_events_bulk";
+            self.record(&event.scope.tenant_id, op, true, bytes, result.is_err());
+        }
+        result
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let result = self.inner.list_events(scope, range, limit);
+        self.read(&scope.tenant_id, "list_events", result)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let result = self.inner.list_events_page(scope, cursor, limit);
+        self.read(&scope.tenant_id, "list_events_page", result)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let result = self.inner.get_events_since(scope, seq, limit);
+        self.read(&scope.tenant_id, "get_events_since", result)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        let result = self.inner.get_events_by_ids(scope, event_ids);
+        self.read(&scope.tenant_id, "get_events_by_ids", result)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let result = self.inner.find_events(scope, filter);
+        self.read(&scope.tenant_id, "find_events", result)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let result = self.inner.search_events(scope, query);
+        self.read(&scope.tenant_id, "search_events", result)
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        let result = self.inner.search_episodes(scope, query);
+        self.read(&scope.tenant_id, "search_episodes", result)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let result = self.inner.acquire_lease(scope, ttl);
+        self.wrote(&scope.tenant_id, "acquire_lease", 0, result)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let result = self.inner.renew_lease(lease, ttl);
+        self.wrote(&lease.scope.tenant_id, "renew_lease", 0, result)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        let result = self.inner.release_lease(lease);
+        self.wrote(&lease.scope.tenant_id, "release_lease", 0, result)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        let result = self.inner.get_working_state(scope);
+        self.read(&scope.tenant_id, "get_working_state", result)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        let bytes = self.sampled_bytes(&patch);
+        let result = self.inner.patch_working_state(scope, patch);
+        self.wrote(&scope.tenant_id, "patch_working_state", bytes, result)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let metered: Vec<(String, u64)> = patches
+            .iter()
+            .map(|(scope, patch)| (scope.tenant_id.clone(), self.sampled_bytes(patch)))
+            .collect();
+        let result = self.inner.patch_working_states(patches);
+        for (tenant_id, bytes) in metered {
+            self.record(&tenant_id, "patch_working_states", true, bytes, result.is_err());
+        }
+        result
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        let result = self.inner.list_working_states(scope);
+        self.read(&scope.tenant_id, "list_working_states", result)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        let result = self.inner.get_stm(scope);
+        self.read(&scope.tenant_id, "get_stm", result)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&stm);
+        let result = self.inner.update_stm(scope, stm);
+        self.wrote(&scope.tenant_id, "update_stm", bytes, result)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        let result = self.inner.list_facts(scope, filter);
+        self.read(&scope.tenant_id, "list_facts", result)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&fact);
+        let result = self.inner.upsert_fact(scope, fact);
+        self.wrote(&scope.tenant_id, "upsert_fact", bytes, result)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(facts);
+        let result = self.inner.upsert_facts_bulk(scope, facts);
+        self.wrote(&scope.tenant_id, "upsert_facts_bulk", bytes, result)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let result = self.inner.list_fact_history(scope, fact_key);
+        self.read(&scope.tenant_id, "list_fact_history", result)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        let result = self.inner.list_episodes(scope, filter);
+        self.read(&scope.tenant_id, "list_episodes", result)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&episode);
+        let result = self.inner.append_episode(scope, episode);
+        self.wrote(&scope.tenant_id, "append_episode", bytes, result)
+    }
+
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(episodes);
+        let result = self.inner.append_episodes_bulk(scope, episodes);
+        self.wrote(&scope.tenant_id, "append_episodes_bulk", bytes, result)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        let result = self.inner.list_procedures(scope, task_type, limit);
+        self.read(&scope.tenant_id, "list_procedures", result)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&procedure);
+        let result = self.inner.upsert_procedure(scope, procedure);
+        self.wrote(&scope.tenant_id, "upsert_procedure", bytes, result)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        let result = self.inner.list_procedure_candidates(scope, filter);
+        self.read(&scope.tenant_id, "list_procedure_candidates", result)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        let result = self.inner.get_procedure_candidate(scope, candidate_id);
+        self.read(&scope.tenant_id, "get_procedure_candidate", result)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&candidate);
+        let result = self.inner.upsert_procedure_candidate(scope, candidate);
+        self.wrote(&scope.tenant_id, "upsert_procedure_candidate", bytes, result)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        let result = self.inner.list_insights(scope, filter);
+        self.read(&scope.tenant_id, "list_insights", result)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&insight);
+        let result = self.inner.append_insight(scope, insight);
+        self.wrote(&scope.tenant_id, "append_insight", bytes, result)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        let result = self.inner.expire_insights(scope, expires_at);
+        self.wrote(&scope.tenant_id, "expire_insights", 0, result)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&packet);
+        let result = self.inner.write_context_build(scope, packet);
+        self.wrote(&scope.tenant_id, "write_context_build", bytes, result)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        let result = self.inner.list_context_builds(scope, limit);
+        self.read(&scope.tenant_id, "list_context_builds", result)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        let result = self.inner.list_context_build_summaries(scope, limit);
+        self.read(&scope.tenant_id, "list_context_build_summaries", result)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        let result = self.inner.tenant_activity(tenant_id, range);
+        self.read(tenant_id, "tenant_activity", result)
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        let result = self.inner.list_scopes(tenant_id, limit);
+        self.read(tenant_id.unwrap_or(""), "list_scopes", result)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&item);
+        let result = self.inner.suppress_memory(scope, item, reason);
+        self.wrote(&scope.tenant_id, "suppress_memory", bytes, result)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        let result = self.inner.unsuppress_memory(scope, item);
+        self.wrote(&scope.tenant_id, "unsuppress_memory", 0, result)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        let result = self.inner.list_suppressions(scope);
+        self.read(&scope.tenant_id, "list_suppressions", result)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        let result = self.inner.evict_memory(scope, items);
+        self.wrote(&scope.tenant_id, "evict_memory", 0, result)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        let result = self.inner.delete_record(scope, item, mode);
+        self.wrote(&scope.tenant_id, "delete_record", 0, result)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        let result = self.inner.clear_scope(scope, mode);
+        self.wrote(&scope.tenant_id, "clear_scope", 0, result)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        let result = self.inner.count_records(scope, kind);
+        self.read(&scope.tenant_id, "count_records", result)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        let result = self.inner.list_tombstones(scope, limit);
+        self.read(&scope.tenant_id, "list_tombstones", result)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&embeddings);
+        let result = self.inner.upsert_embeddings(scope, embeddings);
+        self.wrote(&scope.tenant_id, "upsert_embeddings", bytes, result)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        let result = self.inner.list_embeddings(scope, model);
+        self.read(&scope.tenant_id, "list_embeddings", result)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        let result = self.inner.delete_embeddings(scope, model, items);
+        self.wrote(&scope.tenant_id, "delete_embeddings", 0, result)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&outcome);
+        let result = self.inner.record_run_outcome(scope, outcome);
+        self.wrote(&scope.tenant_id, "record_run_outcome", bytes, result)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        let result = self.inner.list_run_outcomes(scope, limit);
+        self.read(&scope.tenant_id, "list_run_outcomes", result)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&credibility);
+        let result = self.inner.set_source_credibility(tenant_id, credibility);
+        self.wrote(tenant_id, "set_source_credibility", bytes, result)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        let result = self.inner.get_source_credibility(tenant_id);
+        self.read(tenant_id, "get_source_credibility", result)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        let bytes = self.sampled_bytes(&locale);
+        let result = self.inner.set_user_locale(tenant_id, user_id, locale);
+        self.wrote(tenant_id, "set_user_locale", bytes, result)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        let result = self.inner.get_user_locale(tenant_id, user_id);
+        self.read(tenant_id, "get_user_locale", result)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        let bytes = self.sampled_bytes(&op);
+        let result = self.inner.append_change(op);
+        self.wrote("", "append_change", bytes, result)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        let result = self.inner.load_change_cursor(name);
+        self.read("", "load_change_cursor", result)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        let result = self.inner.save_change_cursor(name, seq);
+        self.wrote("", "save_change_cursor", 0, result)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        let mut bytes = 0;
+        let result = self.inner.write_logged(&mut || {
+            let changes = write()?;
+            bytes = changes.iter().map(|op| self.sampled_bytes(op)).sum();
+            Ok(changes)
+        });
+        self.wrote("", "append_change", bytes, result)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        let result = self.inner.list_changes(after_seq, limit);
+        self.read("", "list_changes", result)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        let result = self.inner.last_change_seq();
+        self.read("", "last_change_seq", result)
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use serde_json::json;
+
+    fn scope(tenant_id: &str) -> Scope {
+        Scope {
+            tenant_id: tenant_id.to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn counts_operations_and_bytes_per_tenant() {
+        let store = MeteredStore::new(
+            InMemoryStore::new(),
+            MeteringOptions {
+                byte_sample_every: 1,
+            },
+        );
+        let (acme, globex) = (scope("acme"), scope("globex"));
+        let fact = Fact::new("plan", json!("pro"));
+        let fact_bytes = serde_json::to_vec(&fact).unwrap().len() as u64;
+        store.upsert_fact(&acme, fact).unwrap();
+        store.list_facts(&acme, FactFilter::default()).unwrap();
+        store.list_facts(&acme, FactFilter::default()).unwrap();
+        store.list_facts(&globex, FactFilter::default()).unwrap();
+
+        let report = store.usage_report().unwrap();
+        assert_eq!(report.len(), 2);
+        let acme_usage = &report[0];
+        assert_eq!(acme_usage.tenant_id, "acme");
+        assert_eq!((acme_usage.reads, acme_usage.writes), (2, 1));
+        assert_eq!(acme_usage.bytes_written, fact_bytes);
+        assert!(acme_usage.bytes_scanned > 2 * fact_bytes);
+        assert_eq!(acme_usage.operations["list_facts"].calls, 2);
+        // An empty result still encodes as `[]`.
+        assert_eq!(report[1].bytes_scanned, 2);
+
+        assert_eq!(store.take_usage_report().unwrap().len(), 2);
+        assert_eq!(store.tenant_usage("acme").unwrap(), None);
+
+        // Sampling one call in four measures one of these reads and scales it up.
+        let sampled = MeteredStore::new(
+            InMemoryStore::new(),
+            MeteringOptions {
+                byte_sample_every: 4,
+            },
+        );
+        for _ in 0..4 {
+            sampled.list_facts(&globex, FactFilter::default()).unwrap();
+        }
+        let usage = sampled.tenant_usage("globex").unwrap().unwrap();
+        assert_eq!((usage.reads, usage.bytes_scanned), (4, 8));
+    }
+}
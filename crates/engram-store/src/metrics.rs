@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Calls of one store operation, for [`StoreMetrics`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationMetrics {
+    pub calls: u64,
+    /// Calls that returned an error, whatever the kind.
+    pub errors: u64,
+    /// Mean wall time of a call, including waiting for a pooled connection.
+    pub avg_latency_ms: f64,
+}
+
+/// What a store has done since it was opened, from
+/// [`Store::metrics_snapshot`](crate::Store::metrics_snapshot): a plain value an app
+/// can serve from its health endpoint without running a metrics system.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoreMetrics {
+    /// When counting started; `None` for stores that keep no metrics.
+    pub since: Option<DateTime<Utc>>,
+    pub calls: u64,
+    pub errors: u64,
+    pub avg_latency_ms: f64,
+    /// Counts per backend operation, e.g. `list_facts`.
+    pub operations: BTreeMap<String, OperationMetrics>,
+}
+
+impl StoreMetrics {
+    /// Adds `other`'s counts, as for a wrapper over two stores. Counting starts at
+    /// the earlier of the two.
+    pub fn merge(mut self, other: StoreMetrics) -> Self {
+        self.since = match (self.since, other.since) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.avg_latency_ms =
+            mean(self.avg_latency_ms, self.calls, other.avg_latency_ms, other.calls);
+        self.calls += other.calls;
+        self.errors += other.errors;
+        for (name, theirs) in other.operations {
+            let ours = self.operations.entry(name).or_default();
+            ours.avg_latency_ms =
+                mean(ours.avg_latency_ms, ours.calls, theirs.avg_latency_ms, theirs.calls);
+            ours.calls += theirs.calls;
+            ours.errors += theirs.errors;
+        }
+        self
+    }
+}
+
+fn mean(a: f64, a_calls: u64, b: f64, b_calls: u64) -> f64 {
+    let calls = a_calls + b_calls;
+    if calls == 0 {
+        return 0.0;
+    }
+    (a * a_calls as f64 + b * b_calls as f64) / calls as f64
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    calls: u64,
+    errors: u64,
+    elapsed: Duration,
+}
+
+/// Per-operation counts a SQL backend keeps for [`StoreMetrics`].
+#[derive(Debug)]
+pub(crate) struct MetricsRecorder {
+    since: DateTime<Utc>,
+    operations: Mutex<HashMap<&'static str, Counts>>,
+}
+
+impl Default for MetricsRecorder {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            operations: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record(&self, operation: &'static str, elapsed: Duration, failed: bool) {
+        // Metrics never fail a call; a poisoned lock only loses counts.
+        let Ok(mut operations) = self.operations.lock() else {
+            return;
+        };
+        let counts = operations.entry(operation).or_default();
+        counts.calls += 1;
+        counts.errors += u64::from(failed);
+        counts.elapsed += elapsed;
+    }
+
+    pub(crate) fn snapshot(&self) -> StoreMetrics {
+        let mut metrics = StoreMetrics {
+            since: Some(self.since),
+            ..StoreMetrics::default()
+        };
+        let Ok(operations) = self.operations.lock() else {
+            return metrics;
+        };
+        let mut elapsed = Duration::ZERO;
+        for (name, counts) in operations.iter() {
+            metrics.calls += counts.calls;
+            metrics.errors += counts.errors;
+            elapsed += counts.elapsed;
+            let operation = OperationMetrics {
+                calls: counts.calls,
+                errors: counts.errors,
+                avg_latency_ms: average_ms(counts.elapsed, counts.calls),
+            };
+            metrics.operations.insert(name.to_string(), operation);
+        }
+        metrics.avg_latency_ms = average_ms(elapsed, metrics.calls);
+        metrics
+    }
+}
+
+fn average_ms(elapsed: Duration, calls: u64) -> f64 {
+    if calls == 0 {
+        return 0.0;
+    }
+    elapsed.as_secs_f64() * 1000.0 / calls as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_count_calls_errors_and_latency_per_operation() {
+        let recorder = MetricsRecorder::default();
+        recorder.record("list_facts", Duration::from_millis(4), false);
+        recorder.record("list_facts", Duration::from_millis(2), true);
+        recorder.record("append_event", Duration::from_millis(6), false);
+        let metrics = recorder.snapshot();
+        assert_eq!((metrics.calls, metrics.errors), (3, 1));
+        assert!((metrics.avg_latency_ms - 4.0).abs() < 1e-9);
+        let facts = &metrics.operations["list_facts"];
+        assert_eq!((facts.calls, facts.errors), (2, 1));
+        assert!((facts.avg_latency_ms - 3.0).abs() < 1e-9);
+
+        let merged = metrics.clone().merge(recorder.snapshot()).merge(StoreMetrics::default());
+        assert_eq!((merged.calls, merged.errors), (6, 2));
+        assert_eq!(merged.since, metrics.since);
+        assert!((merged.operations["append_event"].avg_latency_ms - 6.0).abs() < 1e-9);
+    }
+}
@@ -1,23 +1,108 @@
 use chrono::{DateTime, TimeZone, Utc};
 use engram_types::{
-    CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger, InsightType,
-    MemoryPacket, Procedure, Scope, ScopeLevel, ValidationState, WorkingState,
+    CandidateStatus, CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger,
+    InsightType, MemoryPacket, Procedure, ProcedureCandidate, Scope, ScopeLevel, ValidationState,
+    WorkingState,
 };
-use mysql::prelude::Queryable;
+use mysql::prelude::{FromValue, Queryable};
 use mysql::{from_row, Opts, OptsBuilder, Params, Pool, PooledConn, Value as MyValue};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tracing::warn;
 
+use crate::analytics::collect_user_activity;
+use crate::changelog::PinnedConnections;
+use crate::composer::stored_payload;
+use crate::fact_history::next_fact_version;
+use crate::lease::{lease_lock_key, LeaseTable};
+use crate::metrics::MetricsRecorder;
+use crate::outcome::outcome_for_run;
+use crate::pagination::{event_page, page_request};
+use crate::search::{search_episodes_unindexed, search_events_unindexed};
+use crate::sql_context::SqlContext;
+use crate::table_prefix::TablePrefix;
+use crate::tags::normalize_event_tags;
+use crate::timeout::effective_timeout;
 use crate::{
-    EpisodeFilter, Event, EventKind, FactFilter, InsightFilter, StmState, Store, StoreError,
-    StoreResult, TimeRangeFilter, WorkingStatePatch,
+    apply_working_state_patch, check_id_len, check_state_version, correlation_id,
+    find_events_unindexed, state_version_conflict, Change, ChangeOp, ContextBuildSummary,
+    DeleteMode, EpisodeFilter, Event, EventFilter, EventPage, FactFilter, FactVersion,
+    InsightFilter, Lease, MemoryEmbedding, MemoryKind, MemoryRef, OptionalTables,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunStatus, RunWorkingState,
+    SourceCredibility, StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult,
+    Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+    MAX_ID_LEN, PREFERENCE_KEY_PREFIX,
 };
 
-const SCHEMA_VERSION: i64 = 1;
+type FactRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<i64>,
+    Option<i64>,
+    f64,
+    String,
+    String,
+    String,
+    bool,
+    Option<String>,
+);
+
+type ProcedureCandidateRow = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    i64,
+    Option<i64>,
+    String,
+    String,
+);
+
+type TombstoneRow = (String, String, String, String, String, String, String, String, i64);
+
+type EpisodeRow = (
+    String,
+    i64,
+    Option<i64>,
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<f64>,
+    Option<String>,
+);
+
+const SCHEMA_VERSION: i64 = 6;
+
+/// Widest id column [`MySqlStore::with_id_column_len`] creates: the `event_tags` key
+/// spans six ids and a 64-character tag, at up to 4 bytes a character, within
+/// InnoDB's 3072-byte limit.
+const MAX_ID_COLUMN_LEN: usize = 117;
+
+/// How the schema statements spell an id column; [`sized`] rewrites it.
+const ID_COLUMN: &str = "VARCHAR(96)";
+
+/// `ER_QUERY_TIMEOUT`: a statement ran past `max_execution_time`.
+const ER_QUERY_TIMEOUT: u16 = 3024;
 
 pub struct MySqlStore {
     pool: Pool,
+    tables: TablePrefix,
+    statement_timeout: Option<Duration>,
+    optional: OptionalTables,
+    /// Each held lease keeps its `GET_LOCK` connection out of the pool.
+    leases: LeaseTable<(PooledConn, String)>,
+    pinned: PinnedConnections<PooledConn>,
+    metrics: MetricsRecorder,
+    /// Width of the id columns, as found in the schema; longer ids are rejected.
+    id_len: usize,
 }
 
 impl std::fmt::Debug for MySqlStore {
@@ -28,6 +113,33 @@ impl std::fmt::Debug for MySqlStore {
 
 impl MySqlStore {
     pub fn new(url: &str) -> StoreResult<Self> {
+        Self::open(url, MAX_ID_LEN, TablePrefix::default())
+    }
+
+    /// Prefixes every engram table name with `prefix` (e.g. `engram_`), so engram can
+    /// share a database with an application's own tables. The prefix must be a
+    /// lowercase SQL identifier of at most
+    /// [`MAX_TABLE_PREFIX_LEN`](crate::MAX_TABLE_PREFIX_LEN) characters.
+    pub fn with_table_prefix(url: &str, prefix: &str) -> StoreResult<Self> {
+        Self::open(url, MAX_ID_LEN, TablePrefix::new(prefix)?)
+    }
+
+    /// Creates the id columns (scope ids, record ids, task types) as `VARCHAR(id_len)`
+    /// instead of `VARCHAR(96)`, for callers whose ids are longer, e.g. composites of
+    /// several UUIDs. InnoDB caps index keys at 3072 bytes, so `id_len` is at most 117.
+    /// Only tables created by this call take the width; an existing database keeps its
+    /// own, which the store reads back and validates ids against.
+    pub fn with_id_column_len(url: &str, id_len: usize) -> StoreResult<Self> {
+        if !(1..=MAX_ID_COLUMN_LEN).contains(&id_len) {
+            return Err(StoreError::InvalidInput(format!(
+                "id_len must be between 1 and {}, got {}",
+                MAX_ID_COLUMN_LEN, id_len
+            )));
+        }
+        Self::open(url, id_len, TablePrefix::default())
+    }
+
+    fn open(url: &str, id_len: usize, tables: TablePrefix) -> StoreResult<Self> {
         let mut opts =
             mysql::Opts::from_url(url).map_err(|err| StoreError::InvalidInput(err.to_string()))?;
         let db_name = opts
@@ -47,105 +159,344 @@ impl MySqlStore {
             }
             Err(err) => return Err(map_mysql_err(err)),
         };
-        let store = Self { pool };
-        store.with_conn(|conn| ensure_schema(conn))?;
+        let mut store = Self {
+            pool,
+            tables,
+            statement_timeout: None,
+            optional: OptionalTables {
+                event_index: true,
+                episode_index: true,
+                text_index: true,
+            },
+            leases: LeaseTable::default(),
+            pinned: PinnedConnections::default(),
+            metrics: MetricsRecorder::default(),
+            id_len,
+        };
+        let context = SqlContext::new("ensure_schema", "schema_migrations", &[]);
+        store.optional =
+            store.with_conn(context, |conn| ensure_schema(conn, &store.tables, id_len))?;
+        let context = SqlContext::new("id_column_len", "events", &[]);
+        store.id_len = store
+            .with_conn(context, |conn| id_column_len(conn, &store.tables))?
+            .unwrap_or(id_len);
+        if store.id_len != id_len {
+            warn!(
+                "id columns are VARCHAR({}), not VARCHAR({}); ids are limited to {} characters",
+                store.id_len, id_len, store.id_len
+            );
+        }
         Ok(store)
     }
 
-    fn with_conn<F, T>(&self, f: F) -> StoreResult<T>
+    fn insert_episode(
+        &self,
+        conn: &mut PooledConn,
+        scope: &Scope,
+        episode: &Episode,
+    ) -> StoreResult<()> {
+        conn.exec_drop(
+            self.tables.sql("INSERT INTO episodes (
+                tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
+                highlights, tags, entities, sources, compression_level, recency_score,
+                lang
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"),
+            Params::Positional(vec![
+                MyValue::from(scope.tenant_id.clone()),
+                MyValue::from(scope.user_id.clone()),
+                MyValue::from(scope.agent_id.clone()),
+                MyValue::from(episode.episode_id.clone()),
+                MyValue::from(to_millis(episode.time_range.start)),
+                option_i64(option_ts(episode.time_range.end)),
+                MyValue::from(episode.summary.clone()),
+                MyValue::from(encode_json(&episode.highlights)?),
+                MyValue::from(encode_json(&episode.tags)?),
+                MyValue::from(encode_json(&episode.entities)?),
+                MyValue::from(encode_json(&episode.sources)?),
+                MyValue::from(compression_level_to_str(&episode.compression_level).to_string()),
+                option_f64(episode.recency_score),
+                MyValue::from(episode.lang.clone()),
+            ]),
+        )
+        .map_err(map_mysql_err)?;
+        if self.optional.episode_index {
+            insert_episode_tags(
+                conn, &self.tables,
+                scope,
+                &episode.episode_id,
+                &episode.tags,
+                &episode.entities,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes the scope's records of `kind`, or only the one with `id`, with their
+    /// index rows, suppressions and embeddings, keeping tombstones of them in soft
+    /// mode. Returns how many were removed.
+    fn remove_records(
+        &self,
+        conn: &mut PooledConn,
+        scope: &Scope,
+        kind: RecordKind,
+        id: Option<&str>,
+        mode: DeleteMode,
+    ) -> StoreResult<usize> {
+        let (table, id_column) = kind.table();
+        let (mut filter, mut params) = if kind.run_level() {
+            (
+                "tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?"
+                    .to_string(),
+                scope_params(scope),
+            )
+        } else {
+            (
+                "tenant_id = ? AND user_id = ? AND agent_id = ?".to_string(),
+                scope_params_ltm(scope),
+            )
+        };
+        if let Some(id) = id {
+            filter.push_str(&format!(" AND {} = ?", id_column));
+            params.push(MyValue::from(id.to_string()));
+        }
+        if mode == DeleteMode::Soft {
+            for tombstone in load_tombstones(conn, &self.tables, scope, kind, &filter, &params)? {
+                insert_tombstone(conn, &self.tables, &tombstone)?;
+            }
+        }
+        let indexes: &[&str] = match kind {
+            RecordKind::Event if self.optional.event_index => &["event_tags", "event_entities"],
+            RecordKind::Episode if self.optional.episode_index => {
+                &["episode_tags", "episode_entities"]
+            }
+            _ => &[],
+        };
+        for index in indexes {
+            conn.exec_drop(
+                self.tables.sql(&format!("DELETE FROM {} WHERE {}", index, filter)),
+                Params::Positional(params.clone()),
+            )
+            .map_err(map_mysql_err)?;
+        }
+        if kind == RecordKind::Fact && mode == DeleteMode::Hard {
+            conn.exec_drop(
+                self.tables.sql(&format!("DELETE FROM fact_versions WHERE {}", filter)),
+                Params::Positional(params.clone()),
+            )
+            .map_err(map_mysql_err)?;
+        }
+        if let Some(memory_kind) = kind.memory_kind() {
+            let mut params = scope_params_ltm(scope);
+            params.push(MyValue::from(memory_kind_to_str(&memory_kind)));
+            let mut filter =
+                "tenant_id = ? AND user_id = ? AND agent_id = ? AND item_kind = ?".to_string();
+            if let Some(id) = id {
+                filter.push_str(" AND item_id = ?");
+                params.push(MyValue::from(id.to_string()));
+            }
+            for table in ["suppressions", "memory_embeddings"] {
+                conn.exec_drop(
+                    self.tables.sql(&format!("DELETE FROM {} WHERE {}", table, filter)),
+                    Params::Positional(params.clone()),
+                )
+                .map_err(map_mysql_err)?;
+            }
+        }
+        conn.exec_drop(
+            self.tables.sql(&format!("DELETE FROM {} WHERE {}", table, filter)),
+            Params::Positional(params),
+        )
+        .map_err(map_mysql_err)?;
+        Ok(conn.affected_rows() as usize)
+    }
+
+    /// Runs `f` on a pooled connection and counts the call under the context's
+    /// operation; storage errors are tagged with `context`.
+    fn with_conn<F, T>(&self, context: SqlContext<'_>, f: F) -> StoreResult<T>
     where
         F: FnOnce(&mut PooledConn) -> StoreResult<T>,
     {
-        let mut conn = self.pool.get_conn().map_err(map_mysql_err)?;
-        f(&mut conn)
+        let started = Instant::now();
+        let result = self.checkout(f).map_err(|err| context.wrap(err));
+        self.metrics.record(context.operation(), started.elapsed(), result.is_err());
+        result
     }
 
-    pub fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
-        if events.is_empty() {
-            return Ok(());
-        }
-        self.with_conn(|conn| {
-            conn.exec_drop("START TRANSACTION", ())
+    fn checkout<F, T>(&self, f: F) -> StoreResult<T>
+    where
+        F: FnOnce(&mut PooledConn) -> StoreResult<T>,
+    {
+        let connect = || self.pool.get_conn().map_err(map_mysql_err);
+        self.pinned.checkout(connect, |conn| {
+            let Some(timeout) = effective_timeout(self.statement_timeout) else {
+                return f(conn);
+            };
+            conn.query_drop(format!(
+                "SET SESSION max_execution_time = {}",
+                timeout.as_millis().max(1)
+            ))
+            .map_err(map_mysql_err)?;
+            let result = f(conn);
+            // Pooled connections outlive the call; leave them without a timeout.
+            conn.query_drop("SET SESSION max_execution_time = 0")
                 .map_err(map_mysql_err)?;
-            let result = (|| {
-                let mut params = Vec::with_capacity(events.len());
-                for event in events {
-                    params.push(Params::Positional(vec![
-                        MyValue::from(event.event_id.clone()),
-                        MyValue::from(event.scope.tenant_id.clone()),
-                        MyValue::from(event.scope.user_id.clone()),
-                        MyValue::from(event.scope.agent_id.clone()),
-                        MyValue::from(event.scope.session_id.clone()),
-                        MyValue::from(event.scope.run_id.clone()),
-                        MyValue::from(to_millis(event.ts)),
-                        MyValue::from(event_kind_to_str(&event.kind)),
-                        MyValue::from(encode_json(&event.payload)?),
-                        MyValue::from(encode_json(&event.tags)?),
-                        MyValue::from(encode_json(&event.entities)?),
-                    ]));
-                }
+            result
+        })
+    }
 
-                conn.exec_batch(
-                    "INSERT INTO events (
-                        event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                        ts, kind, payload, tags, entities
-                     ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                    params,
-                )
-                .map_err(map_mysql_err)?;
+    /// Starts a transaction on `conn`, or a savepoint when `conn` is held by
+    /// [`Store::write_logged`] and so already in one.
+    fn transaction<'a>(&self, conn: &'a mut PooledConn) -> StoreResult<MySqlTransaction<'a>> {
+        MySqlTransaction::begin(conn, self.pinned.is_pinned()?).map_err(map_mysql_err)
+    }
 
-                insert_event_tags_bulk(conn, events)?;
-                Ok(())
-            })();
+    /// Rejects scope and record ids wider than the id columns, which MySQL would
+    /// truncate or refuse deep in the statement, depending on its SQL mode.
+    fn check_ids(&self, scope: &Scope, ids: &[(&str, &str)]) -> StoreResult<()> {
+        let scope_ids = [
+            ("scope.tenant_id", scope.tenant_id.as_str()),
+            ("scope.user_id", scope.user_id.as_str()),
+            ("scope.agent_id", scope.agent_id.as_str()),
+            ("scope.session_id", scope.session_id.as_str()),
+            ("scope.run_id", scope.run_id.as_str()),
+        ];
+        for (field, value) in scope_ids.iter().chain(ids) {
+            check_id_len(field, value, self.id_len)?;
+        }
+        Ok(())
+    }
 
-            match result {
-                Ok(()) => {
-                    conn.exec_drop("COMMIT", ()).map_err(map_mysql_err)?;
-                    Ok(())
-                }
-                Err(err) => {
-                    let _ = conn.exec_drop("ROLLBACK", ());
-                    Err(err)
-                }
+    fn check_fact_ids(&self, scope: &Scope, fact: &Fact) -> StoreResult<()> {
+        let ids = [
+            ("fact.fact_id", fact.fact_id.as_str()),
+            ("fact.fact_key", fact.fact_key.as_str()),
+        ];
+        self.check_ids(scope, &ids)
+    }
+
+    /// Aborts `SELECT`s running longer than `timeout` via MySQL's
+    /// `max_execution_time`; they fail with [`StoreError::Timeout`]. MySQL does not
+    /// bound writes this way.
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    fn context_build_rows(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<String>> {
+        self.with_conn(SqlContext::scoped("context_build_rows", "context_builds", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT packet_json FROM context_builds
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                 ORDER BY ts ASC",
+            );
+            let mut params = scope_params(scope);
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
             }
+
+            let rows: Vec<(String,)> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            Ok(rows.into_iter().map(|(packet_json,)| packet_json).collect())
         })
     }
 }
 
 impl Store for MySqlStore {
     fn append_event(&self, event: Event) -> StoreResult<()> {
+        let event = normalize_event_tags(event);
+        let payload = stored_payload(&event).into_owned();
         let Event {
             event_id,
             scope,
             ts,
             kind,
-            payload,
             tags,
             entities,
+            lang,
+            ..
         } = event;
-        self.with_conn(|conn| {
+        self.check_ids(&scope, &[("event.event_id", &event_id)])?;
+        self.with_conn(SqlContext::scoped("append_event", "events", &scope), |conn| {
+            in_transaction(conn, |conn| {
+            let seq = next_event_seq(conn, &self.tables, &scope)?;
             conn.exec_drop(
-                "INSERT INTO events (
+                self.tables.sql("INSERT INTO events (
                     event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                    ts, kind, payload, tags, entities
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                (
-                    event_id.clone(),
-                    scope.tenant_id.clone(),
-                    scope.user_id.clone(),
-                    scope.agent_id.clone(),
-                    scope.session_id.clone(),
-                    scope.run_id.clone(),
-                    to_millis(ts),
-                    event_kind_to_str(&kind),
-                    encode_json(&payload)?,
-                    encode_json(&tags)?,
-                    encode_json(&entities)?,
-                ),
+                    ts, kind, payload, tags, entities, seq, lang
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"),
+                Params::Positional(vec![
+                    MyValue::from(event_id.clone()),
+                    MyValue::from(scope.tenant_id.clone()),
+                    MyValue::from(scope.user_id.clone()),
+                    MyValue::from(scope.agent_id.clone()),
+                    MyValue::from(scope.session_id.clone()),
+                    MyValue::from(scope.run_id.clone()),
+                    MyValue::from(to_millis(ts)),
+                    MyValue::from(kind.as_str()),
+                    MyValue::from(encode_json(&payload)?),
+                    MyValue::from(encode_json(&tags)?),
+                    MyValue::from(encode_json(&entities)?),
+                    MyValue::from(seq),
+                    MyValue::from(lang),
+                ]),
+            )
+            .map_err(map_mysql_err)?;
+            if self.optional.event_index {
+                insert_event_tags(conn, &self.tables, &scope, &event_id, &tags, &entities)?;
+            }
+            Ok(())
+            })
+        })
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let events: &[Event] =
+            &events.iter().cloned().map(normalize_event_tags).collect::<Vec<_>>();
+        for event in events {
+            self.check_ids(&event.scope, &[("event.event_id", &event.event_id)])?;
+        }
+        let context = SqlContext::scoped("append_events_bulk", "events", &events[0].scope);
+        self.with_conn(context, |conn| {
+            in_transaction(conn, |conn| {
+            let mut params = Vec::with_capacity(events.len());
+            for event in events {
+                let seq = next_event_seq(conn, &self.tables, &event.scope)?;
+                params.push(Params::Positional(vec![
+                    MyValue::from(event.event_id.clone()),
+                    MyValue::from(event.scope.tenant_id.clone()),
+                    MyValue::from(event.scope.user_id.clone()),
+                    MyValue::from(event.scope.agent_id.clone()),
+                    MyValue::from(event.scope.session_id.clone()),
+                    MyValue::from(event.scope.run_id.clone()),
+                    MyValue::from(to_millis(event.ts)),
+                    MyValue::from(event.kind.as_str()),
+                    MyValue::from(encode_json(&stored_payload(event))?),
+                    MyValue::from(encode_json(&event.tags)?),
+                    MyValue::from(encode_json(&event.entities)?),
+                    MyValue::from(seq),
+                    MyValue::from(event.lang.clone()),
+                ]));
+            }
+
+            conn.exec_batch(
+                self.tables.sql("INSERT INTO events (
+                    event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                    ts, kind, payload, tags, entities, seq, lang
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"),
+                params,
             )
             .map_err(map_mysql_err)?;
-            insert_event_tags(conn, &scope, &event_id, &tags, &entities)?;
+
+            if self.optional.event_index {
+                insert_event_tags_bulk(conn, &self.tables, events)?;
+            }
             Ok(())
+            })
         })
     }
 
@@ -155,10 +506,10 @@ impl Store for MySqlStore {
         range: TimeRangeFilter,
         limit: Option<usize>,
     ) -> StoreResult<Vec<Event>> {
-        self.with_conn(|conn| {
+        self.with_conn(SqlContext::scoped("list_events", "events", scope), |conn| {
             let mut sql = String::from(
                 "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                        ts, kind, payload, tags, entities
+                        ts, kind, payload, tags, entities, seq, lang
                  FROM events WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
             );
             let mut params = scope_params(scope);
@@ -171,320 +522,500 @@ impl Store for MySqlStore {
                 sql.push_str(" AND ts <= ?");
                 params.push(MyValue::from(to_millis(end)));
             }
-            sql.push_str(" ORDER BY ts ASC");
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(MyValue::from(limit as i64));
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut events = Vec::with_capacity(rows.len());
-            for row in rows {
-                let (
-                    event_id,
-                    tenant_id,
-                    user_id,
-                    agent_id,
-                    session_id,
-                    run_id,
-                    ts,
-                    kind,
-                    payload,
-                    tags,
-                    entities,
-                ): (String, String, String, String, String, String, i64, String, String, String, String) =
-                    from_row(row);
-                events.push(Event {
-                    event_id,
-                    scope: Scope {
-                        tenant_id,
-                        user_id,
-                        agent_id,
-                        session_id,
-                        run_id,
-                    },
-                    ts: from_millis(ts),
-                    kind: parse_event_kind(&kind)?,
-                    payload: decode_json(&payload)?,
-                    tags: decode_json(&tags)?,
-                    entities: decode_json(&entities)?,
-                });
-            }
-            Ok(events)
+            rows.into_iter().map(event_from_row).collect()
         })
     }
 
-    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
-        self.with_conn(|conn| {
-            let row: Option<String> = conn
-                .exec_first(
-                    "SELECT state_json FROM wm_state
-                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
-                    (
-                        scope.tenant_id.clone(),
-                        scope.user_id.clone(),
-                        scope.agent_id.clone(),
-                        scope.session_id.clone(),
-                        scope.run_id.clone(),
-                    ),
-                )
-                .map_err(map_mysql_err)?;
-            match row {
-                Some(payload) => Ok(Some(decode_json(&payload)?)),
-                None => Ok(None),
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let cursor = page_request(cursor, limit)?;
+        self.with_conn(SqlContext::scoped("list_events_page", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                        ts, kind, payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
+            );
+            let mut params = scope_params(scope);
+            if let Some(cursor) = cursor {
+                sql.push_str(" AND (ts > ? OR (ts = ? AND event_id > ?))");
+                params.extend([
+                    MyValue::from(cursor.ts_ms),
+                    MyValue::from(cursor.ts_ms),
+                    MyValue::from(cursor.event_id),
+                ]);
             }
+            sql.push_str(" ORDER BY ts ASC, event_id ASC LIMIT ?");
+            params.push(MyValue::from(limit as i64 + 1));
+
+            let rows: Vec<mysql::Row> = conn
+                .exec(self.tables.sql(&sql), Params::Positional(params))
+                .map_err(map_mysql_err)?;
+            let events = rows.into_iter().map(event_from_row).collect::<StoreResult<Vec<_>>>()?;
+            Ok(event_page(events, limit))
         })
     }
 
-    fn patch_working_state(
+    fn get_events_since(
         &self,
         scope: &Scope,
-        patch: WorkingStatePatch,
-    ) -> StoreResult<WorkingState> {
-        let current = self.get_working_state(scope)?.unwrap_or_default();
-        let mut next = current.clone();
-
-        let mut touched = false;
-        if let Some(goal) = patch.goal {
-            next.goal = goal;
-            touched = true;
-        }
-        if let Some(plan) = patch.plan {
-            next.plan = plan;
-            touched = true;
-        }
-        if let Some(slots) = patch.slots {
-            next.slots = slots;
-            touched = true;
-        }
-        if let Some(constraints) = patch.constraints {
-            next.constraints = constraints;
-            touched = true;
-        }
-        if let Some(tool_evidence) = patch.tool_evidence {
-            next.tool_evidence = tool_evidence;
-            touched = true;
-        }
-        if let Some(decisions) = patch.decisions {
-            next.decisions = decisions;
-            touched = true;
-        }
-        if let Some(risks) = patch.risks {
-            next.risks = risks;
-            touched = true;
-        }
-        if let Some(state_version) = patch.state_version {
-            next.state_version = state_version;
-        } else if touched {
-            next.state_version = next.state_version.saturating_add(1);
-        }
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.with_conn(SqlContext::scoped("get_events_since", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                        ts, kind, payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                   AND seq > ?
+                 ORDER BY seq ASC",
+            );
+            let mut params = scope_params(scope);
+            params.push(MyValue::from(seq as i64));
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
 
-        self.with_conn(|conn| {
-            conn.exec_drop(
-                "INSERT INTO wm_state (
-                    tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                 ON DUPLICATE KEY UPDATE state_json = VALUES(state_json),
-                                         updated_at = VALUES(updated_at)",
-                (
-                    scope.tenant_id.clone(),
-                    scope.user_id.clone(),
-                    scope.agent_id.clone(),
-                    scope.session_id.clone(),
-                    scope.run_id.clone(),
-                    encode_json(&next)?,
-                    to_millis(Utc::now()),
-                ),
-            )
-            .map_err(map_mysql_err)?;
-            Ok(next)
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter().map(event_from_row).collect()
         })
     }
 
-    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
-        self.with_conn(|conn| {
-            let row: Option<(String, String)> = conn
-                .exec_first(
-                    "SELECT rolling_summary, key_quotes FROM stm_state
-                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?",
-                    (
-                        scope.tenant_id.clone(),
-                        scope.user_id.clone(),
-                        scope.agent_id.clone(),
-                        scope.session_id.clone(),
-                    ),
-                )
-                .map_err(map_mysql_err)?;
-            match row {
-                Some((rolling_summary, key_quotes)) => Ok(Some(StmState {
-                    rolling_summary,
-                    key_quotes: decode_json(&key_quotes)?,
-                })),
-                None => Ok(None),
-            }
-        })
-    }
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        if event_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_conn(SqlContext::scoped("get_events_by_ids", "events", scope), |conn| {
+            let placeholders = vec!["?"; event_ids.len()].join(", ");
+            let sql = format!(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                        ts, kind, payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND event_id IN ({})
+                 ORDER BY ts ASC, seq ASC",
+                placeholders
+            );
+            let mut params = scope_params_ltm(scope);
+            params.extend(event_ids.iter().map(|id| MyValue::from(id.clone())));
 
-    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
-        self.with_conn(|conn| {
-            conn.exec_drop(
-                "INSERT INTO stm_state (
-                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                 ON DUPLICATE KEY UPDATE rolling_summary = VALUES(rolling_summary),
-                                         key_quotes = VALUES(key_quotes),
-                                         updated_at = VALUES(updated_at)",
-                (
-                    scope.tenant_id.clone(),
-                    scope.user_id.clone(),
-                    scope.agent_id.clone(),
-                    scope.session_id.clone(),
-                    stm.rolling_summary,
-                    encode_json(&stm.key_quotes)?,
-                    to_millis(Utc::now()),
-                ),
-            )
-            .map_err(map_mysql_err)?;
-            Ok(())
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter().map(event_from_row).collect()
         })
     }
 
-    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
-        self.with_conn(|conn| {
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let filter = filter.with_tag_forms();
+        if !self.optional.event_index && (!filter.tags.is_empty() || !filter.entities.is_empty()) {
+            return find_events_unindexed(filter, |filter| self.find_events(scope, filter));
+        }
+        self.with_conn(SqlContext::scoped("find_events", "events", scope), |conn| {
             let mut sql = String::from(
-                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
-                        confidence, sources, scope_level, notes
-                 FROM facts WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                        ts, kind, payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
             );
-            let mut params = scope_params_ltm(scope);
+            let mut params = scope_params(scope);
 
-            if let Some(statuses) = &filter.status {
-                if !statuses.is_empty() {
-                    sql.push_str(" AND status IN (");
-                    for (idx, status) in statuses.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str("?");
-                        params.push(MyValue::from(fact_status_to_str(status)));
-                    }
-                    sql.push(')');
+            if let Some(range) = &filter.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND ts >= ?");
+                    params.push(MyValue::from(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND ts <= ?");
+                    params.push(MyValue::from(to_millis(end)));
                 }
             }
 
-            if let Some(at) = filter.valid_at {
-                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ?)");
-                params.push(MyValue::from(to_millis(at)));
-                sql.push_str(" AND (valid_to IS NULL OR valid_to >= ?)");
-                params.push(MyValue::from(to_millis(at)));
+            if !filter.tags.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_tags i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.tag IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.tags.len()));
+                sql.push_str("))");
+                for tag in &filter.tags {
+                    params.push(MyValue::from(tag.clone()));
+                }
             }
 
-            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
+            if !filter.entities.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_entities i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.entity IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.entities.len()));
+                sql.push_str("))");
+                for entity in &filter.entities {
+                    params.push(MyValue::from(entity.clone()));
+                }
+            }
+
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
             if let Some(limit) = filter.limit {
                 sql.push_str(" LIMIT ?");
                 params.push(MyValue::from(limit as i64));
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut facts = Vec::with_capacity(rows.len());
-            for row in rows {
-                let (
-                    fact_id,
-                    fact_key,
-                    value_json,
-                    status,
-                    valid_from,
-                    valid_to,
-                    confidence,
-                    sources,
-                    scope_level,
-                    notes,
-                ): (
-                    String,
-                    String,
-                    String,
-                    String,
-                    Option<i64>,
-                    Option<i64>,
-                    f64,
-                    String,
-                    String,
-                    String,
-                ) = from_row(row);
-                facts.push(Fact {
-                    fact_id,
-                    fact_key,
-                    value: decode_json(&value_json)?,
-                    status: parse_fact_status(&status)?,
-                    validity: engram_types::Validity {
-                        valid_from: valid_from.map(from_millis),
-                        valid_to: valid_to.map(from_millis),
-                    },
-                    confidence,
-                    sources: decode_json(&sources)?,
-                    scope_level: parse_scope_level(&scope_level)?,
-                    notes,
+            rows.into_iter().map(event_from_row).collect()
+        })
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let terms = query.searchable_terms();
+        if !self.optional.text_index {
+            return search_events_unindexed(self, scope, query);
+        }
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let against = fulltext_query(&terms);
+        self.with_conn(SqlContext::scoped("search_events", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                        ts, kind, payload, tags, entities, seq, lang
+                 FROM events
+                 WHERE MATCH (payload) AGAINST (? IN BOOLEAN MODE)
+                   AND tenant_id = ? AND user_id = ? AND agent_id = ?
+                   AND session_id = ? AND run_id = ?",
+            );
+            let mut params = vec![MyValue::from(against.clone())];
+            params.extend(scope_params(scope));
+
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND ts >= ?");
+                    params.push(MyValue::from(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND ts <= ?");
+                    params.push(MyValue::from(to_millis(end)));
+                }
+            }
+
+            sql.push_str(
+                " ORDER BY MATCH (payload) AGAINST (? IN BOOLEAN MODE) DESC, ts ASC, seq ASC",
+            );
+            params.push(MyValue::from(against.clone()));
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter().map(event_from_row).collect()
+        })
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let name = format!("engram:{:016x}", lease_lock_key(scope));
+        self.leases.acquire(
+            scope,
+            ttl,
+            || {
+                let mut conn = self.pool.get_conn().map_err(map_mysql_err)?;
+                let locked: Option<Option<i64>> = conn
+                    .exec_first("SELECT GET_LOCK(?, 0)", (name.as_str(),))
+                    .map_err(map_mysql_err)?;
+                Ok((locked.flatten() == Some(1)).then_some((conn, name.clone())))
+            },
+            mysql_release_lock,
+        )
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.renew(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        let Some(holder) = self.leases.release(lease)? else {
+            return Ok(false);
+        };
+        mysql_release_lock(holder)?;
+        Ok(true)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.with_conn(SqlContext::scoped("get_working_state", "wm_state", scope), |conn| {
+            working_state_json(conn, &self.tables, scope)?.as_deref().map(decode_json).transpose()
+        })
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.check_ids(scope, &[])?;
+        self.with_conn(SqlContext::scoped("patch_working_state", "wm_state", scope), |conn| {
+            patch_working_state_row(conn, &self.tables, scope, patch)
+        })
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let Some((first, _)) = patches.first() else {
+            return Ok(Vec::new());
+        };
+        for (scope, _) in &patches {
+            self.check_ids(scope, &[])?;
+        }
+        let scope = first.clone();
+        self.with_conn(SqlContext::scoped("patch_working_states", "wm_state", &scope), |conn| {
+            in_transaction(conn, |conn| {
+                let mut states = Vec::with_capacity(patches.len());
+                for (scope, patch) in patches {
+                    states.push(patch_working_state_row(conn, &self.tables, &scope, patch)?);
+                }
+                Ok(states)
+            })
+        })
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.with_conn(SqlContext::scoped("list_working_states", "wm_state", scope), |conn| {
+            let rows: Vec<(String, String)> = conn
+                .exec(
+                    self.tables.sql("SELECT run_id, state_json FROM wm_state
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?
+                     ORDER BY run_id ASC"),
+                    (
+                        scope.tenant_id.clone(),
+                        scope.user_id.clone(),
+                        scope.agent_id.clone(),
+                        scope.session_id.clone(),
+                    ),
+                )
+                .map_err(map_mysql_err)?;
+            let mut states = Vec::with_capacity(rows.len());
+            for (run_id, payload) in rows {
+                states.push(RunWorkingState {
+                    run_id,
+                    state: decode_json(&payload)?,
                 });
             }
-            Ok(facts)
+            Ok(states)
         })
     }
 
-    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.with_conn(SqlContext::scoped("get_stm", "stm_state", scope), |conn| {
+            let row: Option<(String, String)> = conn
+                .exec_first(
+                    self.tables.sql("SELECT rolling_summary, key_quotes FROM stm_state
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?"),
+                    (
+                        scope.tenant_id.clone(),
+                        scope.user_id.clone(),
+                        scope.agent_id.clone(),
+                        scope.session_id.clone(),
+                    ),
+                )
+                .map_err(map_mysql_err)?;
+            match row {
+                Some((rolling_summary, key_quotes)) => Ok(Some(StmState {
+                    rolling_summary,
+                    key_quotes: decode_json(&key_quotes)?,
+                })),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.check_ids(scope, &[])?;
+        self.with_conn(SqlContext::scoped("update_stm", "stm_state", scope), |conn| {
             conn.exec_drop(
-                "INSERT INTO facts (
-                    tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
-                    valid_from, valid_to, confidence, sources, scope_level, notes
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                 ON DUPLICATE KEY UPDATE fact_key = VALUES(fact_key),
-                                         value_json = VALUES(value_json),
-                                         status = VALUES(status),
-                                         valid_from = VALUES(valid_from),
-                                         valid_to = VALUES(valid_to),
-                                         confidence = VALUES(confidence),
-                                         sources = VALUES(sources),
-                                         scope_level = VALUES(scope_level),
-                                         notes = VALUES(notes)",
-                Params::Positional(vec![
-                    MyValue::from(scope.tenant_id.clone()),
-                    MyValue::from(scope.user_id.clone()),
-                    MyValue::from(scope.agent_id.clone()),
-                    MyValue::from(fact.fact_id),
-                    MyValue::from(fact.fact_key),
-                    MyValue::from(encode_json(&fact.value)?),
-                    MyValue::from(fact_status_to_str(&fact.status).to_string()),
-                    option_i64(option_ts(fact.validity.valid_from)),
-                    option_i64(option_ts(fact.validity.valid_to)),
-                    MyValue::from(fact.confidence),
-                    MyValue::from(encode_json(&fact.sources)?),
-                    MyValue::from(scope_level_to_str(&fact.scope_level).to_string()),
-                    MyValue::from(fact.notes),
-                ]),
+                self.tables.sql("INSERT INTO stm_state (
+                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON DUPLICATE KEY UPDATE rolling_summary = VALUES(rolling_summary),
+                                         key_quotes = VALUES(key_quotes),
+                                         updated_at = VALUES(updated_at)"),
+                (
+                    scope.tenant_id.clone(),
+                    scope.user_id.clone(),
+                    scope.agent_id.clone(),
+                    scope.session_id.clone(),
+                    stm.rolling_summary,
+                    encode_json(&stm.key_quotes)?,
+                    to_millis(Utc::now()),
+                ),
             )
             .map_err(map_mysql_err)?;
             Ok(())
         })
     }
 
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        if filter.include_shared {
+            return crate::shared_facts::list_facts_with_shared(self, scope, filter);
+        }
+        self.with_conn(SqlContext::scoped("list_facts", "facts", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
+                        confidence, sources, scope_level, notes, pinned, lang
+                 FROM facts WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push('?');
+                    params.push(MyValue::from(fact_status_to_str(status)));
+                }
+                sql.push(')');
+            }
+
+            if let Some(pinned) = filter.pinned {
+                sql.push_str(" AND pinned = ?");
+                params.push(MyValue::from(pinned));
+            }
+
+            if let Some(preferences) = filter.preferences {
+                let op = if preferences { "=" } else { "<>" };
+                sql.push_str(&format!(" AND LEFT(fact_key, ?) {op} BINARY ?"));
+                params.push(MyValue::from(PREFERENCE_KEY_PREFIX.len() as i64));
+                params.push(MyValue::from(PREFERENCE_KEY_PREFIX));
+            }
+
+            if let Some(keys) = &filter.keys {
+                if keys.is_empty() {
+                    return Ok(Vec::new());
+                }
+                sql.push_str(" AND fact_key IN (");
+                sql.push_str(&vec!["BINARY ?"; keys.len()].join(", "));
+                sql.push(')');
+                params.extend(keys.iter().map(MyValue::from));
+            }
+
+            if let Some(at) = filter.valid_at {
+                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ?)");
+                params.push(MyValue::from(to_millis(at)));
+                sql.push_str(" AND (valid_to IS NULL OR valid_to >= ?)");
+                params.push(MyValue::from(to_millis(at)));
+            }
+
+            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter().map(fact_from_row).collect()
+        })
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.check_fact_ids(scope, &fact)?;
+        let context = SqlContext::scoped("upsert_fact", "facts", scope);
+        self.with_conn(context, |conn| {
+            in_transaction(conn, |conn| upsert_fact_row(conn, &self.tables, scope, &fact))
+        })
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        if facts.is_empty() {
+            return Ok(());
+        }
+        for fact in facts {
+            self.check_fact_ids(scope, fact)?;
+        }
+        self.with_conn(SqlContext::scoped("upsert_facts_bulk", "facts", scope), |conn| {
+            in_transaction(conn, |conn| {
+                for fact in facts {
+                    upsert_fact_row(conn, &self.tables, scope, fact)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let context = SqlContext::scoped("list_fact_history", "fact_versions", scope);
+        self.with_conn(context, |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.push(MyValue::from(fact_key.to_string()));
+            let rows: Vec<mysql::Row> = conn
+                .exec(
+                    self.tables.sql("SELECT version, recorded_at, fact_json FROM fact_versions
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND fact_key = ?
+                     ORDER BY recorded_at ASC, version ASC"),
+                    Params::Positional(params),
+                )
+                .map_err(map_mysql_err)?;
+            rows.into_iter().map(fact_version_from_row).collect()
+        })
+    }
+
     fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
-        self.with_conn(|conn| {
-            let mut use_index = !filter.tags.is_empty() || !filter.entities.is_empty();
-            if !filter.tags.is_empty() && !episode_tags_present(conn, scope)? {
+        let filter = filter.with_tag_forms();
+        self.with_conn(SqlContext::scoped("list_episodes", "episodes", scope), |conn| {
+            let mut use_index = self.optional.episode_index
+                && (!filter.tags.is_empty() || !filter.entities.is_empty());
+            if use_index
+                && !filter.tags.is_empty()
+                && !episode_tags_present(conn, &self.tables, scope)?
+            {
                 use_index = false;
             }
-            if !filter.entities.is_empty() && !episode_entities_present(conn, scope)? {
+            if use_index
+                && !filter.entities.is_empty()
+                && !episode_entities_present(conn, &self.tables, scope)?
+            {
                 use_index = false;
             }
 
             if use_index {
                 let mut sql = String::from(
                     "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
-                            sources, compression_level, recency_score
+                            sources, compression_level, recency_score, lang
                      FROM episodes WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
                 );
                 let mut params = scope_params_ltm(scope);
@@ -541,54 +1072,14 @@ impl Store for MySqlStore {
                 }
 
                 let rows: Vec<mysql::Row> =
-                    conn.exec(sql, Params::Positional(params))
+                    conn.exec(self.tables.sql(&sql), Params::Positional(params))
                         .map_err(map_mysql_err)?;
-                let mut episodes = Vec::with_capacity(rows.len());
-                for row in rows {
-                    let (
-                        episode_id,
-                        start_ts,
-                        end_ts,
-                        summary,
-                        highlights,
-                        tags,
-                        entities,
-                        sources,
-                        compression_level,
-                        recency_score,
-                    ): (
-                        String,
-                        i64,
-                        Option<i64>,
-                        String,
-                        String,
-                        String,
-                        String,
-                        String,
-                        String,
-                        Option<f64>,
-                    ) = from_row(row);
-                    episodes.push(Episode {
-                        episode_id,
-                        time_range: engram_types::TimeRange {
-                            start: from_millis(start_ts),
-                            end: end_ts.map(from_millis),
-                        },
-                        summary,
-                        highlights: decode_json(&highlights)?,
-                        tags: decode_json(&tags)?,
-                        entities: decode_json(&entities)?,
-                        sources: decode_json(&sources)?,
-                        compression_level: parse_compression_level(&compression_level)?,
-                        recency_score,
-                    });
-                }
-                return Ok(episodes);
+                return rows.into_iter().map(episode_from_row).collect();
             }
 
             let mut sql = String::from(
                 "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
-                        sources, compression_level, recency_score
+                        sources, compression_level, recency_score, lang
                  FROM episodes WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
             );
             let mut params = scope_params_ltm(scope);
@@ -616,48 +1107,12 @@ impl Store for MySqlStore {
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut episodes = Vec::with_capacity(rows.len());
-            for row in rows {
-                let (
-                    episode_id,
-                    start_ts,
-                    end_ts,
-                    summary,
-                    highlights,
-                    tags,
-                    entities,
-                    sources,
-                    compression_level,
-                    recency_score,
-                ): (
-                    String,
-                    i64,
-                    Option<i64>,
-                    String,
-                    String,
-                    String,
-                    String,
-                    String,
-                    String,
-                    Option<f64>,
-                ) = from_row(row);
-                episodes.push(Episode {
-                    episode_id,
-                    time_range: engram_types::TimeRange {
-                        start: from_millis(start_ts),
-                        end: end_ts.map(from_millis),
-                    },
-                    summary,
-                    highlights: decode_json(&highlights)?,
-                    tags: decode_json(&tags)?,
-                    entities: decode_json(&entities)?,
-                    sources: decode_json(&sources)?,
-                    compression_level: parse_compression_level(&compression_level)?,
-                    recency_score,
-                });
-            }
+            let episodes = rows
+                .into_iter()
+                .map(episode_from_row)
+                .collect::<StoreResult<Vec<_>>>()?;
 
             if filter.tags.is_empty() && filter.entities.is_empty() {
                 return Ok(episodes);
@@ -683,47 +1138,84 @@ impl Store for MySqlStore {
                 })
                 .collect::<Vec<_>>();
 
-            if limit_in_sql.is_none() {
-                if let Some(limit) = filter.limit {
-                    if filtered.len() > limit {
+            if limit_in_sql.is_none()
+                && let Some(limit) = filter.limit
+                    && filtered.len() > limit {
                         filtered.truncate(limit);
                     }
-                }
-            }
 
             Ok(filtered)
         })
     }
 
     fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
-        let episode_id = episode.episode_id.clone();
-        let tags = episode.tags.clone();
-        let entities = episode.entities.clone();
-        self.with_conn(|conn| {
-            conn.exec_drop(
-                "INSERT INTO episodes (
-                    tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
-                    highlights, tags, entities, sources, compression_level, recency_score
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                Params::Positional(vec![
-                    MyValue::from(scope.tenant_id.clone()),
-                    MyValue::from(scope.user_id.clone()),
-                    MyValue::from(scope.agent_id.clone()),
-                    MyValue::from(episode.episode_id),
-                    MyValue::from(to_millis(episode.time_range.start)),
-                    option_i64(option_ts(episode.time_range.end)),
-                    MyValue::from(episode.summary),
-                    MyValue::from(encode_json(&episode.highlights)?),
-                    MyValue::from(encode_json(&episode.tags)?),
-                    MyValue::from(encode_json(&episode.entities)?),
-                    MyValue::from(encode_json(&episode.sources)?),
-                    MyValue::from(compression_level_to_str(&episode.compression_level).to_string()),
-                    option_f64(episode.recency_score),
-                ]),
-            )
-            .map_err(map_mysql_err)?;
-            insert_episode_tags(conn, scope, &episode_id, &tags, &entities)?;
-            Ok(())
+        self.check_ids(scope, &[("episode.episode_id", &episode.episode_id)])?;
+        let context = SqlContext::scoped("append_episode", "episodes", scope);
+        self.with_conn(context, |conn| self.insert_episode(conn, scope, &episode))
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        if episodes.is_empty() {
+            return Ok(());
+        }
+        for episode in episodes {
+            self.check_ids(scope, &[("episode.episode_id", &episode.episode_id)])?;
+        }
+        self.with_conn(SqlContext::scoped("append_episodes_bulk", "episodes", scope), |conn| {
+            in_transaction(conn, |conn| {
+                for episode in episodes {
+                    self.insert_episode(conn, scope, episode)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        let terms = query.searchable_terms();
+        if !self.optional.text_index {
+            return search_episodes_unindexed(self, scope, query);
+        }
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let against = fulltext_query(&terms);
+        self.with_conn(SqlContext::scoped("search_episodes", "episodes", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
+                        sources, compression_level, recency_score, lang
+                 FROM episodes
+                 WHERE MATCH (summary, highlights) AGAINST (? IN BOOLEAN MODE)
+                   AND tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = vec![MyValue::from(against.clone())];
+            params.extend(scope_params_ltm(scope));
+
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND start_ts >= ?");
+                    params.push(MyValue::from(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ?");
+                    params.push(MyValue::from(to_millis(end)));
+                }
+            }
+
+            sql.push_str(
+                " ORDER BY MATCH (summary, highlights) AGAINST (? IN BOOLEAN MODE) DESC,
+                   start_ts ASC, episode_id ASC",
+            );
+            params.push(MyValue::from(against.clone()));
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter().map(episode_from_row).collect()
         })
     }
 
@@ -733,7 +1225,7 @@ impl Store for MySqlStore {
         task_type: &str,
         limit: Option<usize>,
     ) -> StoreResult<Vec<Procedure>> {
-        self.with_conn(|conn| {
+        self.with_conn(SqlContext::scoped("list_procedures", "procedures", scope), |conn| {
             let mut sql = String::from(
                 "SELECT procedure_id, task_type, content_json, priority, sources, applicability
                  FROM procedures WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND task_type = ?",
@@ -748,35 +1240,21 @@ impl Store for MySqlStore {
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut procedures = Vec::with_capacity(rows.len());
-            for row in rows {
-                let (procedure_id, task_type, content_json, priority, sources, applicability): (
-                    String,
-                    String,
-                    String,
-                    i32,
-                    String,
-                    String,
-                ) = from_row(row);
-                procedures.push(Procedure {
-                    procedure_id,
-                    task_type,
-                    content: decode_json(&content_json)?,
-                    priority,
-                    sources: decode_json(&sources)?,
-                    applicability: decode_json(&applicability)?,
-                });
-            }
-            Ok(procedures)
+            rows.into_iter().map(procedure_from_row).collect()
         })
     }
 
     fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
-        self.with_conn(|conn| {
+        let ids = [
+            ("procedure.procedure_id", procedure.procedure_id.as_str()),
+            ("procedure.task_type", procedure.task_type.as_str()),
+        ];
+        self.check_ids(scope, &ids)?;
+        self.with_conn(SqlContext::scoped("upsert_procedure", "procedures", scope), |conn| {
             conn.exec_drop(
-                "INSERT INTO procedures (
+                self.tables.sql("INSERT INTO procedures (
                     tenant_id, user_id, agent_id, procedure_id, task_type, content_json,
                     priority, sources, applicability
                  ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
@@ -784,7 +1262,7 @@ impl Store for MySqlStore {
                                          content_json = VALUES(content_json),
                                          priority = VALUES(priority),
                                          sources = VALUES(sources),
-                                         applicability = VALUES(applicability)",
+                                         applicability = VALUES(applicability)"),
                 (
                     scope.tenant_id.clone(),
                     scope.user_id.clone(),
@@ -802,28 +1280,159 @@ impl Store for MySqlStore {
         })
     }
 
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        let context =
+            SqlContext::scoped("list_procedure_candidates", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
+            let mut sql = String::from(
+                "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                        created_at, reviewed_at, reviewer, review_note
+                 FROM procedure_candidates WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+
+            if let Some(task_type) = &filter.task_type {
+                sql.push_str(" AND task_type = ?");
+                params.push(MyValue::from(task_type.clone()));
+            }
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push('?');
+                    params.push(MyValue::from(candidate_status_to_str(status).to_string()));
+                }
+                sql.push(')');
+            }
+
+            sql.push_str(" ORDER BY created_at ASC, candidate_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            let mut candidates = Vec::with_capacity(rows.len());
+            for row in rows {
+                candidates.push(procedure_candidate_from_row(from_row(row))?);
+            }
+            Ok(candidates)
+        })
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        let context = SqlContext::scoped("get_procedure_candidate", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
+            let row: Option<ProcedureCandidateRow> = conn
+                .exec_first(
+                    self.tables.sql(
+                        "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                                created_at, reviewed_at, reviewer, review_note
+                         FROM procedure_candidates
+                         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND candidate_id = ?",
+                    ),
+                    (
+                        scope.tenant_id.clone(),
+                        scope.user_id.clone(),
+                        scope.agent_id.clone(),
+                        candidate_id.to_string(),
+                    ),
+                )
+                .map_err(map_mysql_err)?;
+            match row {
+                Some(row) => Ok(Some(procedure_candidate_from_row(row)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        let ids = [
+            ("candidate.candidate_id", candidate.candidate_id.as_str()),
+            ("candidate.procedure.task_type", candidate.procedure.task_type.as_str()),
+            ("candidate.reviewer", candidate.reviewer.as_str()),
+        ];
+        self.check_ids(scope, &ids)?;
+        let context =
+            SqlContext::scoped("upsert_procedure_candidate", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
+            conn.exec_drop(
+                self.tables.sql("INSERT INTO procedure_candidates (
+                    tenant_id, user_id, agent_id, candidate_id, task_type, procedure_json,
+                    source_episodes, evidence, status, created_at, reviewed_at, reviewer,
+                    review_note
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON DUPLICATE KEY UPDATE task_type = VALUES(task_type),
+                                         procedure_json = VALUES(procedure_json),
+                                         source_episodes = VALUES(source_episodes),
+                                         evidence = VALUES(evidence),
+                                         status = VALUES(status),
+                                         created_at = VALUES(created_at),
+                                         reviewed_at = VALUES(reviewed_at),
+                                         reviewer = VALUES(reviewer),
+                                         review_note = VALUES(review_note)"),
+                Params::Positional(vec![
+                    MyValue::from(scope.tenant_id.clone()),
+                    MyValue::from(scope.user_id.clone()),
+                    MyValue::from(scope.agent_id.clone()),
+                    MyValue::from(candidate.candidate_id),
+                    MyValue::from(candidate.procedure.task_type.clone()),
+                    MyValue::from(encode_json(&candidate.procedure)?),
+                    MyValue::from(encode_json(&candidate.source_episodes)?),
+                    MyValue::from(encode_json(&candidate.evidence)?),
+                    MyValue::from(candidate_status_to_str(&candidate.status).to_string()),
+                    MyValue::from(to_millis(candidate.created_at)),
+                    MyValue::from(candidate.reviewed_at.map(to_millis)),
+                    MyValue::from(candidate.reviewer),
+                    MyValue::from(candidate.review_note),
+                ]),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(())
+        })
+    }
+
     fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
-        self.with_conn(|conn| {
+        self.with_conn(SqlContext::scoped("list_insights", "insights", scope), |conn| {
             let mut sql = String::from(
                 "SELECT insight_id, kind, statement, `trigger`, confidence, validation_state,
-                        tests_suggested, expires_at, sources
+                        tests_suggested, expires_at, sources, parent_insight_id
                  FROM insights
                  WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
             );
             let mut params = scope_params(scope);
 
-            if let Some(states) = &filter.validation_state {
-                if !states.is_empty() {
-                    sql.push_str(" AND validation_state IN (");
-                    for (idx, state) in states.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str("?");
-                        params.push(MyValue::from(validation_state_to_str(state)));
+            if let Some(states) = &filter.validation_state
+                && !states.is_empty()
+            {
+                sql.push_str(" AND validation_state IN (");
+                for (idx, state) in states.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
                     }
-                    sql.push(')');
+                    sql.push('?');
+                    params.push(MyValue::from(validation_state_to_str(state)));
                 }
+                sql.push(')');
             }
 
             sql.push_str(" ORDER BY validation_state DESC, confidence DESC, insight_id ASC");
@@ -833,46 +1442,23 @@ impl Store for MySqlStore {
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut insights = Vec::with_capacity(rows.len());
-            for row in rows {
-                let (
-                    insight_id,
-                    kind,
-                    statement,
-                    trigger,
-                    confidence,
-                    validation_state,
-                    tests_suggested,
-                    expires_at,
-                    sources,
-                ): (String, String, String, String, f64, String, String, String, String) =
-                    from_row(row);
-                insights.push(InsightItem {
-                    id: insight_id,
-                    kind: parse_insight_type(&kind)?,
-                    statement,
-                    trigger: parse_insight_trigger(&trigger)?,
-                    confidence,
-                    validation_state: parse_validation_state(&validation_state)?,
-                    tests_suggested: decode_json(&tests_suggested)?,
-                    expires_at,
-                    sources: decode_json(&sources)?,
-                });
-            }
-            Ok(insights)
+            rows.into_iter().map(insight_from_row).collect()
         })
     }
 
     fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
-        self.with_conn(|conn| {
+        let parent = insight.parent_insight_id.as_deref().unwrap_or_default();
+        let ids = [("insight.id", insight.id.as_str()), ("insight.parent_insight_id", parent)];
+        self.check_ids(scope, &ids)?;
+        self.with_conn(SqlContext::scoped("append_insight", "insights", scope), |conn| {
             conn.exec_drop(
-                "INSERT INTO insights (
+                self.tables.sql("INSERT INTO insights (
                     tenant_id, user_id, agent_id, session_id, run_id, insight_id,
                     kind, statement, `trigger`, confidence, validation_state,
-                    tests_suggested, expires_at, sources
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    tests_suggested, expires_at, sources, parent_insight_id
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"),
                 Params::Positional(vec![
                     MyValue::from(scope.tenant_id.clone()),
                     MyValue::from(scope.user_id.clone()),
@@ -888,6 +1474,7 @@ impl Store for MySqlStore {
                     MyValue::from(encode_json(&insight.tests_suggested)?),
                     MyValue::from(insight.expires_at),
                     MyValue::from(encode_json(&insight.sources)?),
+                    MyValue::from(insight.parent_insight_id),
                 ]),
             )
             .map_err(map_mysql_err)?;
@@ -895,12 +1482,33 @@ impl Store for MySqlStore {
         })
     }
 
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("expire_insights", "insights", scope), |conn| {
+            conn.exec_drop(
+                self.tables.sql("DELETE FROM insights
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                   AND expires_at = ?"),
+                (
+                    scope.tenant_id.clone(),
+                    scope.user_id.clone(),
+                    scope.agent_id.clone(),
+                    scope.session_id.clone(),
+                    scope.run_id.clone(),
+                    expires_at.to_string(),
+                ),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(conn.affected_rows() as usize)
+        })
+    }
+
     fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
-        self.with_conn(|conn| {
+        self.check_ids(scope, &[])?;
+        self.with_conn(SqlContext::scoped("write_context_build", "context_builds", scope), |conn| {
             conn.exec_drop(
-                "INSERT INTO context_builds (
+                self.tables.sql("INSERT INTO context_builds (
                     tenant_id, user_id, agent_id, session_id, run_id, ts, packet_json
-                 ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
                 (
                     scope.tenant_id.clone(),
                     scope.user_id.clone(),
@@ -921,137 +1529,882 @@ impl Store for MySqlStore {
         scope: &Scope,
         limit: Option<usize>,
     ) -> StoreResult<Vec<MemoryPacket>> {
-        self.with_conn(|conn| {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| decode_json(packet_json))
+            .collect()
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| ContextBuildSummary::from_packet_json(packet_json))
+            .collect()
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.with_conn(SqlContext::new("tenant_activity", "events", &[tenant_id]), |conn| {
             let mut sql = String::from(
-                "SELECT packet_json FROM context_builds
-                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
-                 ORDER BY ts ASC",
+                "SELECT user_id, ts DIV 86400000 AS day, COUNT(*) FROM events WHERE tenant_id = ?",
+            );
+            let mut params = vec![MyValue::from(tenant_id)];
+            if let Some(start) = range.start {
+                sql.push_str(" AND ts >= ?");
+                params.push(MyValue::from(to_millis(start)));
+            }
+            if let Some(end) = range.end {
+                sql.push_str(" AND ts <= ?");
+                params.push(MyValue::from(to_millis(end)));
+            }
+            sql.push_str(" GROUP BY user_id, day");
+
+            let event_rows: Vec<(String, i64, i64)> = conn
+                .exec(self.tables.sql(&sql), Params::Positional(params))
+                .map_err(map_mysql_err)?;
+
+            let mut counts = Vec::new();
+            for table in ["facts", "episodes"] {
+                let sql = format!(
+                    "SELECT user_id, COUNT(*) FROM {} WHERE tenant_id = ? GROUP BY user_id",
+                    table
+                );
+                let rows: Vec<(String, i64)> = conn
+                    .exec(
+                        self.tables.sql(&sql),
+                        Params::Positional(vec![MyValue::from(tenant_id)]),
+                    )
+                    .map_err(map_mysql_err)?;
+                counts.push(rows);
+            }
+            let episode_rows = counts.pop().unwrap_or_default();
+            let fact_rows = counts.pop().unwrap_or_default();
+            Ok(collect_user_activity(event_rows, fact_rows, episode_rows))
+        })
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        let context = SqlContext::new("list_scopes", "events", &[tenant_id.unwrap_or_default()]);
+        self.with_conn(context, |conn| {
+            let mut sql = String::from(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(ts) AS last_ts
+                 FROM events",
+            );
+            let mut params = Vec::new();
+            if let Some(tenant_id) = tenant_id {
+                sql.push_str(" WHERE tenant_id = ?");
+                params.push(MyValue::from(tenant_id));
+            }
+            sql.push_str(
+                " GROUP BY tenant_id, user_id, agent_id, session_id, run_id
+                 ORDER BY last_ts DESC, tenant_id, user_id, agent_id, session_id, run_id",
             );
-            let mut params = scope_params(scope);
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(MyValue::from(limit as i64));
             }
 
             let rows: Vec<mysql::Row> =
-                conn.exec(sql, Params::Positional(params))
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
                     .map_err(map_mysql_err)?;
-            let mut packets = Vec::with_capacity(rows.len());
+            let mut scopes = Vec::with_capacity(rows.len());
             for row in rows {
-                let (payload,): (String,) = from_row(row);
-                packets.push(decode_json(&payload)?);
+                let (tenant_id, user_id, agent_id, session_id, run_id, _last_ts): (
+                    String,
+                    String,
+                    String,
+                    String,
+                    String,
+                    i64,
+                ) = from_row(row);
+                scopes.push(Scope {
+                    tenant_id,
+                    user_id,
+                    agent_id,
+                    session_id,
+                    run_id,
+                });
             }
-            Ok(packets)
+            Ok(scopes)
         })
     }
-}
 
-fn ensure_schema(conn: &mut PooledConn) -> StoreResult<()> {
-    conn.query_drop(
-        "CREATE TABLE IF NOT EXISTS schema_migrations (
-            version BIGINT NOT NULL PRIMARY KEY,
-            applied_at BIGINT NOT NULL
-        ) ENGINE=InnoDB",
-    )
-    .map_err(map_mysql_err)?;
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.check_ids(scope, &[("item.id", &item.id)])?;
+        self.with_conn(SqlContext::scoped("suppress_memory", "suppressions", scope), |conn| {
+            conn.exec_drop(
+                self.tables.sql("INSERT INTO suppressions (
+                    tenant_id, user_id, agent_id, item_kind, item_id, reason, suppressed_at
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON DUPLICATE KEY UPDATE reason = VALUES(reason),
+                                         suppressed_at = VALUES(suppressed_at)"),
+                (
+                    scope.tenant_id.clone(),
+                    scope.user_id.clone(),
+                    scope.agent_id.clone(),
+                    memory_kind_to_str(&item.kind),
+                    item.id,
+                    reason.to_string(),
+                    to_millis(Utc::now()),
+                ),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(())
+        })
+    }
 
-    let current: Option<(i64,)> = conn
-        .query_first("SELECT version FROM schema_migrations ORDER BY version DESC LIMIT 1")
-        .map_err(map_mysql_err)?;
-    let current = current.map(|(version,)| version).unwrap_or(0);
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.with_conn(SqlContext::scoped("unsuppress_memory", "suppressions", scope), |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.push(MyValue::from(memory_kind_to_str(&item.kind)));
+            params.push(MyValue::from(item.id.clone()));
+            conn.exec_drop(
+                self.tables.sql(
+                    "DELETE FROM suppressions
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND item_kind = ? AND item_id = ?",
+                ),
+                Params::Positional(params),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(conn.affected_rows() > 0)
+        })
+    }
 
-    if current > SCHEMA_VERSION {
-        return Err(StoreError::Storage(format!(
-            "database schema version {} is newer than supported {}",
-            current, SCHEMA_VERSION
-        )));
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.with_conn(SqlContext::scoped("list_suppressions", "suppressions", scope), |conn| {
+            let rows: Vec<mysql::Row> = conn
+                .exec(
+                    self.tables.sql(
+                        "SELECT item_kind, item_id, reason, suppressed_at FROM suppressions
+                         WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                         ORDER BY suppressed_at ASC, item_id ASC",
+                    ),
+                    Params::Positional(scope_params_ltm(scope)),
+                )
+                .map_err(map_mysql_err)?;
+            let mut suppressions = Vec::with_capacity(rows.len());
+            for row in rows {
+                let (kind, id, reason, suppressed_at): (String, String, String, i64) = from_row(row);
+                suppressions.push(Suppression {
+                    item: MemoryRef {
+                        kind: parse_memory_kind(&kind)?,
+                        id,
+                    },
+                    reason,
+                    suppressed_at: from_millis(suppressed_at),
+                });
+            }
+            Ok(suppressions)
+        })
     }
 
-    if current < SCHEMA_VERSION && current != 0 {
-        return Err(StoreError::Storage(format!(
-            "database schema version {} requires migration to {}",
-            current, SCHEMA_VERSION
-        )));
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("evict_memory", "facts, episodes", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut evicted = 0;
+            for item in items {
+                let mut params = scope_params_ltm(scope);
+                params.push(MyValue::from(item.id.clone()));
+                let (table, id_column) = match item.kind {
+                    MemoryKind::Fact => ("facts", "fact_id"),
+                    MemoryKind::Episode => ("episodes", "episode_id"),
+                };
+                tx.exec_drop(
+                    self.tables.sql(&format!(
+                        "DELETE FROM {}
+                         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND {} = ?",
+                        table, id_column
+                    )),
+                    Params::Positional(params.clone()),
+                )
+                .map_err(map_mysql_err)?;
+                evicted += tx.affected_rows() as usize;
+                if item.kind == MemoryKind::Episode && self.optional.episode_index {
+                    for index in ["episode_tags", "episode_entities"] {
+                        tx.exec_drop(
+                            self.tables.sql(&format!(
+                                "DELETE FROM {} WHERE tenant_id = ? AND user_id = ?
+                                   AND agent_id = ? AND episode_id = ?",
+                                index
+                            )),
+                            Params::Positional(params.clone()),
+                        )
+                        .map_err(map_mysql_err)?;
+                    }
+                }
+                params.insert(3, MyValue::from(memory_kind_to_str(&item.kind)));
+                for table in ["suppressions", "memory_embeddings"] {
+                    tx.exec_drop(
+                        self.tables.sql(&format!(
+                            "DELETE FROM {}
+                             WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                               AND item_kind = ? AND item_id = ?",
+                            table
+                        )),
+                        Params::Positional(params.clone()),
+                    )
+                    .map_err(map_mysql_err)?;
+                }
+            }
+            tx.commit().map_err(map_mysql_err)?;
+            Ok(evicted)
+        })
     }
 
-    let schema = [
-        "CREATE TABLE IF NOT EXISTS events (
-            event_id VARCHAR(96) PRIMARY KEY,
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            ts BIGINT NOT NULL,
-            kind VARCHAR(32) NOT NULL,
-            payload TEXT NOT NULL,
-            tags TEXT NOT NULL,
-            entities TEXT NOT NULL
-        ) ENGINE=InnoDB",
-        "CREATE INDEX events_scope_ts
-            ON events (tenant_id, user_id, agent_id, session_id, run_id, ts)",
-        "CREATE TABLE IF NOT EXISTS event_tags (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            event_id VARCHAR(96) NOT NULL,
-            tag VARCHAR(64) NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, event_id, tag)
-        ) ENGINE=InnoDB",
-        "CREATE INDEX event_tags_scope_tag
-            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag)",
-        "CREATE TABLE IF NOT EXISTS event_entities (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            event_id VARCHAR(96) NOT NULL,
-            entity VARCHAR(64) NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, event_id, entity)
-        ) ENGINE=InnoDB",
-        "CREATE INDEX event_entities_scope_entity
-            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity)",
-        "CREATE TABLE IF NOT EXISTS wm_state (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            state_json TEXT NOT NULL,
-            updated_at BIGINT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
-        ) ENGINE=InnoDB",
-        "CREATE TABLE IF NOT EXISTS stm_state (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            rolling_summary TEXT NOT NULL,
-            key_quotes TEXT NOT NULL,
-            updated_at BIGINT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, session_id)
-        ) ENGINE=InnoDB",
-        "CREATE TABLE IF NOT EXISTS facts (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            fact_id VARCHAR(96) NOT NULL,
-            fact_key VARCHAR(96) NOT NULL,
-            value_json TEXT NOT NULL,
-            status VARCHAR(32) NOT NULL,
-            valid_from BIGINT NULL,
-            valid_to BIGINT NULL,
-            confidence DOUBLE NOT NULL,
-            sources TEXT NOT NULL,
-            scope_level VARCHAR(32) NOT NULL,
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.with_conn(SqlContext::scoped("delete_record", item.kind.table().0, scope), |conn| {
+            in_transaction(conn, |conn| {
+                let removed = self.remove_records(conn, scope, item.kind, Some(&item.id), mode)?;
+                Ok(removed > 0)
+            })
+        })
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        let tables = "events, facts, episodes, procedures, insights";
+        let context = SqlContext::scoped("clear_scope", tables, scope);
+        self.with_conn(context, |conn| {
+            in_transaction(conn, |conn| {
+                let mut removed = 0;
+                for kind in RecordKind::ALL {
+                    removed += self.remove_records(conn, scope, kind, None, mode)?;
+                }
+                Ok(removed)
+            })
+        })
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        let (table, _) = kind.table();
+        self.with_conn(SqlContext::scoped("count_records", table, scope), |conn| {
+            let (filter, params) = if kind.run_level() {
+                (
+                    "tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? \
+                     AND run_id = ?",
+                    scope_params(scope),
+                )
+            } else {
+                ("tenant_id = ? AND user_id = ? AND agent_id = ?", scope_params_ltm(scope))
+            };
+            let count: Option<u64> = conn
+                .exec_first(
+                    self.tables.sql(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter)),
+                    Params::Positional(params),
+                )
+                .map_err(map_mysql_err)?;
+            Ok(count.unwrap_or(0) as usize)
+        })
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.with_conn(SqlContext::scoped("list_tombstones", "tombstones", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id,
+                        record_json, deleted_at
+                 FROM tombstones WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                 ORDER BY id DESC",
+            );
+            let mut params = scope_params_ltm(scope);
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+            let rows: Vec<TombstoneRow> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            rows.into_iter()
+                .map(
+                    |(tenant_id, user_id, agent_id, session_id, run_id, kind, id, record, ts)| {
+                        Ok(Tombstone {
+                            scope: Scope {
+                                tenant_id,
+                                user_id,
+                                agent_id,
+                                session_id,
+                                run_id,
+                            },
+                            item: RecordRef::new(parse_record_kind(&kind)?, id),
+                            record: decode_json(&record)?,
+                            deleted_at: from_millis(ts),
+                        })
+                    },
+                )
+                .collect()
+        })
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        for embedding in &embeddings {
+            let ids = [
+                ("embedding.item.id", embedding.item.id.as_str()),
+                ("embedding.model", embedding.model.as_str()),
+            ];
+            self.check_ids(scope, &ids)?;
+        }
+        self.with_conn(SqlContext::scoped("upsert_embeddings", "memory_embeddings", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            for embedding in embeddings {
+                let mut params = scope_params_ltm(scope);
+                params.extend([
+                    MyValue::from(memory_kind_to_str(&embedding.item.kind)),
+                    MyValue::from(embedding.item.id),
+                    MyValue::from(embedding.model),
+                    MyValue::from(encode_json(&embedding.vector)?),
+                    MyValue::from(embedding.digest),
+                    MyValue::from(to_millis(embedding.embedded_at)),
+                ]);
+                tx.exec_drop(
+                    self.tables.sql("INSERT INTO memory_embeddings (
+                        tenant_id, user_id, agent_id, item_kind, item_id, model, vector_json,
+                        digest, embedded_at
+                     ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                     ON DUPLICATE KEY UPDATE vector_json = VALUES(vector_json),
+                        digest = VALUES(digest), embedded_at = VALUES(embedded_at)"),
+                    Params::Positional(params),
+                )
+                .map_err(map_mysql_err)?;
+            }
+            tx.commit().map_err(map_mysql_err)
+        })
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.with_conn(SqlContext::scoped("list_embeddings", "memory_embeddings", scope), |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.push(MyValue::from(model));
+            let rows: Vec<(String, String, String, String, i64)> = conn
+                .exec(
+                    self.tables.sql("SELECT item_kind, item_id, vector_json, digest, embedded_at
+                     FROM memory_embeddings
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND model = ?
+                     ORDER BY item_kind ASC, item_id ASC"),
+                    Params::Positional(params),
+                )
+                .map_err(map_mysql_err)?;
+            rows.into_iter()
+                .map(|(kind, id, vector, digest, embedded_at)| {
+                    Ok(MemoryEmbedding {
+                        item: MemoryRef {
+                            kind: parse_memory_kind(&kind)?,
+                            id,
+                        },
+                        model: model.to_string(),
+                        vector: decode_json(&vector)?,
+                        digest,
+                        embedded_at: from_millis(embedded_at),
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("delete_embeddings", "memory_embeddings", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut deleted = 0;
+            for item in items {
+                let mut params = scope_params_ltm(scope);
+                params.extend([
+                    MyValue::from(memory_kind_to_str(&item.kind)),
+                    MyValue::from(item.id.clone()),
+                    MyValue::from(model),
+                ]);
+                tx.exec_drop(
+                    self.tables.sql("DELETE FROM memory_embeddings
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                       AND item_kind = ? AND item_id = ? AND model = ?"),
+                    Params::Positional(params),
+                )
+                .map_err(map_mysql_err)?;
+                deleted += tx.affected_rows() as usize;
+            }
+            tx.commit().map_err(map_mysql_err)?;
+            Ok(deleted)
+        })
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.check_ids(scope, &[])?;
+        let outcome = outcome_for_run(scope, outcome)?;
+        self.with_conn(SqlContext::scoped("record_run_outcome", "run_outcomes", scope), |conn| {
+            let mut params = scope_params(scope);
+            params.extend([
+                MyValue::from(run_status_to_str(&outcome.status)),
+                MyValue::from(outcome.score),
+                MyValue::from(outcome.error_summary),
+                MyValue::from(outcome.duration_ms as i64),
+                MyValue::from(to_millis(outcome.recorded_at)),
+            ]);
+            conn.exec_drop(
+                self.tables.sql("INSERT INTO run_outcomes (
+                    tenant_id, user_id, agent_id, session_id, run_id,
+                    status, score, error_summary, duration_ms, recorded_at
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON DUPLICATE KEY UPDATE status = VALUES(status),
+                                         score = VALUES(score),
+                                         error_summary = VALUES(error_summary),
+                                         duration_ms = VALUES(duration_ms),
+                                         recorded_at = VALUES(recorded_at)"),
+                Params::Positional(params),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(())
+        })
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.with_conn(SqlContext::scoped("list_run_outcomes", "run_outcomes", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT session_id, run_id, status, score, error_summary, duration_ms, recorded_at
+                 FROM run_outcomes
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                 ORDER BY recorded_at DESC, run_id ASC",
+            );
+            let mut params = scope_params_ltm(scope);
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<mysql::Row> =
+                conn.exec(self.tables.sql(&sql), Params::Positional(params))
+                    .map_err(map_mysql_err)?;
+            let mut outcomes = Vec::with_capacity(rows.len());
+            for row in rows {
+                let (session_id, run_id, status, score, error_summary, duration_ms, recorded_at): (
+                    String,
+                    String,
+                    String,
+                    Option<f64>,
+                    String,
+                    i64,
+                    i64,
+                ) = from_row(row);
+                outcomes.push(RunOutcome {
+                    session_id,
+                    run_id,
+                    status: parse_run_status(&status)?,
+                    score,
+                    error_summary,
+                    duration_ms: duration_ms as u64,
+                    recorded_at: from_millis(recorded_at),
+                });
+            }
+            Ok(outcomes)
+        })
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        credibility.check()?;
+        check_id_len("tenant_id", tenant_id, self.id_len)?;
+        let context = SqlContext::new("set_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_conn(context, |conn| {
+            conn.exec_drop(
+                self.tables.sql(
+                    "INSERT INTO source_credibility (tenant_id, credibility_json, updated_at)
+                     VALUES (?, ?, ?)
+                     ON DUPLICATE KEY UPDATE
+                        credibility_json = VALUES(credibility_json), updated_at = VALUES(updated_at)",
+                ),
+                (tenant_id, encode_json(&credibility)?, to_millis(Utc::now())),
+            )
+            .map_err(map_mysql_err)
+        })
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        let context = SqlContext::new("get_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_conn(context, |conn| {
+            let json: Option<String> = conn
+                .exec_first(
+                    self.tables.sql(
+                        "SELECT credibility_json FROM source_credibility WHERE tenant_id = ?",
+                    ),
+                    (tenant_id,),
+                )
+                .map_err(map_mysql_err)?;
+            json.map(|json| decode_json(&json)).transpose()
+        })
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        locale.check()?;
+        check_id_len("tenant_id", tenant_id, self.id_len)?;
+        check_id_len("user_id", user_id, self.id_len)?;
+        let context = SqlContext::new("set_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_conn(context, |conn| {
+            conn.exec_drop(
+                self.tables.sql(
+                    "INSERT INTO user_locales (tenant_id, user_id, locale_json, updated_at)
+                     VALUES (?, ?, ?, ?)
+                     ON DUPLICATE KEY UPDATE
+                        locale_json = VALUES(locale_json), updated_at = VALUES(updated_at)",
+                ),
+                (tenant_id, user_id, encode_json(&locale)?, to_millis(Utc::now())),
+            )
+            .map_err(map_mysql_err)
+        })
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        let context = SqlContext::new("get_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_conn(context, |conn| {
+            let json: Option<String> = conn
+                .exec_first(
+                    self.tables.sql(
+                        "SELECT locale_json FROM user_locales WHERE tenant_id = ? AND user_id = ?",
+                    ),
+                    (tenant_id, user_id),
+                )
+                .map_err(map_mysql_err)?;
+            json.map(|json| decode_json(&json)).transpose()
+        })
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("append_change", "changelog", &[]), |conn| {
+            conn.exec_drop(
+                self.tables.sql("INSERT INTO changelog (ts, op, correlation_id) VALUES (?, ?, ?)"),
+                (to_millis(Utc::now()), encode_json(&op)?, correlation_id()),
+            )
+            .map_err(map_mysql_err)?;
+            Ok(conn.last_insert_id())
+        })
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        let mut conn = self.pool.get_conn().map_err(map_mysql_err)?;
+        conn.query_drop("START TRANSACTION").map_err(map_mysql_err)?;
+        let (mut conn, result) = self.pinned.run(conn, || {
+            for op in write()? {
+                self.append_change(op)?;
+            }
+            Ok(())
+        })?;
+        let committed = result.and_then(|()| conn.query_drop("COMMIT").map_err(map_mysql_err));
+        if committed.is_err() {
+            // The connection goes back to the pool; it must not stay in the transaction.
+            let _ = conn.query_drop("ROLLBACK");
+        }
+        committed
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("load_change_cursor", "sync_cursors", &[name]), |conn| {
+            let seq: Option<i64> = conn
+                .exec_first(
+                    self.tables.sql("SELECT seq FROM sync_cursors WHERE name = ?"),
+                    (name,),
+                )
+                .map_err(map_mysql_err)?;
+            Ok(seq.unwrap_or(0) as u64)
+        })
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.with_conn(SqlContext::new("save_change_cursor", "sync_cursors", &[name]), |conn| {
+            conn.exec_drop(
+                self.tables.sql("INSERT INTO sync_cursors (name, seq, updated_at) VALUES (?, ?, ?)
+                 ON DUPLICATE KEY UPDATE seq = VALUES(seq), updated_at = VALUES(updated_at)"),
+                (name, seq as i64, to_millis(Utc::now())),
+            )
+            .map_err(map_mysql_err)
+        })
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.with_conn(SqlContext::new("list_changes", "changelog", &[]), |conn| {
+            let mut sql = String::from(
+                "SELECT seq, ts, op, correlation_id FROM changelog WHERE seq > ? ORDER BY seq ASC",
+            );
+            let mut params = vec![MyValue::from(after_seq as i64)];
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(MyValue::from(limit as i64));
+            }
+
+            let rows: Vec<(i64, i64, String, Option<String>)> = conn
+                .exec(self.tables.sql(&sql), Params::Positional(params))
+                .map_err(map_mysql_err)?;
+            let mut changes = Vec::with_capacity(rows.len());
+            for (seq, ts, op, correlation_id) in rows {
+                changes.push(Change {
+                    seq: seq as u64,
+                    ts: from_millis(ts),
+                    correlation_id,
+                    op: decode_json(&op)?,
+                });
+            }
+            Ok(changes)
+        })
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("last_change_seq", "changelog", &[]), |conn| {
+            let seq: Option<i64> = conn
+                .query_first(self.tables.sql("SELECT COALESCE(MAX(seq), 0) FROM changelog"))
+                .map_err(map_mysql_err)?;
+            Ok(seq.unwrap_or(0) as u64)
+        })
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.metrics.snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        StoreCapabilities {
+            vector_search: true,
+            indexed_filters: self.optional.event_index && self.optional.episode_index,
+            transactions: true,
+            bulk_ops: true,
+            full_text_search: self.optional.text_index,
+            subscriptions: false,
+        }
+    }
+}
+
+const EVENT_SEQUENCES_TABLE: &str = "CREATE TABLE IF NOT EXISTS event_sequences (
+    tenant_id VARCHAR(96) NOT NULL,
+    user_id VARCHAR(96) NOT NULL,
+    agent_id VARCHAR(96) NOT NULL,
+    session_id VARCHAR(96) NOT NULL,
+    run_id VARCHAR(96) NOT NULL,
+    last_seq BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+) ENGINE=InnoDB";
+
+/// A transaction, or a savepoint of the transaction its connection is already in;
+/// rolled back when dropped uncommitted.
+struct MySqlTransaction<'a> {
+    conn: &'a mut PooledConn,
+    savepoint: bool,
+    done: bool,
+}
+
+impl<'a> MySqlTransaction<'a> {
+    fn begin(conn: &'a mut PooledConn, savepoint: bool) -> Result<Self, mysql::Error> {
+        conn.query_drop(if savepoint { "SAVEPOINT engram_write" } else { "START TRANSACTION" })?;
+        Ok(Self {
+            conn,
+            savepoint,
+            done: false,
+        })
+    }
+
+    fn commit(mut self) -> Result<(), mysql::Error> {
+        self.done = true;
+        let commit = if self.savepoint { "RELEASE SAVEPOINT engram_write" } else { "COMMIT" };
+        self.conn.query_drop(commit)
+    }
+}
+
+impl std::ops::Deref for MySqlTransaction<'_> {
+    type Target = PooledConn;
+
+    fn deref(&self) -> &PooledConn {
+        self.conn
+    }
+}
+
+impl std::ops::DerefMut for MySqlTransaction<'_> {
+    fn deref_mut(&mut self) -> &mut PooledConn {
+        self.conn
+    }
+}
+
+impl Drop for MySqlTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let rollback =
+                if self.savepoint { "ROLLBACK TO SAVEPOINT engram_write" } else { "ROLLBACK" };
+            let _ = self.conn.query_drop(rollback);
+        }
+    }
+}
+
+fn migrate_event_seq(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    id_len: usize,
+) -> StoreResult<()> {
+    let statement = "ALTER TABLE events ADD COLUMN seq BIGINT NOT NULL DEFAULT 0";
+    match conn.query_drop(tables.sql(statement)) {
+        Ok(()) => {}
+        Err(err) if is_duplicate_column(&err) => {}
+        Err(err) => return Err(map_mysql_err(err)),
+    }
+    conn.query_drop(
+        tables.sql("UPDATE events
+         JOIN (
+            SELECT event_id, ROW_NUMBER() OVER (
+                PARTITION BY tenant_id, user_id, agent_id, session_id, run_id
+                ORDER BY ts, event_id
+            ) AS rn
+            FROM events
+         ) AS ranked ON ranked.event_id = events.event_id
+         SET events.seq = ranked.rn"),
+    )
+    .map_err(map_mysql_err)?;
+    apply_schema_statement(conn, tables, &sized(EVENT_SEQUENCES_TABLE, id_len))?;
+    conn.query_drop(
+        tables.sql(
+            "INSERT IGNORE INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+             SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(seq)
+             FROM events
+             GROUP BY tenant_id, user_id, agent_id, session_id, run_id",
+        ),
+    )
+    .map_err(map_mysql_err)?;
+    Ok(())
+}
+
+fn ensure_schema(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    id_len: usize,
+) -> StoreResult<OptionalTables> {
+    conn.query_drop(
+        tables.sql("CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT NOT NULL PRIMARY KEY,
+            applied_at BIGINT NOT NULL
+        ) ENGINE=InnoDB"),
+    )
+    .map_err(map_mysql_err)?;
+
+    let current: Option<(i64,)> = conn
+        .query_first(
+            tables.sql("SELECT version FROM schema_migrations ORDER BY version DESC LIMIT 1"),
+        )
+        .map_err(map_mysql_err)?;
+    let current = current.map(|(version,)| version).unwrap_or(0);
+
+    if current > SCHEMA_VERSION {
+        return Err(StoreError::Storage(format!(
+            "database schema version {} is newer than supported {}",
+            current, SCHEMA_VERSION
+        )));
+    }
+
+    if current == 1 {
+        migrate_event_seq(conn, tables, id_len)?;
+    }
+
+    let schema = [
+        "CREATE TABLE IF NOT EXISTS events (
+            event_id VARCHAR(96) PRIMARY KEY,
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            ts BIGINT NOT NULL,
+            kind VARCHAR(32) NOT NULL,
+            payload TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            entities TEXT NOT NULL,
+            seq BIGINT NOT NULL DEFAULT 0,
+            lang VARCHAR(35) NULL
+        ) ENGINE=InnoDB",
+        "CREATE INDEX events_scope_ts
+            ON events (tenant_id, user_id, agent_id, session_id, run_id, ts)",
+        "CREATE UNIQUE INDEX events_scope_seq
+            ON events (tenant_id, user_id, agent_id, session_id, run_id, seq)",
+        EVENT_SEQUENCES_TABLE,
+        "CREATE TABLE IF NOT EXISTS wm_state (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            state_json TEXT NOT NULL,
+            updated_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS stm_state (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            rolling_summary TEXT NOT NULL,
+            key_quotes TEXT NOT NULL,
+            updated_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id)
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS facts (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            fact_id VARCHAR(96) NOT NULL,
+            fact_key VARCHAR(96) NOT NULL,
+            value_json TEXT NOT NULL,
+            status VARCHAR(32) NOT NULL,
+            valid_from BIGINT NULL,
+            valid_to BIGINT NULL,
+            confidence DOUBLE NOT NULL,
+            sources TEXT NOT NULL,
+            scope_level VARCHAR(32) NOT NULL,
             notes TEXT NOT NULL,
+            pinned BOOLEAN NOT NULL DEFAULT FALSE,
+            lang VARCHAR(35) NULL,
             PRIMARY KEY (tenant_id, user_id, agent_id, fact_id)
         ) ENGINE=InnoDB",
         "CREATE INDEX facts_scope_status
             ON facts (tenant_id, user_id, agent_id, status)",
+        "CREATE TABLE IF NOT EXISTS fact_versions (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            fact_id VARCHAR(96) NOT NULL,
+            version INT UNSIGNED NOT NULL,
+            fact_key VARCHAR(96) NOT NULL,
+            fact_json MEDIUMTEXT NOT NULL,
+            recorded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, fact_id, version)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX fact_versions_scope_key
+            ON fact_versions (tenant_id, user_id, agent_id, fact_key, recorded_at)",
         "CREATE TABLE IF NOT EXISTS episodes (
             tenant_id VARCHAR(96) NOT NULL,
             user_id VARCHAR(96) NOT NULL,
@@ -1066,10 +2419,227 @@ fn ensure_schema(conn: &mut PooledConn) -> StoreResult<()> {
             sources TEXT NOT NULL,
             compression_level VARCHAR(32) NOT NULL,
             recency_score DOUBLE NULL,
+            lang VARCHAR(35) NULL,
             PRIMARY KEY (tenant_id, user_id, agent_id, episode_id)
         ) ENGINE=InnoDB",
         "CREATE INDEX episodes_scope_start
             ON episodes (tenant_id, user_id, agent_id, start_ts)",
+        "CREATE TABLE IF NOT EXISTS procedures (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            procedure_id VARCHAR(96) NOT NULL,
+            task_type VARCHAR(96) NOT NULL,
+            content_json TEXT NOT NULL,
+            priority INT NOT NULL,
+            sources TEXT NOT NULL,
+            applicability TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, procedure_id)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX procedures_scope_task
+            ON procedures (tenant_id, user_id, agent_id, task_type, priority)",
+        "CREATE TABLE IF NOT EXISTS procedure_candidates (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            candidate_id VARCHAR(96) NOT NULL,
+            task_type VARCHAR(96) NOT NULL,
+            procedure_json MEDIUMTEXT NOT NULL,
+            source_episodes TEXT NOT NULL,
+            evidence TEXT NOT NULL,
+            status VARCHAR(16) NOT NULL,
+            created_at BIGINT NOT NULL,
+            reviewed_at BIGINT NULL,
+            reviewer VARCHAR(96) NOT NULL,
+            review_note TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, candidate_id)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX procedure_candidates_scope_status
+            ON procedure_candidates (tenant_id, user_id, agent_id, status, created_at)",
+        "CREATE TABLE IF NOT EXISTS insights (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            insight_id VARCHAR(96) NOT NULL,
+            kind VARCHAR(32) NOT NULL,
+            statement TEXT NOT NULL,
+            `trigger` VARCHAR(32) NOT NULL,
+            confidence DOUBLE NOT NULL,
+            validation_state VARCHAR(32) NOT NULL,
+            tests_suggested TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            sources TEXT NOT NULL,
+            parent_insight_id VARCHAR(96) NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, insight_id)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX insights_scope_state
+            ON insights (tenant_id, user_id, agent_id, session_id, run_id, validation_state)",
+        "CREATE TABLE IF NOT EXISTS context_builds (
+            id BIGINT AUTO_INCREMENT PRIMARY KEY,
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            ts BIGINT NOT NULL,
+            packet_json TEXT NOT NULL
+        ) ENGINE=InnoDB",
+        "CREATE INDEX context_builds_scope_ts
+            ON context_builds (tenant_id, user_id, agent_id, session_id, run_id, ts)",
+        "CREATE TABLE IF NOT EXISTS changelog (
+            seq BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+            ts BIGINT NOT NULL,
+            op LONGTEXT NOT NULL,
+            correlation_id VARCHAR(255) NULL
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS sync_cursors (
+            name VARCHAR(191) NOT NULL PRIMARY KEY,
+            seq BIGINT NOT NULL,
+            updated_at BIGINT NOT NULL
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS suppressions (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            item_kind VARCHAR(16) NOT NULL,
+            item_id VARCHAR(96) NOT NULL,
+            reason TEXT NOT NULL,
+            suppressed_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id)
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS tombstones (
+            id BIGINT AUTO_INCREMENT PRIMARY KEY,
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            item_kind VARCHAR(16) NOT NULL,
+            item_id VARCHAR(96) NOT NULL,
+            record_json MEDIUMTEXT NOT NULL,
+            deleted_at BIGINT NOT NULL
+        ) ENGINE=InnoDB",
+        "CREATE INDEX tombstones_scope ON tombstones (tenant_id, user_id, agent_id, id)",
+        "CREATE TABLE IF NOT EXISTS run_outcomes (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            status VARCHAR(16) NOT NULL,
+            score DOUBLE NULL,
+            error_summary TEXT NOT NULL,
+            duration_ms BIGINT NOT NULL,
+            recorded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS source_credibility (
+            tenant_id VARCHAR(96) PRIMARY KEY,
+            credibility_json TEXT NOT NULL,
+            updated_at BIGINT NOT NULL
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS memory_embeddings (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            item_kind VARCHAR(16) NOT NULL,
+            item_id VARCHAR(96) NOT NULL,
+            model VARCHAR(96) NOT NULL,
+            vector_json MEDIUMTEXT NOT NULL,
+            digest VARCHAR(32) NOT NULL,
+            embedded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id, model)
+        ) ENGINE=InnoDB",
+        "CREATE TABLE IF NOT EXISTS user_locales (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            locale_json TEXT NOT NULL,
+            updated_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id)
+        ) ENGINE=InnoDB",
+    ];
+
+    for statement in schema {
+        apply_schema_statement(conn, tables, &sized(statement, id_len))?;
+    }
+    // A database this user may not alter keeps working without the index tables.
+    if let Err(err) = ensure_optional_tables(conn, tables, id_len) {
+        warn!("could not create optional index tables: {}", err);
+    }
+    // Without the FULLTEXT indexes text search scans events and episodes instead.
+    for statement in [
+        "CREATE FULLTEXT INDEX events_text ON events (payload)",
+        "CREATE FULLTEXT INDEX episodes_text ON episodes (summary, highlights)",
+    ] {
+        if let Err(err) = apply_schema_statement(conn, tables, statement) {
+            warn!("could not create full-text index: {}", err);
+        }
+    }
+    let optional = detect_optional_tables(conn, tables)?;
+    if (1..3).contains(&current) {
+        let statement = "ALTER TABLE insights ADD COLUMN parent_insight_id VARCHAR(96) NULL";
+        add_column(conn, tables, &sized(statement, id_len))?;
+    }
+    if (1..4).contains(&current) {
+        let statement = "ALTER TABLE facts ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE";
+        add_column(conn, tables, statement)?;
+    }
+    if (1..5).contains(&current) {
+        for table in ["events", "facts", "episodes"] {
+            add_column(
+                conn, tables,
+                &format!("ALTER TABLE {} ADD COLUMN lang VARCHAR(35) NULL", table),
+            )?;
+        }
+    }
+    if (1..6).contains(&current) {
+        let statement = "ALTER TABLE changelog ADD COLUMN correlation_id VARCHAR(255) NULL";
+        add_column(conn, tables, statement)?;
+    }
+
+    if current < SCHEMA_VERSION {
+        conn.exec_drop(
+            tables.sql("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)"),
+            (SCHEMA_VERSION, to_millis(Utc::now())),
+        )
+        .map_err(map_mysql_err)?;
+    }
+
+    Ok(optional)
+}
+
+fn ensure_optional_tables(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    id_len: usize,
+) -> StoreResult<()> {
+    let schema = [
+        "CREATE TABLE IF NOT EXISTS event_tags (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            event_id VARCHAR(96) NOT NULL,
+            tag VARCHAR(64) NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, event_id, tag)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX event_tags_scope_tag
+            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag)",
+        "CREATE TABLE IF NOT EXISTS event_entities (
+            tenant_id VARCHAR(96) NOT NULL,
+            user_id VARCHAR(96) NOT NULL,
+            agent_id VARCHAR(96) NOT NULL,
+            session_id VARCHAR(96) NOT NULL,
+            run_id VARCHAR(96) NOT NULL,
+            event_id VARCHAR(96) NOT NULL,
+            entity VARCHAR(64) NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, event_id, entity)
+        ) ENGINE=InnoDB",
+        "CREATE INDEX event_entities_scope_entity
+            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity)",
         "CREATE TABLE IF NOT EXISTS episode_tags (
             tenant_id VARCHAR(96) NOT NULL,
             user_id VARCHAR(96) NOT NULL,
@@ -1090,66 +2660,144 @@ fn ensure_schema(conn: &mut PooledConn) -> StoreResult<()> {
         ) ENGINE=InnoDB",
         "CREATE INDEX episode_entities_scope_entity
             ON episode_entities (tenant_id, user_id, agent_id, entity)",
-        "CREATE TABLE IF NOT EXISTS procedures (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            procedure_id VARCHAR(96) NOT NULL,
-            task_type VARCHAR(96) NOT NULL,
-            content_json TEXT NOT NULL,
-            priority INT NOT NULL,
-            sources TEXT NOT NULL,
-            applicability TEXT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, procedure_id)
-        ) ENGINE=InnoDB",
-        "CREATE INDEX procedures_scope_task
-            ON procedures (tenant_id, user_id, agent_id, task_type, priority)",
-        "CREATE TABLE IF NOT EXISTS insights (
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            insight_id VARCHAR(96) NOT NULL,
-            kind VARCHAR(32) NOT NULL,
-            statement TEXT NOT NULL,
-            `trigger` VARCHAR(32) NOT NULL,
-            confidence DOUBLE NOT NULL,
-            validation_state VARCHAR(32) NOT NULL,
-            tests_suggested TEXT NOT NULL,
-            expires_at TEXT NOT NULL,
-            sources TEXT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, insight_id)
-        ) ENGINE=InnoDB",
-        "CREATE INDEX insights_scope_state
-            ON insights (tenant_id, user_id, agent_id, session_id, run_id, validation_state)",
-        "CREATE TABLE IF NOT EXISTS context_builds (
-            id BIGINT AUTO_INCREMENT PRIMARY KEY,
-            tenant_id VARCHAR(96) NOT NULL,
-            user_id VARCHAR(96) NOT NULL,
-            agent_id VARCHAR(96) NOT NULL,
-            session_id VARCHAR(96) NOT NULL,
-            run_id VARCHAR(96) NOT NULL,
-            ts BIGINT NOT NULL,
-            packet_json TEXT NOT NULL
-        ) ENGINE=InnoDB",
-        "CREATE INDEX context_builds_scope_ts
-            ON context_builds (tenant_id, user_id, agent_id, session_id, run_id, ts)",
     ];
-
     for statement in schema {
-        apply_schema_statement(conn, statement)?;
+        apply_schema_statement(conn, tables, &sized(statement, id_len))?;
+    }
+    Ok(())
+}
+
+/// `statement` with its id columns `VARCHAR(id_len)`.
+fn sized(statement: &str, id_len: usize) -> String {
+    statement.replace(ID_COLUMN, &format!("VARCHAR({})", id_len))
+}
+
+/// The width of the `events` id columns, which every table shares unless it predates
+/// a change of width; `None` if the database does not report it.
+fn id_column_len(conn: &mut PooledConn, tables: &TablePrefix) -> StoreResult<Option<usize>> {
+    let width: Option<Option<i64>> = conn
+        .exec_first(
+            "SELECT MIN(character_maximum_length) FROM information_schema.columns
+             WHERE table_schema = DATABASE() AND table_name = ?
+               AND column_name IN
+                   ('event_id', 'tenant_id', 'user_id', 'agent_id', 'session_id', 'run_id')",
+            (tables.name("events"),),
+        )
+        .map_err(map_mysql_err)?;
+    Ok(width.flatten().map(|width| width as usize))
+}
+
+fn detect_optional_tables(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+) -> StoreResult<OptionalTables> {
+    let present: Vec<String> = conn
+        .exec(
+            format!(
+                "SELECT table_name FROM information_schema.tables
+                 WHERE table_schema = DATABASE() AND table_name IN ({})",
+                sql_placeholders(OptionalTables::NAMES.len())
+            ),
+            Params::Positional(
+                OptionalTables::NAMES
+                    .iter()
+                    .map(|name| MyValue::from(tables.name(name)))
+                    .collect(),
+            ),
+        )
+        .map_err(map_mysql_err)?;
+    let present: Vec<String> = present.iter().map(|name| tables.strip(name).to_string()).collect();
+    let mut optional = OptionalTables::from_present(&present);
+    let text_indexes: Option<i64> = conn
+        .exec_first(
+            "SELECT COUNT(DISTINCT index_name) FROM information_schema.statistics
+             WHERE table_schema = DATABASE() AND index_type = 'FULLTEXT'
+               AND table_name IN (?, ?) AND index_name IN ('events_text', 'episodes_text')",
+            (tables.name("events"), tables.name("episodes")),
+        )
+        .map_err(map_mysql_err)?;
+    optional.text_index = text_indexes == Some(2);
+    if !optional.text_index {
+        warn!("events_text/episodes_text missing; text search scans events and episodes");
     }
+    Ok(optional)
+}
 
-    if current == 0 {
+/// Applies `patch` to the run's stored working state, comparing and swapping when the
+/// patch expects a state version.
+fn patch_working_state_row(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+    patch: WorkingStatePatch,
+) -> StoreResult<WorkingState> {
+    let stored = working_state_json(conn, tables, scope)?;
+    let current = stored.as_deref().map(decode_json).transpose()?.unwrap_or_default();
+    check_state_version(&current, &patch)?;
+    let expected = patch.expected_state_version;
+    let next = apply_working_state_patch(&current, patch);
+    let state_json = encode_json(&next)?;
+    let updated_at = to_millis(Utc::now());
+    let mut params = scope_params(scope);
+    params.extend([MyValue::from(&state_json), MyValue::from(updated_at)]);
+    let Some(expected) = expected else {
         conn.exec_drop(
-            "INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)",
-            (SCHEMA_VERSION, to_millis(Utc::now())),
+            tables.sql("INSERT INTO wm_state (
+                tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE state_json = VALUES(state_json),
+                                     updated_at = VALUES(updated_at)"),
+            Params::Positional(params),
         )
         .map_err(map_mysql_err)?;
+        return Ok(next);
+    };
+    // Compare and swap: the write only lands on the state that was read.
+    let written = match &stored {
+        Some(stored) if *stored == state_json => 1,
+        Some(stored) => {
+            let mut params = vec![MyValue::from(&state_json), MyValue::from(updated_at)];
+            params.extend(scope_params(scope));
+            params.push(MyValue::from(stored));
+            conn.exec_drop(
+                tables.sql("UPDATE wm_state SET state_json = ?, updated_at = ?
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?
+                   AND run_id = ? AND state_json = ?"),
+                Params::Positional(params),
+            )
+            .map_err(map_mysql_err)?;
+            conn.affected_rows()
+        }
+        None => {
+            conn.exec_drop(
+                tables.sql("INSERT IGNORE INTO wm_state (
+                    tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
+                Params::Positional(params),
+            )
+            .map_err(map_mysql_err)?;
+            conn.affected_rows()
+        }
+    };
+    if written == 0 {
+        let stored = working_state_json(conn, tables, scope)?;
+        let stored = stored.as_deref().map(decode_json).transpose()?;
+        return Err(state_version_conflict(expected, stored));
     }
+    Ok(next)
+}
 
-    Ok(())
+fn working_state_json(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+) -> StoreResult<Option<String>> {
+    conn.exec_first(
+        tables.sql("SELECT state_json FROM wm_state
+         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?"),
+        Params::Positional(scope_params(scope)),
+    )
+    .map_err(map_mysql_err)
 }
 
 fn encode_json<T: Serialize>(value: &T) -> StoreResult<String> {
@@ -1201,8 +2849,7 @@ fn scope_params_ltm(scope: &Scope) -> Vec<MyValue> {
 }
 
 fn sql_placeholders(count: usize) -> String {
-    std::iter::repeat("?")
-        .take(count)
+    std::iter::repeat_n("?", count)
         .collect::<Vec<_>>()
         .join(", ")
 }
@@ -1218,8 +2865,305 @@ fn unique_values(values: &[String]) -> Vec<String> {
     result
 }
 
+/// Runs `f` in a transaction, committing if it succeeds and rolling back otherwise.
+fn in_transaction<T>(
+    conn: &mut PooledConn,
+    f: impl FnOnce(&mut PooledConn) -> StoreResult<T>,
+) -> StoreResult<T> {
+    conn.exec_drop("START TRANSACTION", ()).map_err(map_mysql_err)?;
+    match f(conn) {
+        Ok(value) => {
+            conn.exec_drop("COMMIT", ()).map_err(map_mysql_err)?;
+            Ok(value)
+        }
+        Err(err) => {
+            let _ = conn.exec_drop("ROLLBACK", ());
+            Err(err)
+        }
+    }
+}
+
+fn next_event_seq(conn: &mut PooledConn, tables: &TablePrefix, scope: &Scope) -> StoreResult<i64> {
+    conn.exec_drop(
+        tables.sql(
+            "INSERT INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+             VALUES (?, ?, ?, ?, ?, LAST_INSERT_ID(1))
+             ON DUPLICATE KEY UPDATE last_seq = LAST_INSERT_ID(last_seq + 1)",
+        ),
+        Params::Positional(scope_params(scope)),
+    )
+    .map_err(map_mysql_err)?;
+    let seq: Option<i64> = conn
+        .query_first("SELECT LAST_INSERT_ID()")
+        .map_err(map_mysql_err)?;
+    seq.ok_or_else(|| StoreError::Storage("failed to allocate event sequence".to_string()))
+}
+
+fn episode_from_row(row: mysql::Row) -> StoreResult<Episode> {
+    let (
+        episode_id,
+        start_ts,
+        end_ts,
+        summary,
+        highlights,
+        tags,
+        entities,
+        sources,
+        compression_level,
+        recency_score,
+        lang,
+    ): EpisodeRow = from_row(row);
+    Ok(Episode {
+        episode_id,
+        time_range: engram_types::TimeRange {
+            start: from_millis(start_ts),
+            end: end_ts.map(from_millis),
+        },
+        summary,
+        highlights: decode_json(&highlights)?,
+        tags: decode_json(&tags)?,
+        entities: decode_json(&entities)?,
+        sources: decode_json(&sources)?,
+        compression_level: parse_compression_level(&compression_level)?,
+        recency_score,
+        lang,
+    })
+}
+
+fn fact_from_row(row: mysql::Row) -> StoreResult<Fact> {
+    let (
+        fact_id,
+        fact_key,
+        value_json,
+        status,
+        valid_from,
+        valid_to,
+        confidence,
+        sources,
+        scope_level,
+        notes,
+        pinned,
+        lang,
+    ): FactRow = from_row(row);
+    Ok(Fact {
+        fact_id,
+        fact_key,
+        value: decode_json(&value_json)?,
+        status: parse_fact_status(&status)?,
+        validity: engram_types::Validity {
+            valid_from: valid_from.map(from_millis),
+            valid_to: valid_to.map(from_millis),
+        },
+        confidence,
+        sources: decode_json(&sources)?,
+        scope_level: parse_scope_level(&scope_level)?,
+        notes,
+        pinned,
+        lang,
+    })
+}
+
+fn procedure_from_row(row: mysql::Row) -> StoreResult<Procedure> {
+    let (procedure_id, task_type, content_json, priority, sources, applicability): (
+        String,
+        String,
+        String,
+        i32,
+        String,
+        String,
+    ) = from_row(row);
+    Ok(Procedure {
+        procedure_id,
+        task_type,
+        content: decode_json(&content_json)?,
+        priority,
+        sources: decode_json(&sources)?,
+        applicability: decode_json(&applicability)?,
+    })
+}
+
+fn insight_from_row(row: mysql::Row) -> StoreResult<InsightItem> {
+    let (
+        insight_id,
+        kind,
+        statement,
+        trigger,
+        confidence,
+        validation_state,
+        tests_suggested,
+        expires_at,
+        sources,
+        parent_insight_id,
+    ): (
+        String,
+        String,
+        String,
+        String,
+        f64,
+        String,
+        String,
+        String,
+        String,
+        Option<String>,
+    ) = from_row(row);
+    Ok(InsightItem {
+        id: insight_id,
+        kind: parse_insight_type(&kind)?,
+        statement,
+        trigger: parse_insight_trigger(&trigger)?,
+        confidence,
+        validation_state: parse_validation_state(&validation_state)?,
+        tests_suggested: decode_json(&tests_suggested)?,
+        expires_at,
+        sources: decode_json(&sources)?,
+        parent_insight_id,
+    })
+}
+
+/// Tombstones of the `kind` records that `filter` matches, as they are now.
+fn load_tombstones(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+    kind: RecordKind,
+    filter: &str,
+    params: &[MyValue],
+) -> StoreResult<Vec<Tombstone>> {
+    let columns = match kind {
+        RecordKind::Event => {
+            "event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind, payload, tags,
+             entities, seq, lang"
+        }
+        RecordKind::Fact => {
+            "fact_id, fact_key, value_json, status, valid_from, valid_to, confidence, sources,
+             scope_level, notes, pinned, lang"
+        }
+        RecordKind::Episode => {
+            "episode_id, start_ts, end_ts, summary, highlights, tags, entities, sources,
+             compression_level, recency_score, lang"
+        }
+        RecordKind::Procedure => {
+            "procedure_id, task_type, content_json, priority, sources, applicability"
+        }
+        RecordKind::Insight => {
+            "insight_id, kind, statement, `trigger`, confidence, validation_state,
+             tests_suggested, expires_at, sources, parent_insight_id"
+        }
+    };
+    let (table, _) = kind.table();
+    let rows: Vec<mysql::Row> = conn
+        .exec(
+            tables.sql(&format!("SELECT {} FROM {} WHERE {}", columns, table, filter)),
+            Params::Positional(params.to_vec()),
+        )
+        .map_err(map_mysql_err)?;
+    let records = match kind {
+        RecordKind::Event => record_values(rows, event_from_row, |event| &event.event_id)?,
+        RecordKind::Fact => record_values(rows, fact_from_row, |fact| &fact.fact_id)?,
+        RecordKind::Episode => {
+            record_values(rows, episode_from_row, |episode| &episode.episode_id)?
+        }
+        RecordKind::Procedure => {
+            record_values(rows, procedure_from_row, |procedure| &procedure.procedure_id)?
+        }
+        RecordKind::Insight => record_values(rows, insight_from_row, |insight| &insight.id)?,
+    };
+    let deleted_at = Utc::now();
+    Ok(records
+        .into_iter()
+        .map(|(id, record)| Tombstone {
+            scope: kind.record_scope(scope),
+            item: RecordRef::new(kind, id),
+            record,
+            deleted_at,
+        })
+        .collect())
+}
+
+/// Ids and JSON of the records in `rows`.
+fn record_values<T: Serialize>(
+    rows: Vec<mysql::Row>,
+    from_row: fn(mysql::Row) -> StoreResult<T>,
+    id: fn(&T) -> &str,
+) -> StoreResult<Vec<(String, serde_json::Value)>> {
+    let mut records = Vec::with_capacity(rows.len());
+    for row in rows {
+        let record = from_row(row)?;
+        records.push((id(&record).to_string(), serde_json::to_value(&record)?));
+    }
+    Ok(records)
+}
+
+fn insert_tombstone(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    tombstone: &Tombstone,
+) -> StoreResult<()> {
+    let mut params = scope_params(&tombstone.scope);
+    params.extend([
+        MyValue::from(tombstone.item.kind.as_str()),
+        MyValue::from(tombstone.item.id.clone()),
+        MyValue::from(encode_json(&tombstone.record)?),
+        MyValue::from(to_millis(tombstone.deleted_at)),
+    ]);
+    conn.exec_drop(
+        tables.sql("INSERT INTO tombstones (
+            tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id, record_json,
+            deleted_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"),
+        Params::Positional(params),
+    )
+    .map_err(map_mysql_err)?;
+    Ok(())
+}
+
+/// A boolean-mode `AGAINST` string matching any of `terms` as a phrase. Double quotes
+/// would end a phrase early, so they are dropped.
+fn fulltext_query(terms: &[&str]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', " ")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Event rows are wider than the tuples `from_row` converts, so columns are taken one
+/// at a time.
+fn event_from_row(mut row: mysql::Row) -> StoreResult<Event> {
+    let kind: String = take_column(&mut row, 7)?;
+    let payload: String = take_column(&mut row, 8)?;
+    let tags: String = take_column(&mut row, 9)?;
+    let entities: String = take_column(&mut row, 10)?;
+    Ok(Event {
+        event_id: take_column(&mut row, 0)?,
+        scope: Scope {
+            tenant_id: take_column(&mut row, 1)?,
+            user_id: take_column(&mut row, 2)?,
+            agent_id: take_column(&mut row, 3)?,
+            session_id: take_column(&mut row, 4)?,
+            run_id: take_column(&mut row, 5)?,
+        },
+        ts: from_millis(take_column(&mut row, 6)?),
+        kind: kind.parse()?,
+        payload: decode_json(&payload)?,
+        tags: decode_json(&tags)?,
+        entities: decode_json(&entities)?,
+        seq: take_column::<i64>(&mut row, 11)? as u64,
+        lang: take_column(&mut row, 12)?,
+    })
+}
+
+fn take_column<T: FromValue>(row: &mut mysql::Row, index: usize) -> StoreResult<T> {
+    match row.take_opt(index) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(err)) => Err(StoreError::Storage(format!("column {}: {}", index, err))),
+        None => Err(StoreError::Storage(format!("missing column {}", index))),
+    }
+}
+
 fn insert_event_tags(
     conn: &mut PooledConn,
+    tables: &TablePrefix,
     scope: &Scope,
     event_id: &str,
     tags: &[String],
@@ -1240,9 +3184,9 @@ fn insert_event_tags(
             ]));
         }
         conn.exec_batch(
-            "INSERT IGNORE INTO event_tags (
+            tables.sql("INSERT IGNORE INTO event_tags (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
-             ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
             params,
         )
         .map_err(map_mysql_err)?;
@@ -1263,9 +3207,9 @@ fn insert_event_tags(
             ]));
         }
         conn.exec_batch(
-            "INSERT IGNORE INTO event_entities (
+            tables.sql("INSERT IGNORE INTO event_entities (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
-             ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
             params,
         )
         .map_err(map_mysql_err)?;
@@ -1273,7 +3217,11 @@ fn insert_event_tags(
     Ok(())
 }
 
-fn insert_event_tags_bulk(conn: &mut PooledConn, events: &[Event]) -> StoreResult<()> {
+fn insert_event_tags_bulk(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    events: &[Event],
+) -> StoreResult<()> {
     let mut tag_params = Vec::new();
     let mut entity_params = Vec::new();
     for event in events {
@@ -1302,18 +3250,18 @@ fn insert_event_tags_bulk(conn: &mut PooledConn, events: &[Event]) -> StoreResul
     }
     if !tag_params.is_empty() {
         conn.exec_batch(
-            "INSERT IGNORE INTO event_tags (
+            tables.sql("INSERT IGNORE INTO event_tags (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
-             ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
             tag_params,
         )
         .map_err(map_mysql_err)?;
     }
     if !entity_params.is_empty() {
         conn.exec_batch(
-            "INSERT IGNORE INTO event_entities (
+            tables.sql("INSERT IGNORE INTO event_entities (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
-             ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)"),
             entity_params,
         )
         .map_err(map_mysql_err)?;
@@ -1321,8 +3269,105 @@ fn insert_event_tags_bulk(conn: &mut PooledConn, events: &[Event]) -> StoreResul
     Ok(())
 }
 
+fn upsert_fact_row(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+    fact: &Fact,
+) -> StoreResult<()> {
+    conn.exec_drop(
+        tables.sql("INSERT INTO facts (
+            tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
+            valid_from, valid_to, confidence, sources, scope_level, notes, pinned, lang
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE fact_key = VALUES(fact_key),
+                                 value_json = VALUES(value_json),
+                                 status = VALUES(status),
+                                 valid_from = VALUES(valid_from),
+                                 valid_to = VALUES(valid_to),
+                                 confidence = VALUES(confidence),
+                                 sources = VALUES(sources),
+                                 scope_level = VALUES(scope_level),
+                                 notes = VALUES(notes),
+                                 pinned = VALUES(pinned),
+                                 lang = VALUES(lang)"),
+        Params::Positional(vec![
+            MyValue::from(scope.tenant_id.clone()),
+            MyValue::from(scope.user_id.clone()),
+            MyValue::from(scope.agent_id.clone()),
+            MyValue::from(fact.fact_id.clone()),
+            MyValue::from(fact.fact_key.clone()),
+            MyValue::from(encode_json(&fact.value)?),
+            MyValue::from(fact_status_to_str(&fact.status).to_string()),
+            option_i64(option_ts(fact.validity.valid_from)),
+            option_i64(option_ts(fact.validity.valid_to)),
+            MyValue::from(fact.confidence),
+            MyValue::from(encode_json(&fact.sources)?),
+            MyValue::from(scope_level_to_str(&fact.scope_level).to_string()),
+            MyValue::from(fact.notes.clone()),
+            MyValue::from(fact.pinned),
+            MyValue::from(fact.lang.clone()),
+        ]),
+    )
+    .map_err(map_mysql_err)?;
+    record_fact_version(conn, tables, scope, fact)
+}
+
+/// Adds the version a write of `fact` makes to `fact_versions`, unless it repeats
+/// the fact's last one. The fact's upserted row is locked until commit, so
+/// concurrent writes of it number their versions in turn.
+fn record_fact_version(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+    fact: &Fact,
+) -> StoreResult<()> {
+    let mut params = scope_params_ltm(scope);
+    params.push(MyValue::from(fact.fact_id.clone()));
+    let latest = conn
+        .exec_first::<mysql::Row, _, _>(
+            "SELECT version, recorded_at, fact_json FROM fact_versions
+             WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND fact_id = ?
+             ORDER BY version DESC LIMIT 1",
+            Params::Positional(params),
+        )
+        .map_err(map_mysql_err)?
+        .map(fact_version_from_row)
+        .transpose()?;
+    let Some(version) = next_fact_version(latest.as_ref(), fact, Utc::now()) else {
+        return Ok(());
+    };
+    conn.exec_drop(
+        tables.sql("INSERT INTO fact_versions (
+            tenant_id, user_id, agent_id, fact_id, version, fact_key, fact_json, recorded_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"),
+        Params::Positional(vec![
+            MyValue::from(scope.tenant_id.clone()),
+            MyValue::from(scope.user_id.clone()),
+            MyValue::from(scope.agent_id.clone()),
+            MyValue::from(fact.fact_id.clone()),
+            MyValue::from(version.version),
+            MyValue::from(fact.fact_key.clone()),
+            MyValue::from(encode_json(&version.fact)?),
+            MyValue::from(to_millis(version.recorded_at)),
+        ]),
+    )
+    .map_err(map_mysql_err)?;
+    Ok(())
+}
+
+fn fact_version_from_row(row: mysql::Row) -> StoreResult<FactVersion> {
+    let (version, recorded_at, fact_json): (u32, i64, String) = from_row(row);
+    Ok(FactVersion {
+        version,
+        recorded_at: from_millis(recorded_at),
+        fact: decode_json(&fact_json)?,
+    })
+}
+
 fn insert_episode_tags(
     conn: &mut PooledConn,
+    tables: &TablePrefix,
     scope: &Scope,
     episode_id: &str,
     tags: &[String],
@@ -1341,9 +3386,9 @@ fn insert_episode_tags(
             ]));
         }
         conn.exec_batch(
-            "INSERT IGNORE INTO episode_tags (
+            tables.sql("INSERT IGNORE INTO episode_tags (
                 tenant_id, user_id, agent_id, episode_id, tag
-             ) VALUES (?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?)"),
             params,
         )
         .map_err(map_mysql_err)?;
@@ -1362,9 +3407,9 @@ fn insert_episode_tags(
             ]));
         }
         conn.exec_batch(
-            "INSERT IGNORE INTO episode_entities (
+            tables.sql("INSERT IGNORE INTO episode_entities (
                 tenant_id, user_id, agent_id, episode_id, entity
-             ) VALUES (?, ?, ?, ?, ?)",
+             ) VALUES (?, ?, ?, ?, ?)"),
             params,
         )
         .map_err(map_mysql_err)?;
@@ -1372,48 +3417,38 @@ fn insert_episode_tags(
     Ok(())
 }
 
-fn episode_tags_present(conn: &mut PooledConn, scope: &Scope) -> StoreResult<bool> {
+fn episode_tags_present(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+) -> StoreResult<bool> {
     let row: Option<(u8,)> = conn
         .exec_first(
-            "SELECT 1 FROM episode_tags WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+            tables.sql(
+                "SELECT 1 FROM episode_tags WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+            ),
             Params::Positional(scope_params_ltm(scope)),
         )
         .map_err(map_mysql_err)?;
     Ok(row.is_some())
 }
 
-fn episode_entities_present(conn: &mut PooledConn, scope: &Scope) -> StoreResult<bool> {
+fn episode_entities_present(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    scope: &Scope,
+) -> StoreResult<bool> {
     let row: Option<(u8,)> = conn
         .exec_first(
-            "SELECT 1 FROM episode_entities WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+            tables.sql(
+                "SELECT 1 FROM episode_entities WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+            ),
             Params::Positional(scope_params_ltm(scope)),
         )
         .map_err(map_mysql_err)?;
     Ok(row.is_some())
 }
 
-fn event_kind_to_str(kind: &EventKind) -> &'static str {
-    match kind {
-        EventKind::Message => "message",
-        EventKind::ToolResult => "tool_result",
-        EventKind::StatePatch => "state_patch",
-        EventKind::System => "system",
-    }
-}
-
-fn parse_event_kind(value: &str) -> StoreResult<EventKind> {
-    match value {
-        "message" => Ok(EventKind::Message),
-        "tool_result" => Ok(EventKind::ToolResult),
-        "state_patch" => Ok(EventKind::StatePatch),
-        "system" => Ok(EventKind::System),
-        _ => Err(StoreError::InvalidInput(format!(
-            "invalid event kind: {}",
-            value
-        ))),
-    }
-}
-
 fn fact_status_to_str(status: &FactStatus) -> &'static str {
     match status {
         FactStatus::Active => "active",
@@ -1540,7 +3575,110 @@ fn parse_validation_state(value: &str) -> StoreResult<ValidationState> {
     }
 }
 
+fn memory_kind_to_str(kind: &MemoryKind) -> &'static str {
+    match kind {
+        MemoryKind::Fact => "fact",
+        MemoryKind::Episode => "episode",
+    }
+}
+
+fn parse_record_kind(value: &str) -> StoreResult<RecordKind> {
+    RecordKind::parse(value)
+        .ok_or_else(|| StoreError::InvalidInput(format!("invalid record kind: {}", value)))
+}
+
+fn parse_memory_kind(value: &str) -> StoreResult<MemoryKind> {
+    match value {
+        "fact" => Ok(MemoryKind::Fact),
+        "episode" => Ok(MemoryKind::Episode),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid memory kind: {}",
+            value
+        ))),
+    }
+}
+
+fn run_status_to_str(status: &RunStatus) -> &'static str {
+    match status {
+        RunStatus::Success => "success",
+        RunStatus::Partial => "partial",
+        RunStatus::Failure => "failure",
+        RunStatus::Aborted => "aborted",
+    }
+}
+
+fn parse_run_status(value: &str) -> StoreResult<RunStatus> {
+    match value {
+        "success" => Ok(RunStatus::Success),
+        "partial" => Ok(RunStatus::Partial),
+        "failure" => Ok(RunStatus::Failure),
+        "aborted" => Ok(RunStatus::Aborted),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid run status: {}",
+            value
+        ))),
+    }
+}
+
+fn candidate_status_to_str(status: &CandidateStatus) -> &'static str {
+    match status {
+        CandidateStatus::Pending => "pending",
+        CandidateStatus::Approved => "approved",
+        CandidateStatus::Rejected => "rejected",
+    }
+}
+
+fn parse_candidate_status(value: &str) -> StoreResult<CandidateStatus> {
+    match value {
+        "pending" => Ok(CandidateStatus::Pending),
+        "approved" => Ok(CandidateStatus::Approved),
+        "rejected" => Ok(CandidateStatus::Rejected),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid candidate status: {}",
+            value
+        ))),
+    }
+}
+
+fn procedure_candidate_from_row(row: ProcedureCandidateRow) -> StoreResult<ProcedureCandidate> {
+    let (
+        candidate_id,
+        procedure,
+        source_episodes,
+        evidence,
+        status,
+        created_at,
+        reviewed_at,
+        reviewer,
+        review_note,
+    ) = row;
+    Ok(ProcedureCandidate {
+        candidate_id,
+        procedure: decode_json(&procedure)?,
+        source_episodes: decode_json(&source_episodes)?,
+        evidence: decode_json(&evidence)?,
+        status: parse_candidate_status(&status)?,
+        created_at: from_millis(created_at),
+        reviewed_at: reviewed_at.map(from_millis),
+        reviewer,
+        review_note,
+    })
+}
+
+fn mysql_release_lock((mut conn, name): (PooledConn, String)) -> StoreResult<()> {
+    conn.exec_drop("SELECT RELEASE_LOCK(?)", (name,))
+        .map_err(map_mysql_err)
+}
+
 fn map_mysql_err(err: mysql::Error) -> StoreError {
+    if let mysql::Error::MySqlError(server) = &err
+        && server.code == ER_QUERY_TIMEOUT
+    {
+        return StoreError::Timeout(err.to_string());
+    }
+    if matches!(err, mysql::Error::IoError(_) | mysql::Error::DriverError(_)) {
+        return StoreError::Unavailable(err.to_string());
+    }
     StoreError::Storage(err.to_string())
 }
 
@@ -1556,8 +3694,12 @@ fn ensure_mysql_database(opts: &Opts, db_name: &str) -> StoreResult<()> {
     Ok(())
 }
 
-fn apply_schema_statement(conn: &mut PooledConn, statement: &str) -> StoreResult<()> {
-    match conn.query_drop(statement) {
+fn apply_schema_statement(
+    conn: &mut PooledConn,
+    tables: &TablePrefix,
+    statement: &str,
+) -> StoreResult<()> {
+    match conn.query_drop(tables.sql(statement)) {
         Ok(()) => Ok(()),
         Err(err) if is_duplicate_index(&err) => Ok(()),
         Err(err) => Err(map_mysql_err(err)),
@@ -1571,6 +3713,21 @@ fn is_duplicate_index(err: &mysql::Error) -> bool {
     }
 }
 
+fn add_column(conn: &mut PooledConn, tables: &TablePrefix, statement: &str) -> StoreResult<()> {
+    match conn.query_drop(tables.sql(statement)) {
+        Ok(()) => Ok(()),
+        Err(err) if is_duplicate_column(&err) => Ok(()),
+        Err(err) => Err(map_mysql_err(err)),
+    }
+}
+
+fn is_duplicate_column(err: &mysql::Error) -> bool {
+    match err {
+        mysql::Error::MySqlError(inner) => inner.code == 1060,
+        _ => false,
+    }
+}
+
 fn is_unknown_database(err: &mysql::Error) -> bool {
     match err {
         mysql::Error::MySqlError(inner) => inner.code == 1049,
@@ -1585,6 +3742,7 @@ fn escape_mysql_identifier(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::EventKind;
     use engram_types::{
         Budget, BudgetReport, Insight, JsonMap, LongTerm, MemoryPacket, Meta, Purpose, ShortTerm,
         Validity,
@@ -1623,18 +3781,35 @@ mod tests {
                 budget: Budget {
                     max_tokens: 512,
                     per_section: JsonMap::new(),
+                    model: None,
                 },
                 policy_id: "default".to_string(),
+                timezone: None,
+                locale: None,
             },
             short_term: ShortTerm::default(),
             long_term: LongTerm::default(),
             insight: Insight::default(),
             citations: Vec::new(),
+            external_context: Vec::new(),
             budget_report: BudgetReport::default(),
             explain: JsonMap::new(),
         }
     }
 
+    #[test]
+    fn id_columns_take_the_configured_width() {
+        let statement = sized(EVENT_SEQUENCES_TABLE, 117);
+        assert!(statement.contains("run_id VARCHAR(117) NOT NULL"));
+        assert!(!statement.contains(ID_COLUMN));
+        let tags = "tag VARCHAR(64) NOT NULL";
+        assert_eq!(sized(tags, 117), tags);
+        for id_len in [0, MAX_ID_COLUMN_LEN + 1] {
+            let err = MySqlStore::with_id_column_len("mysql://localhost/engram", id_len);
+            assert!(matches!(err, Err(StoreError::InvalidInput(_))));
+        }
+    }
+
     #[test]
     fn mysql_store_roundtrip() {
         let dsn = match std::env::var("ENGRAM_MYSQL_DSN") {
@@ -1658,6 +3833,8 @@ mod tests {
                 payload: json!({ "role": "user", "content": "hi" }),
                 tags: vec!["alpha".to_string()],
                 entities: vec!["entity1".to_string()],
+                seq: 0,
+                lang: None,
             })
             .unwrap();
 
@@ -1666,6 +3843,19 @@ mod tests {
             .unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].event_id, event_id);
+        let by_id = store
+            .get_events_by_ids(&scope, &[event_id.clone(), "missing".to_string()])
+            .unwrap();
+        assert_eq!(by_id.len(), 1);
+        let page = store.list_events_page(&scope, None, 1).unwrap();
+        assert_eq!(page.events[0].event_id, event_id);
+        assert!(page.next_cursor.is_none());
+        let activity = store
+            .tenant_activity(&scope.tenant_id, TimeRangeFilter::default())
+            .unwrap();
+        assert!(activity.iter().any(|user| user.user_id == scope.user_id));
+        let scopes = store.list_scopes(Some(&scope.tenant_id), None).unwrap();
+        assert!(scopes.iter().any(|listed| crate::scope_matches(listed, &scope)));
 
         let state = store
             .patch_working_state(
@@ -1677,6 +3867,24 @@ mod tests {
             )
             .unwrap();
         assert_eq!(state.goal, "ship");
+        let states = store.list_working_states(&scope).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].run_id, scope.run_id);
+        assert_eq!(states[0].state.goal, "ship");
+        let stale = WorkingStatePatch {
+            goal: Some("review".to_string()),
+            expected_state_version: Some(state.state_version + 1),
+            ..WorkingStatePatch::default()
+        };
+        let err = store.patch_working_state(&scope, stale).unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { .. }));
+        let current = WorkingStatePatch {
+            goal: Some("review".to_string()),
+            expected_state_version: Some(state.state_version),
+            ..WorkingStatePatch::default()
+        };
+        let state = store.patch_working_state(&scope, current).unwrap();
+        assert_eq!(state.goal, "review");
 
         store
             .update_stm(
@@ -1702,6 +3910,8 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1718,6 +3928,8 @@ mod tests {
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1732,6 +3944,35 @@ mod tests {
             )
             .unwrap();
         assert_eq!(facts.len(), 1);
+        crate::pin_fact(&store, &scope, &facts[0].fact_id).unwrap();
+        let pinned = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    pinned: Some(true),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert!(pinned[0].pinned);
+        let history = store.list_fact_history(&scope, "pref.color").unwrap();
+        let versions: Vec<_> = history.iter().map(|v| (v.version, v.fact.pinned)).collect();
+        assert_eq!(versions, [(1, false), (2, true)]);
+        assert_eq!(store.count_records(&scope, RecordKind::Fact).unwrap(), 2);
+
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: pinned[0].fact_id.clone(),
+        };
+        store.suppress_memory(&scope, item.clone(), "outdated").unwrap();
+        store.suppress_memory(&scope, item.clone(), "stop bringing this up").unwrap();
+        let suppressions = store.list_suppressions(&scope).unwrap();
+        assert_eq!(suppressions.len(), 1);
+        assert_eq!(suppressions[0].item, item);
+        assert_eq!(suppressions[0].reason, "stop bringing this up");
+        assert!(store.unsuppress_memory(&scope, &item).unwrap());
+        assert!(store.list_suppressions(&scope).unwrap().is_empty());
 
         store
             .append_episode(
@@ -1749,6 +3990,7 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1768,6 +4010,7 @@ mod tests {
                     sources: vec![],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1802,6 +4045,38 @@ mod tests {
             .unwrap();
         assert_eq!(procedures.len(), 1);
 
+        let candidate_id = unique_id("c1");
+        store
+            .upsert_procedure_candidate(
+                &scope,
+                ProcedureCandidate {
+                    candidate_id: candidate_id.clone(),
+                    procedure: procedures[0].clone(),
+                    source_episodes: vec!["ep1".to_string()],
+                    evidence: vec![],
+                    status: CandidateStatus::Pending,
+                    created_at: Utc::now(),
+                    reviewed_at: None,
+                    reviewer: String::new(),
+                    review_note: String::new(),
+                },
+            )
+            .unwrap();
+        let candidates = store
+            .list_procedure_candidates(
+                &scope,
+                ProcedureCandidateFilter {
+                    status: Some(vec![CandidateStatus::Pending]),
+                    ..ProcedureCandidateFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert!(store
+            .get_procedure_candidate(&scope, &candidate_id)
+            .unwrap()
+            .is_some());
+
         store
             .append_insight(
                 &scope,
@@ -1815,6 +4090,7 @@ mod tests {
                     tests_suggested: vec![],
                     expires_at: "run_end".to_string(),
                     sources: vec![],
+                    parent_insight_id: None,
                 },
             )
             .unwrap();
@@ -1829,6 +4105,11 @@ mod tests {
             )
             .unwrap();
         assert_eq!(insights.len(), 1);
+        assert_eq!(store.expire_insights(&scope, "run_end").unwrap(), 1);
+        assert!(store
+            .list_insights(&scope, InsightFilter::default())
+            .unwrap()
+            .is_empty());
 
         store
             .write_context_build(&scope, sample_packet(scope.clone()))
@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use engram_types::Scope;
+
+use crate::{Change, Store, StoreError, StoreResult};
+
+/// A connector that publishes memory changes elsewhere, such as a webhook endpoint or
+/// a Kafka topic.
+pub trait ChangeSink: Send + Sync {
+    /// Publishes a batch of changes in `seq` order. On error the whole batch is sent
+    /// again, so receivers must tolerate duplicates; `seq` identifies each change.
+    fn deliver(&self, changes: &[Change]) -> Result<(), String>;
+}
+
+/// A feed of the writes committed by every process sharing a backend, such as a
+/// Postgres `PgListener`. [`OutboxDispatcher::dispatch_notified`] and
+/// [`CachedStore::evict_notified`](crate::CachedStore::evict_notified) act on it as
+/// writes commit instead of waiting for their next poll.
+pub trait WriteNotifications: Send {
+    /// Waits up to `timeout` for the next write and returns its scope, or `None` on
+    /// timeout.
+    fn next_write(&mut self, timeout: Duration) -> StoreResult<Option<Scope>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxOptions {
+    /// Name of the cursor that records how far delivery got; one per sink.
+    pub name: String,
+    /// How often the background worker delivers pending changes. `None` disables the
+    /// worker; call [`OutboxDispatcher::dispatch_now`] instead.
+    pub interval: Option<Duration>,
+    pub batch_size: usize,
+    /// Attempts per batch before a dispatch gives up until the next one.
+    pub max_attempts: usize,
+    /// Wait after the first failed attempt, doubled after each further one up to
+    /// `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How long a missing `seq` holds back the changes after it. Postgres and MySQL
+    /// hand out sequence numbers before commit, so a gap is a write still committing
+    /// or one that rolled back and never will; after this long it is taken for the
+    /// latter and skipped, with a warning.
+    pub gap_timeout: Duration,
+    /// How long a skipped `seq` is still looked for. A change that commits in a
+    /// skipped gap within this window is delivered late, out of `seq` order.
+    pub late_change_window: Duration,
+}
+
+impl Default for OutboxOptions {
+    fn default() -> Self {
+        Self {
+            name: "outbox".to_string(),
+            interval: Some(Duration::from_secs(1)),
+            batch_size: 100,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            gap_timeout: Duration::from_secs(10),
+            late_change_window: Duration::from_secs(600),
+        }
+    }
+}
+
+/// The `seq` gaps passed over after their timeout, with when each was skipped. They
+/// are looked up again on later reads, since a write taken for rolled back may only
+/// have been slow to commit. Kept in process, so a restart forgets them.
+#[derive(Debug, Default)]
+pub(crate) struct SkippedGaps(Vec<(Range<u64>, Instant)>);
+
+impl SkippedGaps {
+    pub(crate) fn skip(&mut self, gaps: Vec<Range<u64>>) {
+        let now = Instant::now();
+        self.0.extend(gaps.into_iter().map(|gap| (gap, now)));
+    }
+
+    /// Changes that have committed in a skipped gap, in `seq` order. Gaps skipped
+    /// longer than `window` ago are dropped first; call [`SkippedGaps::found`] once
+    /// the changes are handled.
+    pub(crate) fn late_changes<S: Store + ?Sized>(
+        &mut self,
+        store: &S,
+        window: Duration,
+    ) -> StoreResult<Vec<Change>> {
+        self.0.retain(|(_, skipped_at)| skipped_at.elapsed() < window);
+        let mut late = Vec::new();
+        for (gap, _) in &self.0 {
+            let len = (gap.end - gap.start) as usize;
+            let changes = store.list_changes(gap.start - 1, Some(len))?;
+            late.extend(changes.into_iter().filter(|change| gap.contains(&change.seq)));
+        }
+        late.sort_by_key(|change| change.seq);
+        Ok(late)
+    }
+
+    /// Stops looking for the `seq`s of `changes`.
+    pub(crate) fn found(&mut self, changes: &[Change]) {
+        for change in changes {
+            let seq = change.seq;
+            let Some(idx) = self.0.iter().position(|(gap, _)| gap.contains(&seq)) else {
+                continue;
+            };
+            let (gap, skipped_at) = self.0.swap_remove(idx);
+            for rest in [gap.start..seq, seq + 1..gap.end] {
+                if !rest.is_empty() {
+                    self.0.push((rest, skipped_at));
+                }
+            }
+        }
+    }
+}
+
+/// Logs the gaps `reader` gave up waiting for.
+pub(crate) fn warn_skipped(reader: &str, gaps: &[Range<u64>], gap_timeout: Duration) {
+    for gap in gaps {
+        warn!(
+            "{} skipped changes {}..{} after waiting {:?} for them to commit",
+            reader, gap.start, gap.end, gap_timeout
+        );
+    }
+}
+
+struct OutboxShared {
+    store: Arc<dyn Store>,
+    sink: Arc<dyn ChangeSink>,
+    options: OutboxOptions,
+    /// First missing `seq` of each gap seen after the cursor, and when it was first
+    /// seen. Also held for the whole of a dispatch.
+    gaps: Mutex<HashMap<u64, Instant>>,
+    skipped: Mutex<SkippedGaps>,
+}
+
+impl OutboxShared {
+    fn dispatch_pending(&self) -> StoreResult<usize> {
+        let mut gaps = self.gaps.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut skipped = self.skipped.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut delivered = 0;
+        let late = skipped.late_changes(self.store.as_ref(), self.options.late_change_window)?;
+        for batch in late.chunks(self.options.batch_size) {
+            self.deliver_with_retries(batch)?;
+            skipped.found(batch);
+            delivered += batch.len();
+        }
+        if !late.is_empty() {
+            warn!("delivered {} changes to {} late, out of order", late.len(), self.options.name);
+        }
+
+        let mut cursor = self.store.load_change_cursor(&self.options.name)?;
+        loop {
+            let changes = self
+                .store
+                .list_changes(cursor, Some(self.options.batch_size))?;
+            let mut passed = Vec::new();
+            let ready = deliverable(
+                cursor,
+                &changes,
+                &mut gaps,
+                self.options.gap_timeout,
+                &mut passed,
+            );
+            let Some(last) = changes[..ready].last() else {
+                break;
+            };
+            let last_seq = last.seq;
+            self.deliver_with_retries(&changes[..ready])?;
+            // The cursor only moves once the sink has the batch: a crash in between
+            // sends it again rather than losing it.
+            cursor = last_seq;
+            self.store.save_change_cursor(&self.options.name, cursor)?;
+            warn_skipped(&self.options.name, &passed, self.options.gap_timeout);
+            skipped.skip(passed);
+            gaps.retain(|&seq, _| seq > cursor);
+            delivered += ready;
+            if ready < changes.len() {
+                break;
+            }
+        }
+        if delivered > 0 {
+            debug!(
+                "delivered {} changes to {}, cursor at {}",
+                delivered, self.options.name, cursor
+            );
+        }
+        Ok(delivered)
+    }
+
+    fn deliver_with_retries(&self, changes: &[Change]) -> StoreResult<()> {
+        let attempts = self.options.max_attempts.max(1);
+        let mut backoff = self.options.initial_backoff;
+        for attempt in 1..=attempts {
+            let err = match self.sink.deliver(changes) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            if attempt == attempts {
+                return Err(StoreError::Storage(format!(
+                    "delivering changes {}..={} to {} failed after {} attempts: {}",
+                    changes[0].seq,
+                    changes[changes.len() - 1].seq,
+                    self.options.name,
+                    attempts,
+                    err
+                )));
+            }
+            warn!(
+                "delivering to {} failed (attempt {}/{}), retrying in {:?}: {}",
+                self.options.name, attempt, attempts, backoff, err
+            );
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(self.options.max_backoff);
+        }
+        Ok(())
+    }
+}
+
+/// Delivers a store's change log to a [`ChangeSink`] at least once, in order.
+///
+/// The change log is the outbox: wrap the store in a [`ChangeLogStore`](crate::ChangeLogStore)
+/// so every write is recorded with it, then point a dispatcher at it. Progress is kept
+/// in the store under [`OutboxOptions::name`], so a restarted dispatcher resumes where
+/// the last one stopped. A batch the sink rejects is retried with exponential backoff
+/// and the cursor does not move past it.
+///
+/// Delivery stops at a gap in `seq` until the missing change commits or
+/// [`OutboxOptions::gap_timeout`] passes, so a change that commits after a later one
+/// is not passed over. A gap that times out is skipped with a warning and looked for
+/// on every dispatch for [`OutboxOptions::late_change_window`]; a change that commits
+/// in it by then is delivered late, out of order. One that commits later still, or
+/// while no dispatcher is running, is not delivered.
+pub struct OutboxDispatcher {
+    shared: Arc<OutboxShared>,
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for OutboxDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboxDispatcher")
+            .field("name", &self.shared.options.name)
+            .field("background", &self.worker.is_some())
+            .finish()
+    }
+}
+
+impl OutboxDispatcher {
+    pub fn new(store: Arc<dyn Store>, sink: Arc<dyn ChangeSink>, options: OutboxOptions) -> Self {
+        let interval = options.interval;
+        let shared = Arc::new(OutboxShared {
+            store,
+            sink,
+            options: OutboxOptions {
+                batch_size: options.batch_size.max(1),
+                ..options
+            },
+            gaps: Mutex::new(HashMap::new()),
+            skipped: Mutex::new(SkippedGaps::default()),
+        });
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let worker = interval.map(|interval| {
+            let shared = shared.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || run_worker(shared, stop, interval))
+        });
+        Self {
+            shared,
+            stop,
+            worker,
+        }
+    }
+
+    /// Delivers every pending change and returns how many were sent.
+    pub fn dispatch_now(&self) -> StoreResult<usize> {
+        self.shared.dispatch_pending()
+    }
+
+    /// Waits up to `timeout` for a write from `notifications`, takes any others that
+    /// already arrived, then delivers every pending change. Called in a loop, it
+    /// delivers as writes commit rather than every [`OutboxOptions::interval`].
+    pub fn dispatch_notified(
+        &self,
+        notifications: &mut dyn WriteNotifications,
+        timeout: Duration,
+    ) -> StoreResult<usize> {
+        if notifications.next_write(timeout)?.is_some() {
+            while notifications.next_write(Duration::ZERO)?.is_some() {}
+        }
+        self.shared.dispatch_pending()
+    }
+
+    pub fn pending_changes(&self) -> StoreResult<usize> {
+        let store = &self.shared.store;
+        let cursor = store.load_change_cursor(&self.shared.options.name)?;
+        Ok(store.list_changes(cursor, None)?.len())
+    }
+}
+
+impl Drop for OutboxDispatcher {
+    fn drop(&mut self) {
+        let (lock, signal) = &*self.stop;
+        if let Ok(mut stopped) = lock.lock() {
+            *stopped = true;
+        }
+        signal.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// How many of `changes`, listed after `cursor`, can be delivered now: those before
+/// the first gap in `seq` that is younger than `gap_timeout`. Gaps are timed in `gaps`;
+/// the older ones passed over on the way are added to `skipped`.
+pub(crate) fn deliverable(
+    cursor: u64,
+    changes: &[Change],
+    gaps: &mut HashMap<u64, Instant>,
+    gap_timeout: Duration,
+    skipped: &mut Vec<Range<u64>>,
+) -> usize {
+    let now = Instant::now();
+    let mut expected = cursor + 1;
+    for (idx, change) in changes.iter().enumerate() {
+        if change.seq > expected {
+            let seen = *gaps.entry(expected).or_insert(now);
+            if now.duration_since(seen) < gap_timeout {
+                return idx;
+            }
+            skipped.push(expected..change.seq);
+        }
+        expected = change.seq + 1;
+    }
+    changes.len()
+}
+
+fn run_worker(shared: Arc<OutboxShared>, stop: Arc<(Mutex<bool>, Condvar)>, interval: Duration) {
+    let (lock, signal) = &*stop;
+    loop {
+        let Ok(guard) = lock.lock() else {
+            return;
+        };
+        let Ok((stopped, _)) = signal.wait_timeout_while(guard, interval, |stopped| !*stopped)
+        else {
+            return;
+        };
+        if *stopped {
+            return;
+        }
+        drop(stopped);
+
+        if let Err(err) = shared.dispatch_pending() {
+            warn!("outbox delivery failed, will retry: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChangeLogStore, Event, EventKind, InMemoryStore};
+    use engram_types::Scope;
+    use serde_json::json;
+
+    /// Fails the first `failures` deliveries, then records what it receives.
+    struct FlakySink {
+        failures: Mutex<usize>,
+        received: Mutex<Vec<u64>>,
+    }
+
+    impl ChangeSink for FlakySink {
+        fn deliver(&self, changes: &[Change]) -> Result<(), String> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err("connection refused".to_string());
+            }
+            self.received
+                .lock()
+                .unwrap()
+                .extend(changes.iter().map(|change| change.seq));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn retries_until_delivered_and_resumes_from_cursor() {
+        let store = Arc::new(ChangeLogStore::new(InMemoryStore::new()));
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        for content in ["one", "two", "three"] {
+            store
+                .append_event(Event::new(scope.clone(), EventKind::Message, json!(content)))
+                .unwrap();
+        }
+        let sink = Arc::new(FlakySink {
+            failures: Mutex::new(4),
+            received: Mutex::new(Vec::new()),
+        });
+        let options = OutboxOptions {
+            interval: None,
+            batch_size: 2,
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..OutboxOptions::default()
+        };
+        let outbox = OutboxDispatcher::new(store.clone(), sink.clone(), options.clone());
+
+        assert!(outbox.dispatch_now().is_err());
+        assert_eq!(outbox.pending_changes().unwrap(), 3);
+        assert_eq!(outbox.dispatch_now().unwrap(), 3);
+        assert_eq!(*sink.received.lock().unwrap(), vec![1, 2, 3]);
+
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("four")))
+            .unwrap();
+        let restarted = OutboxDispatcher::new(store.clone(), sink.clone(), options);
+        assert_eq!(restarted.dispatch_now().unwrap(), 1);
+        assert_eq!(*sink.received.lock().unwrap(), vec![1, 2, 3, 4]);
+
+        for content in ["five", "six"] {
+            store
+                .append_event(Event::new(scope.clone(), EventKind::Message, json!(content)))
+                .unwrap();
+        }
+        let mut writes = QueuedWrites(vec![scope.clone(), scope.clone()]);
+        assert_eq!(restarted.dispatch_notified(&mut writes, Duration::ZERO).unwrap(), 2);
+        assert!(writes.0.is_empty());
+        assert_eq!(*sink.received.lock().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    /// Hands out queued scopes as writes, then times out.
+    struct QueuedWrites(Vec<Scope>);
+
+    impl WriteNotifications for QueuedWrites {
+        fn next_write(&mut self, _timeout: Duration) -> StoreResult<Option<Scope>> {
+            Ok(self.0.pop())
+        }
+    }
+
+    #[test]
+    fn skipped_changes_are_delivered_late_once_they_commit() {
+        let store = Arc::new(InMemoryStore::new());
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        for content in ["one", "two", "three"] {
+            store
+                .append_change(crate::ChangeOp::AppendEvent {
+                    event: Event::new(scope.clone(), EventKind::Message, json!(content)),
+                })
+                .unwrap();
+        }
+        // Seq 2 is still committing and gets taken for rolled back.
+        let slow = store.changes.write().unwrap().remove(1);
+        let sink = Arc::new(FlakySink {
+            failures: Mutex::new(0),
+            received: Mutex::new(Vec::new()),
+        });
+        let options = OutboxOptions {
+            interval: None,
+            gap_timeout: Duration::ZERO,
+            ..OutboxOptions::default()
+        };
+        let outbox = OutboxDispatcher::new(store.clone(), sink.clone(), options);
+        assert_eq!(outbox.dispatch_now().unwrap(), 2);
+        assert_eq!(*sink.received.lock().unwrap(), vec![1, 3]);
+
+        store.changes.write().unwrap().insert(1, slow);
+        assert_eq!(outbox.dispatch_now().unwrap(), 1);
+        assert_eq!(outbox.dispatch_now().unwrap(), 0);
+        assert_eq!(*sink.received.lock().unwrap(), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn gaps_hold_back_later_changes_until_they_time_out() {
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let changes: Vec<Change> = [1, 2, 4, 5]
+            .into_iter()
+            .map(|seq| Change {
+                seq,
+                ts: chrono::Utc::now(),
+                correlation_id: None,
+                op: crate::ChangeOp::AppendEvent {
+                    event: Event::new(scope.clone(), EventKind::Message, json!(seq)),
+                },
+            })
+            .collect();
+        let mut gaps = HashMap::new();
+        let mut skipped = Vec::new();
+        let wait = Duration::from_secs(60);
+
+        // Seq 3 may still be committing.
+        assert_eq!(deliverable(0, &changes, &mut gaps, wait, &mut skipped), 2);
+        assert_eq!(deliverable(2, &changes[2..], &mut gaps, wait, &mut skipped), 0);
+        assert!(gaps.contains_key(&3) && skipped.is_empty());
+        // Once it has been missing for the whole timeout it is skipped.
+        let ready = deliverable(2, &changes[2..], &mut gaps, Duration::ZERO, &mut skipped);
+        assert_eq!(ready, 2);
+        assert_eq!(skipped, vec![3..4]);
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use engram_types::{Episode, Scope};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{StoreError, StoreResult};
+
+/// Tag prefix that links an episode to its run; [`end_run`](crate::end_run) tags
+/// the run's episode `run:<run_id>`.
+pub const RUN_TAG_PREFIX: &str = "run:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    Partial,
+    Failure,
+    Aborted,
+}
+
+/// How a run ended, recorded with [`Store::record_run_outcome`](crate::Store::record_run_outcome).
+/// Recall ranks episodes of well-scored runs higher (see
+/// [`RecallPolicy::outcome_weight`](crate::RecallPolicy::outcome_weight)) and procedure
+/// learning skips episodes of runs that did not succeed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutcome {
+    /// Filled in from the scope the outcome is recorded for.
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub run_id: String,
+    pub status: RunStatus,
+    /// Task quality in `[0, 1]`, e.g. from an evaluator or a user rating.
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub error_summary: String,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default = "Utc::now")]
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl RunOutcome {
+    pub fn new(status: RunStatus) -> Self {
+        Self {
+            session_id: String::new(),
+            run_id: String::new(),
+            status,
+            score: None,
+            error_summary: String::new(),
+            duration_ms: 0,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    /// The outcome as a supervision signal in `[0, 1]`: the score when there is one,
+    /// otherwise 1 for success, 0.5 for a partial run and 0 for failed or aborted ones.
+    pub fn signal(&self) -> f64 {
+        self.score.unwrap_or(match self.status {
+            RunStatus::Success => 1.0,
+            RunStatus::Partial => 0.5,
+            RunStatus::Failure | RunStatus::Aborted => 0.0,
+        })
+    }
+}
+
+/// Checks the outcome and points it at the run `scope`, as every backend stores it.
+pub(crate) fn outcome_for_run(scope: &Scope, mut outcome: RunOutcome) -> StoreResult<RunOutcome> {
+    if let Some(score) = outcome.score
+        && !(0.0..=1.0).contains(&score)
+    {
+        return Err(StoreError::InvalidInput(format!(
+            "run outcome score must be between 0 and 1, got {}",
+            score
+        )));
+    }
+    outcome.session_id = scope.session_id.clone();
+    outcome.run_id = scope.run_id.clone();
+    Ok(outcome)
+}
+
+/// Signal of each run by id. `outcomes` come newest first, so a run recorded again in
+/// another session keeps its latest outcome.
+pub(crate) fn run_signals(outcomes: &[RunOutcome]) -> HashMap<&str, f64> {
+    let mut signals = HashMap::new();
+    for outcome in outcomes {
+        signals
+            .entry(outcome.run_id.as_str())
+            .or_insert_with(|| outcome.signal());
+    }
+    signals
+}
+
+/// The run an episode came from, per its `run:<run_id>` tag.
+pub(crate) fn episode_run(episode: &Episode) -> Option<&str> {
+    episode
+        .tags
+        .iter()
+        .find_map(|tag| tag.strip_prefix(RUN_TAG_PREFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rank_episodes_with_outcomes, select_successful_episodes, InMemoryStore,
+        ProcedureLearningOptions, Store,
+    };
+    use engram_types::{CompressionLevel, TimeRange};
+
+    fn run_scope(run_id: &str) -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: run_id.to_string(),
+        }
+    }
+
+    fn run_episode(run_id: &str) -> Episode {
+        Episode {
+            episode_id: format!("ep-{}", run_id),
+            time_range: TimeRange {
+                start: Utc::now(),
+                end: None,
+            },
+            summary: format!("deploy in {}", run_id),
+            highlights: Vec::new(),
+            tags: vec![
+                "deploy".to_string(),
+                "outcome:success".to_string(),
+                format!("{}{}", RUN_TAG_PREFIX, run_id),
+            ],
+            entities: Vec::new(),
+            sources: Vec::new(),
+            compression_level: CompressionLevel::Raw,
+            recency_score: None,
+            lang: None,
+        }
+    }
+
+    #[test]
+    fn outcomes_steer_ranking_and_learning() {
+        let store = InMemoryStore::new();
+        let mut first = RunOutcome::new(RunStatus::Success);
+        first.recorded_at = Utc::now() - chrono::Duration::minutes(1);
+        store.record_run_outcome(&run_scope("run1"), first).unwrap();
+        let mut failed = RunOutcome::new(RunStatus::Failure);
+        failed.error_summary = "rollback".to_string();
+        failed.duration_ms = 1_500;
+        store.record_run_outcome(&run_scope("run1"), failed).unwrap();
+        let mut scored = RunOutcome::new(RunStatus::Success);
+        scored.score = Some(0.9);
+        store.record_run_outcome(&run_scope("run2"), scored).unwrap();
+
+        let mut invalid = RunOutcome::new(RunStatus::Success);
+        invalid.score = Some(1.5);
+        assert!(store.record_run_outcome(&run_scope("run3"), invalid).is_err());
+
+        let outcomes = store.list_run_outcomes(&run_scope("any"), None).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[1].run_id, "run1");
+        assert_eq!(outcomes[1].status, RunStatus::Failure);
+        assert_eq!(outcomes[1].error_summary, "rollback");
+        assert_eq!(store.list_run_outcomes(&run_scope("any"), Some(1)).unwrap().len(), 1);
+
+        let mut episodes = vec![run_episode("run1"), run_episode("run2"), run_episode("run3")];
+        let now = Utc::now();
+        rank_episodes_with_outcomes(&mut episodes, now, 10, &outcomes, 0.5);
+        let ranked: Vec<&str> = episodes.iter().map(|e| e.episode_id.as_str()).collect();
+        assert_eq!(ranked, vec!["ep-run2", "ep-run3", "ep-run1"]);
+
+        for run_id in ["run1", "run2", "run3"] {
+            store
+                .append_episode(&run_scope(run_id), run_episode(run_id))
+                .unwrap();
+        }
+        let options = ProcedureLearningOptions::new("deploy");
+        let selected = select_successful_episodes(&store, &run_scope("any"), &options).unwrap();
+        let selected: Vec<&str> = selected.iter().map(|e| e.episode_id.as_str()).collect();
+        assert_eq!(selected.len(), 2);
+        assert!(!selected.contains(&"ep-run1"));
+    }
+}
@@ -0,0 +1,207 @@
+use engram_types::{ConversationTurn, Fact, FactStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::composer::{estimate_tokens, event_to_turn};
+use crate::{
+    token_counter, with_read_preference, with_token_counter, BuildRequest, EpisodeFilter,
+    FactFilter, InsightFilter, RecordKind, Store, StoreResult, TimeRangeFilter,
+};
+
+/// Records [`estimate_packet_size`] reads per section to measure their typical size.
+pub const ESTIMATE_SAMPLE_SIZE: usize = 8;
+
+/// One packet section in a [`PacketSizeEstimate`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SectionEstimate {
+    /// Items the section would hold: the scope's records, up to the policy's limit.
+    pub items: usize,
+    /// Records read to measure them.
+    pub sampled: usize,
+    pub avg_tokens: u32,
+    pub tokens: u32,
+}
+
+/// What [`estimate_packet_size`] expects a build to use, in the units of
+/// `budget_report.used_tokens_est`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PacketSizeEstimate {
+    pub max_tokens: u32,
+    pub tokens: u32,
+    /// Whether the estimate fits `budget.max_tokens`, i.e. whether the build would
+    /// likely keep every candidate instead of trimming.
+    pub fits_budget: bool,
+    /// Estimates per section, keyed like `budget_report.section_usage`.
+    pub sections: BTreeMap<String, SectionEstimate>,
+}
+
+/// Estimates the tokens a build of `request` would use, before budget trimming,
+/// without building the packet: each section's candidates are counted with
+/// [`Store::count_records`] and sized from a sample of [`ESTIMATE_SAMPLE_SIZE`]
+/// records, so a caller can pick a budget or policy up front. Working state and STM
+/// are read whole. Cue quotes, shared fact pools and pinned tenant procedures are
+/// left out, so treat the result as a pre-flight guide rather than a bound.
+pub fn estimate_packet_size<S: Store + ?Sized>(
+    store: &S,
+    request: &BuildRequest,
+) -> StoreResult<PacketSizeEstimate> {
+    let counter = token_counter(request.budget.model.as_deref());
+    with_read_preference(request.read_preference, || {
+        with_token_counter(counter, || estimate_sections(store, request))
+    })
+}
+
+fn estimate_sections<S: Store + ?Sized>(
+    store: &S,
+    request: &BuildRequest,
+) -> StoreResult<PacketSizeEstimate> {
+    let scope = &request.scope;
+    let policy = &request.policy;
+    let rules = policy.filter.rules(&request.purpose);
+    let mut sections = BTreeMap::new();
+
+    let mut working_state = store.get_working_state(scope)?.unwrap_or_default();
+    working_state.clock = Default::default();
+    sections.insert("working_state", whole(estimate_tokens(&working_state)));
+    let stm = store.get_stm(scope)?.unwrap_or_default();
+    sections.insert("rolling_summary", whole(estimate_tokens(&stm.rolling_summary)));
+    let mut quotes = stm.key_quotes;
+    quotes.truncate(policy.max_key_quotes);
+    sections.insert("key_quotes", whole(estimate_tokens(&quotes)));
+
+    // Preferences live among the facts, so they share the facts' count.
+    let facts = store.count_records(scope, RecordKind::Fact)?;
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Active]),
+        limit: Some(ESTIMATE_SAMPLE_SIZE),
+        ..FactFilter::default()
+    };
+    let sample: Vec<Fact> = store.list_facts(scope, filter)?;
+    let items = facts.min(policy.max_facts + policy.max_preferences);
+    sections.insert("facts", sampled(items, &sample));
+
+    let procedures = store.count_records(scope, RecordKind::Procedure)?;
+    let task_type = request.task_type.as_deref().unwrap_or("generic");
+    let sample = store.list_procedures(scope, task_type, Some(ESTIMATE_SAMPLE_SIZE))?;
+    sections.insert("procedures", sampled(procedures.min(policy.max_procedures), &sample));
+
+    let episodes = store.count_records(scope, RecordKind::Episode)?;
+    let filter = EpisodeFilter {
+        limit: Some(ESTIMATE_SAMPLE_SIZE),
+        ..EpisodeFilter::default()
+    };
+    let sample = store.list_episodes(scope, filter)?;
+    sections.insert("episodes", sampled(episodes.min(policy.max_episodes), &sample));
+
+    if rules.conversation_window {
+        let events = store.count_records(scope, RecordKind::Event)?;
+        let range = TimeRangeFilter::default();
+        let sample = store.list_events(scope, range, Some(ESTIMATE_SAMPLE_SIZE))?;
+        let turns: Vec<ConversationTurn> = sample.iter().filter_map(event_to_turn).collect();
+        let mut section = sampled(events.min(policy.conversation_window), &turns);
+        if policy.conversation_window_tokens > 0 && section.avg_tokens > 0 {
+            // The window takes the turns that fit the token limit instead.
+            let fitting = policy.conversation_window_tokens / section.avg_tokens as usize;
+            section = sampled(events.min(fitting), &turns);
+        }
+        sections.insert("conversation_window", section);
+    }
+    if rules.insights {
+        let insights = store.count_records(scope, RecordKind::Insight)?;
+        let filter = InsightFilter {
+            limit: Some(ESTIMATE_SAMPLE_SIZE),
+            ..InsightFilter::default()
+        };
+        let sample = store.list_insights(scope, filter)?;
+        sections.insert("insight", sampled(insights.min(policy.max_insights), &sample));
+    }
+
+    let tokens = sections.values().map(|section| section.tokens).sum();
+    let max_tokens = request.budget.max_tokens;
+    Ok(PacketSizeEstimate {
+        max_tokens,
+        tokens,
+        fits_budget: tokens <= max_tokens,
+        sections: sections
+            .into_iter()
+            .map(|(name, section)| (name.to_string(), section))
+            .collect(),
+    })
+}
+
+/// A section read whole.
+fn whole(tokens: u32) -> SectionEstimate {
+    SectionEstimate {
+        items: 1,
+        sampled: 1,
+        avg_tokens: tokens,
+        tokens,
+    }
+}
+
+/// A section of `items` records sized like the average of `sample`.
+fn sampled<T: Serialize>(items: usize, sample: &[T]) -> SectionEstimate {
+    if sample.is_empty() {
+        return SectionEstimate::default();
+    }
+    let avg_tokens = estimate_tokens(&sample).div_ceil(sample.len() as u32);
+    SectionEstimate {
+        items,
+        sampled: sample.len(),
+        avg_tokens,
+        tokens: avg_tokens * items as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, Event, EventKind, InMemoryStore};
+    use engram_types::{Episode, Purpose, Scope};
+    use serde_json::json;
+
+    #[test]
+    fn estimates_a_build_from_counts_and_samples() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        for idx in 0..40 {
+            let fact = Fact::new(format!("fact.{idx:02}"), json!("a fairly short value"));
+            store.upsert_fact(&scope, fact).unwrap();
+        }
+        for idx in 0..3 {
+            store.append_episode(&scope, Episode::new(format!("episode {idx}"))).unwrap();
+        }
+        for idx in 0..12 {
+            let payload = json!({"role": "user", "content": format!("message {idx}")});
+            store.append_event(Event::new(scope.clone(), EventKind::Message, payload)).unwrap();
+        }
+        assert_eq!(store.count_records(&scope, RecordKind::Fact).unwrap(), 40);
+        assert_eq!(store.count_records(&scope, RecordKind::Event).unwrap(), 12);
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        request.policy.filter.planner.conversation_window = true;
+        let estimate = estimate_packet_size(&store, &request).unwrap();
+        let facts = &estimate.sections["facts"];
+        assert_eq!((facts.items, facts.sampled), (40, ESTIMATE_SAMPLE_SIZE));
+        assert_eq!(estimate.sections["episodes"].items, 3);
+        assert_eq!(estimate.sections["conversation_window"].items, 5);
+        request.budget.max_tokens = estimate.tokens - 1;
+        assert!(!estimate_packet_size(&store, &request).unwrap().fits_budget);
+
+        // Same-sized records make the estimate land near the built packet.
+        request.policy.max_facts = 40;
+        request.budget.max_tokens = 100_000;
+        let estimate = estimate_packet_size(&store, &request).unwrap();
+        assert!(estimate.fits_budget);
+        let packet = build_memory_packet(&store, request).unwrap();
+        let built = packet.budget_report.used_tokens_est as f64;
+        assert!((estimate.tokens as f64 - built).abs() / built < 0.25, "{estimate:?} vs {built}");
+    }
+}
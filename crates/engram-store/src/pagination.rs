@@ -0,0 +1,313 @@
+use chrono::{TimeZone, Utc};
+use engram_types::Scope;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use crate::{Event, Store, StoreError, StoreResult, TimeRangeFilter};
+
+/// One page of a run's events, from [`Store::list_events_page`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<Event>,
+    /// Opaque cursor to pass back for the next page; `None` after the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Where a page ends: the millisecond timestamp and id of its last event. Cursors
+/// are hex-encoded so callers treat them as opaque tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EventCursor {
+    pub(crate) ts_ms: i64,
+    pub(crate) event_id: String,
+}
+
+impl EventCursor {
+    pub(crate) fn after(event: &Event) -> Self {
+        Self {
+            ts_ms: event.ts.timestamp_millis(),
+            event_id: event.event_id.clone(),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        format!("{}:{}", self.ts_ms, self.event_id)
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub(crate) fn decode(cursor: &str) -> StoreResult<Self> {
+        let malformed = || StoreError::InvalidInput(format!("malformed event cursor: {}", cursor));
+        if !cursor.len().is_multiple_of(2) {
+            return Err(malformed());
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(cursor.get(idx..idx + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(malformed)?;
+        let text = String::from_utf8(bytes).map_err(|_| malformed())?;
+        let (ts_ms, event_id) = text.split_once(':').ok_or_else(malformed)?;
+        Ok(Self {
+            ts_ms: ts_ms.parse().map_err(|_| malformed())?,
+            event_id: event_id.to_string(),
+        })
+    }
+
+    /// Whether `event` comes after the cursor in `(ts, event_id)` order.
+    pub(crate) fn precedes(&self, event: &Event) -> bool {
+        (event.ts.timestamp_millis(), event.event_id.as_str())
+            > (self.ts_ms, self.event_id.as_str())
+    }
+}
+
+/// Decodes the cursor and checks the limit of a [`Store::list_events_page`] call.
+pub(crate) fn page_request(
+    cursor: Option<&str>,
+    limit: usize,
+) -> StoreResult<Option<EventCursor>> {
+    if limit == 0 {
+        return Err(StoreError::InvalidInput("page limit must be positive".to_string()));
+    }
+    cursor.map(EventCursor::decode).transpose()
+}
+
+/// The page for up to `limit + 1` events in cursor order: the extra event only tells
+/// whether another page follows.
+pub(crate) fn event_page(mut events: Vec<Event>, limit: usize) -> EventPage {
+    let next_cursor = if events.len() > limit {
+        events.truncate(limit);
+        events.last().map(|event| EventCursor::after(event).encode())
+    } else {
+        None
+    };
+    EventPage {
+        events,
+        next_cursor,
+    }
+}
+
+/// [`Store::list_events_page`] over [`Store::list_events`], for stores without a
+/// keyset query of their own: it reads every event from the cursor's millisecond on.
+pub(crate) fn page_events_unindexed<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    cursor: Option<&str>,
+    limit: usize,
+) -> StoreResult<EventPage> {
+    let cursor = page_request(cursor, limit)?;
+    let range = TimeRangeFilter {
+        start: cursor
+            .as_ref()
+            .and_then(|cursor| Utc.timestamp_millis_opt(cursor.ts_ms).single()),
+        end: None,
+    };
+    let mut events: Vec<Event> = store
+        .list_events(scope, range, None)?
+        .into_iter()
+        .filter(|event| cursor.as_ref().is_none_or(|cursor| cursor.precedes(event)))
+        .collect();
+    events.sort_by(|a, b| {
+        (a.ts.timestamp_millis(), &a.event_id).cmp(&(b.ts.timestamp_millis(), &b.event_id))
+    });
+    events.truncate(limit + 1);
+    Ok(event_page(events, limit))
+}
+
+/// Events [`stream_events`] reads per chunk unless told otherwise.
+pub const EVENT_STREAM_CHUNK: usize = 500;
+
+/// A run's events in a time range, read a chunk at a time through
+/// [`Store::list_events_page`]; see [`stream_events`]. As an iterator it yields the
+/// events one by one and ends after a failed read.
+#[derive(Debug)]
+pub struct EventStream<S> {
+    store: S,
+    scope: Scope,
+    range: TimeRangeFilter,
+    cursor: Option<String>,
+    chunk_size: usize,
+    buffered: VecDeque<Event>,
+    done: bool,
+}
+
+/// Streams the events of the run scope within `range`, in `(ts, event_id)` order,
+/// holding at most one chunk in memory: SQL backends answer each chunk with a keyset
+/// `SELECT`, so exports and consolidation jobs can walk million-event runs. `store`
+/// is a reference or an `Arc` to the store.
+pub fn stream_events<S>(store: S, scope: &Scope, range: TimeRangeFilter) -> EventStream<S>
+where
+    S: Deref,
+    S::Target: Store,
+{
+    // An empty event id sorts first, so the first chunk starts at `range.start`.
+    let cursor = range.start.map(|start| {
+        let cursor = EventCursor {
+            ts_ms: start.timestamp_millis(),
+            event_id: String::new(),
+        };
+        cursor.encode()
+    });
+    EventStream {
+        store,
+        scope: scope.clone(),
+        range,
+        cursor,
+        chunk_size: EVENT_STREAM_CHUNK,
+        buffered: VecDeque::new(),
+        done: false,
+    }
+}
+
+impl<S> EventStream<S>
+where
+    S: Deref,
+    S::Target: Store,
+{
+    /// Reads `chunk_size` events per query instead of [`EVENT_STREAM_CHUNK`].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// The next events of the range, at most one chunk; empty once the range is read.
+    /// After an error the same chunk is read again on the next call.
+    pub fn next_chunk(&mut self) -> StoreResult<Vec<Event>> {
+        if !self.buffered.is_empty() {
+            return Ok(self.buffered.drain(..).collect());
+        }
+        while !self.done {
+            let page =
+                self.store.list_events_page(&self.scope, self.cursor.as_deref(), self.chunk_size)?;
+            self.cursor = page.next_cursor;
+            self.done = self.cursor.is_none();
+            // Bounds are compared to the millisecond, as SQL backends store timestamps.
+            let start_ms = self.range.start.map(|start| start.timestamp_millis());
+            let end_ms = self.range.end.map(|end| end.timestamp_millis());
+            let mut events = Vec::with_capacity(page.events.len());
+            for event in page.events {
+                let ts_ms = event.ts.timestamp_millis();
+                if end_ms.is_some_and(|end_ms| ts_ms > end_ms) {
+                    self.done = true;
+                    break;
+                }
+                if start_ms.is_none_or(|start_ms| ts_ms >= start_ms) {
+                    events.push(event);
+                }
+            }
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+impl<S> Iterator for EventStream<S>
+where
+    S: Deref,
+    S::Target: Store,
+{
+    type Item = StoreResult<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffered.is_empty() {
+            match self.next_chunk() {
+                Ok(chunk) => self.buffered.extend(chunk),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.buffered.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore};
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[test]
+    fn pages_through_events_in_time_order() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let start = Utc::now();
+        let mut expected = Vec::new();
+        for idx in 0..5 {
+            let mut event = Event::new(scope.clone(), EventKind::Message, json!(idx));
+            // Two events share each millisecond, so pages split within a timestamp.
+            event.ts = start + Duration::milliseconds(idx / 2);
+            event.event_id = format!("e{}", 9 - idx);
+            store.append_event(event.clone()).unwrap();
+            expected.push(event);
+        }
+        expected.sort_by_key(|event| (event.ts.timestamp_millis(), event.event_id.clone()));
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store.list_events_page(&scope, cursor.as_deref(), 2).unwrap();
+            assert!(page.events.len() <= 2);
+            seen.extend(page.events.into_iter().map(|event| event.event_id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        let ids: Vec<_> = expected.iter().map(|event| event.event_id.clone()).collect();
+        assert_eq!(seen, ids);
+
+        let cursor = EventCursor::after(&expected[1]);
+        assert_eq!(EventCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(EventCursor::decode("zz").is_err());
+        assert!(store.list_events_page(&scope, None, 0).is_err());
+    }
+
+    #[test]
+    fn streams_events_of_a_range_in_chunks() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let start = Utc::now();
+        for idx in 0..10 {
+            let mut event = Event::new(scope.clone(), EventKind::Message, json!(idx));
+            event.ts = start + Duration::seconds(idx);
+            event.event_id = format!("e{}", idx);
+            store.append_event(event).unwrap();
+        }
+
+        let range = TimeRangeFilter {
+            start: Some(start + Duration::seconds(2)),
+            end: Some(start + Duration::seconds(7)),
+        };
+        let mut stream = stream_events(&store, &scope, range.clone()).chunk_size(4);
+        assert_eq!(stream.next_chunk().unwrap().len(), 4);
+        assert_eq!(stream.next_chunk().unwrap().len(), 2);
+        assert!(stream.next_chunk().unwrap().is_empty());
+
+        let ids: Vec<String> = stream_events(&store, &scope, range)
+            .chunk_size(3)
+            .map(|event| event.unwrap().event_id)
+            .collect();
+        assert_eq!(ids, ["e2", "e3", "e4", "e5", "e6", "e7"]);
+        let store = std::sync::Arc::new(store);
+        let all = stream_events(store, &scope, TimeRangeFilter::default()).count();
+        assert_eq!(all, 10);
+    }
+}
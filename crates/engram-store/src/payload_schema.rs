@@ -0,0 +1,659 @@
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use jsonschema::{ValidationError, Validator};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventKind,
+    EventPage, FactFilter, FactVersion, InputLimits, InsightFilter, Lease, MemoryEmbedding,
+    MemoryRef, ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState,
+    SlotSchemaRegistry, SourceCredibility, StmState, Store, StoreCapabilities, StoreError,
+    StoreMetrics, StoreResult, Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity,
+    UserLocale, WorkingStatePatch,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Reject the write with `StoreError::InvalidInput`.
+    Strict,
+    /// Log the violation and accept the write.
+    Warn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SchemaTarget {
+    Kind(EventKind),
+    Tag(String),
+}
+
+impl std::fmt::Display for SchemaTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaTarget::Kind(kind) => write!(f, "kind:{}", kind),
+            SchemaTarget::Tag(tag) => write!(f, "tag:{}", tag),
+        }
+    }
+}
+
+struct RegisteredSchema {
+    mode: ValidationMode,
+    validator: Validator,
+}
+
+/// JSON Schemas applied to event payloads, keyed by event kind or tag.
+///
+/// An event is checked against the schema of its kind and the schema of every tag it
+/// carries. Registering a schema for a target replaces the previous one.
+#[derive(Default)]
+pub struct PayloadSchemaRegistry {
+    schemas: RwLock<HashMap<SchemaTarget, RegisteredSchema>>,
+}
+
+impl std::fmt::Debug for PayloadSchemaRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let targets: Vec<String> = match self.schemas.read() {
+            Ok(guard) => guard.keys().map(|target| target.to_string()).collect(),
+            Err(_) => Vec::new(),
+        };
+        f.debug_struct("PayloadSchemaRegistry")
+            .field("targets", &targets)
+            .finish()
+    }
+}
+
+impl PayloadSchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        &self,
+        target: SchemaTarget,
+        schema: &Value,
+        mode: ValidationMode,
+    ) -> StoreResult<()> {
+        let validator = jsonschema::validator_for(schema).map_err(|err| {
+            StoreError::InvalidInput(format!("invalid schema for {}: {}", target, err))
+        })?;
+        let mut guard = self.schemas.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert(target, RegisteredSchema { mode, validator });
+        Ok(())
+    }
+
+    pub fn unregister(&self, target: &SchemaTarget) -> StoreResult<bool> {
+        let mut guard = self.schemas.write().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.remove(target).is_some())
+    }
+
+    pub fn validate_event(&self, event: &Event) -> StoreResult<()> {
+        let guard = self.schemas.read().map_err(|_| StoreError::Poisoned)?;
+        if guard.is_empty() {
+            return Ok(());
+        }
+
+        let targets = std::iter::once(SchemaTarget::Kind(event.kind.clone()))
+            .chain(event.tags.iter().map(|tag| SchemaTarget::Tag(tag.clone())));
+        for target in targets {
+            let Some(registered) = guard.get(&target) else {
+                continue;
+            };
+            let errors = schema_errors(&registered.validator, &event.payload);
+            if errors.is_empty() {
+                continue;
+            }
+
+            let message = format!(
+                "payload of event {} violates {} schema: {}",
+                event.event_id,
+                target,
+                errors.join("; ")
+            );
+            match registered.mode {
+                ValidationMode::Strict => return Err(StoreError::InvalidInput(message)),
+                ValidationMode::Warn => warn!("{}", message),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every violation of `validator` by `value`, with the path of the offending value.
+pub(crate) fn schema_errors(validator: &Validator, value: &Value) -> Vec<String> {
+    validator.iter_errors(value).map(|err| describe_error(&err)).collect()
+}
+
+pub(crate) fn describe_error(err: &ValidationError<'_>) -> String {
+    let path = err.instance_path().to_string();
+    if path.is_empty() {
+        err.to_string()
+    } else {
+        format!("{} at {}", err, path)
+    }
+}
+
+/// Store wrapper that validates event payloads against a [`PayloadSchemaRegistry`]
+/// before delegating `append_event` to the inner store. With [`InputLimits`] set,
+/// every write is also checked before it reaches the backend, and with a
+/// [`SlotSchemaRegistry`] so are working state patches naming a task type.
+#[derive(Debug)]
+pub struct ValidatingStore<S> {
+    inner: S,
+    registry: Arc<PayloadSchemaRegistry>,
+    limits: Option<InputLimits>,
+    slot_schemas: Option<Arc<SlotSchemaRegistry>>,
+}
+
+impl<S: Store> ValidatingStore<S> {
+    pub fn new(inner: S, registry: Arc<PayloadSchemaRegistry>) -> Self {
+        Self {
+            inner,
+            registry,
+            limits: None,
+            slot_schemas: None,
+        }
+    }
+
+    pub fn with_input_limits(mut self, limits: InputLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    pub fn with_slot_schemas(mut self, slot_schemas: Arc<SlotSchemaRegistry>) -> Self {
+        self.slot_schemas = Some(slot_schemas);
+        self
+    }
+
+    pub fn registry(&self) -> &Arc<PayloadSchemaRegistry> {
+        &self.registry
+    }
+
+    pub fn input_limits(&self) -> Option<&InputLimits> {
+        self.limits.as_ref()
+    }
+
+    pub fn slot_schemas(&self) -> Option<&Arc<SlotSchemaRegistry>> {
+        self.slot_schemas.as_ref()
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Store> Store for ValidatingStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_event(&event)?;
+        }
+        self.registry.validate_event(&event)?;
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        for event in events {
+            if let Some(limits) = &self.limits {
+                limits.validate_event(event)?;
+            }
+            self.registry.validate_event(event)?;
+        }
+        self.inner.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.inner.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        if let Some(limits) = &self.limits {
+            limits.validate_working_state_patch(scope, &patch)?;
+        }
+        if let Some(slot_schemas) = &self.slot_schemas {
+            slot_schemas.validate_patch(&patch)?;
+        }
+        self.inner.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        for (scope, patch) in &patches {
+            if let Some(limits) = &self.limits {
+                limits.validate_working_state_patch(scope, patch)?;
+            }
+            if let Some(slot_schemas) = &self.slot_schemas {
+                slot_schemas.validate_patch(patch)?;
+            }
+        }
+        self.inner.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_stm(scope, &stm)?;
+        }
+        self.inner.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_fact(scope, &fact)?;
+        }
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            for fact in facts {
+                limits.validate_fact(scope, fact)?;
+            }
+        }
+        self.inner.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.inner.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_episode(scope, &episode)?;
+        }
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            for episode in episodes {
+                limits.validate_episode(scope, episode)?;
+            }
+        }
+        self.inner.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_procedure(scope, &procedure)?;
+        }
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_procedure_candidate(scope, &candidate)?;
+        }
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_insight(scope, &insight)?;
+        }
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        if let Some(limits) = &self.limits {
+            limits.validate_context_build(scope, &packet)?;
+        }
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    fn tool_event(id: &str, payload: Value, tags: &[&str]) -> Event {
+        Event {
+            event_id: id.to_string(),
+            scope: sample_scope(),
+            ts: Utc::now(),
+            kind: EventKind::ToolResult,
+            payload,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            entities: Vec::new(),
+            seq: 0,
+            lang: None,
+        }
+    }
+
+    #[test]
+    fn validates_payloads_by_kind_and_tag() {
+        let registry = Arc::new(PayloadSchemaRegistry::new());
+        registry
+            .register(
+                SchemaTarget::Kind(EventKind::ToolResult),
+                &json!({
+                    "type": "object",
+                    "required": ["tool", "status"],
+                    "properties": { "status": { "enum": ["ok", "error"] } }
+                }),
+                ValidationMode::Strict,
+            )
+            .unwrap();
+        registry
+            .register(
+                SchemaTarget::Tag("search".to_string()),
+                &json!({ "type": "object", "required": ["results"] }),
+                ValidationMode::Warn,
+            )
+            .unwrap();
+        let store = ValidatingStore::new(InMemoryStore::new(), registry.clone());
+
+        store
+            .append_event(tool_event("e1", json!({ "tool": "search", "status": "ok" }), &["search"]))
+            .unwrap();
+        let err = store
+            .append_event(tool_event("e2", json!({ "tool": "search", "status": "boom" }), &[]))
+            .unwrap_err();
+        assert!(matches!(err, StoreError::InvalidInput(ref msg) if msg.contains("kind:tool_result")));
+
+        let events = store
+            .list_events(&sample_scope(), TimeRangeFilter::default(), None)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+
+        assert!(registry
+            .register(
+                SchemaTarget::Tag("bad".to_string()),
+                &json!({ "type": 5 }),
+                ValidationMode::Strict,
+            )
+            .is_err());
+        assert!(registry
+            .unregister(&SchemaTarget::Kind(EventKind::ToolResult))
+            .unwrap());
+        store
+            .append_event(tool_event("e3", json!("free text"), &[]))
+            .unwrap();
+    }
+}
@@ -0,0 +1,689 @@
+use chrono::{DateTime, TimeZone, Utc};
+use engram_types::{Fact, FactStatus, Scope};
+use postgres::{Client, NoTls};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::postgres::map_pg_err;
+use crate::{Event, EventKind, FactFilter, Store, StoreError, StoreResult};
+
+/// Microseconds between the Unix epoch and the Postgres epoch (2000-01-01).
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+/// A row change kind published by the upstream database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl RowOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RowOperation::Insert => "insert",
+            RowOperation::Update => "update",
+            RowOperation::Delete => "delete",
+        }
+    }
+}
+
+/// What a mapped row change becomes in engram.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum IngestTarget {
+    /// One event per change; the payload holds `table`, `op`, `row` (the removed row for
+    /// deletes) and, for updates that send it, the `old` row.
+    Event {
+        kind: EventKind,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// A fact per row under `key`, e.g. `order.{id}.status`. Its value is the
+    /// `value_columns` (a single column is stored bare, none means the whole row);
+    /// deleting the row deprecates the fact.
+    Fact {
+        key: String,
+        #[serde(default)]
+        value_columns: Vec<String>,
+    },
+}
+
+/// Routes one upstream table into a scope. Every scope field may reference row
+/// columns as `{column}`, e.g. `user_id: "{customer_id}"`. Deletes only carry the
+/// table's replica identity, by default the primary key, so mapping them usually needs
+/// `ALTER TABLE ... REPLICA IDENTITY FULL` upstream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableMapping {
+    /// `schema.table`; a bare name means `public`.
+    pub table: String,
+    pub scope: Scope,
+    #[serde(default = "all_operations")]
+    pub operations: Vec<RowOperation>,
+    pub target: IngestTarget,
+}
+
+fn all_operations() -> Vec<RowOperation> {
+    vec![RowOperation::Insert, RowOperation::Update, RowOperation::Delete]
+}
+
+impl TableMapping {
+    pub fn new(table: impl Into<String>, scope: Scope, target: IngestTarget) -> Self {
+        Self {
+            table: table.into(),
+            scope,
+            operations: all_operations(),
+            target,
+        }
+    }
+
+    fn qualified_table(&self) -> String {
+        if self.table.contains('.') {
+            self.table.clone()
+        } else {
+            format!("public.{}", self.table)
+        }
+    }
+}
+
+/// Outcome of one [`ReplicationIngestor::poll`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IngestReport {
+    pub transactions: usize,
+    /// Row changes decoded from mapped tables.
+    pub changes: usize,
+    pub events: usize,
+    pub facts: usize,
+    /// Changes dropped because their operation is not mapped or a scope placeholder
+    /// named a missing or null column.
+    pub skipped: usize,
+    /// Position the slot was advanced to; `None` if nothing was pending.
+    pub lsn: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Relation {
+    table: String,
+    columns: Vec<(String, u32)>,
+}
+
+/// A column value in a pgoutput tuple.
+#[derive(Debug)]
+enum Column {
+    Null,
+    /// A TOASTed value the update did not touch, which is not sent.
+    Unchanged,
+    Text(String),
+}
+
+#[derive(Debug)]
+struct RowChange {
+    relation: u32,
+    op: RowOperation,
+    new: Option<Vec<Column>>,
+    old: Option<Vec<Column>>,
+}
+
+#[derive(Debug)]
+struct Transaction {
+    final_lsn: u64,
+    committed_at: DateTime<Utc>,
+    changes: Vec<RowChange>,
+}
+
+/// Consumes a `pgoutput` logical replication slot on an upstream application database
+/// and turns changes to the mapped tables into engram events and facts.
+///
+/// Changes are read through the SQL slot functions and the slot is only advanced past
+/// a batch once every change in it was written, so a crash replays the batch. Event
+/// and fact ids derive from the slot and the change's position, which makes a replay
+/// idempotent. Slots hold back WAL on the upstream server until they are consumed;
+/// poll regularly and [`drop_slot`](Self::drop_slot) an ingestor that is retired.
+pub struct ReplicationIngestor {
+    client: Client,
+    slot: String,
+    publication: String,
+    mappings: Vec<TableMapping>,
+    relations: HashMap<u32, Relation>,
+}
+
+impl std::fmt::Debug for ReplicationIngestor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplicationIngestor")
+            .field("slot", &self.slot)
+            .field("publication", &self.publication)
+            .field("mappings", &self.mappings)
+            .finish()
+    }
+}
+
+impl ReplicationIngestor {
+    /// Connects to the upstream database at `dsn`, which needs `wal_level = logical`.
+    pub fn connect(
+        dsn: &str,
+        slot: impl Into<String>,
+        publication: impl Into<String>,
+        mappings: Vec<TableMapping>,
+    ) -> StoreResult<Self> {
+        if mappings.is_empty() {
+            return Err(StoreError::InvalidInput("at least one table mapping required".into()));
+        }
+        let client = Client::connect(dsn, NoTls).map_err(map_pg_err)?;
+        Ok(Self {
+            client,
+            slot: slot.into(),
+            publication: publication.into(),
+            mappings,
+            relations: HashMap::new(),
+        })
+    }
+
+    /// Creates the publication over the mapped tables and the replication slot, unless
+    /// they exist. Changes committed before the slot exists are not ingested.
+    pub fn create_slot(&mut self) -> StoreResult<()> {
+        let publication_exists = self
+            .client
+            .query_opt("SELECT 1 FROM pg_publication WHERE pubname = $1", &[&self.publication])
+            .map_err(map_pg_err)?
+            .is_some();
+        if !publication_exists {
+            let mut tables: Vec<String> = self
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    mapping
+                        .qualified_table()
+                        .split('.')
+                        .map(quote_ident)
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .collect();
+            tables.sort();
+            tables.dedup();
+            let sql = format!(
+                "CREATE PUBLICATION {} FOR TABLE {}",
+                quote_ident(&self.publication),
+                tables.join(", ")
+            );
+            self.client.batch_execute(&sql).map_err(map_pg_err)?;
+        }
+        let slot_exists = self
+            .client
+            .query_opt("SELECT 1 FROM pg_replication_slots WHERE slot_name = $1", &[&self.slot])
+            .map_err(map_pg_err)?
+            .is_some();
+        if !slot_exists {
+            self.client
+                .execute(
+                    "SELECT pg_create_logical_replication_slot($1, 'pgoutput')",
+                    &[&self.slot],
+                )
+                .map_err(map_pg_err)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the replication slot so the upstream server can recycle its WAL. The
+    /// publication is left in place.
+    pub fn drop_slot(&mut self) -> StoreResult<()> {
+        self.client
+            .execute(
+                "SELECT pg_drop_replication_slot(slot_name) FROM pg_replication_slots \
+                 WHERE slot_name = $1",
+                &[&self.slot],
+            )
+            .map_err(map_pg_err)?;
+        Ok(())
+    }
+
+    /// Ingests committed transactions into `store`, whole transactions at a time, until
+    /// at least `max_changes` changes were read or the slot is drained, then advances
+    /// the slot past them.
+    pub fn poll(&mut self, store: &dyn Store, max_changes: usize) -> StoreResult<IngestReport> {
+        let rows = self
+            .client
+            .query(
+                "SELECT lsn::text, data FROM pg_logical_slot_peek_binary_changes(\
+                 $1, NULL, $2, 'proto_version', '1', 'publication_names', $3)",
+                &[&self.slot, &(max_changes.max(1) as i32), &self.publication],
+            )
+            .map_err(map_pg_err)?;
+        let mut report = IngestReport::default();
+        let mut current: Option<Transaction> = None;
+        let mut confirmed = None;
+        for row in rows {
+            let lsn: String = row.get(0);
+            let data: Vec<u8> = row.get(1);
+            let mut message = Reader::new(&data);
+            match message.u8()? {
+                b'B' => {
+                    let final_lsn = message.u64()?;
+                    let micros = message.i64()? + PG_EPOCH_OFFSET_MICROS;
+                    let committed_at = Utc
+                        .timestamp_micros(micros)
+                        .single()
+                        .ok_or_else(|| malformed("commit timestamp out of range"))?;
+                    current = Some(Transaction {
+                        final_lsn,
+                        committed_at,
+                        changes: Vec::new(),
+                    });
+                }
+                b'C' => {
+                    if let Some(transaction) = current.take() {
+                        self.apply(store, transaction, &mut report)?;
+                    }
+                    report.transactions += 1;
+                    confirmed = Some(lsn);
+                }
+                b'R' => {
+                    let id = message.u32()?;
+                    let namespace = message.string()?;
+                    let name = message.string()?;
+                    let _replica_identity = message.u8()?;
+                    let count = message.u16()?;
+                    let mut columns = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        let _flags = message.u8()?;
+                        let column = message.string()?;
+                        let type_oid = message.u32()?;
+                        let _type_modifier = message.u32()?;
+                        columns.push((column, type_oid));
+                    }
+                    let table = format!("{}.{}", namespace, name);
+                    self.relations.insert(id, Relation { table, columns });
+                }
+                tag @ (b'I' | b'U' | b'D') => {
+                    let relation = message.u32()?;
+                    let mut change = RowChange {
+                        relation,
+                        op: match tag {
+                            b'I' => RowOperation::Insert,
+                            b'U' => RowOperation::Update,
+                            _ => RowOperation::Delete,
+                        },
+                        new: None,
+                        old: None,
+                    };
+                    loop {
+                        match message.u8()? {
+                            b'K' | b'O' => change.old = Some(message.tuple()?),
+                            b'N' => change.new = Some(message.tuple()?),
+                            other => {
+                                return Err(malformed(&format!("tuple marker {}", other)));
+                            }
+                        }
+                        if change.new.is_some() || tag == b'D' {
+                            break;
+                        }
+                    }
+                    if let Some(transaction) = current.as_mut() {
+                        transaction.changes.push(change);
+                    }
+                }
+                // Truncate, type and origin messages carry nothing to ingest.
+                _ => {}
+            }
+        }
+        if let Some(lsn) = confirmed {
+            self.client
+                .execute("SELECT pg_replication_slot_advance($1, $2::text::pg_lsn)", &[
+                    &self.slot, &lsn,
+                ])
+                .map_err(map_pg_err)?;
+            report.lsn = Some(lsn);
+        }
+        Ok(report)
+    }
+
+    fn apply(
+        &self,
+        store: &dyn Store,
+        transaction: Transaction,
+        report: &mut IngestReport,
+    ) -> StoreResult<()> {
+        for (index, change) in transaction.changes.into_iter().enumerate() {
+            let Some(relation) = self.relations.get(&change.relation) else {
+                return Err(malformed("change for an unannounced relation"));
+            };
+            let new = change.new.map(|tuple| row_json(relation, tuple));
+            let old = change.old.map(|tuple| row_json(relation, tuple));
+            let Some(row) = new.as_ref().or(old.as_ref()) else {
+                continue;
+            };
+            report.changes += 1;
+            let mappings = self
+                .mappings
+                .iter()
+                .enumerate()
+                .filter(|(_, mapping)| mapping.qualified_table() == relation.table);
+            for (mapping_index, mapping) in mappings {
+                if !mapping.operations.contains(&change.op) {
+                    report.skipped += 1;
+                    continue;
+                }
+                let Some(scope) = render_scope(&mapping.scope, row) else {
+                    warn!(table = %relation.table, "Skipping change with unresolved scope");
+                    report.skipped += 1;
+                    continue;
+                };
+                match &mapping.target {
+                    IngestTarget::Event { kind, tags } => {
+                        let event_id = format!(
+                            "{}-{:016x}-{}-{}",
+                            self.slot, transaction.final_lsn, index, mapping_index
+                        );
+                        let ids = std::slice::from_ref(&event_id);
+                        if !store.get_events_by_ids(&scope, ids)?.is_empty() {
+                            continue;
+                        }
+                        let mut payload = json!({
+                            "table": relation.table,
+                            "op": change.op.as_str(),
+                            "row": row,
+                        });
+                        if let (Some(old), Some(_)) = (old.as_ref(), new.as_ref()) {
+                            payload["old"] = old.clone();
+                        }
+                        let mut event = Event::new(scope, kind.clone(), payload);
+                        event.event_id = event_id;
+                        event.ts = transaction.committed_at;
+                        event.tags = tags.clone();
+                        store.append_event(event)?;
+                        report.events += 1;
+                    }
+                    IngestTarget::Fact { key, value_columns } => {
+                        let Some(fact_key) = render(key, row) else {
+                            report.skipped += 1;
+                            continue;
+                        };
+                        let fact_id = fact_id(&self.slot, &scope, &fact_key);
+                        if change.op == RowOperation::Delete {
+                            let existing = store
+                                .list_facts(&scope, FactFilter::default())?
+                                .into_iter()
+                                .find(|fact| fact.fact_id == fact_id);
+                            let Some(mut fact) = existing else {
+                                continue;
+                            };
+                            fact.status = FactStatus::Deprecated;
+                            fact.validity.valid_to = Some(transaction.committed_at);
+                            store.upsert_fact(&scope, fact)?;
+                        } else {
+                            let mut fact = Fact::new(fact_key, fact_value(row, value_columns));
+                            fact.fact_id = fact_id;
+                            fact.validity.valid_from = Some(transaction.committed_at);
+                            fact.notes = format!("ingested from {}", relation.table);
+                            store.upsert_fact(&scope, fact)?;
+                        }
+                        report.facts += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+fn malformed(detail: &str) -> StoreError {
+    StoreError::Storage(format!("malformed pgoutput message: {}", detail))
+}
+
+/// Same id for the same row key, so later updates overwrite the fact in place.
+fn fact_id(slot: &str, scope: &Scope, fact_key: &str) -> String {
+    let digest = Sha256::digest(
+        [slot, &scope.tenant_id, &scope.user_id, fact_key].join("\u{0}").as_bytes(),
+    );
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("pg-{}", &hex[..32])
+}
+
+fn row_json(relation: &Relation, tuple: Vec<Column>) -> Value {
+    let mut row = Map::new();
+    for ((name, type_oid), column) in relation.columns.iter().zip(tuple) {
+        let value = match column {
+            Column::Null => Value::Null,
+            // Leave unsent values out rather than report them as null.
+            Column::Unchanged => continue,
+            Column::Text(text) => column_json(*type_oid, text),
+        };
+        row.insert(name.clone(), value);
+    }
+    Value::Object(row)
+}
+
+fn column_json(type_oid: u32, text: String) -> Value {
+    let parsed = match type_oid {
+        16 => Some(Value::Bool(text == "t")),
+        20 | 21 | 23 => text.parse::<i64>().ok().map(Value::from),
+        700 | 701 | 1700 => text.parse::<f64>().ok().and_then(|number| {
+            serde_json::Number::from_f64(number).map(Value::Number)
+        }),
+        114 | 3802 => serde_json::from_str(&text).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or(Value::String(text))
+}
+
+fn fact_value(row: &Value, value_columns: &[String]) -> Value {
+    match value_columns {
+        [] => row.clone(),
+        [column] => row.get(column).cloned().unwrap_or(Value::Null),
+        columns => Value::Object(
+            columns
+                .iter()
+                .map(|column| (column.clone(), row.get(column).cloned().unwrap_or(Value::Null)))
+                .collect(),
+        ),
+    }
+}
+
+/// Fills `{column}` placeholders from `row`; `None` if a column is missing or null.
+fn render(template: &str, row: &Value) -> Option<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..].find('}')? + start;
+        let value = match row.get(&rest[start + 1..end])? {
+            Value::Null => return None,
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        rendered.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    Some(rendered)
+}
+
+fn render_scope(template: &Scope, row: &Value) -> Option<Scope> {
+    Some(Scope {
+        tenant_id: render(&template.tenant_id, row)?,
+        user_id: render(&template.user_id, row)?,
+        agent_id: render(&template.agent_id, row)?,
+        session_id: render(&template.session_id, row)?,
+        run_id: render(&template.run_id, row)?,
+    })
+}
+
+/// Big-endian cursor over one pgoutput message.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, len: usize) -> StoreResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(malformed("truncated message"));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> StoreResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> StoreResult<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().expect("two bytes")))
+    }
+
+    fn u32(&mut self) -> StoreResult<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().expect("four bytes")))
+    }
+
+    fn u64(&mut self) -> StoreResult<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().expect("eight bytes")))
+    }
+
+    fn i64(&mut self) -> StoreResult<i64> {
+        Ok(self.u64()? as i64)
+    }
+
+    fn string(&mut self) -> StoreResult<String> {
+        let end = self
+            .data
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| malformed("unterminated string"))?;
+        let text = String::from_utf8_lossy(&self.data[..end]).into_owned();
+        self.data = &self.data[end + 1..];
+        Ok(text)
+    }
+
+    fn tuple(&mut self) -> StoreResult<Vec<Column>> {
+        let count = self.u16()?;
+        let mut values = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            match self.u8()? {
+                b'n' => values.push(Column::Null),
+                b'u' => values.push(Column::Unchanged),
+                b't' => {
+                    let len = self.u32()? as usize;
+                    let text = String::from_utf8_lossy(self.take(len)?).into_owned();
+                    values.push(Column::Text(text));
+                }
+                other => return Err(malformed(&format!("column kind {}", other))),
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, TimeRangeFilter};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn postgres_ingests_row_changes() {
+        let dsn = match std::env::var("ENGRAM_POSTGRES_DSN") {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => {
+                eprintln!("ENGRAM_POSTGRES_DSN not set; skipping postgres_ingests_row_changes");
+                return;
+            }
+        };
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let table = format!("ingest_orders_{}", suffix);
+        let mut upstream = Client::connect(&dsn, NoTls).unwrap();
+        upstream
+            .batch_execute(&format!(
+                "CREATE TABLE {0} (id INT PRIMARY KEY, customer TEXT, status TEXT, \
+                 total NUMERIC, meta JSONB); ALTER TABLE {0} REPLICA IDENTITY FULL",
+                table
+            ))
+            .unwrap();
+        let scope = Scope {
+            tenant_id: "shop".to_string(),
+            user_id: "{customer}".to_string(),
+            agent_id: "support".to_string(),
+            session_id: "orders".to_string(),
+            run_id: "ingest".to_string(),
+        };
+        let events = TableMapping::new(&table, scope.clone(), IngestTarget::Event {
+            kind: EventKind::Custom("order_change".to_string()),
+            tags: vec!["orders".to_string()],
+        });
+        let mut facts = TableMapping::new(&table, scope, IngestTarget::Fact {
+            key: "order.{id}.status".to_string(),
+            value_columns: vec!["status".to_string()],
+        });
+        facts.operations = vec![RowOperation::Update, RowOperation::Delete];
+        let slot = format!("engram_{}", suffix);
+        let mut ingestor =
+            ReplicationIngestor::connect(&dsn, &slot, &slot, vec![events, facts]).unwrap();
+        ingestor.create_slot().unwrap();
+
+        let statements = [
+            format!("INSERT INTO {} VALUES (1, 'ann', 'new', 9.5, '{{\"gift\": true}}')", table),
+            format!(
+                "BEGIN; UPDATE {table} SET status = 'paid' WHERE id = 1; \
+                 INSERT INTO {table} VALUES (2, NULL, 'new', 1, NULL); COMMIT;",
+                table = table
+            ),
+            format!("DELETE FROM {} WHERE id = 1", table),
+        ];
+        for statement in &statements {
+            upstream.batch_execute(statement).unwrap();
+        }
+
+        let store = InMemoryStore::new();
+        let report = ingestor.poll(&store, 1).unwrap();
+        assert_eq!((report.transactions, report.events, report.facts), (1, 1, 0));
+        assert_eq!(report.skipped, 1);
+        let report = ingestor.poll(&store, 100).unwrap();
+        assert_eq!(report.transactions, 2);
+        // Order 2 has no customer to scope it by, and the fact mapping ignores inserts.
+        assert_eq!((report.events, report.facts, report.skipped), (2, 2, 2));
+        assert_eq!(ingestor.poll(&store, 100).unwrap().transactions, 0);
+
+        let ann = Scope {
+            tenant_id: "shop".to_string(),
+            user_id: "ann".to_string(),
+            agent_id: "support".to_string(),
+            session_id: "orders".to_string(),
+            run_id: "ingest".to_string(),
+        };
+        let events = store.list_events(&ann, TimeRangeFilter::default(), None).unwrap();
+        let ops: Vec<&str> =
+            events.iter().map(|event| event.payload["op"].as_str().unwrap()).collect();
+        assert_eq!(ops, vec!["insert", "update", "delete"]);
+        assert_eq!(events[0].payload["row"]["total"], json!(9.5));
+        assert_eq!(events[0].payload["row"]["meta"], json!({"gift": true}));
+        assert_eq!(events[1].payload["row"]["status"], json!("paid"));
+        assert_eq!(events[1].payload["old"]["status"], json!("new"));
+        assert_eq!(events[2].payload["row"]["status"], json!("paid"));
+        assert_eq!(events[0].tags, vec!["orders"]);
+
+        let facts = store.list_facts(&ann, FactFilter::default()).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].fact_key, "order.1.status");
+        assert_eq!(facts[0].value, json!("paid"));
+        assert_eq!(facts[0].status, FactStatus::Deprecated);
+
+        ingestor.drop_slot().unwrap();
+        upstream
+            .batch_execute(&format!("DROP PUBLICATION {}; DROP TABLE {};", slot, table))
+            .unwrap();
+    }
+}
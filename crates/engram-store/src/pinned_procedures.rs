@@ -0,0 +1,128 @@
+use engram_types::{Procedure, Scope, ScopeLevel};
+use tracing::debug;
+
+use crate::shared_facts::shared_fact_scope;
+use crate::{Store, StoreError, StoreResult};
+
+/// Task type under which a tenant's pinned procedures are stored in its tenant pool.
+pub const PINNED_TASK_TYPE: &str = "_pinned";
+
+/// Whether `procedure` is one of its tenant's pinned procedures.
+pub fn is_pinned_procedure(procedure: &Procedure) -> bool {
+    procedure.task_type == PINNED_TASK_TYPE
+}
+
+/// The tenant pool scope that holds the tenant's pinned procedures.
+pub(crate) fn pinned_procedure_scope(tenant_id: &str) -> Scope {
+    let tenant = Scope {
+        tenant_id: tenant_id.to_string(),
+        user_id: String::new(),
+        agent_id: String::new(),
+        session_id: String::new(),
+        run_id: String::new(),
+    };
+    shared_fact_scope(&tenant, &ScopeLevel::Tenant)
+}
+
+/// Registers a procedure, such as a safety rule or the brand voice, that every packet
+/// built for the tenant includes ahead of the task's own procedures, whatever the
+/// task type. Registering the same `procedure_id` again replaces it. Returns the
+/// stored procedure, whose task type becomes [`PINNED_TASK_TYPE`].
+pub fn pin_tenant_procedure<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+    procedure: Procedure,
+) -> StoreResult<Procedure> {
+    if tenant_id.trim().is_empty() {
+        return Err(StoreError::InvalidInput("tenant_id is empty".to_string()));
+    }
+    let procedure = Procedure {
+        task_type: PINNED_TASK_TYPE.to_string(),
+        ..procedure
+    };
+    store.upsert_procedure(&pinned_procedure_scope(tenant_id), procedure.clone())?;
+    debug!("procedure {} pinned for tenant {}", procedure.procedure_id, tenant_id);
+    Ok(procedure)
+}
+
+/// The tenant's pinned procedures, by priority (highest first), then id.
+pub fn list_tenant_procedures<S: Store + ?Sized>(
+    store: &S,
+    tenant_id: &str,
+) -> StoreResult<Vec<Procedure>> {
+    let scope = pinned_procedure_scope(tenant_id);
+    let mut procedures = store.list_procedures(&scope, PINNED_TASK_TYPE, None)?;
+    procedures.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then_with(|| a.procedure_id.cmp(&b.procedure_id))
+    });
+    Ok(procedures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore};
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    #[test]
+    fn pinned_procedures_lead_every_packet_of_the_tenant() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "acme".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut scoped = Procedure::new("refund", json!({"steps": ["check order"]}));
+        scoped.priority = 10;
+        store.upsert_procedure(&scope, scoped.clone()).unwrap();
+        let mut voice = Procedure::new("anything", json!("Answer in a warm, plain tone."));
+        voice.priority = 1;
+        let voice = pin_tenant_procedure(&store, "acme", voice).unwrap();
+        assert_eq!(voice.task_type, PINNED_TASK_TYPE);
+        let safety = Procedure::new("", json!("Never share card numbers."));
+        let safety = Procedure { priority: 5, ..safety };
+        let safety = pin_tenant_procedure(&store, "acme", safety).unwrap();
+        assert!(pin_tenant_procedure(&store, " ", voice.clone()).is_err());
+
+        let ids = |procedures: &[Procedure]| {
+            procedures.iter().map(|p| p.procedure_id.clone()).collect::<Vec<_>>()
+        };
+        let pinned = list_tenant_procedures(&store, "acme").unwrap();
+        assert_eq!(ids(&pinned), [safety.procedure_id.clone(), voice.procedure_id.clone()]);
+
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.task_type = Some("refund".to_string());
+        request.persist = false;
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        assert_eq!(
+            ids(&packet.long_term.procedures),
+            [safety.procedure_id.clone(), voice.procedure_id.clone(), scoped.procedure_id.clone()]
+        );
+        assert_eq!(
+            packet.budget_report.pinned_procedure_ids,
+            [safety.procedure_id.clone(), voice.procedure_id.clone()]
+        );
+
+        // The task's procedures are trimmed to their own budget; pinned ones are not.
+        request.budget.per_section.insert("procedures".to_string(), json!(1));
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        assert_eq!(ids(&packet.long_term.procedures), ids(&pinned));
+        let safety_tokens = serde_json::to_string(&[&safety]).unwrap().len().div_ceil(4);
+        let section = "pinned_procedures".to_string();
+        request.budget.per_section.insert(section, json!(safety_tokens));
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(ids(&packet.long_term.procedures), [safety.procedure_id]);
+
+        let other = Scope {
+            tenant_id: "globex".to_string(),
+            ..scope
+        };
+        let request = BuildRequest::new(other, Purpose::Planner);
+        assert!(build_memory_packet(&store, request).unwrap().long_term.procedures.is_empty());
+    }
+}
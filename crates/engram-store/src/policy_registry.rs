@@ -0,0 +1,346 @@
+use engram_types::Purpose;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::{BuildRequest, LangMode, PurposeRules, RecallPolicy, StoreError, StoreResult};
+
+/// Overrides of a [`RecallPolicy`] as written in a policy file or sent by a client;
+/// fields left out keep the value of the policy they are applied to.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub max_total_candidates: Option<usize>,
+    #[serde(default)]
+    pub max_facts: Option<usize>,
+    #[serde(default)]
+    pub max_preferences: Option<usize>,
+    #[serde(default)]
+    pub max_procedures: Option<usize>,
+    #[serde(default)]
+    pub max_pinned_procedures: Option<usize>,
+    #[serde(default)]
+    pub max_episodes: Option<usize>,
+    #[serde(default)]
+    pub max_insights: Option<usize>,
+    #[serde(default)]
+    pub max_key_quotes: Option<usize>,
+    #[serde(default)]
+    pub conversation_window: Option<usize>,
+    #[serde(default)]
+    pub conversation_window_tokens: Option<usize>,
+    #[serde(default)]
+    pub episode_time_window_days: Option<i64>,
+    #[serde(default)]
+    pub max_daily_summaries: Option<usize>,
+    #[serde(default)]
+    pub max_weekly_summaries: Option<usize>,
+    #[serde(default)]
+    pub last_tool_evidence_limit: Option<usize>,
+    #[serde(default)]
+    pub max_quote_tokens: Option<usize>,
+    #[serde(default)]
+    pub max_highlight_tokens: Option<usize>,
+    #[serde(default)]
+    pub max_summary_tokens: Option<usize>,
+    #[serde(default)]
+    pub include_shared_facts: Option<bool>,
+    #[serde(default)]
+    pub outcome_weight: Option<f64>,
+    #[serde(default)]
+    pub lang_mode: Option<LangMode>,
+    #[serde(default)]
+    pub include_conversation_window: Option<bool>,
+    #[serde(default)]
+    pub include_insights_in_tool: Option<bool>,
+    #[serde(default)]
+    pub allow_insights_in_responder: Option<bool>,
+    #[serde(default)]
+    pub purpose_filter: Option<PurposeFilterConfig>,
+    #[serde(default)]
+    pub synonyms: Option<HashMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PurposeFilterConfig {
+    #[serde(default)]
+    pub planner: Option<PurposeRulesConfig>,
+    #[serde(default)]
+    pub tool: Option<PurposeRulesConfig>,
+    #[serde(default)]
+    pub responder: Option<PurposeRulesConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PurposeRulesConfig {
+    #[serde(default)]
+    pub conversation_window: Option<bool>,
+    #[serde(default)]
+    pub insights: Option<bool>,
+    #[serde(default)]
+    pub unvalidated_insights: Option<bool>,
+    #[serde(default)]
+    pub excluded_fact_prefixes: Option<Vec<String>>,
+}
+
+impl PurposeRulesConfig {
+    pub fn apply_to(self, rules: &mut PurposeRules) {
+        if let Some(value) = self.conversation_window {
+            rules.conversation_window = value;
+        }
+        if let Some(value) = self.insights {
+            rules.insights = value;
+        }
+        if let Some(value) = self.unvalidated_insights {
+            rules.unvalidated_insights = value;
+        }
+        if let Some(value) = self.excluded_fact_prefixes {
+            rules.excluded_fact_prefixes = value;
+        }
+    }
+}
+
+impl PolicyConfig {
+    /// `policy` with these overrides applied.
+    pub fn apply_to(self, mut policy: RecallPolicy) -> RecallPolicy {
+        if let Some(value) = self.max_total_candidates {
+            policy.max_total_candidates = value;
+        }
+        if let Some(value) = self.max_facts {
+            policy.max_facts = value;
+        }
+        if let Some(value) = self.max_preferences {
+            policy.max_preferences = value;
+        }
+        if let Some(value) = self.max_procedures {
+            policy.max_procedures = value;
+        }
+        if let Some(value) = self.max_pinned_procedures {
+            policy.max_pinned_procedures = value;
+        }
+        if let Some(value) = self.max_episodes {
+            policy.max_episodes = value;
+        }
+        if let Some(value) = self.max_insights {
+            policy.max_insights = value;
+        }
+        if let Some(value) = self.max_key_quotes {
+            policy.max_key_quotes = value;
+        }
+        if let Some(value) = self.conversation_window {
+            policy.conversation_window = value;
+        }
+        if let Some(value) = self.conversation_window_tokens {
+            policy.conversation_window_tokens = value;
+        }
+        if let Some(value) = self.episode_time_window_days {
+            policy.episode_time_window_days = value;
+        }
+        if let Some(value) = self.max_daily_summaries {
+            policy.max_daily_summaries = value;
+        }
+        if let Some(value) = self.max_weekly_summaries {
+            policy.max_weekly_summaries = value;
+        }
+        if let Some(value) = self.last_tool_evidence_limit {
+            policy.last_tool_evidence_limit = value;
+        }
+        if let Some(value) = self.max_quote_tokens {
+            policy.max_quote_tokens = value;
+        }
+        if let Some(value) = self.max_highlight_tokens {
+            policy.max_highlight_tokens = value;
+        }
+        if let Some(value) = self.max_summary_tokens {
+            policy.summary_condensing.max_tokens = value;
+        }
+        if let Some(value) = self.outcome_weight {
+            policy.outcome_weight = value;
+        }
+        if let Some(value) = self.lang_mode {
+            policy.lang_mode = value;
+        }
+        if let Some(value) = self.include_shared_facts {
+            policy.include_shared_facts = value;
+        }
+        // Older flags, kept as shorthands for the matching purpose rules.
+        if let Some(value) = self.include_conversation_window {
+            policy.filter.planner.conversation_window = value;
+            policy.filter.responder.conversation_window = value;
+        }
+        if let Some(value) = self.include_insights_in_tool {
+            policy.filter.tool.insights = value;
+        }
+        if let Some(value) = self.allow_insights_in_responder {
+            policy.filter.responder.insights = value;
+        }
+        if let Some(filter) = self.purpose_filter {
+            let overrides = [
+                (Purpose::Planner, filter.planner),
+                (Purpose::Tool, filter.tool),
+                (Purpose::Responder, filter.responder),
+            ];
+            for (purpose, rules) in overrides {
+                if let Some(rules) = rules {
+                    rules.apply_to(policy.filter.rules_mut(&purpose));
+                }
+            }
+        }
+        if let Some(value) = self.synonyms {
+            policy.cue_expansion.synonyms = value;
+        }
+        policy
+    }
+}
+
+/// Named [`RecallPolicy`] presets that a [`BuildRequest::policy_id`] resolves against,
+/// so recall policies can be versioned in config files (`support-v2`) instead of code.
+///
+/// A policy file maps each id to a [`PolicyConfig`], in JSON or TOML:
+///
+/// ```toml
+/// [support-v2]
+/// max_facts = 12
+/// max_episodes = 5
+/// lang_mode = "prefer"
+/// ```
+///
+/// Each preset starts from [`RecallPolicy::default`]. Presets registered in code may
+/// also carry plugins such as a [`Ranker`](crate::Ranker).
+#[derive(Default)]
+pub struct PolicyRegistry {
+    policies: RwLock<HashMap<String, RecallPolicy>>,
+}
+
+impl std::fmt::Debug for PolicyRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut policy_ids: Vec<String> = match self.policies.read() {
+            Ok(guard) => guard.keys().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+        policy_ids.sort();
+        f.debug_struct("PolicyRegistry")
+            .field("policy_ids", &policy_ids)
+            .finish()
+    }
+}
+
+impl PolicyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `policy` as `policy_id`, replacing the previous preset.
+    pub fn register(&self, policy_id: impl Into<String>, policy: RecallPolicy) -> StoreResult<()> {
+        let mut guard = self.policies.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert(policy_id.into(), policy);
+        Ok(())
+    }
+
+    pub fn unregister(&self, policy_id: &str) -> StoreResult<bool> {
+        let mut guard = self.policies.write().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.remove(policy_id).is_some())
+    }
+
+    pub fn get(&self, policy_id: &str) -> StoreResult<Option<RecallPolicy>> {
+        let guard = self.policies.read().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.get(policy_id).cloned())
+    }
+
+    /// Registers every preset of a JSON policy file; returns their ids, sorted.
+    pub fn load_json(&self, json: &str) -> StoreResult<Vec<String>> {
+        let configs: HashMap<String, PolicyConfig> = serde_json::from_str(json)
+            .map_err(|err| StoreError::InvalidInput(format!("invalid policy file: {}", err)))?;
+        self.register_configs(configs)
+    }
+
+    /// Registers every preset of a TOML policy file; returns their ids, sorted.
+    pub fn load_toml(&self, toml: &str) -> StoreResult<Vec<String>> {
+        let configs: HashMap<String, PolicyConfig> = toml::from_str(toml)
+            .map_err(|err| StoreError::InvalidInput(format!("invalid policy file: {}", err)))?;
+        self.register_configs(configs)
+    }
+
+    /// Registers the presets of the file at `path`, read as TOML if its extension is
+    /// `toml` and as JSON otherwise.
+    pub fn load_file(&self, path: impl AsRef<Path>) -> StoreResult<Vec<String>> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            StoreError::InvalidInput(format!("cannot read {}: {}", path.display(), err))
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => self.load_toml(&contents),
+            _ => self.load_json(&contents),
+        }
+    }
+
+    fn register_configs(&self, configs: HashMap<String, PolicyConfig>) -> StoreResult<Vec<String>> {
+        let mut guard = self.policies.write().map_err(|_| StoreError::Poisoned)?;
+        let mut policy_ids = Vec::with_capacity(configs.len());
+        for (policy_id, config) in configs {
+            guard.insert(policy_id.clone(), config.apply_to(RecallPolicy::default()));
+            policy_ids.push(policy_id);
+        }
+        policy_ids.sort();
+        Ok(policy_ids)
+    }
+
+    /// Sets `request.policy` to the preset named by `request.policy_id`. Returns
+    /// `false`, leaving the request's policy as it was, when no such preset exists.
+    pub fn resolve(&self, request: &mut BuildRequest) -> StoreResult<bool> {
+        match self.get(&request.policy_id)? {
+            Some(policy) => {
+                request.policy = policy;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_types::Scope;
+
+    #[test]
+    fn policy_ids_resolve_against_presets_from_config_files() {
+        let registry = PolicyRegistry::new();
+        let loaded = registry
+            .load_toml(
+                r#"
+                [support-v2]
+                max_facts = 12
+                lang_mode = "prefer"
+
+                [support-v2.purpose_filter.responder]
+                insights = true
+                "#,
+            )
+            .unwrap();
+        assert_eq!(loaded, ["support-v2"]);
+        registry.load_json(r#"{"planner-v1": {"max_episodes": 3}}"#).unwrap();
+
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut request = BuildRequest::new(scope, Purpose::Responder);
+        request.policy_id = "support-v2".to_string();
+        assert!(registry.resolve(&mut request).unwrap());
+        assert_eq!(request.policy.max_facts, 12);
+        assert_eq!(request.policy.lang_mode, LangMode::Prefer);
+        assert!(request.policy.filter.responder.insights);
+        assert_eq!(request.policy.max_episodes, RecallPolicy::default().max_episodes);
+
+        request.policy_id = "unknown".to_string();
+        assert!(!registry.resolve(&mut request).unwrap());
+        assert_eq!(request.policy.max_facts, 12);
+        let err = registry.load_json(r#"{"broken": {"max_facts": "many"}}"#).unwrap_err();
+        assert!(matches!(err, StoreError::InvalidInput(_)));
+    }
+}
@@ -1,25 +1,71 @@
 use chrono::{DateTime, TimeZone, Utc};
 use engram_types::{
-    CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger, InsightType,
-    MemoryPacket, Procedure, Scope, ScopeLevel, ValidationState, WorkingState,
+    CandidateStatus, CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger,
+    InsightType, MemoryPacket, Procedure, ProcedureCandidate, Scope, ScopeLevel, ValidationState,
+    WorkingState,
 };
+use postgres::error::SqlState;
+use postgres::fallible_iterator::FallibleIterator;
 use postgres::types::ToSql;
-use postgres::{Client, NoTls};
-use r2d2::Pool;
+use postgres::{Client, Config, GenericClient, NoTls};
+use r2d2::{Pool, PooledConnection};
 use r2d2_postgres::PostgresConnectionManager;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tracing::warn;
 
+use crate::analytics::collect_user_activity;
+use crate::changelog::PinnedConnections;
+use crate::composer::stored_payload;
+use crate::fact_history::next_fact_version;
+use crate::lease::{lease_lock_key, LeaseTable};
+use crate::metrics::MetricsRecorder;
+use crate::outcome::outcome_for_run;
+use crate::pagination::{event_page, page_request};
+use crate::sql_context::SqlContext;
+use crate::tags::normalize_event_tags;
+use crate::timeout::effective_timeout;
 use crate::{
-    EpisodeFilter, Event, EventKind, FactFilter, InsightFilter, StmState, Store, StoreError,
-    StoreResult, TimeRangeFilter, WorkingStatePatch,
+    apply_working_state_patch, check_state_version, correlation_id, find_events_unindexed,
+    list_episodes_unindexed, scope_matches, state_version_conflict, Change, ChangeOp,
+    ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage, FactFilter,
+    FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryKind, MemoryRef, OptionalTables,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunStatus, RunWorkingState,
+    SourceCredibility, StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult,
+    Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+    WriteNotifications, PREFERENCE_KEY_PREFIX,
 };
 
-const SCHEMA_VERSION: i64 = 1;
+const SCHEMA_VERSION: i64 = 8;
+/// Fast levels already shrink packet JSON several times; higher ones cost more CPU
+/// per build than they save in transfer.
+const PACKET_ZSTD_LEVEL: i32 = 3;
+/// Postgres truncates channel names past 63 bytes.
+const MAX_CHANNEL_LEN: usize = 63;
+/// Postgres truncates identifiers past 63 bytes, so a longer schema name would quietly
+/// name a different schema than the one configured.
+const MAX_SCHEMA_LEN: usize = 63;
+/// Searchable text of an event, every string in its payload, and of an episode, its
+/// summary and highlights. The `simple` configuration neither stems nor drops stop
+/// words, so text in any language is indexed the same way. Queries must repeat these
+/// expressions verbatim to use the `events_text` and `episodes_text` indexes.
+const EVENT_TEXT: &str = "jsonb_to_tsvector('simple', payload::jsonb, '[\"string\"]')";
+const EPISODE_TEXT: &str = "(to_tsvector('simple', summary)
+    || jsonb_to_tsvector('simple', highlights::jsonb, '[\"string\"]'))";
 
 pub struct PostgresStore {
     pool: Pool<PostgresConnectionManager<NoTls>>,
+    config: Config,
+    notifications: bool,
+    statement_timeout: Option<Duration>,
+    compress_packets: bool,
+    optional: OptionalTables,
+    /// Each held lease keeps its advisory lock's connection out of the pool.
+    leases: LeaseTable<(PooledConnection<PostgresConnectionManager<NoTls>>, i64)>,
+    pinned: PinnedConnections<PooledConnection<PostgresConnectionManager<NoTls>>>,
+    metrics: MetricsRecorder,
 }
 
 impl std::fmt::Debug for PostgresStore {
@@ -34,62 +80,541 @@ impl PostgresStore {
     }
 
     pub fn with_pool_size(dsn: &str, max_size: u32) -> StoreResult<Self> {
+        Self::open(dsn, max_size, None)
+    }
+
+    /// Keeps every engram table in Postgres schema `schema`, created if missing, so
+    /// engram can share a database with an application's own tables. The schema name
+    /// must be a lowercase SQL identifier.
+    pub fn in_schema(dsn: &str, schema: &str) -> StoreResult<Self> {
+        Self::open(dsn, 10, Some(schema))
+    }
+
+    fn open(dsn: &str, max_size: u32, schema: Option<&str>) -> StoreResult<Self> {
         let (normalized_dsn, db_name) = normalize_postgres_dsn(dsn)?;
         ensure_postgres_database(&normalized_dsn, &db_name)?;
-        let manager =
-            PostgresConnectionManager::new(normalized_dsn.parse().map_err(map_pg_err)?, NoTls);
+        let mut config: Config = normalized_dsn.parse().map_err(map_pg_err)?;
+        if let Some(schema) = schema {
+            if !is_plain_identifier(schema) {
+                return Err(StoreError::InvalidInput(format!(
+                    "schema must be a lowercase identifier of at most {} bytes: {}",
+                    MAX_SCHEMA_LEN, schema
+                )));
+            }
+            let mut client = config.connect(NoTls).map_err(map_pg_err)?;
+            client
+                .batch_execute(&format!(
+                    "CREATE SCHEMA IF NOT EXISTS {}",
+                    quote_pg_identifier(schema)
+                ))
+                .map_err(map_pg_err)?;
+            // Every pooled connection resolves unqualified table names in the schema.
+            config.options(&format!("-c search_path={}", schema));
+        }
+        let manager = PostgresConnectionManager::new(config.clone(), NoTls);
         let pool = Pool::builder()
             .max_size(max_size)
             .build(manager)
             .map_err(|err| StoreError::Storage(err.to_string()))?;
-        let store = Self { pool };
-        store.with_conn(|conn| ensure_schema(conn))?;
+        let mut store = Self {
+            pool,
+            config,
+            notifications: true,
+            statement_timeout: None,
+            compress_packets: false,
+            optional: OptionalTables {
+                event_index: true,
+                episode_index: true,
+                text_index: true,
+            },
+            leases: LeaseTable::default(),
+            pinned: PinnedConnections::default(),
+            metrics: MetricsRecorder::default(),
+        };
+        let context = SqlContext::new("ensure_schema", "schema_migrations", &[]);
+        store.optional = store.with_conn(context, ensure_schema)?;
         Ok(store)
     }
 
-    fn with_conn<F, T>(&self, f: F) -> StoreResult<T>
+    /// Runs `f` on a pooled connection and counts the call under the context's
+    /// operation; storage errors are tagged with `context`.
+    fn with_conn<F, T>(&self, context: SqlContext<'_>, f: F) -> StoreResult<T>
     where
         F: FnOnce(&mut Client) -> StoreResult<T>,
     {
-        let mut conn = self
-            .pool
-            .get()
-            .map_err(|err| StoreError::Storage(err.to_string()))?;
-        f(&mut conn)
+        let started = Instant::now();
+        let result = self.checkout(f).map_err(|err| context.wrap(err));
+        self.metrics.record(context.operation(), started.elapsed(), result.is_err());
+        result
+    }
+
+    fn checkout<F, T>(&self, f: F) -> StoreResult<T>
+    where
+        F: FnOnce(&mut Client) -> StoreResult<T>,
+    {
+        let connect = || self.pool.get().map_err(|err| StoreError::Unavailable(err.to_string()));
+        self.pinned.checkout(connect, |conn| {
+            let Some(timeout) = effective_timeout(self.statement_timeout) else {
+                return f(conn);
+            };
+            conn.batch_execute(&format!("SET statement_timeout = {}", timeout.as_millis().max(1)))
+                .map_err(map_pg_err)?;
+            let result = f(conn);
+            // Pooled connections outlive the call; leave them without a timeout. In a
+            // failed logged write this fails too, so the call's own error comes first.
+            let reset = conn.batch_execute("SET statement_timeout = 0").map_err(map_pg_err);
+            let value = result?;
+            reset?;
+            Ok(value)
+        })
+    }
+
+    /// Starts a transaction on `conn`, or a savepoint when `conn` is held by
+    /// [`Store::write_logged`] and so already in one.
+    fn transaction<'a>(&self, conn: &'a mut Client) -> StoreResult<PgTransaction<'a>> {
+        PgTransaction::begin(conn, self.pinned.is_pinned()?).map_err(map_pg_err)
+    }
+
+    /// Cancels statements running longer than `timeout` via Postgres'
+    /// `statement_timeout`; they fail with [`StoreError::Timeout`]. Costs two extra
+    /// round trips per call, to set and clear the session setting.
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Stores context build packets zstd-compressed in `packet_zstd` instead of as
+    /// `packet_json` text. Packets of several megabytes shrink severalfold, which cuts
+    /// table bloat and the bytes `list_context_builds` transfers. Rows written either
+    /// way stay readable.
+    pub fn with_compressed_packets(mut self) -> Self {
+        self.compress_packets = true;
+        self
+    }
+
+    /// Stops this store from sending `NOTIFY` messages on writes.
+    pub fn without_notifications(mut self) -> Self {
+        self.notifications = false;
+        self
+    }
+
+    /// Opens a dedicated connection that `LISTEN`s on the tenant's channel. Every
+    /// write through a [`PostgresStore`] with notifications on, in any process,
+    /// sends one message there once it commits.
+    pub fn listen(&self, tenant_id: &str) -> StoreResult<PgListener> {
+        let mut client = self.config.connect(NoTls).map_err(map_pg_err)?;
+        client
+            .batch_execute(&format!(
+                "LISTEN {}",
+                quote_pg_identifier(&notify_channel(tenant_id))
+            ))
+            .map_err(map_pg_err)?;
+        Ok(PgListener {
+            client,
+            tenant_id: tenant_id.to_string(),
+        })
+    }
+
+    fn insert_episode<C: GenericClient>(
+        &self,
+        conn: &mut C,
+        scope: &Scope,
+        episode: &Episode,
+    ) -> StoreResult<()> {
+        conn.execute(
+            "INSERT INTO episodes (
+                tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
+                highlights, tags, entities, sources, compression_level, recency_score,
+                lang
+             ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14)",
+            &[
+                &scope.tenant_id,
+                &scope.user_id,
+                &scope.agent_id,
+                &episode.episode_id,
+                &to_millis(episode.time_range.start),
+                &episode.time_range.end.map(to_millis),
+                &episode.summary,
+                &encode_json(&episode.highlights)?,
+                &encode_json(&episode.tags)?,
+                &encode_json(&episode.entities)?,
+                &encode_json(&episode.sources)?,
+                &compression_level_to_str(&episode.compression_level),
+                &episode.recency_score,
+                &episode.lang,
+            ],
+        )
+        .map_err(map_pg_err)?;
+        if self.optional.episode_index {
+            insert_episode_tags(
+                conn,
+                scope,
+                &episode.episode_id,
+                &episode.tags,
+                &episode.entities,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn notify<C: GenericClient>(&self, conn: &mut C, scope: &Scope, op: &str) -> StoreResult<()> {
+        if !self.notifications {
+            return Ok(());
+        }
+        let payload = encode_json(&PgNotification {
+            op: op.to_string(),
+            scope: scope.clone(),
+        })?;
+        conn.execute(
+            "SELECT pg_notify($1, $2)",
+            &[&notify_channel(&scope.tenant_id), &payload],
+        )
+        .map_err(map_pg_err)?;
+        Ok(())
+    }
+
+    /// Removes the scope's records of `kind`, or only the one with `id`, with their
+    /// index rows, suppressions and embeddings, keeping tombstones of them in soft
+    /// mode. Returns how many were removed.
+    fn remove_records<C: GenericClient>(
+        &self,
+        conn: &mut C,
+        scope: &Scope,
+        kind: RecordKind,
+        id: Option<&str>,
+        mode: DeleteMode,
+    ) -> StoreResult<usize> {
+        let (table, id_column) = kind.table();
+        let mut params = PgParams::new();
+        let mut filter = format!(
+            "tenant_id = {} AND user_id = {} AND agent_id = {}",
+            params.add(scope.tenant_id.clone()),
+            params.add(scope.user_id.clone()),
+            params.add(scope.agent_id.clone())
+        );
+        if kind.run_level() {
+            filter.push_str(&format!(
+                " AND session_id = {} AND run_id = {}",
+                params.add(scope.session_id.clone()),
+                params.add(scope.run_id.clone())
+            ));
+        }
+        if let Some(id) = id {
+            filter.push_str(&format!(" AND {} = {}", id_column, params.add(id.to_string())));
+        }
+        if mode == DeleteMode::Soft {
+            for tombstone in load_tombstones(conn, scope, kind, &filter, &params)? {
+                insert_tombstone(conn, &tombstone)?;
+            }
+        }
+        let mut indexes = Vec::new();
+        if kind == RecordKind::Event && self.optional.event_index {
+            indexes.extend(["event_tags", "event_entities"]);
+        }
+        if kind == RecordKind::Episode && self.optional.episode_index {
+            indexes.extend(["episode_tags", "episode_entities"]);
+        }
+        for index in indexes {
+            conn.execute(&format!("DELETE FROM {} WHERE {}", index, filter), &params.refs())
+                .map_err(map_pg_err)?;
+        }
+        if kind == RecordKind::Fact && mode == DeleteMode::Hard {
+            conn.execute(&format!("DELETE FROM fact_versions WHERE {}", filter), &params.refs())
+                .map_err(map_pg_err)?;
+        }
+        if let Some(memory_kind) = kind.memory_kind() {
+            let mut params = PgParams::new();
+            let mut filter = format!(
+                "tenant_id = {} AND user_id = {} AND agent_id = {} AND item_kind = {}",
+                params.add(scope.tenant_id.clone()),
+                params.add(scope.user_id.clone()),
+                params.add(scope.agent_id.clone()),
+                params.add(memory_kind_to_str(&memory_kind).to_string())
+            );
+            if let Some(id) = id {
+                filter.push_str(&format!(" AND item_id = {}", params.add(id.to_string())));
+            }
+            for table in ["suppressions", "memory_embeddings"] {
+                conn.execute(&format!("DELETE FROM {} WHERE {}", table, filter), &params.refs())
+                    .map_err(map_pg_err)?;
+            }
+        }
+        let removed = conn
+            .execute(&format!("DELETE FROM {} WHERE {}", table, filter), &params.refs())
+            .map_err(map_pg_err)?;
+        Ok(removed as usize)
+    }
+
+    fn context_build_rows(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<String>> {
+        self.with_conn(SqlContext::scoped("context_build_rows", "context_builds", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT packet_json, packet_zstd FROM context_builds
+                 WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+            sql.push_str(" ORDER BY ts ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.into_iter()
+                .map(|row| packet_json(row.get(0), row.get(1)))
+                .collect()
+        })
+    }
+}
+
+/// A write announced on a tenant's notification channel. `op` is the name of the
+/// [`Store`] method that made it, such as `patch_working_state`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgNotification {
+    pub op: String,
+    pub scope: Scope,
+}
+
+/// A connection listening for one tenant's write notifications, from
+/// [`PostgresStore::listen`].
+pub struct PgListener {
+    client: Client,
+    tenant_id: String,
+}
+
+impl std::fmt::Debug for PgListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PgListener")
+            .field("tenant_id", &self.tenant_id)
+            .finish()
+    }
+}
+
+impl PgListener {
+    /// Waits up to `timeout` for the next notification. Returns `None` on timeout.
+    pub fn recv(&mut self, timeout: Duration) -> StoreResult<Option<PgNotification>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut notifications = self.client.notifications();
+            let Some(message) = notifications
+                .timeout_iter(remaining)
+                .next()
+                .map_err(map_pg_err)?
+            else {
+                return Ok(None);
+            };
+            let notification: PgNotification = decode_json(message.payload())?;
+            // Long tenant ids are truncated into the channel name, so a channel can
+            // be shared; skip other tenants' writes.
+            if notification.scope.tenant_id == self.tenant_id {
+                return Ok(Some(notification));
+            }
+        }
+    }
+
+    /// Returns the notifications that already arrived, without waiting.
+    pub fn drain(&mut self) -> StoreResult<Vec<PgNotification>> {
+        let mut drained = Vec::new();
+        let mut notifications = self.client.notifications();
+        let mut messages = notifications.iter();
+        while let Some(message) = messages.next().map_err(map_pg_err)? {
+            let notification: PgNotification = decode_json(message.payload())?;
+            if notification.scope.tenant_id == self.tenant_id {
+                drained.push(notification);
+            }
+        }
+        Ok(drained)
+    }
+}
+
+impl WriteNotifications for PgListener {
+    fn next_write(&mut self, timeout: Duration) -> StoreResult<Option<Scope>> {
+        Ok(self.recv(timeout)?.map(|notification| notification.scope))
+    }
+}
+
+/// A transaction, or a savepoint of the transaction its connection is already in;
+/// rolled back when dropped uncommitted.
+struct PgTransaction<'a> {
+    conn: &'a mut Client,
+    savepoint: bool,
+    done: bool,
+}
+
+impl<'a> PgTransaction<'a> {
+    fn begin(conn: &'a mut Client, savepoint: bool) -> Result<Self, postgres::Error> {
+        conn.batch_execute(if savepoint { "SAVEPOINT engram_write" } else { "BEGIN" })?;
+        Ok(Self {
+            conn,
+            savepoint,
+            done: false,
+        })
+    }
+
+    fn commit(mut self) -> Result<(), postgres::Error> {
+        self.done = true;
+        let commit = if self.savepoint { "RELEASE SAVEPOINT engram_write" } else { "COMMIT" };
+        self.conn.batch_execute(commit)
+    }
+}
+
+impl std::ops::Deref for PgTransaction<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.conn
+    }
+}
+
+impl std::ops::DerefMut for PgTransaction<'_> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.conn
+    }
+}
+
+impl Drop for PgTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let rollback =
+                if self.savepoint { "ROLLBACK TO SAVEPOINT engram_write" } else { "ROLLBACK" };
+            let _ = self.conn.batch_execute(rollback);
+        }
+    }
+}
+
+fn migrate_event_seq(conn: &mut Client) -> StoreResult<()> {
+    let mut tx = conn.transaction().map_err(map_pg_err)?;
+    tx.batch_execute(
+        "
+        ALTER TABLE events ADD COLUMN IF NOT EXISTS seq BIGINT NOT NULL DEFAULT 0;
+        UPDATE events SET seq = ranked.rn
+        FROM (
+            SELECT event_id, ROW_NUMBER() OVER (
+                PARTITION BY tenant_id, user_id, agent_id, session_id, run_id
+                ORDER BY ts, event_id
+            ) AS rn
+            FROM events
+        ) AS ranked
+        WHERE ranked.event_id = events.event_id;
+        CREATE TABLE IF NOT EXISTS event_sequences (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            last_seq BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+        );
+        INSERT INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+            SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(seq)
+            FROM events
+            GROUP BY tenant_id, user_id, agent_id, session_id, run_id
+        ON CONFLICT DO NOTHING;
+        ",
+    )
+    .map_err(map_pg_err)?;
+    tx.commit().map_err(map_pg_err)?;
+    Ok(())
+}
+
+impl Store for PostgresStore {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        let event = normalize_event_tags(event);
+        let payload = stored_payload(&event).into_owned();
+        let Event {
+            event_id,
+            scope,
+            ts,
+            kind,
+            tags,
+            entities,
+            lang,
+            ..
+        } = event;
+        self.with_conn(SqlContext::scoped("append_event", "events", &scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let seq = next_event_seq(&mut *tx, &scope)?;
+            tx.execute(
+                "INSERT INTO events (
+                    event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                    ts, kind, payload, tags, entities, seq, lang
+                ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)",
+                &[
+                    &event_id,
+                    &scope.tenant_id,
+                    &scope.user_id,
+                    &scope.agent_id,
+                    &scope.session_id,
+                    &scope.run_id,
+                    &to_millis(ts),
+                    &kind.as_str(),
+                    &encode_json(&payload)?,
+                    &encode_json(&tags)?,
+                    &encode_json(&entities)?,
+                    &seq,
+                    &lang,
+                ],
+            )
+            .map_err(map_pg_err)?;
+            if self.optional.event_index {
+                insert_event_tags(&mut *tx, &scope, &event_id, &tags, &entities)?;
+            }
+            self.notify(&mut *tx, &scope, "append_event")?;
+            tx.commit().map_err(map_pg_err)?;
+            Ok(())
+        })
     }
 
-    pub fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
         if events.is_empty() {
             return Ok(());
         }
-        self.with_conn(|conn| {
-            let mut tx = conn.transaction().map_err(map_pg_err)?;
+        let events: &[Event] =
+            &events.iter().cloned().map(normalize_event_tags).collect::<Vec<_>>();
+        let context = SqlContext::scoped("append_events_bulk", "events", &events[0].scope);
+        self.with_conn(context, |conn| {
+            let mut tx = self.transaction(conn)?;
             let stmt_event = tx
                 .prepare(
                     "INSERT INTO events (
                         event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                        ts, kind, payload, tags, entities
-                    ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)",
-                )
-                .map_err(map_pg_err)?;
-            let stmt_tag = tx
-                .prepare(
-                    "INSERT INTO event_tags (
-                        tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
-                    ) VALUES ($1,$2,$3,$4,$5,$6,$7)
-                    ON CONFLICT DO NOTHING",
-                )
-                .map_err(map_pg_err)?;
-            let stmt_entity = tx
-                .prepare(
-                    "INSERT INTO event_entities (
-                        tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
-                    ) VALUES ($1,$2,$3,$4,$5,$6,$7)
-                    ON CONFLICT DO NOTHING",
+                        ts, kind, payload, tags, entities, seq, lang
+                    ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)",
                 )
                 .map_err(map_pg_err)?;
+            let index = if self.optional.event_index {
+                let stmt_tag = tx
+                    .prepare(
+                        "INSERT INTO event_tags (
+                            tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
+                        ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                        ON CONFLICT DO NOTHING",
+                    )
+                    .map_err(map_pg_err)?;
+                let stmt_entity = tx
+                    .prepare(
+                        "INSERT INTO event_entities (
+                            tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
+                        ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                        ON CONFLICT DO NOTHING",
+                    )
+                    .map_err(map_pg_err)?;
+                Some((stmt_tag, stmt_entity))
+            } else {
+                None
+            };
 
             for event in events {
+                let seq = next_event_seq(&mut *tx, &event.scope)?;
                 tx.execute(
                     &stmt_event,
                     &[
@@ -100,100 +625,921 @@ impl PostgresStore {
                         &event.scope.session_id,
                         &event.scope.run_id,
                         &to_millis(event.ts),
-                        &event_kind_to_str(&event.kind),
-                        &encode_json(&event.payload)?,
+                        &event.kind.as_str(),
+                        &encode_json(&stored_payload(event))?,
                         &encode_json(&event.tags)?,
                         &encode_json(&event.entities)?,
+                        &seq,
+                        &event.lang,
                     ],
                 )
                 .map_err(map_pg_err)?;
 
-                for tag in unique_values(&event.tags) {
-                    tx.execute(
-                        &stmt_tag,
-                        &[
-                            &event.scope.tenant_id,
-                            &event.scope.user_id,
-                            &event.scope.agent_id,
-                            &event.scope.session_id,
-                            &event.scope.run_id,
-                            &event.event_id,
-                            &tag,
-                        ],
-                    )
-                    .map_err(map_pg_err)?;
-                }
-                for entity in unique_values(&event.entities) {
-                    tx.execute(
-                        &stmt_entity,
-                        &[
-                            &event.scope.tenant_id,
-                            &event.scope.user_id,
-                            &event.scope.agent_id,
-                            &event.scope.session_id,
-                            &event.scope.run_id,
-                            &event.event_id,
-                            &entity,
-                        ],
-                    )
-                    .map_err(map_pg_err)?;
+                if let Some((stmt_tag, stmt_entity)) = &index {
+                    for tag in unique_values(&event.tags) {
+                        tx.execute(
+                            stmt_tag,
+                            &[
+                                &event.scope.tenant_id,
+                                &event.scope.user_id,
+                                &event.scope.agent_id,
+                                &event.scope.session_id,
+                                &event.scope.run_id,
+                                &event.event_id,
+                                &tag,
+                            ],
+                        )
+                        .map_err(map_pg_err)?;
+                    }
+                    for entity in unique_values(&event.entities) {
+                        tx.execute(
+                            stmt_entity,
+                            &[
+                                &event.scope.tenant_id,
+                                &event.scope.user_id,
+                                &event.scope.agent_id,
+                                &event.scope.session_id,
+                                &event.scope.run_id,
+                                &event.event_id,
+                                &entity,
+                            ],
+                        )
+                        .map_err(map_pg_err)?;
+                    }
                 }
             }
 
+            let mut notified: Vec<&Scope> = Vec::new();
+            for event in events {
+                if !notified.iter().any(|scope| scope_matches(scope, &event.scope)) {
+                    self.notify(&mut *tx, &event.scope, "append_event")?;
+                    notified.push(&event.scope);
+                }
+            }
             tx.commit().map_err(map_pg_err)?;
             Ok(())
         })
     }
-}
 
-impl Store for PostgresStore {
-    fn append_event(&self, event: Event) -> StoreResult<()> {
-        let Event {
-            event_id,
-            scope,
-            ts,
-            kind,
-            payload,
-            tags,
-            entities,
-        } = event;
-        self.with_conn(|conn| {
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.with_conn(SqlContext::scoped("list_events", "events", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+
+            if let Some(start) = range.start {
+                sql.push_str(" AND ts >= ");
+                sql.push_str(&params.add(to_millis(start)));
+            }
+            if let Some(end) = range.end {
+                sql.push_str(" AND ts <= ");
+                sql.push_str(&params.add(to_millis(end)));
+            }
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(event_from_row).collect()
+        })
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let cursor = page_request(cursor, limit)?;
+        self.with_conn(SqlContext::scoped("list_events_page", "events", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+            if let Some(cursor) = cursor {
+                sql.push_str(" AND (ts, event_id) > (");
+                sql.push_str(&params.add(cursor.ts_ms));
+                sql.push_str(", ");
+                sql.push_str(&params.add(cursor.event_id));
+                sql.push(')');
+            }
+            sql.push_str(" ORDER BY ts ASC, event_id ASC LIMIT ");
+            sql.push_str(&params.add(limit as i64 + 1));
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            let events = rows.iter().map(event_from_row).collect::<StoreResult<Vec<_>>>()?;
+            Ok(event_page(events, limit))
+        })
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.with_conn(SqlContext::scoped("get_events_since", "events", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+            sql.push_str(" AND seq > ");
+            sql.push_str(&params.add(seq as i64));
+            sql.push_str(" ORDER BY seq ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(event_from_row).collect()
+        })
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        if event_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_conn(SqlContext::scoped("get_events_by_ids", "events", scope), |conn| {
+            let rows = conn
+                .query(
+                    "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                            payload, tags, entities, seq, lang
+                     FROM events
+                     WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3 AND event_id = ANY($4)
+                     ORDER BY ts ASC, seq ASC",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &event_ids],
+                )
+                .map_err(map_pg_err)?;
+            rows.iter().map(event_from_row).collect()
+        })
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let filter = filter.with_tag_forms();
+        if !self.optional.event_index && (!filter.tags.is_empty() || !filter.entities.is_empty()) {
+            return find_events_unindexed(filter, |filter| self.find_events(scope, filter));
+        }
+        self.with_conn(SqlContext::scoped("find_events", "events", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+
+            if let Some(range) = &filter.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND ts >= ");
+                    sql.push_str(&params.add(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND ts <= ");
+                    sql.push_str(&params.add(to_millis(end)));
+                }
+            }
+
+            if !filter.tags.is_empty() {
+                let placeholders = filter
+                    .tags
+                    .iter()
+                    .map(|tag| params.add(tag.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_tags i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.tag IN (",
+                );
+                sql.push_str(&placeholders);
+                sql.push_str("))");
+            }
+
+            if !filter.entities.is_empty() {
+                let placeholders = filter
+                    .entities
+                    .iter()
+                    .map(|entity| params.add(entity.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_entities i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.entity IN (",
+                );
+                sql.push_str(&placeholders);
+                sql.push_str("))");
+            }
+
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(event_from_row).collect()
+        })
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let terms = query.searchable_terms();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_conn(SqlContext::scoped("search_events", "events", scope), |conn| {
+            let mut params = PgParams::new();
+            let tsquery = text_query(&mut params, &terms);
+            let mut sql = format!(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events WHERE {} @@ {} AND tenant_id = ",
+                EVENT_TEXT, tsquery
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND session_id = ");
+            sql.push_str(&params.add(scope.session_id.clone()));
+            sql.push_str(" AND run_id = ");
+            sql.push_str(&params.add(scope.run_id.clone()));
+
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND ts >= ");
+                    sql.push_str(&params.add(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND ts <= ");
+                    sql.push_str(&params.add(to_millis(end)));
+                }
+            }
+
+            sql.push_str(&format!(
+                " ORDER BY ts_rank({}, {}) DESC, ts ASC, seq ASC",
+                EVENT_TEXT, tsquery
+            ));
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(event_from_row).collect()
+        })
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let key = lease_lock_key(scope);
+        self.leases.acquire(
+            scope,
+            ttl,
+            || {
+                let mut conn = self
+                    .pool
+                    .get()
+                    .map_err(|err| StoreError::Unavailable(err.to_string()))?;
+                let locked: bool = conn
+                    .query_one("SELECT pg_try_advisory_lock($1)", &[&key])
+                    .map_err(map_pg_err)?
+                    .get(0);
+                Ok(locked.then_some((conn, key)))
+            },
+            pg_advisory_unlock,
+        )
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.renew(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        let Some(holder) = self.leases.release(lease)? else {
+            return Ok(false);
+        };
+        pg_advisory_unlock(holder)?;
+        Ok(true)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.with_conn(SqlContext::scoped("get_working_state", "wm_state", scope), |conn| {
+            working_state_json(conn, scope)?.as_deref().map(decode_json).transpose()
+        })
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.with_conn(SqlContext::scoped("patch_working_state", "wm_state", scope), |conn| {
+            let next = patch_working_state_row(conn, scope, patch)?;
+            self.notify(conn, scope, "patch_working_state")?;
+            Ok(next)
+        })
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let Some((first, _)) = patches.first() else {
+            return Ok(Vec::new());
+        };
+        let scope = first.clone();
+        self.with_conn(SqlContext::scoped("patch_working_states", "wm_state", &scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut states = Vec::with_capacity(patches.len());
+            for (scope, patch) in patches {
+                states.push(patch_working_state_row(&mut *tx, &scope, patch)?);
+                self.notify(&mut *tx, &scope, "patch_working_state")?;
+            }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(states)
+        })
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.with_conn(SqlContext::scoped("list_working_states", "wm_state", scope), |conn| {
+            let rows = conn
+                .query(
+                    "SELECT run_id, state_json FROM wm_state
+                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4
+                     ORDER BY run_id ASC",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &scope.session_id],
+                )
+                .map_err(map_pg_err)?;
+            let mut states = Vec::with_capacity(rows.len());
+            for row in rows {
+                let payload: String = row.get(1);
+                states.push(RunWorkingState {
+                    run_id: row.get(0),
+                    state: decode_json(&payload)?,
+                });
+            }
+            Ok(states)
+        })
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.with_conn(SqlContext::scoped("get_stm", "stm_state", scope), |conn| {
+            let rows = conn
+                .query(
+                    "SELECT rolling_summary, key_quotes FROM stm_state
+                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &scope.session_id],
+                )
+                .map_err(map_pg_err)?;
+            if let Some(row) = rows.first() {
+                let summary: String = row.get(0);
+                let key_quotes: String = row.get(1);
+                Ok(Some(StmState {
+                    rolling_summary: summary,
+                    key_quotes: decode_json(&key_quotes)?,
+                }))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("update_stm", "stm_state", scope), |conn| {
+            conn.execute(
+                "INSERT INTO stm_state (
+                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                 ON CONFLICT (tenant_id, user_id, agent_id, session_id)
+                 DO UPDATE SET rolling_summary=excluded.rolling_summary,
+                               key_quotes=excluded.key_quotes,
+                               updated_at=excluded.updated_at",
+                &[
+                    &scope.tenant_id,
+                    &scope.user_id,
+                    &scope.agent_id,
+                    &scope.session_id,
+                    &stm.rolling_summary,
+                    &encode_json(&stm.key_quotes)?,
+                    &to_millis(Utc::now()),
+                ],
+            )
+            .map_err(map_pg_err)?;
+            self.notify(conn, scope, "update_stm")?;
+            Ok(())
+        })
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        if filter.include_shared {
+            return crate::shared_facts::list_facts_with_shared(self, scope, filter);
+        }
+        self.with_conn(SqlContext::scoped("list_facts", "facts", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
+                        confidence, sources, scope_level, notes, pinned, lang
+                 FROM facts WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push_str(&params.add(fact_status_to_str(status).to_string()));
+                }
+                sql.push(')');
+            }
+
+            if let Some(pinned) = filter.pinned {
+                sql.push_str(" AND pinned = ");
+                sql.push_str(&params.add(pinned));
+            }
+
+            if let Some(preferences) = filter.preferences {
+                sql.push_str(if preferences { " AND " } else { " AND NOT " });
+                sql.push_str("starts_with(fact_key, ");
+                sql.push_str(&params.add(PREFERENCE_KEY_PREFIX.to_string()));
+                sql.push(')');
+            }
+
+            if let Some(keys) = &filter.keys {
+                sql.push_str(" AND fact_key = ANY(");
+                sql.push_str(&params.add(keys.clone()));
+                sql.push(')');
+            }
+
+            if let Some(at) = filter.valid_at {
+                let ts = to_millis(at);
+                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ");
+                sql.push_str(&params.add(ts));
+                sql.push_str(") AND (valid_to IS NULL OR valid_to >= ");
+                sql.push_str(&params.add(ts));
+                sql.push(')');
+            }
+
+            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(fact_from_row).collect()
+        })
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("upsert_fact", "facts", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            upsert_fact_row(&mut *tx, scope, &fact)?;
+            self.notify(&mut *tx, scope, "upsert_fact")?;
+            tx.commit().map_err(map_pg_err)?;
+            Ok(())
+        })
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        if facts.is_empty() {
+            return Ok(());
+        }
+        self.with_conn(SqlContext::scoped("upsert_facts_bulk", "facts", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            for fact in facts {
+                upsert_fact_row(&mut *tx, scope, fact)?;
+            }
+            self.notify(&mut *tx, scope, "upsert_fact")?;
+            tx.commit().map_err(map_pg_err)?;
+            Ok(())
+        })
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let context = SqlContext::scoped("list_fact_history", "fact_versions", scope);
+        self.with_conn(context, |conn| {
+            let rows = conn
+                .query(
+                    "SELECT version, recorded_at, fact_json FROM fact_versions
+                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND fact_key=$4
+                     ORDER BY recorded_at ASC, version ASC",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &fact_key],
+                )
+                .map_err(map_pg_err)?;
+            rows.iter().map(fact_version_from_row).collect()
+        })
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        let filter = filter.with_tag_forms();
+        if !self.optional.episode_index && (!filter.tags.is_empty() || !filter.entities.is_empty())
+        {
+            return list_episodes_unindexed(filter, |filter| self.list_episodes(scope, filter));
+        }
+        self.with_conn(SqlContext::scoped("list_episodes", "episodes", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
+                        sources, compression_level, recency_score, lang
+                 FROM episodes WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+
+            if let Some(range) = &filter.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND start_ts >= ");
+                    sql.push_str(&params.add(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ");
+                    sql.push_str(&params.add(to_millis(end)));
+                }
+            }
+
+            if !filter.tags.is_empty() {
+                let placeholders = filter
+                    .tags
+                    .iter()
+                    .map(|tag| params.add(tag.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM episode_tags t
+                        WHERE t.tenant_id = episodes.tenant_id
+                          AND t.user_id = episodes.user_id
+                          AND t.agent_id = episodes.agent_id
+                          AND t.episode_id = episodes.episode_id
+                          AND t.tag IN (",
+                );
+                sql.push_str(&placeholders);
+                sql.push_str("))");
+            }
+
+            if !filter.entities.is_empty() {
+                let placeholders = filter
+                    .entities
+                    .iter()
+                    .map(|entity| params.add(entity.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM episode_entities e
+                        WHERE e.tenant_id = episodes.tenant_id
+                          AND e.user_id = episodes.user_id
+                          AND e.agent_id = episodes.agent_id
+                          AND e.episode_id = episodes.episode_id
+                          AND e.entity IN (",
+                );
+                sql.push_str(&placeholders);
+                sql.push_str("))");
+            }
+
+            sql.push_str(" ORDER BY start_ts ASC, episode_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(episode_from_row).collect()
+        })
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("append_episode", "episodes", scope), |conn| {
+            self.insert_episode(conn, scope, &episode)?;
+            self.notify(conn, scope, "append_episode")?;
+            Ok(())
+        })
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        if episodes.is_empty() {
+            return Ok(());
+        }
+        self.with_conn(SqlContext::scoped("append_episodes_bulk", "episodes", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            for episode in episodes {
+                self.insert_episode(&mut *tx, scope, episode)?;
+            }
+            self.notify(&mut *tx, scope, "append_episode")?;
+            tx.commit().map_err(map_pg_err)?;
+            Ok(())
+        })
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        let terms = query.searchable_terms();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_conn(SqlContext::scoped("search_episodes", "episodes", scope), |conn| {
+            let mut params = PgParams::new();
+            let tsquery = text_query(&mut params, &terms);
+            let mut sql = format!(
+                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
+                        sources, compression_level, recency_score, lang
+                 FROM episodes WHERE {} @@ {} AND tenant_id = ",
+                EPISODE_TEXT, tsquery
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND start_ts >= ");
+                    sql.push_str(&params.add(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ");
+                    sql.push_str(&params.add(to_millis(end)));
+                }
+            }
+
+            sql.push_str(&format!(
+                " ORDER BY ts_rank({}, {}) DESC, start_ts ASC, episode_id ASC",
+                EPISODE_TEXT, tsquery
+            ));
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(episode_from_row).collect()
+        })
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.with_conn(SqlContext::scoped("list_procedures", "procedures", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT procedure_id, task_type, content_json, priority, sources, applicability
+                 FROM procedures WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+            sql.push_str(" AND task_type = ");
+            sql.push_str(&params.add(task_type.to_string()));
+            sql.push_str(" ORDER BY priority DESC, procedure_id ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            rows.iter().map(procedure_from_row).collect()
+        })
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("upsert_procedure", "procedures", scope), |conn| {
+            conn.execute(
+                "INSERT INTO procedures (
+                    tenant_id, user_id, agent_id, procedure_id, task_type, content_json,
+                    priority, sources, applicability
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
+                 ON CONFLICT (tenant_id, user_id, agent_id, procedure_id)
+                 DO UPDATE SET task_type=excluded.task_type,
+                               content_json=excluded.content_json,
+                               priority=excluded.priority,
+                               sources=excluded.sources,
+                               applicability=excluded.applicability",
+                &[
+                    &scope.tenant_id,
+                    &scope.user_id,
+                    &scope.agent_id,
+                    &procedure.procedure_id,
+                    &procedure.task_type,
+                    &encode_json(&procedure.content)?,
+                    &procedure.priority,
+                    &encode_json(&procedure.sources)?,
+                    &encode_json(&procedure.applicability)?,
+                ],
+            )
+            .map_err(map_pg_err)?;
+            self.notify(conn, scope, "upsert_procedure")?;
+            Ok(())
+        })
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        let context =
+            SqlContext::scoped("list_procedure_candidates", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                        created_at, reviewed_at, reviewer, review_note
+                 FROM procedure_candidates WHERE tenant_id = ",
+            );
+            sql.push_str(&params.add(scope.tenant_id.clone()));
+            sql.push_str(" AND user_id = ");
+            sql.push_str(&params.add(scope.user_id.clone()));
+            sql.push_str(" AND agent_id = ");
+            sql.push_str(&params.add(scope.agent_id.clone()));
+
+            if let Some(task_type) = &filter.task_type {
+                sql.push_str(" AND task_type = ");
+                sql.push_str(&params.add(task_type.clone()));
+            }
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push_str(&params.add(candidate_status_to_str(status).to_string()));
+                }
+                sql.push(')');
+            }
+
+            sql.push_str(" ORDER BY created_at ASC, candidate_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ");
+                sql.push_str(&params.add(limit as i64));
+            }
+
+            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
+            let mut candidates = Vec::new();
+            for row in rows {
+                candidates.push(row_to_procedure_candidate(&row)?);
+            }
+            Ok(candidates)
+        })
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        let context = SqlContext::scoped("get_procedure_candidate", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
+            let rows = conn
+                .query(
+                    "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                            created_at, reviewed_at, reviewer, review_note
+                     FROM procedure_candidates
+                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND candidate_id=$4",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &candidate_id],
+                )
+                .map_err(map_pg_err)?;
+            match rows.first() {
+                Some(row) => Ok(Some(row_to_procedure_candidate(row)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        let context =
+            SqlContext::scoped("upsert_procedure_candidate", "procedure_candidates", scope);
+        self.with_conn(context, |conn| {
             conn.execute(
-                "INSERT INTO events (
-                    event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                    ts, kind, payload, tags, entities
-                ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)",
+                "INSERT INTO procedure_candidates (
+                    tenant_id, user_id, agent_id, candidate_id, task_type, procedure_json,
+                    source_episodes, evidence, status, created_at, reviewed_at, reviewer,
+                    review_note
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
+                 ON CONFLICT (tenant_id, user_id, agent_id, candidate_id)
+                 DO UPDATE SET task_type=excluded.task_type,
+                               procedure_json=excluded.procedure_json,
+                               source_episodes=excluded.source_episodes,
+                               evidence=excluded.evidence,
+                               status=excluded.status,
+                               created_at=excluded.created_at,
+                               reviewed_at=excluded.reviewed_at,
+                               reviewer=excluded.reviewer,
+                               review_note=excluded.review_note",
                 &[
-                    &event_id,
                     &scope.tenant_id,
                     &scope.user_id,
                     &scope.agent_id,
-                    &scope.session_id,
-                    &scope.run_id,
-                    &to_millis(ts),
-                    &event_kind_to_str(&kind),
-                    &encode_json(&payload)?,
-                    &encode_json(&tags)?,
-                    &encode_json(&entities)?,
+                    &candidate.candidate_id,
+                    &candidate.procedure.task_type,
+                    &encode_json(&candidate.procedure)?,
+                    &encode_json(&candidate.source_episodes)?,
+                    &encode_json(&candidate.evidence)?,
+                    &candidate_status_to_str(&candidate.status),
+                    &to_millis(candidate.created_at),
+                    &candidate.reviewed_at.map(to_millis),
+                    &candidate.reviewer,
+                    &candidate.review_note,
                 ],
             )
             .map_err(map_pg_err)?;
-            insert_event_tags(conn, &scope, &event_id, &tags, &entities)?;
+            self.notify(conn, scope, "upsert_procedure_candidate")?;
             Ok(())
         })
     }
 
-    fn list_events(
-        &self,
-        scope: &Scope,
-        range: TimeRangeFilter,
-        limit: Option<usize>,
-    ) -> StoreResult<Vec<Event>> {
-        self.with_conn(|conn| {
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.with_conn(SqlContext::scoped("list_insights", "insights", scope), |conn| {
             let mut params = PgParams::new();
             let mut sql = String::from(
-                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind, payload, tags, entities
-                 FROM events WHERE tenant_id = ",
+                "SELECT insight_id, kind, statement, trigger, confidence, validation_state,
+                        tests_suggested, expires_at, sources, parent_insight_id
+                 FROM insights WHERE tenant_id = ",
             );
             sql.push_str(&params.add(scope.tenant_id.clone()));
             sql.push_str(" AND user_id = ");
@@ -205,732 +1551,804 @@ impl Store for PostgresStore {
             sql.push_str(" AND run_id = ");
             sql.push_str(&params.add(scope.run_id.clone()));
 
-            if let Some(start) = range.start {
-                sql.push_str(" AND ts >= ");
-                sql.push_str(&params.add(to_millis(start)));
-            }
-            if let Some(end) = range.end {
-                sql.push_str(" AND ts <= ");
-                sql.push_str(&params.add(to_millis(end)));
+            if let Some(states) = &filter.validation_state
+                && !states.is_empty()
+            {
+                sql.push_str(" AND validation_state IN (");
+                for (idx, state) in states.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push_str(&params.add(validation_state_to_str(state).to_string()));
+                }
+                sql.push(')');
             }
-            sql.push_str(" ORDER BY ts ASC");
-            if let Some(limit) = limit {
+
+            sql.push_str(" ORDER BY validation_state DESC, confidence DESC, insight_id ASC");
+            if let Some(limit) = filter.limit {
                 sql.push_str(" LIMIT ");
                 sql.push_str(&params.add(limit as i64));
             }
 
             let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut events = Vec::new();
-            for row in rows {
-                let kind: String = row.get(7);
-                let payload: String = row.get(8);
-                let tags: String = row.get(9);
-                let entities: String = row.get(10);
-                events.push(Event {
-                    event_id: row.get(0),
-                    scope: Scope {
-                        tenant_id: row.get(1),
-                        user_id: row.get(2),
-                        agent_id: row.get(3),
-                        session_id: row.get(4),
-                        run_id: row.get(5),
-                    },
-                    ts: from_millis(row.get(6)),
-                    kind: parse_event_kind(&kind)?,
-                    payload: decode_json(&payload)?,
-                    tags: decode_json(&tags)?,
-                    entities: decode_json(&entities)?,
-                });
-            }
-            Ok(events)
-        })
-    }
-
-    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
-        self.with_conn(|conn| {
-            let rows = conn
-                .query(
-                    "SELECT state_json FROM wm_state
-                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4 AND run_id=$5",
-                    &[
-                        &scope.tenant_id,
-                        &scope.user_id,
-                        &scope.agent_id,
-                        &scope.session_id,
-                        &scope.run_id,
-                    ],
-                )
-                .map_err(map_pg_err)?;
-            if let Some(row) = rows.first() {
-                let payload: String = row.get(0);
-                Ok(Some(decode_json(&payload)?))
-            } else {
-                Ok(None)
-            }
+            rows.iter().map(insight_from_row).collect()
         })
     }
 
-    fn patch_working_state(
-        &self,
-        scope: &Scope,
-        patch: WorkingStatePatch,
-    ) -> StoreResult<WorkingState> {
-        let current = self.get_working_state(scope)?.unwrap_or_default();
-        let mut next = current.clone();
-
-        let mut touched = false;
-        if let Some(goal) = patch.goal {
-            next.goal = goal;
-            touched = true;
-        }
-        if let Some(plan) = patch.plan {
-            next.plan = plan;
-            touched = true;
-        }
-        if let Some(slots) = patch.slots {
-            next.slots = slots;
-            touched = true;
-        }
-        if let Some(constraints) = patch.constraints {
-            next.constraints = constraints;
-            touched = true;
-        }
-        if let Some(tool_evidence) = patch.tool_evidence {
-            next.tool_evidence = tool_evidence;
-            touched = true;
-        }
-        if let Some(decisions) = patch.decisions {
-            next.decisions = decisions;
-            touched = true;
-        }
-        if let Some(risks) = patch.risks {
-            next.risks = risks;
-            touched = true;
-        }
-        if let Some(state_version) = patch.state_version {
-            next.state_version = state_version;
-        } else if touched {
-            next.state_version = next.state_version.saturating_add(1);
-        }
-
-        self.with_conn(|conn| {
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("append_insight", "insights", scope), |conn| {
             conn.execute(
-                "INSERT INTO wm_state (
-                    tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)
-                 ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
-                 DO UPDATE SET state_json=excluded.state_json, updated_at=excluded.updated_at",
+                "INSERT INTO insights (
+                    tenant_id, user_id, agent_id, session_id, run_id, insight_id,
+                    kind, statement, trigger, confidence, validation_state,
+                    tests_suggested, expires_at, sources, parent_insight_id
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)",
                 &[
                     &scope.tenant_id,
                     &scope.user_id,
                     &scope.agent_id,
                     &scope.session_id,
                     &scope.run_id,
-                    &encode_json(&next)?,
-                    &to_millis(Utc::now()),
+                    &insight.id,
+                    &insight_type_to_str(&insight.kind),
+                    &insight.statement,
+                    &insight_trigger_to_str(&insight.trigger),
+                    &insight.confidence,
+                    &validation_state_to_str(&insight.validation_state),
+                    &encode_json(&insight.tests_suggested)?,
+                    &insight.expires_at,
+                    &encode_json(&insight.sources)?,
+                    &insight.parent_insight_id,
                 ],
             )
             .map_err(map_pg_err)?;
-            Ok(next)
+            self.notify(conn, scope, "append_insight")?;
+            Ok(())
         })
     }
 
-    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
-        self.with_conn(|conn| {
-            let rows = conn
-                .query(
-                    "SELECT rolling_summary, key_quotes FROM stm_state
-                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4",
-                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &scope.session_id],
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("expire_insights", "insights", scope), |conn| {
+            let removed = conn
+                .execute(
+                    "DELETE FROM insights
+                     WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4 AND run_id=$5
+                       AND expires_at=$6",
+                    &[
+                        &scope.tenant_id,
+                        &scope.user_id,
+                        &scope.agent_id,
+                        &scope.session_id,
+                        &scope.run_id,
+                        &expires_at,
+                    ],
                 )
                 .map_err(map_pg_err)?;
-            if let Some(row) = rows.first() {
-                let summary: String = row.get(0);
-                let key_quotes: String = row.get(1);
-                Ok(Some(StmState {
-                    rolling_summary: summary,
-                    key_quotes: decode_json(&key_quotes)?,
-                }))
-            } else {
-                Ok(None)
+            if removed > 0 {
+                self.notify(conn, scope, "expire_insights")?;
             }
+            Ok(removed as usize)
         })
     }
 
-    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        let packet_json = encode_json(&packet)?;
+        let (packet_json, packet_zstd) = if self.compress_packets {
+            (None, Some(compress_packet(&packet_json)?))
+        } else {
+            (Some(packet_json), None)
+        };
+        self.with_conn(SqlContext::scoped("write_context_build", "context_builds", scope), |conn| {
             conn.execute(
-                "INSERT INTO stm_state (
-                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)
-                 ON CONFLICT (tenant_id, user_id, agent_id, session_id)
-                 DO UPDATE SET rolling_summary=excluded.rolling_summary,
-                               key_quotes=excluded.key_quotes,
-                               updated_at=excluded.updated_at",
+                "INSERT INTO context_builds (
+                    tenant_id, user_id, agent_id, session_id, run_id, ts, packet_json, packet_zstd
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)",
                 &[
                     &scope.tenant_id,
                     &scope.user_id,
                     &scope.agent_id,
                     &scope.session_id,
-                    &stm.rolling_summary,
-                    &encode_json(&stm.key_quotes)?,
-                    &to_millis(Utc::now()),
+                    &scope.run_id,
+                    &to_millis(packet.meta.generated_at),
+                    &packet_json,
+                    &packet_zstd,
                 ],
             )
             .map_err(map_pg_err)?;
+            self.notify(conn, scope, "write_context_build")?;
             Ok(())
         })
     }
 
-    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
-        self.with_conn(|conn| {
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| decode_json(packet_json))
+            .collect()
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| ContextBuildSummary::from_packet_json(packet_json))
+            .collect()
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.with_conn(SqlContext::new("tenant_activity", "events", &[tenant_id]), |conn| {
             let mut params = PgParams::new();
             let mut sql = String::from(
-                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
-                        confidence, sources, scope_level, notes
-                 FROM facts WHERE tenant_id = ",
+                "SELECT user_id, ts / 86400000 AS day, COUNT(*) FROM events WHERE tenant_id = ",
             );
-            sql.push_str(&params.add(scope.tenant_id.clone()));
-            sql.push_str(" AND user_id = ");
-            sql.push_str(&params.add(scope.user_id.clone()));
-            sql.push_str(" AND agent_id = ");
-            sql.push_str(&params.add(scope.agent_id.clone()));
-
-            if let Some(statuses) = &filter.status {
-                if !statuses.is_empty() {
-                    sql.push_str(" AND status IN (");
-                    for (idx, status) in statuses.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str(&params.add(fact_status_to_str(status).to_string()));
-                    }
-                    sql.push(')');
-                }
+            sql.push_str(&params.add(tenant_id.to_string()));
+            if let Some(start) = range.start {
+                sql.push_str(" AND ts >= ");
+                sql.push_str(&params.add(to_millis(start)));
+            }
+            if let Some(end) = range.end {
+                sql.push_str(" AND ts <= ");
+                sql.push_str(&params.add(to_millis(end)));
             }
+            sql.push_str(" GROUP BY user_id, day");
 
-            if let Some(at) = filter.valid_at {
-                let ts = to_millis(at);
-                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ");
-                sql.push_str(&params.add(ts));
-                sql.push_str(") AND (valid_to IS NULL OR valid_to >= ");
-                sql.push_str(&params.add(ts));
-                sql.push(')');
+            let event_rows = conn
+                .query(&sql, &params.refs())
+                .map_err(map_pg_err)?
+                .iter()
+                .map(|row| (row.get::<_, String>(0), row.get::<_, i64>(1), row.get::<_, i64>(2)))
+                .collect();
+
+            let mut counts = Vec::new();
+            for table in ["facts", "episodes"] {
+                let sql = format!(
+                    "SELECT user_id, COUNT(*) FROM {} WHERE tenant_id = $1 GROUP BY user_id",
+                    table
+                );
+                let rows: Vec<(String, i64)> = conn
+                    .query(&sql, &[&tenant_id])
+                    .map_err(map_pg_err)?
+                    .iter()
+                    .map(|row| (row.get(0), row.get(1)))
+                    .collect();
+                counts.push(rows);
             }
+            let episode_rows = counts.pop().unwrap_or_default();
+            let fact_rows = counts.pop().unwrap_or_default();
+            Ok(collect_user_activity(event_rows, fact_rows, episode_rows))
+        })
+    }
 
-            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
-            if let Some(limit) = filter.limit {
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        let context = SqlContext::new("list_scopes", "events", &[tenant_id.unwrap_or_default()]);
+        self.with_conn(context, |conn| {
+            let mut params = PgParams::new();
+            let mut sql = String::from(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(ts) AS last_ts
+                 FROM events",
+            );
+            if let Some(tenant_id) = tenant_id {
+                sql.push_str(" WHERE tenant_id = ");
+                sql.push_str(&params.add(tenant_id.to_string()));
+            }
+            sql.push_str(
+                " GROUP BY tenant_id, user_id, agent_id, session_id, run_id
+                 ORDER BY last_ts DESC, tenant_id, user_id, agent_id, session_id, run_id",
+            );
+            if let Some(limit) = limit {
                 sql.push_str(" LIMIT ");
                 sql.push_str(&params.add(limit as i64));
             }
 
             let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut facts = Vec::new();
-            for row in rows {
-                let value_json: String = row.get(2);
-                let status: String = row.get(3);
-                let sources: String = row.get(7);
-                let scope_level: String = row.get(8);
-                facts.push(Fact {
-                    fact_id: row.get(0),
-                    fact_key: row.get(1),
-                    value: decode_json(&value_json)?,
-                    status: parse_fact_status(&status)?,
-                    validity: engram_types::Validity {
-                        valid_from: row.get::<_, Option<i64>>(4).map(from_millis),
-                        valid_to: row.get::<_, Option<i64>>(5).map(from_millis),
-                    },
-                    confidence: row.get(6),
-                    sources: decode_json(&sources)?,
-                    scope_level: parse_scope_level(&scope_level)?,
-                    notes: row.get(9),
-                });
-            }
-            Ok(facts)
+            Ok(rows
+                .iter()
+                .map(|row| Scope {
+                    tenant_id: row.get(0),
+                    user_id: row.get(1),
+                    agent_id: row.get(2),
+                    session_id: row.get(3),
+                    run_id: row.get(4),
+                })
+                .collect())
         })
     }
 
-    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("suppress_memory", "suppressions", scope), |conn| {
             conn.execute(
-                "INSERT INTO facts (
-                    tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
-                    valid_from, valid_to, confidence, sources, scope_level, notes
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
-                 ON CONFLICT (tenant_id, user_id, agent_id, fact_id)
-                 DO UPDATE SET fact_key=excluded.fact_key,
-                               value_json=excluded.value_json,
-                               status=excluded.status,
-                               valid_from=excluded.valid_from,
-                               valid_to=excluded.valid_to,
-                               confidence=excluded.confidence,
-                               sources=excluded.sources,
-                               scope_level=excluded.scope_level,
-                               notes=excluded.notes",
+                "INSERT INTO suppressions (
+                    tenant_id, user_id, agent_id, item_kind, item_id, reason, suppressed_at
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                 ON CONFLICT (tenant_id, user_id, agent_id, item_kind, item_id)
+                 DO UPDATE SET reason=excluded.reason, suppressed_at=excluded.suppressed_at",
                 &[
                     &scope.tenant_id,
                     &scope.user_id,
                     &scope.agent_id,
-                    &fact.fact_id,
-                    &fact.fact_key,
-                    &encode_json(&fact.value)?,
-                    &fact_status_to_str(&fact.status),
-                    &fact.validity.valid_from.map(to_millis),
-                    &fact.validity.valid_to.map(to_millis),
-                    &fact.confidence,
-                    &encode_json(&fact.sources)?,
-                    &scope_level_to_str(&fact.scope_level),
-                    &fact.notes,
+                    &memory_kind_to_str(&item.kind),
+                    &item.id,
+                    &reason,
+                    &to_millis(Utc::now()),
                 ],
             )
             .map_err(map_pg_err)?;
+            self.notify(conn, scope, "suppress_memory")?;
             Ok(())
         })
     }
 
-    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
-        self.with_conn(|conn| {
-            let mut use_index = !filter.tags.is_empty() || !filter.entities.is_empty();
-            if !filter.tags.is_empty() && !episode_tags_present(conn, scope)? {
-                use_index = false;
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.with_conn(SqlContext::scoped("unsuppress_memory", "suppressions", scope), |conn| {
+            let deleted = conn
+                .execute(
+                    "DELETE FROM suppressions
+                     WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3
+                       AND item_kind = $4 AND item_id = $5",
+                    &[
+                        &scope.tenant_id,
+                        &scope.user_id,
+                        &scope.agent_id,
+                        &memory_kind_to_str(&item.kind),
+                        &item.id,
+                    ],
+                )
+                .map_err(map_pg_err)?;
+            if deleted > 0 {
+                self.notify(conn, scope, "unsuppress_memory")?;
             }
-            if !filter.entities.is_empty() && !episode_entities_present(conn, scope)? {
-                use_index = false;
+            Ok(deleted > 0)
+        })
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.with_conn(SqlContext::scoped("list_suppressions", "suppressions", scope), |conn| {
+            let rows = conn
+                .query(
+                    "SELECT item_kind, item_id, reason, suppressed_at FROM suppressions
+                     WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3
+                     ORDER BY suppressed_at ASC, item_id ASC",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id],
+                )
+                .map_err(map_pg_err)?;
+            let mut suppressions = Vec::new();
+            for row in rows {
+                let kind: String = row.get(0);
+                suppressions.push(Suppression {
+                    item: MemoryRef {
+                        kind: parse_memory_kind(&kind)?,
+                        id: row.get(1),
+                    },
+                    reason: row.get(2),
+                    suppressed_at: from_millis(row.get(3)),
+                });
             }
+            Ok(suppressions)
+        })
+    }
 
-            if use_index {
-                let mut params = PgParams::new();
-                let mut sql = String::from(
-                    "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
-                            sources, compression_level, recency_score
-                     FROM episodes WHERE tenant_id = ",
-                );
-                sql.push_str(&params.add(scope.tenant_id.clone()));
-                sql.push_str(" AND user_id = ");
-                sql.push_str(&params.add(scope.user_id.clone()));
-                sql.push_str(" AND agent_id = ");
-                sql.push_str(&params.add(scope.agent_id.clone()));
-
-                if let Some(range) = &filter.time_range {
-                    if let Some(start) = range.start {
-                        sql.push_str(" AND start_ts >= ");
-                        sql.push_str(&params.add(to_millis(start)));
-                    }
-                    if let Some(end) = range.end {
-                        sql.push_str(" AND COALESCE(end_ts, start_ts) <= ");
-                        sql.push_str(&params.add(to_millis(end)));
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("evict_memory", "facts, episodes", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut evicted = 0;
+            for item in items {
+                let (table, id_column) = match item.kind {
+                    MemoryKind::Fact => ("facts", "fact_id"),
+                    MemoryKind::Episode => ("episodes", "episode_id"),
+                };
+                let params: [&(dyn ToSql + Sync); 4] =
+                    [&scope.tenant_id, &scope.user_id, &scope.agent_id, &item.id];
+                evicted += tx
+                    .execute(
+                        &format!(
+                            "DELETE FROM {}
+                             WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3 AND {} = $4",
+                            table, id_column
+                        ),
+                        &params,
+                    )
+                    .map_err(map_pg_err)?;
+                if item.kind == MemoryKind::Episode && self.optional.episode_index {
+                    for index in ["episode_tags", "episode_entities"] {
+                        tx.execute(
+                            &format!(
+                                "DELETE FROM {} WHERE tenant_id = $1 AND user_id = $2
+                                   AND agent_id = $3 AND episode_id = $4",
+                                index
+                            ),
+                            &params,
+                        )
+                        .map_err(map_pg_err)?;
                     }
                 }
-
-                if !filter.tags.is_empty() {
-                    let placeholders = filter
-                        .tags
-                        .iter()
-                        .map(|tag| params.add(tag.clone()))
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    sql.push_str(
-                        " AND EXISTS (
-                            SELECT 1 FROM episode_tags t
-                            WHERE t.tenant_id = episodes.tenant_id
-                              AND t.user_id = episodes.user_id
-                              AND t.agent_id = episodes.agent_id
-                              AND t.episode_id = episodes.episode_id
-                              AND t.tag IN (",
-                    );
-                    sql.push_str(&placeholders);
-                    sql.push_str("))");
-                }
-
-                if !filter.entities.is_empty() {
-                    let placeholders = filter
-                        .entities
-                        .iter()
-                        .map(|entity| params.add(entity.clone()))
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    sql.push_str(
-                        " AND EXISTS (
-                            SELECT 1 FROM episode_entities e
-                            WHERE e.tenant_id = episodes.tenant_id
-                              AND e.user_id = episodes.user_id
-                              AND e.agent_id = episodes.agent_id
-                              AND e.episode_id = episodes.episode_id
-                              AND e.entity IN (",
-                    );
-                    sql.push_str(&placeholders);
-                    sql.push_str("))");
+                for table in ["suppressions", "memory_embeddings"] {
+                    tx.execute(
+                        &format!(
+                            "DELETE FROM {}
+                             WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3
+                               AND item_kind = $4 AND item_id = $5",
+                            table
+                        ),
+                        &[
+                            &scope.tenant_id,
+                            &scope.user_id,
+                            &scope.agent_id,
+                            &memory_kind_to_str(&item.kind),
+                            &item.id,
+                        ],
+                    )
+                    .map_err(map_pg_err)?;
                 }
+            }
+            if evicted > 0 {
+                self.notify(&mut *tx, scope, "evict_memory")?;
+            }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(evicted as usize)
+        })
+    }
 
-                sql.push_str(" ORDER BY start_ts ASC, episode_id ASC");
-                if let Some(limit) = filter.limit {
-                    sql.push_str(" LIMIT ");
-                    sql.push_str(&params.add(limit as i64));
-                }
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.with_conn(SqlContext::scoped("delete_record", item.kind.table().0, scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let removed = self.remove_records(&mut *tx, scope, item.kind, Some(&item.id), mode)?;
+            if removed > 0 {
+                self.notify(&mut *tx, scope, "delete_record")?;
+            }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(removed > 0)
+        })
+    }
 
-                let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-                let mut episodes = Vec::new();
-                for row in rows {
-                    let highlights: String = row.get(4);
-                    let tags: String = row.get(5);
-                    let entities: String = row.get(6);
-                    let sources: String = row.get(7);
-                    let compression_level: String = row.get(8);
-                    episodes.push(Episode {
-                        episode_id: row.get(0),
-                        time_range: engram_types::TimeRange {
-                            start: from_millis(row.get(1)),
-                            end: row.get::<_, Option<i64>>(2).map(from_millis),
-                        },
-                        summary: row.get(3),
-                        highlights: decode_json(&highlights)?,
-                        tags: decode_json(&tags)?,
-                        entities: decode_json(&entities)?,
-                        sources: decode_json(&sources)?,
-                        compression_level: parse_compression_level(&compression_level)?,
-                        recency_score: row.get(9),
-                    });
-                }
-                return Ok(episodes);
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        let tables = "events, facts, episodes, procedures, insights";
+        let context = SqlContext::scoped("clear_scope", tables, scope);
+        self.with_conn(context, |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut removed = 0;
+            for kind in RecordKind::ALL {
+                removed += self.remove_records(&mut *tx, scope, kind, None, mode)?;
             }
+            if removed > 0 {
+                self.notify(&mut *tx, scope, "clear_scope")?;
+            }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(removed)
+        })
+    }
 
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        let (table, _) = kind.table();
+        self.with_conn(SqlContext::scoped("count_records", table, scope), |conn| {
             let mut params = PgParams::new();
-            let mut sql = String::from(
-                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
-                        sources, compression_level, recency_score
-                 FROM episodes WHERE tenant_id = ",
+            let mut sql = format!(
+                "SELECT COUNT(*) FROM {} WHERE tenant_id = {} AND user_id = {} AND agent_id = {}",
+                table,
+                params.add(scope.tenant_id.clone()),
+                params.add(scope.user_id.clone()),
+                params.add(scope.agent_id.clone())
             );
-            sql.push_str(&params.add(scope.tenant_id.clone()));
-            sql.push_str(" AND user_id = ");
-            sql.push_str(&params.add(scope.user_id.clone()));
-            sql.push_str(" AND agent_id = ");
-            sql.push_str(&params.add(scope.agent_id.clone()));
-
-            if let Some(range) = &filter.time_range {
-                if let Some(start) = range.start {
-                    sql.push_str(" AND start_ts >= ");
-                    sql.push_str(&params.add(to_millis(start)));
-                }
-                if let Some(end) = range.end {
-                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ");
-                    sql.push_str(&params.add(to_millis(end)));
-                }
+            if kind.run_level() {
+                sql.push_str(&format!(
+                    " AND session_id = {} AND run_id = {}",
+                    params.add(scope.session_id.clone()),
+                    params.add(scope.run_id.clone())
+                ));
             }
+            let row = conn.query_one(&sql, &params.refs()).map_err(map_pg_err)?;
+            Ok(row.get::<_, i64>(0) as usize)
+        })
+    }
 
-            sql.push_str(" ORDER BY start_ts ASC, episode_id ASC");
-            if let Some(limit) = filter.limit {
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.with_conn(SqlContext::scoped("list_tombstones", "tombstones", scope), |conn| {
+            let mut params = PgParams::new();
+            let mut sql = format!(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id,
+                        record_json, deleted_at
+                 FROM tombstones WHERE tenant_id = {} AND user_id = {} AND agent_id = {}
+                 ORDER BY id DESC",
+                params.add(scope.tenant_id.clone()),
+                params.add(scope.user_id.clone()),
+                params.add(scope.agent_id.clone())
+            );
+            if let Some(limit) = limit {
                 sql.push_str(" LIMIT ");
                 sql.push_str(&params.add(limit as i64));
             }
-
             let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut episodes = Vec::new();
+            let mut tombstones = Vec::with_capacity(rows.len());
             for row in rows {
-                let highlights: String = row.get(4);
-                let tags: String = row.get(5);
-                let entities: String = row.get(6);
-                let sources: String = row.get(7);
-                let compression_level: String = row.get(8);
-                episodes.push(Episode {
-                    episode_id: row.get(0),
-                    time_range: engram_types::TimeRange {
-                        start: from_millis(row.get(1)),
-                        end: row.get::<_, Option<i64>>(2).map(from_millis),
+                let kind: String = row.get(5);
+                let record: String = row.get(7);
+                tombstones.push(Tombstone {
+                    scope: Scope {
+                        tenant_id: row.get(0),
+                        user_id: row.get(1),
+                        agent_id: row.get(2),
+                        session_id: row.get(3),
+                        run_id: row.get(4),
                     },
-                    summary: row.get(3),
-                    highlights: decode_json(&highlights)?,
-                    tags: decode_json(&tags)?,
-                    entities: decode_json(&entities)?,
-                    sources: decode_json(&sources)?,
-                    compression_level: parse_compression_level(&compression_level)?,
-                    recency_score: row.get(9),
+                    item: RecordRef::new(parse_record_kind(&kind)?, row.get::<_, String>(6)),
+                    record: decode_json(&record)?,
+                    deleted_at: from_millis(row.get(8)),
                 });
             }
+            Ok(tombstones)
+        })
+    }
 
-            if filter.tags.is_empty() && filter.entities.is_empty() {
-                return Ok(episodes);
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.with_conn(SqlContext::scoped("upsert_embeddings", "memory_embeddings", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let stmt = tx
+                .prepare(
+                    "INSERT INTO memory_embeddings (
+                        tenant_id, user_id, agent_id, item_kind, item_id, model, vector_json,
+                        digest, embedded_at
+                     ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
+                     ON CONFLICT (tenant_id, user_id, agent_id, item_kind, item_id, model)
+                     DO UPDATE SET vector_json = EXCLUDED.vector_json, digest = EXCLUDED.digest,
+                        embedded_at = EXCLUDED.embedded_at",
+                )
+                .map_err(map_pg_err)?;
+            for embedding in &embeddings {
+                tx.execute(
+                    &stmt,
+                    &[
+                        &scope.tenant_id,
+                        &scope.user_id,
+                        &scope.agent_id,
+                        &memory_kind_to_str(&embedding.item.kind),
+                        &embedding.item.id,
+                        &embedding.model,
+                        &encode_json(&embedding.vector)?,
+                        &embedding.digest,
+                        &to_millis(embedding.embedded_at),
+                    ],
+                )
+                .map_err(map_pg_err)?;
+            }
+            if !embeddings.is_empty() {
+                self.notify(&mut *tx, scope, "upsert_embeddings")?;
             }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(())
+        })
+    }
 
-            let filtered = episodes
-                .into_iter()
-                .filter(|episode| {
-                    let tags_ok = if filter.tags.is_empty() {
-                        true
-                    } else {
-                        episode.tags.iter().any(|tag| filter.tags.contains(tag))
-                    };
-                    let entities_ok = if filter.entities.is_empty() {
-                        true
-                    } else {
-                        episode
-                            .entities
-                            .iter()
-                            .any(|entity| filter.entities.contains(entity))
-                    };
-                    tags_ok && entities_ok
-                })
-                .collect();
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.with_conn(SqlContext::scoped("list_embeddings", "memory_embeddings", scope), |conn| {
+            let rows = conn
+                .query(
+                    "SELECT item_kind, item_id, vector_json, digest, embedded_at
+                     FROM memory_embeddings
+                     WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3 AND model = $4
+                     ORDER BY item_kind ASC, item_id ASC",
+                    &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &model],
+                )
+                .map_err(map_pg_err)?;
+            let mut embeddings = Vec::new();
+            for row in rows {
+                let kind: String = row.get(0);
+                embeddings.push(MemoryEmbedding {
+                    item: MemoryRef {
+                        kind: parse_memory_kind(&kind)?,
+                        id: row.get(1),
+                    },
+                    model: model.to_string(),
+                    vector: decode_json(&row.get::<_, String>(2))?,
+                    digest: row.get(3),
+                    embedded_at: from_millis(row.get(4)),
+                });
+            }
+            Ok(embeddings)
+        })
+    }
 
-            Ok(filtered)
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.with_conn(SqlContext::scoped("delete_embeddings", "memory_embeddings", scope), |conn| {
+            let mut tx = self.transaction(conn)?;
+            let mut deleted = 0;
+            for item in items {
+                deleted += tx
+                    .execute(
+                        "DELETE FROM memory_embeddings
+                         WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3
+                           AND item_kind = $4 AND item_id = $5 AND model = $6",
+                        &[
+                            &scope.tenant_id,
+                            &scope.user_id,
+                            &scope.agent_id,
+                            &memory_kind_to_str(&item.kind),
+                            &item.id,
+                            &model,
+                        ],
+                    )
+                    .map_err(map_pg_err)?;
+            }
+            if deleted > 0 {
+                self.notify(&mut *tx, scope, "delete_embeddings")?;
+            }
+            tx.commit().map_err(map_pg_err)?;
+            Ok(deleted as usize)
         })
     }
 
-    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
-        let episode_id = episode.episode_id.clone();
-        let tags = episode.tags.clone();
-        let entities = episode.entities.clone();
-        self.with_conn(|conn| {
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        let outcome = outcome_for_run(scope, outcome)?;
+        self.with_conn(SqlContext::scoped("record_run_outcome", "run_outcomes", scope), |conn| {
             conn.execute(
-                "INSERT INTO episodes (
-                    tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
-                    highlights, tags, entities, sources, compression_level, recency_score
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)",
+                "INSERT INTO run_outcomes (
+                    tenant_id, user_id, agent_id, session_id, run_id,
+                    status, score, error_summary, duration_ms, recorded_at
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+                 ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
+                 DO UPDATE SET status=excluded.status, score=excluded.score,
+                    error_summary=excluded.error_summary, duration_ms=excluded.duration_ms,
+                    recorded_at=excluded.recorded_at",
                 &[
                     &scope.tenant_id,
                     &scope.user_id,
                     &scope.agent_id,
-                    &episode.episode_id,
-                    &to_millis(episode.time_range.start),
-                    &episode.time_range.end.map(to_millis),
-                    &episode.summary,
-                    &encode_json(&episode.highlights)?,
-                    &encode_json(&episode.tags)?,
-                    &encode_json(&episode.entities)?,
-                    &encode_json(&episode.sources)?,
-                    &compression_level_to_str(&episode.compression_level),
-                    &episode.recency_score,
+                    &scope.session_id,
+                    &scope.run_id,
+                    &run_status_to_str(&outcome.status),
+                    &outcome.score,
+                    &outcome.error_summary,
+                    &(outcome.duration_ms as i64),
+                    &to_millis(outcome.recorded_at),
                 ],
             )
             .map_err(map_pg_err)?;
-            insert_episode_tags(conn, scope, &episode_id, &tags, &entities)?;
+            self.notify(conn, scope, "record_run_outcome")?;
             Ok(())
         })
     }
 
-    fn list_procedures(
+    fn list_run_outcomes(
         &self,
         scope: &Scope,
-        task_type: &str,
         limit: Option<usize>,
-    ) -> StoreResult<Vec<Procedure>> {
-        self.with_conn(|conn| {
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.with_conn(SqlContext::scoped("list_run_outcomes", "run_outcomes", scope), |conn| {
             let mut params = PgParams::new();
             let mut sql = String::from(
-                "SELECT procedure_id, task_type, content_json, priority, sources, applicability
-                 FROM procedures WHERE tenant_id = ",
+                "SELECT session_id, run_id, status, score, error_summary, duration_ms, recorded_at
+                 FROM run_outcomes
+                 WHERE tenant_id = ",
             );
             sql.push_str(&params.add(scope.tenant_id.clone()));
             sql.push_str(" AND user_id = ");
             sql.push_str(&params.add(scope.user_id.clone()));
             sql.push_str(" AND agent_id = ");
             sql.push_str(&params.add(scope.agent_id.clone()));
-            sql.push_str(" AND task_type = ");
-            sql.push_str(&params.add(task_type.to_string()));
-            sql.push_str(" ORDER BY priority DESC, procedure_id ASC");
+            sql.push_str(" ORDER BY recorded_at DESC, run_id ASC");
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ");
                 sql.push_str(&params.add(limit as i64));
             }
 
             let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut procedures = Vec::new();
+            let mut outcomes = Vec::with_capacity(rows.len());
             for row in rows {
-                let content: String = row.get(2);
-                let sources: String = row.get(4);
-                let applicability: String = row.get(5);
-                procedures.push(Procedure {
-                    procedure_id: row.get(0),
-                    task_type: row.get(1),
-                    content: decode_json(&content)?,
-                    priority: row.get(3),
-                    sources: decode_json(&sources)?,
-                    applicability: decode_json(&applicability)?,
+                let status: String = row.get(2);
+                let duration_ms: i64 = row.get(5);
+                outcomes.push(RunOutcome {
+                    session_id: row.get(0),
+                    run_id: row.get(1),
+                    status: parse_run_status(&status)?,
+                    score: row.get(3),
+                    error_summary: row.get(4),
+                    duration_ms: duration_ms as u64,
+                    recorded_at: from_millis(row.get(6)),
                 });
             }
-            Ok(procedures)
+            Ok(outcomes)
         })
     }
 
-    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        credibility.check()?;
+        let context = SqlContext::new("set_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_conn(context, |conn| {
             conn.execute(
-                "INSERT INTO procedures (
-                    tenant_id, user_id, agent_id, procedure_id, task_type, content_json,
-                    priority, sources, applicability
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
-                 ON CONFLICT (tenant_id, user_id, agent_id, procedure_id)
-                 DO UPDATE SET task_type=excluded.task_type,
-                               content_json=excluded.content_json,
-                               priority=excluded.priority,
-                               sources=excluded.sources,
-                               applicability=excluded.applicability",
-                &[
-                    &scope.tenant_id,
-                    &scope.user_id,
-                    &scope.agent_id,
-                    &procedure.procedure_id,
-                    &procedure.task_type,
-                    &encode_json(&procedure.content)?,
-                    &procedure.priority,
-                    &encode_json(&procedure.sources)?,
-                    &encode_json(&procedure.applicability)?,
-                ],
+                "INSERT INTO source_credibility (tenant_id, credibility_json, updated_at)
+                 VALUES ($1,$2,$3)
+                 ON CONFLICT (tenant_id) DO UPDATE
+                 SET credibility_json = EXCLUDED.credibility_json, updated_at = EXCLUDED.updated_at",
+                &[&tenant_id, &encode_json(&credibility)?, &to_millis(Utc::now())],
             )
             .map_err(map_pg_err)?;
             Ok(())
         })
     }
 
-    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
-        self.with_conn(|conn| {
-            let mut params = PgParams::new();
-            let mut sql = String::from(
-                "SELECT insight_id, kind, statement, trigger, confidence, validation_state,
-                        tests_suggested, expires_at, sources
-                 FROM insights WHERE tenant_id = ",
-            );
-            sql.push_str(&params.add(scope.tenant_id.clone()));
-            sql.push_str(" AND user_id = ");
-            sql.push_str(&params.add(scope.user_id.clone()));
-            sql.push_str(" AND agent_id = ");
-            sql.push_str(&params.add(scope.agent_id.clone()));
-            sql.push_str(" AND session_id = ");
-            sql.push_str(&params.add(scope.session_id.clone()));
-            sql.push_str(" AND run_id = ");
-            sql.push_str(&params.add(scope.run_id.clone()));
-
-            if let Some(states) = &filter.validation_state {
-                if !states.is_empty() {
-                    sql.push_str(" AND validation_state IN (");
-                    for (idx, state) in states.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str(&params.add(validation_state_to_str(state).to_string()));
-                    }
-                    sql.push(')');
-                }
-            }
-
-            sql.push_str(" ORDER BY validation_state DESC, confidence DESC, insight_id ASC");
-            if let Some(limit) = filter.limit {
-                sql.push_str(" LIMIT ");
-                sql.push_str(&params.add(limit as i64));
-            }
-
-            let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut insights = Vec::new();
-            for row in rows {
-                let kind: String = row.get(1);
-                let trigger: String = row.get(3);
-                let validation_state: String = row.get(5);
-                let tests: String = row.get(6);
-                let sources: String = row.get(8);
-                insights.push(InsightItem {
-                    id: row.get(0),
-                    kind: parse_insight_type(&kind)?,
-                    statement: row.get(2),
-                    trigger: parse_insight_trigger(&trigger)?,
-                    confidence: row.get(4),
-                    validation_state: parse_validation_state(&validation_state)?,
-                    tests_suggested: decode_json(&tests)?,
-                    expires_at: row.get(7),
-                    sources: decode_json(&sources)?,
-                });
-            }
-            Ok(insights)
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        let context = SqlContext::new("get_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_conn(context, |conn| {
+            let row = conn
+                .query_opt(
+                    "SELECT credibility_json FROM source_credibility WHERE tenant_id = $1",
+                    &[&tenant_id],
+                )
+                .map_err(map_pg_err)?;
+            row.map(|row| decode_json(&row.get::<_, String>(0))).transpose()
         })
     }
 
-    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        locale.check()?;
+        let context = SqlContext::new("set_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_conn(context, |conn| {
             conn.execute(
-                "INSERT INTO insights (
-                    tenant_id, user_id, agent_id, session_id, run_id, insight_id,
-                    kind, statement, trigger, confidence, validation_state,
-                    tests_suggested, expires_at, sources
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14)",
-                &[
-                    &scope.tenant_id,
-                    &scope.user_id,
-                    &scope.agent_id,
-                    &scope.session_id,
-                    &scope.run_id,
-                    &insight.id,
-                    &insight_type_to_str(&insight.kind),
-                    &insight.statement,
-                    &insight_trigger_to_str(&insight.trigger),
-                    &insight.confidence,
-                    &validation_state_to_str(&insight.validation_state),
-                    &encode_json(&insight.tests_suggested)?,
-                    &insight.expires_at,
-                    &encode_json(&insight.sources)?,
-                ],
+                "INSERT INTO user_locales (tenant_id, user_id, locale_json, updated_at)
+                 VALUES ($1,$2,$3,$4)
+                 ON CONFLICT (tenant_id, user_id) DO UPDATE
+                 SET locale_json = EXCLUDED.locale_json, updated_at = EXCLUDED.updated_at",
+                &[&tenant_id, &user_id, &encode_json(&locale)?, &to_millis(Utc::now())],
             )
             .map_err(map_pg_err)?;
             Ok(())
         })
     }
 
-    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
-        self.with_conn(|conn| {
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        let context = SqlContext::new("get_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_conn(context, |conn| {
+            let row = conn
+                .query_opt(
+                    "SELECT locale_json FROM user_locales WHERE tenant_id = $1 AND user_id = $2",
+                    &[&tenant_id, &user_id],
+                )
+                .map_err(map_pg_err)?;
+            row.map(|row| decode_json(&row.get::<_, String>(0))).transpose()
+        })
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("append_change", "changelog", &[]), |conn| {
+            let row = conn
+                .query_one(
+                    "INSERT INTO changelog (ts, op, correlation_id) VALUES ($1,$2,$3)
+                     RETURNING seq",
+                    &[&to_millis(Utc::now()), &encode_json(&op)?, &correlation_id()],
+                )
+                .map_err(map_pg_err)?;
+            Ok(row.get::<_, i64>(0) as u64)
+        })
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        let mut conn = self.pool.get().map_err(|err| StoreError::Unavailable(err.to_string()))?;
+        conn.batch_execute("BEGIN").map_err(map_pg_err)?;
+        let (mut conn, result) = self.pinned.run(conn, || {
+            for op in write()? {
+                self.append_change(op)?;
+            }
+            Ok(())
+        })?;
+        let committed = result.and_then(|()| conn.batch_execute("COMMIT").map_err(map_pg_err));
+        if committed.is_err() {
+            // The connection goes back to the pool; it must not stay in the transaction.
+            let _ = conn.batch_execute("ROLLBACK");
+        }
+        committed
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("load_change_cursor", "sync_cursors", &[name]), |conn| {
+            let row = conn
+                .query_opt("SELECT seq FROM sync_cursors WHERE name = $1", &[&name])
+                .map_err(map_pg_err)?;
+            Ok(row.map_or(0, |row| row.get::<_, i64>(0) as u64))
+        })
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.with_conn(SqlContext::new("save_change_cursor", "sync_cursors", &[name]), |conn| {
             conn.execute(
-                "INSERT INTO context_builds (
-                    tenant_id, user_id, agent_id, session_id, run_id, ts, packet_json
-                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)",
-                &[
-                    &scope.tenant_id,
-                    &scope.user_id,
-                    &scope.agent_id,
-                    &scope.session_id,
-                    &scope.run_id,
-                    &to_millis(packet.meta.generated_at),
-                    &encode_json(&packet)?,
-                ],
+                "INSERT INTO sync_cursors (name, seq, updated_at) VALUES ($1,$2,$3)
+                 ON CONFLICT (name) DO UPDATE
+                 SET seq = EXCLUDED.seq, updated_at = EXCLUDED.updated_at",
+                &[&name, &(seq as i64), &to_millis(Utc::now())],
             )
             .map_err(map_pg_err)?;
             Ok(())
         })
     }
 
-    fn list_context_builds(
-        &self,
-        scope: &Scope,
-        limit: Option<usize>,
-    ) -> StoreResult<Vec<MemoryPacket>> {
-        self.with_conn(|conn| {
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.with_conn(SqlContext::new("list_changes", "changelog", &[]), |conn| {
             let mut params = PgParams::new();
-            let mut sql = String::from(
-                "SELECT packet_json FROM context_builds
-                 WHERE tenant_id = ",
-            );
-            sql.push_str(&params.add(scope.tenant_id.clone()));
-            sql.push_str(" AND user_id = ");
-            sql.push_str(&params.add(scope.user_id.clone()));
-            sql.push_str(" AND agent_id = ");
-            sql.push_str(&params.add(scope.agent_id.clone()));
-            sql.push_str(" AND session_id = ");
-            sql.push_str(&params.add(scope.session_id.clone()));
-            sql.push_str(" AND run_id = ");
-            sql.push_str(&params.add(scope.run_id.clone()));
-            sql.push_str(" ORDER BY ts ASC");
+            let mut sql =
+                String::from("SELECT seq, ts, op, correlation_id FROM changelog WHERE seq > ");
+            sql.push_str(&params.add(after_seq as i64));
+            sql.push_str(" ORDER BY seq ASC");
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ");
                 sql.push_str(&params.add(limit as i64));
             }
 
             let rows = conn.query(&sql, &params.refs()).map_err(map_pg_err)?;
-            let mut packets = Vec::new();
+            let mut changes = Vec::new();
             for row in rows {
-                let payload: String = row.get(0);
-                packets.push(decode_json(&payload)?);
+                let op: String = row.get(2);
+                changes.push(Change {
+                    seq: row.get::<_, i64>(0) as u64,
+                    ts: from_millis(row.get(1)),
+                    correlation_id: row.get(3),
+                    op: decode_json(&op)?,
+                });
             }
-            Ok(packets)
+            Ok(changes)
         })
     }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.with_conn(SqlContext::new("last_change_seq", "changelog", &[]), |conn| {
+            let row = conn
+                .query_one("SELECT COALESCE(MAX(seq), 0) FROM changelog", &[])
+                .map_err(map_pg_err)?;
+            Ok(row.get::<_, i64>(0) as u64)
+        })
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.metrics.snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        // Search always goes through `tsvector`; the GIN indexes only make it faster.
+        StoreCapabilities {
+            vector_search: true,
+            indexed_filters: self.optional.event_index && self.optional.episode_index,
+            transactions: true,
+            bulk_ops: true,
+            full_text_search: true,
+            subscriptions: self.notifications,
+        }
+    }
 }
 
-fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
+fn ensure_schema(conn: &mut Client) -> StoreResult<OptionalTables> {
     conn.batch_execute(
         "
         CREATE TABLE IF NOT EXISTS schema_migrations (
@@ -955,11 +2373,8 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             current, SCHEMA_VERSION
         )));
     }
-    if current < SCHEMA_VERSION && current != 0 {
-        return Err(StoreError::Storage(format!(
-            "database schema version {} requires migration to {}",
-            current, SCHEMA_VERSION
-        )));
+    if current == 1 {
+        migrate_event_seq(conn)?;
     }
 
     conn.batch_execute(
@@ -975,55 +2390,23 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             kind TEXT NOT NULL,
             payload TEXT NOT NULL,
             tags TEXT NOT NULL,
-            entities TEXT NOT NULL
+            entities TEXT NOT NULL,
+            seq BIGINT NOT NULL DEFAULT 0,
+            lang TEXT
         );
         CREATE INDEX IF NOT EXISTS events_scope_ts
             ON events (tenant_id, user_id, agent_id, session_id, run_id, ts);
-        CREATE TABLE IF NOT EXISTS event_tags (
-            tenant_id TEXT NOT NULL,
-            user_id TEXT NOT NULL,
-            agent_id TEXT NOT NULL,
-            session_id TEXT NOT NULL,
-            run_id TEXT NOT NULL,
-            event_id TEXT NOT NULL,
-            tag TEXT NOT NULL,
-            PRIMARY KEY (
-                tenant_id,
-                user_id,
-                agent_id,
-                session_id,
-                run_id,
-                event_id,
-                tag
-            )
-        );
-        CREATE INDEX IF NOT EXISTS event_tags_scope_tag
-            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag);
-        CREATE INDEX IF NOT EXISTS event_tags_scope_event
-            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, event_id);
-
-        CREATE TABLE IF NOT EXISTS event_entities (
+        CREATE UNIQUE INDEX IF NOT EXISTS events_scope_seq
+            ON events (tenant_id, user_id, agent_id, session_id, run_id, seq);
+        CREATE TABLE IF NOT EXISTS event_sequences (
             tenant_id TEXT NOT NULL,
             user_id TEXT NOT NULL,
             agent_id TEXT NOT NULL,
             session_id TEXT NOT NULL,
             run_id TEXT NOT NULL,
-            event_id TEXT NOT NULL,
-            entity TEXT NOT NULL,
-            PRIMARY KEY (
-                tenant_id,
-                user_id,
-                agent_id,
-                session_id,
-                run_id,
-                event_id,
-                entity
-            )
+            last_seq BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
         );
-        CREATE INDEX IF NOT EXISTS event_entities_scope_entity
-            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity);
-        CREATE INDEX IF NOT EXISTS event_entities_scope_event
-            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, event_id);
 
         CREATE TABLE IF NOT EXISTS wm_state (
             tenant_id TEXT NOT NULL,
@@ -1061,11 +2444,27 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             sources TEXT NOT NULL,
             scope_level TEXT NOT NULL,
             notes TEXT NOT NULL,
+            pinned BOOLEAN NOT NULL DEFAULT FALSE,
+            lang TEXT,
             PRIMARY KEY (tenant_id, user_id, agent_id, fact_id)
         );
         CREATE INDEX IF NOT EXISTS facts_scope_status
             ON facts (tenant_id, user_id, agent_id, status);
 
+        CREATE TABLE IF NOT EXISTS fact_versions (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            fact_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            fact_key TEXT NOT NULL,
+            fact_json TEXT NOT NULL,
+            recorded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, fact_id, version)
+        );
+        CREATE INDEX IF NOT EXISTS fact_versions_scope_key
+            ON fact_versions (tenant_id, user_id, agent_id, fact_key, recorded_at);
+
         CREATE TABLE IF NOT EXISTS episodes (
             tenant_id TEXT NOT NULL,
             user_id TEXT NOT NULL,
@@ -1080,35 +2479,11 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             sources TEXT NOT NULL,
             compression_level TEXT NOT NULL,
             recency_score DOUBLE PRECISION,
+            lang TEXT,
             PRIMARY KEY (tenant_id, user_id, agent_id, episode_id)
         );
         CREATE INDEX IF NOT EXISTS episodes_scope_start
             ON episodes (tenant_id, user_id, agent_id, start_ts);
-        CREATE TABLE IF NOT EXISTS episode_tags (
-            tenant_id TEXT NOT NULL,
-            user_id TEXT NOT NULL,
-            agent_id TEXT NOT NULL,
-            episode_id TEXT NOT NULL,
-            tag TEXT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, tag)
-        );
-        CREATE INDEX IF NOT EXISTS episode_tags_scope_tag
-            ON episode_tags (tenant_id, user_id, agent_id, tag);
-        CREATE INDEX IF NOT EXISTS episode_tags_scope_episode
-            ON episode_tags (tenant_id, user_id, agent_id, episode_id);
-
-        CREATE TABLE IF NOT EXISTS episode_entities (
-            tenant_id TEXT NOT NULL,
-            user_id TEXT NOT NULL,
-            agent_id TEXT NOT NULL,
-            episode_id TEXT NOT NULL,
-            entity TEXT NOT NULL,
-            PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, entity)
-        );
-        CREATE INDEX IF NOT EXISTS episode_entities_scope_entity
-            ON episode_entities (tenant_id, user_id, agent_id, entity);
-        CREATE INDEX IF NOT EXISTS episode_entities_scope_episode
-            ON episode_entities (tenant_id, user_id, agent_id, episode_id);
 
         CREATE TABLE IF NOT EXISTS procedures (
             tenant_id TEXT NOT NULL,
@@ -1125,6 +2500,25 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
         CREATE INDEX IF NOT EXISTS procedures_scope_task
             ON procedures (tenant_id, user_id, agent_id, task_type);
 
+        CREATE TABLE IF NOT EXISTS procedure_candidates (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            candidate_id TEXT NOT NULL,
+            task_type TEXT NOT NULL,
+            procedure_json TEXT NOT NULL,
+            source_episodes TEXT NOT NULL,
+            evidence TEXT NOT NULL,
+            status TEXT NOT NULL,
+            created_at BIGINT NOT NULL,
+            reviewed_at BIGINT,
+            reviewer TEXT NOT NULL,
+            review_note TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, candidate_id)
+        );
+        CREATE INDEX IF NOT EXISTS procedure_candidates_scope_status
+            ON procedure_candidates (tenant_id, user_id, agent_id, status, created_at);
+
         CREATE TABLE IF NOT EXISTS insights (
             tenant_id TEXT NOT NULL,
             user_id TEXT NOT NULL,
@@ -1140,6 +2534,7 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             tests_suggested TEXT NOT NULL,
             expires_at TEXT NOT NULL,
             sources TEXT NOT NULL,
+            parent_insight_id TEXT,
             PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, insight_id)
         );
         CREATE INDEX IF NOT EXISTS insights_scope_state
@@ -1153,15 +2548,130 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
             session_id TEXT NOT NULL,
             run_id TEXT NOT NULL,
             ts BIGINT NOT NULL,
-            packet_json TEXT NOT NULL
+            packet_json TEXT,
+            packet_zstd BYTEA
         );
         CREATE INDEX IF NOT EXISTS context_builds_scope_ts
             ON context_builds (tenant_id, user_id, agent_id, session_id, run_id, ts);
+        CREATE TABLE IF NOT EXISTS changelog (
+            seq BIGSERIAL PRIMARY KEY,
+            ts BIGINT NOT NULL,
+            op TEXT NOT NULL,
+            correlation_id TEXT
+        );
+        CREATE TABLE IF NOT EXISTS sync_cursors (
+            name TEXT PRIMARY KEY,
+            seq BIGINT NOT NULL,
+            updated_at BIGINT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS suppressions (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            item_kind TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            suppressed_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id)
+        );
+        CREATE TABLE IF NOT EXISTS run_outcomes (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            score DOUBLE PRECISION,
+            error_summary TEXT NOT NULL,
+            duration_ms BIGINT NOT NULL,
+            recorded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+        );
+        CREATE TABLE IF NOT EXISTS source_credibility (
+            tenant_id TEXT PRIMARY KEY,
+            credibility_json TEXT NOT NULL,
+            updated_at BIGINT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS memory_embeddings (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            item_kind TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            model TEXT NOT NULL,
+            vector_json TEXT NOT NULL,
+            digest TEXT NOT NULL,
+            embedded_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id, model)
+        );
+        CREATE TABLE IF NOT EXISTS user_locales (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            locale_json TEXT NOT NULL,
+            updated_at BIGINT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id)
+        );
+        CREATE TABLE IF NOT EXISTS tombstones (
+            id BIGSERIAL PRIMARY KEY,
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            item_kind TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            record_json TEXT NOT NULL,
+            deleted_at BIGINT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS tombstones_scope
+            ON tombstones (tenant_id, user_id, agent_id, id);
         ",
     )
     .map_err(map_pg_err)?;
+    // A database this role may not alter keeps working without the index tables.
+    if let Err(err) = ensure_optional_tables(conn) {
+        warn!("could not create optional index tables: {}", err);
+    }
+    // Text search works without these indexes, only slower.
+    if let Err(err) = ensure_text_index(conn) {
+        warn!("could not create full-text indexes: {}", err);
+    }
+    let optional = detect_optional_tables(conn)?;
+
+    if (1..3).contains(&current) {
+        conn.batch_execute("ALTER TABLE insights ADD COLUMN IF NOT EXISTS parent_insight_id TEXT")
+            .map_err(map_pg_err)?;
+    }
+    if (1..4).contains(&current) {
+        conn.batch_execute(
+            "ALTER TABLE facts ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .map_err(map_pg_err)?;
+    }
+    if (1..5).contains(&current) && optional.episode_index {
+        backfill_episode_index(conn)?;
+    }
+    if (1..6).contains(&current) {
+        conn.batch_execute(
+            "ALTER TABLE context_builds ADD COLUMN IF NOT EXISTS packet_zstd BYTEA;
+             ALTER TABLE context_builds ALTER COLUMN packet_json DROP NOT NULL;",
+        )
+        .map_err(map_pg_err)?;
+    }
+    if (1..7).contains(&current) {
+        conn.batch_execute(
+            "ALTER TABLE events ADD COLUMN IF NOT EXISTS lang TEXT;
+             ALTER TABLE facts ADD COLUMN IF NOT EXISTS lang TEXT;
+             ALTER TABLE episodes ADD COLUMN IF NOT EXISTS lang TEXT;",
+        )
+        .map_err(map_pg_err)?;
+    }
+    if (1..8).contains(&current) {
+        conn.batch_execute("ALTER TABLE changelog ADD COLUMN IF NOT EXISTS correlation_id TEXT")
+            .map_err(map_pg_err)?;
+    }
 
-    if current == 0 {
+    if current < SCHEMA_VERSION {
         conn.execute(
             "INSERT INTO schema_migrations (version, applied_at) VALUES ($1,$2)",
             &[&SCHEMA_VERSION, &to_millis(Utc::now())],
@@ -1169,62 +2679,493 @@ fn ensure_schema(conn: &mut Client) -> StoreResult<()> {
         .map_err(map_pg_err)?;
     }
 
+    Ok(optional)
+}
+
+fn ensure_text_index(conn: &mut Client) -> StoreResult<()> {
+    conn.batch_execute(&format!(
+        "CREATE INDEX IF NOT EXISTS events_text ON events USING GIN ({});
+         CREATE INDEX IF NOT EXISTS episodes_text ON episodes USING GIN ({});",
+        EVENT_TEXT, EPISODE_TEXT
+    ))
+    .map_err(map_pg_err)?;
     Ok(())
 }
 
-struct PgParams {
-    values: Vec<Box<dyn ToSql + Sync>>,
+fn ensure_optional_tables(conn: &mut Client) -> StoreResult<()> {
+    conn.batch_execute(
+        "
+        CREATE TABLE IF NOT EXISTS event_tags (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            event_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (
+                tenant_id,
+                user_id,
+                agent_id,
+                session_id,
+                run_id,
+                event_id,
+                tag
+            )
+        );
+        CREATE INDEX IF NOT EXISTS event_tags_scope_tag
+            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag);
+        CREATE INDEX IF NOT EXISTS event_tags_scope_event
+            ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, event_id);
+
+        CREATE TABLE IF NOT EXISTS event_entities (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            event_id TEXT NOT NULL,
+            entity TEXT NOT NULL,
+            PRIMARY KEY (
+                tenant_id,
+                user_id,
+                agent_id,
+                session_id,
+                run_id,
+                event_id,
+                entity
+            )
+        );
+        CREATE INDEX IF NOT EXISTS event_entities_scope_entity
+            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity);
+        CREATE INDEX IF NOT EXISTS event_entities_scope_event
+            ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, event_id);
+
+        CREATE TABLE IF NOT EXISTS episode_tags (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            episode_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, tag)
+        );
+        CREATE INDEX IF NOT EXISTS episode_tags_scope_tag
+            ON episode_tags (tenant_id, user_id, agent_id, tag);
+        CREATE INDEX IF NOT EXISTS episode_tags_scope_episode
+            ON episode_tags (tenant_id, user_id, agent_id, episode_id);
+
+        CREATE TABLE IF NOT EXISTS episode_entities (
+            tenant_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            agent_id TEXT NOT NULL,
+            episode_id TEXT NOT NULL,
+            entity TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, entity)
+        );
+        CREATE INDEX IF NOT EXISTS episode_entities_scope_entity
+            ON episode_entities (tenant_id, user_id, agent_id, entity);
+        CREATE INDEX IF NOT EXISTS episode_entities_scope_episode
+            ON episode_entities (tenant_id, user_id, agent_id, episode_id);
+        ",
+    )
+    .map_err(map_pg_err)
+}
+
+fn detect_optional_tables(conn: &mut Client) -> StoreResult<OptionalTables> {
+    let rows = conn
+        .query(
+            "SELECT table_name::text FROM information_schema.tables
+             WHERE table_schema = current_schema() AND table_name = ANY($1)",
+            &[&OptionalTables::NAMES.to_vec()],
+        )
+        .map_err(map_pg_err)?;
+    let present: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+    Ok(OptionalTables::from_present(&present))
+}
+
+struct PgParams {
+    values: Vec<Box<dyn ToSql + Sync>>,
+}
+
+impl PgParams {
+    fn new() -> Self {
+        Self { values: Vec::new() }
+    }
+
+    fn add<T: ToSql + Sync + 'static>(&mut self, value: T) -> String {
+        self.values.push(Box::new(value));
+        format!("${}", self.values.len())
+    }
+
+    fn refs(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.values
+            .iter()
+            .map(|value| &**value as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+fn encode_json<T: Serialize>(value: &T) -> StoreResult<String> {
+    serde_json::to_string(value).map_err(|err| StoreError::InvalidInput(err.to_string()))
+}
+
+fn decode_json<T: DeserializeOwned>(value: &str) -> StoreResult<T> {
+    serde_json::from_str(value).map_err(|err| StoreError::InvalidInput(err.to_string()))
+}
+
+fn compress_packet(packet_json: &str) -> StoreResult<Vec<u8>> {
+    zstd::encode_all(packet_json.as_bytes(), PACKET_ZSTD_LEVEL)
+        .map_err(|err| StoreError::Storage(err.to_string()))
+}
+
+/// The packet JSON of a context build row, decompressing only rows written compressed.
+fn packet_json(packet_json: Option<String>, packet_zstd: Option<Vec<u8>>) -> StoreResult<String> {
+    match (packet_json, packet_zstd) {
+        (Some(packet_json), _) => Ok(packet_json),
+        (None, Some(packet_zstd)) => {
+            let packet_json = zstd::decode_all(packet_zstd.as_slice())
+                .map_err(|err| StoreError::Storage(err.to_string()))?;
+            String::from_utf8(packet_json).map_err(|err| StoreError::Storage(err.to_string()))
+        }
+        (None, None) => Err(StoreError::Storage(
+            "context build row has no packet".to_string(),
+        )),
+    }
+}
+
+fn to_millis(ts: DateTime<Utc>) -> i64 {
+    ts.timestamp_millis()
+}
+
+fn from_millis(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .unwrap_or_else(|| Utc.timestamp_millis_opt(0).single().unwrap())
+}
+
+fn unique_values(values: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for value in values {
+        if seen.insert(value.clone()) {
+            out.push(value.clone());
+        }
+    }
+    out
+}
+
+/// Applies `patch` to the run's stored working state, comparing and swapping when the
+/// patch expects a state version.
+fn patch_working_state_row<C: GenericClient>(
+    conn: &mut C,
+    scope: &Scope,
+    patch: WorkingStatePatch,
+) -> StoreResult<WorkingState> {
+    let stored = working_state_json(conn, scope)?;
+    let current = stored.as_deref().map(decode_json).transpose()?.unwrap_or_default();
+    check_state_version(&current, &patch)?;
+    let expected = patch.expected_state_version;
+    let next = apply_working_state_patch(&current, patch);
+    let state_json = encode_json(&next)?;
+    let updated_at = to_millis(Utc::now());
+    let params: [&(dyn ToSql + Sync); 7] = [
+        &scope.tenant_id,
+        &scope.user_id,
+        &scope.agent_id,
+        &scope.session_id,
+        &scope.run_id,
+        &state_json,
+        &updated_at,
+    ];
+    let Some(expected) = expected else {
+        conn.execute(
+            "INSERT INTO wm_state (
+                tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+             ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+             ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
+             DO UPDATE SET state_json=excluded.state_json, updated_at=excluded.updated_at",
+            &params,
+        )
+        .map_err(map_pg_err)?;
+        return Ok(next);
+    };
+    // Compare and swap: the write only lands on the state that was read.
+    let written = match &stored {
+        Some(stored) if *stored == state_json => 1,
+        Some(stored) => conn
+            .execute(
+                "UPDATE wm_state SET state_json=$6, updated_at=$7
+                 WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4
+                   AND run_id=$5 AND state_json=$8",
+                &[&params[..], &[stored]].concat(),
+            )
+            .map_err(map_pg_err)?,
+        None => conn
+            .execute(
+                "INSERT INTO wm_state (
+                    tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+                 ) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                 ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id) DO NOTHING",
+                &params,
+            )
+            .map_err(map_pg_err)?,
+    };
+    if written == 0 {
+        let stored = working_state_json(conn, scope)?;
+        let stored = stored.as_deref().map(decode_json).transpose()?;
+        return Err(state_version_conflict(expected, stored));
+    }
+    Ok(next)
+}
+
+fn working_state_json<C: GenericClient>(
+    conn: &mut C,
+    scope: &Scope,
+) -> StoreResult<Option<String>> {
+    let row = conn
+        .query_opt(
+            "SELECT state_json FROM wm_state
+             WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND session_id=$4 AND run_id=$5",
+            &[
+                &scope.tenant_id,
+                &scope.user_id,
+                &scope.agent_id,
+                &scope.session_id,
+                &scope.run_id,
+            ],
+        )
+        .map_err(map_pg_err)?;
+    Ok(row.map(|row| row.get(0)))
+}
+
+fn next_event_seq<C: GenericClient>(conn: &mut C, scope: &Scope) -> StoreResult<i64> {
+    let row = conn
+        .query_one(
+            "INSERT INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+             VALUES ($1,$2,$3,$4,$5,1)
+             ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
+             DO UPDATE SET last_seq = event_sequences.last_seq + 1
+             RETURNING last_seq",
+            &[
+                &scope.tenant_id,
+                &scope.user_id,
+                &scope.agent_id,
+                &scope.session_id,
+                &scope.run_id,
+            ],
+        )
+        .map_err(map_pg_err)?;
+    Ok(row.get(0))
+}
+
+fn episode_from_row(row: &postgres::Row) -> StoreResult<Episode> {
+    let highlights: String = row.get(4);
+    let tags: String = row.get(5);
+    let entities: String = row.get(6);
+    let sources: String = row.get(7);
+    let compression_level: String = row.get(8);
+    Ok(Episode {
+        episode_id: row.get(0),
+        time_range: engram_types::TimeRange {
+            start: from_millis(row.get(1)),
+            end: row.get::<_, Option<i64>>(2).map(from_millis),
+        },
+        summary: row.get(3),
+        highlights: decode_json(&highlights)?,
+        tags: decode_json(&tags)?,
+        entities: decode_json(&entities)?,
+        sources: decode_json(&sources)?,
+        compression_level: parse_compression_level(&compression_level)?,
+        recency_score: row.get(9),
+        lang: row.get(10),
+    })
 }
 
-impl PgParams {
-    fn new() -> Self {
-        Self { values: Vec::new() }
-    }
+fn fact_from_row(row: &postgres::Row) -> StoreResult<Fact> {
+    let value_json: String = row.get(2);
+    let status: String = row.get(3);
+    let sources: String = row.get(7);
+    let scope_level: String = row.get(8);
+    Ok(Fact {
+        fact_id: row.get(0),
+        fact_key: row.get(1),
+        value: decode_json(&value_json)?,
+        status: parse_fact_status(&status)?,
+        validity: engram_types::Validity {
+            valid_from: row.get::<_, Option<i64>>(4).map(from_millis),
+            valid_to: row.get::<_, Option<i64>>(5).map(from_millis),
+        },
+        confidence: row.get(6),
+        sources: decode_json(&sources)?,
+        scope_level: parse_scope_level(&scope_level)?,
+        notes: row.get(9),
+        pinned: row.get(10),
+        lang: row.get(11),
+    })
+}
 
-    fn add<T: ToSql + Sync + 'static>(&mut self, value: T) -> String {
-        self.values.push(Box::new(value));
-        format!("${}", self.values.len())
-    }
+fn procedure_from_row(row: &postgres::Row) -> StoreResult<Procedure> {
+    let content: String = row.get(2);
+    let sources: String = row.get(4);
+    let applicability: String = row.get(5);
+    Ok(Procedure {
+        procedure_id: row.get(0),
+        task_type: row.get(1),
+        content: decode_json(&content)?,
+        priority: row.get(3),
+        sources: decode_json(&sources)?,
+        applicability: decode_json(&applicability)?,
+    })
+}
 
-    fn refs(&self) -> Vec<&(dyn ToSql + Sync)> {
-        self.values
-            .iter()
-            .map(|value| &**value as &(dyn ToSql + Sync))
-            .collect()
-    }
+fn insight_from_row(row: &postgres::Row) -> StoreResult<InsightItem> {
+    let kind: String = row.get(1);
+    let trigger: String = row.get(3);
+    let validation_state: String = row.get(5);
+    let tests: String = row.get(6);
+    let sources: String = row.get(8);
+    Ok(InsightItem {
+        id: row.get(0),
+        kind: parse_insight_type(&kind)?,
+        statement: row.get(2),
+        trigger: parse_insight_trigger(&trigger)?,
+        confidence: row.get(4),
+        validation_state: parse_validation_state(&validation_state)?,
+        tests_suggested: decode_json(&tests)?,
+        expires_at: row.get(7),
+        sources: decode_json(&sources)?,
+        parent_insight_id: row.get(9),
+    })
 }
 
-fn encode_json<T: Serialize>(value: &T) -> StoreResult<String> {
-    Ok(serde_json::to_string(value).map_err(|err| StoreError::InvalidInput(err.to_string()))?)
+/// Tombstones of the `kind` records that `filter` matches, as they are now.
+fn load_tombstones<C: GenericClient>(
+    conn: &mut C,
+    scope: &Scope,
+    kind: RecordKind,
+    filter: &str,
+    params: &PgParams,
+) -> StoreResult<Vec<Tombstone>> {
+    let columns = match kind {
+        RecordKind::Event => {
+            "event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind, payload, tags,
+             entities, seq, lang"
+        }
+        RecordKind::Fact => {
+            "fact_id, fact_key, value_json, status, valid_from, valid_to, confidence, sources,
+             scope_level, notes, pinned, lang"
+        }
+        RecordKind::Episode => {
+            "episode_id, start_ts, end_ts, summary, highlights, tags, entities, sources,
+             compression_level, recency_score, lang"
+        }
+        RecordKind::Procedure => {
+            "procedure_id, task_type, content_json, priority, sources, applicability"
+        }
+        RecordKind::Insight => {
+            "insight_id, kind, statement, trigger, confidence, validation_state, tests_suggested,
+             expires_at, sources, parent_insight_id"
+        }
+    };
+    let (table, _) = kind.table();
+    let rows = conn
+        .query(&format!("SELECT {} FROM {} WHERE {}", columns, table, filter), &params.refs())
+        .map_err(map_pg_err)?;
+    let records = match kind {
+        RecordKind::Event => record_values(&rows, event_from_row, |event| &event.event_id)?,
+        RecordKind::Fact => record_values(&rows, fact_from_row, |fact| &fact.fact_id)?,
+        RecordKind::Episode => {
+            record_values(&rows, episode_from_row, |episode| &episode.episode_id)?
+        }
+        RecordKind::Procedure => {
+            record_values(&rows, procedure_from_row, |procedure| &procedure.procedure_id)?
+        }
+        RecordKind::Insight => record_values(&rows, insight_from_row, |insight| &insight.id)?,
+    };
+    let deleted_at = Utc::now();
+    Ok(records
+        .into_iter()
+        .map(|(id, record)| Tombstone {
+            scope: kind.record_scope(scope),
+            item: RecordRef::new(kind, id),
+            record,
+            deleted_at,
+        })
+        .collect())
 }
 
-fn decode_json<T: DeserializeOwned>(value: &str) -> StoreResult<T> {
-    Ok(serde_json::from_str(value).map_err(|err| StoreError::InvalidInput(err.to_string()))?)
+/// Ids and JSON of the records in `rows`.
+fn record_values<T: Serialize>(
+    rows: &[postgres::Row],
+    from_row: fn(&postgres::Row) -> StoreResult<T>,
+    id: fn(&T) -> &str,
+) -> StoreResult<Vec<(String, serde_json::Value)>> {
+    let mut records = Vec::new();
+    for row in rows {
+        let record = from_row(row)?;
+        records.push((id(&record).to_string(), serde_json::to_value(&record)?));
+    }
+    Ok(records)
 }
 
-fn to_millis(ts: DateTime<Utc>) -> i64 {
-    ts.timestamp_millis()
+fn insert_tombstone<C: GenericClient>(conn: &mut C, tombstone: &Tombstone) -> StoreResult<()> {
+    let scope = &tombstone.scope;
+    conn.execute(
+        "INSERT INTO tombstones (
+            tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id, record_json,
+            deleted_at
+         ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)",
+        &[
+            &scope.tenant_id,
+            &scope.user_id,
+            &scope.agent_id,
+            &scope.session_id,
+            &scope.run_id,
+            &tombstone.item.kind.as_str(),
+            &tombstone.item.id,
+            &encode_json(&tombstone.record)?,
+            &to_millis(tombstone.deleted_at),
+        ],
+    )
+    .map_err(map_pg_err)?;
+    Ok(())
 }
 
-fn from_millis(millis: i64) -> DateTime<Utc> {
-    Utc.timestamp_millis_opt(millis)
-        .single()
-        .unwrap_or_else(|| Utc.timestamp_millis_opt(0).single().unwrap())
+/// A `tsquery` over `terms` matching any of them as a phrase, with one parameter per term.
+fn text_query(params: &mut PgParams, terms: &[&str]) -> String {
+    let phrases: Vec<String> = terms
+        .iter()
+        .map(|term| format!("phraseto_tsquery('simple', {})", params.add(term.to_string())))
+        .collect();
+    format!("({})", phrases.join(" || "))
 }
 
-fn unique_values(values: &[String]) -> Vec<String> {
-    let mut seen = HashSet::new();
-    let mut out = Vec::new();
-    for value in values {
-        if seen.insert(value.clone()) {
-            out.push(value.clone());
-        }
-    }
-    out
+fn event_from_row(row: &postgres::Row) -> StoreResult<Event> {
+    let kind: String = row.get(7);
+    let payload: String = row.get(8);
+    let tags: String = row.get(9);
+    let entities: String = row.get(10);
+    Ok(Event {
+        event_id: row.get(0),
+        scope: Scope {
+            tenant_id: row.get(1),
+            user_id: row.get(2),
+            agent_id: row.get(3),
+            session_id: row.get(4),
+            run_id: row.get(5),
+        },
+        ts: from_millis(row.get(6)),
+        kind: kind.parse()?,
+        payload: decode_json(&payload)?,
+        tags: decode_json(&tags)?,
+        entities: decode_json(&entities)?,
+        seq: row.get::<_, i64>(11) as u64,
+        lang: row.get(12),
+    })
 }
 
-fn insert_event_tags(
-    conn: &mut Client,
+fn insert_event_tags<C: GenericClient>(
+    conn: &mut C,
     scope: &Scope,
     event_id: &str,
     tags: &[String],
@@ -1291,8 +3232,98 @@ fn insert_event_tags(
     Ok(())
 }
 
-fn insert_episode_tags(
-    conn: &mut Client,
+fn upsert_fact_row<C: GenericClient>(conn: &mut C, scope: &Scope, fact: &Fact) -> StoreResult<()> {
+    conn.execute(
+        "INSERT INTO facts (
+            tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
+            valid_from, valid_to, confidence, sources, scope_level, notes, pinned, lang
+         ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)
+         ON CONFLICT (tenant_id, user_id, agent_id, fact_id)
+         DO UPDATE SET fact_key=excluded.fact_key,
+                       value_json=excluded.value_json,
+                       status=excluded.status,
+                       valid_from=excluded.valid_from,
+                       valid_to=excluded.valid_to,
+                       confidence=excluded.confidence,
+                       sources=excluded.sources,
+                       scope_level=excluded.scope_level,
+                       notes=excluded.notes,
+                       pinned=excluded.pinned,
+                       lang=excluded.lang",
+        &[
+            &scope.tenant_id,
+            &scope.user_id,
+            &scope.agent_id,
+            &fact.fact_id,
+            &fact.fact_key,
+            &encode_json(&fact.value)?,
+            &fact_status_to_str(&fact.status),
+            &fact.validity.valid_from.map(to_millis),
+            &fact.validity.valid_to.map(to_millis),
+            &fact.confidence,
+            &encode_json(&fact.sources)?,
+            &scope_level_to_str(&fact.scope_level),
+            &fact.notes,
+            &fact.pinned,
+            &fact.lang,
+        ],
+    )
+    .map_err(map_pg_err)?;
+    record_fact_version(conn, scope, fact)
+}
+
+/// Adds the version a write of `fact` makes to `fact_versions`, unless it repeats
+/// the fact's last one. The fact's upserted row is locked until commit, so
+/// concurrent writes of it number their versions in turn.
+fn record_fact_version<C: GenericClient>(
+    conn: &mut C,
+    scope: &Scope,
+    fact: &Fact,
+) -> StoreResult<()> {
+    let latest = conn
+        .query_opt(
+            "SELECT version, recorded_at, fact_json FROM fact_versions
+             WHERE tenant_id=$1 AND user_id=$2 AND agent_id=$3 AND fact_id=$4
+             ORDER BY version DESC LIMIT 1",
+            &[&scope.tenant_id, &scope.user_id, &scope.agent_id, &fact.fact_id],
+        )
+        .map_err(map_pg_err)?
+        .map(|row| fact_version_from_row(&row))
+        .transpose()?;
+    let Some(version) = next_fact_version(latest.as_ref(), fact, Utc::now()) else {
+        return Ok(());
+    };
+    conn.execute(
+        "INSERT INTO fact_versions (
+            tenant_id, user_id, agent_id, fact_id, version, fact_key, fact_json, recorded_at
+         ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)",
+        &[
+            &scope.tenant_id,
+            &scope.user_id,
+            &scope.agent_id,
+            &fact.fact_id,
+            &(version.version as i32),
+            &fact.fact_key,
+            &encode_json(&version.fact)?,
+            &to_millis(version.recorded_at),
+        ],
+    )
+    .map_err(map_pg_err)?;
+    Ok(())
+}
+
+fn fact_version_from_row(row: &postgres::Row) -> StoreResult<FactVersion> {
+    let version: i32 = row.get(0);
+    let fact_json: String = row.get(2);
+    Ok(FactVersion {
+        version: version as u32,
+        recorded_at: from_millis(row.get(1)),
+        fact: decode_json(&fact_json)?,
+    })
+}
+
+fn insert_episode_tags<C: GenericClient>(
+    conn: &mut C,
     scope: &Scope,
     episode_id: &str,
     tags: &[String],
@@ -1355,46 +3386,22 @@ fn insert_episode_tags(
     Ok(())
 }
 
-fn episode_tags_present(conn: &mut Client, scope: &Scope) -> StoreResult<bool> {
-    let row = conn
-        .query_opt(
-            "SELECT 1 FROM episode_tags WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3 LIMIT 1",
-            &[&scope.tenant_id, &scope.user_id, &scope.agent_id],
-        )
-        .map_err(map_pg_err)?;
-    Ok(row.is_some())
-}
-
-fn episode_entities_present(conn: &mut Client, scope: &Scope) -> StoreResult<bool> {
-    let row = conn
-        .query_opt(
-            "SELECT 1 FROM episode_entities WHERE tenant_id = $1 AND user_id = $2 AND agent_id = $3 LIMIT 1",
-            &[&scope.tenant_id, &scope.user_id, &scope.agent_id],
-        )
-        .map_err(map_pg_err)?;
-    Ok(row.is_some())
-}
-
-fn event_kind_to_str(kind: &EventKind) -> &'static str {
-    match kind {
-        EventKind::Message => "message",
-        EventKind::ToolResult => "tool_result",
-        EventKind::StatePatch => "state_patch",
-        EventKind::System => "system",
-    }
-}
-
-fn parse_event_kind(value: &str) -> StoreResult<EventKind> {
-    match value {
-        "message" => Ok(EventKind::Message),
-        "tool_result" => Ok(EventKind::ToolResult),
-        "state_patch" => Ok(EventKind::StatePatch),
-        "system" => Ok(EventKind::System),
-        _ => Err(StoreError::InvalidInput(format!(
-            "invalid event kind: {}",
-            value
-        ))),
-    }
+/// Indexes tags and entities of episodes written before every episode was indexed
+/// on insert, so tag and entity filters can always go through the index tables.
+fn backfill_episode_index(conn: &mut Client) -> StoreResult<()> {
+    conn.batch_execute(
+        "
+        INSERT INTO episode_tags (tenant_id, user_id, agent_id, episode_id, tag)
+            SELECT e.tenant_id, e.user_id, e.agent_id, e.episode_id, t.value
+            FROM episodes e, jsonb_array_elements_text(e.tags::jsonb) AS t(value)
+            ON CONFLICT DO NOTHING;
+        INSERT INTO episode_entities (tenant_id, user_id, agent_id, episode_id, entity)
+            SELECT e.tenant_id, e.user_id, e.agent_id, e.episode_id, x.value
+            FROM episodes e, jsonb_array_elements_text(e.entities::jsonb) AS x(value)
+            ON CONFLICT DO NOTHING;
+        ",
+    )
+    .map_err(map_pg_err)
 }
 
 fn fact_status_to_str(status: &FactStatus) -> &'static str {
@@ -1523,7 +3530,115 @@ fn parse_validation_state(value: &str) -> StoreResult<ValidationState> {
     }
 }
 
-fn map_pg_err(err: postgres::Error) -> StoreError {
+fn candidate_status_to_str(status: &CandidateStatus) -> &'static str {
+    match status {
+        CandidateStatus::Pending => "pending",
+        CandidateStatus::Approved => "approved",
+        CandidateStatus::Rejected => "rejected",
+    }
+}
+
+fn parse_candidate_status(value: &str) -> StoreResult<CandidateStatus> {
+    match value {
+        "pending" => Ok(CandidateStatus::Pending),
+        "approved" => Ok(CandidateStatus::Approved),
+        "rejected" => Ok(CandidateStatus::Rejected),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid candidate status: {}",
+            value
+        ))),
+    }
+}
+
+fn memory_kind_to_str(kind: &MemoryKind) -> &'static str {
+    match kind {
+        MemoryKind::Fact => "fact",
+        MemoryKind::Episode => "episode",
+    }
+}
+
+fn parse_record_kind(value: &str) -> StoreResult<RecordKind> {
+    RecordKind::parse(value)
+        .ok_or_else(|| StoreError::InvalidInput(format!("invalid record kind: {}", value)))
+}
+
+fn parse_memory_kind(value: &str) -> StoreResult<MemoryKind> {
+    match value {
+        "fact" => Ok(MemoryKind::Fact),
+        "episode" => Ok(MemoryKind::Episode),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid memory kind: {}",
+            value
+        ))),
+    }
+}
+
+fn run_status_to_str(status: &RunStatus) -> &'static str {
+    match status {
+        RunStatus::Success => "success",
+        RunStatus::Partial => "partial",
+        RunStatus::Failure => "failure",
+        RunStatus::Aborted => "aborted",
+    }
+}
+
+fn parse_run_status(value: &str) -> StoreResult<RunStatus> {
+    match value {
+        "success" => Ok(RunStatus::Success),
+        "partial" => Ok(RunStatus::Partial),
+        "failure" => Ok(RunStatus::Failure),
+        "aborted" => Ok(RunStatus::Aborted),
+        _ => Err(StoreError::InvalidInput(format!(
+            "invalid run status: {}",
+            value
+        ))),
+    }
+}
+
+fn row_to_procedure_candidate(row: &postgres::Row) -> StoreResult<ProcedureCandidate> {
+    let procedure: String = row.get(1);
+    let source_episodes: String = row.get(2);
+    let evidence: String = row.get(3);
+    let status: String = row.get(4);
+    let created_at: i64 = row.get(5);
+    let reviewed_at: Option<i64> = row.get(6);
+    Ok(ProcedureCandidate {
+        candidate_id: row.get(0),
+        procedure: decode_json(&procedure)?,
+        source_episodes: decode_json(&source_episodes)?,
+        evidence: decode_json(&evidence)?,
+        status: parse_candidate_status(&status)?,
+        created_at: from_millis(created_at),
+        reviewed_at: reviewed_at.map(from_millis),
+        reviewer: row.get(7),
+        review_note: row.get(8),
+    })
+}
+
+fn pg_advisory_unlock(
+    (mut conn, key): (PooledConnection<PostgresConnectionManager<NoTls>>, i64),
+) -> StoreResult<()> {
+    conn.query_one("SELECT pg_advisory_unlock($1)", &[&key])
+        .map_err(map_pg_err)?;
+    Ok(())
+}
+
+pub(crate) fn map_pg_err(err: postgres::Error) -> StoreError {
+    if err.code() == Some(&SqlState::QUERY_CANCELED) {
+        return StoreError::Timeout(err.to_string());
+    }
+    // Class 08 is connection exceptions; 57P01-57P03 are the server shutting down or
+    // not yet accepting connections.
+    let shutting_down =
+        [SqlState::ADMIN_SHUTDOWN, SqlState::CRASH_SHUTDOWN, SqlState::CANNOT_CONNECT_NOW];
+    let disconnected = err.is_closed()
+        || err
+            .code()
+            .is_some_and(|code| code.code().starts_with("08") || shutting_down.contains(code))
+        || std::error::Error::source(&err).is_some_and(|source| source.is::<std::io::Error>());
+    if disconnected {
+        return StoreError::Unavailable(err.to_string());
+    }
     StoreError::Storage(err.to_string())
 }
 
@@ -1581,6 +3696,28 @@ fn ensure_postgres_database(dsn: &str, db_name: &str) -> StoreResult<()> {
     Ok(())
 }
 
+/// Notification channel of a tenant: `engram_<tenant_id>`, cut to the identifier
+/// length limit.
+pub fn notify_channel(tenant_id: &str) -> String {
+    let mut channel = String::from("engram_");
+    for ch in tenant_id.chars() {
+        if channel.len() + ch.len_utf8() > MAX_CHANNEL_LEN {
+            break;
+        }
+        channel.push(ch);
+    }
+    channel
+}
+
+fn is_plain_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+        && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+        && value.len() <= MAX_SCHEMA_LEN
+}
+
 fn quote_pg_identifier(value: &str) -> String {
     let mut quoted = String::from("\"");
     for ch in value.chars() {
@@ -1597,6 +3734,7 @@ fn quote_pg_identifier(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::EventKind;
     use engram_types::{
         Budget, BudgetReport, Insight, JsonMap, LongTerm, MemoryPacket, Meta, Purpose, ShortTerm,
         Validity,
@@ -1635,13 +3773,17 @@ mod tests {
                 budget: Budget {
                     max_tokens: 512,
                     per_section: JsonMap::new(),
+                    model: None,
                 },
                 policy_id: "default".to_string(),
+                timezone: None,
+                locale: None,
             },
             short_term: ShortTerm::default(),
             long_term: LongTerm::default(),
             insight: Insight::default(),
             citations: Vec::new(),
+            external_context: Vec::new(),
             budget_report: BudgetReport::default(),
             explain: JsonMap::new(),
         }
@@ -1659,6 +3801,7 @@ mod tests {
 
         let store = PostgresStore::new(&dsn).unwrap();
         let scope = sample_scope();
+        let mut listener = store.listen(&scope.tenant_id).unwrap();
 
         let event_id = unique_id("event");
         store
@@ -1667,9 +3810,11 @@ mod tests {
                 scope: scope.clone(),
                 ts: Utc::now(),
                 kind: EventKind::Message,
-                payload: json!({ "role": "user", "content": "hi" }),
+                payload: json!({ "role": "user", "content": "hi, where is my refund?" }),
                 tags: vec!["alpha".to_string()],
                 entities: vec!["entity1".to_string()],
+                seq: 0,
+                lang: Some("en".to_string()),
             })
             .unwrap();
 
@@ -1678,6 +3823,61 @@ mod tests {
             .unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].event_id, event_id);
+        let by_id = store
+            .get_events_by_ids(&scope, &[event_id.clone(), "missing".to_string()])
+            .unwrap();
+        assert_eq!(by_id.len(), 1);
+        assert_eq!(by_id[0].lang.as_deref(), Some("en"));
+        let tagged = store
+            .find_events(
+                &scope,
+                EventFilter {
+                    tags: vec!["alpha".to_string()],
+                    entities: vec!["entity1".to_string()],
+                    ..EventFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert!(store
+            .find_events(
+                &scope,
+                EventFilter {
+                    tags: vec!["beta".to_string()],
+                    ..EventFilter::default()
+                },
+            )
+            .unwrap()
+            .is_empty());
+        let found = store
+            .search_events(&scope, &TextQuery::new(["invoice", "my refund"]))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(store.search_events(&scope, &TextQuery::new(["refund my"])).unwrap().is_empty());
+        assert_eq!(events[0].seq, 1);
+        assert_eq!(store.get_events_since(&scope, 0, None).unwrap().len(), 1);
+        assert!(store.get_events_since(&scope, 1, None).unwrap().is_empty());
+        let page = store.list_events_page(&scope, None, 1).unwrap();
+        assert_eq!(page.events[0].event_id, events[0].event_id);
+        assert!(page.next_cursor.is_none());
+        let notification = listener.recv(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(notification.op, "append_event");
+        assert!(crate::scope_matches(&notification.scope, &scope));
+        let scopes = store.list_scopes(Some(&scope.tenant_id), None).unwrap();
+        assert!(scopes.iter().any(|listed| crate::scope_matches(listed, &scope)));
+
+        let change_seq = store
+            .append_change(crate::ChangeOp::UpdateStm {
+                scope: scope.clone(),
+                stm: StmState::default(),
+            })
+            .unwrap();
+        let changes = store.list_changes(change_seq - 1, Some(1)).unwrap();
+        assert_eq!(changes[0].seq, change_seq);
+        let activity = store
+            .tenant_activity(&scope.tenant_id, TimeRangeFilter::default())
+            .unwrap();
+        assert!(activity.iter().any(|user| user.user_id == scope.user_id));
 
         let state = store
             .patch_working_state(
@@ -1689,6 +3889,49 @@ mod tests {
             )
             .unwrap();
         assert_eq!(state.goal, "ship");
+        let states = store.list_working_states(&scope).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].run_id, scope.run_id);
+        assert_eq!(states[0].state.goal, "ship");
+        let stale = WorkingStatePatch {
+            goal: Some("review".to_string()),
+            expected_state_version: Some(state.state_version + 1),
+            ..WorkingStatePatch::default()
+        };
+        let err = store.patch_working_state(&scope, stale).unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { .. }));
+        let current = WorkingStatePatch {
+            goal: Some("review".to_string()),
+            expected_state_version: Some(state.state_version),
+            ..WorkingStatePatch::default()
+        };
+        let state = store.patch_working_state(&scope, current).unwrap();
+        assert_eq!(state.goal, "review");
+        let other_run = Scope {
+            run_id: unique_id("run"),
+            ..scope.clone()
+        };
+        let patch = |goal: &str, expected: Option<u32>| WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            expected_state_version: expected,
+            ..WorkingStatePatch::default()
+        };
+        let err = store
+            .patch_working_states(vec![
+                (other_run.clone(), patch("test", None)),
+                (scope.clone(), patch("ship", Some(state.state_version + 1))),
+            ])
+            .unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { .. }));
+        assert!(store.get_working_state(&other_run).unwrap().is_none());
+        let states = store
+            .patch_working_states(vec![
+                (other_run.clone(), patch("test", None)),
+                (scope.clone(), patch("review", Some(state.state_version))),
+            ])
+            .unwrap();
+        assert_eq!(states[0].goal, "test");
+        assert_eq!(store.list_working_states(&scope).unwrap().len(), 2);
 
         store
             .update_stm(
@@ -1714,6 +3957,8 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: Some("en".to_string()),
                 },
             )
             .unwrap();
@@ -1730,6 +3975,8 @@ mod tests {
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1744,6 +3991,62 @@ mod tests {
             )
             .unwrap();
         assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].lang.as_deref(), Some("en"));
+        crate::pin_fact(&store, &scope, &facts[0].fact_id).unwrap();
+        let pinned = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    pinned: Some(true),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert!(pinned[0].pinned);
+        let history = store.list_fact_history(&scope, "pref.color").unwrap();
+        let versions: Vec<_> = history.iter().map(|v| (v.version, v.fact.pinned)).collect();
+        assert_eq!(versions, [(1, false), (2, true)]);
+        assert_eq!(store.count_records(&scope, RecordKind::Fact).unwrap(), 2);
+        let tone = crate::set_preference(&store, &scope, "tone", json!("terse"), 0.8).unwrap();
+        let preferences = crate::get_preferences(&store, &scope).unwrap();
+        assert_eq!(preferences.len(), 1);
+        assert_eq!(preferences[0].fact_id, tone.fact_id);
+        let facts = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    preferences: Some(false),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert!(!facts.is_empty());
+        assert!(facts.iter().all(|fact| fact.fact_id != tone.fact_id));
+        let facts = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    keys: Some(vec!["old.pref".to_string()]),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert!(!facts.is_empty());
+        assert!(facts.iter().all(|fact| fact.fact_key == "old.pref"));
+
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: pinned[0].fact_id.clone(),
+        };
+        store.suppress_memory(&scope, item.clone(), "outdated").unwrap();
+        store.suppress_memory(&scope, item.clone(), "stop bringing this up").unwrap();
+        let suppressions = store.list_suppressions(&scope).unwrap();
+        assert_eq!(suppressions.len(), 1);
+        assert_eq!(suppressions[0].item, item);
+        assert_eq!(suppressions[0].reason, "stop bringing this up");
+        assert!(store.unsuppress_memory(&scope, &item).unwrap());
+        assert!(store.list_suppressions(&scope).unwrap().is_empty());
 
         store
             .append_episode(
@@ -1761,6 +4064,7 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: Some("en".to_string()),
                 },
             )
             .unwrap();
@@ -1780,6 +4084,7 @@ mod tests {
                     sources: vec![],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1794,6 +4099,48 @@ mod tests {
             )
             .unwrap();
         assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].lang.as_deref(), Some("en"));
+        let found = store.search_episodes(&scope, &TextQuery::new(["another", "h1"])).unwrap();
+        assert_eq!(found.len(), 2);
+
+        let mut duplicate = Episode::new("duplicate");
+        duplicate.episode_id = episodes[0].episode_id.clone();
+        assert!(store
+            .append_episodes_bulk(&scope, &[Episode::new("rolled back"), duplicate])
+            .is_err());
+        let all = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let evict = [MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episodes[0].episode_id.clone(),
+        }];
+        let embedding = MemoryEmbedding {
+            item: evict[0].clone(),
+            model: "test-model".to_string(),
+            vector: vec![0.5, -0.25],
+            digest: crate::text_digest(&episodes[0].summary),
+            embedded_at: from_millis(to_millis(Utc::now())),
+        };
+        store
+            .upsert_embeddings(&scope, vec![embedding.clone()])
+            .unwrap();
+        let embeddings = store.list_embeddings(&scope, "test-model").unwrap();
+        assert_eq!(embeddings, vec![embedding.clone()]);
+        assert_eq!(store.delete_embeddings(&scope, "other-model", &evict).unwrap(), 0);
+        assert_eq!(store.delete_embeddings(&scope, "test-model", &evict).unwrap(), 1);
+        assert!(store.list_embeddings(&scope, "test-model").unwrap().is_empty());
+        store.upsert_embeddings(&scope, vec![embedding]).unwrap();
+        assert_eq!(store.evict_memory(&scope, &evict).unwrap(), 1);
+        assert!(store.list_embeddings(&scope, "test-model").unwrap().is_empty());
+        let remaining = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_ne!(remaining[0].episode_id, evict[0].id);
+        let tagged = EpisodeFilter {
+            tags: vec!["alpha".to_string()],
+            ..EpisodeFilter::default()
+        };
+        assert!(store.list_episodes(&scope, tagged).unwrap().is_empty());
 
         store
             .upsert_procedure(
@@ -1813,6 +4160,45 @@ mod tests {
             .list_procedures(&scope, "generic", Some(5))
             .unwrap();
         assert_eq!(procedures.len(), 1);
+        let procedure_id = &procedures[0].procedure_id;
+        assert!(store.delete_procedure(&scope, procedure_id, DeleteMode::Soft).unwrap());
+        assert!(store.list_procedures(&scope, "generic", None).unwrap().is_empty());
+        let tombstones = store.list_tombstones(&scope, Some(1)).unwrap();
+        assert_eq!(tombstones[0].item, RecordRef::new(RecordKind::Procedure, procedure_id));
+        assert_eq!(tombstones[0].record["content"], json!({"steps": ["a", "b"]}));
+        assert!(!store.delete_procedure(&scope, procedure_id, DeleteMode::Hard).unwrap());
+
+        let candidate_id = unique_id("c1");
+        store
+            .upsert_procedure_candidate(
+                &scope,
+                ProcedureCandidate {
+                    candidate_id: candidate_id.clone(),
+                    procedure: procedures[0].clone(),
+                    source_episodes: vec!["ep1".to_string()],
+                    evidence: vec![],
+                    status: CandidateStatus::Pending,
+                    created_at: Utc::now(),
+                    reviewed_at: None,
+                    reviewer: String::new(),
+                    review_note: String::new(),
+                },
+            )
+            .unwrap();
+        let candidates = store
+            .list_procedure_candidates(
+                &scope,
+                ProcedureCandidateFilter {
+                    status: Some(vec![CandidateStatus::Pending]),
+                    ..ProcedureCandidateFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert!(store
+            .get_procedure_candidate(&scope, &candidate_id)
+            .unwrap()
+            .is_some());
 
         store
             .append_insight(
@@ -1827,6 +4213,7 @@ mod tests {
                     tests_suggested: vec![],
                     expires_at: "run_end".to_string(),
                     sources: vec![],
+                    parent_insight_id: None,
                 },
             )
             .unwrap();
@@ -1841,11 +4228,180 @@ mod tests {
             )
             .unwrap();
         assert_eq!(insights.len(), 1);
+        assert_eq!(store.expire_insights(&scope, "run_end").unwrap(), 1);
+        assert!(store
+            .list_insights(&scope, InsightFilter::default())
+            .unwrap()
+            .is_empty());
 
         store
             .write_context_build(&scope, sample_packet(scope.clone()))
             .unwrap();
         let builds = store.list_context_builds(&scope, None).unwrap();
         assert_eq!(builds.len(), 1);
+        let compressed = PostgresStore::new(&dsn).unwrap().with_compressed_packets();
+        compressed
+            .write_context_build(&scope, sample_packet(scope.clone()))
+            .unwrap();
+        let builds = compressed.list_context_builds(&scope, None).unwrap();
+        assert_eq!(builds.len(), 2);
+        assert!(builds.iter().all(|packet| packet.meta.scope.run_id == scope.run_id));
+        let summaries = compressed.list_context_build_summaries(&scope, Some(2)).unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.iter().all(|summary| summary.policy_id == builds[0].meta.policy_id));
+
+        let slow = crate::with_timeout(Duration::from_millis(20), || {
+            let context = SqlContext::new("sleep", "pg_catalog", &[]);
+            store.with_conn(context, |conn| {
+                conn.batch_execute("SELECT pg_sleep(1)").map_err(map_pg_err)
+            })
+        });
+        assert!(matches!(slow, Err(StoreError::Timeout(_))));
+        assert_eq!(store.list_context_builds(&scope, None).unwrap().len(), 2);
+        let namespaced = PostgresStore::in_schema(&dsn, "engram_ns_test").unwrap();
+        namespaced
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        assert_eq!(namespaced.get_events_since(&scope, 0, None).unwrap().len(), 1);
+        let context = SqlContext::new("count_tables", "information_schema.tables", &[]);
+        let tables: i64 = namespaced
+            .with_conn(context, |conn| {
+                let row = conn
+                    .query_one(
+                        "SELECT COUNT(*) FROM information_schema.tables
+                         WHERE table_schema = 'engram_ns_test' AND table_name = 'events'",
+                        &[],
+                    )
+                    .map_err(map_pg_err)?;
+                Ok(row.get(0))
+            })
+            .unwrap();
+        assert_eq!(tables, 1);
+        assert!(PostgresStore::in_schema(&dsn, "Engram; DROP").is_err());
+
+        let lease = store.acquire_lease(&scope, Duration::from_secs(60)).unwrap().unwrap();
+        assert!(namespaced.acquire_lease(&scope, Duration::from_secs(60)).unwrap().is_none());
+        assert!(store.release_lease(&lease).unwrap());
+        let taken = namespaced.acquire_lease(&scope, Duration::from_secs(60)).unwrap().unwrap();
+        assert!(namespaced.release_lease(&taken).unwrap());
+
+        namespaced.save_change_cursor("outbox", 7).unwrap();
+        namespaced.save_change_cursor("outbox", 9).unwrap();
+        assert_eq!(namespaced.load_change_cursor("outbox").unwrap(), 9);
+        assert_eq!(namespaced.load_change_cursor("missing").unwrap(), 0);
+
+        let mut outcome = RunOutcome::new(RunStatus::Failure);
+        outcome.score = Some(0.25);
+        outcome.error_summary = "timeout".to_string();
+        namespaced.record_run_outcome(&scope, outcome.clone()).unwrap();
+        outcome.duration_ms = 4_000;
+        namespaced.record_run_outcome(&scope, outcome).unwrap();
+        let outcomes = namespaced.list_run_outcomes(&scope, Some(5)).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].status, RunStatus::Failure);
+        assert_eq!(outcomes[0].score, Some(0.25));
+        assert_eq!(outcomes[0].duration_ms, 4_000);
+
+        let credibility = SourceCredibility::default().with_score("user", 0.4);
+        namespaced
+            .set_source_credibility(&scope.tenant_id, credibility.clone())
+            .unwrap();
+        let stored = namespaced.get_source_credibility(&scope.tenant_id).unwrap();
+        assert_eq!(stored, Some(credibility));
+
+        let locale = UserLocale::new("-04:00", "en-US");
+        namespaced
+            .set_user_locale(&scope.tenant_id, &scope.user_id, locale.clone())
+            .unwrap();
+        let stored = namespaced.get_user_locale(&scope.tenant_id, &scope.user_id).unwrap();
+        assert_eq!(stored, Some(locale));
+    }
+
+    #[test]
+    fn postgres_logs_changes_in_the_write_transaction() {
+        let dsn = match std::env::var("ENGRAM_POSTGRES_DSN") {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => {
+                eprintln!("ENGRAM_POSTGRES_DSN not set; skipping postgres_logs_changes");
+                return;
+            }
+        };
+        let store = crate::ChangeLogStore::new(PostgresStore::new(&dsn).unwrap());
+        let scope = sample_scope();
+        let ours = |changes: Vec<Change>| -> Vec<Change> {
+            changes
+                .into_iter()
+                .filter_map(|mut change| {
+                    let ours = change.op.scope_mut().is_some_and(|s| scope_matches(s, &scope));
+                    ours.then_some(change)
+                })
+                .collect()
+        };
+        let start = store.list_changes(0, None).unwrap().last().map_or(0, |c| c.seq);
+
+        let failed = store.inner().write_logged(&mut || {
+            store.inner().upsert_fact(&scope, Fact::new("user.name", json!("Ada")))?;
+            Err(StoreError::Storage("crashed before the change was logged".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(store.list_facts(&scope, FactFilter::default()).unwrap().is_empty());
+        assert!(ours(store.list_changes(start, None).unwrap()).is_empty());
+
+        let fact = Fact::new("user.name", json!("Ada"));
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        // A failing write inside a logged one rolls back to its savepoint only.
+        let duplicate = store.inner().write_logged(&mut || {
+            store.inner().upsert_fact(&scope, Fact::new("user.city", json!("Oslo")))?;
+            let same = Event {
+                event_id: unique_id("event"),
+                ..Event::new(scope.clone(), EventKind::Message, json!("x"))
+            };
+            store.inner().append_event(same.clone())?;
+            assert!(store.inner().append_event(same).is_err());
+            Ok(Vec::new())
+        });
+        duplicate.unwrap();
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 2);
+
+        let changes = ours(store.list_changes(start, None).unwrap());
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(changes[0].op, ChangeOp::UpsertFact { .. }));
+        assert!(matches!(changes[1].op, ChangeOp::AppendEvent { .. }));
+    }
+
+    #[test]
+    fn postgres_notifications_evict_cached_entries() {
+        let dsn = match std::env::var("ENGRAM_POSTGRES_DSN") {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => {
+                eprintln!("ENGRAM_POSTGRES_DSN not set; skipping postgres_notifications");
+                return;
+            }
+        };
+        let writer = PostgresStore::new(&dsn).unwrap();
+        let scope = sample_scope();
+        let patch = |goal: &str| WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            ..WorkingStatePatch::default()
+        };
+        writer.patch_working_state(&scope, patch("draft")).unwrap();
+
+        // No change log and no polling: only the notification tells the cache.
+        let options = crate::CacheOptions {
+            max_staleness: Duration::from_secs(3600),
+            ..crate::CacheOptions::default()
+        };
+        let cache = crate::CachedStore::new(PostgresStore::new(&dsn).unwrap(), options).unwrap();
+        let mut listener = cache.inner().listen(&scope.tenant_id).unwrap();
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+        listener.drain().unwrap();
+
+        writer.patch_working_state(&scope, patch("ship")).unwrap();
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "draft");
+        assert_eq!(cache.evict_notified(&mut listener, Duration::from_secs(5)).unwrap(), 1);
+        assert_eq!(cache.get_working_state(&scope).unwrap().unwrap().goal, "ship");
     }
 }
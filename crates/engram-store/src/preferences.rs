@@ -0,0 +1,105 @@
+use engram_types::{Fact, FactStatus, Scope};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{FactFilter, Store, StoreError, StoreResult};
+
+/// Fact key prefix reserved for user preferences. Facts under it are recalled into
+/// `long_term.preferences` instead of `long_term.facts`.
+pub const PREFERENCE_KEY_PREFIX: &str = "preference:";
+
+/// Whether `fact` holds a user preference set through [`set_preference`].
+pub fn is_preference(fact: &Fact) -> bool {
+    fact.fact_key.starts_with(PREFERENCE_KEY_PREFIX)
+}
+
+/// Records that the scope's user prefers `value` for `key`, e.g. `tone` or
+/// `units`, with a confidence between 0 and 1. Setting a key again updates the
+/// active preference in place. Returns the stored fact, keyed
+/// `PREFERENCE_KEY_PREFIX` + `key`.
+pub fn set_preference<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    key: &str,
+    value: Value,
+    confidence: f64,
+) -> StoreResult<Fact> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(StoreError::InvalidInput("preference key is empty".to_string()));
+    }
+    if !(0.0..=1.0).contains(&confidence) {
+        return Err(StoreError::InvalidInput(format!(
+            "preference confidence {} is outside 0..=1",
+            confidence
+        )));
+    }
+    let fact_key = format!("{}{}", PREFERENCE_KEY_PREFIX, key);
+    let existing = get_preferences(store, scope)?
+        .into_iter()
+        .find(|fact| fact.fact_key == fact_key);
+    let mut fact = existing.unwrap_or_else(|| Fact::new(fact_key, Value::Null));
+    fact.value = value;
+    fact.confidence = confidence;
+    store.upsert_fact(scope, fact.clone())?;
+    debug!("preference {} set for user {}", fact.fact_key, scope.user_id);
+    Ok(fact)
+}
+
+/// The scope's active preferences, ordered by key.
+pub fn get_preferences<S: Store + ?Sized>(store: &S, scope: &Scope) -> StoreResult<Vec<Fact>> {
+    let filter = FactFilter {
+        status: Some(vec![FactStatus::Active]),
+        preferences: Some(true),
+        ..FactFilter::default()
+    };
+    let mut preferences = store.list_facts(scope, filter)?;
+    preferences.sort_by(|a, b| a.fact_key.cmp(&b.fact_key).then_with(|| a.fact_id.cmp(&b.fact_id)));
+    Ok(preferences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore};
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    #[test]
+    fn preferences_fill_their_own_packet_section() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store.upsert_fact(&scope, Fact::new("pref.color", json!("blue"))).unwrap();
+        let tone = set_preference(&store, &scope, "tone", json!("formal"), 0.6).unwrap();
+        let updated = set_preference(&store, &scope, " tone ", json!("casual"), 0.9).unwrap();
+        assert_eq!(updated.fact_id, tone.fact_id);
+        set_preference(&store, &scope, "units", json!("metric"), 1.0).unwrap();
+        assert!(set_preference(&store, &scope, "units", json!("imperial"), 1.5).is_err());
+        assert!(set_preference(&store, &scope, " ", json!("x"), 0.5).is_err());
+
+        let preferences = get_preferences(&store, &scope).unwrap();
+        let keys: Vec<&str> = preferences.iter().map(|fact| fact.fact_key.as_str()).collect();
+        assert_eq!(keys, vec!["preference:tone", "preference:units"]);
+        assert_eq!(preferences[0].value, json!("casual"));
+        assert_eq!(preferences[0].confidence, 0.9);
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        let facts: Vec<&str> =
+            packet.long_term.facts.iter().map(|fact| fact.fact_key.as_str()).collect();
+        assert_eq!(facts, vec!["pref.color"]);
+        assert_eq!(packet.long_term.preferences.len(), 2);
+        assert!(packet.budget_report.section_usage.contains_key("preferences"));
+
+        request.policy.max_preferences = 1;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.long_term.preferences[0].fact_id, tone.fact_id);
+        assert_eq!(packet.long_term.preferences.len(), 1);
+    }
+}
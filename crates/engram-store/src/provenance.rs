@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use engram_types::{Episode, Fact, Scope};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::chunk::{chunk_passage, parse_chunk_id};
+use crate::composer::parse_event_payload;
+use crate::{EpisodeFilter, Event, FactFilter, Store, StoreError, StoreResult};
+
+const MAX_DEPTH: usize = 8;
+const MAX_QUOTE_CHARS: usize = 280;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    Event,
+    Episode,
+    Fact,
+    /// The source id did not match anything visible in the scope.
+    Unresolved,
+}
+
+/// One hop of a provenance chain: `id` is cited by `cited_by` at distance `depth`
+/// from the fact being explained.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceLink {
+    pub id: String,
+    pub kind: EvidenceKind,
+    pub cited_by: String,
+    pub depth: usize,
+    pub ts: Option<DateTime<Utc>>,
+    pub quote: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    pub fact: Fact,
+    pub chain: Vec<EvidenceLink>,
+}
+
+/// Follows a fact's `sources` through facts, episodes and events, breadth first,
+/// and returns every piece of evidence reached with a quote where one exists.
+/// Events are searched across all sessions and runs of the scope's user and agent.
+pub fn resolve_provenance<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    fact_id: &str,
+) -> StoreResult<Provenance> {
+    let facts: HashMap<String, Fact> = store
+        .list_facts(scope, FactFilter::default())?
+        .into_iter()
+        .map(|fact| (fact.fact_id.clone(), fact))
+        .collect();
+    let fact = facts.get(fact_id).cloned().ok_or(StoreError::NotFound)?;
+    let mut episodes: Option<HashMap<String, Episode>> = None;
+
+    let mut chain = Vec::new();
+    let mut seen: HashSet<String> = HashSet::from([fact.fact_id.clone()]);
+    let mut frontier: Vec<(String, String)> = fact
+        .sources
+        .iter()
+        .map(|source| (source.clone(), fact.fact_id.clone()))
+        .collect();
+
+    for depth in 1..=MAX_DEPTH {
+        frontier.retain(|(id, _)| seen.insert(id.clone()));
+        if frontier.is_empty() {
+            break;
+        }
+
+        let unknown: Vec<String> = frontier
+            .iter()
+            .filter(|(id, _)| !facts.contains_key(id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        if !unknown.is_empty() && episodes.is_none() {
+            episodes = Some(
+                store
+                    .list_episodes(scope, EpisodeFilter::default())?
+                    .into_iter()
+                    .map(|episode| (episode.episode_id.clone(), episode))
+                    .collect(),
+            );
+        }
+        let episodes = episodes.as_ref();
+        // Chunk ids resolve through the event they were cut from.
+        let event_ids: Vec<String> = unknown
+            .into_iter()
+            .filter(|id| !episodes.is_some_and(|episodes| episodes.contains_key(id)))
+            .map(|id| match parse_chunk_id(&id) {
+                Some((event_id, _, _)) => event_id.to_string(),
+                None => id,
+            })
+            .collect();
+        let events: HashMap<String, Event> = store
+            .get_events_by_ids(scope, &event_ids)?
+            .into_iter()
+            .map(|event| (event.event_id.clone(), event))
+            .collect();
+
+        let mut next = Vec::new();
+        for (id, cited_by) in frontier.drain(..) {
+            let mut link = EvidenceLink {
+                id: id.clone(),
+                kind: EvidenceKind::Unresolved,
+                cited_by,
+                depth,
+                ts: None,
+                quote: None,
+            };
+            if let Some(source) = facts.get(&id) {
+                link.kind = EvidenceKind::Fact;
+                link.quote = Some(clip(&format!("{} = {}", source.fact_key, source.value)));
+                next.extend(source.sources.iter().map(|s| (s.clone(), id.clone())));
+            } else if let Some(episode) = episodes.and_then(|episodes| episodes.get(&id)) {
+                link.kind = EvidenceKind::Episode;
+                link.ts = Some(episode.time_range.start);
+                link.quote = Some(clip(&episode.summary));
+                next.extend(episode.sources.iter().map(|s| (s.clone(), id.clone())));
+            } else if let Some(event) = events.get(&id) {
+                link.kind = EvidenceKind::Event;
+                link.ts = Some(event.ts);
+                link.quote = Some(clip(&event_quote(event)));
+            } else if let Some((event_id, start, end)) = parse_chunk_id(&id)
+                && let Some(event) = events.get(event_id)
+            {
+                link.kind = EvidenceKind::Event;
+                link.ts = Some(event.ts);
+                link.quote = Some(clip(&chunk_passage(&event_quote(event), start, end)));
+            }
+            chain.push(link);
+        }
+        frontier = next;
+    }
+
+    Ok(Provenance { fact, chain })
+}
+
+pub(crate) fn event_quote(event: &Event) -> String {
+    match parse_event_payload(&event.payload) {
+        Some((content, _)) => content,
+        None => event.payload.to_string(),
+    }
+}
+
+fn clip(text: &str) -> String {
+    if text.chars().count() <= MAX_QUOTE_CHARS {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(MAX_QUOTE_CHARS).collect();
+    clipped.push('…');
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore};
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn follows_sources_through_episodes_to_events() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let other_run = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+        let event = Event::new(
+            other_run,
+            EventKind::Message,
+            json!({ "role": "user", "content": "I prefer tea over coffee" }),
+        );
+        store.append_event(event.clone()).unwrap();
+
+        let mut episode = Episode::new("Discussed drinks");
+        episode.sources = vec![event.event_id.clone()];
+        store.append_episode(&scope, episode.clone()).unwrap();
+
+        let mut fact = Fact::new("user.drink", json!("tea"));
+        fact.sources = vec![episode.episode_id.clone(), "missing".to_string()];
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+
+        let provenance = resolve_provenance(&store, &scope, &fact.fact_id).unwrap();
+        let kinds: Vec<(EvidenceKind, usize)> = provenance
+            .chain
+            .iter()
+            .map(|link| (link.kind, link.depth))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (EvidenceKind::Episode, 1),
+                (EvidenceKind::Unresolved, 1),
+                (EvidenceKind::Event, 2)
+            ]
+        );
+        assert_eq!(provenance.chain[2].cited_by, episode.episode_id);
+        assert_eq!(
+            provenance.chain[2].quote.as_deref(),
+            Some("I prefer tea over coffee")
+        );
+
+        // A source citing one chunk of the event quotes just that passage.
+        let chunk = &crate::chunk_text(&event.event_id, "I prefer tea over coffee", &{
+            crate::ChunkOptions {
+                max_tokens: 3,
+                overlap_tokens: 0,
+            }
+        })[1];
+        let mut chunked = Fact::new("user.drink.not", json!("coffee"));
+        chunked.sources = vec![chunk.chunk_id.clone()];
+        store.upsert_fact(&scope, chunked.clone()).unwrap();
+        let provenance = resolve_provenance(&store, &scope, &chunked.fact_id).unwrap();
+        assert_eq!(provenance.chain[0].kind, EvidenceKind::Event);
+        assert_eq!(provenance.chain[0].quote.as_deref(), Some("over coffee"));
+        assert!(matches!(
+            resolve_provenance(&store, &scope, "nope"),
+            Err(StoreError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+use engram_types::{Episode, Fact, InsightItem, Procedure, ValidationState};
+use std::fmt;
+
+use crate::RecallCues;
+
+/// A memory `build_memory_packet` is deciding whether, and where, to include.
+#[derive(Debug, Clone, Copy)]
+pub enum Candidate<'a> {
+    Fact(&'a Fact),
+    Episode(&'a Episode),
+    Procedure(&'a Procedure),
+    Insight(&'a InsightItem),
+}
+
+/// Scores recall candidates; each section keeps its highest scored candidates, best
+/// first. Set one on [`RecallPolicy::ranker`](crate::RecallPolicy::ranker) to rank by
+/// recency decay, confidence or embedding similarity without forking the composer.
+pub trait Ranker: Send + Sync {
+    /// Score of `candidate` for a request with `cues`; higher ranks first. `recency` is
+    /// `1 / (1 + days)` since an episode started, and `None` for other candidates.
+    fn score(&self, candidate: Candidate<'_>, cues: &RecallCues, recency: Option<f64>) -> f64;
+}
+
+impl fmt::Debug for dyn Ranker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ranker").finish_non_exhaustive()
+    }
+}
+
+/// The composer's own ranking: episodes by recency, procedures by priority and insights
+/// by validation state then confidence. Facts all score alike, so they keep the key
+/// order the backend lists them in and the same facts are recalled as without a ranker.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRanker;
+
+impl Ranker for DefaultRanker {
+    fn score(&self, candidate: Candidate<'_>, _cues: &RecallCues, recency: Option<f64>) -> f64 {
+        match candidate {
+            Candidate::Fact(_) => 0.0,
+            Candidate::Episode(_) => recency.unwrap_or(0.0),
+            Candidate::Procedure(procedure) => f64::from(procedure.priority),
+            Candidate::Insight(item) => {
+                let state_rank = match item.validation_state {
+                    ValidationState::Validated => 3.0,
+                    ValidationState::Testing => 2.0,
+                    ValidationState::Unvalidated => 1.0,
+                    ValidationState::Rejected => 0.0,
+                };
+                // Confidence only orders insights within a validation state.
+                state_rank + item.confidence.clamp(0.0, 1.0) * 0.5
+            }
+        }
+    }
+}
+
+/// Orders `items` by descending `score`, computed once per item; ties keep their order.
+pub(crate) fn sort_by_score<T>(items: &mut Vec<T>, score: impl Fn(&T) -> f64) {
+    let mut scored: Vec<(f64, T)> = items.drain(..).map(|item| (score(&item), item)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    items.extend(scored.into_iter().map(|(_, item)| item));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore, Store};
+    use chrono::{Duration, Utc};
+    use engram_types::{Purpose, Scope};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// Ranks every section the other way round from [`DefaultRanker`], and facts by
+    /// confidence.
+    struct InverseRanker;
+
+    impl Ranker for InverseRanker {
+        fn score(&self, candidate: Candidate<'_>, cues: &RecallCues, recency: Option<f64>) -> f64 {
+            match candidate {
+                Candidate::Fact(fact) => fact.confidence,
+                _ => -DefaultRanker.score(candidate, cues, recency),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_rankers_reorder_candidates() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut trip = Episode::new("Booked the flight to Lisbon");
+        trip.time_range.start = Utc::now() - Duration::days(3);
+        store.append_episode(&scope, trip).unwrap();
+        store.append_episode(&scope, Episode::new("Paid the phone bill")).unwrap();
+        for (key, confidence) in [("user.city", 0.3), ("user.zodiac", 0.9)] {
+            let mut fact = Fact::new(key, json!("x"));
+            fact.confidence = confidence;
+            store.upsert_fact(&scope, fact).unwrap();
+        }
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.persist = false;
+        request.policy.max_episodes = 1;
+        request.policy.max_facts = 1;
+        let recalled = |request: &BuildRequest| {
+            let long_term = build_memory_packet(&store, request.clone()).unwrap().long_term;
+            (long_term.episodes[0].summary.clone(), long_term.facts[0].fact_key.clone())
+        };
+        assert_eq!(recalled(&request).0, "Paid the phone bill");
+
+        request.policy.ranker = Some(Arc::new(DefaultRanker));
+        assert_eq!(
+            recalled(&request),
+            ("Paid the phone bill".to_string(), "user.city".to_string())
+        );
+        request.policy.ranker = Some(Arc::new(InverseRanker));
+        assert_eq!(
+            recalled(&request),
+            ("Booked the flight to Lisbon".to_string(), "user.zodiac".to_string())
+        );
+
+        request.policy.max_facts = 2;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let keys: Vec<_> = packet.long_term.facts.iter().map(|f| f.fact_key.as_str()).collect();
+        assert_eq!(keys, ["user.zodiac", "user.city"]);
+    }
+}
@@ -0,0 +1,474 @@
+use engram_types::{
+    Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreMetrics, StoreResult, Suppression, TextQuery,
+    TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+thread_local! {
+    static CALL_READ_PREFERENCE: Cell<ReadPreference> =
+        const { Cell::new(ReadPreference::Primary) };
+}
+
+/// Where the reads of a call may be served from, trading freshness for latency. Store
+/// wrappers interpret it: [`CachedStore`](crate::CachedStore) for the cache preferences
+/// and [`ReadReplicaStore`] for `replica-ok`; backends ignore it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadPreference {
+    /// Fresh reads: caches are used within their staleness bound, replicas are not.
+    #[default]
+    Primary,
+    /// Reads may come from a replica that lags the primary.
+    ReplicaOk,
+    /// Cached entries are served however stale, and misses are not looked up.
+    CacheOnly,
+    /// Caches are skipped and refilled from the store.
+    BypassCache,
+}
+
+/// Runs `f` with the reads it makes on this thread under `preference`; see
+/// [`BuildRequest::read_preference`](crate::BuildRequest::read_preference).
+///
+/// Like [`with_timeout`](crate::with_timeout), the preference is thread-local: with
+/// `spawn_blocking` or a thread pool, call this inside the closure doing the reads.
+pub fn with_read_preference<T>(preference: ReadPreference, f: impl FnOnce() -> T) -> T {
+    let previous = CALL_READ_PREFERENCE.with(|cell| cell.replace(preference));
+    let result = f();
+    CALL_READ_PREFERENCE.with(|cell| cell.set(previous));
+    result
+}
+
+/// The preference of the call running on this thread, for store wrappers to act on.
+pub fn read_preference() -> ReadPreference {
+    CALL_READ_PREFERENCE.with(Cell::get)
+}
+
+/// Store wrapper that sends writes to `primary` and reads to `replica` when the call
+/// allows it ([`ReadPreference::ReplicaOk`]), e.g. a Postgres hot standby serving
+/// latency-critical responder builds. Change log reads always go to the primary.
+#[derive(Debug)]
+pub struct ReadReplicaStore<P, R> {
+    primary: P,
+    replica: R,
+}
+
+impl<P: Store, R: Store> ReadReplicaStore<P, R> {
+    pub fn new(primary: P, replica: R) -> Self {
+        Self { primary, replica }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn replica(&self) -> &R {
+        &self.replica
+    }
+
+    fn reader(&self) -> &dyn Store {
+        match read_preference() {
+            ReadPreference::ReplicaOk => &self.replica,
+            _ => &self.primary,
+        }
+    }
+}
+
+impl<P: Store, R: Store> Store for ReadReplicaStore<P, R> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.primary.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.primary.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.reader().list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.reader().list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.reader().get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.reader().get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.reader().find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.reader().search_events(scope, query)
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.reader().search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.primary.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.primary.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.primary.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.reader().get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.primary.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.primary.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.reader().list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.reader().get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.primary.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.reader().list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.primary.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.primary.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.reader().list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.reader().list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()> {
+        self.primary.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        self.primary.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.reader().list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.primary.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.reader().list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.reader().get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.primary.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.reader().list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.primary.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.primary.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.primary.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.reader().list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.reader().list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.reader().tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        self.reader().list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.primary.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.primary.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.reader().list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.primary.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.primary.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.primary.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.reader().count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.reader().list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.primary.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.reader().list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.primary.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.primary.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.reader().list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.primary.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.reader().get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.primary.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.reader().get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.primary.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.primary.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.primary.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.primary.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.primary.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.primary.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.primary.metrics_snapshot().merge(self.replica.metrics_snapshot())
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.primary.capabilities().intersect(self.replica.capabilities())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore};
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    #[test]
+    fn replica_ok_builds_read_from_the_replica() {
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let store = ReadReplicaStore::new(InMemoryStore::new(), InMemoryStore::new());
+        store.upsert_fact(&scope, Fact::new("plan", json!("fresh"))).unwrap();
+        store
+            .replica()
+            .upsert_fact(&scope, Fact::new("plan", json!("lagging")))
+            .unwrap();
+
+        let build = |preference| {
+            let mut request = BuildRequest::new(scope.clone(), Purpose::Responder);
+            request.read_preference = preference;
+            build_memory_packet(&store, request).unwrap().long_term.facts[0].value.clone()
+        };
+        assert_eq!(build(ReadPreference::Primary), json!("fresh"));
+        assert_eq!(build(ReadPreference::ReplicaOk), json!("lagging"));
+        assert_eq!(read_preference(), ReadPreference::Primary);
+        // Persisted builds are writes and land on the primary either way.
+        assert_eq!(store.primary().list_context_builds(&scope, None).unwrap().len(), 2);
+        assert!(store.replica().list_context_builds(&scope, None).unwrap().is_empty());
+
+        let parsed: ReadPreference = serde_json::from_value(json!("cache-only")).unwrap();
+        assert_eq!(parsed, ReadPreference::CacheOnly);
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Duration, Utc};
+use engram_types::Scope;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+use crate::{
+    DeleteMode, EpisodeFilter, FactFilter, InsightFilter, RecordKind, RecordRef, Store,
+    StoreResult, TimeRangeFilter,
+};
+
+/// How long each kind of record is kept, for [`run_retention`]. Kinds without a TTL
+/// are kept forever; procedures have no timestamps and always are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Days an event is kept after its `ts`.
+    pub event_ttl_days: Option<u32>,
+    /// Days an episode is kept after it ended, or after it started if it is still open.
+    pub episode_ttl_days: Option<u32>,
+    /// Days a fact is kept after its `validity.valid_to`. Facts without an end of
+    /// validity never expire.
+    pub fact_ttl_days: Option<u32>,
+    /// Removes insights whose `expires_at` is a timestamp in the past. Markers such as
+    /// `run_end` are left to their own lifecycle.
+    pub expired_insights: bool,
+    /// `Soft` archives expired records as tombstones instead of deleting them outright.
+    pub mode: DeleteMode,
+}
+
+/// What [`run_retention`] removed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// Runs whose events and insights were swept.
+    pub runs: usize,
+    /// The records removed, or archived in soft mode.
+    pub removed: Vec<RecordRef>,
+}
+
+/// Prunes the scope's records that `policy` no longer keeps, as of `now`. Facts and
+/// episodes are swept for the user and agent; events and insights for the scope's
+/// run or, when `run_id` is empty, for every run of the user and agent with events
+/// (of the session, if `session_id` is set). Meant for a periodic maintenance job.
+pub fn run_retention<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> StoreResult<RetentionReport> {
+    let mut report = RetentionReport::default();
+    if policy.event_ttl_days.is_some() || policy.expired_insights {
+        for run in runs(store, scope)? {
+            report.runs += 1;
+            if let Some(cutoff) = cutoff(policy.event_ttl_days, now) {
+                let range = TimeRangeFilter {
+                    start: None,
+                    end: Some(cutoff),
+                };
+                for event in store.list_events(&run, range, None)? {
+                    if event.ts < cutoff {
+                        let id = event.event_id;
+                        remove(store, &run, RecordKind::Event, id, policy, &mut report)?;
+                    }
+                }
+            }
+            if policy.expired_insights {
+                for insight in store.list_insights(&run, InsightFilter::default())? {
+                    let expired = DateTime::parse_from_rfc3339(&insight.expires_at)
+                        .is_ok_and(|expires_at| expires_at < now);
+                    if expired {
+                        remove(store, &run, RecordKind::Insight, insight.id, policy, &mut report)?;
+                    }
+                }
+            }
+        }
+    }
+    if let Some(cutoff) = cutoff(policy.episode_ttl_days, now) {
+        for episode in store.list_episodes(scope, EpisodeFilter::default())? {
+            let range = &episode.time_range;
+            if range.end.unwrap_or(range.start) < cutoff {
+                let id = episode.episode_id;
+                remove(store, scope, RecordKind::Episode, id, policy, &mut report)?;
+            }
+        }
+    }
+    if let Some(cutoff) = cutoff(policy.fact_ttl_days, now) {
+        for fact in store.list_facts(scope, FactFilter::default())? {
+            if fact.validity.valid_to.is_some_and(|valid_to| valid_to < cutoff) {
+                remove(store, scope, RecordKind::Fact, fact.fact_id, policy, &mut report)?;
+            }
+        }
+    }
+    if !report.removed.is_empty() {
+        debug!(
+            "retention removed {} records of {}/{}/{}",
+            report.removed.len(),
+            scope.tenant_id,
+            scope.user_id,
+            scope.agent_id
+        );
+    }
+    Ok(report)
+}
+
+fn cutoff(ttl_days: Option<u32>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    ttl_days.map(|days| now - Duration::days(i64::from(days)))
+}
+
+/// The run scopes swept for `scope`: itself if it names a run, otherwise the user and
+/// agent's runs with events.
+fn runs<S: Store + ?Sized>(store: &S, scope: &Scope) -> StoreResult<Vec<Scope>> {
+    if !scope.run_id.is_empty() {
+        return Ok(vec![scope.clone()]);
+    }
+    let mut runs = BTreeMap::new();
+    for run in store.list_scopes(Some(&scope.tenant_id), None)? {
+        if run.user_id == scope.user_id
+            && run.agent_id == scope.agent_id
+            && (scope.session_id.is_empty() || run.session_id == scope.session_id)
+        {
+            runs.entry((run.session_id.clone(), run.run_id.clone())).or_insert(run);
+        }
+    }
+    Ok(runs.into_values().collect())
+}
+
+fn remove<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    kind: RecordKind,
+    id: String,
+    policy: &RetentionPolicy,
+    report: &mut RetentionReport,
+) -> StoreResult<()> {
+    let item = RecordRef::new(kind, id);
+    if store.delete_record(scope, &item, policy.mode)? {
+        report.removed.push(item);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, EventKind, InMemoryStore};
+    use engram_types::{Episode, Fact, InsightItem, InsightType};
+    use serde_json::json;
+
+    #[test]
+    fn prunes_records_past_their_ttl() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let other_run = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+        let now = Utc::now();
+        let mut old = Event::new(scope.clone(), EventKind::Message, json!("old"));
+        old.ts = now - Duration::days(40);
+        let recent = Event::new(scope.clone(), EventKind::Message, json!("recent"));
+        let mut elsewhere = Event::new(other_run.clone(), EventKind::Message, json!("old"));
+        elsewhere.ts = now - Duration::days(31);
+        for event in [&old, &recent, &elsewhere] {
+            store.append_event(event.clone()).unwrap();
+        }
+        let mut stale = InsightItem::new(InsightType::Hypothesis, "stale");
+        stale.expires_at = (now - Duration::hours(1)).to_rfc3339();
+        let mut current = InsightItem::new(InsightType::Hypothesis, "current");
+        current.expires_at = "run_end".to_string();
+        store.append_insight(&other_run, stale.clone()).unwrap();
+        store.append_insight(&other_run, current).unwrap();
+        let mut ended = Episode::new("ended");
+        ended.time_range.start = now - Duration::days(100);
+        ended.time_range.end = Some(now - Duration::days(95));
+        store.append_episode(&scope, ended.clone()).unwrap();
+        store.append_episode(&scope, Episode::new("fresh")).unwrap();
+        let mut lapsed = Fact::new("plan.trial", json!(true));
+        lapsed.validity.valid_to = Some(now - Duration::days(10));
+        store.upsert_fact(&scope, lapsed).unwrap();
+
+        let policy = RetentionPolicy {
+            event_ttl_days: Some(30),
+            episode_ttl_days: Some(90),
+            fact_ttl_days: Some(30),
+            expired_insights: true,
+            mode: DeleteMode::Soft,
+        };
+        let user = Scope {
+            session_id: String::new(),
+            run_id: String::new(),
+            ..scope.clone()
+        };
+        let report = run_retention(&store, &user, &policy, now).unwrap();
+        assert_eq!(report.runs, 2);
+        assert_eq!(
+            report.removed,
+            [
+                RecordRef::new(RecordKind::Event, &old.event_id),
+                RecordRef::new(RecordKind::Event, &elsewhere.event_id),
+                RecordRef::new(RecordKind::Insight, &stale.id),
+                RecordRef::new(RecordKind::Episode, &ended.episode_id),
+            ]
+        );
+        let events = store.list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_id, recent.event_id);
+        assert_eq!(store.list_insights(&other_run, InsightFilter::default()).unwrap().len(), 1);
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+        assert_eq!(store.list_tombstones(&scope, None).unwrap().len(), 4);
+
+        // A second pass finds nothing left to prune.
+        let report = run_retention(&store, &scope, &policy, now).unwrap();
+        assert_eq!(report.runs, 1);
+        assert!(report.removed.is_empty());
+    }
+}
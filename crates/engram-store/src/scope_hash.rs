@@ -0,0 +1,668 @@
+use engram_types::{
+    Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventPage,
+    FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState, SourceCredibility,
+    StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult, Suppression,
+    TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+    SHARED_SCOPE_ID,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Shortest key [`ScopeHasher::new`] accepts.
+pub const MIN_SCOPE_KEY_BYTES: usize = 16;
+
+/// Marks stored ids as hashed, so an id read back from the store and passed in again
+/// is recognised and not hashed a second time.
+pub const HASHED_ID_PREFIX: &str = "hmac:";
+
+/// Hashed ids remembered for showing callers their own ids back; beyond this reads
+/// return the hashed id of users not seen recently enough.
+const MAX_KNOWN_IDS: usize = 100_000;
+
+/// Replaces user ids with their HMAC-SHA256 under a key only the application holds, so
+/// a leaked database does not reveal who the users are, and ids cannot be confirmed by
+/// hashing guesses without the key. Empty ids, [`SHARED_SCOPE_ID`] and ids already in
+/// the hashed form are kept as is, so user ids must not take that form themselves.
+#[derive(Clone)]
+pub struct ScopeHasher {
+    mac: HmacSha256,
+    /// Hashed id to the id it was computed from, shared by clones.
+    known: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl fmt::Debug for ScopeHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let known = self.known.read().map(|known| known.len()).unwrap_or_default();
+        f.debug_struct("ScopeHasher").field("known", &known).finish_non_exhaustive()
+    }
+}
+
+impl ScopeHasher {
+    pub fn new(key: &[u8]) -> StoreResult<Self> {
+        if key.len() < MIN_SCOPE_KEY_BYTES {
+            return Err(StoreError::InvalidInput(format!(
+                "scope hashing key must be at least {} bytes",
+                MIN_SCOPE_KEY_BYTES
+            )));
+        }
+        let mac = <HmacSha256 as Mac>::new_from_slice(key)
+            .map_err(|err| StoreError::InvalidInput(err.to_string()))?;
+        Ok(Self {
+            mac,
+            known: Arc::default(),
+        })
+    }
+
+    /// The stored form of `user_id`: [`HASHED_ID_PREFIX`] and 64 lowercase hex digits.
+    pub fn hash_user_id(&self, user_id: &str) -> String {
+        if user_id.is_empty() || user_id == SHARED_SCOPE_ID || is_hashed(user_id) {
+            return user_id.to_string();
+        }
+        let mut mac = self.mac.clone();
+        mac.update(user_id.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let hashed = format!("{}{}", HASHED_ID_PREFIX, hex);
+        if let Ok(mut known) = self.known.write()
+            && known.len() < MAX_KNOWN_IDS
+        {
+            known.entry(hashed.clone()).or_insert_with(|| user_id.to_string());
+        }
+        hashed
+    }
+
+    /// The id `hashed` was computed from, when it was hashed by this hasher or a clone;
+    /// `hashed` itself otherwise.
+    pub fn restore_user_id(&self, hashed: &str) -> String {
+        self.known
+            .read()
+            .ok()
+            .and_then(|known| known.get(hashed).cloned())
+            .unwrap_or_else(|| hashed.to_string())
+    }
+
+    pub fn hash_scope(&self, scope: &Scope) -> Scope {
+        Scope {
+            user_id: self.hash_user_id(&scope.user_id),
+            ..scope.clone()
+        }
+    }
+
+    pub fn restore_scope(&self, scope: &Scope) -> Scope {
+        Scope {
+            user_id: self.restore_user_id(&scope.user_id),
+            ..scope.clone()
+        }
+    }
+}
+
+/// Whether `user_id` is in the form [`ScopeHasher::hash_user_id`] stores.
+fn is_hashed(user_id: &str) -> bool {
+    user_id.strip_prefix(HASHED_ID_PREFIX).is_some_and(|hex| {
+        hex.len() == 64 && hex.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+/// Store wrapper that hashes user ids with a [`ScopeHasher`] before they reach the
+/// backend: in scopes, events, leases, stored packets, locales and the change log.
+///
+/// Reads map hashed ids back to the ids callers passed in. Ids this process has not
+/// hashed, e.g. users listed by [`Store::list_scopes`] after a restart, come back hashed;
+/// they carry [`HASHED_ID_PREFIX`], so passed back as they are they reach the same rows.
+/// Payloads and memory text are stored as given, so keep raw identifiers out of them as
+/// well.
+#[derive(Debug)]
+pub struct ScopeHashingStore<S> {
+    inner: S,
+    hasher: ScopeHasher,
+}
+
+impl<S: Store> ScopeHashingStore<S> {
+    pub fn new(inner: S, hasher: ScopeHasher) -> Self {
+        Self { inner, hasher }
+    }
+
+    pub fn hasher(&self) -> &ScopeHasher {
+        &self.hasher
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn restore_events(&self, events: Vec<Event>) -> Vec<Event> {
+        events
+            .into_iter()
+            .map(|event| Event {
+                scope: self.hasher.restore_scope(&event.scope),
+                ..event
+            })
+            .collect()
+    }
+
+    /// Restores the tombstone's scope, and the scope a deleted event carries.
+    fn restore_tombstone(&self, mut tombstone: Tombstone) -> Tombstone {
+        tombstone.scope = self.hasher.restore_scope(&tombstone.scope);
+        if tombstone.item.kind == RecordKind::Event
+            && let Some(scope) = tombstone.record.get_mut("scope")
+            && let Ok(restored) = serde_json::to_value(&tombstone.scope)
+        {
+            *scope = restored;
+        }
+        tombstone
+    }
+
+    fn hash_lease(&self, lease: &Lease) -> Lease {
+        Lease {
+            scope: self.hasher.hash_scope(&lease.scope),
+            ..lease.clone()
+        }
+    }
+
+    fn restore_lease(&self, lease: Lease) -> Lease {
+        Lease {
+            scope: self.hasher.restore_scope(&lease.scope),
+            ..lease
+        }
+    }
+}
+
+/// Applies `map` to every user id `op` carries.
+fn map_user_ids(mut op: ChangeOp, map: &dyn Fn(&str) -> String) -> ChangeOp {
+    if let Some(scope) = op.scope_mut() {
+        scope.user_id = map(&scope.user_id);
+    }
+    match &mut op {
+        ChangeOp::WriteContextBuild { packet, .. } => {
+            packet.meta.scope.user_id = map(&packet.meta.scope.user_id);
+        }
+        ChangeOp::SetUserLocale { user_id, .. } => *user_id = map(user_id),
+        _ => {}
+    }
+    op
+}
+
+impl<S: Store> Store for ScopeHashingStore<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.inner.append_event(Event {
+            scope: self.hasher.hash_scope(&event.scope),
+            ..event
+        })
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        let events: Vec<Event> = events
+            .iter()
+            .map(|event| Event {
+                scope: self.hasher.hash_scope(&event.scope),
+                ..event.clone()
+            })
+            .collect();
+        self.inner.append_events_bulk(&events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let events = self.inner.list_events(&self.hasher.hash_scope(scope), range, limit)?;
+        Ok(self.restore_events(events))
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let page = self.inner.list_events_page(&self.hasher.hash_scope(scope), cursor, limit)?;
+        Ok(EventPage {
+            events: self.restore_events(page.events),
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        let events = self.inner.get_events_since(&self.hasher.hash_scope(scope), seq, limit)?;
+        Ok(self.restore_events(events))
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        let events = self.inner.get_events_by_ids(&self.hasher.hash_scope(scope), event_ids)?;
+        Ok(self.restore_events(events))
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let events = self.inner.find_events(&self.hasher.hash_scope(scope), filter)?;
+        Ok(self.restore_events(events))
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let events = self.inner.search_events(&self.hasher.hash_scope(scope), query)?;
+        Ok(self.restore_events(events))
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.inner.search_episodes(&self.hasher.hash_scope(scope), query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let lease = self.inner.acquire_lease(&self.hasher.hash_scope(scope), ttl)?;
+        Ok(lease.map(|lease| self.restore_lease(lease)))
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        let lease = self.inner.renew_lease(&self.hash_lease(lease), ttl)?;
+        Ok(lease.map(|lease| self.restore_lease(lease)))
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(&self.hash_lease(lease))
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(&self.hasher.hash_scope(scope))
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.inner.patch_working_state(&self.hasher.hash_scope(scope), patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let patches = patches
+            .into_iter()
+            .map(|(scope, patch)| (self.hasher.hash_scope(&scope), patch))
+            .collect();
+        self.inner.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(&self.hasher.hash_scope(scope))
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(&self.hasher.hash_scope(scope))
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(&self.hasher.hash_scope(scope), stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(&self.hasher.hash_scope(scope), filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.inner.upsert_fact(&self.hasher.hash_scope(scope), fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.inner.upsert_facts_bulk(&self.hasher.hash_scope(scope), facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(&self.hasher.hash_scope(scope), fact_key)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.inner.list_episodes(&self.hasher.hash_scope(scope), filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()> {
+        self.inner.append_episode(&self.hasher.hash_scope(scope), episode)
+    }
+
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        self.inner.append_episodes_bulk(&self.hasher.hash_scope(scope), episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(&self.hasher.hash_scope(scope), task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(&self.hasher.hash_scope(scope), procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(&self.hasher.hash_scope(scope), filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(&self.hasher.hash_scope(scope), candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(&self.hasher.hash_scope(scope), candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(&self.hasher.hash_scope(scope), filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(&self.hasher.hash_scope(scope), insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(&self.hasher.hash_scope(scope), expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, mut packet: MemoryPacket) -> StoreResult<()> {
+        packet.meta.scope = self.hasher.hash_scope(&packet.meta.scope);
+        self.inner.write_context_build(&self.hasher.hash_scope(scope), packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        let scope = self.hasher.hash_scope(scope);
+        let mut packets = self.inner.list_context_builds(&scope, limit)?;
+        for packet in &mut packets {
+            packet.meta.scope = self.hasher.restore_scope(&packet.meta.scope);
+        }
+        Ok(packets)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(&self.hasher.hash_scope(scope), limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        let mut activity = self.inner.tenant_activity(tenant_id, range)?;
+        for user in &mut activity {
+            user.user_id = self.hasher.restore_user_id(&user.user_id);
+        }
+        Ok(activity)
+    }
+
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        let scopes = self.inner.list_scopes(tenant_id, limit)?;
+        Ok(scopes.iter().map(|scope| self.hasher.restore_scope(scope)).collect())
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(&self.hasher.hash_scope(scope), item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(&self.hasher.hash_scope(scope), item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(&self.hasher.hash_scope(scope))
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(&self.hasher.hash_scope(scope), items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(&self.hasher.hash_scope(scope), item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(&self.hasher.hash_scope(scope), mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(&self.hasher.hash_scope(scope), kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        let tombstones = self.inner.list_tombstones(&self.hasher.hash_scope(scope), limit)?;
+        Ok(tombstones
+            .into_iter()
+            .map(|tombstone| self.restore_tombstone(tombstone))
+            .collect())
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(&self.hasher.hash_scope(scope), embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(&self.hasher.hash_scope(scope), model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(&self.hasher.hash_scope(scope), model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(&self.hasher.hash_scope(scope), outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(&self.hasher.hash_scope(scope), limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, &self.hasher.hash_user_id(user_id), locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, &self.hasher.hash_user_id(user_id))
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(map_user_ids(op, &|id| self.hasher.hash_user_id(id)))
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(&mut || {
+            let changes = write()?;
+            let hash = |id: &str| self.hasher.hash_user_id(id);
+            Ok(changes.into_iter().map(|op| map_user_ids(op, &hash)).collect())
+        })
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        let changes = self.inner.list_changes(after_seq, limit)?;
+        Ok(changes
+            .into_iter()
+            .map(|change| Change {
+                op: map_user_ids(change.op, &|id| self.hasher.restore_user_id(id)),
+                ..change
+            })
+            .collect())
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore};
+    use serde_json::json;
+
+    #[test]
+    fn stores_only_hashed_user_ids() {
+        let key = b"0123456789abcdef-app-secret";
+        let store = ScopeHashingStore::new(InMemoryStore::new(), ScopeHasher::new(key).unwrap());
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "alice@example.com".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let hashed = store.hasher().hash_user_id(&scope.user_id);
+        assert_eq!(hashed.len(), HASHED_ID_PREFIX.len() + 64);
+        let other = ScopeHasher::new(b"another-key-of-16+").unwrap();
+        assert_ne!(other.hash_user_id(&scope.user_id), hashed);
+
+        let event = Event::new(scope.clone(), EventKind::Message, json!({"text": "hi"}));
+        store.append_event(event).unwrap();
+        store.upsert_fact(&scope, Fact::new("plan", json!("pro"))).unwrap();
+
+        // The backend only ever sees the hashed id.
+        let raw = store.inner().list_scopes(None, None).unwrap();
+        assert!(raw.iter().all(|stored| stored.user_id == hashed));
+        let unhashed = store.inner().list_events(&scope, TimeRangeFilter::default(), None);
+        assert!(unhashed.unwrap().is_empty());
+
+        // Callers see their own ids.
+        let events = store.list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(events[0].scope.user_id, scope.user_id);
+        assert_eq!(store.list_scopes(None, None).unwrap()[0].user_id, scope.user_id);
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+
+        // Another process with the same key reaches the same rows but cannot restore ids.
+        let restarted = ScopeHasher::new(key).unwrap();
+        assert_eq!(restarted.hash_user_id(&scope.user_id), hashed);
+        assert_eq!(ScopeHasher::new(key).unwrap().restore_user_id(&hashed), hashed);
+        assert_eq!(store.hasher().hash_user_id(SHARED_SCOPE_ID), SHARED_SCOPE_ID);
+        assert!(ScopeHasher::new(b"short").is_err());
+    }
+
+    #[test]
+    fn hashed_ids_listed_after_a_restart_reach_the_same_rows() {
+        let key = b"0123456789abcdef-app-secret";
+        let backend = Arc::new(InMemoryStore::new());
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "alice@example.com".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let store = ScopeHashingStore::new(backend.clone(), ScopeHasher::new(key).unwrap());
+        let event = Event::new(scope.clone(), EventKind::Message, json!({"text": "hi"}));
+        store.append_event(event).unwrap();
+        store.upsert_fact(&scope, Fact::new("plan", json!("pro"))).unwrap();
+
+        let restarted = ScopeHashingStore::new(backend, ScopeHasher::new(key).unwrap());
+        let listed = restarted.list_scopes(None, None).unwrap().remove(0);
+        assert!(listed.user_id.starts_with(HASHED_ID_PREFIX));
+        assert_eq!(restarted.hasher().hash_user_id(&listed.user_id), listed.user_id);
+        let facts = restarted.list_facts(&listed, FactFilter::default()).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(restarted.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+    }
+}
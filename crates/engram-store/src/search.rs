@@ -0,0 +1,175 @@
+use engram_types::{Episode, Scope};
+use serde_json::Value;
+
+use crate::{EpisodeFilter, Event, Store, StoreResult, TimeRangeFilter};
+
+/// A full-text lookup for [`Store::search_events`] and [`Store::search_episodes`].
+#[derive(Debug, Clone, Default)]
+pub struct TextQuery {
+    /// Words or phrases to look for; a record matches when any of them occurs.
+    pub terms: Vec<String>,
+    pub time_range: Option<TimeRangeFilter>,
+    pub limit: Option<usize>,
+}
+
+impl TextQuery {
+    pub fn new<I, T>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// The trimmed terms, without those lacking a letter or digit: no backend indexes
+    /// punctuation, so they could never match.
+    pub(crate) fn searchable_terms(&self) -> Vec<&str> {
+        self.terms
+            .iter()
+            .map(|term| term.trim())
+            .filter(|term| term.chars().any(char::is_alphanumeric))
+            .collect()
+    }
+}
+
+/// The searchable text of an event: every string in its payload, in document order.
+pub(crate) fn event_text(payload: &Value) -> String {
+    fn collect<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::String(text) => out.push(text),
+            Value::Array(items) => items.iter().for_each(|item| collect(item, out)),
+            Value::Object(map) => map.values().for_each(|item| collect(item, out)),
+            _ => {}
+        }
+    }
+    let mut parts = Vec::new();
+    collect(payload, &mut parts);
+    parts.join(" ")
+}
+
+/// The searchable text of an episode: its summary, then its highlights.
+pub(crate) fn episode_text(episode: &Episode) -> String {
+    let mut text = episode.summary.clone();
+    for highlight in &episode.highlights {
+        text.push(' ');
+        text.push_str(highlight);
+    }
+    text
+}
+
+/// Answers [`Store::search_events`] by scanning the run's events for the terms as
+/// substrings, ignoring case. Stores without a text index use this.
+pub(crate) fn search_events_unindexed<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    query: &TextQuery,
+) -> StoreResult<Vec<Event>> {
+    let terms = query.searchable_terms();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let range = query.time_range.clone().unwrap_or_default();
+    let events = store.list_events(scope, range, None)?;
+    Ok(rank_matches(events, |event| event_text(&event.payload), &terms, query.limit))
+}
+
+/// The episode counterpart of [`search_events_unindexed`].
+pub(crate) fn search_episodes_unindexed<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    query: &TextQuery,
+) -> StoreResult<Vec<Episode>> {
+    let terms = query.searchable_terms();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let filter = EpisodeFilter {
+        time_range: query.time_range.clone(),
+        ..EpisodeFilter::default()
+    };
+    let episodes = store.list_episodes(scope, filter)?;
+    Ok(rank_matches(episodes, episode_text, &terms, query.limit))
+}
+
+/// Keeps the items whose text contains a term, most distinct terms first and
+/// otherwise in their original order.
+fn rank_matches<T>(
+    items: Vec<T>,
+    text: impl Fn(&T) -> String,
+    terms: &[&str],
+    limit: Option<usize>,
+) -> Vec<T> {
+    let terms: Vec<String> = terms.iter().map(|term| term.to_lowercase()).collect();
+    let mut matched: Vec<(usize, T)> = items
+        .into_iter()
+        .filter_map(|item| {
+            let text = text(&item).to_lowercase();
+            let hits = terms.iter().filter(|term| text.contains(term.as_str())).count();
+            (hits > 0).then_some((hits, item))
+        })
+        .collect();
+    matched.sort_by_key(|(hits, _)| std::cmp::Reverse(*hits));
+    matched
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(_, item)| item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, EventKind, InMemoryStore};
+    use engram_types::Purpose;
+    use serde_json::json;
+
+    #[test]
+    fn keyword_cues_recall_text_matches() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut refund = Episode::new("Issued a partial REFUND");
+        refund.tags = vec!["billing".to_string()];
+        let mut deploy = Episode::new("Deployed the release");
+        deploy.tags = vec!["ops".to_string()];
+        deploy.highlights = vec!["rolled back once".to_string()];
+        store.append_episode(&scope, refund.clone()).unwrap();
+        store.append_episode(&scope, deploy.clone()).unwrap();
+        let message = json!({"role": "user", "content": "Is my refund on its way?"});
+        let asked = Event::new(scope.clone(), EventKind::Message, message);
+        store.append_event(asked.clone()).unwrap();
+        let other = json!({"role": "user", "content": "thanks"});
+        store.append_event(Event::new(scope.clone(), EventKind::Message, other)).unwrap();
+
+        let found = store.search_episodes(&scope, &TextQuery::new(["refund", "rolled"])).unwrap();
+        assert_eq!(found.len(), 2);
+        let found = store.search_events(&scope, &TextQuery::new(["Refund"])).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].event_id, asked.event_id);
+
+        // The tag cue alone finds the deploy; the keyword adds the refund and ranks it first.
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.cues.tags = vec!["ops".to_string()];
+        request.cues.keywords = vec!["refund".to_string()];
+        let packet = build_memory_packet(&store, request.clone()).unwrap();
+        let episodes: Vec<&str> =
+            packet.long_term.episodes.iter().map(|episode| episode.summary.as_str()).collect();
+        assert_eq!(episodes, vec![refund.summary.as_str(), deploy.summary.as_str()]);
+        let quotes = &packet.short_term.key_quotes;
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].evidence_id, asked.event_id);
+
+        request.policy.max_episodes = 1;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.long_term.episodes[0].episode_id, refund.episode_id);
+        assert_eq!(packet.long_term.episodes.len(), 1);
+    }
+}
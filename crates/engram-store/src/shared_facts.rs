@@ -0,0 +1,251 @@
+use engram_types::{Fact, Scope, ScopeLevel};
+use std::collections::HashSet;
+
+use crate::{FactFilter, Store, StoreResult};
+
+/// Reserved user (and, for tenant pools, agent) id under which shared facts are stored.
+pub const SHARED_SCOPE_ID: &str = "_shared";
+
+/// Returns the scope that holds the fact pool of `level` for `scope`: the tenant pool
+/// is shared by every user and agent, the agent pool by every user of one agent.
+/// Write shared facts with `upsert_fact` on this scope.
+pub fn shared_fact_scope(scope: &Scope, level: &ScopeLevel) -> Scope {
+    let (user_id, agent_id) = match level {
+        ScopeLevel::User => return scope.clone(),
+        ScopeLevel::Agent => (SHARED_SCOPE_ID, scope.agent_id.as_str()),
+        ScopeLevel::Tenant => (SHARED_SCOPE_ID, SHARED_SCOPE_ID),
+    };
+    Scope {
+        user_id: user_id.to_string(),
+        agent_id: agent_id.to_string(),
+        ..scope.clone()
+    }
+}
+
+/// Lists user facts with the agent and tenant pools beneath them. A fact key
+/// present at a narrower level hides the same key from every wider level, so user
+/// facts win over agent facts and agent facts win over tenant facts. Pinned and
+/// unpinned facts hide each other alike.
+///
+/// Each level is asked for at most `filter.limit` facts. When that or the pinned
+/// filter may leave out a narrower fact, the narrower levels are asked for the wider
+/// level's keys, and a level whose facts were hidden is asked again for more.
+///
+/// Backends call this from `list_facts` when [`FactFilter::include_shared`] is set.
+pub(crate) fn list_facts_with_shared<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    filter: FactFilter,
+) -> StoreResult<Vec<Fact>> {
+    let level_filter = FactFilter {
+        include_shared: false,
+        ..filter.clone()
+    };
+    let partial = filter.limit.is_some() || filter.pinned.is_some();
+
+    let mut facts = Vec::new();
+    let mut seen_keys = HashSet::new();
+    let mut narrower: Vec<Scope> = Vec::new();
+    for level in [ScopeLevel::User, ScopeLevel::Agent, ScopeLevel::Tenant] {
+        let level_scope = shared_fact_scope(scope, &level);
+        let is_scope =
+            level_scope.user_id == scope.user_id && level_scope.agent_id == scope.agent_id;
+        if !matches!(level, ScopeLevel::User) && is_scope {
+            // The scope already is this pool; don't overlay it on itself.
+            continue;
+        }
+        let mut limit = filter.limit;
+        let visible = loop {
+            let level_facts = store.list_facts(
+                &level_scope,
+                FactFilter {
+                    limit,
+                    ..level_filter.clone()
+                },
+            )?;
+            let fetched = level_facts.len();
+            if partial {
+                let keys: Vec<String> = level_facts
+                    .iter()
+                    .map(|fact| fact.fact_key.clone())
+                    .filter(|key| !seen_keys.contains(key))
+                    .collect();
+                seen_keys.extend(held_keys(store, &narrower, &filter, keys)?);
+            }
+            let visible: Vec<Fact> = level_facts
+                .into_iter()
+                .filter(|fact| !seen_keys.contains(&fact.fact_key))
+                .collect();
+            match (filter.limit, limit) {
+                (Some(wanted), Some(asked)) if fetched == asked && visible.len() < wanted => {
+                    limit = Some(asked + wanted - visible.len());
+                }
+                _ => break visible,
+            }
+        };
+        seen_keys.extend(visible.iter().map(|fact| fact.fact_key.clone()));
+        facts.extend(visible.into_iter().map(|fact| match level {
+            ScopeLevel::User => fact,
+            _ => Fact {
+                scope_level: level.clone(),
+                ..fact
+            },
+        }));
+        narrower.push(level_scope);
+    }
+
+    facts.sort_by(|a, b| {
+        a.fact_key
+            .cmp(&b.fact_key)
+            .then_with(|| a.fact_id.cmp(&b.fact_id))
+    });
+    if let Some(limit) = filter.limit {
+        facts.truncate(limit);
+    }
+    Ok(facts)
+}
+
+/// Which of `keys` the `narrower` scopes hold under `filter`, pinned or not.
+fn held_keys<S: Store + ?Sized>(
+    store: &S,
+    narrower: &[Scope],
+    filter: &FactFilter,
+    keys: Vec<String>,
+) -> StoreResult<HashSet<String>> {
+    let mut held = HashSet::new();
+    if keys.is_empty() {
+        return Ok(held);
+    }
+    let lookup = FactFilter {
+        limit: None,
+        include_shared: false,
+        pinned: None,
+        keys: Some(keys),
+        ..filter.clone()
+    };
+    for scope in narrower {
+        let facts = store.list_facts(scope, lookup.clone())?;
+        held.extend(facts.into_iter().map(|fact| fact.fact_key));
+    }
+    Ok(held)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    fn upsert(store: &InMemoryStore, scope: &Scope, key: &str, value: &str) {
+        store.upsert_fact(scope, Fact::new(key, json!(value))).unwrap();
+    }
+
+    /// `key=value@level` per fact.
+    fn values(facts: &[Fact]) -> Vec<String> {
+        facts
+            .iter()
+            .map(|fact| {
+                let value = fact.value.as_str().unwrap_or_default();
+                format!("{}={}@{:?}", fact.fact_key, value, fact.scope_level)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn narrower_levels_hide_wider_pools() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let agent_pool = shared_fact_scope(&scope, &ScopeLevel::Agent);
+        let tenant_pool = shared_fact_scope(&scope, &ScopeLevel::Tenant);
+        upsert(&store, &tenant_pool, "org.timezone", "UTC");
+        upsert(&store, &tenant_pool, "org.tone", "formal");
+        upsert(&store, &tenant_pool, "user.city", "Paris");
+        upsert(&store, &agent_pool, "org.tone", "friendly");
+        upsert(&store, &agent_pool, "user.city", "Porto");
+        upsert(&store, &scope, "user.city", "Lisbon");
+        let other_agent = Scope {
+            agent_id: "agent2".to_string(),
+            ..scope.clone()
+        };
+        upsert(&store, &shared_fact_scope(&other_agent, &ScopeLevel::Agent), "org.tone", "terse");
+
+        let shared = FactFilter {
+            include_shared: true,
+            ..FactFilter::default()
+        };
+        let facts = store.list_facts(&scope, shared.clone()).unwrap();
+        assert_eq!(
+            values(&facts),
+            ["org.timezone=UTC@Tenant", "org.tone=friendly@Agent", "user.city=Lisbon@User"]
+        );
+
+        // The agent pool itself sees the tenant pool beneath it, and only once.
+        let facts = store.list_facts(&agent_pool, shared.clone()).unwrap();
+        assert_eq!(
+            values(&facts),
+            ["org.timezone=UTC@Tenant", "org.tone=friendly@User", "user.city=Porto@User"]
+        );
+        let facts = store.list_facts(&tenant_pool, shared.clone()).unwrap();
+        assert_eq!(
+            values(&facts),
+            ["org.timezone=UTC@User", "org.tone=formal@User", "user.city=Paris@User"]
+        );
+
+        // The limit applies after precedence, to the merged list.
+        let limited = FactFilter {
+            limit: Some(2),
+            ..shared
+        };
+        let facts = store.list_facts(&scope, limited).unwrap();
+        assert_eq!(values(&facts), ["org.timezone=UTC@Tenant", "org.tone=friendly@Agent"]);
+    }
+
+    #[test]
+    fn pinned_and_unpinned_facts_hide_each_other_under_a_limit() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let tenant_pool = shared_fact_scope(&scope, &ScopeLevel::Tenant);
+        let pin = |store: &InMemoryStore, scope: &Scope, key: &str, value: &str| {
+            let fact = Fact {
+                pinned: true,
+                ..Fact::new(key, json!(value))
+            };
+            store.upsert_fact(scope, fact).unwrap();
+        };
+        pin(&store, &scope, "a.lang", "pt");
+        pin(&store, &tenant_pool, "z.tone", "formal");
+        for (key, value) in [("a.lang", "en"), ("b.city", "Paris"), ("c.unit", "metric")] {
+            upsert(&store, &tenant_pool, key, value);
+        }
+        upsert(&store, &scope, "z.tone", "casual");
+
+        let unpinned = FactFilter {
+            include_shared: true,
+            pinned: Some(false),
+            limit: Some(1),
+            ..FactFilter::default()
+        };
+        // The user's pinned `a.lang` hides the tenant's, which the first page held.
+        let facts = store.list_facts(&scope, unpinned).unwrap();
+        assert_eq!(values(&facts), ["b.city=Paris@Tenant"]);
+
+        let pinned = FactFilter {
+            include_shared: true,
+            pinned: Some(true),
+            ..FactFilter::default()
+        };
+        // The user's unpinned `z.tone` hides the tenant's pinned one.
+        let facts = store.list_facts(&scope, pinned).unwrap();
+        assert_eq!(values(&facts), ["a.lang=pt@User"]);
+    }
+}
@@ -0,0 +1,238 @@
+use engram_types::{JsonMap, WorkingState};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Validator;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::warn;
+
+use crate::payload_schema::describe_error;
+use crate::{StoreError, StoreResult, ValidationMode, WorkingStatePatch};
+
+/// JSON Schemas for the [`slots`](WorkingState::slots) and
+/// [`constraints`](WorkingState::constraints) of a task type's working state; either
+/// may be left out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlotSchema {
+    #[serde(default)]
+    pub slots: Option<Value>,
+    #[serde(default)]
+    pub constraints: Option<Value>,
+}
+
+struct RegisteredSlotSchema {
+    mode: ValidationMode,
+    slots: Option<Validator>,
+    constraints: Option<Validator>,
+    required_slots: Vec<String>,
+    required_constraints: Vec<String>,
+}
+
+/// Working state schemas keyed by task type, checked by [`ValidatingStore`] on
+/// patches that name their [`task_type`](WorkingStatePatch::task_type).
+///
+/// Slots fill in over a run, so a patch is checked as a partial state: properties the
+/// schema lists as `required` may be missing. The composer reports the ones still
+/// missing as open loops instead, when its policy has the registry
+/// ([`RecallPolicy::slot_schemas`](crate::RecallPolicy::slot_schemas)).
+///
+/// [`ValidatingStore`]: crate::ValidatingStore
+#[derive(Default)]
+pub struct SlotSchemaRegistry {
+    schemas: RwLock<HashMap<String, RegisteredSlotSchema>>,
+}
+
+impl std::fmt::Debug for SlotSchemaRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let task_types: Vec<String> = match self.schemas.read() {
+            Ok(guard) => guard.keys().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+        f.debug_struct("SlotSchemaRegistry")
+            .field("task_types", &task_types)
+            .finish()
+    }
+}
+
+impl SlotSchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `schema` for `task_type`, replacing the previous one.
+    pub fn register(
+        &self,
+        task_type: impl Into<String>,
+        schema: &SlotSchema,
+        mode: ValidationMode,
+    ) -> StoreResult<()> {
+        let task_type = task_type.into();
+        let validator = |field: &str, schema: &Option<Value>| {
+            schema
+                .as_ref()
+                .map(|schema| {
+                    jsonschema::validator_for(schema).map_err(|err| {
+                        StoreError::InvalidInput(format!(
+                            "invalid {} schema for task type {}: {}",
+                            field, task_type, err
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        let registered = RegisteredSlotSchema {
+            mode,
+            slots: validator("slots", &schema.slots)?,
+            constraints: validator("constraints", &schema.constraints)?,
+            required_slots: required_properties(&schema.slots),
+            required_constraints: required_properties(&schema.constraints),
+        };
+        let mut guard = self.schemas.write().map_err(|_| StoreError::Poisoned)?;
+        guard.insert(task_type, registered);
+        Ok(())
+    }
+
+    pub fn unregister(&self, task_type: &str) -> StoreResult<bool> {
+        let mut guard = self.schemas.write().map_err(|_| StoreError::Poisoned)?;
+        Ok(guard.remove(task_type).is_some())
+    }
+
+    /// Checks the slots and constraints `patch` sets against the schema of its task
+    /// type; patches without a task type, or for one without a schema, pass.
+    pub fn validate_patch(&self, patch: &WorkingStatePatch) -> StoreResult<()> {
+        let Some(task_type) = &patch.task_type else {
+            return Ok(());
+        };
+        let guard = self.schemas.read().map_err(|_| StoreError::Poisoned)?;
+        let Some(registered) = guard.get(task_type) else {
+            return Ok(());
+        };
+
+        let fields = [
+            ("slots", &registered.slots, &patch.slots),
+            ("constraints", &registered.constraints, &patch.constraints),
+        ];
+        for (field, validator, value) in fields {
+            let (Some(validator), Some(value)) = (validator, value) else {
+                continue;
+            };
+            let errors = partial_state_errors(validator, value);
+            if errors.is_empty() {
+                continue;
+            }
+
+            let message = format!(
+                "{} violate the schema of task type {}: {}",
+                field,
+                task_type,
+                errors.join("; ")
+            );
+            match registered.mode {
+                ValidationMode::Strict => return Err(StoreError::InvalidInput(message)),
+                ValidationMode::Warn => warn!("{}", message),
+            }
+        }
+        Ok(())
+    }
+
+    /// The required slots and constraints of `task_type` that `state` has yet to
+    /// fill, as `missing slot: <name>` and `missing constraint: <name>`.
+    pub fn open_loops(&self, task_type: &str, state: &WorkingState) -> StoreResult<Vec<String>> {
+        let guard = self.schemas.read().map_err(|_| StoreError::Poisoned)?;
+        let Some(registered) = guard.get(task_type) else {
+            return Ok(Vec::new());
+        };
+        let missing = |kind: &str, required: &[String], values: &JsonMap| {
+            required
+                .iter()
+                .filter(|name| values.get(*name).is_none_or(Value::is_null))
+                .map(|name| format!("missing {}: {}", kind, name))
+                .collect::<Vec<_>>()
+        };
+        let mut loops = missing("slot", &registered.required_slots, &state.slots);
+        loops.extend(missing(
+            "constraint",
+            &registered.required_constraints,
+            &state.constraints,
+        ));
+        Ok(loops)
+    }
+}
+
+fn required_properties(schema: &Option<Value>) -> Vec<String> {
+    schema
+        .as_ref()
+        .and_then(|schema| schema.get("required"))
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Violations of `validator` by `values`, except top-level properties it requires.
+fn partial_state_errors(validator: &Validator, values: &JsonMap) -> Vec<String> {
+    let value = Value::Object(values.clone().into_iter().collect());
+    validator
+        .iter_errors(&value)
+        .filter(|err| {
+            let top_level = err.instance_path().to_string().is_empty();
+            !(top_level && matches!(err.kind(), ValidationErrorKind::Required { .. }))
+        })
+        .map(|err| describe_error(&err))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_memory_packet, BuildRequest, InMemoryStore, PayloadSchemaRegistry, Store,
+        ValidatingStore,
+    };
+    use engram_types::{Purpose, Scope};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn slot_patches_are_validated_and_missing_slots_reported() {
+        let registry = Arc::new(SlotSchemaRegistry::new());
+        let schema = SlotSchema {
+            slots: Some(json!({
+                "type": "object",
+                "properties": {
+                    "order_id": {"type": "string"},
+                    "refund_amount": {"type": "number"}
+                },
+                "required": ["order_id", "refund_amount"]
+            })),
+            constraints: None,
+        };
+        registry.register("refund", &schema, ValidationMode::Strict).unwrap();
+        let payload_schemas = Arc::new(PayloadSchemaRegistry::new());
+        let store = ValidatingStore::new(InMemoryStore::new(), payload_schemas)
+            .with_slot_schemas(registry.clone());
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+
+        let patch = |slots: Value| WorkingStatePatch {
+            slots: serde_json::from_value(slots).unwrap(),
+            task_type: Some("refund".to_string()),
+            ..WorkingStatePatch::default()
+        };
+        let err = store.patch_working_state(&scope, patch(json!({"order_id": 42}))).unwrap_err();
+        assert!(matches!(err, StoreError::InvalidInput(message) if message.contains("order_id")));
+        store.patch_working_state(&scope, patch(json!({"order_id": "42"}))).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Responder);
+        request.task_type = Some("refund".to_string());
+        request.persist = false;
+        request.policy.slot_schemas = Some(registry);
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert_eq!(packet.short_term.open_loops, ["missing slot: refund_amount"]);
+    }
+}
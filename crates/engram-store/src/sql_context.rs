@@ -0,0 +1,109 @@
+use engram_types::Scope;
+use sha2::{Digest, Sha256};
+
+use crate::StoreError;
+
+/// What a SQL backend was doing when a call failed: the store operation, the table it
+/// works on and the parameters that picked the rows. Storage errors carry it so that a
+/// log line points at the failing call without echoing tenant or user data.
+pub(crate) struct SqlContext<'a> {
+    operation: &'static str,
+    table: &'static str,
+    params: Vec<&'a str>,
+}
+
+impl<'a> SqlContext<'a> {
+    pub(crate) fn new(operation: &'static str, table: &'static str, params: &[&'a str]) -> Self {
+        Self {
+            operation,
+            table,
+            params: params.to_vec(),
+        }
+    }
+
+    /// A context whose parameters are the ids of `scope`.
+    pub(crate) fn scoped(operation: &'static str, table: &'static str, scope: &'a Scope) -> Self {
+        Self::new(
+            operation,
+            table,
+            &[
+                &scope.tenant_id,
+                &scope.user_id,
+                &scope.agent_id,
+                &scope.session_id,
+                &scope.run_id,
+            ],
+        )
+    }
+
+    pub(crate) fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// Prefixes a [`StoreError::Storage`] message with the operation, the table and a
+    /// fingerprint of the parameters; other errors pass through unchanged.
+    pub(crate) fn wrap(&self, err: StoreError) -> StoreError {
+        match err {
+            StoreError::Storage(message) if self.params.is_empty() => StoreError::Storage(
+                format!("{} on {}: {}", self.operation, self.table, message),
+            ),
+            StoreError::Storage(message) => StoreError::Storage(format!(
+                "{} on {} (params {}): {}",
+                self.operation,
+                self.table,
+                param_fingerprint(&self.params),
+                message
+            )),
+            err => err,
+        }
+    }
+}
+
+/// A short digest of `params`: the same values always give the same fingerprint, so
+/// failures can be correlated across log lines, but the values cannot be read back.
+pub(crate) fn param_fingerprint(params: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for param in params {
+        hasher.update((param.len() as u64).to_le_bytes());
+        hasher.update(param.as_bytes());
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest[..6].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}#{}", params.len(), hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_errors_name_the_call_without_its_values() {
+        let scope = Scope {
+            tenant_id: "acme".to_string(),
+            user_id: "alice@example.com".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let context = SqlContext::scoped("list_facts", "facts", &scope);
+        let StoreError::Storage(message) = context.wrap(StoreError::Storage("disk full".into()))
+        else {
+            panic!("expected a storage error");
+        };
+        assert!(message.starts_with("list_facts on facts (params 5#"), "{}", message);
+        assert!(message.ends_with("): disk full"));
+        assert!(!message.contains("alice"));
+
+        let again = SqlContext::scoped("list_facts", "facts", &scope);
+        let err = again.wrap(StoreError::Storage("disk full".into()));
+        assert!(matches!(err, StoreError::Storage(repeated) if repeated == message));
+        // Moving a character between parameters changes the fingerprint.
+        assert_ne!(param_fingerprint(&["ab", "c"]), param_fingerprint(&["a", "bc"]));
+
+        let context = SqlContext::new("append_change", "changelog", &[]);
+        let err = context.wrap(StoreError::Storage("locked".into()));
+        let expected = "append_change on changelog: locked";
+        assert!(matches!(err, StoreError::Storage(message) if message == expected));
+        assert!(matches!(context.wrap(StoreError::NotFound), StoreError::NotFound));
+    }
+}
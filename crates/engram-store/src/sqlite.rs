@@ -1,27 +1,58 @@
 use chrono::{DateTime, TimeZone, Utc};
 use engram_types::{
-    CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger, InsightType,
-    MemoryPacket, Procedure, Scope, ScopeLevel, ValidationState, WorkingState,
+    CandidateStatus, CompressionLevel, Episode, Fact, FactStatus, InsightItem, InsightTrigger,
+    InsightType, MemoryPacket, Procedure, ProcedureCandidate, Scope, ScopeLevel, ValidationState,
+    WorkingState,
 };
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::types::{Type, Value as SqlValue};
 use rusqlite::{params_from_iter, Connection};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::warn;
 
+use crate::analytics::collect_user_activity;
+use crate::changelog::PinnedConnections;
+use crate::composer::stored_payload;
+use crate::fact_history::next_fact_version;
+use crate::lease::LeaseTable;
+use crate::outcome::outcome_for_run;
+use crate::pagination::{event_page, page_request};
+use crate::metrics::MetricsRecorder;
+use crate::search::{episode_text, event_text, search_episodes_unindexed, search_events_unindexed};
+use crate::sql_context::SqlContext;
+use crate::table_prefix::TablePrefix;
+use crate::tags::normalize_event_tags;
+use crate::timeout::{effective_timeout, DEFAULT_BUSY_TIMEOUT};
 use crate::{
-    EpisodeFilter, Event, EventKind, FactFilter, InsightFilter, StmState, Store, StoreError,
-    StoreResult, TimeRangeFilter, WorkingStatePatch,
+    apply_working_state_patch, check_state_version, correlation_id, find_events_unindexed,
+    list_episodes_unindexed, state_version_conflict, Change, ChangeOp, ContextBuildSummary,
+    DeleteMode, EpisodeFilter, Event, EventFilter, EventKind, EventPage, FactFilter, FactVersion,
+    InsightFilter, Lease, MemoryEmbedding, MemoryKind, MemoryRef, OptionalTables,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunStatus, RunWorkingState,
+    SourceCredibility, StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult,
+    Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+    PREFERENCE_KEY_PREFIX,
 };
 
-const SCHEMA_VERSION: i64 = 1;
+const SCHEMA_VERSION: i64 = 8;
+/// FTS5 tables holding the searchable text of events and episodes.
+const TEXT_INDEX: [&str; 2] = ["event_text", "episode_text"];
 
 pub struct SqliteStore {
     path: PathBuf,
     pool: Pool<SqliteConnectionManager>,
+    tables: TablePrefix,
+    statement_timeout: Option<Duration>,
+    optional: OptionalTables,
+    leases: LeaseTable,
+    pinned: PinnedConnections<PooledConnection<SqliteConnectionManager>>,
+    metrics: MetricsRecorder,
 }
 
 impl std::fmt::Debug for SqliteStore {
@@ -34,10 +65,25 @@ impl std::fmt::Debug for SqliteStore {
 
 impl SqliteStore {
     pub fn new<P: Into<PathBuf>>(path: P) -> StoreResult<Self> {
-        let path = path.into();
+        Self::open(path.into(), TablePrefix::default())
+    }
+
+    /// Prefixes every engram table and index name with `prefix` (e.g. `engram_`), so
+    /// engram can share a database file with an application's own tables. The prefix
+    /// must be a lowercase SQL identifier of at most
+    /// [`MAX_TABLE_PREFIX_LEN`](crate::MAX_TABLE_PREFIX_LEN) characters.
+    pub fn with_table_prefix<P: Into<PathBuf>>(path: P, prefix: &str) -> StoreResult<Self> {
+        Self::open(path.into(), TablePrefix::new(prefix)?)
+    }
+
+    pub fn new_in_memory() -> StoreResult<Self> {
+        Self::open_in_memory(TablePrefix::default())
+    }
+
+    fn open(path: PathBuf, tables: TablePrefix) -> StoreResult<Self> {
         let as_str = path.to_string_lossy();
         if as_str == ":memory:" {
-            return Self::new_in_memory();
+            return Self::open_in_memory(tables);
         }
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
@@ -47,13 +93,13 @@ impl SqliteStore {
         // Initialize DB and Schema sequentially before creating the pool
         // to avoid "database is locked" errors when multiple pool connections
         // try to set WAL mode or run migrations simultaneously.
-        {
+        let optional = {
             let mut conn = Connection::open(&path)
                 .map_err(|err| StoreError::Storage(err.to_string()))?;
             configure_connection(&mut conn, true)
                 .map_err(|err| StoreError::Storage(err.to_string()))?;
-            ensure_schema(&conn)?;
-        }
+            ensure_schema(&conn, &tables)?
+        };
         
         let manager = SqliteConnectionManager::file(&path)
             .with_init(|conn| configure_connection(conn, true));
@@ -63,108 +109,259 @@ impl SqliteStore {
         Ok(Self {
             path,
             pool,
+            tables,
+            statement_timeout: None,
+            optional,
+            leases: LeaseTable::default(),
+            pinned: PinnedConnections::default(),
+            metrics: MetricsRecorder::default(),
         })
     }
 
-    pub fn new_in_memory() -> StoreResult<Self> {
+    fn open_in_memory(tables: TablePrefix) -> StoreResult<Self> {
         let manager = SqliteConnectionManager::memory()
             .with_init(|conn| configure_connection(conn, false));
         let pool = Pool::new(manager)
             .map_err(|err| StoreError::Storage(err.to_string()))?;
             
-        let mut conn = pool.get().map_err(|err| StoreError::Storage(err.to_string()))?;
-        ensure_schema(&mut conn)?;
-        
+        let conn = pool.get().map_err(|err| StoreError::Unavailable(err.to_string()))?;
+        let optional = ensure_schema(&conn, &tables)?;
+        drop(conn);
+
         Ok(Self {
             path: PathBuf::from(":memory:"),
             pool,
+            tables,
+            statement_timeout: None,
+            optional,
+            leases: LeaseTable::default(),
+            pinned: PinnedConnections::default(),
+            metrics: MetricsRecorder::default(),
         })
     }
 
-    pub fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
-        if events.is_empty() {
-            return Ok(());
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the last change log seq recorded for the named sync target, or 0.
+    pub fn load_sync_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.with_connection(SqlContext::new("load_sync_cursor", "sync_cursors", &[name]), |conn| {
+            let result = conn.query_row(
+                &self.tables.sql("SELECT seq FROM sync_cursors WHERE name = ?"),
+                params_from_iter(vec![SqlValue::Text(name.to_string())]),
+                |row| row.get::<_, i64>(0),
+            );
+            match result {
+                Ok(seq) => Ok(seq as u64),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    pub fn save_sync_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.with_connection(SqlContext::new("save_sync_cursor", "sync_cursors", &[name]), |conn| {
+            conn.execute(
+                &self.tables.sql(
+                    "INSERT INTO sync_cursors (name, seq, updated_at) VALUES (?, ?, ?)
+                     ON CONFLICT(name) DO UPDATE SET seq = excluded.seq, updated_at = excluded.updated_at",
+                ),
+                params_from_iter(vec![
+                    SqlValue::Text(name.to_string()),
+                    SqlValue::Integer(seq as i64),
+                    SqlValue::Integer(to_millis(Utc::now())),
+                ]),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Bounds how long calls wait for a database lock, in place of SQLite's default
+    /// of five seconds. A call that gives up fails with [`StoreError::Timeout`].
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    fn insert_episode(
+        &self,
+        conn: &Connection,
+        scope: &Scope,
+        episode: &Episode,
+    ) -> StoreResult<()> {
+        if self.optional.text_index {
+            insert_episode_text(conn, &self.tables, scope, episode)?;
         }
-        self.with_connection(|conn| {
-            let tx = conn.transaction()?;
-            let mut stmt_event = tx.prepare(
-                "
-                INSERT INTO events (
-                    event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                    ts, kind, payload, tags, entities
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ",
+        conn.execute(
+            &self.tables.sql("
+            INSERT INTO episodes (
+                tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
+                highlights, tags, entities, sources, compression_level, recency_score,
+                lang
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "),
+            params_from_iter(vec![
+                SqlValue::Text(scope.tenant_id.clone()),
+                SqlValue::Text(scope.user_id.clone()),
+                SqlValue::Text(scope.agent_id.clone()),
+                SqlValue::Text(episode.episode_id.clone()),
+                SqlValue::Integer(to_millis(episode.time_range.start)),
+                option_ts_to_value(episode.time_range.end),
+                SqlValue::Text(episode.summary.clone()),
+                SqlValue::Text(encode_json(&episode.highlights)?),
+                SqlValue::Text(encode_json(&episode.tags)?),
+                SqlValue::Text(encode_json(&episode.entities)?),
+                SqlValue::Text(encode_json(&episode.sources)?),
+                SqlValue::Text(compression_level_to_str(&episode.compression_level).to_string()),
+                option_f64_to_value(episode.recency_score),
+                episode.lang.clone().map_or(SqlValue::Null, SqlValue::Text),
+            ]),
+        )?;
+        if self.optional.episode_index {
+            insert_episode_tags(
+                conn, &self.tables,
+                scope,
+                &episode.episode_id,
+                &episode.tags,
+                &episode.entities,
             )?;
-            let mut stmt_tag = tx.prepare(
-                "
-                INSERT OR IGNORE INTO event_tags (
-                    tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
-                ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                ",
+        }
+        Ok(())
+    }
+
+    /// Removes the scope's records of `kind`, or only the one with `id`, with their
+    /// index rows, suppressions and embeddings, keeping tombstones of them in soft
+    /// mode. Returns how many were removed.
+    fn remove_records(
+        &self,
+        conn: &Connection,
+        scope: &Scope,
+        kind: RecordKind,
+        id: Option<&str>,
+        mode: DeleteMode,
+    ) -> StoreResult<usize> {
+        let (table, id_column) = kind.table();
+        let (mut filter, mut params) = if kind.run_level() {
+            (
+                "tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?"
+                    .to_string(),
+                scope_params(scope),
+            )
+        } else {
+            (
+                "tenant_id = ? AND user_id = ? AND agent_id = ?".to_string(),
+                scope_params_ltm(scope),
+            )
+        };
+        if let Some(id) = id {
+            filter.push_str(&format!(" AND {} = ?", id_column));
+            params.push(SqlValue::Text(id.to_string()));
+        }
+        if mode == DeleteMode::Soft {
+            for tombstone in load_tombstones(conn, &self.tables, scope, kind, &filter, &params)? {
+                insert_tombstone(conn, &self.tables, &tombstone)?;
+            }
+        }
+        let mut indexes = Vec::new();
+        if kind == RecordKind::Event && self.optional.text_index {
+            conn.execute(
+                &self.tables.sql(&format!(
+                    "DELETE FROM event_text
+                     WHERE event_id IN (SELECT event_id FROM events WHERE {})",
+                    filter
+                )),
+                params_from_iter(&params),
             )?;
-            let mut stmt_entity = tx.prepare(
-                "
-                INSERT OR IGNORE INTO event_entities (
-                    tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
-                ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                ",
+        }
+        if kind == RecordKind::Event && self.optional.event_index {
+            indexes.extend(["event_tags", "event_entities"]);
+        }
+        if kind == RecordKind::Episode && self.optional.text_index {
+            indexes.push("episode_text");
+        }
+        if kind == RecordKind::Episode && self.optional.episode_index {
+            indexes.extend(["episode_tags", "episode_entities"]);
+        }
+        for index in indexes {
+            conn.execute(
+                &self.tables.sql(&format!("DELETE FROM {} WHERE {}", index, filter)),
+                params_from_iter(&params),
             )?;
-            for event in events {
-                stmt_event.execute(params_from_iter(vec![
-                    SqlValue::Text(event.event_id.clone()),
-                    SqlValue::Text(event.scope.tenant_id.clone()),
-                    SqlValue::Text(event.scope.user_id.clone()),
-                    SqlValue::Text(event.scope.agent_id.clone()),
-                    SqlValue::Text(event.scope.session_id.clone()),
-                    SqlValue::Text(event.scope.run_id.clone()),
-                    SqlValue::Integer(to_millis(event.ts)),
-                    SqlValue::Text(event_kind_to_str(&event.kind).to_string()),
-                    SqlValue::Text(encode_json(&event.payload)?),
-                    SqlValue::Text(encode_json(&event.tags)?),
-                    SqlValue::Text(encode_json(&event.entities)?),
-                ]))?;
-
-                for tag in unique_values(&event.tags) {
-                    stmt_tag.execute(params_from_iter(vec![
-                        SqlValue::Text(event.scope.tenant_id.clone()),
-                        SqlValue::Text(event.scope.user_id.clone()),
-                        SqlValue::Text(event.scope.agent_id.clone()),
-                        SqlValue::Text(event.scope.session_id.clone()),
-                        SqlValue::Text(event.scope.run_id.clone()),
-                        SqlValue::Text(event.event_id.clone()),
-                        SqlValue::Text(tag),
-                    ]))?;
-                }
-                for entity in unique_values(&event.entities) {
-                    stmt_entity.execute(params_from_iter(vec![
-                        SqlValue::Text(event.scope.tenant_id.clone()),
-                        SqlValue::Text(event.scope.user_id.clone()),
-                        SqlValue::Text(event.scope.agent_id.clone()),
-                        SqlValue::Text(event.scope.session_id.clone()),
-                        SqlValue::Text(event.scope.run_id.clone()),
-                        SqlValue::Text(event.event_id.clone()),
-                        SqlValue::Text(entity),
-                    ]))?;
-                }
+        }
+        if kind == RecordKind::Fact && mode == DeleteMode::Hard {
+            conn.execute(
+                &self.tables.sql(&format!("DELETE FROM fact_versions WHERE {}", filter)),
+                params_from_iter(&params),
+            )?;
+        }
+        if let Some(memory_kind) = kind.memory_kind() {
+            let mut params = scope_params_ltm(scope);
+            params.push(SqlValue::Text(memory_kind_to_str(&memory_kind).to_string()));
+            let mut filter =
+                "tenant_id = ? AND user_id = ? AND agent_id = ? AND item_kind = ?".to_string();
+            if let Some(id) = id {
+                filter.push_str(" AND item_id = ?");
+                params.push(SqlValue::Text(id.to_string()));
             }
-            drop(stmt_entity);
-            drop(stmt_tag);
-            drop(stmt_event);
-            tx.commit()?;
-            Ok(())
-        })
+            for table in ["suppressions", "memory_embeddings"] {
+                conn.execute(
+                    &self.tables.sql(&format!("DELETE FROM {} WHERE {}", table, filter)),
+                    params_from_iter(&params),
+                )?;
+            }
+        }
+        let removed = conn.execute(
+            &self.tables.sql(&format!("DELETE FROM {} WHERE {}", table, filter)),
+            params_from_iter(params),
+        )?;
+        Ok(removed)
     }
 
-    pub fn path(&self) -> &Path {
-        &self.path
+    /// Runs `f` on a pooled connection and counts the call under the context's
+    /// operation; storage errors are tagged with `context`.
+    fn with_connection<F, T>(&self, context: SqlContext<'_>, f: F) -> StoreResult<T>
+    where
+        F: FnOnce(&mut Connection) -> StoreResult<T>,
+    {
+        let started = Instant::now();
+        let result = self.checkout(f).map_err(|err| context.wrap(err));
+        self.metrics.record(context.operation(), started.elapsed(), result.is_err());
+        result
     }
 
-    fn with_connection<F, T>(&self, f: F) -> StoreResult<T>
+    fn checkout<F, T>(&self, f: F) -> StoreResult<T>
     where
         F: FnOnce(&mut Connection) -> StoreResult<T>,
     {
-        let mut conn = self.pool.get().map_err(|err| StoreError::Storage(err.to_string()))?;
-        f(&mut conn)
+        let connect = || self.pool.get().map_err(|err| StoreError::Unavailable(err.to_string()));
+        self.pinned.checkout(connect, |conn| {
+            // Set on every checkout: pooled connections keep the last call's busy handler.
+            let timeout =
+                effective_timeout(self.statement_timeout).unwrap_or(DEFAULT_BUSY_TIMEOUT);
+            conn.busy_timeout(timeout)?;
+            f(conn)
+        })
+    }
+
+    fn context_build_rows(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<String>> {
+        let context = SqlContext::scoped("context_build_rows", "context_builds", scope);
+        self.with_connection(context, |conn| {
+            let mut sql = String::from(
+                "SELECT packet_json FROM context_builds
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                 ORDER BY ts ASC",
+            );
+            let mut params = scope_params(scope);
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), |row| row.get::<_, String>(0))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+        })
     }
 }
 
@@ -185,18 +382,18 @@ fn configure_connection(conn: &mut Connection, use_wal: bool) -> Result<(), rusq
     Ok(())
 }
 
-fn ensure_schema(conn: &Connection) -> StoreResult<()> {
+fn ensure_schema(conn: &Connection, tables: &TablePrefix) -> StoreResult<OptionalTables> {
     conn.execute_batch(
-        "
+        &tables.sql("
             CREATE TABLE IF NOT EXISTS schema_migrations (
                 version INTEGER NOT NULL,
                 applied_at INTEGER NOT NULL
             );
-            ",
+            "),
     )?;
 
     let current = match conn.query_row(
-        "SELECT version FROM schema_migrations ORDER BY version DESC LIMIT 1",
+        &tables.sql("SELECT version FROM schema_migrations ORDER BY version DESC LIMIT 1"),
         [],
         |row| row.get::<_, i64>(0),
     ) {
@@ -212,15 +409,12 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
         )));
     }
 
-    if current < SCHEMA_VERSION && current != 0 {
-        return Err(StoreError::Storage(format!(
-            "database schema version {} requires migration to {}",
-            current, SCHEMA_VERSION
-        )));
+    if current == 1 {
+        migrate_event_seq(conn, tables)?;
     }
 
     conn.execute_batch(
-        "
+        &tables.sql("
             CREATE TABLE IF NOT EXISTS events (
                 event_id TEXT PRIMARY KEY,
                 tenant_id TEXT NOT NULL,
@@ -232,55 +426,23 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
                 kind TEXT NOT NULL,
                 payload TEXT NOT NULL,
                 tags TEXT NOT NULL,
-                entities TEXT NOT NULL
+                entities TEXT NOT NULL,
+                seq INTEGER NOT NULL DEFAULT 0,
+                lang TEXT
             );
             CREATE INDEX IF NOT EXISTS events_scope_ts
                 ON events (tenant_id, user_id, agent_id, session_id, run_id, ts);
-            CREATE TABLE IF NOT EXISTS event_tags (
-                tenant_id TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                agent_id TEXT NOT NULL,
-                session_id TEXT NOT NULL,
-                run_id TEXT NOT NULL,
-                event_id TEXT NOT NULL,
-                tag TEXT NOT NULL,
-                PRIMARY KEY (
-                    tenant_id,
-                    user_id,
-                    agent_id,
-                    session_id,
-                    run_id,
-                    event_id,
-                    tag
-                )
-            );
-            CREATE INDEX IF NOT EXISTS event_tags_scope_tag
-                ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag);
-            CREATE INDEX IF NOT EXISTS event_tags_scope_event
-                ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, event_id);
-
-            CREATE TABLE IF NOT EXISTS event_entities (
+            CREATE UNIQUE INDEX IF NOT EXISTS events_scope_seq
+                ON events (tenant_id, user_id, agent_id, session_id, run_id, seq);
+            CREATE TABLE IF NOT EXISTS event_sequences (
                 tenant_id TEXT NOT NULL,
                 user_id TEXT NOT NULL,
                 agent_id TEXT NOT NULL,
                 session_id TEXT NOT NULL,
                 run_id TEXT NOT NULL,
-                event_id TEXT NOT NULL,
-                entity TEXT NOT NULL,
-                PRIMARY KEY (
-                    tenant_id,
-                    user_id,
-                    agent_id,
-                    session_id,
-                    run_id,
-                    event_id,
-                    entity
-                )
+                last_seq INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
             );
-            CREATE INDEX IF NOT EXISTS event_entities_scope_entity
-                ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity);
-            CREATE INDEX IF NOT EXISTS event_entities_scope_event
-                ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, event_id);
 
             CREATE TABLE IF NOT EXISTS wm_state (
                 tenant_id TEXT NOT NULL,
@@ -318,11 +480,27 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
                 sources TEXT NOT NULL,
                 scope_level TEXT NOT NULL,
                 notes TEXT NOT NULL,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                lang TEXT,
                 PRIMARY KEY (tenant_id, user_id, agent_id, fact_id)
             );
             CREATE INDEX IF NOT EXISTS facts_scope_status
                 ON facts (tenant_id, user_id, agent_id, status);
 
+            CREATE TABLE IF NOT EXISTS fact_versions (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                fact_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                fact_key TEXT NOT NULL,
+                fact_json TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, fact_id, version)
+            );
+            CREATE INDEX IF NOT EXISTS fact_versions_scope_key
+                ON fact_versions (tenant_id, user_id, agent_id, fact_key, recorded_at);
+
             CREATE TABLE IF NOT EXISTS episodes (
                 tenant_id TEXT NOT NULL,
                 user_id TEXT NOT NULL,
@@ -337,35 +515,11 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
                 sources TEXT NOT NULL,
                 compression_level TEXT NOT NULL,
                 recency_score REAL,
+                lang TEXT,
                 PRIMARY KEY (tenant_id, user_id, agent_id, episode_id)
             );
             CREATE INDEX IF NOT EXISTS episodes_scope_start
                 ON episodes (tenant_id, user_id, agent_id, start_ts);
-            CREATE TABLE IF NOT EXISTS episode_tags (
-                tenant_id TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                agent_id TEXT NOT NULL,
-                episode_id TEXT NOT NULL,
-                tag TEXT NOT NULL,
-                PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, tag)
-            );
-            CREATE INDEX IF NOT EXISTS episode_tags_scope_tag
-                ON episode_tags (tenant_id, user_id, agent_id, tag);
-            CREATE INDEX IF NOT EXISTS episode_tags_scope_episode
-                ON episode_tags (tenant_id, user_id, agent_id, episode_id);
-
-            CREATE TABLE IF NOT EXISTS episode_entities (
-                tenant_id TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                agent_id TEXT NOT NULL,
-                episode_id TEXT NOT NULL,
-                entity TEXT NOT NULL,
-                PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, entity)
-            );
-            CREATE INDEX IF NOT EXISTS episode_entities_scope_entity
-                ON episode_entities (tenant_id, user_id, agent_id, entity);
-            CREATE INDEX IF NOT EXISTS episode_entities_scope_episode
-                ON episode_entities (tenant_id, user_id, agent_id, episode_id);
 
             CREATE TABLE IF NOT EXISTS procedures (
                 tenant_id TEXT NOT NULL,
@@ -382,6 +536,25 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
             CREATE INDEX IF NOT EXISTS procedures_scope_task
                 ON procedures (tenant_id, user_id, agent_id, task_type);
 
+            CREATE TABLE IF NOT EXISTS procedure_candidates (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                candidate_id TEXT NOT NULL,
+                task_type TEXT NOT NULL,
+                procedure_json TEXT NOT NULL,
+                source_episodes TEXT NOT NULL,
+                evidence TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                reviewed_at INTEGER,
+                reviewer TEXT NOT NULL,
+                review_note TEXT NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, candidate_id)
+            );
+            CREATE INDEX IF NOT EXISTS procedure_candidates_scope_status
+                ON procedure_candidates (tenant_id, user_id, agent_id, status, created_at);
+
             CREATE TABLE IF NOT EXISTS insights (
                 tenant_id TEXT NOT NULL,
                 user_id TEXT NOT NULL,
@@ -397,6 +570,7 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
                 tests_suggested TEXT NOT NULL,
                 expires_at TEXT NOT NULL,
                 sources TEXT NOT NULL,
+                parent_insight_id TEXT,
                 PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id, insight_id)
             );
             CREATE INDEX IF NOT EXISTS insights_scope_state
@@ -414,12 +588,119 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
             );
             CREATE INDEX IF NOT EXISTS context_builds_scope_ts
                 ON context_builds (tenant_id, user_id, agent_id, session_id, run_id, ts);
-            ",
+            CREATE TABLE IF NOT EXISTS changelog (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                ts INTEGER NOT NULL,
+                op TEXT NOT NULL,
+                correlation_id TEXT
+            );
+            CREATE TABLE IF NOT EXISTS sync_cursors (
+                name TEXT PRIMARY KEY,
+                seq INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS suppressions (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                item_kind TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                suppressed_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id)
+            );
+            CREATE TABLE IF NOT EXISTS run_outcomes (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                score REAL,
+                error_summary TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+            );
+            CREATE TABLE IF NOT EXISTS source_credibility (
+                tenant_id TEXT PRIMARY KEY,
+                credibility_json TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS memory_embeddings (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                item_kind TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                vector_json TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                embedded_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, item_kind, item_id, model)
+            );
+            CREATE TABLE IF NOT EXISTS user_locales (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                locale_json TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id)
+            );
+            CREATE TABLE IF NOT EXISTS tombstones (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                item_kind TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                record_json TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS tombstones_scope
+                ON tombstones (tenant_id, user_id, agent_id, id);
+            "),
     )?;
 
-    if current == 0 {
+    // A database this store may not alter keeps working without the index tables.
+    if let Err(err) = ensure_optional_tables(conn, tables) {
+        warn!("could not create optional index tables: {}", err);
+    }
+    // FTS5 may be compiled out of a system SQLite; text search then scans instead.
+    if let Err(err) = ensure_text_index(conn, tables) {
+        warn!("could not create full-text index tables: {}", err);
+    }
+    let optional = detect_optional_tables(conn, tables)?;
+
+    if (1..3).contains(&current) && !has_column(conn, tables, "insights", "parent_insight_id")? {
+        conn.execute_batch(&tables.sql("ALTER TABLE insights ADD COLUMN parent_insight_id TEXT;"))?;
+    }
+    if (1..4).contains(&current) && !has_column(conn, tables, "facts", "pinned")? {
+        let statement = "ALTER TABLE facts ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;";
+        conn.execute_batch(&tables.sql(statement))?;
+    }
+    if (1..5).contains(&current) && optional.episode_index {
+        backfill_episode_index(conn, tables)?;
+    }
+    if (1..6).contains(&current) {
+        for table in ["events", "facts", "episodes"] {
+            if !has_column(conn, tables, table, "lang")? {
+                let statement = format!("ALTER TABLE {} ADD COLUMN lang TEXT;", table);
+                conn.execute_batch(&tables.sql(&statement))?;
+            }
+        }
+    }
+    if (1..7).contains(&current) && optional.text_index {
+        backfill_text_index(conn, tables)?;
+    }
+    if (1..8).contains(&current) && !has_column(conn, tables, "changelog", "correlation_id")? {
+        conn.execute_batch(&tables.sql("ALTER TABLE changelog ADD COLUMN correlation_id TEXT;"))?;
+    }
+
+    if current < SCHEMA_VERSION {
         conn.execute(
-            "INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)",
+            &tables.sql("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)"),
             params_from_iter(vec![
                 SqlValue::Integer(SCHEMA_VERSION),
                 SqlValue::Integer(to_millis(Utc::now())),
@@ -427,29 +708,204 @@ fn ensure_schema(conn: &Connection) -> StoreResult<()> {
         )?;
     }
 
-    Ok(())
+    Ok(optional)
 }
 
-impl Store for SqliteStore {
-    fn append_event(&self, event: Event) -> StoreResult<()> {
-        let Event {
-            event_id,
-            scope,
-            ts,
+fn ensure_optional_tables(conn: &Connection, tables: &TablePrefix) -> StoreResult<()> {
+    conn.execute_batch(
+        &tables.sql("
+            CREATE TABLE IF NOT EXISTS event_tags (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                event_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (
+                    tenant_id,
+                    user_id,
+                    agent_id,
+                    session_id,
+                    run_id,
+                    event_id,
+                    tag
+                )
+            );
+            CREATE INDEX IF NOT EXISTS event_tags_scope_tag
+                ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, tag);
+            CREATE INDEX IF NOT EXISTS event_tags_scope_event
+                ON event_tags (tenant_id, user_id, agent_id, session_id, run_id, event_id);
+
+            CREATE TABLE IF NOT EXISTS event_entities (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                event_id TEXT NOT NULL,
+                entity TEXT NOT NULL,
+                PRIMARY KEY (
+                    tenant_id,
+                    user_id,
+                    agent_id,
+                    session_id,
+                    run_id,
+                    event_id,
+                    entity
+                )
+            );
+            CREATE INDEX IF NOT EXISTS event_entities_scope_entity
+                ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, entity);
+            CREATE INDEX IF NOT EXISTS event_entities_scope_event
+                ON event_entities (tenant_id, user_id, agent_id, session_id, run_id, event_id);
+
+            CREATE TABLE IF NOT EXISTS episode_tags (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                episode_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS episode_tags_scope_tag
+                ON episode_tags (tenant_id, user_id, agent_id, tag);
+            CREATE INDEX IF NOT EXISTS episode_tags_scope_episode
+                ON episode_tags (tenant_id, user_id, agent_id, episode_id);
+
+            CREATE TABLE IF NOT EXISTS episode_entities (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                episode_id TEXT NOT NULL,
+                entity TEXT NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, episode_id, entity)
+            );
+            CREATE INDEX IF NOT EXISTS episode_entities_scope_entity
+                ON episode_entities (tenant_id, user_id, agent_id, entity);
+            CREATE INDEX IF NOT EXISTS episode_entities_scope_episode
+                ON episode_entities (tenant_id, user_id, agent_id, episode_id);
+            "),
+    )?;
+    Ok(())
+}
+
+fn ensure_text_index(conn: &Connection, tables: &TablePrefix) -> StoreResult<()> {
+    conn.execute_batch(
+        &tables.sql("
+            CREATE VIRTUAL TABLE IF NOT EXISTS event_text USING fts5(
+                body,
+                event_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS episode_text USING fts5(
+                body,
+                tenant_id UNINDEXED,
+                user_id UNINDEXED,
+                agent_id UNINDEXED,
+                episode_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            "),
+    )?;
+    Ok(())
+}
+
+fn detect_optional_tables(conn: &Connection, tables: &TablePrefix) -> StoreResult<OptionalTables> {
+    let names: Vec<String> = OptionalTables::NAMES
+        .into_iter()
+        .chain(TEXT_INDEX)
+        .map(|name| tables.name(name))
+        .collect();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name IN ({})",
+        sql_placeholders(names.len())
+    ))?;
+    let rows = stmt.query_map(params_from_iter(&names), |row| row.get::<_, String>(0))?;
+    let mut present: Vec<String> = Vec::new();
+    for name in rows {
+        present.push(tables.strip(&name?).to_string());
+    }
+    let mut optional = OptionalTables::from_present(&present);
+    optional.text_index = TEXT_INDEX.iter().all(|name| present.iter().any(|p| p == name));
+    if !optional.text_index {
+        warn!("event_text/episode_text missing; text search scans events and episodes");
+    }
+    Ok(optional)
+}
+
+fn has_column(
+    conn: &Connection,
+    tables: &TablePrefix,
+    table: &str,
+    column: &str,
+) -> StoreResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?",
+        params_from_iter([tables.name(table), column.to_string()]),
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn migrate_event_seq(conn: &Connection, tables: &TablePrefix) -> StoreResult<()> {
+    conn.execute_batch(
+        &tables.sql("
+            BEGIN;
+            ALTER TABLE events ADD COLUMN seq INTEGER NOT NULL DEFAULT 0;
+            UPDATE events SET seq = (
+                SELECT ranked.rn FROM (
+                    SELECT event_id, ROW_NUMBER() OVER (
+                        PARTITION BY tenant_id, user_id, agent_id, session_id, run_id
+                        ORDER BY ts, event_id
+                    ) AS rn
+                    FROM events
+                ) AS ranked
+                WHERE ranked.event_id = events.event_id
+            );
+            CREATE TABLE IF NOT EXISTS event_sequences (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                last_seq INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, session_id, run_id)
+            );
+            INSERT INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+                SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(seq)
+                FROM events
+                GROUP BY tenant_id, user_id, agent_id, session_id, run_id;
+            COMMIT;
+            "),
+    )?;
+    Ok(())
+}
+
+impl Store for SqliteStore {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        let event = normalize_event_tags(event);
+        let payload = stored_payload(&event).into_owned();
+        let Event {
+            event_id,
+            scope,
+            ts,
             kind,
-            payload,
             tags,
             entities,
+            lang,
+            ..
         } = event;
-        self.with_connection(|conn| {
-            let tx = conn.transaction()?;
+        self.with_connection(SqlContext::scoped("append_event", "events", &scope), |conn| {
+            let tx = conn.savepoint()?;
+            let seq = next_event_seq(&tx, &self.tables, &scope)?;
             tx.execute(
-                "
+                &self.tables.sql("
                 INSERT INTO events (
                     event_id, tenant_id, user_id, agent_id, session_id, run_id,
-                    ts, kind, payload, tags, entities
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ",
+                    ts, kind, payload, tags, entities, seq, lang
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "),
                 params_from_iter(vec![
                     SqlValue::Text(event_id.clone()),
                     SqlValue::Text(scope.tenant_id.clone()),
@@ -458,13 +914,110 @@ impl Store for SqliteStore {
                     SqlValue::Text(scope.session_id.clone()),
                     SqlValue::Text(scope.run_id.clone()),
                     SqlValue::Integer(to_millis(ts)),
-                    SqlValue::Text(event_kind_to_str(&kind).to_string()),
+                    SqlValue::Text(kind.as_str().to_string()),
                     SqlValue::Text(encode_json(&payload)?),
                     SqlValue::Text(encode_json(&tags)?),
                     SqlValue::Text(encode_json(&entities)?),
+                    SqlValue::Integer(seq),
+                    lang.map_or(SqlValue::Null, SqlValue::Text),
                 ]),
             )?;
-            insert_event_tags(&tx, &scope, &event_id, &tags, &entities)?;
+            if self.optional.event_index {
+                insert_event_tags(&tx, &self.tables, &scope, &event_id, &tags, &entities)?;
+            }
+            if self.optional.text_index {
+                insert_event_text(&tx, &self.tables, &event_id, &payload)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let events: &[Event] =
+            &events.iter().cloned().map(normalize_event_tags).collect::<Vec<_>>();
+        let context = SqlContext::scoped("append_events_bulk", "events", &events[0].scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            let mut stmt_event = tx.prepare(
+                &self.tables.sql("
+                INSERT INTO events (
+                    event_id, tenant_id, user_id, agent_id, session_id, run_id,
+                    ts, kind, payload, tags, entities, seq, lang
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "),
+            )?;
+            let mut index = if self.optional.event_index {
+                let stmt_tag = tx.prepare(
+                    &self.tables.sql("
+                    INSERT OR IGNORE INTO event_tags (
+                        tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "),
+                )?;
+                let stmt_entity = tx.prepare(
+                    &self.tables.sql("
+                    INSERT OR IGNORE INTO event_entities (
+                        tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "),
+                )?;
+                Some((stmt_tag, stmt_entity))
+            } else {
+                None
+            };
+            for event in events {
+                let seq = next_event_seq(&tx, &self.tables, &event.scope)?;
+                let payload = stored_payload(event);
+                stmt_event.execute(params_from_iter(vec![
+                    SqlValue::Text(event.event_id.clone()),
+                    SqlValue::Text(event.scope.tenant_id.clone()),
+                    SqlValue::Text(event.scope.user_id.clone()),
+                    SqlValue::Text(event.scope.agent_id.clone()),
+                    SqlValue::Text(event.scope.session_id.clone()),
+                    SqlValue::Text(event.scope.run_id.clone()),
+                    SqlValue::Integer(to_millis(event.ts)),
+                    SqlValue::Text(event.kind.as_str().to_string()),
+                    SqlValue::Text(encode_json(&payload)?),
+                    SqlValue::Text(encode_json(&event.tags)?),
+                    SqlValue::Text(encode_json(&event.entities)?),
+                    SqlValue::Integer(seq),
+                    event.lang.clone().map_or(SqlValue::Null, SqlValue::Text),
+                ]))?;
+                if self.optional.text_index {
+                    insert_event_text(&tx, &self.tables, &event.event_id, &payload)?;
+                }
+
+                if let Some((stmt_tag, stmt_entity)) = index.as_mut() {
+                    for tag in unique_values(&event.tags) {
+                        stmt_tag.execute(params_from_iter(vec![
+                            SqlValue::Text(event.scope.tenant_id.clone()),
+                            SqlValue::Text(event.scope.user_id.clone()),
+                            SqlValue::Text(event.scope.agent_id.clone()),
+                            SqlValue::Text(event.scope.session_id.clone()),
+                            SqlValue::Text(event.scope.run_id.clone()),
+                            SqlValue::Text(event.event_id.clone()),
+                            SqlValue::Text(tag),
+                        ]))?;
+                    }
+                    for entity in unique_values(&event.entities) {
+                        stmt_entity.execute(params_from_iter(vec![
+                            SqlValue::Text(event.scope.tenant_id.clone()),
+                            SqlValue::Text(event.scope.user_id.clone()),
+                            SqlValue::Text(event.scope.agent_id.clone()),
+                            SqlValue::Text(event.scope.session_id.clone()),
+                            SqlValue::Text(event.scope.run_id.clone()),
+                            SqlValue::Text(event.event_id.clone()),
+                            SqlValue::Text(entity),
+                        ]))?;
+                    }
+                }
+            }
+            drop(index);
+            drop(stmt_event);
             tx.commit()?;
             Ok(())
         })
@@ -476,9 +1029,10 @@ impl Store for SqliteStore {
         range: TimeRangeFilter,
         limit: Option<usize>,
     ) -> StoreResult<Vec<Event>> {
-        self.with_connection(|conn| {
+        self.with_connection(SqlContext::scoped("list_events", "events", scope), |conn| {
             let mut sql = String::from(
-                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind, payload, tags, entities
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
                  FROM events
                  WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
             );
@@ -492,677 +1046,1662 @@ impl Store for SqliteStore {
                 sql.push_str(" AND ts <= ?");
                 params.push(SqlValue::Integer(to_millis(end)));
             }
-            sql.push_str(" ORDER BY ts ASC");
-            if let Some(limit) = limit {
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+
+            let mut events = Vec::new();
+            for event in rows {
+                events.push(event?);
+            }
+            Ok(events)
+        })
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        let cursor = page_request(cursor, limit)?;
+        self.with_connection(SqlContext::scoped("list_events_page", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
+            );
+            let mut params = scope_params(scope);
+            if let Some(cursor) = &cursor {
+                sql.push_str(" AND (ts > ? OR (ts = ? AND event_id > ?))");
+                params.extend([
+                    SqlValue::Integer(cursor.ts_ms),
+                    SqlValue::Integer(cursor.ts_ms),
+                    SqlValue::Text(cursor.event_id.clone()),
+                ]);
+            }
+            sql.push_str(" ORDER BY ts ASC, event_id ASC LIMIT ?");
+            params.push(SqlValue::Integer(limit as i64 + 1));
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+            let events = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(event_page(events, limit))
+        })
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.with_connection(SqlContext::scoped("get_events_since", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                   AND seq > ?
+                 ORDER BY seq ASC",
+            );
+            let mut params = scope_params(scope);
+            params.push(SqlValue::Integer(seq as i64));
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+
+            let mut events = Vec::new();
+            for event in rows {
+                events.push(event?);
+            }
+            Ok(events)
+        })
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        if event_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_connection(SqlContext::scoped("get_events_by_ids", "events", scope), |conn| {
+            let placeholders = vec!["?"; event_ids.len()].join(", ");
+            let sql = format!(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND event_id IN ({})
+                 ORDER BY ts ASC, seq ASC",
+                placeholders
+            );
+            let mut params = scope_params_ltm(scope);
+            params.extend(event_ids.iter().map(|id| SqlValue::Text(id.clone())));
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+
+            let mut events = Vec::new();
+            for event in rows {
+                events.push(event?);
+            }
+            Ok(events)
+        })
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        let filter = filter.with_tag_forms();
+        if !self.optional.event_index && (!filter.tags.is_empty() || !filter.entities.is_empty()) {
+            return find_events_unindexed(filter, |filter| self.find_events(scope, filter));
+        }
+        self.with_connection(SqlContext::scoped("find_events", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind,
+                        payload, tags, entities, seq, lang
+                 FROM events
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
+            );
+            let mut params = scope_params(scope);
+
+            if let Some(range) = &filter.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND ts >= ?");
+                    params.push(SqlValue::Integer(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND ts <= ?");
+                    params.push(SqlValue::Integer(to_millis(end)));
+                }
+            }
+
+            if !filter.tags.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_tags i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.tag IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.tags.len()));
+                sql.push_str("))");
+                for tag in &filter.tags {
+                    params.push(SqlValue::Text(tag.clone()));
+                }
+            }
+
+            if !filter.entities.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM event_entities i
+                        WHERE i.tenant_id = events.tenant_id
+                          AND i.user_id = events.user_id
+                          AND i.agent_id = events.agent_id
+                          AND i.session_id = events.session_id
+                          AND i.run_id = events.run_id
+                          AND i.event_id = events.event_id
+                          AND i.entity IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.entities.len()));
+                sql.push_str("))");
+                for entity in &filter.entities {
+                    params.push(SqlValue::Text(entity.clone()));
+                }
+            }
+
+            sql.push_str(" ORDER BY ts ASC, seq ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+
+            let mut events = Vec::new();
+            for event in rows {
+                events.push(event?);
+            }
+            Ok(events)
+        })
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        let terms = query.searchable_terms();
+        if !self.optional.text_index {
+            return search_events_unindexed(self, scope, query);
+        }
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_connection(SqlContext::scoped("search_events", "events", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT e.event_id, e.tenant_id, e.user_id, e.agent_id, e.session_id, e.run_id,
+                        e.ts, e.kind, e.payload, e.tags, e.entities, e.seq, e.lang
+                 FROM event_text JOIN events e ON e.event_id = event_text.event_id
+                 WHERE event_text MATCH ? AND e.tenant_id = ? AND e.user_id = ?
+                   AND e.agent_id = ? AND e.session_id = ? AND e.run_id = ?",
+            );
+            let mut params = vec![SqlValue::Text(fts_query(&terms))];
+            params.extend(scope_params(scope));
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND e.ts >= ?");
+                    params.push(SqlValue::Integer(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND e.ts <= ?");
+                    params.push(SqlValue::Integer(to_millis(end)));
+                }
+            }
+            sql.push_str(" ORDER BY bm25(event_text), e.ts ASC, e.seq ASC");
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), event_from_row)?;
+            let mut events = Vec::new();
+            for event in rows {
+                events.push(event?);
+            }
+            Ok(events)
+        })
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.acquire(scope, ttl, || Ok(Some(())), |_| Ok(()))
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.leases.renew(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        Ok(self.leases.release(lease)?.is_some())
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.with_connection(SqlContext::scoped("get_working_state", "wm_state", scope), |conn| {
+            working_state_json(conn, &self.tables, scope)?.as_deref().map(decode_json).transpose()
+        })
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.with_connection(SqlContext::scoped("patch_working_state", "wm_state", scope), |conn| {
+            patch_working_state_row(conn, &self.tables, scope, patch)
+        })
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        let Some((first, _)) = patches.first() else {
+            return Ok(Vec::new());
+        };
+        let scope = first.clone();
+        let context = SqlContext::scoped("patch_working_states", "wm_state", &scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            let mut states = Vec::with_capacity(patches.len());
+            for (scope, patch) in patches {
+                states.push(patch_working_state_row(&tx, &self.tables, &scope, patch)?);
+            }
+            tx.commit()?;
+            Ok(states)
+        })
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.with_connection(SqlContext::scoped("list_working_states", "wm_state", scope), |conn| {
+            let mut stmt = conn.prepare(
+                &self.tables.sql("SELECT run_id, state_json FROM wm_state
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?
+                 ORDER BY run_id ASC"),
+            )?;
+            let rows = stmt.query_map(params_from_iter(scope_params_session(scope)), |row| {
+                let payload: String = row.get(1)?;
+                Ok(RunWorkingState {
+                    run_id: row.get(0)?,
+                    state: decode_json_row(&payload)?,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.with_connection(SqlContext::scoped("get_stm", "stm_state", scope), |conn| {
+            let mut stmt = conn.prepare(
+                &self.tables.sql("SELECT rolling_summary, key_quotes FROM stm_state
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?"),
+            )?;
+            let result = stmt.query_row(
+                params_from_iter(scope_params_session(scope)),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            );
+
+            match result {
+                Ok((rolling_summary, key_quotes)) => Ok(Some(StmState {
+                    rolling_summary,
+                    key_quotes: decode_json(&key_quotes)?,
+                })),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.with_connection(SqlContext::scoped("update_stm", "stm_state", scope), |conn| {
+            conn.execute(
+                &self.tables.sql("
+                INSERT INTO stm_state (
+                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (tenant_id, user_id, agent_id, session_id)
+                DO UPDATE SET rolling_summary = excluded.rolling_summary,
+                              key_quotes = excluded.key_quotes,
+                              updated_at = excluded.updated_at
+                "),
+                params_from_iter(vec![
+                    SqlValue::Text(scope.tenant_id.clone()),
+                    SqlValue::Text(scope.user_id.clone()),
+                    SqlValue::Text(scope.agent_id.clone()),
+                    SqlValue::Text(scope.session_id.clone()),
+                    SqlValue::Text(stm.rolling_summary),
+                    SqlValue::Text(encode_json(&stm.key_quotes)?),
+                    SqlValue::Integer(to_millis(Utc::now())),
+                ]),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        if filter.include_shared {
+            return crate::shared_facts::list_facts_with_shared(self, scope, filter);
+        }
+        self.with_connection(SqlContext::scoped("list_facts", "facts", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
+                        confidence, sources, scope_level, notes, pinned, lang
+                 FROM facts WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push('?');
+                    params.push(SqlValue::Text(fact_status_to_str(status).to_string()));
+                }
+                sql.push(')');
+            }
+
+            if let Some(pinned) = filter.pinned {
+                sql.push_str(" AND pinned = ?");
+                params.push(SqlValue::Integer(pinned as i64));
+            }
+
+            if let Some(preferences) = filter.preferences {
+                let op = if preferences { "=" } else { "<>" };
+                sql.push_str(&format!(" AND substr(fact_key, 1, ?) {op} ?"));
+                params.push(SqlValue::Integer(PREFERENCE_KEY_PREFIX.len() as i64));
+                params.push(SqlValue::Text(PREFERENCE_KEY_PREFIX.to_string()));
+            }
+
+            if let Some(keys) = &filter.keys {
+                if keys.is_empty() {
+                    return Ok(Vec::new());
+                }
+                sql.push_str(" AND fact_key IN (");
+                sql.push_str(&vec!["?"; keys.len()].join(", "));
+                sql.push(')');
+                params.extend(keys.iter().map(|key| SqlValue::Text(key.clone())));
+            }
+
+            if let Some(at) = filter.valid_at {
+                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ?)");
+                params.push(SqlValue::Integer(to_millis(at)));
+                sql.push_str(" AND (valid_to IS NULL OR valid_to >= ?)");
+                params.push(SqlValue::Integer(to_millis(at)));
+            }
+
+            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), fact_from_row)?;
+
+            let mut facts = Vec::new();
+            for fact in rows {
+                facts.push(fact?);
+            }
+            Ok(facts)
+        })
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.with_connection(SqlContext::scoped("upsert_fact", "facts", scope), |conn| {
+            let tx = conn.savepoint()?;
+            upsert_fact_row(&tx, &self.tables, scope, &fact)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        if facts.is_empty() {
+            return Ok(());
+        }
+        self.with_connection(SqlContext::scoped("upsert_facts_bulk", "facts", scope), |conn| {
+            let tx = conn.savepoint()?;
+            for fact in facts {
+                upsert_fact_row(&tx, &self.tables, scope, fact)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        let context = SqlContext::scoped("list_fact_history", "fact_versions", scope);
+        self.with_connection(context, |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.push(SqlValue::Text(fact_key.to_string()));
+            let mut stmt = conn.prepare(
+                &self.tables.sql("SELECT version, recorded_at, fact_json FROM fact_versions
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND fact_key = ?
+                 ORDER BY recorded_at ASC, version ASC"),
+            )?;
+            let rows = stmt.query_map(params_from_iter(params), fact_version_from_row)?;
+            let mut history = Vec::new();
+            for version in rows {
+                history.push(version?);
+            }
+            Ok(history)
+        })
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        let filter = filter.with_tag_forms();
+        if !self.optional.episode_index && (!filter.tags.is_empty() || !filter.entities.is_empty())
+        {
+            return list_episodes_unindexed(filter, |filter| self.list_episodes(scope, filter));
+        }
+        self.with_connection(SqlContext::scoped("list_episodes", "episodes", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
+                        sources, compression_level, recency_score, lang
+                 FROM episodes WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+
+            if let Some(range) = &filter.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND start_ts >= ?");
+                    params.push(SqlValue::Integer(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ?");
+                    params.push(SqlValue::Integer(to_millis(end)));
+                }
+            }
+
+            if !filter.tags.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM episode_tags t
+                        WHERE t.tenant_id = episodes.tenant_id
+                          AND t.user_id = episodes.user_id
+                          AND t.agent_id = episodes.agent_id
+                          AND t.episode_id = episodes.episode_id
+                          AND t.tag IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.tags.len()));
+                sql.push_str("))");
+                for tag in &filter.tags {
+                    params.push(SqlValue::Text(tag.clone()));
+                }
+            }
+
+            if !filter.entities.is_empty() {
+                sql.push_str(
+                    " AND EXISTS (
+                        SELECT 1 FROM episode_entities e
+                        WHERE e.tenant_id = episodes.tenant_id
+                          AND e.user_id = episodes.user_id
+                          AND e.agent_id = episodes.agent_id
+                          AND e.episode_id = episodes.episode_id
+                          AND e.entity IN (",
+                );
+                sql.push_str(&sql_placeholders(filter.entities.len()));
+                sql.push_str("))");
+                for entity in &filter.entities {
+                    params.push(SqlValue::Text(entity.clone()));
+                }
+            }
+
+            sql.push_str(" ORDER BY start_ts ASC, episode_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), episode_from_row)?;
+
+            let mut episodes = Vec::new();
+            for episode in rows {
+                episodes.push(episode?);
+            }
+            Ok(episodes)
+        })
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.append_episodes_bulk(scope, std::slice::from_ref(&episode))
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        if episodes.is_empty() {
+            return Ok(());
+        }
+        self.with_connection(SqlContext::scoped("append_episodes_bulk", "episodes", scope), |conn| {
+            let tx = conn.savepoint()?;
+            for episode in episodes {
+                self.insert_episode(&tx, scope, episode)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        let terms = query.searchable_terms();
+        if !self.optional.text_index {
+            return search_episodes_unindexed(self, scope, query);
+        }
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_connection(SqlContext::scoped("search_episodes", "episodes", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT e.episode_id, e.start_ts, e.end_ts, e.summary, e.highlights, e.tags,
+                        e.entities, e.sources, e.compression_level, e.recency_score, e.lang
+                 FROM episode_text JOIN episodes e
+                   ON e.tenant_id = episode_text.tenant_id
+                  AND e.user_id = episode_text.user_id
+                  AND e.agent_id = episode_text.agent_id
+                  AND e.episode_id = episode_text.episode_id
+                 WHERE episode_text MATCH ? AND e.tenant_id = ? AND e.user_id = ?
+                   AND e.agent_id = ?",
+            );
+            let mut params = vec![SqlValue::Text(fts_query(&terms))];
+            params.extend(scope_params_ltm(scope));
+            if let Some(range) = &query.time_range {
+                if let Some(start) = range.start {
+                    sql.push_str(" AND e.start_ts >= ?");
+                    params.push(SqlValue::Integer(to_millis(start)));
+                }
+                if let Some(end) = range.end {
+                    sql.push_str(" AND COALESCE(e.end_ts, e.start_ts) <= ?");
+                    params.push(SqlValue::Integer(to_millis(end)));
+                }
+            }
+            sql.push_str(" ORDER BY bm25(episode_text), e.start_ts ASC, e.episode_id ASC");
+            if let Some(limit) = query.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), episode_from_row)?;
+            let mut episodes = Vec::new();
+            for episode in rows {
+                episodes.push(episode?);
+            }
+            Ok(episodes)
+        })
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.with_connection(SqlContext::scoped("list_procedures", "procedures", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT procedure_id, task_type, content_json, priority, sources, applicability
+                 FROM procedures WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND task_type = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+            params.push(SqlValue::Text(task_type.to_string()));
+
+            sql.push_str(" ORDER BY priority DESC, procedure_id ASC");
+            if let Some(limit) = limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), procedure_from_row)?;
+
+            let mut procedures = Vec::new();
+            for procedure in rows {
+                procedures.push(procedure?);
+            }
+            Ok(procedures)
+        })
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.with_connection(SqlContext::scoped("upsert_procedure", "procedures", scope), |conn| {
+            conn.execute(
+                &self.tables.sql("
+                INSERT INTO procedures (
+                    tenant_id, user_id, agent_id, procedure_id, task_type, content_json,
+                    priority, sources, applicability
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (tenant_id, user_id, agent_id, procedure_id)
+                DO UPDATE SET task_type = excluded.task_type,
+                              content_json = excluded.content_json,
+                              priority = excluded.priority,
+                              sources = excluded.sources,
+                              applicability = excluded.applicability
+                "),
+                params_from_iter(vec![
+                    SqlValue::Text(scope.tenant_id.clone()),
+                    SqlValue::Text(scope.user_id.clone()),
+                    SqlValue::Text(scope.agent_id.clone()),
+                    SqlValue::Text(procedure.procedure_id),
+                    SqlValue::Text(procedure.task_type),
+                    SqlValue::Text(encode_json(&procedure.content)?),
+                    SqlValue::Integer(procedure.priority as i64),
+                    SqlValue::Text(encode_json(&procedure.sources)?),
+                    SqlValue::Text(encode_json(&procedure.applicability)?),
+                ]),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        let context =
+            SqlContext::scoped("list_procedure_candidates", "procedure_candidates", scope);
+        self.with_connection(context, |conn| {
+            let mut sql = String::from(
+                "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                        created_at, reviewed_at, reviewer, review_note
+                 FROM procedure_candidates WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+            );
+            let mut params = scope_params_ltm(scope);
+
+            if let Some(task_type) = &filter.task_type {
+                sql.push_str(" AND task_type = ?");
+                params.push(SqlValue::Text(task_type.clone()));
+            }
+
+            if let Some(statuses) = &filter.status
+                && !statuses.is_empty()
+            {
+                sql.push_str(" AND status IN (");
+                for (idx, status) in statuses.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push('?');
+                    params.push(SqlValue::Text(candidate_status_to_str(status).to_string()));
+                }
+                sql.push(')');
+            }
+
+            sql.push_str(" ORDER BY created_at ASC, candidate_id ASC");
+            if let Some(limit) = filter.limit {
+                sql.push_str(" LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+            }
+
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), row_to_procedure_candidate)?;
+
+            let mut candidates = Vec::new();
+            for candidate in rows {
+                candidates.push(candidate?);
+            }
+            Ok(candidates)
+        })
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        let context = SqlContext::scoped("get_procedure_candidate", "procedure_candidates", scope);
+        self.with_connection(context, |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.push(SqlValue::Text(candidate_id.to_string()));
+            let result = conn.query_row(
+                &self.tables.sql(
+                    "SELECT candidate_id, procedure_json, source_episodes, evidence, status,
+                            created_at, reviewed_at, reviewer, review_note
+                     FROM procedure_candidates
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND candidate_id = ?",
+                ),
+                params_from_iter(params),
+                row_to_procedure_candidate,
+            );
+            match result {
+                Ok(candidate) => Ok(Some(candidate)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        let context =
+            SqlContext::scoped("upsert_procedure_candidate", "procedure_candidates", scope);
+        self.with_connection(context, |conn| {
+            conn.execute(
+                &self.tables.sql("
+                INSERT INTO procedure_candidates (
+                    tenant_id, user_id, agent_id, candidate_id, task_type, procedure_json,
+                    source_episodes, evidence, status, created_at, reviewed_at, reviewer,
+                    review_note
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (tenant_id, user_id, agent_id, candidate_id)
+                DO UPDATE SET task_type = excluded.task_type,
+                              procedure_json = excluded.procedure_json,
+                              source_episodes = excluded.source_episodes,
+                              evidence = excluded.evidence,
+                              status = excluded.status,
+                              created_at = excluded.created_at,
+                              reviewed_at = excluded.reviewed_at,
+                              reviewer = excluded.reviewer,
+                              review_note = excluded.review_note
+                "),
+                params_from_iter(vec![
+                    SqlValue::Text(scope.tenant_id.clone()),
+                    SqlValue::Text(scope.user_id.clone()),
+                    SqlValue::Text(scope.agent_id.clone()),
+                    SqlValue::Text(candidate.candidate_id),
+                    SqlValue::Text(candidate.procedure.task_type.clone()),
+                    SqlValue::Text(encode_json(&candidate.procedure)?),
+                    SqlValue::Text(encode_json(&candidate.source_episodes)?),
+                    SqlValue::Text(encode_json(&candidate.evidence)?),
+                    SqlValue::Text(candidate_status_to_str(&candidate.status).to_string()),
+                    SqlValue::Integer(to_millis(candidate.created_at)),
+                    option_ts_to_value(candidate.reviewed_at),
+                    SqlValue::Text(candidate.reviewer),
+                    SqlValue::Text(candidate.review_note),
+                ]),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.with_connection(SqlContext::scoped("list_insights", "insights", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT insight_id, kind, statement, trigger, confidence, validation_state,
+                        tests_suggested, expires_at, sources, parent_insight_id
+                 FROM insights
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
+            );
+            let mut params = scope_params(scope);
+
+            if let Some(states) = &filter.validation_state
+                && !states.is_empty()
+            {
+                sql.push_str(" AND validation_state IN (");
+                for (idx, state) in states.iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    sql.push('?');
+                    params.push(SqlValue::Text(validation_state_to_str(state).to_string()));
+                }
+                sql.push(')');
+            }
+
+            sql.push_str(" ORDER BY validation_state DESC, confidence DESC, insight_id ASC");
+            if let Some(limit) = filter.limit {
                 sql.push_str(" LIMIT ?");
                 params.push(SqlValue::Integer(limit as i64));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
-            let rows = stmt.query_map(params_from_iter(params), |row| {
-                let kind: String = row.get(7)?;
-                let payload: String = row.get(8)?;
-                let tags: String = row.get(9)?;
-                let entities: String = row.get(10)?;
-                Ok(Event {
-                    event_id: row.get(0)?,
-                    scope: Scope {
-                        tenant_id: row.get(1)?,
-                        user_id: row.get(2)?,
-                        agent_id: row.get(3)?,
-                        session_id: row.get(4)?,
-                        run_id: row.get(5)?,
-                    },
-                    ts: from_millis(row.get(6)?),
-                    kind: parse_enum(&kind, event_kind_from_str)?,
-                    payload: decode_json_row(&payload)?,
-                    tags: decode_json_row(&tags)?,
-                    entities: decode_json_row(&entities)?,
-                })
-            })?;
-
-            let mut events = Vec::new();
-            for event in rows {
-                events.push(event?);
-            }
-            Ok(events)
-        })
-    }
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), insight_from_row)?;
 
-    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
-        self.with_connection(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT state_json FROM wm_state
-                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
-            )?;
-            let result = stmt.query_row(
-                params_from_iter(scope_params(scope)),
-                |row| row.get::<_, String>(0),
-            );
-            match result {
-                Ok(payload) => Ok(Some(decode_json(&payload)?)),
-                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-                Err(err) => Err(err.into()),
+            let mut insights = Vec::new();
+            for insight in rows {
+                insights.push(insight?);
             }
+            Ok(insights)
         })
     }
 
-    fn patch_working_state(
-        &self,
-        scope: &Scope,
-        patch: WorkingStatePatch,
-    ) -> StoreResult<WorkingState> {
-        let current = self.get_working_state(scope)?.unwrap_or_default();
-        let mut next = current.clone();
-
-        let mut touched = false;
-        if let Some(goal) = patch.goal {
-            next.goal = goal;
-            touched = true;
-        }
-        if let Some(plan) = patch.plan {
-            next.plan = plan;
-            touched = true;
-        }
-        if let Some(slots) = patch.slots {
-            next.slots = slots;
-            touched = true;
-        }
-        if let Some(constraints) = patch.constraints {
-            next.constraints = constraints;
-            touched = true;
-        }
-        if let Some(tool_evidence) = patch.tool_evidence {
-            next.tool_evidence = tool_evidence;
-            touched = true;
-        }
-        if let Some(decisions) = patch.decisions {
-            next.decisions = decisions;
-            touched = true;
-        }
-        if let Some(risks) = patch.risks {
-            next.risks = risks;
-            touched = true;
-        }
-        if let Some(state_version) = patch.state_version {
-            next.state_version = state_version;
-        } else if touched {
-            next.state_version = next.state_version.saturating_add(1);
-        }
-
-        self.with_connection(|conn| {
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.with_connection(SqlContext::scoped("append_insight", "insights", scope), |conn| {
             conn.execute(
-                "
-                INSERT INTO wm_state (
-                    tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
-                ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
-                DO UPDATE SET state_json = excluded.state_json, updated_at = excluded.updated_at
-                ",
+                &self.tables.sql("
+                INSERT INTO insights (
+                    tenant_id, user_id, agent_id, session_id, run_id, insight_id,
+                    kind, statement, trigger, confidence, validation_state,
+                    tests_suggested, expires_at, sources, parent_insight_id
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "),
                 params_from_iter(vec![
                     SqlValue::Text(scope.tenant_id.clone()),
                     SqlValue::Text(scope.user_id.clone()),
                     SqlValue::Text(scope.agent_id.clone()),
                     SqlValue::Text(scope.session_id.clone()),
                     SqlValue::Text(scope.run_id.clone()),
-                    SqlValue::Text(encode_json(&next)?),
-                    SqlValue::Integer(to_millis(Utc::now())),
+                    SqlValue::Text(insight.id),
+                    SqlValue::Text(insight_type_to_str(&insight.kind).to_string()),
+                    SqlValue::Text(insight.statement),
+                    SqlValue::Text(insight_trigger_to_str(&insight.trigger).to_string()),
+                    SqlValue::Real(insight.confidence),
+                    SqlValue::Text(validation_state_to_str(&insight.validation_state).to_string()),
+                    SqlValue::Text(encode_json(&insight.tests_suggested)?),
+                    SqlValue::Text(insight.expires_at),
+                    SqlValue::Text(encode_json(&insight.sources)?),
+                    insight.parent_insight_id.map_or(SqlValue::Null, SqlValue::Text),
                 ]),
             )?;
-            Ok(next)
+            Ok(())
         })
     }
 
-    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
-        self.with_connection(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT rolling_summary, key_quotes FROM stm_state
-                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ?",
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.with_connection(SqlContext::scoped("expire_insights", "insights", scope), |conn| {
+            let mut params = scope_params(scope);
+            params.push(SqlValue::Text(expires_at.to_string()));
+            let removed = conn.execute(
+                &self.tables.sql("DELETE FROM insights
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
+                   AND expires_at = ?"),
+                params_from_iter(params),
             )?;
-            let result = stmt.query_row(
-                params_from_iter(scope_params_session(scope)),
-                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
-            );
-
-            match result {
-                Ok((rolling_summary, key_quotes)) => Ok(Some(StmState {
-                    rolling_summary,
-                    key_quotes: decode_json(&key_quotes)?,
-                })),
-                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-                Err(err) => Err(err.into()),
-            }
+            Ok(removed)
         })
     }
 
-    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
-        self.with_connection(|conn| {
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        let context = SqlContext::scoped("write_context_build", "context_builds", scope);
+        self.with_connection(context, |conn| {
+            let generated = to_millis(packet.meta.generated_at);
             conn.execute(
-                "
-                INSERT INTO stm_state (
-                    tenant_id, user_id, agent_id, session_id, rolling_summary, key_quotes, updated_at
+                &self.tables.sql("
+                INSERT INTO context_builds (
+                    tenant_id, user_id, agent_id, session_id, run_id, ts, packet_json
                 ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT (tenant_id, user_id, agent_id, session_id)
-                DO UPDATE SET rolling_summary = excluded.rolling_summary,
-                              key_quotes = excluded.key_quotes,
-                              updated_at = excluded.updated_at
-                ",
+                "),
                 params_from_iter(vec![
                     SqlValue::Text(scope.tenant_id.clone()),
                     SqlValue::Text(scope.user_id.clone()),
                     SqlValue::Text(scope.agent_id.clone()),
                     SqlValue::Text(scope.session_id.clone()),
-                    SqlValue::Text(stm.rolling_summary),
-                    SqlValue::Text(encode_json(&stm.key_quotes)?),
-                    SqlValue::Integer(to_millis(Utc::now())),
+                    SqlValue::Text(scope.run_id.clone()),
+                    SqlValue::Integer(generated),
+                    SqlValue::Text(encode_json(&packet)?),
                 ]),
             )?;
             Ok(())
         })
     }
 
-    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
-        self.with_connection(|conn| {
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| decode_json(packet_json))
+            .collect()
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.context_build_rows(scope, limit)?
+            .iter()
+            .map(|packet_json| ContextBuildSummary::from_packet_json(packet_json))
+            .collect()
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.with_connection(SqlContext::new("tenant_activity", "events", &[tenant_id]), |conn| {
             let mut sql = String::from(
-                "SELECT fact_id, fact_key, value_json, status, valid_from, valid_to,
-                        confidence, sources, scope_level, notes
-                 FROM facts WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
+                "SELECT user_id, ts / 86400000 AS day, COUNT(*) FROM events WHERE tenant_id = ?",
             );
-            let mut params = scope_params_ltm(scope);
-
-            if let Some(statuses) = &filter.status {
-                if !statuses.is_empty() {
-                    sql.push_str(" AND status IN (");
-                    for (idx, status) in statuses.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str("?");
-                        params.push(SqlValue::Text(fact_status_to_str(status).to_string()));
-                    }
-                    sql.push(')');
-                }
+            let mut params = vec![SqlValue::Text(tenant_id.to_string())];
+            if let Some(start) = range.start {
+                sql.push_str(" AND ts >= ?");
+                params.push(SqlValue::Integer(to_millis(start)));
             }
+            if let Some(end) = range.end {
+                sql.push_str(" AND ts <= ?");
+                params.push(SqlValue::Integer(to_millis(end)));
+            }
+            sql.push_str(" GROUP BY user_id, day");
 
-            if let Some(at) = filter.valid_at {
-                sql.push_str(" AND (valid_from IS NULL OR valid_from <= ?)");
-                params.push(SqlValue::Integer(to_millis(at)));
-                sql.push_str(" AND (valid_to IS NULL OR valid_to >= ?)");
-                params.push(SqlValue::Integer(to_millis(at)));
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let event_rows = stmt
+                .query_map(params_from_iter(params), |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut counts = Vec::new();
+            for table in ["facts", "episodes"] {
+                let mut stmt = conn.prepare(&self.tables.sql(&format!(
+                    "SELECT user_id, COUNT(*) FROM {} WHERE tenant_id = ? GROUP BY user_id",
+                    table
+                )))?;
+                let rows = stmt
+                    .query_map(params_from_iter(vec![SqlValue::Text(tenant_id.to_string())]), |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                counts.push(rows);
             }
+            let episode_rows = counts.pop().unwrap_or_default();
+            let fact_rows = counts.pop().unwrap_or_default();
+            Ok(collect_user_activity(event_rows, fact_rows, episode_rows))
+        })
+    }
 
-            sql.push_str(" ORDER BY fact_key ASC, fact_id ASC");
-            if let Some(limit) = filter.limit {
+    fn list_scopes(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Scope>> {
+        let context = SqlContext::new("list_scopes", "events", &[tenant_id.unwrap_or_default()]);
+        self.with_connection(context, |conn| {
+            let mut sql = String::from(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, MAX(ts) AS last_ts
+                 FROM events",
+            );
+            let mut params = Vec::new();
+            if let Some(tenant_id) = tenant_id {
+                sql.push_str(" WHERE tenant_id = ?");
+                params.push(SqlValue::Text(tenant_id.to_string()));
+            }
+            sql.push_str(
+                " GROUP BY tenant_id, user_id, agent_id, session_id, run_id
+                 ORDER BY last_ts DESC, tenant_id, user_id, agent_id, session_id, run_id",
+            );
+            if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(SqlValue::Integer(limit as i64));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
             let rows = stmt.query_map(params_from_iter(params), |row| {
-                let value_json: String = row.get(2)?;
-                let status: String = row.get(3)?;
-                let sources: String = row.get(7)?;
-                let scope_level: String = row.get(8)?;
-                Ok(Fact {
-                    fact_id: row.get(0)?,
-                    fact_key: row.get(1)?,
-                    value: decode_json_row(&value_json)?,
-                    status: parse_enum(&status, fact_status_from_str)?,
-                    validity: engram_types::Validity {
-                        valid_from: row.get::<_, Option<i64>>(4)?.map(from_millis),
-                        valid_to: row.get::<_, Option<i64>>(5)?.map(from_millis),
-                    },
-                    confidence: row.get(6)?,
-                    sources: decode_json_row(&sources)?,
-                    scope_level: parse_enum(&scope_level, scope_level_from_str)?,
-                    notes: row.get(9)?,
+                Ok(Scope {
+                    tenant_id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    agent_id: row.get(2)?,
+                    session_id: row.get(3)?,
+                    run_id: row.get(4)?,
                 })
             })?;
-
-            let mut facts = Vec::new();
-            for fact in rows {
-                facts.push(fact?);
-            }
-            Ok(facts)
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
         })
     }
 
-    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
-        self.with_connection(|conn| {
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.with_connection(SqlContext::scoped("suppress_memory", "suppressions", scope), |conn| {
+            let mut params = scope_params_ltm(scope);
+            params.extend([
+                SqlValue::Text(memory_kind_to_str(&item.kind).to_string()),
+                SqlValue::Text(item.id),
+                SqlValue::Text(reason.to_string()),
+                SqlValue::Integer(to_millis(Utc::now())),
+            ]);
             conn.execute(
-                "
-                INSERT INTO facts (
-                    tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
-                    valid_from, valid_to, confidence, sources, scope_level, notes
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT (tenant_id, user_id, agent_id, fact_id)
-                DO UPDATE SET fact_key = excluded.fact_key,
-                              value_json = excluded.value_json,
-                              status = excluded.status,
-                              valid_from = excluded.valid_from,
-                              valid_to = excluded.valid_to,
-                              confidence = excluded.confidence,
-                              sources = excluded.sources,
-                              scope_level = excluded.scope_level,
-                              notes = excluded.notes
-                ",
-                params_from_iter(vec![
-                    SqlValue::Text(scope.tenant_id.clone()),
-                    SqlValue::Text(scope.user_id.clone()),
-                    SqlValue::Text(scope.agent_id.clone()),
-                    SqlValue::Text(fact.fact_id),
-                    SqlValue::Text(fact.fact_key),
-                    SqlValue::Text(encode_json(&fact.value)?),
-                    SqlValue::Text(fact_status_to_str(&fact.status).to_string()),
-                    option_ts_to_value(fact.validity.valid_from),
-                    option_ts_to_value(fact.validity.valid_to),
-                    SqlValue::Real(fact.confidence),
-                    SqlValue::Text(encode_json(&fact.sources)?),
-                    SqlValue::Text(scope_level_to_str(&fact.scope_level).to_string()),
-                    SqlValue::Text(fact.notes),
-                ]),
+                &self.tables.sql(
+                    "INSERT INTO suppressions (tenant_id, user_id, agent_id, item_kind, item_id, reason, suppressed_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(tenant_id, user_id, agent_id, item_kind, item_id)
+                     DO UPDATE SET reason = excluded.reason, suppressed_at = excluded.suppressed_at",
+                ),
+                params_from_iter(params),
             )?;
             Ok(())
         })
     }
 
-    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
-        self.with_connection(|conn| {
-            let mut sql = String::from(
-                "SELECT episode_id, start_ts, end_ts, summary, highlights, tags, entities,
-                        sources, compression_level, recency_score
-                 FROM episodes WHERE tenant_id = ? AND user_id = ? AND agent_id = ?",
-            );
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        let context = SqlContext::scoped("unsuppress_memory", "suppressions", scope);
+        self.with_connection(context, |conn| {
             let mut params = scope_params_ltm(scope);
-            let mut filter_tags_in_memory = false;
-            let mut filter_entities_in_memory = false;
+            params.extend([
+                SqlValue::Text(memory_kind_to_str(&item.kind).to_string()),
+                SqlValue::Text(item.id.clone()),
+            ]);
+            let deleted = conn.execute(
+                &self.tables.sql(
+                    "DELETE FROM suppressions
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND item_kind = ? AND item_id = ?",
+                ),
+                params_from_iter(params),
+            )?;
+            Ok(deleted > 0)
+        })
+    }
 
-            if let Some(range) = &filter.time_range {
-                if let Some(start) = range.start {
-                    sql.push_str(" AND start_ts >= ?");
-                    params.push(SqlValue::Integer(to_millis(start)));
-                }
-                if let Some(end) = range.end {
-                    sql.push_str(" AND COALESCE(end_ts, start_ts) <= ?");
-                    params.push(SqlValue::Integer(to_millis(end)));
-                }
-            }
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        let context = SqlContext::scoped("list_suppressions", "suppressions", scope);
+        self.with_connection(context, |conn| {
+            let mut stmt = conn.prepare(
+                &self.tables.sql("SELECT item_kind, item_id, reason, suppressed_at FROM suppressions
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                 ORDER BY suppressed_at ASC, item_id ASC"),
+            )?;
+            let rows = stmt.query_map(params_from_iter(scope_params_ltm(scope)), |row| {
+                let kind: String = row.get(0)?;
+                Ok(Suppression {
+                    item: MemoryRef {
+                        kind: parse_enum(&kind, memory_kind_from_str)?,
+                        id: row.get(1)?,
+                    },
+                    reason: row.get(2)?,
+                    suppressed_at: from_millis(row.get(3)?),
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
 
-            if !filter.tags.is_empty() {
-                if episode_tags_present(conn, scope)? {
-                    let placeholders = sql_placeholders(filter.tags.len());
-                    sql.push_str(
-                        " AND EXISTS (
-                            SELECT 1 FROM episode_tags t
-                            WHERE t.tenant_id = episodes.tenant_id
-                              AND t.user_id = episodes.user_id
-                              AND t.agent_id = episodes.agent_id
-                              AND t.episode_id = episodes.episode_id
-                              AND t.tag IN (",
-                    );
-                    sql.push_str(&placeholders);
-                    sql.push_str("))");
-                    for tag in &filter.tags {
-                        params.push(SqlValue::Text(tag.clone()));
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.with_connection(SqlContext::scoped("evict_memory", "facts, episodes", scope), |conn| {
+            let tx = conn.savepoint()?;
+            let mut evicted = 0;
+            for item in items {
+                let mut params = scope_params_ltm(scope);
+                params.push(SqlValue::Text(item.id.clone()));
+                let (table, id_column) = match item.kind {
+                    MemoryKind::Fact => ("facts", "fact_id"),
+                    MemoryKind::Episode => ("episodes", "episode_id"),
+                };
+                evicted += tx.execute(
+                    &self.tables.sql(&format!(
+                        "DELETE FROM {}
+                         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND {} = ?",
+                        table, id_column
+                    )),
+                    params_from_iter(params.clone()),
+                )?;
+                if item.kind == MemoryKind::Episode && self.optional.text_index {
+                    tx.execute(
+                        &self.tables.sql(
+                            "DELETE FROM episode_text WHERE tenant_id = ? AND user_id = ?
+                               AND agent_id = ? AND episode_id = ?",
+                        ),
+                        params_from_iter(params.clone()),
+                    )?;
+                }
+                if item.kind == MemoryKind::Episode && self.optional.episode_index {
+                    for index in ["episode_tags", "episode_entities"] {
+                        tx.execute(
+                            &self.tables.sql(&format!(
+                                "DELETE FROM {} WHERE tenant_id = ? AND user_id = ?
+                                   AND agent_id = ? AND episode_id = ?",
+                                index
+                            )),
+                            params_from_iter(params.clone()),
+                        )?;
                     }
-                } else {
-                    filter_tags_in_memory = true;
+                }
+                params.insert(3, SqlValue::Text(memory_kind_to_str(&item.kind).to_string()));
+                for table in ["suppressions", "memory_embeddings"] {
+                    tx.execute(
+                        &self.tables.sql(&format!(
+                            "DELETE FROM {}
+                             WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                               AND item_kind = ? AND item_id = ?",
+                            table
+                        )),
+                        params_from_iter(params.clone()),
+                    )?;
                 }
             }
+            tx.commit()?;
+            Ok(evicted)
+        })
+    }
 
-            if !filter.entities.is_empty() {
-                if episode_entities_present(conn, scope)? {
-                    let placeholders = sql_placeholders(filter.entities.len());
-                    sql.push_str(
-                        " AND EXISTS (
-                            SELECT 1 FROM episode_entities e
-                            WHERE e.tenant_id = episodes.tenant_id
-                              AND e.user_id = episodes.user_id
-                              AND e.agent_id = episodes.agent_id
-                              AND e.episode_id = episodes.episode_id
-                              AND e.entity IN (",
-                    );
-                    sql.push_str(&placeholders);
-                    sql.push_str("))");
-                    for entity in &filter.entities {
-                        params.push(SqlValue::Text(entity.clone()));
-                    }
-                } else {
-                    filter_entities_in_memory = true;
-                }
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        let context = SqlContext::scoped("delete_record", item.kind.table().0, scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            let removed = self.remove_records(&tx, scope, item.kind, Some(&item.id), mode)?;
+            tx.commit()?;
+            Ok(removed > 0)
+        })
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        let tables = "events, facts, episodes, procedures, insights";
+        let context = SqlContext::scoped("clear_scope", tables, scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            let mut removed = 0;
+            for kind in RecordKind::ALL {
+                removed += self.remove_records(&tx, scope, kind, None, mode)?;
             }
+            tx.commit()?;
+            Ok(removed)
+        })
+    }
 
-            sql.push_str(" ORDER BY start_ts ASC, episode_id ASC");
-            let limit_in_sql = if filter_tags_in_memory || filter_entities_in_memory {
-                None
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        let (table, _) = kind.table();
+        self.with_connection(SqlContext::scoped("count_records", table, scope), |conn| {
+            let (filter, params) = if kind.run_level() {
+                (
+                    "tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? \
+                     AND run_id = ?",
+                    scope_params(scope),
+                )
             } else {
-                filter.limit
+                ("tenant_id = ? AND user_id = ? AND agent_id = ?", scope_params_ltm(scope))
             };
-            if let Some(limit) = limit_in_sql {
+            let count: i64 = conn.query_row(
+                &self.tables.sql(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter)),
+                params_from_iter(params),
+                |row| row.get(0),
+            )?;
+            Ok(count as usize)
+        })
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.with_connection(SqlContext::scoped("list_tombstones", "tombstones", scope), |conn| {
+            let mut sql = String::from(
+                "SELECT tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id,
+                        record_json, deleted_at
+                 FROM tombstones WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                 ORDER BY id DESC",
+            );
+            let mut params = scope_params_ltm(scope);
+            if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(SqlValue::Integer(limit as i64));
             }
-
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
             let rows = stmt.query_map(params_from_iter(params), |row| {
-                let highlights: String = row.get(4)?;
-                let tags: String = row.get(5)?;
-                let entities: String = row.get(6)?;
-                let sources: String = row.get(7)?;
-                let compression_level: String = row.get(8)?;
-                Ok(Episode {
-                    episode_id: row.get(0)?,
-                    time_range: engram_types::TimeRange {
-                        start: from_millis(row.get(1)?),
-                        end: row.get::<_, Option<i64>>(2)?.map(from_millis),
+                let kind: String = row.get(5)?;
+                let record: String = row.get(7)?;
+                Ok(Tombstone {
+                    scope: Scope {
+                        tenant_id: row.get(0)?,
+                        user_id: row.get(1)?,
+                        agent_id: row.get(2)?,
+                        session_id: row.get(3)?,
+                        run_id: row.get(4)?,
                     },
-                    summary: row.get(3)?,
-                    highlights: decode_json_row(&highlights)?,
-                    tags: decode_json_row(&tags)?,
-                    entities: decode_json_row(&entities)?,
-                    sources: decode_json_row(&sources)?,
-                    compression_level: parse_enum(&compression_level, compression_level_from_str)?,
-                    recency_score: row.get(9)?,
+                    item: RecordRef::new(
+                        parse_enum(&kind, RecordKind::parse)?,
+                        row.get::<_, String>(6)?,
+                    ),
+                    record: decode_json_row(&record)?,
+                    deleted_at: from_millis(row.get(8)?),
                 })
             })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
 
-            let mut episodes = Vec::new();
-            for episode in rows {
-                episodes.push(episode?);
-            }
-
-            if !filter_tags_in_memory && !filter_entities_in_memory {
-                return Ok(episodes);
-            }
-
-            let mut filtered = episodes
-                .into_iter()
-                .filter(|episode| {
-                    let tags_ok = if filter_tags_in_memory {
-                        episode.tags.iter().any(|tag| filter.tags.contains(tag))
-                    } else {
-                        true
-                    };
-                    let entities_ok = if filter_entities_in_memory {
-                        episode
-                            .entities
-                            .iter()
-                            .any(|entity| filter.entities.contains(entity))
-                    } else {
-                        true
-                    };
-                    tags_ok && entities_ok
-                })
-                .collect::<Vec<_>>();
-
-            if limit_in_sql.is_none() {
-                if let Some(limit) = filter.limit {
-                    if filtered.len() > limit {
-                        filtered.truncate(limit);
-                    }
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        let context = SqlContext::scoped("upsert_embeddings", "memory_embeddings", scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            {
+                let mut stmt = tx.prepare(
+                    &self.tables.sql("INSERT INTO memory_embeddings (
+                        tenant_id, user_id, agent_id, item_kind, item_id, model, vector_json,
+                        digest, embedded_at
+                     ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(tenant_id, user_id, agent_id, item_kind, item_id, model)
+                     DO UPDATE SET vector_json = excluded.vector_json, digest = excluded.digest,
+                        embedded_at = excluded.embedded_at"),
+                )?;
+                for embedding in embeddings {
+                    let mut params = scope_params_ltm(scope);
+                    params.extend([
+                        SqlValue::Text(memory_kind_to_str(&embedding.item.kind).to_string()),
+                        SqlValue::Text(embedding.item.id),
+                        SqlValue::Text(embedding.model),
+                        SqlValue::Text(encode_json(&embedding.vector)?),
+                        SqlValue::Text(embedding.digest),
+                        SqlValue::Integer(to_millis(embedding.embedded_at)),
+                    ]);
+                    stmt.execute(params_from_iter(params))?;
                 }
             }
-
-            Ok(filtered)
+            tx.commit()?;
+            Ok(())
         })
     }
 
-    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
-        self.with_connection(|conn| {
-            let tx = conn.transaction()?;
-            tx.execute(
-                "
-                INSERT INTO episodes (
-                    tenant_id, user_id, agent_id, episode_id, start_ts, end_ts, summary,
-                    highlights, tags, entities, sources, compression_level, recency_score
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ",
-                params_from_iter(vec![
-                    SqlValue::Text(scope.tenant_id.clone()),
-                    SqlValue::Text(scope.user_id.clone()),
-                    SqlValue::Text(scope.agent_id.clone()),
-                    SqlValue::Text(episode.episode_id.clone()),
-                    SqlValue::Integer(to_millis(episode.time_range.start)),
-                    option_ts_to_value(episode.time_range.end),
-                    SqlValue::Text(episode.summary),
-                    SqlValue::Text(encode_json(&episode.highlights)?),
-                    SqlValue::Text(encode_json(&episode.tags)?),
-                    SqlValue::Text(encode_json(&episode.entities)?),
-                    SqlValue::Text(encode_json(&episode.sources)?),
-                    SqlValue::Text(compression_level_to_str(&episode.compression_level).to_string()),
-                    option_f64_to_value(episode.recency_score),
-                ]),
-            )?;
-            insert_episode_tags(
-                &tx,
-                scope,
-                &episode.episode_id,
-                &episode.tags,
-                &episode.entities,
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        let context = SqlContext::scoped("list_embeddings", "memory_embeddings", scope);
+        self.with_connection(context, |conn| {
+            let mut stmt = conn.prepare(
+                &self.tables.sql(
+                    "SELECT item_kind, item_id, vector_json, digest, embedded_at FROM memory_embeddings
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND model = ?
+                     ORDER BY item_kind ASC, item_id ASC",
+                ),
             )?;
+            let mut params = scope_params_ltm(scope);
+            params.push(SqlValue::Text(model.to_string()));
+            let rows = stmt.query_map(params_from_iter(params), |row| {
+                let kind: String = row.get(0)?;
+                let item = MemoryRef {
+                    kind: parse_enum(&kind, memory_kind_from_str)?,
+                    id: row.get(1)?,
+                };
+                Ok((item, row.get::<_, String>(2)?, row.get(3)?, row.get(4)?))
+            })?;
+            let mut embeddings = Vec::new();
+            for row in rows {
+                let (item, vector, digest, embedded_at) = row?;
+                embeddings.push(MemoryEmbedding {
+                    item,
+                    model: model.to_string(),
+                    vector: decode_json(&vector)?,
+                    digest,
+                    embedded_at: from_millis(embedded_at),
+                });
+            }
+            Ok(embeddings)
+        })
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        let context = SqlContext::scoped("delete_embeddings", "memory_embeddings", scope);
+        self.with_connection(context, |conn| {
+            let tx = conn.savepoint()?;
+            let mut deleted = 0;
+            for item in items {
+                let mut params = scope_params_ltm(scope);
+                params.extend([
+                    SqlValue::Text(memory_kind_to_str(&item.kind).to_string()),
+                    SqlValue::Text(item.id.clone()),
+                    SqlValue::Text(model.to_string()),
+                ]);
+                deleted += tx.execute(
+                    &self.tables.sql("DELETE FROM memory_embeddings
+                     WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                       AND item_kind = ? AND item_id = ? AND model = ?"),
+                    params_from_iter(params),
+                )?;
+            }
             tx.commit()?;
+            Ok(deleted)
+        })
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        let outcome = outcome_for_run(scope, outcome)?;
+        let context = SqlContext::scoped("record_run_outcome", "run_outcomes", scope);
+        self.with_connection(context, |conn| {
+            let mut params = scope_params(scope);
+            params.extend([
+                SqlValue::Text(run_status_to_str(&outcome.status).to_string()),
+                outcome.score.map_or(SqlValue::Null, SqlValue::Real),
+                SqlValue::Text(outcome.error_summary),
+                SqlValue::Integer(outcome.duration_ms as i64),
+                SqlValue::Integer(to_millis(outcome.recorded_at)),
+            ]);
+            conn.execute(
+                &self.tables.sql("INSERT INTO run_outcomes (
+                    tenant_id, user_id, agent_id, session_id, run_id,
+                    status, score, error_summary, duration_ms, recorded_at
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(tenant_id, user_id, agent_id, session_id, run_id)
+                 DO UPDATE SET status = excluded.status, score = excluded.score,
+                    error_summary = excluded.error_summary, duration_ms = excluded.duration_ms,
+                    recorded_at = excluded.recorded_at"),
+                params_from_iter(params),
+            )?;
             Ok(())
         })
     }
 
-    fn list_procedures(
+    fn list_run_outcomes(
         &self,
         scope: &Scope,
-        task_type: &str,
         limit: Option<usize>,
-    ) -> StoreResult<Vec<Procedure>> {
-        self.with_connection(|conn| {
+    ) -> StoreResult<Vec<RunOutcome>> {
+        let context = SqlContext::scoped("list_run_outcomes", "run_outcomes", scope);
+        self.with_connection(context, |conn| {
             let mut sql = String::from(
-                "SELECT procedure_id, task_type, content_json, priority, sources, applicability
-                 FROM procedures WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND task_type = ?",
+                "SELECT session_id, run_id, status, score, error_summary, duration_ms, recorded_at
+                 FROM run_outcomes
+                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ?
+                 ORDER BY recorded_at DESC, run_id ASC",
             );
             let mut params = scope_params_ltm(scope);
-            params.push(SqlValue::Text(task_type.to_string()));
-
-            sql.push_str(" ORDER BY priority DESC, procedure_id ASC");
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(SqlValue::Integer(limit as i64));
             }
-
-            let mut stmt = conn.prepare(&sql)?;
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
             let rows = stmt.query_map(params_from_iter(params), |row| {
-                let content: String = row.get(2)?;
-                let sources: String = row.get(4)?;
-                let applicability: String = row.get(5)?;
-                Ok(Procedure {
-                    procedure_id: row.get(0)?,
-                    task_type: row.get(1)?,
-                    content: decode_json_row(&content)?,
-                    priority: row.get(3)?,
-                    sources: decode_json_row(&sources)?,
-                    applicability: decode_json_row(&applicability)?,
+                let status: String = row.get(2)?;
+                let duration_ms: i64 = row.get(5)?;
+                Ok(RunOutcome {
+                    session_id: row.get(0)?,
+                    run_id: row.get(1)?,
+                    status: parse_enum(&status, run_status_from_str)?,
+                    score: row.get(3)?,
+                    error_summary: row.get(4)?,
+                    duration_ms: duration_ms as u64,
+                    recorded_at: from_millis(row.get(6)?),
                 })
             })?;
-
-            let mut procedures = Vec::new();
-            for procedure in rows {
-                procedures.push(procedure?);
-            }
-            Ok(procedures)
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
         })
     }
 
-    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
-        self.with_connection(|conn| {
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        credibility.check()?;
+        let context = SqlContext::new("set_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_connection(context, |conn| {
             conn.execute(
-                "
-                INSERT INTO procedures (
-                    tenant_id, user_id, agent_id, procedure_id, task_type, content_json,
-                    priority, sources, applicability
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT (tenant_id, user_id, agent_id, procedure_id)
-                DO UPDATE SET task_type = excluded.task_type,
-                              content_json = excluded.content_json,
-                              priority = excluded.priority,
-                              sources = excluded.sources,
-                              applicability = excluded.applicability
-                ",
+                &self.tables.sql(
+                    "INSERT INTO source_credibility (tenant_id, credibility_json, updated_at)
+                     VALUES (?, ?, ?)
+                     ON CONFLICT(tenant_id) DO UPDATE SET
+                        credibility_json = excluded.credibility_json, updated_at = excluded.updated_at",
+                ),
                 params_from_iter(vec![
-                    SqlValue::Text(scope.tenant_id.clone()),
-                    SqlValue::Text(scope.user_id.clone()),
-                    SqlValue::Text(scope.agent_id.clone()),
-                    SqlValue::Text(procedure.procedure_id),
-                    SqlValue::Text(procedure.task_type),
-                    SqlValue::Text(encode_json(&procedure.content)?),
-                    SqlValue::Integer(procedure.priority as i64),
-                    SqlValue::Text(encode_json(&procedure.sources)?),
-                    SqlValue::Text(encode_json(&procedure.applicability)?),
+                    SqlValue::Text(tenant_id.to_string()),
+                    SqlValue::Text(encode_json(&credibility)?),
+                    SqlValue::Integer(to_millis(Utc::now())),
                 ]),
             )?;
             Ok(())
         })
     }
 
-    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
-        self.with_connection(|conn| {
-            let mut sql = String::from(
-                "SELECT insight_id, kind, statement, trigger, confidence, validation_state,
-                        tests_suggested, expires_at, sources
-                 FROM insights
-                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?",
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        let context = SqlContext::new("get_source_credibility", "source_credibility", &[tenant_id]);
+        self.with_connection(context, |conn| {
+            let result = conn.query_row(
+                &self.tables.sql(
+                    "SELECT credibility_json FROM source_credibility WHERE tenant_id = ?",
+                ),
+                params_from_iter(vec![SqlValue::Text(tenant_id.to_string())]),
+                |row| row.get::<_, String>(0),
             );
-            let mut params = scope_params(scope);
-
-            if let Some(states) = &filter.validation_state {
-                if !states.is_empty() {
-                    sql.push_str(" AND validation_state IN (");
-                    for (idx, state) in states.iter().enumerate() {
-                        if idx > 0 {
-                            sql.push_str(", ");
-                        }
-                        sql.push_str("?");
-                        params.push(SqlValue::Text(validation_state_to_str(state).to_string()));
-                    }
-                    sql.push(')');
-                }
-            }
-
-            sql.push_str(" ORDER BY validation_state DESC, confidence DESC, insight_id ASC");
-            if let Some(limit) = filter.limit {
-                sql.push_str(" LIMIT ?");
-                params.push(SqlValue::Integer(limit as i64));
-            }
-
-            let mut stmt = conn.prepare(&sql)?;
-            let rows = stmt.query_map(params_from_iter(params), |row| {
-                let kind: String = row.get(1)?;
-                let trigger: String = row.get(3)?;
-                let validation_state: String = row.get(5)?;
-                let tests: String = row.get(6)?;
-                let sources: String = row.get(8)?;
-                Ok(InsightItem {
-                    id: row.get(0)?,
-                    kind: parse_enum(&kind, insight_type_from_str)?,
-                    statement: row.get(2)?,
-                    trigger: parse_enum(&trigger, insight_trigger_from_str)?,
-                    confidence: row.get(4)?,
-                    validation_state: parse_enum(&validation_state, validation_state_from_str)?,
-                    tests_suggested: decode_json_row(&tests)?,
-                    expires_at: row.get(7)?,
-                    sources: decode_json_row(&sources)?,
-                })
-            })?;
-
-            let mut insights = Vec::new();
-            for insight in rows {
-                insights.push(insight?);
+            match result {
+                Ok(json) => Ok(Some(decode_json(&json)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(err) => Err(err.into()),
             }
-            Ok(insights)
         })
     }
 
-    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
-        self.with_connection(|conn| {
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        locale.check()?;
+        let context = SqlContext::new("set_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_connection(context, |conn| {
             conn.execute(
-                "
-                INSERT INTO insights (
-                    tenant_id, user_id, agent_id, session_id, run_id, insight_id,
-                    kind, statement, trigger, confidence, validation_state,
-                    tests_suggested, expires_at, sources
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ",
+                &self.tables.sql(
+                    "INSERT INTO user_locales (tenant_id, user_id, locale_json, updated_at)
+                     VALUES (?, ?, ?, ?)
+                     ON CONFLICT(tenant_id, user_id) DO UPDATE SET
+                        locale_json = excluded.locale_json, updated_at = excluded.updated_at",
+                ),
                 params_from_iter(vec![
-                    SqlValue::Text(scope.tenant_id.clone()),
-                    SqlValue::Text(scope.user_id.clone()),
-                    SqlValue::Text(scope.agent_id.clone()),
-                    SqlValue::Text(scope.session_id.clone()),
-                    SqlValue::Text(scope.run_id.clone()),
-                    SqlValue::Text(insight.id),
-                    SqlValue::Text(insight_type_to_str(&insight.kind).to_string()),
-                    SqlValue::Text(insight.statement),
-                    SqlValue::Text(insight_trigger_to_str(&insight.trigger).to_string()),
-                    SqlValue::Real(insight.confidence),
-                    SqlValue::Text(validation_state_to_str(&insight.validation_state).to_string()),
-                    SqlValue::Text(encode_json(&insight.tests_suggested)?),
-                    SqlValue::Text(insight.expires_at),
-                    SqlValue::Text(encode_json(&insight.sources)?),
+                    SqlValue::Text(tenant_id.to_string()),
+                    SqlValue::Text(user_id.to_string()),
+                    SqlValue::Text(encode_json(&locale)?),
+                    SqlValue::Integer(to_millis(Utc::now())),
                 ]),
             )?;
             Ok(())
         })
     }
 
-    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
-        self.with_connection(|conn| {
-            let generated = to_millis(packet.meta.generated_at);
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        let context = SqlContext::new("get_user_locale", "user_locales", &[tenant_id, user_id]);
+        self.with_connection(context, |conn| {
+            let result = conn.query_row(
+                &self.tables.sql(
+                    "SELECT locale_json FROM user_locales WHERE tenant_id = ? AND user_id = ?",
+                ),
+                params_from_iter(vec![
+                    SqlValue::Text(tenant_id.to_string()),
+                    SqlValue::Text(user_id.to_string()),
+                ]),
+                |row| row.get::<_, String>(0),
+            );
+            match result {
+                Ok(json) => Ok(Some(decode_json(&json)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.with_connection(SqlContext::new("append_change", "changelog", &[]), |conn| {
             conn.execute(
-                "
-                INSERT INTO context_builds (
-                    tenant_id, user_id, agent_id, session_id, run_id, ts, packet_json
-                ) VALUES (?, ?, ?, ?, ?, ?, ?)
-                ",
+                &self.tables.sql("INSERT INTO changelog (ts, op, correlation_id) VALUES (?, ?, ?)"),
                 params_from_iter(vec![
-                    SqlValue::Text(scope.tenant_id.clone()),
-                    SqlValue::Text(scope.user_id.clone()),
-                    SqlValue::Text(scope.agent_id.clone()),
-                    SqlValue::Text(scope.session_id.clone()),
-                    SqlValue::Text(scope.run_id.clone()),
-                    SqlValue::Integer(generated),
-                    SqlValue::Text(encode_json(&packet)?),
+                    SqlValue::Integer(to_millis(Utc::now())),
+                    SqlValue::Text(encode_json(&op)?),
+                    correlation_id().map_or(SqlValue::Null, SqlValue::Text),
                 ]),
             )?;
-            Ok(())
+            Ok(conn.last_insert_rowid() as u64)
         })
     }
 
-    fn list_context_builds(
+    fn write_logged(
         &self,
-        scope: &Scope,
-        limit: Option<usize>,
-    ) -> StoreResult<Vec<MemoryPacket>> {
-        self.with_connection(|conn| {
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        let conn = self.pool.get().map_err(|err| StoreError::Unavailable(err.to_string()))?;
+        let timeout = effective_timeout(self.statement_timeout).unwrap_or(DEFAULT_BUSY_TIMEOUT);
+        conn.busy_timeout(timeout)?;
+        // Taking the write lock up front keeps the write's own savepoints from
+        // deadlocking against another writer.
+        conn.execute_batch("BEGIN IMMEDIATE")?;
+        let (conn, result) = self.pinned.run(conn, || {
+            for op in write()? {
+                self.append_change(op)?;
+            }
+            Ok(())
+        })?;
+        let committed = result.and_then(|()| Ok(conn.execute_batch("COMMIT")?));
+        if committed.is_err() {
+            // The connection goes back to the pool; it must not stay in the transaction.
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+        committed
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.load_sync_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.save_sync_cursor(name, seq)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.with_connection(SqlContext::new("list_changes", "changelog", &[]), |conn| {
             let mut sql = String::from(
-                "SELECT packet_json FROM context_builds
-                 WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?
-                 ORDER BY ts ASC",
+                "SELECT seq, ts, op, correlation_id FROM changelog WHERE seq > ? ORDER BY seq ASC",
             );
-            let mut params = scope_params(scope);
+            let mut params = vec![SqlValue::Integer(after_seq as i64)];
             if let Some(limit) = limit {
                 sql.push_str(" LIMIT ?");
                 params.push(SqlValue::Integer(limit as i64));
             }
 
-            let mut stmt = conn.prepare(&sql)?;
-            let rows = stmt.query_map(params_from_iter(params), |row| row.get::<_, String>(0))?;
-            let mut packets = Vec::new();
-            for row in rows {
-                packets.push(decode_json(&row?)?);
+            let mut stmt = conn.prepare(&self.tables.sql(&sql))?;
+            let rows = stmt.query_map(params_from_iter(params), |row| {
+                let op: String = row.get(2)?;
+                Ok(Change {
+                    seq: row.get::<_, i64>(0)? as u64,
+                    ts: from_millis(row.get(1)?),
+                    correlation_id: row.get(3)?,
+                    op: decode_json_row(&op)?,
+                })
+            })?;
+            let mut changes = Vec::new();
+            for change in rows {
+                changes.push(change?);
             }
-            Ok(packets)
+            Ok(changes)
+        })
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.with_connection(SqlContext::new("last_change_seq", "changelog", &[]), |conn| {
+            let seq: i64 = conn.query_row(
+                &self.tables.sql("SELECT COALESCE(MAX(seq), 0) FROM changelog"),
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(seq as u64)
         })
     }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.metrics.snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        StoreCapabilities {
+            vector_search: true,
+            indexed_filters: self.optional.event_index && self.optional.episode_index,
+            transactions: true,
+            bulk_ops: true,
+            full_text_search: self.optional.text_index,
+            subscriptions: false,
+        }
+    }
 }
 
 fn encode_json<T: Serialize>(value: &T) -> StoreResult<String> {
     Ok(serde_json::to_string(value)?)
 }
 
+/// Applies `patch` to the run's stored working state, comparing and swapping when the
+/// patch expects a state version.
+fn patch_working_state_row(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+    patch: WorkingStatePatch,
+) -> StoreResult<WorkingState> {
+    let stored = working_state_json(conn, tables, scope)?;
+    let current = stored.as_deref().map(decode_json).transpose()?.unwrap_or_default();
+    check_state_version(&current, &patch)?;
+    let expected = patch.expected_state_version;
+    let next = apply_working_state_patch(&current, patch);
+    let state_json = encode_json(&next)?;
+    let mut params = scope_params(scope);
+    params.extend([
+        SqlValue::Text(state_json.clone()),
+        SqlValue::Integer(to_millis(Utc::now())),
+    ]);
+    let Some(expected) = expected else {
+        conn.execute(
+            &tables.sql("
+            INSERT INTO wm_state (
+                tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
+            DO UPDATE SET state_json = excluded.state_json, updated_at = excluded.updated_at
+            "),
+            params_from_iter(params),
+        )?;
+        return Ok(next);
+    };
+    // Compare and swap: the write only lands on the state that was read.
+    let written = match &stored {
+        Some(stored) if *stored == state_json => 1,
+        Some(stored) => {
+            params.push(SqlValue::Text(stored.clone()));
+            conn.execute(
+                &tables.sql("UPDATE wm_state SET state_json = ?6, updated_at = ?7
+                 WHERE tenant_id = ?1 AND user_id = ?2 AND agent_id = ?3
+                   AND session_id = ?4 AND run_id = ?5 AND state_json = ?8"),
+                params_from_iter(params),
+            )?
+        }
+        None => conn.execute(
+            &tables.sql("INSERT INTO wm_state (
+                tenant_id, user_id, agent_id, session_id, run_id, state_json, updated_at
+             ) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id) DO NOTHING"),
+            params_from_iter(params),
+        )?,
+    };
+    if written == 0 {
+        let stored = working_state_json(conn, tables, scope)?;
+        let stored = stored.as_deref().map(decode_json).transpose()?;
+        return Err(state_version_conflict(expected, stored));
+    }
+    Ok(next)
+}
+
+fn working_state_json(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+) -> StoreResult<Option<String>> {
+    let result = conn.query_row(
+        &tables.sql("SELECT state_json FROM wm_state
+         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND session_id = ? AND run_id = ?"),
+        params_from_iter(scope_params(scope)),
+        |row| row.get::<_, String>(0),
+    );
+    match result {
+        Ok(payload) => Ok(Some(payload)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
 fn decode_json<T: DeserializeOwned>(value: &str) -> StoreResult<T> {
     Ok(serde_json::from_str(value)?)
 }
@@ -1245,8 +2784,24 @@ fn sql_placeholders(count: usize) -> String {
     out
 }
 
+fn next_event_seq(conn: &Connection, tables: &TablePrefix, scope: &Scope) -> StoreResult<i64> {
+    let seq = conn.query_row(
+        &tables.sql(
+            "INSERT INTO event_sequences (tenant_id, user_id, agent_id, session_id, run_id, last_seq)
+             VALUES (?, ?, ?, ?, ?, 1)
+             ON CONFLICT (tenant_id, user_id, agent_id, session_id, run_id)
+             DO UPDATE SET last_seq = last_seq + 1
+             RETURNING last_seq",
+        ),
+        params_from_iter(scope_params(scope)),
+        |row| row.get(0),
+    )?;
+    Ok(seq)
+}
+
 fn insert_event_tags(
     conn: &Connection,
+    tables: &TablePrefix,
     scope: &Scope,
     event_id: &str,
     tags: &[String],
@@ -1260,11 +2815,11 @@ fn insert_event_tags(
 
     if !tags.is_empty() {
         let mut stmt = conn.prepare(
-            "
+            &tables.sql("
             INSERT OR IGNORE INTO event_tags (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, tag
             ) VALUES (?, ?, ?, ?, ?, ?, ?)
-            ",
+            "),
         )?;
         for tag in tags {
             stmt.execute(params_from_iter(vec![
@@ -1281,11 +2836,11 @@ fn insert_event_tags(
 
     if !entities.is_empty() {
         let mut stmt = conn.prepare(
-            "
+            &tables.sql("
             INSERT OR IGNORE INTO event_entities (
                 tenant_id, user_id, agent_id, session_id, run_id, event_id, entity
             ) VALUES (?, ?, ?, ?, ?, ?, ?)
-            ",
+            "),
         )?;
         for entity in entities {
             stmt.execute(params_from_iter(vec![
@@ -1305,6 +2860,7 @@ fn insert_event_tags(
 
 fn insert_episode_tags(
     conn: &Connection,
+    tables: &TablePrefix,
     scope: &Scope,
     episode_id: &str,
     tags: &[String],
@@ -1318,11 +2874,11 @@ fn insert_episode_tags(
 
     if !tags.is_empty() {
         let mut stmt = conn.prepare(
-            "
+            &tables.sql("
             INSERT OR IGNORE INTO episode_tags (
                 tenant_id, user_id, agent_id, episode_id, tag
             ) VALUES (?, ?, ?, ?, ?)
-            ",
+            "),
         )?;
         for tag in tags {
             stmt.execute(params_from_iter(vec![
@@ -1337,11 +2893,11 @@ fn insert_episode_tags(
 
     if !entities.is_empty() {
         let mut stmt = conn.prepare(
-            "
+            &tables.sql("
             INSERT OR IGNORE INTO episode_entities (
                 tenant_id, user_id, agent_id, episode_id, entity
             ) VALUES (?, ?, ?, ?, ?)
-            ",
+            "),
         )?;
         for entity in entities {
             stmt.execute(params_from_iter(vec![
@@ -1357,45 +2913,385 @@ fn insert_episode_tags(
     Ok(())
 }
 
-fn episode_tags_present(conn: &Connection, scope: &Scope) -> StoreResult<bool> {
-    let mut stmt = conn.prepare(
-        "SELECT 1 FROM episode_tags WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+/// Indexes tags and entities of episodes written before every episode was indexed
+/// on insert, so tag and entity filters can always go through the index tables.
+fn backfill_episode_index(conn: &Connection, tables: &TablePrefix) -> StoreResult<()> {
+    conn.execute_batch(
+        &tables.sql("
+            INSERT OR IGNORE INTO episode_tags (tenant_id, user_id, agent_id, episode_id, tag)
+                SELECT e.tenant_id, e.user_id, e.agent_id, e.episode_id, t.value
+                FROM episodes e, json_each(e.tags) t;
+            INSERT OR IGNORE INTO episode_entities (tenant_id, user_id, agent_id, episode_id, entity)
+                SELECT e.tenant_id, e.user_id, e.agent_id, e.episode_id, x.value
+                FROM episodes e, json_each(e.entities) x;
+            "),
     )?;
-    match stmt.query_row(params_from_iter(scope_params_ltm(scope)), |_| Ok(())) {
-        Ok(_) => Ok(true),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
-        Err(err) => Err(err.into()),
-    }
+    Ok(())
 }
 
-fn episode_entities_present(conn: &Connection, scope: &Scope) -> StoreResult<bool> {
-    let mut stmt = conn.prepare(
-        "SELECT 1 FROM episode_entities WHERE tenant_id = ? AND user_id = ? AND agent_id = ? LIMIT 1",
+/// Indexes the text of events and episodes written before the store kept a full-text
+/// index. Mirrors [`event_text`] and [`episode_text`] in SQL.
+fn backfill_text_index(conn: &Connection, tables: &TablePrefix) -> StoreResult<()> {
+    conn.execute_batch(
+        &tables.sql("
+            INSERT INTO event_text (body, event_id)
+                SELECT COALESCE((
+                    SELECT group_concat(t.value, ' ') FROM json_tree(e.payload) t
+                    WHERE t.type = 'text'
+                ), ''), e.event_id
+                FROM events e;
+            INSERT INTO episode_text (body, tenant_id, user_id, agent_id, episode_id)
+                SELECT e.summary || COALESCE((
+                    SELECT ' ' || group_concat(h.value, ' ') FROM json_each(e.highlights) h
+                ), ''), e.tenant_id, e.user_id, e.agent_id, e.episode_id
+                FROM episodes e;
+            "),
     )?;
-    match stmt.query_row(params_from_iter(scope_params_ltm(scope)), |_| Ok(())) {
-        Ok(_) => Ok(true),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
-        Err(err) => Err(err.into()),
+    Ok(())
+}
+
+fn upsert_fact_row(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+    fact: &Fact,
+) -> StoreResult<()> {
+    conn.execute(
+        &tables.sql("
+        INSERT INTO facts (
+            tenant_id, user_id, agent_id, fact_id, fact_key, value_json, status,
+            valid_from, valid_to, confidence, sources, scope_level, notes, pinned, lang
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (tenant_id, user_id, agent_id, fact_id)
+        DO UPDATE SET fact_key = excluded.fact_key,
+                      value_json = excluded.value_json,
+                      status = excluded.status,
+                      valid_from = excluded.valid_from,
+                      valid_to = excluded.valid_to,
+                      confidence = excluded.confidence,
+                      sources = excluded.sources,
+                      scope_level = excluded.scope_level,
+                      notes = excluded.notes,
+                      pinned = excluded.pinned,
+                      lang = excluded.lang
+        "),
+        params_from_iter(vec![
+            SqlValue::Text(scope.tenant_id.clone()),
+            SqlValue::Text(scope.user_id.clone()),
+            SqlValue::Text(scope.agent_id.clone()),
+            SqlValue::Text(fact.fact_id.clone()),
+            SqlValue::Text(fact.fact_key.clone()),
+            SqlValue::Text(encode_json(&fact.value)?),
+            SqlValue::Text(fact_status_to_str(&fact.status).to_string()),
+            option_ts_to_value(fact.validity.valid_from),
+            option_ts_to_value(fact.validity.valid_to),
+            SqlValue::Real(fact.confidence),
+            SqlValue::Text(encode_json(&fact.sources)?),
+            SqlValue::Text(scope_level_to_str(&fact.scope_level).to_string()),
+            SqlValue::Text(fact.notes.clone()),
+            SqlValue::Integer(fact.pinned as i64),
+            fact.lang.clone().map_or(SqlValue::Null, SqlValue::Text),
+        ]),
+    )?;
+    record_fact_version(conn, tables, scope, fact)
+}
+
+/// Adds the version a write of `fact` makes to `fact_versions`, unless it repeats
+/// the fact's last one.
+fn record_fact_version(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+    fact: &Fact,
+) -> StoreResult<()> {
+    let mut params = scope_params_ltm(scope);
+    params.push(SqlValue::Text(fact.fact_id.clone()));
+    let latest = match conn.query_row(
+        &tables.sql("SELECT version, recorded_at, fact_json FROM fact_versions
+         WHERE tenant_id = ? AND user_id = ? AND agent_id = ? AND fact_id = ?
+         ORDER BY version DESC LIMIT 1"),
+        params_from_iter(params),
+        fact_version_from_row,
+    ) {
+        Ok(version) => Some(version),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(err) => return Err(err.into()),
+    };
+    let Some(version) = next_fact_version(latest.as_ref(), fact, Utc::now()) else {
+        return Ok(());
+    };
+    conn.execute(
+        &tables.sql("INSERT INTO fact_versions (
+            tenant_id, user_id, agent_id, fact_id, version, fact_key, fact_json, recorded_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"),
+        params_from_iter(vec![
+            SqlValue::Text(scope.tenant_id.clone()),
+            SqlValue::Text(scope.user_id.clone()),
+            SqlValue::Text(scope.agent_id.clone()),
+            SqlValue::Text(fact.fact_id.clone()),
+            SqlValue::Integer(i64::from(version.version)),
+            SqlValue::Text(fact.fact_key.clone()),
+            SqlValue::Text(encode_json(&version.fact)?),
+            SqlValue::Integer(to_millis(version.recorded_at)),
+        ]),
+    )?;
+    Ok(())
+}
+
+fn fact_version_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FactVersion> {
+    Ok(FactVersion {
+        version: row.get(0)?,
+        recorded_at: from_millis(row.get(1)?),
+        fact: decode_json_row(&row.get::<_, String>(2)?)?,
+    })
+}
+
+fn insert_event_text(
+    conn: &Connection,
+    tables: &TablePrefix,
+    event_id: &str,
+    payload: &Value,
+) -> StoreResult<()> {
+    conn.prepare_cached(&tables.sql("INSERT INTO event_text (body, event_id) VALUES (?, ?)"))?
+        .execute(params_from_iter([event_text(payload), event_id.to_string()]))?;
+    Ok(())
+}
+
+fn insert_episode_text(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+    episode: &Episode,
+) -> StoreResult<()> {
+    conn.execute(
+        &tables.sql("INSERT INTO episode_text (body, tenant_id, user_id, agent_id, episode_id)
+         VALUES (?, ?, ?, ?, ?)"),
+        params_from_iter([
+            episode_text(episode),
+            scope.tenant_id.clone(),
+            scope.user_id.clone(),
+            scope.agent_id.clone(),
+            episode.episode_id.clone(),
+        ]),
+    )?;
+    Ok(())
+}
+
+/// Tombstones of the `kind` records that `filter` matches, as they are now.
+fn load_tombstones(
+    conn: &Connection,
+    tables: &TablePrefix,
+    scope: &Scope,
+    kind: RecordKind,
+    filter: &str,
+    params: &[SqlValue],
+) -> StoreResult<Vec<Tombstone>> {
+    let columns = match kind {
+        RecordKind::Event => {
+            "event_id, tenant_id, user_id, agent_id, session_id, run_id, ts, kind, payload, tags,
+             entities, seq, lang"
+        }
+        RecordKind::Fact => {
+            "fact_id, fact_key, value_json, status, valid_from, valid_to, confidence, sources,
+             scope_level, notes, pinned, lang"
+        }
+        RecordKind::Episode => {
+            "episode_id, start_ts, end_ts, summary, highlights, tags, entities, sources,
+             compression_level, recency_score, lang"
+        }
+        RecordKind::Procedure => {
+            "procedure_id, task_type, content_json, priority, sources, applicability"
+        }
+        RecordKind::Insight => {
+            "insight_id, kind, statement, trigger, confidence, validation_state, tests_suggested,
+             expires_at, sources, parent_insight_id"
+        }
+    };
+    let (table, _) = kind.table();
+    let sql = format!("SELECT {} FROM {} WHERE {}", columns, table, filter);
+    let mut stmt = conn.prepare(&tables.sql(&sql))?;
+    let params = params_from_iter(params);
+    let records = match kind {
+        RecordKind::Event => {
+            record_values(stmt.query_map(params, event_from_row)?, |event| &event.event_id)?
+        }
+        RecordKind::Fact => {
+            record_values(stmt.query_map(params, fact_from_row)?, |fact| &fact.fact_id)?
+        }
+        RecordKind::Episode => record_values(stmt.query_map(params, episode_from_row)?, |episode| {
+            &episode.episode_id
+        })?,
+        RecordKind::Procedure => {
+            record_values(stmt.query_map(params, procedure_from_row)?, |procedure| {
+                &procedure.procedure_id
+            })?
+        }
+        RecordKind::Insight => {
+            record_values(stmt.query_map(params, insight_from_row)?, |insight| &insight.id)?
+        }
+    };
+    let deleted_at = Utc::now();
+    Ok(records
+        .into_iter()
+        .map(|(id, record)| Tombstone {
+            scope: kind.record_scope(scope),
+            item: RecordRef::new(kind, id),
+            record,
+            deleted_at,
+        })
+        .collect())
+}
+
+/// Ids and JSON of the records `rows` yields.
+fn record_values<T: Serialize>(
+    rows: impl Iterator<Item = rusqlite::Result<T>>,
+    id: fn(&T) -> &str,
+) -> StoreResult<Vec<(String, Value)>> {
+    let mut records = Vec::new();
+    for record in rows {
+        let record = record?;
+        records.push((id(&record).to_string(), serde_json::to_value(&record)?));
     }
+    Ok(records)
+}
+
+fn insert_tombstone(
+    conn: &Connection,
+    tables: &TablePrefix,
+    tombstone: &Tombstone,
+) -> StoreResult<()> {
+    let mut params = scope_params(&tombstone.scope);
+    params.extend([
+        SqlValue::Text(tombstone.item.kind.as_str().to_string()),
+        SqlValue::Text(tombstone.item.id.clone()),
+        SqlValue::Text(encode_json(&tombstone.record)?),
+        SqlValue::Integer(to_millis(tombstone.deleted_at)),
+    ]);
+    conn.execute(
+        &tables.sql("INSERT INTO tombstones (
+            tenant_id, user_id, agent_id, session_id, run_id, item_kind, item_id, record_json,
+            deleted_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"),
+        params_from_iter(params),
+    )?;
+    Ok(())
+}
+
+/// An FTS5 query matching any of `terms`, each as a phrase.
+fn fts_query(terms: &[&str]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+fn event_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Event> {
+    let kind: String = row.get(7)?;
+    let payload: String = row.get(8)?;
+    let tags: String = row.get(9)?;
+    let entities: String = row.get(10)?;
+    Ok(Event {
+        event_id: row.get(0)?,
+        scope: Scope {
+            tenant_id: row.get(1)?,
+            user_id: row.get(2)?,
+            agent_id: row.get(3)?,
+            session_id: row.get(4)?,
+            run_id: row.get(5)?,
+        },
+        ts: from_millis(row.get(6)?),
+        kind: parse_enum(&kind, event_kind_from_str)?,
+        payload: decode_json_row(&payload)?,
+        tags: decode_json_row(&tags)?,
+        entities: decode_json_row(&entities)?,
+        seq: row.get::<_, i64>(11)? as u64,
+        lang: row.get(12)?,
+    })
+}
+
+fn episode_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Episode> {
+    let highlights: String = row.get(4)?;
+    let tags: String = row.get(5)?;
+    let entities: String = row.get(6)?;
+    let sources: String = row.get(7)?;
+    let compression_level: String = row.get(8)?;
+    Ok(Episode {
+        episode_id: row.get(0)?,
+        time_range: engram_types::TimeRange {
+            start: from_millis(row.get(1)?),
+            end: row.get::<_, Option<i64>>(2)?.map(from_millis),
+        },
+        summary: row.get(3)?,
+        highlights: decode_json_row(&highlights)?,
+        tags: decode_json_row(&tags)?,
+        entities: decode_json_row(&entities)?,
+        sources: decode_json_row(&sources)?,
+        compression_level: parse_enum(&compression_level, compression_level_from_str)?,
+        recency_score: row.get(9)?,
+        lang: row.get(10)?,
+    })
+}
+
+fn fact_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Fact> {
+    let value_json: String = row.get(2)?;
+    let status: String = row.get(3)?;
+    let sources: String = row.get(7)?;
+    let scope_level: String = row.get(8)?;
+    Ok(Fact {
+        fact_id: row.get(0)?,
+        fact_key: row.get(1)?,
+        value: decode_json_row(&value_json)?,
+        status: parse_enum(&status, fact_status_from_str)?,
+        validity: engram_types::Validity {
+            valid_from: row.get::<_, Option<i64>>(4)?.map(from_millis),
+            valid_to: row.get::<_, Option<i64>>(5)?.map(from_millis),
+        },
+        confidence: row.get(6)?,
+        sources: decode_json_row(&sources)?,
+        scope_level: parse_enum(&scope_level, scope_level_from_str)?,
+        notes: row.get(9)?,
+        pinned: row.get(10)?,
+        lang: row.get(11)?,
+    })
+}
+
+fn procedure_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Procedure> {
+    let content: String = row.get(2)?;
+    let sources: String = row.get(4)?;
+    let applicability: String = row.get(5)?;
+    Ok(Procedure {
+        procedure_id: row.get(0)?,
+        task_type: row.get(1)?,
+        content: decode_json_row(&content)?,
+        priority: row.get(3)?,
+        sources: decode_json_row(&sources)?,
+        applicability: decode_json_row(&applicability)?,
+    })
 }
 
-fn event_kind_to_str(kind: &EventKind) -> &'static str {
-    match kind {
-        EventKind::Message => "message",
-        EventKind::ToolResult => "tool_result",
-        EventKind::StatePatch => "state_patch",
-        EventKind::System => "system",
-    }
+fn insight_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<InsightItem> {
+    let kind: String = row.get(1)?;
+    let trigger: String = row.get(3)?;
+    let validation_state: String = row.get(5)?;
+    let tests: String = row.get(6)?;
+    let sources: String = row.get(8)?;
+    Ok(InsightItem {
+        id: row.get(0)?,
+        kind: parse_enum(&kind, insight_type_from_str)?,
+        statement: row.get(2)?,
+        trigger: parse_enum(&trigger, insight_trigger_from_str)?,
+        confidence: row.get(4)?,
+        validation_state: parse_enum(&validation_state, validation_state_from_str)?,
+        tests_suggested: decode_json_row(&tests)?,
+        expires_at: row.get(7)?,
+        sources: decode_json_row(&sources)?,
+        parent_insight_id: row.get(9)?,
+    })
 }
 
 fn event_kind_from_str(value: &str) -> Option<EventKind> {
-    match value {
-        "message" => Some(EventKind::Message),
-        "tool_result" => Some(EventKind::ToolResult),
-        "state_patch" => Some(EventKind::StatePatch),
-        "system" => Some(EventKind::System),
-        _ => None,
-    }
+    value.parse().ok()
 }
 
 fn fact_status_to_str(status: &FactStatus) -> &'static str {
@@ -1506,6 +3402,76 @@ fn validation_state_from_str(value: &str) -> Option<ValidationState> {
     }
 }
 
+fn candidate_status_to_str(status: &CandidateStatus) -> &'static str {
+    match status {
+        CandidateStatus::Pending => "pending",
+        CandidateStatus::Approved => "approved",
+        CandidateStatus::Rejected => "rejected",
+    }
+}
+
+fn candidate_status_from_str(value: &str) -> Option<CandidateStatus> {
+    match value {
+        "pending" => Some(CandidateStatus::Pending),
+        "approved" => Some(CandidateStatus::Approved),
+        "rejected" => Some(CandidateStatus::Rejected),
+        _ => None,
+    }
+}
+
+fn memory_kind_to_str(kind: &MemoryKind) -> &'static str {
+    match kind {
+        MemoryKind::Fact => "fact",
+        MemoryKind::Episode => "episode",
+    }
+}
+
+fn memory_kind_from_str(value: &str) -> Option<MemoryKind> {
+    match value {
+        "fact" => Some(MemoryKind::Fact),
+        "episode" => Some(MemoryKind::Episode),
+        _ => None,
+    }
+}
+
+fn run_status_to_str(status: &RunStatus) -> &'static str {
+    match status {
+        RunStatus::Success => "success",
+        RunStatus::Partial => "partial",
+        RunStatus::Failure => "failure",
+        RunStatus::Aborted => "aborted",
+    }
+}
+
+fn run_status_from_str(value: &str) -> Option<RunStatus> {
+    match value {
+        "success" => Some(RunStatus::Success),
+        "partial" => Some(RunStatus::Partial),
+        "failure" => Some(RunStatus::Failure),
+        "aborted" => Some(RunStatus::Aborted),
+        _ => None,
+    }
+}
+
+fn row_to_procedure_candidate(row: &rusqlite::Row<'_>) -> rusqlite::Result<ProcedureCandidate> {
+    let procedure: String = row.get(1)?;
+    let source_episodes: String = row.get(2)?;
+    let evidence: String = row.get(3)?;
+    let status: String = row.get(4)?;
+    let reviewed_at: Option<i64> = row.get(6)?;
+    Ok(ProcedureCandidate {
+        candidate_id: row.get(0)?,
+        procedure: decode_json_row(&procedure)?,
+        source_episodes: decode_json_row(&source_episodes)?,
+        evidence: decode_json_row(&evidence)?,
+        status: parse_enum(&status, candidate_status_from_str)?,
+        created_at: from_millis(row.get(5)?),
+        reviewed_at: reviewed_at.map(from_millis),
+        reviewer: row.get(7)?,
+        review_note: row.get(8)?,
+    })
+}
+
 fn parse_enum<T>(value: &str, parser: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
     parser(value).ok_or_else(|| {
         rusqlite::Error::FromSqlConversionFailure(
@@ -1522,7 +3488,7 @@ fn parse_enum<T>(value: &str, parser: fn(&str) -> Option<T>) -> rusqlite::Result
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Store, TimeRangeFilter};
+    use crate::{stream_events, Store, TimeRangeFilter, ORIGINAL_TAGS_KEY};
     use engram_types::{
         Budget, FactStatus, InsightItem, InsightTrigger, InsightType, JsonMap, Meta, Purpose, Scope,
         ScopeLevel, ShortTerm, Validity, ValidationState,
@@ -1551,13 +3517,17 @@ mod tests {
                 budget: Budget {
                     max_tokens: 512,
                     per_section: JsonMap::new(),
+                    model: None,
                 },
                 policy_id: "default".to_string(),
+                timezone: None,
+                locale: None,
             },
             short_term: ShortTerm::default(),
             long_term: engram_types::LongTerm::default(),
             insight: engram_types::Insight::default(),
             citations: Vec::new(),
+            external_context: Vec::new(),
             budget_report: engram_types::BudgetReport::default(),
             explain: JsonMap::new(),
         }
@@ -1575,16 +3545,84 @@ mod tests {
                 ts: Utc::now(),
                 kind: EventKind::Message,
                 payload: json!({ "role": "user", "content": "hi" }),
-                tags: vec!["alpha".to_string()],
+                tags: vec!["Alpha".to_string()],
                 entities: vec!["entity1".to_string()],
+                seq: 0,
+                lang: Some("en".to_string()),
+            })
+            .unwrap();
+
+        store
+            .append_event(Event {
+                event_id: "e2".to_string(),
+                scope: scope.clone(),
+                ts: Utc::now() + chrono::Duration::seconds(1),
+                kind: "plan_step".parse().unwrap(),
+                payload: json!({ "step": 1 }),
+                tags: vec![],
+                entities: vec![],
+                seq: 0,
+                lang: None,
             })
             .unwrap();
 
         let events = store
             .list_events(&scope, TimeRangeFilter::default(), None)
             .unwrap();
-        assert_eq!(events.len(), 1);
+        assert_eq!(events.len(), 2);
         assert_eq!(events[0].event_id, "e1");
+        assert_eq!(events[0].lang.as_deref(), Some("en"));
+        assert_eq!(events[1].lang, None);
+        assert_eq!(events[0].token_count(), Some(1));
+        assert_eq!(events[1].token_count(), None);
+        let by_id = store
+            .get_events_by_ids(&scope, &["e2".to_string(), "missing".to_string()])
+            .unwrap();
+        assert_eq!(by_id.len(), 1);
+        let tagged = store
+            .find_events(
+                &scope,
+                EventFilter {
+                    tags: vec![" ALPHA".to_string()],
+                    entities: vec!["Entity1".to_string()],
+                    ..EventFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].event_id, "e1");
+        assert_eq!(tagged[0].tags, ["alpha"]);
+        assert_eq!(tagged[0].payload[ORIGINAL_TAGS_KEY], json!(["Alpha"]));
+        assert!(store
+            .find_events(
+                &scope,
+                EventFilter {
+                    entities: vec!["entity2".to_string()],
+                    ..EventFilter::default()
+                },
+            )
+            .unwrap()
+            .is_empty());
+        assert_eq!(events[1].kind, EventKind::Custom("plan_step".to_string()));
+        assert!("Plan Step".parse::<EventKind>().is_err());
+        assert_eq!((events[0].seq, events[1].seq), (1, 2));
+        let since = store.get_events_since(&scope, 1, None).unwrap();
+        assert_eq!(since.len(), 1);
+        assert_eq!(since[0].event_id, "e2");
+        let other_run = Scope {
+            run_id: "run2".to_string(),
+            ..scope.clone()
+        };
+        assert!(store.get_events_since(&other_run, 0, None).unwrap().is_empty());
+        let scopes = store.list_scopes(Some(&scope.tenant_id), None).unwrap();
+        assert_eq!(scopes.len(), 1);
+        assert!(crate::scope_matches(&scopes[0], &scope));
+        assert!(store.list_scopes(Some("other"), None).unwrap().is_empty());
+        let activity = store
+            .tenant_activity(&scope.tenant_id, TimeRangeFilter::default())
+            .unwrap();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].events_by_day.values().sum::<u64>(), 2);
 
         let state = store
             .patch_working_state(
@@ -1596,6 +3634,10 @@ mod tests {
             )
             .unwrap();
         assert_eq!(state.goal, "ship");
+        let states = store.list_working_states(&scope).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].run_id, scope.run_id);
+        assert_eq!(states[0].state.goal, "ship");
 
         store
             .update_stm(
@@ -1621,6 +3663,8 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: Some("en".to_string()),
                 },
             )
             .unwrap();
@@ -1637,6 +3681,8 @@ mod tests {
                     sources: vec![],
                     scope_level: ScopeLevel::User,
                     notes: String::new(),
+                    pinned: false,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1651,6 +3697,112 @@ mod tests {
             )
             .unwrap();
         assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].lang.as_deref(), Some("en"));
+        crate::pin_fact(&store, &scope, "f1").unwrap();
+        let pinned = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    pinned: Some(true),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert!(pinned[0].pinned);
+
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: pinned[0].fact_id.clone(),
+        };
+        store.suppress_memory(&scope, item.clone(), "outdated").unwrap();
+        store.suppress_memory(&scope, item.clone(), "stop bringing this up").unwrap();
+        let suppressions = store.list_suppressions(&scope).unwrap();
+        assert_eq!(suppressions.len(), 1);
+        assert_eq!(suppressions[0].item, item);
+        assert_eq!(suppressions[0].reason, "stop bringing this up");
+        assert!(store.unsuppress_memory(&scope, &item).unwrap());
+        assert!(store.list_suppressions(&scope).unwrap().is_empty());
+
+        let mut outcome = RunOutcome::new(RunStatus::Partial);
+        outcome.score = Some(0.4);
+        outcome.duration_ms = 2_000;
+        store.record_run_outcome(&scope, outcome.clone()).unwrap();
+        outcome.status = RunStatus::Success;
+        outcome.score = None;
+        store.record_run_outcome(&scope, outcome).unwrap();
+        let outcomes = store.list_run_outcomes(&scope, None).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].status, RunStatus::Success);
+        assert_eq!(outcomes[0].score, None);
+        assert_eq!(outcomes[0].duration_ms, 2_000);
+        assert_eq!(outcomes[0].run_id, scope.run_id);
+
+        let credibility = SourceCredibility::default().with_score("tool:crm", 0.9);
+        store
+            .set_source_credibility(&scope.tenant_id, credibility.clone())
+            .unwrap();
+        let stored = store.get_source_credibility(&scope.tenant_id).unwrap();
+        assert_eq!(stored, Some(credibility));
+
+        let locale = UserLocale::new("+05:30", "en-IN");
+        store
+            .set_user_locale(&scope.tenant_id, &scope.user_id, locale.clone())
+            .unwrap();
+        let stored = store.get_user_locale(&scope.tenant_id, &scope.user_id).unwrap();
+        assert_eq!(stored, Some(locale));
+
+        let tenant_pool = crate::shared_fact_scope(&scope, &ScopeLevel::Tenant);
+        store
+            .upsert_fact(&tenant_pool, Fact::new("pref.color", json!("green")))
+            .unwrap();
+        store
+            .upsert_fact(&tenant_pool, Fact::new("org.name", json!("acme")))
+            .unwrap();
+        let overlaid = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    status: Some(vec![FactStatus::Active]),
+                    include_shared: true,
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        let overlaid: Vec<(&str, &serde_json::Value)> = overlaid
+            .iter()
+            .map(|fact| (fact.fact_key.as_str(), &fact.value))
+            .collect();
+        assert_eq!(
+            overlaid,
+            vec![("org.name", &json!("acme")), ("pref.color", &json!("blue"))]
+        );
+        let tone = crate::set_preference(&store, &scope, "tone", json!("terse"), 0.8).unwrap();
+        let preferences = crate::get_preferences(&store, &scope).unwrap();
+        assert_eq!(preferences.len(), 1);
+        assert_eq!(preferences[0].fact_id, tone.fact_id);
+        let facts = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    preferences: Some(false),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert!(!facts.is_empty());
+        assert!(facts.iter().all(|fact| fact.fact_id != tone.fact_id));
+        let facts = store
+            .list_facts(
+                &scope,
+                FactFilter {
+                    keys: Some(vec!["pref.color".to_string()]),
+                    ..FactFilter::default()
+                },
+            )
+            .unwrap();
+        assert!(!facts.is_empty());
+        assert!(facts.iter().all(|fact| fact.fact_key == "pref.color"));
 
         store
             .append_episode(
@@ -1668,6 +3820,7 @@ mod tests {
                     sources: vec!["e1".to_string()],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: Some("en".to_string()),
                 },
             )
             .unwrap();
@@ -1687,6 +3840,7 @@ mod tests {
                     sources: vec![],
                     compression_level: CompressionLevel::Raw,
                     recency_score: None,
+                    lang: None,
                 },
             )
             .unwrap();
@@ -1701,6 +3855,38 @@ mod tests {
             )
             .unwrap();
         assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].lang.as_deref(), Some("en"));
+
+        let evict = [MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episodes[0].episode_id.clone(),
+        }];
+        let embedding = MemoryEmbedding {
+            item: evict[0].clone(),
+            model: "test-model".to_string(),
+            vector: vec![0.5, -0.25],
+            digest: crate::text_digest(&episodes[0].summary),
+            embedded_at: from_millis(to_millis(Utc::now())),
+        };
+        store
+            .upsert_embeddings(&scope, vec![embedding.clone()])
+            .unwrap();
+        let embeddings = store.list_embeddings(&scope, "test-model").unwrap();
+        assert_eq!(embeddings, vec![embedding.clone()]);
+        assert_eq!(store.delete_embeddings(&scope, "other-model", &evict).unwrap(), 0);
+        assert_eq!(store.delete_embeddings(&scope, "test-model", &evict).unwrap(), 1);
+        assert!(store.list_embeddings(&scope, "test-model").unwrap().is_empty());
+        store.upsert_embeddings(&scope, vec![embedding]).unwrap();
+        assert_eq!(store.evict_memory(&scope, &evict).unwrap(), 1);
+        assert!(store.list_embeddings(&scope, "test-model").unwrap().is_empty());
+        let remaining = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_ne!(remaining[0].episode_id, evict[0].id);
+        let tagged = EpisodeFilter {
+            tags: vec!["alpha".to_string()],
+            ..EpisodeFilter::default()
+        };
+        assert!(store.list_episodes(&scope, tagged).unwrap().is_empty());
 
         store
             .upsert_procedure(
@@ -1721,6 +3907,36 @@ mod tests {
             .unwrap();
         assert_eq!(procedures.len(), 1);
 
+        store
+            .upsert_procedure_candidate(
+                &scope,
+                ProcedureCandidate {
+                    candidate_id: "c1".to_string(),
+                    procedure: procedures[0].clone(),
+                    source_episodes: vec!["ep1".to_string()],
+                    evidence: vec!["e1".to_string()],
+                    status: CandidateStatus::Pending,
+                    created_at: Utc::now(),
+                    reviewed_at: None,
+                    reviewer: String::new(),
+                    review_note: String::new(),
+                },
+            )
+            .unwrap();
+        let candidates = store
+            .list_procedure_candidates(
+                &scope,
+                ProcedureCandidateFilter {
+                    status: Some(vec![CandidateStatus::Pending]),
+                    ..ProcedureCandidateFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].source_episodes, vec!["ep1"]);
+        let candidate = store.get_procedure_candidate(&scope, "c1").unwrap().unwrap();
+        assert_eq!(candidate.procedure.procedure_id, "p1");
+
         store
             .append_insight(
                 &scope,
@@ -1734,6 +3950,7 @@ mod tests {
                     tests_suggested: vec![],
                     expires_at: "run_end".to_string(),
                     sources: vec![],
+                    parent_insight_id: None,
                 },
             )
             .unwrap();
@@ -1748,11 +3965,480 @@ mod tests {
             )
             .unwrap();
         assert_eq!(insights.len(), 1);
+        assert_eq!(store.expire_insights(&scope, "run_end").unwrap(), 1);
+        assert!(store
+            .list_insights(&scope, InsightFilter::default())
+            .unwrap()
+            .is_empty());
 
-        store
-            .write_context_build(&scope, sample_packet(scope.clone()))
-            .unwrap();
+        let mut packet = sample_packet(scope.clone());
+        packet.budget_report.used_tokens_est = 200;
+        store.write_context_build(&scope, packet).unwrap();
         let builds = store.list_context_builds(&scope, None).unwrap();
         assert_eq!(builds.len(), 1);
+        let summaries = store.list_context_build_summaries(&scope, None).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].policy_id, "default");
+        assert_eq!(summaries[0].used_tokens_est, 200);
+        assert_eq!(summaries[0].generated_at, builds[0].meta.generated_at);
+    }
+
+    #[test]
+    fn degrades_without_optional_index_tables() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("engram-optional-{}", nanos));
+        let mut store = SqliteStore::new(dir.join("engram.db")).unwrap();
+        let context = SqlContext::new("drop_optional_tables", "event_tags", &[]);
+        store
+            .with_connection(context, |conn| {
+                conn.execute_batch(
+                    "DROP TABLE event_tags; DROP TABLE event_entities;
+                     DROP TABLE episode_tags; DROP TABLE episode_entities;
+                     DROP TABLE event_text; DROP TABLE episode_text;",
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        let context = SqlContext::new("detect_optional_tables", "sqlite_master", &[]);
+        store.optional = store
+            .with_connection(context, |conn| detect_optional_tables(conn, &store.tables))
+            .unwrap();
+        assert!(!store.optional.event_index && !store.optional.episode_index);
+        assert!(!store.optional.text_index);
+
+        let scope = sample_scope();
+        let mut tagged = Event::new(scope.clone(), EventKind::Message, json!("hi"));
+        tagged.tags = vec!["alpha".to_string()];
+        store.append_event(tagged.clone()).unwrap();
+        store
+            .append_events_bulk(&[Event::new(scope.clone(), EventKind::Message, json!("bye"))])
+            .unwrap();
+        let found = store
+            .find_events(
+                &scope,
+                EventFilter {
+                    tags: vec!["alpha".to_string()],
+                    ..EventFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].event_id, tagged.event_id);
+
+        let mut episode = Episode::new("legacy");
+        episode.entities = vec!["entity1".to_string()];
+        store.append_episode(&scope, episode).unwrap();
+        store.append_episode(&scope, Episode::new("other")).unwrap();
+        let episodes = store
+            .list_episodes(
+                &scope,
+                EpisodeFilter {
+                    entities: vec!["entity1".to_string()],
+                    limit: Some(1),
+                    ..EpisodeFilter::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].summary, "legacy");
+        let found = store.search_episodes(&scope, &TextQuery::new(["LEG"])).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(store.search_events(&scope, &TextQuery::new(["bye"])).unwrap().len(), 1);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn bulk_writes_commit_or_roll_back_as_a_whole() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let facts = vec![
+            Fact::new("user.city", json!("Lisbon")),
+            Fact::new("user.name", json!("Sam")),
+        ];
+        store.upsert_facts_bulk(&scope, &facts).unwrap();
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 2);
+
+        let first = Episode::new("first");
+        let mut duplicate = Episode::new("duplicate");
+        duplicate.episode_id = first.episode_id.clone();
+        let batch = [Episode::new("rolled back"), first, duplicate];
+        assert!(store.append_episodes_bulk(&scope, &batch).is_err());
+        assert!(store.list_episodes(&scope, EpisodeFilter::default()).unwrap().is_empty());
+
+        let episodes = [Episode::new("one"), Episode::new("two")];
+        store.append_episodes_bulk(&scope, &episodes).unwrap();
+        assert_eq!(store.list_episodes(&scope, EpisodeFilter::default()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn searches_events_and_episodes_by_text() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let refund = json!({"role": "user", "content": "Where is my refund? It's been a week."});
+        let refund = Event::new(scope.clone(), EventKind::Message, refund);
+        store.append_event(refund.clone()).unwrap();
+        store
+            .append_events_bulk(&[
+                Event::new(scope.clone(), EventKind::Message, json!({"content": "refund refund"})),
+                Event::new(scope.clone(), EventKind::Message, json!({"content": "hello"})),
+            ])
+            .unwrap();
+        let mut other_run = sample_scope();
+        other_run.run_id = "run2".to_string();
+        store
+            .append_event(Event::new(other_run, EventKind::Message, json!("refund")))
+            .unwrap();
+
+        let found = store
+            .search_events(&scope, &TextQuery::new(["refund", "\"week\""]))
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        // bm25 ranks the event matching both terms first.
+        assert_eq!(found[0].event_id, refund.event_id);
+        let mut limited = TextQuery::new(["refund"]);
+        limited.limit = Some(1);
+        assert_eq!(store.search_events(&scope, &limited).unwrap().len(), 1);
+        assert!(store.search_events(&scope, &TextQuery::new(["?!", " "])).unwrap().is_empty());
+
+        let mut episode = Episode::new("Handled a café refund");
+        episode.highlights = vec!["issued credit note".to_string()];
+        store.append_episode(&scope, episode.clone()).unwrap();
+        store.append_episode(&scope, Episode::new("unrelated")).unwrap();
+        let found = store
+            .search_episodes(&scope, &TextQuery::new(["cafe", "credit note"]))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].episode_id, episode.episode_id);
+        let item = MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode.episode_id.clone(),
+        };
+        store.evict_memory(&scope, &[item]).unwrap();
+        assert!(store.search_episodes(&scope, &TextQuery::new(["cafe"])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn deletes_records_hard_and_soft() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let event = Event::new(scope.clone(), EventKind::Message, json!({"content": "refund"}));
+        store.append_event(event.clone()).unwrap();
+        let fact = Fact::new("plan", json!("pro"));
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        let episode = Episode::new("refund handled");
+        store.append_episode(&scope, episode.clone()).unwrap();
+        let item = MemoryRef {
+            kind: MemoryKind::Fact,
+            id: fact.fact_id.clone(),
+        };
+        store.suppress_memory(&scope, item, "").unwrap();
+        assert_eq!(store.count_records(&scope, RecordKind::Fact).unwrap(), 1);
+        assert_eq!(store.count_records(&scope, RecordKind::Event).unwrap(), 1);
+
+        assert!(store.delete_fact(&scope, &fact.fact_id, DeleteMode::Hard).unwrap());
+        assert_eq!(store.count_records(&scope, RecordKind::Fact).unwrap(), 0);
+        assert!(!store.delete_fact(&scope, &fact.fact_id, DeleteMode::Hard).unwrap());
+        assert!(store.list_suppressions(&scope).unwrap().is_empty());
+        assert!(store.list_tombstones(&scope, None).unwrap().is_empty());
+
+        assert!(store.delete_event(&scope, &event.event_id, DeleteMode::Soft).unwrap());
+        assert!(store.list_events(&scope, TimeRangeFilter::default(), None).unwrap().is_empty());
+        assert!(store.search_events(&scope, &TextQuery::new(["refund"])).unwrap().is_empty());
+        let mut other_run = sample_scope();
+        other_run.run_id = "run2".to_string();
+        store.append_event(Event::new(other_run.clone(), EventKind::Message, json!({}))).unwrap();
+        store.append_episode(&scope, Episode::new("second")).unwrap();
+
+        // Clearing a run leaves other runs' events but takes the user's long-term records.
+        assert_eq!(store.clear_scope(&scope, DeleteMode::Soft).unwrap(), 2);
+        assert!(store.list_episodes(&scope, EpisodeFilter::default()).unwrap().is_empty());
+        assert!(store.search_episodes(&scope, &TextQuery::new(["refund"])).unwrap().is_empty());
+        let remaining = store.list_events(&other_run, TimeRangeFilter::default(), None).unwrap();
+        assert_eq!(remaining.len(), 1);
+        let tombstones = store.list_tombstones(&scope, None).unwrap();
+        assert_eq!(tombstones.len(), 3);
+        assert_eq!(tombstones[2].item, RecordRef::new(RecordKind::Event, event.event_id));
+        assert_eq!(tombstones[2].scope.run_id, scope.run_id);
+        assert_eq!(tombstones[2].record["payload"]["content"], "refund");
+        let deleted = tombstones.iter().find(|t| t.item.id == episode.episode_id).unwrap();
+        assert!(deleted.scope.run_id.is_empty());
+        assert_eq!(store.list_tombstones(&scope, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn records_each_fact_version_once() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let mut fact = Fact::new("plan", json!("basic"));
+        fact.sources = vec!["signup_form".to_string()];
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        store.upsert_facts_bulk(&scope, &[fact.clone(), fact.clone()]).unwrap();
+        fact.value = json!("pro");
+        fact.sources = vec!["billing_webhook".to_string()];
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+
+        let history = store.list_fact_history(&scope, "plan").unwrap();
+        let versions: Vec<_> = history
+            .iter()
+            .map(|version| (version.version, version.fact.value.clone()))
+            .collect();
+        assert_eq!(versions, [(1, json!("basic")), (2, json!("pro"))]);
+        assert_eq!(history[1].fact.sources, ["billing_webhook"]);
+        assert!(history[0].recorded_at <= history[1].recorded_at);
+        assert!(store.list_fact_history(&scope, "locale").unwrap().is_empty());
+
+        store.delete_fact(&scope, &fact.fact_id, DeleteMode::Soft).unwrap();
+        assert_eq!(store.list_fact_history(&scope, "plan").unwrap().len(), 2);
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        store.delete_fact(&scope, &fact.fact_id, DeleteMode::Hard).unwrap();
+        assert!(store.list_fact_history(&scope, "plan").unwrap().is_empty());
+    }
+
+    #[test]
+    fn prefixes_every_table_and_index() {
+        let store = SqliteStore::with_table_prefix(":memory:", "engram_").unwrap();
+        let scope = sample_scope();
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!({ "content": "hi" })))
+            .unwrap();
+        store.upsert_fact(&scope, Fact::new("user.city", json!("Paris"))).unwrap();
+        assert_eq!(store.list_facts(&scope, FactFilter::default()).unwrap().len(), 1);
+        assert_eq!(store.search_events(&scope, &TextQuery::new(["hi"])).unwrap().len(), 1);
+        assert!(store.optional.event_index && store.optional.text_index);
+
+        let names: Vec<String> = store
+            .with_connection(SqlContext::new("list_objects", "sqlite_master", &[]), |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%'",
+                )?;
+                let rows = stmt.query_map([], |row| row.get(0))?;
+                Ok(rows.collect::<rusqlite::Result<_>>()?)
+            })
+            .unwrap();
+        assert!(names.len() > 40);
+        assert!(names.iter().all(|name| name.starts_with("engram_")), "{:?}", names);
+        assert!(SqliteStore::with_table_prefix(":memory:", "Engram").is_err());
+    }
+
+    #[test]
+    fn storage_errors_carry_the_failing_call() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let context = SqlContext::new("drop_facts", "facts", &[]);
+        store
+            .with_connection(context, |conn| Ok(conn.execute_batch("DROP TABLE facts")?))
+            .unwrap();
+
+        let err = store.list_facts(&scope, FactFilter::default()).unwrap_err();
+        let StoreError::Storage(message) = err else {
+            panic!("expected a storage error, got {:?}", err);
+        };
+        assert!(message.starts_with("list_facts on facts (params 5#"), "{}", message);
+        assert!(message.contains("no such table: facts"));
+
+        store.list_facts(&scope, FactFilter::default()).unwrap_err();
+        store.list_events(&scope, TimeRangeFilter::default(), None).unwrap();
+        let metrics = store.metrics_snapshot();
+        assert!(metrics.since.is_some());
+        assert_eq!((metrics.calls, metrics.errors), (4, 2));
+        let facts = &metrics.operations["list_facts"];
+        assert_eq!((facts.calls, facts.errors), (2, 2));
+        assert_eq!(metrics.operations["list_events"].errors, 0);
+        assert!(!message.contains(&scope.user_id));
+    }
+
+    #[test]
+    fn pages_events_by_cursor() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let ts = Utc::now();
+        let events: Vec<Event> = ["c", "a", "b"]
+            .into_iter()
+            .map(|id| Event {
+                event_id: id.to_string(),
+                ts,
+                ..Event::new(scope.clone(), EventKind::Message, json!(id))
+            })
+            .collect();
+        store.append_events_bulk(&events).unwrap();
+
+        let page = store.list_events_page(&scope, None, 2).unwrap();
+        let ids: Vec<_> = page.events.iter().map(|event| event.event_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        let cursor = page.next_cursor.unwrap();
+        let page = store.list_events_page(&scope, Some(&cursor), 2).unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].event_id, "c");
+        assert!(page.next_cursor.is_none());
+        let err = store.list_events_page(&scope, Some("not a cursor"), 2).unwrap_err();
+        assert!(matches!(err, StoreError::InvalidInput(_)));
+
+        let range = TimeRangeFilter {
+            start: Some(ts),
+            end: Some(ts),
+        };
+        let streamed: Vec<String> = stream_events(&store, &scope, range)
+            .chunk_size(2)
+            .map(|event| event.unwrap().event_id)
+            .collect();
+        assert_eq!(streamed, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rejects_working_state_patches_against_a_stale_version() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let scope = sample_scope();
+        let patch = |goal: &str, expected: u32| WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            expected_state_version: Some(expected),
+            ..WorkingStatePatch::default()
+        };
+        let err = store.patch_working_state(&scope, patch("draft", 1)).unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { expected: 1, actual: 0 }));
+        assert!(store.get_working_state(&scope).unwrap().is_none());
+
+        let state = store.patch_working_state(&scope, patch("draft", 0)).unwrap();
+        assert_eq!(state.state_version, 1);
+        let state = store.patch_working_state(&scope, patch("review", 1)).unwrap();
+        assert_eq!(state.state_version, 2);
+        let err = store.patch_working_state(&scope, patch("ship", 1)).unwrap_err();
+        assert_eq!(err.to_string(), "version conflict: expected state_version 1, found 2");
+        let stored = store.get_working_state(&scope).unwrap().unwrap();
+        assert_eq!((stored.goal.as_str(), stored.state_version), ("review", 2));
+
+        // Without an expected version the patch still wins unconditionally.
+        let unchecked = WorkingStatePatch {
+            goal: Some("ship".to_string()),
+            ..WorkingStatePatch::default()
+        };
+        assert_eq!(store.patch_working_state(&scope, unchecked).unwrap().state_version, 3);
+
+        // A merge writes against the version it read and moves past it.
+        let mut remote = store.get_working_state(&scope).unwrap().unwrap();
+        remote.goal = "merge".to_string();
+        remote.clock.goal = Some(Utc::now() + chrono::Duration::seconds(1));
+        let merged = store.merge_working_state(&scope, remote).unwrap();
+        assert_eq!((merged.goal.as_str(), merged.state_version), ("merge", 4));
+        let err = store.patch_working_state(&scope, patch("stale", 3)).unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { expected: 3, actual: 4 }));
+    }
+
+    #[test]
+    fn patches_working_states_of_many_runs_atomically() {
+        let store = SqliteStore::new_in_memory().unwrap();
+        let planner = sample_scope();
+        let coder = Scope {
+            run_id: "run-coder".to_string(),
+            ..sample_scope()
+        };
+        let patch = |goal: &str, expected: Option<u32>| WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            expected_state_version: expected,
+            ..WorkingStatePatch::default()
+        };
+
+        let states = store
+            .patch_working_states(vec![
+                (planner.clone(), patch("plan", None)),
+                (coder.clone(), patch("code", None)),
+                (planner.clone(), patch("review", Some(1))),
+            ])
+            .unwrap();
+        let goals: Vec<&str> = states.iter().map(|state| state.goal.as_str()).collect();
+        assert_eq!(goals, ["plan", "code", "review"]);
+
+        // A stale patch rolls back the whole batch.
+        let err = store
+            .patch_working_states(vec![
+                (coder.clone(), patch("test", None)),
+                (planner.clone(), patch("ship", Some(1))),
+            ])
+            .unwrap_err();
+        assert!(matches!(err, StoreError::VersionConflict { expected: 1, actual: 2 }));
+        assert_eq!(store.get_working_state(&coder).unwrap().unwrap().goal, "code");
+        assert_eq!(store.get_working_state(&planner).unwrap().unwrap().goal, "review");
+    }
+
+    #[test]
+    fn migrates_v1_events_to_sequences() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "
+            CREATE TABLE schema_migrations (version INTEGER NOT NULL, applied_at INTEGER NOT NULL);
+            INSERT INTO schema_migrations VALUES (1, 0);
+            CREATE TABLE events (
+                event_id TEXT PRIMARY KEY,
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                ts INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                tags TEXT NOT NULL,
+                entities TEXT NOT NULL
+            );
+            INSERT INTO events VALUES
+                ('late', 'default', 'user1', 'agent1', 'session1', 'run1', 20, 'message', '{}', '[]', '[]'),
+                ('early', 'default', 'user1', 'agent1', 'session1', 'run1', 10, 'message', '{}', '[]', '[]');
+            CREATE TABLE episodes (
+                tenant_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                episode_id TEXT NOT NULL,
+                start_ts INTEGER NOT NULL,
+                end_ts INTEGER,
+                summary TEXT NOT NULL,
+                highlights TEXT NOT NULL,
+                tags TEXT NOT NULL,
+                entities TEXT NOT NULL,
+                sources TEXT NOT NULL,
+                compression_level TEXT NOT NULL,
+                recency_score REAL,
+                PRIMARY KEY (tenant_id, user_id, agent_id, episode_id)
+            );
+            INSERT INTO episodes VALUES
+                ('default', 'user1', 'agent1', 'ep1', 10, NULL, 'legacy', '[]', '[\"alpha\"]', '[\"entity1\"]', '[]', 'raw', NULL);
+            ",
+        )
+        .unwrap();
+
+        ensure_schema(&conn, &TablePrefix::default()).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT event_id, seq FROM events ORDER BY seq")
+            .unwrap();
+        let rows: Vec<(String, i64)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("early".to_string(), 1), ("late".to_string(), 2)]
+        );
+        assert_eq!(next_event_seq(&conn, &TablePrefix::default(), &sample_scope()).unwrap(), 3);
+        let tag: String = conn
+            .query_row("SELECT tag FROM episode_tags WHERE episode_id = 'ep1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(tag, "alpha");
+        let indexed: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM episode_text WHERE episode_text MATCH 'legacy'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(indexed, 1);
+        let version: i64 = conn
+            .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
     }
 }
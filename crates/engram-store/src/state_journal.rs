@@ -0,0 +1,531 @@
+use engram_types::{
+    Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use serde_json::{json, Map, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    Change, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event, EventFilter, EventKind,
+    EventPage, FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding, MemoryRef,
+    ProcedureCandidateFilter, RecordKind, RecordRef, RunKey, RunOutcome, RunWorkingState,
+    SourceCredibility, StmState, Store, StoreCapabilities, StoreError, StoreMetrics, StoreResult,
+    Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale, WorkingStatePatch,
+};
+
+/// Store wrapper that journals every working-state change as a `state_patch` event in
+/// the same run, so the event log alone is a complete audit trail of the run's working
+/// memory and [`replay_working_state`] can rebuild it exactly.
+///
+/// The event payload is `{"diff": {...}}`: each top-level field of the state that the
+/// patch changed, with its new value (merge clock and `state_version` included).
+/// Patches that change nothing are not journaled. The state is read before the patch
+/// and the event appended after it, so concurrent writers to one run can interleave
+/// their diffs; hold a [`Lease`] on the run while patching when that matters.
+#[derive(Debug)]
+pub struct StatePatchJournal<S> {
+    inner: S,
+}
+
+impl<S: Store> StatePatchJournal<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+/// Rebuilds a run's working state from its `state_patch` events, applied in the order
+/// given (e.g. [`Store::get_events_since`] up to a checkpoint's `event_seq`). Returns
+/// `None` when none of the events is a state patch.
+pub fn replay_working_state(events: &[Event]) -> StoreResult<Option<WorkingState>> {
+    let mut state = Map::new();
+    let mut patched = false;
+    for event in events {
+        if event.kind != EventKind::StatePatch {
+            continue;
+        }
+        let Some(diff) = event.payload.get("diff").and_then(Value::as_object) else {
+            continue;
+        };
+        for (field, value) in diff {
+            if value.is_null() {
+                state.remove(field);
+            } else {
+                state.insert(field.clone(), value.clone());
+            }
+        }
+        patched = true;
+    }
+    if !patched {
+        return Ok(None);
+    }
+    serde_json::from_value(Value::Object(state))
+        .map(Some)
+        .map_err(|err| StoreError::Storage(format!("invalid state patch journal: {}", err)))
+}
+
+/// Top-level fields that differ between the two states, with their value in `after`;
+/// a field `after` no longer serializes maps to `null`.
+fn state_diff(before: &WorkingState, after: &WorkingState) -> StoreResult<Map<String, Value>> {
+    let to_object = |state: &WorkingState| match serde_json::to_value(state) {
+        Ok(Value::Object(object)) => Ok(object),
+        Ok(_) => Ok(Map::new()),
+        Err(err) => Err(StoreError::Storage(err.to_string())),
+    };
+    let before = to_object(before)?;
+    let after = to_object(after)?;
+    let mut diff = Map::new();
+    for (field, value) in &after {
+        if before.get(field) != Some(value) {
+            diff.insert(field.clone(), value.clone());
+        }
+    }
+    for field in before.keys() {
+        if !after.contains_key(field) {
+            diff.insert(field.clone(), Value::Null);
+        }
+    }
+    Ok(diff)
+}
+
+impl<S: Store> Store for StatePatchJournal<S> {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.inner.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.inner.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.inner.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.inner.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.inner.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.inner.search_events(scope, query)
+    }
+
+    fn search_episodes(
+        &self,
+        scope: &Scope,
+        query: &TextQuery,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.inner.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.inner.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.inner.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.inner.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        let before = self.inner.get_working_state(scope)?.unwrap_or_default();
+        let state = self.inner.patch_working_state(scope, patch)?;
+        let diff = state_diff(&before, &state)?;
+        if !diff.is_empty() {
+            let payload = json!({ "diff": Value::Object(diff) });
+            self.inner
+                .append_event(Event::new(scope.clone(), EventKind::StatePatch, payload))?;
+        }
+        Ok(state)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        // Each patch is journaled against the state before it, including earlier
+        // patches of the batch to the same run.
+        let mut before: HashMap<RunKey, WorkingState> = HashMap::new();
+        for (scope, _) in &patches {
+            if let Entry::Vacant(entry) = before.entry(RunKey::from(scope)) {
+                entry.insert(self.inner.get_working_state(scope)?.unwrap_or_default());
+            }
+        }
+        let scopes: Vec<Scope> = patches.iter().map(|(scope, _)| scope.clone()).collect();
+        let states = self.inner.patch_working_states(patches)?;
+        let mut events = Vec::new();
+        for (scope, state) in scopes.into_iter().zip(&states) {
+            let previous = before.insert(RunKey::from(&scope), state.clone()).unwrap_or_default();
+            let diff = state_diff(&previous, state)?;
+            if !diff.is_empty() {
+                let payload = json!({ "diff": Value::Object(diff) });
+                events.push(Event::new(scope, EventKind::StatePatch, payload));
+            }
+        }
+        self.inner.append_events_bulk(&events)?;
+        Ok(states)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.inner.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.inner.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.inner.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.inner.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.inner.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.inner.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.inner.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(
+        &self,
+        scope: &Scope,
+        filter: EpisodeFilter,
+    ) -> StoreResult<Vec<engram_types::Episode>> {
+        self.inner.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: engram_types::Episode) -> StoreResult<()> {
+        self.inner.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(
+        &self,
+        scope: &Scope,
+        episodes: &[engram_types::Episode],
+    ) -> StoreResult<()> {
+        self.inner.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.inner.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.inner.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.inner.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.inner.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.inner.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.inner.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.inner.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.inner.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.inner.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.inner.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.inner.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.inner.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.inner.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.inner.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.inner.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.inner.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.inner.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.inner.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.inner.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.inner.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.inner.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.inner.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.inner.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.inner.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.inner.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.inner.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.inner.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.inner.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.inner.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.inner.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.inner.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.inner.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.inner.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.inner.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.inner.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.inner.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.inner.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use engram_types::JsonMap;
+
+    #[test]
+    fn journals_state_patches_for_exact_replay() {
+        let store = StatePatchJournal::new(InMemoryStore::new());
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut slots = JsonMap::new();
+        slots.insert("city".to_string(), json!("Lisbon"));
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    goal: Some("book a flight".to_string()),
+                    slots: Some(slots),
+                    plan: Some(vec!["search".to_string(), "book".to_string()]),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        store
+            .patch_working_state(
+                &scope,
+                WorkingStatePatch {
+                    decisions: Some(vec!["aisle seat".to_string()]),
+                    ..WorkingStatePatch::default()
+                },
+            )
+            .unwrap();
+        let mut remote = store.get_working_state(&scope).unwrap().unwrap();
+        remote.goal = "book a train".to_string();
+        remote.clock.goal = Some(chrono::Utc::now() + chrono::Duration::seconds(1));
+        store.merge_working_state(&scope, remote).unwrap();
+
+        let events = store.get_events_since(&scope, 0, None).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|event| event.kind == EventKind::StatePatch));
+        let second = events[1].payload["diff"].as_object().unwrap();
+        assert!(second.contains_key("decisions"));
+        assert!(!second.contains_key("goal"));
+
+        let current = store.get_working_state(&scope).unwrap().unwrap();
+        let replayed = replay_working_state(&events).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&replayed).unwrap(),
+            serde_json::to_value(&current).unwrap()
+        );
+        let first = replay_working_state(&events[..1]).unwrap().unwrap();
+        assert_eq!(first.goal, "book a flight");
+        assert!(first.decisions.is_empty());
+        assert!(replay_working_state(&[]).unwrap().is_none());
+    }
+}
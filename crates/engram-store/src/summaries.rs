@@ -0,0 +1,275 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use engram_types::{CompressionLevel, Episode, Scope, TimeRange};
+use std::collections::{BTreeMap, HashSet};
+use tracing::debug;
+
+use crate::lifecycle::clip;
+use crate::{
+    EpisodeFilter, MemoryKind, MemoryRef, Store, StoreError, StoreResult, TimeRangeFilter,
+    RUN_TAG_PREFIX,
+};
+
+/// Tag of the episode summarizing one local day of a scope's episodes.
+pub const DAILY_SUMMARY_TAG: &str = "summary:daily";
+/// Tag of the episode summarizing one ISO week (Monday to Sunday) of them.
+pub const WEEKLY_SUMMARY_TAG: &str = "summary:weekly";
+
+const SUMMARY_TAG_PREFIX: &str = "summary:";
+const MAX_SUMMARY_HIGHLIGHTS: usize = 8;
+
+/// Whether `episode` is a daily or weekly summary rather than a recorded episode.
+pub fn is_period_summary(episode: &Episode) -> bool {
+    episode.tags.iter().any(|tag| tag.starts_with(SUMMARY_TAG_PREFIX))
+}
+
+/// Rewrites the daily and weekly summaries of the local day and week containing `at`
+/// from the scope's episodes, replacing the previous versions. Days and weeks run in
+/// the user's timezone when one is set (see [`UserLocale`](crate::UserLocale)).
+///
+/// A daily summary lists the day's episode summaries, a weekly one a line per active
+/// day; both collect the highlights, tags, entities and sources of the episodes they
+/// cover. Suppressed episodes are left out, and a period without episodes loses its
+/// summary. [`end_run`](crate::end_run) calls this when
+/// [`RunEndOptions::period_summaries`](crate::RunEndOptions::period_summaries) is set.
+/// Returns the summaries written.
+pub fn refresh_period_summaries<S: Store + ?Sized>(
+    store: &S,
+    scope: &Scope,
+    at: DateTime<Utc>,
+) -> StoreResult<Vec<Episode>> {
+    let offset = match store.get_user_locale(&scope.tenant_id, &scope.user_id)? {
+        Some(locale) => locale.utc_offset()?,
+        None => FixedOffset::east_opt(0).expect("zero offset"),
+    };
+    let day = at.with_timezone(&offset).date_naive();
+    let monday = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+    let week_start = local_midnight(&offset, monday)?;
+    let week_end = local_midnight(&offset, monday + Duration::days(7))?;
+
+    let suppressed: HashSet<String> = store
+        .list_suppressions(scope)?
+        .into_iter()
+        .filter(|suppression| suppression.item.kind == MemoryKind::Episode)
+        .map(|suppression| suppression.item.id)
+        .collect();
+    let stored = store.list_episodes(
+        scope,
+        EpisodeFilter {
+            time_range: Some(TimeRangeFilter {
+                start: Some(week_start),
+                end: None,
+            }),
+            ..EpisodeFilter::default()
+        },
+    )?;
+    let (previous, mut recorded): (Vec<Episode>, Vec<Episode>) =
+        stored.into_iter().partition(is_period_summary);
+    recorded.retain(|episode| {
+        episode.time_range.start < week_end && !suppressed.contains(&episode.episode_id)
+    });
+    recorded.sort_by(|a, b| {
+        a.time_range
+            .start
+            .cmp(&b.time_range.start)
+            .then_with(|| a.episode_id.cmp(&b.episode_id))
+    });
+    let mut days: BTreeMap<NaiveDate, Vec<&Episode>> = BTreeMap::new();
+    for episode in &recorded {
+        let local = episode.time_range.start.with_timezone(&offset).date_naive();
+        days.entry(local).or_default().push(episode);
+    }
+
+    let day_tag = format!("day:{}", day);
+    let week = monday.iso_week();
+    let week_tag = format!("week:{}-W{:02}", week.year(), week.week());
+    let stale: Vec<MemoryRef> = previous
+        .iter()
+        .filter(|episode| episode.tags.contains(&day_tag) || episode.tags.contains(&week_tag))
+        .map(|episode| MemoryRef {
+            kind: MemoryKind::Episode,
+            id: episode.episode_id.clone(),
+        })
+        .collect();
+    if !stale.is_empty() {
+        store.evict_memory(scope, &stale)?;
+    }
+
+    let mut written = Vec::new();
+    if let Some(episodes) = days.get(&day) {
+        let lines = episodes.iter().map(|episode| clip(&episode.summary)).collect();
+        let range = TimeRange {
+            start: local_midnight(&offset, day)?,
+            end: Some(local_midnight(&offset, day + Duration::days(1))?),
+        };
+        let tags = [DAILY_SUMMARY_TAG.to_string(), day_tag];
+        written.push(summarize(lines, episodes, range, CompressionLevel::Milestone, tags));
+    }
+    if !days.is_empty() {
+        let lines = days
+            .iter()
+            .map(|(day, episodes)| {
+                let summaries: Vec<&str> =
+                    episodes.iter().map(|episode| episode.summary.trim()).collect();
+                format!("{}: {}", day, clip(&summaries.join("; ")))
+            })
+            .collect();
+        let episodes: Vec<&Episode> = days.values().flatten().copied().collect();
+        let range = TimeRange {
+            start: week_start,
+            end: Some(week_end),
+        };
+        let tags = [WEEKLY_SUMMARY_TAG.to_string(), week_tag];
+        written.push(summarize(lines, &episodes, range, CompressionLevel::Theme, tags));
+    }
+    for summary in &written {
+        store.append_episode(scope, summary.clone())?;
+    }
+    debug!(
+        "refreshed {} period summaries of {}/{}/{} for {}",
+        written.len(),
+        scope.tenant_id,
+        scope.user_id,
+        scope.agent_id,
+        day
+    );
+    Ok(written)
+}
+
+fn local_midnight(offset: &FixedOffset, day: NaiveDate) -> StoreResult<DateTime<Utc>> {
+    offset
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .single()
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .ok_or_else(|| StoreError::InvalidInput(format!("no midnight on {}", day)))
+}
+
+fn summarize(
+    lines: Vec<String>,
+    episodes: &[&Episode],
+    time_range: TimeRange,
+    compression_level: CompressionLevel,
+    tags: [String; 2],
+) -> Episode {
+    let mut summary = Episode::new(lines.join("\n"));
+    summary.time_range = time_range;
+    summary.compression_level = compression_level;
+    summary.tags = tags.to_vec();
+    for episode in episodes {
+        for highlight in &episode.highlights {
+            if summary.highlights.len() < MAX_SUMMARY_HIGHLIGHTS
+                && !summary.highlights.contains(highlight)
+            {
+                summary.highlights.push(highlight.clone());
+            }
+        }
+        // Run tags would tie the summary to one of its runs.
+        let tags = episode.tags.iter().filter(|tag| {
+            !tag.starts_with(SUMMARY_TAG_PREFIX) && !tag.starts_with(RUN_TAG_PREFIX)
+        });
+        for tag in tags {
+            if !summary.tags.contains(tag) {
+                summary.tags.push(tag.clone());
+            }
+        }
+        for entity in &episode.entities {
+            if !summary.entities.contains(entity) {
+                summary.entities.push(entity.clone());
+            }
+        }
+        for source in &episode.sources {
+            if !summary.sources.contains(source) {
+                summary.sources.push(source.clone());
+            }
+        }
+    }
+    let first_lang = episodes.first().and_then(|episode| episode.lang.clone());
+    if episodes.iter().all(|episode| episode.lang == first_lang) {
+        summary.lang = first_lang;
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, end_run, BuildRequest, InMemoryStore, RunEndOptions};
+    use crate::{UserLocale, WorkingStatePatch};
+    use engram_types::Purpose;
+
+    fn episode(summary: &str, start: &str, tags: &[&str]) -> Episode {
+        let mut episode = Episode::new(summary);
+        episode.time_range.start = DateTime::parse_from_rfc3339(start).unwrap().to_utc();
+        episode.highlights = vec![format!("{} done", summary)];
+        episode.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        episode.sources = vec![format!("ev-{}", summary)];
+        episode
+    }
+
+    #[test]
+    fn maintains_daily_and_weekly_summaries_in_local_time() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store
+            .set_user_locale("default", "user1", UserLocale::new("+02:00", "de-DE"))
+            .unwrap();
+        // Monday 2026-10-12 and Wednesday 2026-10-14 local time; the last one is
+        // Wednesday 00:30 in Berlin summer time, still Tuesday in UTC.
+        let triage = episode("triage", "2026-10-12T08:00:00Z", &["run:a"]);
+        store.append_episode(&scope, triage).unwrap();
+        store.append_episode(&scope, episode("fix", "2026-10-13T22:30:00Z", &["bug"])).unwrap();
+        let wednesday = DateTime::parse_from_rfc3339("2026-10-14T10:00:00Z").unwrap().to_utc();
+
+        let written = refresh_period_summaries(&store, &scope, wednesday).unwrap();
+        assert_eq!(written.len(), 2);
+        let (daily, weekly) = (&written[0], &written[1]);
+        assert_eq!(daily.summary, "fix");
+        assert_eq!(daily.tags, vec![DAILY_SUMMARY_TAG, "day:2026-10-14", "bug"]);
+        assert_eq!(daily.time_range.start.to_rfc3339(), "2026-10-13T22:00:00+00:00");
+        assert_eq!(weekly.summary, "2026-10-12: triage\n2026-10-14: fix");
+        assert_eq!(weekly.tags, vec![WEEKLY_SUMMARY_TAG, "week:2026-W42", "bug"]);
+        assert_eq!(weekly.highlights, vec!["triage done", "fix done"]);
+        assert_eq!(weekly.sources, vec!["ev-triage", "ev-fix"]);
+
+        // Refreshing again replaces both summaries.
+        store.append_episode(&scope, episode("deploy", "2026-10-14T09:00:00Z", &[])).unwrap();
+        refresh_period_summaries(&store, &scope, wednesday).unwrap();
+        let summaries = || -> Vec<Episode> {
+            let episodes = store.list_episodes(&scope, EpisodeFilter::default()).unwrap();
+            episodes.into_iter().filter(is_period_summary).collect()
+        };
+        assert_eq!(summaries().len(), 2);
+
+        let patch = WorkingStatePatch {
+            goal: Some("release".to_string()),
+            ..WorkingStatePatch::default()
+        };
+        store.patch_working_state(&scope, patch).unwrap();
+        let message = serde_json::json!({"role": "user", "content": "ship it"});
+        store
+            .append_event(crate::Event::new(scope.clone(), crate::EventKind::Message, message))
+            .unwrap();
+        let options = RunEndOptions {
+            period_summaries: true,
+            ..RunEndOptions::default()
+        };
+        end_run(&store, &scope, "success", &options).unwrap();
+        assert!(summaries().iter().any(|summary| summary.summary == "release"));
+
+        let mut request = BuildRequest::new(scope, Purpose::Planner);
+        request.policy.max_daily_summaries = 1;
+        request.policy.max_weekly_summaries = 1;
+        let packet = build_memory_packet(&store, request).unwrap();
+        let period = &packet.short_term.period_summaries;
+        assert_eq!(period.len(), 2);
+        assert_eq!(period[0].summary, "release");
+        assert!(period[1].tags.contains(&WEEKLY_SUMMARY_TAG.to_string()));
+        assert!(packet.long_term.episodes.iter().all(|episode| !is_period_summary(episode)));
+        assert!(packet.budget_report.section_usage.contains_key("period_summaries"));
+    }
+}
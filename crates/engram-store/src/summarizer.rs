@@ -0,0 +1,155 @@
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::composer::summary_sentences;
+use crate::StoreResult;
+
+/// Marks the inputs [`ExtractiveSummarizer`] left out.
+const GAP: &str = " … ";
+
+/// Hook used to condense several texts into one, typically backed by an LLM. Besides
+/// procedure learning, the composer condenses long rolling summaries with one (see
+/// [`SummaryCondensing`]) and [`Consolidator`](crate::Consolidator) summarizes long
+/// phases of a run; both fall back to [`ExtractiveSummarizer`], so engram itself
+/// never calls a model.
+pub trait Summarizer: Send + Sync {
+    /// Summarizes `inputs`, given oldest first, into a single text of roughly `budget`
+    /// tokens (4 chars per token, as the composer estimates them).
+    fn summarize(&self, inputs: &[String], budget: usize) -> StoreResult<String>;
+}
+
+/// Summarizer that needs no model: all inputs when they fit the budget, otherwise the
+/// first one followed by as many of the latest as fit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractiveSummarizer;
+
+impl Summarizer for ExtractiveSummarizer {
+    fn summarize(&self, inputs: &[String], budget: usize) -> StoreResult<String> {
+        let inputs: Vec<&str> = inputs
+            .iter()
+            .map(|input| input.trim())
+            .filter(|input| !input.is_empty())
+            .collect();
+        let max_chars = budget.saturating_mul(4);
+        let all = inputs.join("\n");
+        if all.chars().count() <= max_chars {
+            return Ok(all);
+        }
+        let Some((first, rest)) = inputs.split_first() else {
+            return Ok(all);
+        };
+
+        let mut used = first.chars().count() + GAP.chars().count();
+        let mut latest = Vec::new();
+        for input in rest.iter().rev() {
+            used += input.chars().count() + usize::from(!latest.is_empty());
+            if used > max_chars {
+                break;
+            }
+            latest.push(*input);
+        }
+        if latest.is_empty() {
+            let mut clipped: String = first.chars().take(max_chars.saturating_sub(1)).collect();
+            clipped.push('…');
+            return Ok(clipped);
+        }
+        latest.reverse();
+        Ok(format!("{}{}{}", first, GAP, latest.join("\n")))
+    }
+}
+
+/// How the composer shortens a rolling summary that outgrew
+/// [`RecallPolicy`](crate::RecallPolicy): split into sentences and condensed to
+/// `max_tokens`. Off by default.
+#[derive(Clone, Default)]
+pub struct SummaryCondensing {
+    /// Summaries longer than this many tokens are condensed; 0 leaves them as stored.
+    pub max_tokens: usize,
+    /// Condenses the sentences; [`ExtractiveSummarizer`] when unset or when it fails.
+    pub summarizer: Option<Arc<dyn Summarizer>>,
+}
+
+impl fmt::Debug for SummaryCondensing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SummaryCondensing")
+            .field("max_tokens", &self.max_tokens)
+            .field("summarizer", &self.summarizer.is_some())
+            .finish()
+    }
+}
+
+impl SummaryCondensing {
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            summarizer: None,
+        }
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// `summary` condensed to `max_tokens`, or unchanged if it fits. A failing
+    /// summarizer is logged and the summary condensed extractively instead.
+    pub(crate) fn condense(&self, summary: String) -> String {
+        if self.max_tokens == 0 || summary.chars().count() <= self.max_tokens * 4 {
+            return summary;
+        }
+        let sentences: Vec<String> =
+            summary_sentences(&summary).into_iter().map(str::to_string).collect();
+        if let Some(summarizer) = &self.summarizer {
+            match summarizer.summarize(&sentences, self.max_tokens) {
+                Ok(condensed) => return condensed,
+                Err(err) => warn!("summarizer failed, condensing extractively: {}", err),
+            }
+        }
+        ExtractiveSummarizer.summarize(&sentences, self.max_tokens).unwrap_or(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StoreError;
+
+    #[test]
+    fn extractive_summaries_keep_the_opening_and_the_latest_inputs() {
+        let inputs: Vec<String> = ["Order 42 arrived broken.", "Photos sent.", "Refund approved."]
+            .iter()
+            .map(|input| input.to_string())
+            .collect();
+        let summarizer = ExtractiveSummarizer;
+        assert_eq!(
+            summarizer.summarize(&inputs, 100).unwrap(),
+            "Order 42 arrived broken.\nPhotos sent.\nRefund approved."
+        );
+        assert_eq!(
+            summarizer.summarize(&inputs, 12).unwrap(),
+            "Order 42 arrived broken. … Refund approved."
+        );
+        assert_eq!(summarizer.summarize(&inputs, 3).unwrap(), "Order 42 ar…");
+        assert_eq!(summarizer.summarize(&[], 3).unwrap(), "");
+    }
+
+    struct Unreachable;
+
+    impl Summarizer for Unreachable {
+        fn summarize(&self, _inputs: &[String], _budget: usize) -> StoreResult<String> {
+            Err(StoreError::Storage("model endpoint unreachable".to_string()))
+        }
+    }
+
+    #[test]
+    fn condensing_falls_back_to_extraction() {
+        let summary = "Order 42 arrived broken. Photos sent. Refund approved.".to_string();
+        assert_eq!(SummaryCondensing::default().condense(summary.clone()), summary);
+        let condensing = SummaryCondensing::new(12).with_summarizer(Arc::new(Unreachable));
+        assert_eq!(
+            condensing.condense(summary),
+            "Order 42 arrived broken. … Refund approved."
+        );
+    }
+}
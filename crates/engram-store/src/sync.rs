@@ -0,0 +1,721 @@
+use chrono::{DateTime, Utc};
+use engram_types::{
+    Episode, Fact, InsightItem, MemoryPacket, Procedure, ProcedureCandidate, Scope, WorkingState,
+};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::changelog::{apply_change, reapply_change};
+use crate::{
+    Change, ChangeLogStore, ChangeOp, ContextBuildSummary, DeleteMode, EpisodeFilter, Event,
+    EventFilter, EventPage, FactFilter, FactVersion, InsightFilter, Lease, MemoryEmbedding,
+    MemoryRef, ProcedureCandidateFilter, RecordKind, RecordRef, RunOutcome, RunWorkingState,
+    SourceCredibility, SqliteStore, StmState, Store, StoreCapabilities, StoreError, StoreMetrics,
+    StoreResult, Suppression, TextQuery, TimeRangeFilter, Tombstone, UserActivity, UserLocale,
+    WorkingStatePatch,
+};
+
+const REMOTE_CURSOR: &str = "remote";
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// How often the background worker pushes pending changes. `None` disables the
+    /// worker; call [`SyncingStore::sync_now`] instead.
+    pub interval: Option<Duration>,
+    pub batch_size: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(5)),
+            batch_size: 200,
+        }
+    }
+}
+
+struct SyncShared {
+    local: ChangeLogStore<SqliteStore>,
+    remote: Arc<dyn Store>,
+    batch_size: usize,
+    push_lock: Mutex<()>,
+}
+
+impl SyncShared {
+    fn push_pending(&self) -> StoreResult<usize> {
+        let _guard = self.push_lock.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut cursor = self.local.inner().load_sync_cursor(REMOTE_CURSOR)?;
+        let mut pushed = 0;
+        loop {
+            let changes = self.local.list_changes(cursor, Some(self.batch_size))?;
+            if changes.is_empty() {
+                break;
+            }
+            for change in changes {
+                // The cursor is saved after every push, so only the first change can
+                // have reached the remote already, before a crash or a failed call.
+                push_change(self.remote.as_ref(), change.op, pushed == 0)?;
+                cursor = change.seq;
+                self.local.inner().save_sync_cursor(REMOTE_CURSOR, cursor)?;
+                pushed += 1;
+            }
+        }
+        if pushed > 0 {
+            debug!("pushed {} changes to remote, cursor at {}", pushed, cursor);
+        }
+        Ok(pushed)
+    }
+}
+
+/// Offline-first store: writes land in a local SQLite store immediately and are pushed
+/// to a remote store in the background, resuming from a cursor kept in the local
+/// database. Reads are always served locally.
+///
+/// Conflicts are resolved on push. Working state is merged field by field (see
+/// [`WorkingState::merge`]). STM is replaced unless the remote one quotes later
+/// evidence (by key quote `ts`); procedures are replaced by the last push. A fact that
+/// already exists remotely is merged: the local value, status and validity win,
+/// sources are unioned and the higher confidence is kept. Appends (events, episodes,
+/// insights, context builds) never conflict, and one the remote already holds after
+/// an interrupted push is skipped when the push resumes.
+pub struct SyncingStore {
+    shared: Arc<SyncShared>,
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for SyncingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncingStore")
+            .field("local", self.shared.local.inner())
+            .field("background", &self.worker.is_some())
+            .finish()
+    }
+}
+
+impl SyncingStore {
+    pub fn new(local: SqliteStore, remote: Arc<dyn Store>, options: SyncOptions) -> Self {
+        let shared = Arc::new(SyncShared {
+            local: ChangeLogStore::new(local),
+            remote,
+            batch_size: options.batch_size.max(1),
+            push_lock: Mutex::new(()),
+        });
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let worker = options.interval.map(|interval| {
+            let shared = shared.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || run_worker(shared, stop, interval))
+        });
+        Self {
+            shared,
+            stop,
+            worker,
+        }
+    }
+
+    /// Pushes every pending change to the remote store and returns how many were sent.
+    pub fn sync_now(&self) -> StoreResult<usize> {
+        self.shared.push_pending()
+    }
+
+    pub fn pending_changes(&self) -> StoreResult<usize> {
+        let cursor = self.shared.local.inner().load_sync_cursor(REMOTE_CURSOR)?;
+        Ok(self.shared.local.list_changes(cursor, None)?.len())
+    }
+
+    pub fn local(&self) -> &SqliteStore {
+        self.shared.local.inner()
+    }
+
+    pub fn remote(&self) -> &Arc<dyn Store> {
+        &self.shared.remote
+    }
+}
+
+impl Drop for SyncingStore {
+    fn drop(&mut self) {
+        let (lock, signal) = &*self.stop;
+        if let Ok(mut stopped) = lock.lock() {
+            *stopped = true;
+        }
+        signal.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker(shared: Arc<SyncShared>, stop: Arc<(Mutex<bool>, Condvar)>, interval: Duration) {
+    let (lock, signal) = &*stop;
+    loop {
+        let Ok(guard) = lock.lock() else {
+            return;
+        };
+        let Ok((stopped, _)) = signal.wait_timeout_while(guard, interval, |stopped| !*stopped)
+        else {
+            return;
+        };
+        if *stopped {
+            return;
+        }
+        drop(stopped);
+
+        if let Err(err) = shared.push_pending() {
+            warn!("sync to remote store failed, will retry: {}", err);
+        }
+    }
+}
+
+/// Pushes `op` to the remote; `resumed` marks a change an interrupted push may already
+/// have applied.
+fn push_change(remote: &dyn Store, op: ChangeOp, resumed: bool) -> StoreResult<()> {
+    match op {
+        ChangeOp::UpsertFact { scope, fact } => {
+            let existing = remote
+                .list_facts(&scope, FactFilter::default())?
+                .into_iter()
+                .find(|candidate| candidate.fact_id == fact.fact_id);
+            remote.upsert_fact(&scope, merge_fact(fact, existing))
+        }
+        ChangeOp::PatchWorkingState { scope, state } => {
+            remote.merge_working_state(&scope, *state).map(|_| ())
+        }
+        ChangeOp::UpdateStm { scope, stm } => {
+            let remote_stm = remote.get_stm(&scope)?;
+            if remote_stm.is_some_and(|remote_stm| latest_quote(&remote_stm) > latest_quote(&stm)) {
+                return Ok(());
+            }
+            remote.update_stm(&scope, stm)
+        }
+        op if resumed => reapply_change(remote, op),
+        op => apply_change(remote, op),
+    }
+}
+
+fn latest_quote(stm: &StmState) -> Option<DateTime<Utc>> {
+    stm.key_quotes.iter().filter_map(|quote| quote.ts).max()
+}
+
+fn merge_fact(local: Fact, remote: Option<Fact>) -> Fact {
+    let Some(remote) = remote else {
+        return local;
+    };
+    let mut merged = local;
+    for source in remote.sources {
+        if !merged.sources.contains(&source) {
+            merged.sources.push(source);
+        }
+    }
+    merged.confidence = merged.confidence.max(remote.confidence);
+    if merged.notes.is_empty() {
+        merged.notes = remote.notes;
+    }
+    merged
+}
+
+impl Store for SyncingStore {
+    fn append_event(&self, event: Event) -> StoreResult<()> {
+        self.shared.local.append_event(event)
+    }
+
+    fn append_events_bulk(&self, events: &[Event]) -> StoreResult<()> {
+        self.shared.local.append_events_bulk(events)
+    }
+
+    fn list_events(
+        &self,
+        scope: &Scope,
+        range: TimeRangeFilter,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.shared.local.list_events(scope, range, limit)
+    }
+
+    fn list_events_page(
+        &self,
+        scope: &Scope,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<EventPage> {
+        self.shared.local.list_events_page(scope, cursor, limit)
+    }
+
+    fn get_events_since(
+        &self,
+        scope: &Scope,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Event>> {
+        self.shared.local.get_events_since(scope, seq, limit)
+    }
+
+    fn get_events_by_ids(&self, scope: &Scope, event_ids: &[String]) -> StoreResult<Vec<Event>> {
+        self.shared.local.get_events_by_ids(scope, event_ids)
+    }
+
+    fn find_events(&self, scope: &Scope, filter: EventFilter) -> StoreResult<Vec<Event>> {
+        self.shared.local.find_events(scope, filter)
+    }
+
+    fn search_events(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Event>> {
+        self.shared.local.search_events(scope, query)
+    }
+
+    fn search_episodes(&self, scope: &Scope, query: &TextQuery) -> StoreResult<Vec<Episode>> {
+        self.shared.local.search_episodes(scope, query)
+    }
+
+    fn acquire_lease(&self, scope: &Scope, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.shared.local.acquire_lease(scope, ttl)
+    }
+
+    fn renew_lease(&self, lease: &Lease, ttl: Duration) -> StoreResult<Option<Lease>> {
+        self.shared.local.renew_lease(lease, ttl)
+    }
+
+    fn release_lease(&self, lease: &Lease) -> StoreResult<bool> {
+        self.shared.local.release_lease(lease)
+    }
+
+    fn get_working_state(&self, scope: &Scope) -> StoreResult<Option<WorkingState>> {
+        self.shared.local.get_working_state(scope)
+    }
+
+    fn patch_working_state(
+        &self,
+        scope: &Scope,
+        patch: WorkingStatePatch,
+    ) -> StoreResult<WorkingState> {
+        self.shared.local.patch_working_state(scope, patch)
+    }
+
+    fn patch_working_states(
+        &self,
+        patches: Vec<(Scope, WorkingStatePatch)>,
+    ) -> StoreResult<Vec<WorkingState>> {
+        self.shared.local.patch_working_states(patches)
+    }
+
+    fn list_working_states(&self, scope: &Scope) -> StoreResult<Vec<RunWorkingState>> {
+        self.shared.local.list_working_states(scope)
+    }
+
+    fn get_stm(&self, scope: &Scope) -> StoreResult<Option<StmState>> {
+        self.shared.local.get_stm(scope)
+    }
+
+    fn update_stm(&self, scope: &Scope, stm: StmState) -> StoreResult<()> {
+        self.shared.local.update_stm(scope, stm)
+    }
+
+    fn list_facts(&self, scope: &Scope, filter: FactFilter) -> StoreResult<Vec<Fact>> {
+        self.shared.local.list_facts(scope, filter)
+    }
+
+    fn upsert_fact(&self, scope: &Scope, fact: Fact) -> StoreResult<()> {
+        self.shared.local.upsert_fact(scope, fact)
+    }
+
+    fn upsert_facts_bulk(&self, scope: &Scope, facts: &[Fact]) -> StoreResult<()> {
+        self.shared.local.upsert_facts_bulk(scope, facts)
+    }
+
+    fn list_fact_history(&self, scope: &Scope, fact_key: &str) -> StoreResult<Vec<FactVersion>> {
+        self.shared.local.list_fact_history(scope, fact_key)
+    }
+
+    fn list_episodes(&self, scope: &Scope, filter: EpisodeFilter) -> StoreResult<Vec<Episode>> {
+        self.shared.local.list_episodes(scope, filter)
+    }
+
+    fn append_episode(&self, scope: &Scope, episode: Episode) -> StoreResult<()> {
+        self.shared.local.append_episode(scope, episode)
+    }
+
+    fn append_episodes_bulk(&self, scope: &Scope, episodes: &[Episode]) -> StoreResult<()> {
+        self.shared.local.append_episodes_bulk(scope, episodes)
+    }
+
+    fn list_procedures(
+        &self,
+        scope: &Scope,
+        task_type: &str,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<Procedure>> {
+        self.shared.local.list_procedures(scope, task_type, limit)
+    }
+
+    fn upsert_procedure(&self, scope: &Scope, procedure: Procedure) -> StoreResult<()> {
+        self.shared.local.upsert_procedure(scope, procedure)
+    }
+
+    fn list_procedure_candidates(
+        &self,
+        scope: &Scope,
+        filter: ProcedureCandidateFilter,
+    ) -> StoreResult<Vec<ProcedureCandidate>> {
+        self.shared.local.list_procedure_candidates(scope, filter)
+    }
+
+    fn get_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate_id: &str,
+    ) -> StoreResult<Option<ProcedureCandidate>> {
+        self.shared.local.get_procedure_candidate(scope, candidate_id)
+    }
+
+    fn upsert_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.shared.local.upsert_procedure_candidate(scope, candidate)
+    }
+
+    fn list_insights(&self, scope: &Scope, filter: InsightFilter) -> StoreResult<Vec<InsightItem>> {
+        self.shared.local.list_insights(scope, filter)
+    }
+
+    fn append_insight(&self, scope: &Scope, insight: InsightItem) -> StoreResult<()> {
+        self.shared.local.append_insight(scope, insight)
+    }
+
+    fn expire_insights(&self, scope: &Scope, expires_at: &str) -> StoreResult<usize> {
+        self.shared.local.expire_insights(scope, expires_at)
+    }
+
+    fn write_context_build(&self, scope: &Scope, packet: MemoryPacket) -> StoreResult<()> {
+        self.shared.local.write_context_build(scope, packet)
+    }
+
+    fn list_context_builds(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<MemoryPacket>> {
+        self.shared.local.list_context_builds(scope, limit)
+    }
+
+    fn list_context_build_summaries(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<ContextBuildSummary>> {
+        self.shared.local.list_context_build_summaries(scope, limit)
+    }
+
+    fn tenant_activity(
+        &self,
+        tenant_id: &str,
+        range: TimeRangeFilter,
+    ) -> StoreResult<Vec<UserActivity>> {
+        self.shared.local.tenant_activity(tenant_id, range)
+    }
+
+    fn list_scopes(&self, tenant_id: Option<&str>, limit: Option<usize>) -> StoreResult<Vec<Scope>> {
+        self.shared.local.list_scopes(tenant_id, limit)
+    }
+
+    fn suppress_memory(&self, scope: &Scope, item: MemoryRef, reason: &str) -> StoreResult<()> {
+        self.shared.local.suppress_memory(scope, item, reason)
+    }
+
+    fn unsuppress_memory(&self, scope: &Scope, item: &MemoryRef) -> StoreResult<bool> {
+        self.shared.local.unsuppress_memory(scope, item)
+    }
+
+    fn list_suppressions(&self, scope: &Scope) -> StoreResult<Vec<Suppression>> {
+        self.shared.local.list_suppressions(scope)
+    }
+
+    fn evict_memory(&self, scope: &Scope, items: &[MemoryRef]) -> StoreResult<usize> {
+        self.shared.local.evict_memory(scope, items)
+    }
+
+    fn delete_record(
+        &self,
+        scope: &Scope,
+        item: &RecordRef,
+        mode: DeleteMode,
+    ) -> StoreResult<bool> {
+        self.shared.local.delete_record(scope, item, mode)
+    }
+
+    fn clear_scope(&self, scope: &Scope, mode: DeleteMode) -> StoreResult<usize> {
+        self.shared.local.clear_scope(scope, mode)
+    }
+
+    fn count_records(&self, scope: &Scope, kind: RecordKind) -> StoreResult<usize> {
+        self.shared.local.count_records(scope, kind)
+    }
+
+    fn list_tombstones(&self, scope: &Scope, limit: Option<usize>) -> StoreResult<Vec<Tombstone>> {
+        self.shared.local.list_tombstones(scope, limit)
+    }
+
+    fn upsert_embeddings(
+        &self,
+        scope: &Scope,
+        embeddings: Vec<MemoryEmbedding>,
+    ) -> StoreResult<()> {
+        self.shared.local.upsert_embeddings(scope, embeddings)
+    }
+
+    fn list_embeddings(&self, scope: &Scope, model: &str) -> StoreResult<Vec<MemoryEmbedding>> {
+        self.shared.local.list_embeddings(scope, model)
+    }
+
+    fn delete_embeddings(
+        &self,
+        scope: &Scope,
+        model: &str,
+        items: &[MemoryRef],
+    ) -> StoreResult<usize> {
+        self.shared.local.delete_embeddings(scope, model, items)
+    }
+
+    fn record_run_outcome(&self, scope: &Scope, outcome: RunOutcome) -> StoreResult<()> {
+        self.shared.local.record_run_outcome(scope, outcome)
+    }
+
+    fn list_run_outcomes(
+        &self,
+        scope: &Scope,
+        limit: Option<usize>,
+    ) -> StoreResult<Vec<RunOutcome>> {
+        self.shared.local.list_run_outcomes(scope, limit)
+    }
+
+    fn set_source_credibility(
+        &self,
+        tenant_id: &str,
+        credibility: SourceCredibility,
+    ) -> StoreResult<()> {
+        self.shared.local.set_source_credibility(tenant_id, credibility)
+    }
+
+    fn get_source_credibility(&self, tenant_id: &str) -> StoreResult<Option<SourceCredibility>> {
+        self.shared.local.get_source_credibility(tenant_id)
+    }
+
+    fn set_user_locale(
+        &self,
+        tenant_id: &str,
+        user_id: &str,
+        locale: UserLocale,
+    ) -> StoreResult<()> {
+        self.shared.local.set_user_locale(tenant_id, user_id, locale)
+    }
+
+    fn get_user_locale(&self, tenant_id: &str, user_id: &str) -> StoreResult<Option<UserLocale>> {
+        self.shared.local.get_user_locale(tenant_id, user_id)
+    }
+
+    fn append_change(&self, op: ChangeOp) -> StoreResult<u64> {
+        self.shared.local.append_change(op)
+    }
+
+    fn load_change_cursor(&self, name: &str) -> StoreResult<u64> {
+        self.shared.local.load_change_cursor(name)
+    }
+
+    fn save_change_cursor(&self, name: &str, seq: u64) -> StoreResult<()> {
+        self.shared.local.save_change_cursor(name, seq)
+    }
+
+    fn write_logged(
+        &self,
+        write: &mut dyn FnMut() -> StoreResult<Vec<ChangeOp>>,
+    ) -> StoreResult<()> {
+        self.shared.local.write_logged(write)
+    }
+
+    fn list_changes(&self, after_seq: u64, limit: Option<usize>) -> StoreResult<Vec<Change>> {
+        self.shared.local.list_changes(after_seq, limit)
+    }
+
+    fn last_change_seq(&self) -> StoreResult<u64> {
+        self.shared.local.last_change_seq()
+    }
+
+    fn metrics_snapshot(&self) -> StoreMetrics {
+        self.shared.local.metrics_snapshot()
+    }
+
+    fn capabilities(&self) -> StoreCapabilities {
+        self.shared.local.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, InMemoryStore};
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn pushes_local_writes_and_merges_facts() {
+        let scope = sample_scope();
+        let remote: Arc<dyn Store> = Arc::new(InMemoryStore::new());
+        let mut shared_fact = Fact::new("user.city", json!("Paris"));
+        shared_fact.sources = vec!["remote-doc".to_string()];
+        shared_fact.confidence = 0.9;
+        remote.upsert_fact(&scope, shared_fact.clone()).unwrap();
+
+        let options = SyncOptions {
+            interval: None,
+            ..SyncOptions::default()
+        };
+        let store = SyncingStore::new(SqliteStore::new_in_memory().unwrap(), remote.clone(), options);
+
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!({ "content": "hi" })))
+            .unwrap();
+        let mut local_fact = shared_fact.clone();
+        local_fact.value = json!("Lyon");
+        local_fact.sources = vec!["local-msg".to_string()];
+        local_fact.confidence = 0.6;
+        store.upsert_fact(&scope, local_fact).unwrap();
+        store
+            .update_stm(
+                &scope,
+                StmState {
+                    rolling_summary: "moved to Lyon".to_string(),
+                    key_quotes: Vec::new(),
+                },
+            )
+            .unwrap();
+
+        assert!(remote
+            .list_events(&scope, TimeRangeFilter::default(), None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.pending_changes().unwrap(), 3);
+        assert_eq!(store.sync_now().unwrap(), 3);
+        assert_eq!(store.sync_now().unwrap(), 0);
+
+        assert_eq!(
+            remote
+                .list_events(&scope, TimeRangeFilter::default(), None)
+                .unwrap()
+                .len(),
+            1
+        );
+        let facts = remote.list_facts(&scope, FactFilter::default()).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].value, json!("Lyon"));
+        assert_eq!(facts[0].sources, vec!["local-msg", "remote-doc"]);
+        assert_eq!(facts[0].confidence, 0.9);
+        assert_eq!(
+            remote.get_stm(&scope).unwrap().unwrap().rolling_summary,
+            "moved to Lyon"
+        );
+    }
+
+    #[test]
+    fn resuming_skips_appends_the_remote_already_holds() {
+        let scope = sample_scope();
+        let remote: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+        let options = SyncOptions {
+            interval: None,
+            ..SyncOptions::default()
+        };
+        let store = SyncingStore::new(SqliteStore::new_in_memory().unwrap(), remote.clone(), options);
+        // As after a crash between pushing the last change and saving the cursor.
+        let rewind = || {
+            let cursor = store.local().load_sync_cursor(REMOTE_CURSOR).unwrap();
+            store.local().save_sync_cursor(REMOTE_CURSOR, cursor - 1).unwrap();
+        };
+
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!({ "content": "hi" })))
+            .unwrap();
+        assert_eq!(store.sync_now().unwrap(), 1);
+        rewind();
+        assert_eq!(store.sync_now().unwrap(), 1);
+
+        let episode = Episode {
+            episode_id: "ep1".to_string(),
+            time_range: engram_types::TimeRange {
+                start: Utc::now(),
+                end: None,
+            },
+            summary: "did something".to_string(),
+            highlights: vec![],
+            tags: vec![],
+            entities: vec![],
+            sources: vec![],
+            compression_level: engram_types::CompressionLevel::Raw,
+            recency_score: None,
+            lang: None,
+        };
+        store.append_episode(&scope, episode).unwrap();
+        assert_eq!(store.sync_now().unwrap(), 1);
+        rewind();
+        assert_eq!(store.sync_now().unwrap(), 1);
+        assert_eq!(store.pending_changes().unwrap(), 0);
+
+        assert_eq!(
+            remote
+                .list_events(&scope, TimeRangeFilter::default(), None)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            remote
+                .list_episodes(&scope, EpisodeFilter::default())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn keeps_the_remote_stm_when_it_quotes_later_evidence() {
+        let scope = sample_scope();
+        let remote: Arc<dyn Store> = Arc::new(InMemoryStore::new());
+        let options = SyncOptions {
+            interval: None,
+            ..SyncOptions::default()
+        };
+        let store = SyncingStore::new(SqliteStore::new_in_memory().unwrap(), remote.clone(), options);
+        let stm = |summary: &str, ts: DateTime<Utc>| StmState {
+            rolling_summary: summary.to_string(),
+            key_quotes: vec![engram_types::KeyQuote {
+                evidence_id: summary.to_string(),
+                quote: summary.to_string(),
+                role: engram_types::Role::User,
+                ts: Some(ts),
+            }],
+        };
+        let now = Utc::now();
+
+        remote.update_stm(&scope, stm("remote", now)).unwrap();
+        store
+            .update_stm(&scope, stm("stale", now - chrono::Duration::minutes(5)))
+            .unwrap();
+        assert_eq!(store.sync_now().unwrap(), 1);
+        assert_eq!(remote.get_stm(&scope).unwrap().unwrap().rolling_summary, "remote");
+
+        store
+            .update_stm(&scope, stm("fresh", now + chrono::Duration::minutes(5)))
+            .unwrap();
+        assert_eq!(store.sync_now().unwrap(), 1);
+        assert_eq!(remote.get_stm(&scope).unwrap().unwrap().rolling_summary, "fresh");
+    }
+}
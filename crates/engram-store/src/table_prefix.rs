@@ -0,0 +1,170 @@
+use std::borrow::Cow;
+
+use crate::{StoreError, StoreResult};
+
+/// Longest prefix [`TablePrefix::new`] accepts, leaving the longest prefixed name
+/// within MySQL's 64-character identifier limit.
+pub const MAX_TABLE_PREFIX_LEN: usize = 30;
+
+/// Every table and index name the SQLite and MySQL backends create, sorted. SQLite
+/// index names share the table namespace, so they are prefixed along with the tables.
+const OBJECT_NAMES: [&str; 44] = [
+    "changelog",
+    "context_builds",
+    "context_builds_scope_ts",
+    "episode_entities",
+    "episode_entities_scope_entity",
+    "episode_entities_scope_episode",
+    "episode_tags",
+    "episode_tags_scope_episode",
+    "episode_tags_scope_tag",
+    "episode_text",
+    "episodes",
+    "episodes_scope_start",
+    "event_entities",
+    "event_entities_scope_entity",
+    "event_entities_scope_event",
+    "event_sequences",
+    "event_tags",
+    "event_tags_scope_event",
+    "event_tags_scope_tag",
+    "event_text",
+    "events",
+    "events_scope_seq",
+    "events_scope_ts",
+    "fact_versions",
+    "fact_versions_scope_key",
+    "facts",
+    "facts_scope_status",
+    "insights",
+    "insights_scope_state",
+    "memory_embeddings",
+    "procedure_candidates",
+    "procedure_candidates_scope_status",
+    "procedures",
+    "procedures_scope_task",
+    "run_outcomes",
+    "schema_migrations",
+    "source_credibility",
+    "stm_state",
+    "suppressions",
+    "sync_cursors",
+    "tombstones",
+    "tombstones_scope",
+    "user_locales",
+    "wm_state",
+];
+
+/// Prefix put in front of every name in [`OBJECT_NAMES`], so engram's tables can share
+/// a database with an application's own. Statements are written with the bare names
+/// and pass through [`TablePrefix::sql`] before they run.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TablePrefix {
+    prefix: String,
+}
+
+impl TablePrefix {
+    /// A prefix of lowercase letters, digits and underscores, starting with a letter.
+    pub(crate) fn new(prefix: &str) -> StoreResult<Self> {
+        let mut chars = prefix.chars();
+        let valid = chars.next().is_some_and(|first| first.is_ascii_lowercase())
+            && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+            && prefix.len() <= MAX_TABLE_PREFIX_LEN;
+        if !valid {
+            return Err(StoreError::InvalidInput(format!(
+                "table prefix must be a lowercase identifier of at most {} characters: {}",
+                MAX_TABLE_PREFIX_LEN, prefix
+            )));
+        }
+        Ok(Self {
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// `name` as stored, for comparing with names read from the database's catalog.
+    pub(crate) fn name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// A name read from the catalog with the prefix taken off again.
+    pub(crate) fn strip<'a>(&self, name: &'a str) -> &'a str {
+        name.strip_prefix(self.prefix.as_str()).unwrap_or(name)
+    }
+
+    /// `sql` with every table and index name prefixed. String literals, quoted
+    /// identifiers and comments are left alone.
+    pub(crate) fn sql<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        if self.prefix.is_empty() {
+            return Cow::Borrowed(sql);
+        }
+        let bytes = sql.as_bytes();
+        let mut out = String::with_capacity(sql.len() + 64);
+        let mut copied = 0;
+        let mut prefixed = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                quote @ (b'\'' | b'"' | b'`') => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+                byte if is_word_byte(byte) => {
+                    let start = i;
+                    while i < bytes.len() && is_word_byte(bytes[i]) {
+                        i += 1;
+                    }
+                    let word = &sql[start..i];
+                    if OBJECT_NAMES.binary_search(&word).is_ok() {
+                        out.push_str(&sql[copied..start]);
+                        out.push_str(&self.prefix);
+                        copied = start;
+                        prefixed = true;
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+        if !prefixed {
+            return Cow::Borrowed(sql);
+        }
+        out.push_str(&sql[copied..]);
+        Cow::Owned(out)
+    }
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_names_outside_literals() {
+        assert!(OBJECT_NAMES.windows(2).all(|pair| pair[0] < pair[1]));
+        let tables = TablePrefix::new("engram_").unwrap();
+        let sql = "SELECT e.event_id FROM events e JOIN event_text ON e.event_id = \
+                   event_text.event_id WHERE event_text MATCH 'facts' AND e.kind = \"events\"";
+        assert_eq!(
+            tables.sql(sql),
+            "SELECT e.event_id FROM engram_events e JOIN engram_event_text ON e.event_id = \
+             engram_event_text.event_id WHERE engram_event_text MATCH 'facts' AND e.kind = \
+             \"events\""
+        );
+        assert_eq!(tables.sql("events_text -- events"), "events_text -- events");
+        assert_eq!(tables.name("facts"), "engram_facts");
+        assert!(matches!(TablePrefix::default().sql("FROM facts"), Cow::Borrowed("FROM facts")));
+        assert!(TablePrefix::new("Engram").is_err());
+        assert!(TablePrefix::new("_engram").is_err());
+        assert!(TablePrefix::new(&"a".repeat(MAX_TABLE_PREFIX_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use serde_json::{json, Value};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::Event;
+
+/// Longest tag or entity kept, in characters: the width of MySQL's tag index columns.
+pub const MAX_TAG_LEN: usize = 64;
+/// Payload key under which an event keeps its tags as written, when normalizing
+/// changed them.
+pub const ORIGINAL_TAGS_KEY: &str = "original_tags";
+/// The [`ORIGINAL_TAGS_KEY`] counterpart for entities.
+pub const ORIGINAL_ENTITIES_KEY: &str = "original_entities";
+
+/// The form a tag or entity is stored and matched in: trimmed, NFC-normalized,
+/// lowercased and cut to [`MAX_TAG_LEN`] characters, so `Café`, `café` and a
+/// decomposed `cafe\u{301}` are one tag.
+pub fn normalize_tag(tag: &str) -> String {
+    let normalized: String = tag
+        .trim()
+        .nfc()
+        .flat_map(char::to_lowercase)
+        .take(MAX_TAG_LEN)
+        .collect();
+    normalized.trim_end().to_string()
+}
+
+/// Normalizes each tag, dropping empty ones and duplicates; order is kept.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().map(|tag| normalize_tag(tag)) {
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// `event` as backends store it: tags and entities normalized, with the originals
+/// kept in an object payload when they differ. Originals already in the payload, as
+/// on a replayed event, are left alone.
+pub(crate) fn normalize_event_tags(mut event: Event) -> Event {
+    let tags = normalize_tags(&event.tags);
+    let entities = normalize_tags(&event.entities);
+    if let Value::Object(map) = &mut event.payload {
+        if tags != event.tags {
+            map.entry(ORIGINAL_TAGS_KEY).or_insert_with(|| json!(event.tags));
+        }
+        if entities != event.entities {
+            map.entry(ORIGINAL_ENTITIES_KEY).or_insert_with(|| json!(event.entities));
+        }
+    }
+    event.tags = tags;
+    event.entities = entities;
+    event
+}
+
+/// The values a tag or entity filter matches: each as given and normalized, so
+/// filters find both normalized events and records stored as written, such as
+/// episodes and events from before normalization.
+pub(crate) fn tag_forms(values: &[String]) -> Vec<String> {
+    let mut forms = values.to_vec();
+    for tag in normalize_tags(values) {
+        if !forms.contains(&tag) {
+            forms.push(tag);
+        }
+    }
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventFilter, EventKind, InMemoryStore, Store};
+    use engram_types::Scope;
+
+    #[test]
+    fn tags_match_across_case_and_normalization_form() {
+        assert_eq!(normalize_tag("  Café "), "café");
+        assert_eq!(normalize_tag("CAFE\u{301}"), "café");
+        assert_eq!(normalize_tag(&"Ä".repeat(100)).chars().count(), MAX_TAG_LEN);
+        let tags = ["Billing".to_string(), "billing".to_string(), " ".to_string()];
+        assert_eq!(normalize_tags(&tags), ["billing"]);
+
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let mut event = Event::new(scope.clone(), EventKind::Message, json!({"content": "hi"}));
+        event.tags = vec!["Café".to_string()];
+        event.entities = vec!["acme".to_string()];
+        store.append_event(event).unwrap();
+
+        let filter = EventFilter {
+            tags: vec!["cafe\u{301}".to_string()],
+            ..EventFilter::default()
+        };
+        let events = store.find_events(&scope, filter).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].tags, ["café"]);
+        assert_eq!(events[0].payload[ORIGINAL_TAGS_KEY], json!(["Café"]));
+        assert!(events[0].payload.get(ORIGINAL_ENTITIES_KEY).is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+thread_local! {
+    static CALL_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+    static CALL_TIMEOUT_CAP: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// SQLite's lock wait when no timeout is configured; rusqlite's own default.
+pub(crate) const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs `f` with every store call it makes on this thread bounded by `timeout`,
+/// overriding the store's own statement timeout. Calls that run out of time fail with
+/// [`StoreError::Timeout`](crate::StoreError::Timeout).
+///
+/// The override is thread-local: with `spawn_blocking` or a thread pool, call this
+/// inside the closure that performs the store calls.
+pub fn with_timeout<T>(timeout: Duration, f: impl FnOnce() -> T) -> T {
+    let previous = CALL_TIMEOUT.with(|cell| cell.replace(Some(timeout)));
+    let result = f();
+    CALL_TIMEOUT.with(|cell| cell.set(previous));
+    result
+}
+
+/// Runs `f` with every store call it makes on this thread bounded by at most `cap`:
+/// unlike [`with_timeout`], a shorter timeout of the store or an enclosing call wins.
+pub(crate) fn with_timeout_cap<T>(cap: Duration, f: impl FnOnce() -> T) -> T {
+    let previous = CALL_TIMEOUT_CAP.with(|cell| {
+        let capped = cell.get().map_or(cap, |outer| outer.min(cap));
+        cell.replace(Some(capped))
+    });
+    let result = f();
+    CALL_TIMEOUT_CAP.with(|cell| cell.set(previous));
+    result
+}
+
+/// The timeout for a call on this thread: the [`with_timeout`] override if any,
+/// otherwise the store's own, cut to any [`with_timeout_cap`] in effect.
+pub(crate) fn effective_timeout(store_timeout: Option<Duration>) -> Option<Duration> {
+    let timeout = CALL_TIMEOUT.with(Cell::get).or(store_timeout);
+    match CALL_TIMEOUT_CAP.with(Cell::get) {
+        Some(cap) => Some(timeout.map_or(cap, |timeout| timeout.min(cap))),
+        None => timeout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, EventKind, SqliteStore, Store, StoreError};
+    use engram_types::Scope;
+    use rusqlite::Connection;
+    use serde_json::json;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn lock_waits_time_out_per_call() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("engram-timeout-{}", nanos));
+        let path = dir.join("engram.db");
+        let store = SqliteStore::new(&path)
+            .unwrap()
+            .with_statement_timeout(Duration::from_secs(30));
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+
+        let writer = Connection::open(&path).unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let result = with_timeout(Duration::from_millis(50), || {
+            store.append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+        });
+        assert!(matches!(result, Err(StoreError::Timeout(_))));
+
+        writer.execute_batch("ROLLBACK").unwrap();
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
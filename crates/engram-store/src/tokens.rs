@@ -0,0 +1,165 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+thread_local! {
+    static CALL_TOKEN_COUNTER: RefCell<Option<Arc<dyn TokenCounter>>> =
+        const { RefCell::new(None) };
+}
+
+/// Counters registered per model name, plus BPE counters loaded on first use.
+static TOKEN_COUNTERS: OnceLock<RwLock<HashMap<String, Arc<dyn TokenCounter>>>> =
+    OnceLock::new();
+
+/// Counts the tokens a model would see for a text. The composer sizes sections,
+/// degrades and reports [`BudgetReport`](engram_types::BudgetReport) usage with the
+/// counter for [`Budget::model`](engram_types::Budget::model), see [`token_counter`].
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Model-agnostic estimate of 4 chars per token, used when no model is named or
+/// none of its tokenizers is available.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenCounter;
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Byte-pair encoding counter using OpenAI's tokenizers, e.g. for `gpt-4o`.
+#[cfg(feature = "tiktoken")]
+pub struct BpeTokenCounter {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl BpeTokenCounter {
+    /// The tokenizer of `model`; fails for models tiktoken does not know.
+    pub fn for_model(model: &str) -> crate::StoreResult<Self> {
+        let bpe = tiktoken_rs::get_bpe_from_model(model)
+            .map_err(|err| crate::StoreError::InvalidInput(err.to_string()))?;
+        Ok(Self { bpe })
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl std::fmt::Debug for BpeTokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BpeTokenCounter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for BpeTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+fn counters() -> &'static RwLock<HashMap<String, Arc<dyn TokenCounter>>> {
+    TOKEN_COUNTERS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Makes `counter` the one [`token_counter`] returns for `model`, replacing any
+/// earlier registration, e.g. to count with a provider's own tokenizer.
+pub fn register_token_counter(model: impl Into<String>, counter: Arc<dyn TokenCounter>) {
+    let mut counters = counters().write().unwrap_or_else(|err| err.into_inner());
+    counters.insert(model.into(), counter);
+}
+
+/// The counter for `model`: the registered one, else (with the `tiktoken` feature)
+/// the model's BPE tokenizer, else [`HeuristicTokenCounter`].
+pub fn token_counter(model: Option<&str>) -> Arc<dyn TokenCounter> {
+    let Some(model) = model.map(str::trim).filter(|model| !model.is_empty()) else {
+        return Arc::new(HeuristicTokenCounter);
+    };
+    {
+        let counters = counters().read().unwrap_or_else(|err| err.into_inner());
+        if let Some(counter) = counters.get(model) {
+            return Arc::clone(counter);
+        }
+    }
+    #[cfg(feature = "tiktoken")]
+    match BpeTokenCounter::for_model(model) {
+        Ok(counter) => {
+            let counter: Arc<dyn TokenCounter> = Arc::new(counter);
+            register_token_counter(model, Arc::clone(&counter));
+            return counter;
+        }
+        Err(err) => tracing::debug!("no tokenizer for model {}: {}", model, err),
+    }
+    Arc::new(HeuristicTokenCounter)
+}
+
+/// Runs `f` with the token counts it makes on this thread taken by `counter`.
+///
+/// Like [`with_read_preference`](crate::with_read_preference), the counter is
+/// thread-local: with `spawn_blocking` or a thread pool, call this inside the closure.
+pub fn with_token_counter<T>(counter: Arc<dyn TokenCounter>, f: impl FnOnce() -> T) -> T {
+    let previous = CALL_TOKEN_COUNTER.with(|cell| cell.replace(Some(counter)));
+    let result = f();
+    CALL_TOKEN_COUNTER.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+/// Tokens in `text` by the counter of the call running on this thread.
+pub(crate) fn count_tokens(text: &str) -> usize {
+    CALL_TOKEN_COUNTER.with(|cell| match cell.borrow().as_ref() {
+        Some(counter) => counter.count(text),
+        None => HeuristicTokenCounter.count(text),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, InMemoryStore, StmState, Store};
+    use engram_types::{MemoryPacket, Purpose, Scope};
+
+    /// One token per word, like a tokenizer with a very large vocabulary.
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    #[test]
+    fn budgets_are_counted_with_the_models_tokenizer() {
+        register_token_counter("word-model", Arc::new(WordCounter));
+        assert_eq!(token_counter(None).count("twelve chars"), 3);
+        assert_eq!(token_counter(Some("word-model")).count("twelve chars"), 2);
+        assert_eq!(token_counter(Some("unknown-model")).count(""), 0);
+
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let stm = StmState {
+            rolling_summary: "The customer asked for a refund on order 42 after it arrived broken"
+                .to_string(),
+            key_quotes: Vec::new(),
+        };
+        store.update_stm(&scope, stm).unwrap();
+
+        let mut request = BuildRequest::new(scope, Purpose::Responder);
+        let heuristic = build_memory_packet(&store, request.clone()).unwrap();
+        request.budget.model = Some("word-model".to_string());
+        let counted = build_memory_packet(&store, request).unwrap();
+
+        let summary_tokens = |packet: &MemoryPacket| {
+            packet.budget_report.section_usage["rolling_summary"].as_u64().unwrap()
+        };
+        assert_eq!(summary_tokens(&counted), 13);
+        assert_eq!(summary_tokens(&heuristic), 18);
+        assert!(counted.budget_report.used_tokens_est < heuristic.budget_report.used_tokens_est);
+    }
+}
@@ -0,0 +1,436 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use engram_types::{
+    Episode, Fact, InsightItem, KeyQuote, MemoryPacket, Procedure, ProcedureCandidate, Scope,
+};
+use serde::Serialize;
+
+use crate::{Event, StmState, StoreError, StoreResult, WorkingStatePatch};
+
+/// Longest id, in characters, every backend stores as is: MySQL keys are `VARCHAR(96)`
+/// unless the store was opened with `MySqlStore::with_id_column_len`.
+pub const MAX_ID_LEN: usize = 96;
+
+/// Bounds enforced on writes when strict input validation is enabled.
+///
+/// Observed timestamps (event time, episode range, candidate review times) must fall
+/// between `min_timestamp` and `now + max_clock_skew`; validity windows only need to
+/// stay within `min_timestamp..=max_timestamp`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputLimits {
+    pub min_timestamp: DateTime<Utc>,
+    pub max_timestamp: DateTime<Utc>,
+    pub max_clock_skew: Duration,
+    pub max_text_bytes: usize,
+    pub max_payload_bytes: usize,
+    pub max_list_len: usize,
+    /// Longest scope or record id, in characters.
+    pub max_id_len: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            min_timestamp: Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            max_timestamp: Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap(),
+            max_clock_skew: Duration::days(1),
+            max_text_bytes: 64 * 1024,
+            max_payload_bytes: 1024 * 1024,
+            max_list_len: 1024,
+            max_id_len: MAX_ID_LEN,
+        }
+    }
+}
+
+impl InputLimits {
+    pub fn validate_event(&self, event: &Event) -> StoreResult<()> {
+        self.check_run_scope("event.scope", &event.scope)?;
+        self.check_id("event.event_id", &event.event_id)?;
+        self.check_observed_ts("event.ts", event.ts)?;
+        self.check_payload("event.payload", &event.payload)?;
+        self.check_texts("event.tags", &event.tags)?;
+        self.check_texts("event.entities", &event.entities)
+    }
+
+    pub fn validate_working_state_patch(
+        &self,
+        scope: &Scope,
+        patch: &WorkingStatePatch,
+    ) -> StoreResult<()> {
+        self.check_run_scope("scope", scope)?;
+        if let Some(goal) = &patch.goal {
+            self.check_text("working_state.goal", goal)?;
+        }
+        if let Some(plan) = &patch.plan {
+            self.check_texts("working_state.plan", plan)?;
+        }
+        if let Some(slots) = &patch.slots {
+            self.check_payload("working_state.slots", slots)?;
+        }
+        if let Some(constraints) = &patch.constraints {
+            self.check_payload("working_state.constraints", constraints)?;
+        }
+        if let Some(evidence) = &patch.tool_evidence {
+            self.check_len("working_state.tool_evidence", evidence.len())?;
+            for (idx, item) in evidence.iter().enumerate() {
+                self.check_id(
+                    &format!("working_state.tool_evidence[{}].evidence_id", idx),
+                    &item.evidence_id,
+                )?;
+                self.check_text(
+                    &format!("working_state.tool_evidence[{}].summary", idx),
+                    &item.summary,
+                )?;
+            }
+        }
+        if let Some(decisions) = &patch.decisions {
+            self.check_texts("working_state.decisions", decisions)?;
+        }
+        if let Some(risks) = &patch.risks {
+            self.check_texts("working_state.risks", risks)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate_stm(&self, scope: &Scope, stm: &StmState) -> StoreResult<()> {
+        self.check_session_scope("scope", scope)?;
+        self.check_text("stm.rolling_summary", &stm.rolling_summary)?;
+        self.check_len("stm.key_quotes", stm.key_quotes.len())?;
+        for (idx, quote) in stm.key_quotes.iter().enumerate() {
+            self.check_key_quote(&format!("stm.key_quotes[{}]", idx), quote)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate_fact(&self, scope: &Scope, fact: &Fact) -> StoreResult<()> {
+        self.check_ltm_scope("scope", scope)?;
+        self.check_id("fact.fact_id", &fact.fact_id)?;
+        self.check_id("fact.fact_key", &fact.fact_key)?;
+        self.check_payload("fact.value", &fact.value)?;
+        check_confidence("fact.confidence", fact.confidence)?;
+        if let Some(from) = fact.validity.valid_from {
+            self.check_bounded_ts("fact.validity.valid_from", from)?;
+        }
+        if let Some(to) = fact.validity.valid_to {
+            self.check_bounded_ts("fact.validity.valid_to", to)?;
+        }
+        if let (Some(from), Some(to)) = (fact.validity.valid_from, fact.validity.valid_to)
+            && to < from
+        {
+            return Err(invalid(format!(
+                "fact.validity.valid_to ({}) is before valid_from ({})",
+                to.to_rfc3339(),
+                from.to_rfc3339()
+            )));
+        }
+        self.check_texts("fact.sources", &fact.sources)?;
+        self.check_text("fact.notes", &fact.notes)
+    }
+
+    pub fn validate_episode(&self, scope: &Scope, episode: &Episode) -> StoreResult<()> {
+        self.check_ltm_scope("scope", scope)?;
+        self.check_id("episode.episode_id", &episode.episode_id)?;
+        self.check_observed_ts("episode.time_range.start", episode.time_range.start)?;
+        if let Some(end) = episode.time_range.end {
+            self.check_observed_ts("episode.time_range.end", end)?;
+            if end < episode.time_range.start {
+                return Err(invalid(format!(
+                    "episode.time_range.end ({}) is before start ({})",
+                    end.to_rfc3339(),
+                    episode.time_range.start.to_rfc3339()
+                )));
+            }
+        }
+        self.check_text("episode.summary", &episode.summary)?;
+        self.check_texts("episode.highlights", &episode.highlights)?;
+        self.check_texts("episode.tags", &episode.tags)?;
+        self.check_texts("episode.entities", &episode.entities)?;
+        self.check_texts("episode.sources", &episode.sources)?;
+        if let Some(score) = episode.recency_score
+            && !(score.is_finite() && score >= 0.0)
+        {
+            return Err(invalid(format!(
+                "episode.recency_score must be a non-negative number, got {}",
+                score
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn validate_procedure(&self, scope: &Scope, procedure: &Procedure) -> StoreResult<()> {
+        self.check_ltm_scope("scope", scope)?;
+        self.check_procedure("procedure", procedure)
+    }
+
+    pub fn validate_procedure_candidate(
+        &self,
+        scope: &Scope,
+        candidate: &ProcedureCandidate,
+    ) -> StoreResult<()> {
+        self.check_ltm_scope("scope", scope)?;
+        self.check_id("candidate.candidate_id", &candidate.candidate_id)?;
+        self.check_procedure("candidate.procedure", &candidate.procedure)?;
+        self.check_texts("candidate.source_episodes", &candidate.source_episodes)?;
+        self.check_texts("candidate.evidence", &candidate.evidence)?;
+        self.check_observed_ts("candidate.created_at", candidate.created_at)?;
+        if let Some(reviewed_at) = candidate.reviewed_at {
+            self.check_observed_ts("candidate.reviewed_at", reviewed_at)?;
+        }
+        self.check_text("candidate.reviewer", &candidate.reviewer)?;
+        self.check_text("candidate.review_note", &candidate.review_note)
+    }
+
+    pub fn validate_insight(&self, scope: &Scope, insight: &InsightItem) -> StoreResult<()> {
+        self.check_ltm_scope("scope", scope)?;
+        self.check_id("insight.id", &insight.id)?;
+        self.check_text("insight.statement", &insight.statement)?;
+        check_confidence("insight.confidence", insight.confidence)?;
+        self.check_texts("insight.tests_suggested", &insight.tests_suggested)?;
+        self.check_texts("insight.sources", &insight.sources)
+    }
+
+    pub fn validate_context_build(&self, scope: &Scope, packet: &MemoryPacket) -> StoreResult<()> {
+        self.check_run_scope("scope", scope)?;
+        self.check_payload("packet", packet)
+    }
+
+    fn check_procedure(&self, field: &str, procedure: &Procedure) -> StoreResult<()> {
+        self.check_id(&format!("{}.procedure_id", field), &procedure.procedure_id)?;
+        self.check_id(&format!("{}.task_type", field), &procedure.task_type)?;
+        self.check_payload(&format!("{}.content", field), &procedure.content)?;
+        self.check_payload(&format!("{}.applicability", field), &procedure.applicability)?;
+        self.check_texts(&format!("{}.sources", field), &procedure.sources)
+    }
+
+    fn check_key_quote(&self, field: &str, quote: &KeyQuote) -> StoreResult<()> {
+        self.check_id(&format!("{}.evidence_id", field), &quote.evidence_id)?;
+        self.check_text(&format!("{}.quote", field), &quote.quote)?;
+        if let Some(ts) = quote.ts {
+            self.check_observed_ts(&format!("{}.ts", field), ts)?;
+        }
+        Ok(())
+    }
+
+    fn check_ltm_scope(&self, field: &str, scope: &Scope) -> StoreResult<()> {
+        self.check_id(&format!("{}.tenant_id", field), &scope.tenant_id)?;
+        self.check_id(&format!("{}.user_id", field), &scope.user_id)?;
+        self.check_id(&format!("{}.agent_id", field), &scope.agent_id)
+    }
+
+    fn check_session_scope(&self, field: &str, scope: &Scope) -> StoreResult<()> {
+        self.check_ltm_scope(field, scope)?;
+        self.check_id(&format!("{}.session_id", field), &scope.session_id)
+    }
+
+    fn check_run_scope(&self, field: &str, scope: &Scope) -> StoreResult<()> {
+        self.check_session_scope(field, scope)?;
+        self.check_id(&format!("{}.run_id", field), &scope.run_id)
+    }
+
+    fn check_id(&self, field: &str, value: &str) -> StoreResult<()> {
+        if value.trim().is_empty() {
+            return Err(invalid(format!("{} must not be empty", field)));
+        }
+        check_id_len(field, value, self.max_id_len)
+    }
+
+    fn check_observed_ts(&self, field: &str, ts: DateTime<Utc>) -> StoreResult<()> {
+        let latest = Utc::now() + self.max_clock_skew;
+        if ts < self.min_timestamp || ts > latest {
+            return Err(invalid(format!(
+                "{} ({}) is outside the accepted range {} to {}",
+                field,
+                ts.to_rfc3339(),
+                self.min_timestamp.to_rfc3339(),
+                latest.to_rfc3339()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_bounded_ts(&self, field: &str, ts: DateTime<Utc>) -> StoreResult<()> {
+        if ts < self.min_timestamp || ts > self.max_timestamp {
+            return Err(invalid(format!(
+                "{} ({}) is outside the accepted range {} to {}",
+                field,
+                ts.to_rfc3339(),
+                self.min_timestamp.to_rfc3339(),
+                self.max_timestamp.to_rfc3339()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_text(&self, field: &str, value: &str) -> StoreResult<()> {
+        if value.len() > self.max_text_bytes {
+            return Err(invalid(format!(
+                "{} is {} bytes, exceeding the limit of {}",
+                field,
+                value.len(),
+                self.max_text_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_texts(&self, field: &str, values: &[String]) -> StoreResult<()> {
+        self.check_len(field, values.len())?;
+        for (idx, value) in values.iter().enumerate() {
+            self.check_text(&format!("{}[{}]", field, idx), value)?;
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, field: &str, len: usize) -> StoreResult<()> {
+        if len > self.max_list_len {
+            return Err(invalid(format!(
+                "{} has {} entries, exceeding the limit of {}",
+                field, len, self.max_list_len
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_payload<T: Serialize + ?Sized>(&self, field: &str, value: &T) -> StoreResult<()> {
+        let size = serde_json::to_vec(value)
+            .map_err(|err| invalid(format!("{} is not serializable: {}", field, err)))?
+            .len();
+        if size > self.max_payload_bytes {
+            return Err(invalid(format!(
+                "{} is {} bytes when serialized, exceeding the limit of {}",
+                field, size, self.max_payload_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Fails with [`StoreError::InvalidInput`] if `value` is longer than `max_len`
+/// characters, before a backend truncates it or rejects it deep in a statement.
+pub fn check_id_len(field: &str, value: &str, max_len: usize) -> StoreResult<()> {
+    let len = value.chars().count();
+    if len > max_len {
+        return Err(invalid(format!(
+            "{} is {} characters, exceeding the id limit of {}",
+            field, len, max_len
+        )));
+    }
+    Ok(())
+}
+
+fn check_confidence(field: &str, value: f64) -> StoreResult<()> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(invalid(format!(
+            "{} must be between 0 and 1, got {}",
+            field, value
+        )));
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> StoreError {
+    StoreError::InvalidInput(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryStore, PayloadSchemaRegistry, Store, ValidatingStore};
+    use engram_types::{FactStatus, ScopeLevel, Validity};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    fn sample_fact(id: &str, confidence: f64) -> Fact {
+        Fact {
+            fact_id: id.to_string(),
+            fact_key: "user.name".to_string(),
+            value: json!("Ada"),
+            status: FactStatus::Active,
+            validity: Validity::default(),
+            confidence,
+            sources: Vec::new(),
+            scope_level: ScopeLevel::User,
+            notes: String::new(),
+            pinned: false,
+            lang: None,
+        }
+    }
+
+    fn message(err: StoreError) -> String {
+        match err {
+            StoreError::InvalidInput(msg) => msg,
+            other => panic!("expected invalid input, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn strict_mode_rejects_malformed_writes() {
+        let scope = sample_scope();
+        let registry = Arc::new(PayloadSchemaRegistry::new());
+        let lenient = ValidatingStore::new(InMemoryStore::new(), registry.clone());
+        lenient.upsert_fact(&scope, sample_fact("f0", 1.5)).unwrap();
+
+        let limits = InputLimits {
+            max_text_bytes: 8,
+            ..InputLimits::default()
+        };
+        let store = ValidatingStore::new(InMemoryStore::new(), registry).with_input_limits(limits);
+
+        store.upsert_fact(&scope, sample_fact("f1", 0.9)).unwrap();
+        let msg = message(store.upsert_fact(&scope, sample_fact("f2", 1.5)).unwrap_err());
+        assert_eq!(msg, "fact.confidence must be between 0 and 1, got 1.5");
+        let msg = message(store.upsert_fact(&scope, sample_fact(" ", 0.5)).unwrap_err());
+        assert_eq!(msg, "fact.fact_id must not be empty");
+        let long_id = "f".repeat(MAX_ID_LEN + 1);
+        let msg = message(store.upsert_fact(&scope, sample_fact(&long_id, 0.5)).unwrap_err());
+        assert_eq!(msg, "fact.fact_id is 97 characters, exceeding the id limit of 96");
+        // The limit counts characters, not bytes.
+        let wide_id = "é".repeat(MAX_ID_LEN);
+        store.upsert_fact(&scope, sample_fact(&wide_id, 0.5)).unwrap();
+
+        let mut fact = sample_fact("f3", 0.5);
+        fact.notes = "far too long".to_string();
+        let msg = message(store.upsert_fact(&scope, fact).unwrap_err());
+        assert_eq!(msg, "fact.notes is 12 bytes, exceeding the limit of 8");
+
+        let event = Event {
+            event_id: "e1".to_string(),
+            scope: Scope {
+                run_id: String::new(),
+                ..scope.clone()
+            },
+            ts: Utc::now(),
+            kind: crate::EventKind::Message,
+            payload: json!({}),
+            tags: Vec::new(),
+            entities: Vec::new(),
+            seq: 0,
+            lang: None,
+        };
+        let msg = message(store.append_event(event.clone()).unwrap_err());
+        assert_eq!(msg, "event.scope.run_id must not be empty");
+
+        for year in [1970, 3000] {
+            let stale = Event {
+                scope: scope.clone(),
+                ts: Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap(),
+                ..event.clone()
+            };
+            let msg = message(store.append_event(stale).unwrap_err());
+            assert!(msg.starts_with("event.ts ("), "{}", msg);
+            assert!(msg.contains(&format!("{}-01-01", year)), "{}", msg);
+        }
+
+        let facts = store.list_facts(&scope, Default::default()).unwrap();
+        assert_eq!(facts.len(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+use engram_types::Scope;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::info;
+
+use crate::embedding::memory_texts;
+use crate::{
+    backfill_embeddings, text_digest, BackfillOptions, BackfillReport, Embedder, MemoryEmbedding,
+    MemoryRef, Store, StoreResult,
+};
+
+/// How well the stored embeddings of one model match the memory they describe; see
+/// [`vector_index_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorIndexStats {
+    pub model: String,
+    /// Long-term scopes (tenant, user, agent) inspected.
+    pub scopes: usize,
+    /// Facts and episodes in those scopes.
+    pub items: usize,
+    /// Items with an embedding of their current text.
+    pub embedded: usize,
+    /// Items without an embedding.
+    pub missing: usize,
+    /// Items embedded from text that has changed since.
+    pub stale: usize,
+    /// Embeddings whose fact or episode no longer exists.
+    pub orphaned: usize,
+    /// Embeddings by vector length; more than one length means the model changed.
+    pub dimensions: BTreeMap<usize, usize>,
+}
+
+impl VectorIndexStats {
+    /// Whether every item has a current embedding and every embedding an item, all of
+    /// one length.
+    pub fn is_healthy(&self) -> bool {
+        self.missing == 0 && self.stale == 0 && self.orphaned == 0 && self.dimensions.len() <= 1
+    }
+}
+
+/// What [`rebuild_vector_index`] did.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RebuildIndexReport {
+    pub scopes: usize,
+    /// Orphaned embeddings and embeddings of a minority length that were deleted.
+    pub removed: usize,
+    /// Items embedded again, summed over the scopes.
+    pub backfill: BackfillReport,
+}
+
+struct ScopeIndex {
+    scope: Scope,
+    embeddings: Vec<MemoryEmbedding>,
+    items: usize,
+    missing: usize,
+    stale: usize,
+    orphaned: Vec<MemoryRef>,
+}
+
+/// Compares the stored embeddings of `model` with the facts and episodes they were
+/// computed from, for `scope` or, without one, every long-term scope that has events.
+/// Operators run it to detect drift, e.g. after restoring a backup or changing the
+/// model, and repair it with [`rebuild_vector_index`].
+pub fn vector_index_stats<S: Store + ?Sized>(
+    store: &S,
+    model: &str,
+    scope: Option<&Scope>,
+) -> StoreResult<VectorIndexStats> {
+    let indexes = inspect(store, model, scope)?;
+    let mut stats = VectorIndexStats {
+        model: model.to_string(),
+        scopes: indexes.len(),
+        ..VectorIndexStats::default()
+    };
+    for index in &indexes {
+        stats.items += index.items;
+        stats.missing += index.missing;
+        stats.stale += index.stale;
+        stats.orphaned += index.orphaned.len();
+        for embedding in &index.embeddings {
+            *stats.dimensions.entry(embedding.vector.len()).or_default() += 1;
+        }
+    }
+    stats.embedded = stats.items - stats.missing - stats.stale;
+    Ok(stats)
+}
+
+/// Repairs the stored embeddings of the embedder's model in place: deletes orphaned
+/// embeddings and those whose length differs from the most common one, then embeds
+/// missing and stale items as [`backfill_embeddings`] does, per scope.
+pub fn rebuild_vector_index<S: Store + ?Sized>(
+    store: &S,
+    scope: Option<&Scope>,
+    embedder: &dyn Embedder,
+    options: &BackfillOptions,
+) -> StoreResult<RebuildIndexReport> {
+    let indexes = inspect(store, embedder.model(), scope)?;
+    let mut lengths: HashMap<usize, usize> = HashMap::new();
+    for embedding in indexes.iter().flat_map(|index| &index.embeddings) {
+        *lengths.entry(embedding.vector.len()).or_default() += 1;
+    }
+    let dimension = lengths
+        .into_iter()
+        .max_by_key(|(length, count)| (*count, *length))
+        .map(|(length, _)| length);
+
+    let mut report = RebuildIndexReport {
+        scopes: indexes.len(),
+        ..RebuildIndexReport::default()
+    };
+    for index in indexes {
+        let mut remove = index.orphaned;
+        remove.extend(
+            index
+                .embeddings
+                .into_iter()
+                .filter(|embedding| Some(embedding.vector.len()) != dimension)
+                .map(|embedding| embedding.item),
+        );
+        if !remove.is_empty() {
+            report.removed += store.delete_embeddings(&index.scope, embedder.model(), &remove)?;
+        }
+        let backfill = backfill_embeddings(store, &index.scope, embedder, options)?;
+        report.backfill.embedded += backfill.embedded;
+        report.backfill.batches += backfill.batches;
+        report.backfill.remaining += backfill.remaining;
+    }
+    info!(
+        "rebuilt {} vector index over {} scopes: {} removed, {} embedded",
+        embedder.model(),
+        report.scopes,
+        report.removed,
+        report.backfill.embedded
+    );
+    Ok(report)
+}
+
+fn inspect<S: Store + ?Sized>(
+    store: &S,
+    model: &str,
+    scope: Option<&Scope>,
+) -> StoreResult<Vec<ScopeIndex>> {
+    let scopes = match scope {
+        Some(scope) => vec![scope.clone()],
+        None => {
+            let mut seen = HashSet::new();
+            store
+                .list_scopes(None, None)?
+                .into_iter()
+                .filter(|scope| {
+                    seen.insert((
+                        scope.tenant_id.clone(),
+                        scope.user_id.clone(),
+                        scope.agent_id.clone(),
+                    ))
+                })
+                .collect()
+        }
+    };
+    let mut indexes = Vec::new();
+    for scope in scopes {
+        let texts: HashMap<MemoryRef, String> = memory_texts(store, &scope)?
+            .into_iter()
+            .map(|(item, text)| (item, text_digest(&text)))
+            .collect();
+        let embeddings = store.list_embeddings(&scope, model)?;
+        let digests: HashMap<&MemoryRef, &str> = embeddings
+            .iter()
+            .map(|embedding| (&embedding.item, embedding.digest.as_str()))
+            .collect();
+        let missing = texts.keys().filter(|item| !digests.contains_key(item)).count();
+        let stale = texts
+            .iter()
+            .filter(|(item, digest)| digests.get(item).is_some_and(|stored| stored != digest))
+            .count();
+        let orphaned = embeddings
+            .iter()
+            .filter(|embedding| !texts.contains_key(&embedding.item))
+            .map(|embedding| embedding.item.clone())
+            .collect();
+        indexes.push(ScopeIndex {
+            items: texts.len(),
+            missing,
+            stale,
+            orphaned,
+            embeddings,
+            scope,
+        });
+    }
+    Ok(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, EventKind, InMemoryStore, MemoryKind};
+    use chrono::Utc;
+    use engram_types::Fact;
+    use serde_json::json;
+
+    struct FixedEmbedder;
+
+    impl Embedder for FixedEmbedder {
+        fn model(&self) -> &str {
+            "fixed"
+        }
+
+        fn embed(&self, texts: &[String]) -> StoreResult<Vec<Vec<f32>>> {
+            Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[test]
+    fn reports_drift_and_rebuilds_in_place() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        store
+            .append_event(Event::new(scope.clone(), EventKind::Message, json!("hi")))
+            .unwrap();
+        for id in ["f1", "f2", "f3"] {
+            let mut fact = Fact::new(format!("key.{}", id), json!(id));
+            fact.fact_id = id.to_string();
+            store.upsert_fact(&scope, fact).unwrap();
+        }
+        let embedding = |id: &str, text: &str, vector: Vec<f32>| MemoryEmbedding {
+            item: MemoryRef {
+                kind: MemoryKind::Fact,
+                id: id.to_string(),
+            },
+            model: "fixed".to_string(),
+            vector,
+            digest: text_digest(text),
+            embedded_at: Utc::now(),
+        };
+        store
+            .upsert_embeddings(
+                &scope,
+                vec![
+                    embedding("f1", "key.f1: f1", vec![1.0, 0.0]),
+                    embedding("f2", "key.f2: outdated", vec![1.0, 0.0]),
+                    embedding("gone", "key.gone: gone", vec![1.0, 0.0]),
+                    embedding("f3", "key.f3: f3", vec![1.0, 0.0, 0.0]),
+                ],
+            )
+            .unwrap();
+
+        let stats = vector_index_stats(&store, "fixed", None).unwrap();
+        assert_eq!(stats.scopes, 1);
+        assert_eq!((stats.items, stats.embedded, stats.missing), (3, 2, 0));
+        assert_eq!((stats.stale, stats.orphaned), (1, 1));
+        assert_eq!(stats.dimensions, BTreeMap::from([(2, 3), (3, 1)]));
+        assert!(!stats.is_healthy());
+
+        let report =
+            rebuild_vector_index(&store, Some(&scope), &FixedEmbedder, &BackfillOptions::default())
+                .unwrap();
+        assert_eq!(report.removed, 2);
+        assert_eq!(report.backfill.embedded, 2);
+        let stats = vector_index_stats(&store, "fixed", Some(&scope)).unwrap();
+        assert!(stats.is_healthy(), "{:?}", stats);
+        assert_eq!(stats.embedded, 3);
+    }
+}
@@ -0,0 +1,231 @@
+use engram_types::{json_schema, WireType};
+use jsonschema::Validator;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::payload_schema::schema_errors;
+use crate::{StoreError, StoreResult};
+
+/// Checks `payload` against the published schema of `kind` (see
+/// [`WIRE_SCHEMA`](engram_types::WIRE_SCHEMA)), so producers can reject a malformed
+/// packet, event, fact or build request before it crosses the FFI or HTTP boundary.
+/// Every violation is reported in one `StoreError::InvalidInput`.
+pub fn validate_json(kind: WireType, payload: &Value) -> StoreResult<()> {
+    static VALIDATORS: OnceLock<Vec<Validator>> = OnceLock::new();
+    let validators = VALIDATORS.get_or_init(|| {
+        WireType::ALL
+            .iter()
+            .map(|kind| {
+                jsonschema::validator_for(&json_schema(*kind)).expect("wire schema compiles")
+            })
+            .collect()
+    });
+    let index = WireType::ALL.iter().position(|known| *known == kind).expect("listed");
+    let errors = schema_errors(&validators[index], payload);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(StoreError::InvalidInput(format!(
+        "payload violates the {} schema: {}",
+        kind,
+        errors.join("; ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_memory_packet, BuildRequest, Event, EventKind, InMemoryStore, Store};
+    use crate::{StmState, WorkingStatePatch};
+    use engram_types::{
+        Episode, EvidenceRef, Fact, InsightItem, InsightType, KeyQuote, Procedure, Purpose, Role,
+        Scope,
+    };
+    use crate::{LangMode, ReadPreference};
+    use engram_types::{
+        Budget, BudgetReport, Citation, CompressionLevel, ConversationTurn, ExternalContext,
+        FactStatus, Insight, InsightTrigger, LongTerm, MemoryPacket, Meta, ScopeLevel, ShortTerm,
+        TimeRange, ValidationState, Validity, WorkingState, WIRE_SCHEMA,
+    };
+    use serde::de::{self, DeserializeOwned, Visitor};
+    use serde_json::json;
+
+    #[test]
+    fn schemas_match_what_the_types_serialize() {
+        let store = InMemoryStore::new();
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let patch = WorkingStatePatch {
+            goal: Some("ship v1".to_string()),
+            plan: Some(vec!["test".to_string()]),
+            tool_evidence: Some(vec![EvidenceRef {
+                evidence_id: "e0".to_string(),
+                summary: "ci green".to_string(),
+                kind: "tool_result".to_string(),
+            }]),
+            ..WorkingStatePatch::default()
+        };
+        store.patch_working_state(&scope, patch).unwrap();
+        let stm = StmState {
+            rolling_summary: "summary".to_string(),
+            key_quotes: vec![KeyQuote {
+                evidence_id: "e1".to_string(),
+                quote: "ship it friday".to_string(),
+                role: Role::User,
+                ts: None,
+            }],
+        };
+        store.update_stm(&scope, stm).unwrap();
+        let mut fact = Fact::new("release.day", json!("friday"));
+        fact.sources = vec!["e1".to_string()];
+        store.upsert_fact(&scope, fact.clone()).unwrap();
+        store.append_episode(&scope, Episode::new("planned the release")).unwrap();
+        let procedure = Procedure::new("release", json!(["tag", "deploy"]));
+        store.upsert_procedure(&scope, procedure).unwrap();
+        store
+            .append_insight(&scope, InsightItem::new(InsightType::Hypothesis, "fridays are risky"))
+            .unwrap();
+        let event = Event::new(
+            scope.clone(),
+            EventKind::Message,
+            json!({"role": "user", "content": "ship it friday"}),
+        );
+        store.append_event(event.clone()).unwrap();
+        let mut request = BuildRequest::new(scope.clone(), Purpose::Planner);
+        request.policy.filter.planner.conversation_window = true;
+        let packet = build_memory_packet(&store, request).unwrap();
+        assert!(!packet.short_term.conversation_window.is_empty());
+        assert!(!packet.insight.hypotheses.is_empty() && !packet.citations.is_empty());
+
+        validate_json(WireType::MemoryPacket, &serde_json::to_value(&packet).unwrap()).unwrap();
+        validate_json(WireType::Event, &serde_json::to_value(&event).unwrap()).unwrap();
+        validate_json(WireType::Fact, &serde_json::to_value(&fact).unwrap()).unwrap();
+        let mut request = json!({
+            "scope": scope,
+            "purpose": "responder",
+            "cues": {"keywords": ["release"], "time_range": {"start_ms": 0}},
+            "policy": {"max_facts": 5, "lang_mode": "filter", "synonyms": {"ship": ["deploy"]}},
+            "read_preference": "replica-ok",
+        });
+        validate_json(WireType::BuildRequest, &request).unwrap();
+        let windows = json!([{"start_ms": 0, "end_ms": 1000}, {"start_ms": 5000}]);
+        request["cues"]["time_range"] = windows;
+        validate_json(WireType::BuildRequest, &request).unwrap();
+
+        let err = validate_json(WireType::Event, &json!({"kind": "Bad Kind", "payload": {}}))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("\"scope\" is a required property"), "{}", err);
+        assert!(err.contains("at /kind"), "{}", err);
+        let unknown_field = json!({"fact_key": "k", "value": 1, "x": 1});
+        assert!(validate_json(WireType::Fact, &unknown_field).is_err());
+        assert_eq!("build_request".parse::<WireType>(), Ok(WireType::BuildRequest));
+    }
+
+    /// Deserializer that records the fields or variants a type's `Deserialize` impl
+    /// asks for and then gives up, so the schema can be diffed against the types.
+    struct Shape(&'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for &mut Shape {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct or enum"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0 = fields;
+            Err(de::Error::custom("recorded"))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0 = variants;
+            Err(de::Error::custom("recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+            identifier ignored_any
+        }
+    }
+
+    /// Field names of a struct, or variant names of an enum, sorted.
+    fn names<T: DeserializeOwned>() -> Vec<String> {
+        let mut shape = Shape(&[]);
+        assert!(T::deserialize(&mut shape).is_err());
+        let mut names: Vec<String> = shape.0.iter().map(|name| name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn schema_properties_and_enums_match_the_types() {
+        let mut event = names::<Event>();
+        // Accepted by the FFI and HTTP layers in place of `ts`.
+        event.push("ts_ms".to_string());
+        event.sort();
+        let generated = [
+            ("/$defs/Scope/properties", names::<Scope>()),
+            ("/$defs/Role/enum", names::<Role>()),
+            ("/$defs/Purpose/enum", names::<Purpose>()),
+            ("/$defs/Budget/properties", names::<Budget>()),
+            ("/$defs/Event/properties", event),
+            ("/$defs/Validity/properties", names::<Validity>()),
+            ("/$defs/Fact/properties", names::<Fact>()),
+            ("/$defs/Fact/properties/status/enum", names::<FactStatus>()),
+            ("/$defs/Fact/properties/scope_level/enum", names::<ScopeLevel>()),
+            ("/$defs/Procedure/properties", names::<Procedure>()),
+            ("/$defs/TimeRange/properties", names::<TimeRange>()),
+            ("/$defs/Episode/properties", names::<Episode>()),
+            ("/$defs/Episode/properties/compression_level/enum", names::<CompressionLevel>()),
+            ("/$defs/EvidenceRef/properties", names::<EvidenceRef>()),
+            ("/$defs/WorkingState/properties", names::<WorkingState>()),
+            ("/$defs/KeyQuote/properties", names::<KeyQuote>()),
+            ("/$defs/ConversationTurn/properties", names::<ConversationTurn>()),
+            ("/$defs/ShortTerm/properties", names::<ShortTerm>()),
+            ("/$defs/LongTerm/properties", names::<LongTerm>()),
+            ("/$defs/InsightItem/properties", names::<InsightItem>()),
+            ("/$defs/InsightItem/properties/type/enum", names::<InsightType>()),
+            ("/$defs/InsightItem/properties/trigger/enum", names::<InsightTrigger>()),
+            ("/$defs/InsightItem/properties/validation_state/enum", names::<ValidationState>()),
+            ("/$defs/Insight/properties", names::<Insight>()),
+            ("/$defs/Citation/properties", names::<Citation>()),
+            ("/$defs/ExternalContext/properties", names::<ExternalContext>()),
+            ("/$defs/BudgetReport/properties", names::<BudgetReport>()),
+            ("/$defs/Meta/properties", names::<Meta>()),
+            ("/$defs/MemoryPacket/properties", names::<MemoryPacket>()),
+            ("/$defs/RecallPolicy/properties/lang_mode/enum", names::<LangMode>()),
+            ("/$defs/BuildRequest/properties/read_preference/enum", names::<ReadPreference>()),
+        ];
+
+        let schema: Value = serde_json::from_str(WIRE_SCHEMA).unwrap();
+        for (pointer, expected) in generated {
+            let mut committed: Vec<String> = match &schema.pointer(pointer) {
+                Some(Value::Object(properties)) => properties.keys().cloned().collect(),
+                Some(Value::Array(values)) => values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(str::to_string))
+                    .collect(),
+                _ => panic!("schema has nothing at {}", pointer),
+            };
+            committed.sort();
+            assert_eq!(committed, expected, "{} differs from the Rust type", pointer);
+        }
+    }
+}
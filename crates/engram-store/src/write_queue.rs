@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::{Event, Store, StoreError, StoreResult};
+
+#[derive(Debug, Clone)]
+pub struct WriteQueueOptions {
+    /// Events that may wait for the backend before new writes are refused.
+    pub capacity: usize,
+    /// Events the worker writes before taking the queue lock again.
+    pub batch_size: usize,
+}
+
+impl Default for WriteQueueOptions {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            batch_size: 64,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<Event>,
+    /// Events taken by the worker and not yet written.
+    in_flight: usize,
+    /// First write that failed since the last [`WriteQueue::flush`].
+    error: Option<StoreError>,
+    failed: usize,
+    closed: bool,
+}
+
+struct QueueShared {
+    store: Arc<dyn Store>,
+    options: WriteQueueOptions,
+    state: Mutex<QueueState>,
+    /// Signalled when events are queued or the queue closes.
+    queued: Condvar,
+    /// Signalled when the worker has taken or written events.
+    drained: Condvar,
+}
+
+impl QueueShared {
+    fn lock(&self) -> StoreResult<MutexGuard<'_, QueueState>> {
+        self.state.lock().map_err(|_| StoreError::Poisoned)
+    }
+}
+
+/// Bounded queue of event writes drained by a background worker, so an agent loop
+/// never waits on a saturated backend: once `capacity` events are waiting, new writes
+/// fail fast with [`StoreError::Overloaded`] and the caller decides whether to drop,
+/// sample or retry them.
+///
+/// Writes are acknowledged when queued, not when stored. A write the backend rejects
+/// is logged and reported by the next [`flush`](WriteQueue::flush); events of one
+/// queue are written in the order they were accepted.
+pub struct WriteQueue {
+    shared: Arc<QueueShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for WriteQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteQueue")
+            .field("capacity", &self.shared.options.capacity)
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
+
+impl WriteQueue {
+    pub fn new(store: Arc<dyn Store>, options: WriteQueueOptions) -> Self {
+        let shared = Arc::new(QueueShared {
+            store,
+            options: WriteQueueOptions {
+                capacity: options.capacity.max(1),
+                batch_size: options.batch_size.max(1),
+            },
+            state: Mutex::new(QueueState::default()),
+            queued: Condvar::new(),
+            drained: Condvar::new(),
+        });
+        let worker = {
+            let shared = shared.clone();
+            std::thread::spawn(move || run_worker(shared))
+        };
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queues the event, or fails with [`StoreError::Overloaded`] when the queue is
+    /// full. Never blocks on the backend.
+    pub fn try_append_event(&self, event: Event) -> StoreResult<()> {
+        self.append_event_within(event, Duration::ZERO)
+    }
+
+    /// Queues the event, waiting up to `timeout` for room before failing with
+    /// [`StoreError::Overloaded`].
+    pub fn append_event_within(&self, event: Event, timeout: Duration) -> StoreResult<()> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = self.shared.lock()?;
+        loop {
+            if state.closed {
+                return Err(StoreError::Storage("write queue is closed".to_string()));
+            }
+            if state.pending.len() < self.shared.options.capacity {
+                break;
+            }
+            let remaining = deadline.map_or(Duration::MAX, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return Err(StoreError::Overloaded(format!(
+                    "write queue is full ({} events pending)",
+                    state.pending.len()
+                )));
+            }
+            state = self
+                .shared
+                .drained
+                .wait_timeout(state, remaining)
+                .map_err(|_| StoreError::Poisoned)?
+                .0;
+        }
+        state.pending.push_back(event);
+        self.shared.queued.notify_one();
+        Ok(())
+    }
+
+    /// Events accepted but not yet written.
+    pub fn pending(&self) -> usize {
+        self.shared
+            .lock()
+            .map(|state| state.pending.len() + state.in_flight)
+            .unwrap_or_default()
+    }
+
+    /// Waits until every accepted event has been written. Fails with the first write
+    /// error since the previous flush, if any.
+    pub fn flush(&self) -> StoreResult<()> {
+        let mut state = self.shared.lock()?;
+        while !state.pending.is_empty() || state.in_flight > 0 {
+            state = self
+                .shared
+                .drained
+                .wait(state)
+                .map_err(|_| StoreError::Poisoned)?;
+        }
+        let failed = std::mem::take(&mut state.failed);
+        match state.error.take() {
+            Some(err) if failed > 1 => Err(StoreError::Storage(format!(
+                "{} queued writes failed, first: {}",
+                failed, err
+            ))),
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for WriteQueue {
+    /// Writes what is still queued before returning.
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.closed = true;
+        }
+        self.shared.queued.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker(shared: Arc<QueueShared>) {
+    loop {
+        let batch: Vec<Event> = {
+            let Ok(mut state) = shared.lock() else {
+                return;
+            };
+            while state.pending.is_empty() && !state.closed {
+                let Ok(next) = shared.queued.wait(state) else {
+                    return;
+                };
+                state = next;
+            }
+            if state.pending.is_empty() {
+                return;
+            }
+            let take = state.pending.len().min(shared.options.batch_size);
+            state.in_flight = take;
+            state.pending.drain(..take).collect()
+        };
+        shared.drained.notify_all();
+
+        let mut errors = Vec::new();
+        for event in batch {
+            if let Err(err) = shared.store.append_event(event) {
+                warn!("queued event write failed: {}", err);
+                errors.push(err);
+            }
+        }
+
+        let Ok(mut state) = shared.lock() else {
+            return;
+        };
+        state.in_flight = 0;
+        state.failed += errors.len();
+        if state.error.is_none() {
+            state.error = errors.into_iter().next();
+        }
+        drop(state);
+        shared.drained.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventKind, SqliteStore};
+    use engram_types::Scope;
+    use rusqlite::Connection;
+    use serde_json::json;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn sheds_writes_when_full_and_drains_on_flush() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("engram-write-queue-{}", nanos));
+        let path = dir.join("engram.db");
+        let store = Arc::new(
+            SqliteStore::new(&path)
+                .unwrap()
+                .with_statement_timeout(Duration::from_secs(30)),
+        );
+        let scope = Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        };
+        let event = |content: &str| Event::new(scope.clone(), EventKind::Message, json!(content));
+
+        // Another writer holds the database, so the worker stalls on the first event.
+        let writer = Connection::open(&path).unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let queue = WriteQueue::new(
+            store.clone(),
+            WriteQueueOptions {
+                capacity: 2,
+                batch_size: 1,
+            },
+        );
+        queue.try_append_event(event("one")).unwrap();
+        while queue.shared.lock().unwrap().in_flight == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        queue.try_append_event(event("two")).unwrap();
+        queue.try_append_event(event("three")).unwrap();
+        let shed = queue.try_append_event(event("four"));
+        assert!(matches!(shed, Err(StoreError::Overloaded(_))));
+        let waited = queue.append_event_within(event("four"), Duration::from_millis(20));
+        assert!(matches!(waited, Err(StoreError::Overloaded(_))));
+        assert_eq!(queue.pending(), 3);
+
+        writer.execute_batch("ROLLBACK").unwrap();
+        queue.flush().unwrap();
+        assert_eq!(queue.pending(), 0);
+        let stored = store.get_events_since(&scope, 0, None).unwrap();
+        let contents: Vec<&str> = stored
+            .iter()
+            .filter_map(|event| event.payload.as_str())
+            .collect();
+        assert_eq!(contents, vec!["one", "two", "three"]);
+        drop(queue);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
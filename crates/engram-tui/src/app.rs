@@ -0,0 +1,243 @@
+use engram_store::{build_memory_packet, BuildRequest, Event, Store, StoreResult};
+use engram_types::{MemoryPacket, Purpose, Scope, WorkingState};
+
+use crate::diff::{diff_lines, DiffLine};
+
+/// Events kept in the live tail; older ones scroll off.
+const EVENT_TAIL: usize = 500;
+/// Working state versions kept for diffing.
+const STATE_HISTORY: usize = 16;
+const MAX_SCOPES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Events,
+    WorkingState,
+    Packet,
+}
+
+impl View {
+    pub const ALL: [View; 3] = [View::Events, View::WorkingState, View::Packet];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Events => "Events",
+            View::WorkingState => "Working state",
+            View::Packet => "Packet preview",
+        }
+    }
+}
+
+pub struct App {
+    store: Box<dyn Store>,
+    tenant_id: Option<String>,
+    pub scopes: Vec<Scope>,
+    pub selected: usize,
+    pub view: View,
+    pub events: Vec<Event>,
+    last_seq: u64,
+    pub states: Vec<WorkingState>,
+    pub purpose: Purpose,
+    pub packet: Option<MemoryPacket>,
+    pub scroll: u16,
+    pub status: String,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new(store: Box<dyn Store>, tenant_id: Option<String>) -> StoreResult<Self> {
+        let mut app = Self {
+            store,
+            tenant_id,
+            scopes: Vec::new(),
+            selected: 0,
+            view: View::Events,
+            events: Vec::new(),
+            last_seq: 0,
+            states: Vec::new(),
+            purpose: Purpose::Planner,
+            packet: None,
+            scroll: 0,
+            status: String::new(),
+            should_quit: false,
+        };
+        app.reload_scopes()?;
+        Ok(app)
+    }
+
+    pub fn scope(&self) -> Option<&Scope> {
+        self.scopes.get(self.selected)
+    }
+
+    /// Re-reads the scope list, keeping the current selection when it still exists.
+    pub fn reload_scopes(&mut self) -> StoreResult<()> {
+        let current = self.scope().cloned();
+        self.scopes = self
+            .store
+            .list_scopes(self.tenant_id.as_deref(), Some(MAX_SCOPES))?;
+        let position = current.and_then(|current| {
+            self.scopes
+                .iter()
+                .position(|scope| scope_key(scope) == scope_key(&current))
+        });
+        match position {
+            Some(position) => self.selected = position,
+            None => self.select_index(0),
+        }
+        self.status = format!("{} scopes", self.scopes.len());
+        self.refresh()
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.scopes.is_empty() {
+            return;
+        }
+        let last = self.scopes.len() as isize - 1;
+        let next = (self.selected as isize + delta).clamp(0, last) as usize;
+        if next != self.selected {
+            self.select_index(next);
+        }
+    }
+
+    fn select_index(&mut self, index: usize) {
+        self.selected = index;
+        self.events.clear();
+        self.last_seq = 0;
+        self.states.clear();
+        self.packet = None;
+        self.scroll = 0;
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.scroll = 0;
+    }
+
+    pub fn next_view(&mut self) {
+        let index = View::ALL.iter().position(|view| *view == self.view).unwrap_or(0);
+        self.set_view(View::ALL[(index + 1) % View::ALL.len()]);
+    }
+
+    pub fn cycle_purpose(&mut self) {
+        self.purpose = match self.purpose {
+            Purpose::Planner => Purpose::Tool,
+            Purpose::Tool => Purpose::Responder,
+            Purpose::Responder => Purpose::Planner,
+        };
+        self.packet = None;
+    }
+
+    /// Pulls new events and the latest working state of the selected scope. Called on
+    /// every tick, so it only reads what changed since the last call.
+    pub fn refresh(&mut self) -> StoreResult<()> {
+        let Some(scope) = self.scope().cloned() else {
+            return Ok(());
+        };
+
+        let events = self.store.get_events_since(&scope, self.last_seq, None)?;
+        if let Some(last) = events.last() {
+            self.last_seq = last.seq;
+        }
+        self.events.extend(events);
+        if self.events.len() > EVENT_TAIL {
+            self.events.drain(..self.events.len() - EVENT_TAIL);
+        }
+
+        if let Some(state) = self.store.get_working_state(&scope)?
+            && self.states.last().is_none_or(|last| last.state_version != state.state_version)
+        {
+            self.states.push(state);
+            if self.states.len() > STATE_HISTORY {
+                self.states.remove(0);
+            }
+        }
+
+        if self.view == View::Packet && self.packet.is_none() {
+            self.build_packet()?;
+        }
+        Ok(())
+    }
+
+    /// Builds a packet for the selected scope without persisting it.
+    pub fn build_packet(&mut self) -> StoreResult<()> {
+        let Some(scope) = self.scope().cloned() else {
+            return Ok(());
+        };
+        let mut request = BuildRequest::new(scope, self.purpose.clone());
+        request.persist = false;
+        self.packet = Some(build_memory_packet(self.store.as_ref(), request)?);
+        Ok(())
+    }
+
+    /// Diff between the two most recent working state versions seen for the scope,
+    /// or the whole state when only one version has been seen.
+    pub fn state_diff(&self) -> Vec<DiffLine> {
+        let render = |state: &WorkingState| {
+            serde_json::to_string_pretty(state).unwrap_or_default()
+        };
+        match self.states.as_slice() {
+            [] => Vec::new(),
+            [only] => diff_lines("", &render(only)),
+            [.., previous, current] => diff_lines(&render(previous), &render(current)),
+        }
+    }
+}
+
+pub fn scope_key(scope: &Scope) -> String {
+    format!(
+        "{}/{}/{}/{}/{}",
+        scope.tenant_id, scope.user_id, scope.agent_id, scope.session_id, scope.run_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engram_store::{EventKind, InMemoryStore, WorkingStatePatch};
+    use serde_json::json;
+
+    fn sample_scope() -> Scope {
+        Scope {
+            tenant_id: "default".to_string(),
+            user_id: "user1".to_string(),
+            agent_id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            run_id: "run1".to_string(),
+        }
+    }
+
+    #[test]
+    fn tails_events_and_diffs_working_state() {
+        let store = InMemoryStore::new();
+        let scope = sample_scope();
+        let message = |content: &str| {
+            Event::new(scope.clone(), EventKind::Message, json!({ "content": content }))
+        };
+        let set_goal = |goal: &str| WorkingStatePatch {
+            goal: Some(goal.to_string()),
+            ..WorkingStatePatch::default()
+        };
+        store.append_event(message("first")).unwrap();
+        store.patch_working_state(&scope, set_goal("draft")).unwrap();
+
+        let store = std::sync::Arc::new(store);
+        let mut app = App::new(Box::new(store.clone()), None).unwrap();
+        assert_eq!(app.scopes.len(), 1);
+        assert_eq!(app.events.len(), 1);
+
+        store.append_event(message("second")).unwrap();
+        store.patch_working_state(&scope, set_goal("ship")).unwrap();
+        app.refresh().unwrap();
+        assert_eq!(app.events.len(), 2);
+        assert_eq!(app.events[1].payload["content"], "second");
+
+        let diff = app.state_diff();
+        assert!(diff.contains(&DiffLine::Removed("  \"goal\": \"draft\",".to_string())));
+        assert!(diff.contains(&DiffLine::Added("  \"goal\": \"ship\",".to_string())));
+
+        app.set_view(View::Packet);
+        app.refresh().unwrap();
+        assert!(app.packet.is_some());
+        assert!(store.list_context_builds(&scope, None).unwrap().is_empty());
+    }
+}
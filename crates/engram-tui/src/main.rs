@@ -0,0 +1,148 @@
+//! Interactive inspector for an engram store: browse scopes, tail events as they
+//! arrive, watch working state change and preview packet builds.
+//!
+//! ```text
+//! engram-tui [--tenant ID] [TARGET]
+//! ```
+//!
+//! `TARGET` is a SQLite path (default `data/engram.db`) or a `postgres://` /
+//! `mysql://` DSN when the matching feature is enabled.
+
+mod app;
+mod diff;
+mod ui;
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use engram_store::{SqliteStore, Store, StoreError, StoreResult};
+#[cfg(feature = "mysql")]
+use engram_store::MySqlStore;
+#[cfg(feature = "postgres")]
+use engram_store::PostgresStore;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::DefaultTerminal;
+
+use crate::app::{App, View};
+
+const TICK: Duration = Duration::from_millis(500);
+const USAGE: &str = "usage: engram-tui [--tenant ID] [SQLITE_PATH | postgres://... | mysql://...]";
+
+fn main() -> ExitCode {
+    let mut tenant_id = None;
+    let mut target = "data/engram.db".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tenant" => tenant_id = args.next(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => target = arg,
+        }
+    }
+
+    let app = open_store(&target).and_then(|store| App::new(store, tenant_id));
+    let mut app = match app {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("engram-tui: cannot open {}: {}", target, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    match ratatui::run(|terminal| run(terminal, &mut app)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("engram-tui: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn open_store(target: &str) -> StoreResult<Box<dyn Store>> {
+    if target.starts_with("postgres://") || target.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Box::new(PostgresStore::new(target)?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(StoreError::InvalidInput("postgres feature not enabled".to_string()));
+    }
+    if target.starts_with("mysql://") {
+        #[cfg(feature = "mysql")]
+        return Ok(Box::new(MySqlStore::new(target)?));
+        #[cfg(not(feature = "mysql"))]
+        return Err(StoreError::InvalidInput("mysql feature not enabled".to_string()));
+    }
+    let path = target.strip_prefix("sqlite://").unwrap_or(target);
+    if !std::path::Path::new(path).exists() {
+        return Err(StoreError::InvalidInput(format!("{} does not exist", path)));
+    }
+    Ok(Box::new(SqliteStore::new(path)?))
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if event::poll(TICK)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            handle_key(app, key.code);
+        }
+        if let Err(err) = app.refresh() {
+            app.status = format!("refresh failed: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle_key(app: &mut App, code: KeyCode) {
+    let result = match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.should_quit = true;
+            Ok(())
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.move_selection(-1);
+            Ok(())
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.move_selection(1);
+            Ok(())
+        }
+        KeyCode::Tab => {
+            app.next_view();
+            Ok(())
+        }
+        KeyCode::Char('1') => {
+            app.set_view(View::Events);
+            Ok(())
+        }
+        KeyCode::Char('2') => {
+            app.set_view(View::WorkingState);
+            Ok(())
+        }
+        KeyCode::Char('3') => {
+            app.set_view(View::Packet);
+            Ok(())
+        }
+        KeyCode::Char('r') => app.reload_scopes(),
+        KeyCode::Char('p') => {
+            app.cycle_purpose();
+            Ok(())
+        }
+        KeyCode::Char('b') => app.build_packet(),
+        KeyCode::PageDown => {
+            app.scroll = app.scroll.saturating_add(10);
+            Ok(())
+        }
+        KeyCode::PageUp => {
+            app.scroll = app.scroll.saturating_sub(10);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    if let Err(err) = result {
+        app.status = format!("error: {}", err);
+    }
+}
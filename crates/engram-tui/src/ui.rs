@@ -0,0 +1,135 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs, Wrap};
+use ratatui::Frame;
+
+use crate::app::{scope_key, App, View};
+use crate::diff::DiffLine;
+
+const HELP: &str =
+    "q quit · ↑/↓ scope · tab/1-3 view · r reload · p purpose · b rebuild · PgUp/PgDn scroll";
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [tabs, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [scopes, detail] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(body);
+
+    let selected = View::ALL.iter().position(|view| *view == app.view);
+    frame.render_widget(
+        Tabs::new(View::ALL.iter().map(|view| view.title()))
+            .select(selected)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)),
+        tabs,
+    );
+    draw_scopes(frame, app, scopes);
+
+    let lines = match app.view {
+        View::Events => event_lines(app),
+        View::WorkingState => state_lines(app),
+        View::Packet => packet_lines(app),
+    };
+    let title = match app.view {
+        View::Packet => format!("{} ({:?})", app.view.title(), app.purpose),
+        view => view.title().to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::bordered().title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((app.scroll, 0)),
+        detail,
+    );
+
+    let status = if app.status.is_empty() {
+        HELP.to_string()
+    } else {
+        format!("{} │ {}", app.status, HELP)
+    };
+    frame.render_widget(Paragraph::new(status).style(Style::default().fg(Color::DarkGray)), footer);
+}
+
+fn draw_scopes(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .scopes
+        .iter()
+        .map(|scope| ListItem::new(scope_key(scope)))
+        .collect();
+    let mut state = ListState::default().with_selected(app.scope().map(|_| app.selected));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title("Scopes"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        area,
+        &mut state,
+    );
+}
+
+fn event_lines(app: &App) -> Vec<Line<'static>> {
+    // Newest first, so the live tail stays in view without scrolling.
+    app.events
+        .iter()
+        .rev()
+        .map(|event| {
+            Line::from(vec![
+                Span::styled(format!("{:>5} ", event.seq), Style::default().fg(Color::DarkGray)),
+                Span::raw(format!("{} ", event.ts.format("%H:%M:%S"))),
+                Span::styled(
+                    format!("{:<12} ", event.kind.to_string()),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(event.payload.to_string()),
+            ])
+        })
+        .collect()
+}
+
+fn state_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(current) = app.states.last() else {
+        return vec![Line::from("no working state")];
+    };
+    let mut lines = vec![Line::from(format!(
+        "state_version {} · {} versions seen",
+        current.state_version,
+        app.states.len()
+    ))];
+    lines.extend(app.state_diff().into_iter().map(|line| match line {
+        DiffLine::Same(text) => Line::from(format!("  {}", text)),
+        DiffLine::Added(text) => {
+            Line::styled(format!("+ {}", text), Style::default().fg(Color::Green))
+        }
+        DiffLine::Removed(text) => {
+            Line::styled(format!("- {}", text), Style::default().fg(Color::Red))
+        }
+    }));
+    lines
+}
+
+fn packet_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(packet) = &app.packet else {
+        return vec![Line::from("no packet built yet")];
+    };
+    let report = &packet.budget_report;
+    let mut lines = vec![
+        Line::styled(
+            format!(
+                "{} / {} tokens · {} facts · {} episodes · {} omissions",
+                report.used_tokens_est,
+                report.max_tokens,
+                packet.long_term.facts.len(),
+                packet.long_term.episodes.len(),
+                report.omissions.len()
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Line::from(""),
+    ];
+    let json = serde_json::to_string_pretty(packet).unwrap_or_default();
+    lines.extend(json.lines().map(|line| Line::from(line.to_string())));
+    lines
+}
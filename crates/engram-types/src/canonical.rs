@@ -0,0 +1,123 @@
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{Episode, Fact, Procedure};
+
+/// Integers a JSON number can hold exactly as an `f64`.
+const MAX_EXACT_FLOAT_INT: f64 = 9_007_199_254_740_992.0;
+
+/// Serializes `value` in canonical form: object keys sorted, no whitespace, and
+/// floats without a fractional part written as integers (`1.0` as `1`), so the
+/// same data gives the same text whichever language or map type produced it.
+pub fn canonical_json(value: &Value) -> String {
+    canonicalize(value).to_string()
+}
+
+/// Hex SHA-256 of [`canonical_json`]`(value)`.
+pub fn canonical_hash(value: &Value) -> String {
+    let digest = Sha256::digest(canonical_json(value).as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let map: Map<String, Value> = entries
+                .into_iter()
+                .map(|(key, value)| (key.clone(), canonicalize(value)))
+                .collect();
+            Value::Object(map)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        Value::Number(number) => match number.as_f64() {
+            Some(float)
+                if !number.is_i64()
+                    && !number.is_u64()
+                    && float.fract() == 0.0
+                    && float.abs() <= MAX_EXACT_FLOAT_INT =>
+            {
+                Value::Number(Number::from(float as i64))
+            }
+            _ => value.clone(),
+        },
+        _ => value.clone(),
+    }
+}
+
+/// Stable identity of a memory item's content, shared by every subsystem that
+/// compares items: dedupe, sync, signing and cache keys. Ids and values computed at
+/// recall time are left out, so two writes of the same content hash alike.
+pub trait ContentHash: Serialize {
+    /// Fields of the item left out of its content.
+    const EXCLUDED_FIELDS: &'static [&'static str];
+
+    /// The item as JSON, without [`EXCLUDED_FIELDS`](Self::EXCLUDED_FIELDS).
+    fn content(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        if let Value::Object(map) = &mut value {
+            for field in Self::EXCLUDED_FIELDS {
+                map.remove(*field);
+            }
+        }
+        value
+    }
+
+    fn canonical_json(&self) -> String {
+        canonical_json(&self.content())
+    }
+
+    /// Hex SHA-256 of the item's [`canonical_json`](Self::canonical_json).
+    fn content_hash(&self) -> String {
+        canonical_hash(&self.content())
+    }
+}
+
+impl ContentHash for Fact {
+    const EXCLUDED_FIELDS: &'static [&'static str] = &["fact_id"];
+}
+
+impl ContentHash for Episode {
+    const EXCLUDED_FIELDS: &'static [&'static str] = &["episode_id", "recency_score"];
+}
+
+impl ContentHash for Procedure {
+    const EXCLUDED_FIELDS: &'static [&'static str] = &["procedure_id"];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn content_hashes_ignore_ids_key_order_and_integral_floats() {
+        assert_eq!(
+            canonical_json(&json!({"b": [1.0, 2.5], "a": {"d": null, "c": true}})),
+            r#"{"a":{"c":true,"d":null},"b":[1,2.5]}"#
+        );
+
+        let mut fact = Fact::new("user.city", json!({"name": "Lisbon", "country": "PT"}));
+        let mut copy: Fact = serde_json::from_value(json!({
+            "fact_key": "user.city",
+            "value": {"country": "PT", "name": "Lisbon"},
+            "confidence": 1,
+        }))
+        .unwrap();
+        fact.confidence = 1.0;
+        assert_ne!(fact.fact_id, copy.fact_id);
+        assert_eq!(fact.content_hash(), copy.content_hash());
+        assert_eq!(fact.content_hash().len(), 64);
+
+        copy.value["name"] = json!("Porto");
+        assert_ne!(fact.content_hash(), copy.content_hash());
+
+        let mut episode = Episode::new("Moved to Lisbon");
+        let hash = episode.content_hash();
+        episode.recency_score = Some(0.7);
+        assert_eq!(episode.content_hash(), hash);
+        assert!(!episode.canonical_json().contains("episode_id"));
+    }
+}
@@ -1,6 +1,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use ulid::{Generator, Ulid};
+
+mod canonical;
+mod schema;
+
+pub use canonical::{canonical_hash, canonical_json, ContentHash};
+pub use schema::{json_schema, WireType, WIRE_SCHEMA};
 
 pub type JsonMap = BTreeMap<String, serde_json::Value>;
 
@@ -26,6 +35,7 @@ pub struct LongTerm {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fact {
+    #[serde(default = "new_ulid")]
     pub fact_id: String,
     pub fact_key: String,
     pub value: serde_json::Value,
@@ -41,6 +51,12 @@ pub struct Fact {
     pub scope_level: ScopeLevel,
     #[serde(default)]
     pub notes: String,
+    /// Pinned facts go into every packet, ahead of ranking and budget trimming.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Language tag of the fact's text (`en`, `pt-BR`, ...); `None` if unknown.
+    #[serde(default)]
+    pub lang: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +68,9 @@ pub struct MemoryPacket {
     pub insight: Insight,
     #[serde(default)]
     pub citations: Vec<Citation>,
+    /// Context from outside memory (retrieved documents, tickets), budgeted with it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_context: Vec<ExternalContext>,
     #[serde(default)]
     pub budget_report: BudgetReport,
     #[serde(default)]
@@ -73,6 +92,12 @@ pub struct Meta {
     pub budget: Budget,
     #[serde(default)]
     pub policy_id: String,
+    /// UTC offset of the user, e.g. `+02:00`, when they set a timezone preference.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Locale tag of the user, e.g. `de-DE`, when they set one.
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +123,9 @@ pub struct Budget {
     pub max_tokens: u32,
     #[serde(default)]
     pub per_section: JsonMap,
+    /// Model whose tokenizer counts the budget; a 4 chars per token estimate if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -114,6 +142,9 @@ pub struct ShortTerm {
     pub open_loops: Vec<String>,
     #[serde(default)]
     pub last_tool_evidence: Vec<EvidenceRef>,
+    /// Daily then weekly summaries of the scope's recent episodes, newest first.
+    #[serde(default)]
+    pub period_summaries: Vec<Episode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -134,6 +165,271 @@ pub struct WorkingState {
     pub risks: Vec<String>,
     #[serde(default)]
     pub state_version: u32,
+    #[serde(default, skip_serializing_if = "WorkingStateClock::is_empty")]
+    pub clock: WorkingStateClock,
+}
+
+/// Merge metadata for [`WorkingState`]. `goal`, `slots`, `constraints` and
+/// `tool_evidence` are last-writer-wins registers stamped with their write time;
+/// `plan`, `decisions` and `risks` are observed-remove sets.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct WorkingStateClock {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slots: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_evidence: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "OrSet::is_empty")]
+    pub plan: OrSet,
+    #[serde(default, skip_serializing_if = "OrSet::is_empty")]
+    pub decisions: OrSet,
+    #[serde(default, skip_serializing_if = "OrSet::is_empty")]
+    pub risks: OrSet,
+}
+
+impl WorkingStateClock {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Tombstones an [`OrSet`] keeps before it forgets the oldest along with their adds.
+pub const MAX_OR_SET_TOMBSTONES: usize = 256;
+
+/// Observed-remove set: every add carries a unique tag and a remove tombstones only
+/// the tags it has seen, so a concurrent re-add survives the merge.
+///
+/// Past [`MAX_OR_SET_TOMBSTONES`] the oldest tombstoned tags are dropped with their
+/// adds, and `compacted` records the newest tag dropped. A replica then treats an add
+/// it no longer holds at or below that mark as removed, so a stale replica cannot
+/// bring the item back; an add that reaches it only after it compacted past the add
+/// is lost the same way. Tombstones of items tagged before the state carried a clock
+/// are kept: their tags say nothing about when the item was added, so a replica still
+/// in the old format must keep finding them.
+///
+/// `order` is the last order written that adding alone would not produce, e.g. a
+/// reordered plan, stamped with a ULID in `order_tag`; the later stamp wins a merge.
+/// Live items follow it, and items it does not name come after in add order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct OrSet {
+    #[serde(default)]
+    pub adds: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    pub removes: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compacted: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_tag: Option<String>,
+}
+
+impl OrSet {
+    pub fn is_empty(&self) -> bool {
+        self.adds.is_empty() && self.removes.is_empty() && self.order_tag.is_none()
+    }
+
+    /// Records the transition from `before` to `after` as adds, removes and, when
+    /// `after` is not in add order, a new order.
+    pub fn update(&mut self, before: &[String], after: &[String]) {
+        self.observe(before);
+        for item in after {
+            if !self.contains(item) {
+                self.adds.entry(item.clone()).or_default().insert(new_ulid());
+            }
+        }
+        for item in before {
+            if !after.contains(item)
+                && let Some(tags) = self.adds.get(item)
+            {
+                self.removes.extend(tags.iter().cloned());
+            }
+        }
+        let mut wanted: Vec<String> = Vec::with_capacity(after.len());
+        for item in after {
+            if !wanted.contains(item) {
+                wanted.push(item.clone());
+            }
+        }
+        if self.items() != wanted {
+            self.order = wanted;
+            self.order_tag = Some(new_ulid());
+        }
+        self.compact();
+    }
+
+    pub fn merge(&self, other: &OrSet) -> OrSet {
+        let mut merged = OrSet {
+            removes: self.removes.union(&other.removes).cloned().collect(),
+            compacted: self.compacted.clone().max(other.compacted.clone()),
+            ..OrSet::default()
+        };
+        for (side, peer) in [(self, other), (other, self)] {
+            for (item, tags) in &side.adds {
+                for tag in tags.iter().filter(|tag| !peer.forgot(tag)) {
+                    merged.adds.entry(item.clone()).or_default().insert(tag.clone());
+                }
+            }
+        }
+        let latest = if other.order_tag > self.order_tag { other } else { self };
+        merged.order = latest.order.clone();
+        merged.order_tag = latest.order_tag.clone();
+        merged.compact();
+        merged
+    }
+
+    fn contains(&self, item: &str) -> bool {
+        self.adds
+            .get(item)
+            .is_some_and(|tags| tags.iter().any(|tag| !self.removes.contains(tag)))
+    }
+
+    /// Whether `tag` was dropped here by [`OrSet::compact`]: it is at or below the
+    /// mark but no longer among the adds. Legacy tags are never dropped.
+    fn forgot(&self, tag: &str) -> bool {
+        !is_legacy_tag(tag)
+            && self.compacted.as_deref().is_some_and(|mark| tag <= mark)
+            && !self.adds.values().any(|tags| tags.contains(tag))
+    }
+
+    /// Drops the oldest tombstones past [`MAX_OR_SET_TOMBSTONES`] and the adds they
+    /// cancel. Tags are ULIDs, so the smallest are the oldest; legacy tombstones stay.
+    fn compact(&mut self) {
+        let excess = self.removes.len().saturating_sub(MAX_OR_SET_TOMBSTONES);
+        if excess == 0 {
+            return;
+        }
+        let dropped: BTreeSet<String> = self
+            .removes
+            .iter()
+            .filter(|tag| !is_legacy_tag(tag))
+            .take(excess)
+            .cloned()
+            .collect();
+        self.removes.retain(|tag| !dropped.contains(tag));
+        self.compacted = self.compacted.take().max(dropped.last().cloned());
+        self.adds.retain(|_, tags| {
+            tags.retain(|tag| !dropped.contains(tag));
+            !tags.is_empty()
+        });
+    }
+
+    /// Items written before the state carried a clock get a synthetic tag derived
+    /// from the item itself, so every replica assigns the same one. `!` sorts before
+    /// any ULID, keeping those items ahead of later adds.
+    fn observe(&mut self, items: &[String]) {
+        for item in items {
+            if !self.adds.contains_key(item) {
+                self.adds
+                    .entry(item.clone())
+                    .or_default()
+                    .insert(format!("!{}", item));
+            }
+        }
+    }
+
+    /// Live items in `order`, then the rest ordered by their earliest surviving add.
+    fn items(&self) -> Vec<String> {
+        let mut live: Vec<(&String, &String)> = self
+            .adds
+            .iter()
+            .filter_map(|(item, tags)| {
+                tags.iter()
+                    .find(|tag| !self.removes.contains(*tag))
+                    .map(|tag| (tag, item))
+            })
+            .collect();
+        live.sort();
+        let mut items: Vec<String> = Vec::with_capacity(live.len());
+        for item in &self.order {
+            if self.contains(item) && !items.contains(item) {
+                items.push(item.clone());
+            }
+        }
+        for (_, item) in live {
+            if !items.contains(item) {
+                items.push(item.clone());
+            }
+        }
+        items
+    }
+}
+
+/// Tag [`OrSet::observe`] gave an item written before the state carried a clock.
+fn is_legacy_tag(tag: &str) -> bool {
+    tag.starts_with('!')
+}
+
+impl WorkingState {
+    /// Merges two replicas of the same working state. The result does not depend
+    /// on argument order, so replicas that exchange states converge.
+    pub fn merge(&self, other: &WorkingState) -> WorkingState {
+        let mut left = self.clone();
+        let mut right = other.clone();
+        left.observe_sets();
+        right.observe_sets();
+
+        let (goal, goal_ts) = lww((&left.goal, left.clock.goal), (&right.goal, right.clock.goal));
+        let (slots, slots_ts) =
+            lww((&left.slots, left.clock.slots), (&right.slots, right.clock.slots));
+        let (constraints, constraints_ts) = lww(
+            (&left.constraints, left.clock.constraints),
+            (&right.constraints, right.clock.constraints),
+        );
+        let (tool_evidence, tool_evidence_ts) = lww(
+            (&left.tool_evidence, left.clock.tool_evidence),
+            (&right.tool_evidence, right.clock.tool_evidence),
+        );
+        let plan = left.clock.plan.merge(&right.clock.plan);
+        let decisions = left.clock.decisions.merge(&right.clock.decisions);
+        let risks = left.clock.risks.merge(&right.clock.risks);
+
+        WorkingState {
+            goal,
+            plan: plan.items(),
+            slots,
+            constraints,
+            tool_evidence,
+            decisions: decisions.items(),
+            risks: risks.items(),
+            state_version: left.state_version.max(right.state_version),
+            clock: WorkingStateClock {
+                goal: goal_ts,
+                slots: slots_ts,
+                constraints: constraints_ts,
+                tool_evidence: tool_evidence_ts,
+                plan,
+                decisions,
+                risks,
+            },
+        }
+    }
+
+    fn observe_sets(&mut self) {
+        self.clock.plan.observe(&self.plan);
+        self.clock.decisions.observe(&self.decisions);
+        self.clock.risks.observe(&self.risks);
+    }
+}
+
+/// Later stamp wins; equal stamps fall back to comparing the serialized values so
+/// the choice is the same on every replica.
+fn lww<T: Clone + Serialize>(
+    left: (&T, Option<DateTime<Utc>>),
+    right: (&T, Option<DateTime<Utc>>),
+) -> (T, Option<DateTime<Utc>>) {
+    let ordering = left.1.cmp(&right.1).then_with(|| {
+        let left_json = serde_json::to_string(left.0).unwrap_or_default();
+        let right_json = serde_json::to_string(right.0).unwrap_or_default();
+        left_json.cmp(&right_json)
+    });
+    match ordering {
+        Ordering::Less => (right.0.clone(), right.1),
+        _ => (left.0.clone(), left.1),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -183,6 +479,7 @@ pub enum ScopeLevel {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Procedure {
+    #[serde(default = "new_ulid")]
     pub procedure_id: String,
     pub task_type: String,
     pub content: serde_json::Value,
@@ -194,8 +491,37 @@ pub struct Procedure {
     pub applicability: JsonMap,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcedureCandidate {
+    pub candidate_id: String,
+    pub procedure: Procedure,
+    #[serde(default)]
+    pub source_episodes: Vec<String>,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default = "default_candidate_status")]
+    pub status: CandidateStatus,
+    #[serde(default = "now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub reviewed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub reviewer: String,
+    #[serde(default)]
+    pub review_note: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Episode {
+    #[serde(default = "new_ulid")]
     pub episode_id: String,
     pub time_range: TimeRange,
     pub summary: String,
@@ -211,6 +537,9 @@ pub struct Episode {
     pub compression_level: CompressionLevel,
     #[serde(default)]
     pub recency_score: Option<f64>,
+    /// Language tag of the episode's summary; `None` if unknown.
+    #[serde(default)]
+    pub lang: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -238,7 +567,7 @@ pub struct EvidenceRef {
     pub kind: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Insight {
     #[serde(default)]
     pub usage_policy: UsagePolicy,
@@ -250,33 +579,15 @@ pub struct Insight {
     pub patterns: Vec<InsightItem>,
 }
 
-impl Default for Insight {
-    fn default() -> Self {
-        Self {
-            usage_policy: UsagePolicy::default(),
-            hypotheses: Vec::new(),
-            strategy_sketches: Vec::new(),
-            patterns: Vec::new(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct UsagePolicy {
     #[serde(default)]
     pub allow_in_responder: bool,
 }
 
-impl Default for UsagePolicy {
-    fn default() -> Self {
-        Self {
-            allow_in_responder: false,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsightItem {
+    #[serde(default = "new_ulid")]
     pub id: String,
     #[serde(rename = "type")]
     pub kind: InsightType,
@@ -293,6 +604,9 @@ pub struct InsightItem {
     pub expires_at: String,
     #[serde(default)]
     pub sources: Vec<String>,
+    /// The insight this one was carried forward from, usually in an earlier run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_insight_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -338,6 +652,29 @@ pub enum CitationType {
     Message,
     ToolResult,
     StatePatch,
+    /// An [`ExternalContext`] item, cited by its id.
+    External,
+    /// Evidence from an application-defined event kind, serialized as the bare kind name.
+    #[serde(untagged)]
+    Custom(String),
+}
+
+/// A document, ticket or other non-memory item that an external context provider
+/// fetched for a packet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalContext {
+    pub id: String,
+    /// Name of the provider the item came from.
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub title: String,
+    pub content: String,
+    /// Relevance the provider gave the item, when it ranks its results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub metadata: JsonMap,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -352,6 +689,100 @@ pub struct BudgetReport {
     pub degradations: Vec<serde_json::Value>,
     #[serde(default)]
     pub omissions: Vec<serde_json::Value>,
+    /// Pinned facts are budgeted before everything else and never trimmed.
+    #[serde(default)]
+    pub pinned_fact_ids: Vec<String>,
+    #[serde(default)]
+    pub pinned_tokens_est: u32,
+    /// Tenant-pinned procedures lead the procedures section and are budgeted apart
+    /// from the task's own.
+    #[serde(default)]
+    pub pinned_procedure_ids: Vec<String>,
+    #[serde(default)]
+    pub pinned_procedure_tokens_est: u32,
+}
+
+impl Fact {
+    pub fn new(fact_key: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            fact_id: new_ulid(),
+            fact_key: fact_key.into(),
+            value,
+            status: default_fact_status(),
+            validity: Validity::default(),
+            confidence: default_confidence(),
+            sources: Vec::new(),
+            scope_level: default_scope_level(),
+            notes: String::new(),
+            pinned: false,
+            lang: None,
+        }
+    }
+}
+
+impl Procedure {
+    pub fn new(task_type: impl Into<String>, content: serde_json::Value) -> Self {
+        Self {
+            procedure_id: new_ulid(),
+            task_type: task_type.into(),
+            content,
+            priority: 0,
+            sources: Vec::new(),
+            applicability: JsonMap::new(),
+        }
+    }
+}
+
+impl Episode {
+    pub fn new(summary: impl Into<String>) -> Self {
+        Self {
+            episode_id: new_ulid(),
+            time_range: TimeRange {
+                start: Utc::now(),
+                end: None,
+            },
+            summary: summary.into(),
+            highlights: Vec::new(),
+            tags: Vec::new(),
+            entities: Vec::new(),
+            sources: Vec::new(),
+            compression_level: default_compression_level(),
+            recency_score: None,
+            lang: None,
+        }
+    }
+}
+
+impl InsightItem {
+    pub fn new(kind: InsightType, statement: impl Into<String>) -> Self {
+        Self {
+            id: new_ulid(),
+            kind,
+            statement: statement.into(),
+            trigger: default_trigger(),
+            confidence: default_insight_confidence(),
+            validation_state: default_validation_state(),
+            tests_suggested: Vec::new(),
+            expires_at: String::new(),
+            sources: Vec::new(),
+            parent_insight_id: None,
+        }
+    }
+}
+
+static ULID_GENERATOR: Mutex<Generator> = Mutex::new(Generator::new());
+
+/// Returns a new ULID. Ids sort by creation time and are strictly increasing within
+/// this process, even when several are generated in the same millisecond.
+pub fn new_ulid() -> String {
+    let now = std::time::SystemTime::now();
+    let mut generator = ULID_GENERATOR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    generator
+        .generate_from_datetime(now)
+        .unwrap_or_else(|_| Ulid::from_datetime(now))
+        .to_string()
 }
 
 fn now() -> DateTime<Utc> {
@@ -382,6 +813,10 @@ fn default_confidence() -> f64 {
     0.5
 }
 
+fn default_candidate_status() -> CandidateStatus {
+    CandidateStatus::Pending
+}
+
 fn default_compression_level() -> CompressionLevel {
     CompressionLevel::Raw
 }
@@ -421,13 +856,17 @@ mod tests {
                 budget: Budget {
                     max_tokens: 2048,
                     per_section: JsonMap::new(),
+                    model: None,
                 },
                 policy_id: "default".to_string(),
+                timezone: None,
+                locale: None,
             },
             short_term: ShortTerm::default(),
             long_term: LongTerm::default(),
             insight: Insight::default(),
             citations: Vec::new(),
+            external_context: Vec::new(),
             budget_report: BudgetReport::default(),
             explain: JsonMap::new(),
         };
@@ -438,4 +877,158 @@ mod tests {
         assert_eq!(back.meta.schema_version, "v1");
         assert!(!back.insight.usage_policy.allow_in_responder);
     }
+
+    #[test]
+    fn custom_citation_type_roundtrips_as_plain_string() {
+        let citation = Citation {
+            id: "e1".to_string(),
+            kind: CitationType::Custom("observation".to_string()),
+            ts: None,
+            summary: String::new(),
+        };
+        let json = serde_json::to_value(&citation).unwrap();
+        assert_eq!(json["type"], "observation");
+
+        let back: Citation = serde_json::from_value(json).unwrap();
+        assert!(matches!(back.kind, CitationType::Custom(ref name) if name == "observation"));
+        let builtin: CitationType = serde_json::from_str("\"tool_result\"").unwrap();
+        assert!(matches!(builtin, CitationType::ToolResult));
+    }
+
+    #[test]
+    fn generated_ids_are_sortable_ulids() {
+        let ids: Vec<String> = (0..100).map(|_| new_ulid()).collect();
+        assert!(ids.iter().all(|id| id.len() == 26));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let fact = Fact::new("user.name", serde_json::json!("Ada"));
+        let episode = Episode::new("deployed v2");
+        assert!(fact.fact_id < episode.episode_id);
+        assert_eq!(fact.confidence, 0.5);
+
+        let parsed: Fact =
+            serde_json::from_str(r#"{"fact_key": "user.name", "value": "Ada"}"#).unwrap();
+        assert_eq!(parsed.fact_id.len(), 26);
+    }
+
+    #[test]
+    fn concurrent_working_state_edits_converge() {
+        let base = WorkingState {
+            goal: "draft".to_string(),
+            plan: vec!["outline".to_string(), "write".to_string()],
+            ..WorkingState::default()
+        };
+        let stamp = |ms| DateTime::<Utc>::from_timestamp_millis(ms);
+
+        let mut left = base.clone();
+        let plan = vec!["outline".to_string(), "write".to_string(), "review".to_string()];
+        left.clock.plan.update(&left.plan, &plan);
+        left.plan = plan;
+        left.goal = "publish".to_string();
+        left.clock.goal = stamp(1_000);
+
+        let mut right = base.clone();
+        let plan = vec!["write".to_string()];
+        right.clock.plan.update(&right.plan, &plan);
+        right.plan = plan;
+        right.goal = "ship".to_string();
+        right.clock.goal = stamp(2_000);
+        right.state_version = 3;
+
+        let merged = left.merge(&right);
+        assert_eq!(merged.goal, "ship");
+        assert_eq!(merged.plan, vec!["write", "review"]);
+        assert_eq!(merged.state_version, 3);
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(right.merge(&left)).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(merged.merge(&left)).unwrap(),
+            serde_json::to_value(&merged).unwrap()
+        );
+    }
+
+    #[test]
+    fn plan_reorders_survive_concurrent_adds() {
+        let steps = |items: &[&str]| items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        let mut base = WorkingState::default();
+        let plan = steps(&["outline", "write", "review"]);
+        base.clock.plan.update(&base.plan, &plan);
+        base.plan = plan;
+
+        let mut left = base.clone();
+        let plan = steps(&["review", "outline", "write"]);
+        left.clock.plan.update(&left.plan, &plan);
+        left.plan = plan;
+
+        let mut right = base.clone();
+        let plan = steps(&["outline", "write", "review", "publish"]);
+        right.clock.plan.update(&right.plan, &plan);
+        right.plan = plan;
+
+        let merged = left.merge(&right);
+        assert_eq!(merged.plan, steps(&["review", "outline", "write", "publish"]));
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(right.merge(&left)).unwrap()
+        );
+
+        let mut later = right.clone();
+        let plan = steps(&["publish", "outline", "write", "review"]);
+        later.clock.plan.update(&later.plan, &plan);
+        later.plan = plan.clone();
+        assert_eq!(merged.merge(&later).plan, plan);
+    }
+
+    #[test]
+    fn tombstones_stay_bounded_without_resurrecting_items() {
+        let item = vec!["retry".to_string()];
+        let mut stale = OrSet::default();
+        stale.update(&[], &item);
+        let mut set = stale.clone();
+        set.update(&item, &[]);
+        for _ in 0..MAX_OR_SET_TOMBSTONES {
+            set.update(&[], &item);
+            set.update(&item, &[]);
+        }
+        assert_eq!(set.removes.len(), MAX_OR_SET_TOMBSTONES);
+        assert_eq!(set.adds["retry"].len(), MAX_OR_SET_TOMBSTONES);
+        assert!(set.compacted.is_some());
+
+        assert!(set.merge(&stale).items().is_empty());
+        assert!(stale.merge(&set).items().is_empty());
+        assert_eq!(set.merge(&stale), set);
+    }
+
+    #[test]
+    fn compaction_keeps_legacy_tombstones_for_old_replicas() {
+        let steps = |items: &[&str]| items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        let old = WorkingState {
+            plan: steps(&["outline", "retry", "publish"]),
+            ..WorkingState::default()
+        };
+
+        let mut compacted = WorkingState {
+            plan: steps(&["outline", "retry"]),
+            ..WorkingState::default()
+        };
+        let plan = steps(&["outline"]);
+        compacted.clock.plan.update(&compacted.plan, &plan);
+        compacted.plan = plan;
+        let churn = steps(&["outline", "check"]);
+        for _ in 0..=MAX_OR_SET_TOMBSTONES {
+            compacted.clock.plan.update(&compacted.plan, &churn);
+            compacted.clock.plan.update(&churn, &compacted.plan);
+        }
+        assert!(compacted.clock.plan.compacted.is_some());
+        assert!(compacted.clock.plan.removes.contains("!retry"));
+
+        let merged = compacted.merge(&old);
+        assert_eq!(merged.plan, steps(&["outline", "publish"]));
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(old.merge(&compacted)).unwrap()
+        );
+    }
 }
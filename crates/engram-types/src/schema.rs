@@ -0,0 +1,75 @@
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// JSON Schema (draft 2020-12) with a definition in `$defs` for every wire format and
+/// the types they share. Tests in `engram-store` validate what the Rust types serialize
+/// against it and diff its properties and enum values against the names the types'
+/// `Deserialize` impls accept, so it changes together with them.
+pub const WIRE_SCHEMA: &str = include_str!("../schemas/wire.schema.json");
+
+/// Payloads that producers outside Rust send to, or receive from, the FFI and HTTP
+/// layers. `Event` and `BuildRequest` describe the input forms those layers accept:
+/// generated ids, `ts_ms` timestamps and partial recall policies are allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WireType {
+    MemoryPacket,
+    Event,
+    Fact,
+    BuildRequest,
+}
+
+impl WireType {
+    pub const ALL: [WireType; 4] = [
+        WireType::MemoryPacket,
+        WireType::Event,
+        WireType::Fact,
+        WireType::BuildRequest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireType::MemoryPacket => "memory_packet",
+            WireType::Event => "event",
+            WireType::Fact => "fact",
+            WireType::BuildRequest => "build_request",
+        }
+    }
+
+    /// Name of the type's definition in [`WIRE_SCHEMA`].
+    pub fn definition(&self) -> &'static str {
+        match self {
+            WireType::MemoryPacket => "MemoryPacket",
+            WireType::Event => "Event",
+            WireType::Fact => "Fact",
+            WireType::BuildRequest => "BuildRequest",
+        }
+    }
+}
+
+impl fmt::Display for WireType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WireType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        WireType::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| format!("unknown wire type: {}", value))
+    }
+}
+
+/// Standalone schema of `kind`: [`WIRE_SCHEMA`] with a root `$ref` to the type's
+/// definition, ready for any JSON Schema validator.
+pub fn json_schema(kind: WireType) -> Value {
+    let mut schema: Value =
+        serde_json::from_str(WIRE_SCHEMA).expect("bundled wire schema is valid JSON");
+    schema["title"] = json!(kind.definition());
+    schema["$ref"] = json!(format!("#/$defs/{}", kind.definition()));
+    schema
+}
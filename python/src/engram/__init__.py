@@ -1,10 +1,29 @@
-from ._core import EngramStore
+from ._core import EngramStore, FactConflictError, VersionConflictError, new_id
 from .adapters import (
     EngramChatMessageHistory,
     EngramCheckpointer,
     EngramContextInjector,
     EngramNodeMiddleware,
 )
-from .client import AsyncMemory, Memory
+from .client import (
+    AsyncMemory,
+    Memory,
+    check_grounding,
+    chunk_text,
+    content_hash,
+    json_schema,
+    validate_json,
+)
 
-__all__ = ["Memory", "AsyncMemory"]
+__all__ = [
+    "Memory",
+    "AsyncMemory",
+    "check_grounding",
+    "chunk_text",
+    "content_hash",
+    "json_schema",
+    "validate_json",
+    "new_id",
+    "VersionConflictError",
+    "FactConflictError",
+]
@@ -1,6 +1,56 @@
+import copy
+import datetime
 import json
 
 from ._core import EngramStore
+from ._core import check_grounding as _check_grounding
+from ._core import chunk_text as _chunk_text
+from ._core import content_hash as _content_hash
+from ._core import json_schema as _json_schema
+from ._core import validate_json as _validate_json
+
+
+def check_grounding(response, packet):
+    return json.loads(_check_grounding(response, json.dumps(packet)))
+
+
+def chunk_text(source_id, text, options=None):
+    options_json = json.dumps(options) if options is not None else None
+    return json.loads(_chunk_text(source_id, text, options_json))
+
+
+def _codec(wire_format):
+    """Encoder and decoder for events and packets in `wire_format`."""
+    if wire_format == "json":
+        return json.dumps, json.loads
+    if wire_format != "cbor":
+        raise ValueError(f"invalid wire format: {wire_format}")
+    try:
+        import cbor2
+    except ImportError as err:
+        raise ImportError('wire_format="cbor" requires cbor2: pip install "engram[cbor]"') from err
+    return cbor2.dumps, cbor2.loads
+
+
+def _rfc3339(at):
+    """`at` as an RFC 3339 string; naive datetimes are taken as UTC."""
+    if isinstance(at, str):
+        return at
+    if at.tzinfo is None:
+        at = at.replace(tzinfo=datetime.timezone.utc)
+    return at.isoformat()
+
+
+def json_schema(kind):
+    return json.loads(_json_schema(kind))
+
+
+def validate_json(kind, payload):
+    _validate_json(kind, json.dumps(payload))
+
+
+def content_hash(kind, item):
+    return _content_hash(kind, json.dumps(item))
 
 
 class Memory:
@@ -11,31 +61,194 @@ class Memory:
         backend="sqlite",
         dsn=None,
         database=None,
+        strict=False,
+        changelog=False,
+        state_journal=False,
+        detect_lang=False,
+        agent_access=None,
+        metering=False,
+        scope_key=None,
+        wire_format="json",
+        statement_timeout_ms=None,
+        schema=None,
+        table_prefix=None,
     ):
+        self._dumps, self._loads = _codec(wire_format)
         self._store = EngramStore(
             path=path,
             backend=backend,
             dsn=dsn,
             database=database,
             in_memory=in_memory,
+            strict=strict,
+            changelog=changelog,
+            state_journal=state_journal,
+            detect_lang=detect_lang,
+            agent_access=json.dumps(agent_access) if agent_access is not None else None,
+            metering=metering,
+            scope_key=scope_key.encode() if isinstance(scope_key, str) else scope_key,
+            wire_format=wire_format,
+            statement_timeout_ms=statement_timeout_ms,
+            schema=schema,
+            table_prefix=table_prefix,
+        )
+
+    def with_correlation_id(self, correlation_id):
+        """A view of this memory whose writes are traced to `correlation_id`."""
+        handle = copy.copy(self)
+        handle._store = self._store.with_correlation_id(correlation_id)
+        return handle
+
+    def register_payload_schema(self, schema, kind=None, tag=None, mode="strict"):
+        self._store.register_payload_schema(json.dumps(schema), kind, tag, mode)
+
+    def register_slot_schema(self, task_type, schema, mode="strict"):
+        self._store.register_slot_schema(task_type, json.dumps(schema), mode)
+
+    def register_policy(self, policy_id, policy):
+        self._store.register_policy(policy_id, json.dumps(policy))
+
+    def load_policies(self, path):
+        return self._store.load_policies(str(path))
+
+    def list_changes(self, after_seq=0, limit=None):
+        return json.loads(self._store.list_changes(after_seq, limit))
+
+    def apply_changes(self, changes):
+        self._store.apply_changes(json.dumps(changes))
+
+    def tenant_stats(self, tenant_id, time_range=None, k_anonymity=5, epsilon=None):
+        payload = json.dumps(time_range) if time_range is not None else None
+        return json.loads(
+            self._store.tenant_stats(tenant_id, payload, k_anonymity, epsilon)
         )
 
     def append_event(self, event):
-        self._store.append_event(json.dumps(event))
+        self._store.append_event(self._dumps(event))
+
+    def append_events_bulk(self, events):
+        self._store.append_events_bulk(self._dumps(events))
+
+    def try_append_event(self, event):
+        self._store.try_append_event(self._dumps(event))
+
+    def flush_writes(self):
+        self._store.flush_writes()
 
     def list_events(self, scope, time_range=None, limit=None):
         payload = json.dumps(time_range) if time_range is not None else None
-        return json.loads(self._store.list_events(json.dumps(scope), payload, limit))
+        return self._loads(self._store.list_events(json.dumps(scope), payload, limit))
+
+    def list_events_page(self, scope, cursor=None, limit=100):
+        return self._loads(self._store.list_events_page(json.dumps(scope), cursor, limit))
+
+    def stream_events(self, scope, time_range=None, chunk_size=500):
+        payload = json.dumps(time_range) if time_range is not None else None
+        chunks = self._store.stream_events(json.dumps(scope), payload, chunk_size)
+        while True:
+            events = self._loads(chunks.next_chunk())
+            if not events:
+                return
+            yield from events
+
+    def search_events(self, scope, terms, time_range=None, limit=None):
+        query = {"terms": list(terms), "time_range": time_range, "limit": limit}
+        return self._loads(self._store.search_events(json.dumps(scope), json.dumps(query)))
+
+    def get_events_since(self, scope, seq=0, limit=None):
+        return self._loads(self._store.get_events_since(json.dumps(scope), seq, limit))
 
     def get_working_state(self, scope):
         data = self._store.get_working_state(json.dumps(scope))
         return json.loads(data) if data is not None else None
 
+    def replay_working_state(self, scope, up_to_seq=None):
+        data = self._store.replay_working_state(json.dumps(scope), up_to_seq)
+        return json.loads(data) if data is not None else None
+
+    def list_working_states(self, scope):
+        return json.loads(self._store.list_working_states(json.dumps(scope)))
+
     def patch_working_state(self, scope, patch):
         return json.loads(
             self._store.patch_working_state(json.dumps(scope), json.dumps(patch))
         )
 
+    def patch_working_states(self, patches):
+        batch = [{"scope": scope, "patch": patch} for scope, patch in patches]
+        return json.loads(self._store.patch_working_states(json.dumps(batch)))
+
+    def merge_working_state(self, scope, remote_state):
+        return json.loads(
+            self._store.merge_working_state(json.dumps(scope), json.dumps(remote_state))
+        )
+
+    def checkpoint_run(self, scope, label):
+        return json.loads(self._store.checkpoint_run(json.dumps(scope), label))
+
+    def replay_from_checkpoint(self, scope, label, target_scope):
+        return json.loads(
+            self._store.replay_from_checkpoint(
+                json.dumps(scope), label, json.dumps(target_scope)
+            )
+        )
+
+    def begin_run(self, scope):
+        return json.loads(self._store.begin_run(json.dumps(scope)))
+
+    def end_run(self, scope, outcome, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        return json.loads(self._store.end_run(json.dumps(scope), outcome, options_json))
+
+    def consolidate_run(self, scope, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        return json.loads(self._store.consolidate_run(json.dumps(scope), options_json))
+
+    def rebuild_derived_memory(self, scope, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        return json.loads(self._store.rebuild_derived_memory(json.dumps(scope), options_json))
+
+    def backfill_embeddings(self, scope, model, embed, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        return json.loads(
+            self._store.backfill_embeddings(json.dumps(scope), model, embed, options_json)
+        )
+
+    def merge_similar_episodes(self, scope, model, threshold=0.9):
+        return json.loads(
+            self._store.merge_similar_episodes(json.dumps(scope), model, threshold)
+        )
+
+    def tenant_usage(self, reset=False):
+        return json.loads(self._store.tenant_usage(reset))
+
+    def vector_index_stats(self, model, scope=None):
+        scope_json = json.dumps(scope) if scope is not None else None
+        return json.loads(self._store.vector_index_stats(model, scope_json))
+
+    def rebuild_vector_index(self, model, embed, scope=None, options=None):
+        scope_json = json.dumps(scope) if scope is not None else None
+        options_json = json.dumps(options) if options is not None else None
+        return json.loads(
+            self._store.rebuild_vector_index(model, embed, scope_json, options_json)
+        )
+
+    def enforce_memory_budget(self, scope, budget):
+        return json.loads(
+            self._store.enforce_memory_budget(json.dumps(scope), json.dumps(budget))
+        )
+
+    def run_retention(self, scope, policy):
+        return json.loads(self._store.run_retention(json.dumps(scope), json.dumps(policy)))
+
+    def memory_footprint(self, scope):
+        return self._store.memory_footprint(json.dumps(scope))
+
+    def build_handoff_packet(self, from_scope, to_scope):
+        return json.loads(
+            self._store.build_handoff_packet(json.dumps(from_scope), json.dumps(to_scope))
+        )
+
     def get_stm(self, scope):
         data = self._store.get_stm(json.dumps(scope))
         return json.loads(data) if data is not None else None
@@ -47,16 +260,136 @@ class Memory:
         payload = json.dumps(fact_filter) if fact_filter is not None else None
         return json.loads(self._store.list_facts(json.dumps(scope), payload))
 
-    def upsert_fact(self, scope, fact):
-        self._store.upsert_fact(json.dumps(scope), json.dumps(fact))
+    def upsert_fact(self, scope, fact, conflict_policy=None):
+        if conflict_policy is None:
+            self._store.upsert_fact(json.dumps(scope), json.dumps(fact))
+            return None
+        data = self._store.upsert_fact_checked(
+            json.dumps(scope), json.dumps(fact), conflict_policy
+        )
+        return json.loads(data)
+
+    def upsert_facts_bulk(self, scope, facts):
+        self._store.upsert_facts_bulk(json.dumps(scope), json.dumps(facts))
+
+    def list_fact_history(self, scope, fact_key):
+        return json.loads(self._store.list_fact_history(json.dumps(scope), fact_key))
+
+    def pin_fact(self, scope, fact_id):
+        return json.loads(self._store.pin_fact(json.dumps(scope), fact_id, True))
+
+    def unpin_fact(self, scope, fact_id):
+        return json.loads(self._store.pin_fact(json.dumps(scope), fact_id, False))
+
+    def set_preference(self, scope, key, value, confidence=1.0):
+        data = self._store.set_preference(json.dumps(scope), key, json.dumps(value), confidence)
+        return json.loads(data)
+
+    def get_preferences(self, scope):
+        return json.loads(self._store.get_preferences(json.dumps(scope)))
+
+    def expire_facts(self, tenant_id):
+        return json.loads(self._store.expire_facts(tenant_id))
+
+    def export_tenant_archive(self, tenant_id):
+        return self._store.export_tenant_archive(tenant_id)
+
+    def import_tenant_archive(self, archive, tenant_id=None, user_ids=None, regenerate_ids=False):
+        user_ids_json = json.dumps(user_ids) if user_ids is not None else None
+        return json.loads(
+            self._store.import_tenant_archive(archive, tenant_id, user_ids_json, regenerate_ids)
+        )
+
+    def verify_archive(self, archive):
+        return json.loads(self._store.verify_archive(archive))
+
+    def metrics_snapshot(self):
+        return json.loads(self._store.metrics_snapshot())
+
+    def capabilities(self):
+        return json.loads(self._store.capabilities())
+
+    def suppress_memory(self, scope, item, reason=""):
+        self._store.suppress_memory(json.dumps(scope), json.dumps(item), reason)
+
+    def unsuppress_memory(self, scope, item):
+        return self._store.unsuppress_memory(json.dumps(scope), json.dumps(item))
+
+    def delete_record(self, scope, item, soft=False):
+        return self._store.delete_record(json.dumps(scope), json.dumps(item), soft)
+
+    def delete_event(self, scope, event_id, soft=False):
+        return self.delete_record(scope, {"kind": "event", "id": event_id}, soft)
+
+    def delete_fact(self, scope, fact_id, soft=False):
+        return self.delete_record(scope, {"kind": "fact", "id": fact_id}, soft)
+
+    def delete_episode(self, scope, episode_id, soft=False):
+        return self.delete_record(scope, {"kind": "episode", "id": episode_id}, soft)
+
+    def delete_procedure(self, scope, procedure_id, soft=False):
+        return self.delete_record(scope, {"kind": "procedure", "id": procedure_id}, soft)
+
+    def delete_insight(self, scope, insight_id, soft=False):
+        return self.delete_record(scope, {"kind": "insight", "id": insight_id}, soft)
+
+    def clear_scope(self, scope, soft=False):
+        return self._store.clear_scope(json.dumps(scope), soft)
+
+    def list_tombstones(self, scope, limit=None):
+        return json.loads(self._store.list_tombstones(json.dumps(scope), limit))
+
+    def acquire_lease(self, scope, ttl_ms):
+        data = self._store.acquire_lease(json.dumps(scope), ttl_ms)
+        return json.loads(data) if data is not None else None
+
+    def renew_lease(self, lease, ttl_ms):
+        data = self._store.renew_lease(json.dumps(lease), ttl_ms)
+        return json.loads(data) if data is not None else None
+
+    def release_lease(self, lease):
+        return self._store.release_lease(json.dumps(lease))
+
+    def list_suppressions(self, scope):
+        return json.loads(self._store.list_suppressions(json.dumps(scope)))
+
+    def record_run_outcome(self, scope, outcome):
+        self._store.record_run_outcome(json.dumps(scope), json.dumps(outcome))
+
+    def list_run_outcomes(self, scope, limit=None):
+        return json.loads(self._store.list_run_outcomes(json.dumps(scope), limit))
+
+    def set_source_credibility(self, tenant_id, credibility):
+        self._store.set_source_credibility(tenant_id, json.dumps(credibility))
+
+    def get_source_credibility(self, tenant_id):
+        data = self._store.get_source_credibility(tenant_id)
+        return json.loads(data) if data is not None else None
+
+    def set_user_locale(self, tenant_id, user_id, locale):
+        self._store.set_user_locale(tenant_id, user_id, json.dumps(locale))
+
+    def get_user_locale(self, tenant_id, user_id):
+        data = self._store.get_user_locale(tenant_id, user_id)
+        return json.loads(data) if data is not None else None
+
+    def resolve_provenance(self, scope, fact_id):
+        return json.loads(self._store.resolve_provenance(json.dumps(scope), fact_id))
 
     def list_episodes(self, scope, episode_filter=None):
         payload = json.dumps(episode_filter) if episode_filter is not None else None
         return json.loads(self._store.list_episodes(json.dumps(scope), payload))
 
+    def search_episodes(self, scope, terms, time_range=None, limit=None):
+        query = {"terms": list(terms), "time_range": time_range, "limit": limit}
+        return json.loads(self._store.search_episodes(json.dumps(scope), json.dumps(query)))
+
     def append_episode(self, scope, episode):
         self._store.append_episode(json.dumps(scope), json.dumps(episode))
 
+    def append_episodes_bulk(self, scope, episodes):
+        self._store.append_episodes_bulk(json.dumps(scope), json.dumps(episodes))
+
     def list_procedures(self, scope, task_type, limit=None):
         return json.loads(
             self._store.list_procedures(json.dumps(scope), task_type, limit)
@@ -65,6 +398,12 @@ class Memory:
     def upsert_procedure(self, scope, procedure):
         self._store.upsert_procedure(json.dumps(scope), json.dumps(procedure))
 
+    def pin_tenant_procedure(self, tenant_id, procedure):
+        return json.loads(self._store.pin_tenant_procedure(tenant_id, json.dumps(procedure)))
+
+    def list_tenant_procedures(self, tenant_id):
+        return json.loads(self._store.list_tenant_procedures(tenant_id))
+
     def list_insights(self, scope, insight_filter=None):
         payload = json.dumps(insight_filter) if insight_filter is not None else None
         return json.loads(self._store.list_insights(json.dumps(scope), payload))
@@ -72,14 +411,35 @@ class Memory:
     def append_insight(self, scope, insight):
         self._store.append_insight(json.dumps(scope), json.dumps(insight))
 
+    def carry_forward_insights(self, from_scope, to_scope):
+        return json.loads(
+            self._store.carry_forward_insights(json.dumps(from_scope), json.dumps(to_scope))
+        )
+
+    def insight_lineage(self, runs, insight_id):
+        return json.loads(self._store.insight_lineage(json.dumps(runs), insight_id))
+
     def write_context_build(self, scope, packet):
         self._store.write_context_build(json.dumps(scope), json.dumps(packet))
 
-    def list_context_builds(self, scope, limit=None):
-        return json.loads(self._store.list_context_builds(json.dumps(scope), limit))
+    def list_context_builds(self, scope, limit=None, metadata_only=False):
+        return json.loads(
+            self._store.list_context_builds(json.dumps(scope), limit, metadata_only)
+        )
 
     def build_memory_packet(self, request):
-        return json.loads(self._store.build_memory_packet(json.dumps(request)))
+        return self._loads(self._store.build_memory_packet(json.dumps(request)))
+
+    def build_memory_packet_as_of(self, request, at):
+        return self._loads(self._store.build_memory_packet_as_of(json.dumps(request), _rfc3339(at)))
+
+    def estimate_packet_size(self, request):
+        return json.loads(self._store.estimate_packet_size(json.dumps(request)))
+
+    def build_memory_packets_bulk(self, requests, concurrency=0):
+        return self._loads(
+            self._store.build_memory_packets_bulk(json.dumps(requests), concurrency)
+        )
 
 
 class AsyncMemory:
@@ -90,33 +450,215 @@ class AsyncMemory:
         backend="sqlite",
         dsn=None,
         database=None,
+        strict=False,
+        changelog=False,
+        state_journal=False,
+        detect_lang=False,
+        agent_access=None,
+        metering=False,
+        scope_key=None,
+        wire_format="json",
+        statement_timeout_ms=None,
+        schema=None,
+        table_prefix=None,
     ):
+        self._dumps, self._loads = _codec(wire_format)
         self._store = EngramStore(
             path=path,
             backend=backend,
             dsn=dsn,
             database=database,
             in_memory=in_memory,
+            strict=strict,
+            changelog=changelog,
+            state_journal=state_journal,
+            detect_lang=detect_lang,
+            agent_access=json.dumps(agent_access) if agent_access is not None else None,
+            metering=metering,
+            scope_key=scope_key.encode() if isinstance(scope_key, str) else scope_key,
+            wire_format=wire_format,
+            statement_timeout_ms=statement_timeout_ms,
+            schema=schema,
+            table_prefix=table_prefix,
         )
 
+    def with_correlation_id(self, correlation_id):
+        """A view of this memory whose writes are traced to `correlation_id`."""
+        handle = copy.copy(self)
+        handle._store = self._store.with_correlation_id(correlation_id)
+        return handle
+
+    def register_payload_schema(self, schema, kind=None, tag=None, mode="strict"):
+        self._store.register_payload_schema(json.dumps(schema), kind, tag, mode)
+
+    def register_slot_schema(self, task_type, schema, mode="strict"):
+        self._store.register_slot_schema(task_type, json.dumps(schema), mode)
+
+    def register_policy(self, policy_id, policy):
+        self._store.register_policy(policy_id, json.dumps(policy))
+
+    def load_policies(self, path):
+        return self._store.load_policies(str(path))
+
+    async def list_changes(self, after_seq=0, limit=None):
+        data = await self._store.async_list_changes(after_seq, limit)
+        return json.loads(data)
+
+    async def apply_changes(self, changes):
+        await self._store.async_apply_changes(json.dumps(changes))
+
+    async def tenant_stats(self, tenant_id, time_range=None, k_anonymity=5, epsilon=None):
+        payload = json.dumps(time_range) if time_range is not None else None
+        data = await self._store.async_tenant_stats(tenant_id, payload, k_anonymity, epsilon)
+        return json.loads(data)
+
     async def append_event(self, event):
-        await self._store.async_append_event(json.dumps(event))
+        await self._store.async_append_event(self._dumps(event))
+
+    async def append_events_bulk(self, events):
+        await self._store.async_append_events_bulk(self._dumps(events))
+
+    def try_append_event(self, event):
+        self._store.try_append_event(self._dumps(event))
+
+    async def flush_writes(self):
+        await self._store.async_flush_writes()
 
     async def list_events(self, scope, time_range=None, limit=None):
         payload = json.dumps(time_range) if time_range is not None else None
         data = await self._store.async_list_events(json.dumps(scope), payload, limit)
-        return json.loads(data)
+        return self._loads(data)
+
+    async def list_events_page(self, scope, cursor=None, limit=100):
+        data = await self._store.async_list_events_page(json.dumps(scope), cursor, limit)
+        return self._loads(data)
+
+    async def stream_events(self, scope, time_range=None, chunk_size=500):
+        payload = json.dumps(time_range) if time_range is not None else None
+        chunks = self._store.stream_events(json.dumps(scope), payload, chunk_size)
+        while True:
+            events = self._loads(await chunks.async_next_chunk())
+            if not events:
+                return
+            for event in events:
+                yield event
+
+    async def search_events(self, scope, terms, time_range=None, limit=None):
+        query = {"terms": list(terms), "time_range": time_range, "limit": limit}
+        data = await self._store.async_search_events(json.dumps(scope), json.dumps(query))
+        return self._loads(data)
+
+    async def get_events_since(self, scope, seq=0, limit=None):
+        data = await self._store.async_get_events_since(json.dumps(scope), seq, limit)
+        return self._loads(data)
 
     async def get_working_state(self, scope):
         data = await self._store.async_get_working_state(json.dumps(scope))
         return json.loads(data) if data is not None else None
 
+    async def replay_working_state(self, scope, up_to_seq=None):
+        data = await self._store.async_replay_working_state(json.dumps(scope), up_to_seq)
+        return json.loads(data) if data is not None else None
+
+    async def list_working_states(self, scope):
+        data = await self._store.async_list_working_states(json.dumps(scope))
+        return json.loads(data)
+
     async def patch_working_state(self, scope, patch):
         data = await self._store.async_patch_working_state(
             json.dumps(scope), json.dumps(patch)
         )
         return json.loads(data)
 
+    async def patch_working_states(self, patches):
+        batch = [{"scope": scope, "patch": patch} for scope, patch in patches]
+        data = await self._store.async_patch_working_states(json.dumps(batch))
+        return json.loads(data)
+
+    async def merge_working_state(self, scope, remote_state):
+        data = await self._store.async_merge_working_state(
+            json.dumps(scope), json.dumps(remote_state)
+        )
+        return json.loads(data)
+
+    async def checkpoint_run(self, scope, label):
+        data = await self._store.async_checkpoint_run(json.dumps(scope), label)
+        return json.loads(data)
+
+    async def replay_from_checkpoint(self, scope, label, target_scope):
+        data = await self._store.async_replay_from_checkpoint(
+            json.dumps(scope), label, json.dumps(target_scope)
+        )
+        return json.loads(data)
+
+    async def begin_run(self, scope):
+        data = await self._store.async_begin_run(json.dumps(scope))
+        return json.loads(data)
+
+    async def end_run(self, scope, outcome, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        data = await self._store.async_end_run(json.dumps(scope), outcome, options_json)
+        return json.loads(data)
+
+    async def consolidate_run(self, scope, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        data = await self._store.async_consolidate_run(json.dumps(scope), options_json)
+        return json.loads(data)
+
+    async def rebuild_derived_memory(self, scope, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        data = await self._store.async_rebuild_derived_memory(json.dumps(scope), options_json)
+        return json.loads(data)
+
+    async def backfill_embeddings(self, scope, model, embed, options=None):
+        options_json = json.dumps(options) if options is not None else None
+        data = await self._store.async_backfill_embeddings(
+            json.dumps(scope), model, embed, options_json
+        )
+        return json.loads(data)
+
+    async def merge_similar_episodes(self, scope, model, threshold=0.9):
+        data = await self._store.async_merge_similar_episodes(
+            json.dumps(scope), model, threshold
+        )
+        return json.loads(data)
+
+    async def tenant_usage(self, reset=False):
+        data = await self._store.async_tenant_usage(reset)
+        return json.loads(data)
+
+    async def vector_index_stats(self, model, scope=None):
+        scope_json = json.dumps(scope) if scope is not None else None
+        data = await self._store.async_vector_index_stats(model, scope_json)
+        return json.loads(data)
+
+    async def rebuild_vector_index(self, model, embed, scope=None, options=None):
+        scope_json = json.dumps(scope) if scope is not None else None
+        options_json = json.dumps(options) if options is not None else None
+        data = await self._store.async_rebuild_vector_index(
+            model, embed, scope_json, options_json
+        )
+        return json.loads(data)
+
+    async def run_retention(self, scope, policy):
+        data = await self._store.async_run_retention(json.dumps(scope), json.dumps(policy))
+        return json.loads(data)
+
+    async def enforce_memory_budget(self, scope, budget):
+        data = await self._store.async_enforce_memory_budget(
+            json.dumps(scope), json.dumps(budget)
+        )
+        return json.loads(data)
+
+    async def memory_footprint(self, scope):
+        return await self._store.async_memory_footprint(json.dumps(scope))
+
+    async def build_handoff_packet(self, from_scope, to_scope):
+        data = await self._store.async_build_handoff_packet(
+            json.dumps(from_scope), json.dumps(to_scope)
+        )
+        return json.loads(data)
+
     async def get_stm(self, scope):
         data = await self._store.async_get_stm(json.dumps(scope))
         return json.loads(data) if data is not None else None
@@ -129,17 +671,153 @@ class AsyncMemory:
         data = await self._store.async_list_facts(json.dumps(scope), payload)
         return json.loads(data)
 
-    async def upsert_fact(self, scope, fact):
-        await self._store.async_upsert_fact(json.dumps(scope), json.dumps(fact))
+    async def upsert_fact(self, scope, fact, conflict_policy=None):
+        if conflict_policy is None:
+            await self._store.async_upsert_fact(json.dumps(scope), json.dumps(fact))
+            return None
+        data = await self._store.async_upsert_fact_checked(
+            json.dumps(scope), json.dumps(fact), conflict_policy
+        )
+        return json.loads(data)
+
+    async def upsert_facts_bulk(self, scope, facts):
+        await self._store.async_upsert_facts_bulk(json.dumps(scope), json.dumps(facts))
+
+    async def list_fact_history(self, scope, fact_key):
+        data = await self._store.async_list_fact_history(json.dumps(scope), fact_key)
+        return json.loads(data)
+
+    async def pin_fact(self, scope, fact_id):
+        data = await self._store.async_pin_fact(json.dumps(scope), fact_id, True)
+        return json.loads(data)
+
+    async def unpin_fact(self, scope, fact_id):
+        data = await self._store.async_pin_fact(json.dumps(scope), fact_id, False)
+        return json.loads(data)
+
+    async def set_preference(self, scope, key, value, confidence=1.0):
+        data = await self._store.async_set_preference(
+            json.dumps(scope), key, json.dumps(value), confidence
+        )
+        return json.loads(data)
+
+    async def get_preferences(self, scope):
+        return json.loads(await self._store.async_get_preferences(json.dumps(scope)))
+
+    async def expire_facts(self, tenant_id):
+        return json.loads(await self._store.async_expire_facts(tenant_id))
+
+    async def export_tenant_archive(self, tenant_id):
+        return await self._store.async_export_tenant_archive(tenant_id)
+
+    async def import_tenant_archive(
+        self, archive, tenant_id=None, user_ids=None, regenerate_ids=False
+    ):
+        user_ids_json = json.dumps(user_ids) if user_ids is not None else None
+        return json.loads(
+            await self._store.async_import_tenant_archive(
+                archive, tenant_id, user_ids_json, regenerate_ids
+            )
+        )
+
+    def verify_archive(self, archive):
+        return json.loads(self._store.verify_archive(archive))
+
+    def metrics_snapshot(self):
+        return json.loads(self._store.metrics_snapshot())
+
+    def capabilities(self):
+        return json.loads(self._store.capabilities())
+
+    async def suppress_memory(self, scope, item, reason=""):
+        await self._store.async_suppress_memory(json.dumps(scope), json.dumps(item), reason)
+
+    async def unsuppress_memory(self, scope, item):
+        return await self._store.async_unsuppress_memory(json.dumps(scope), json.dumps(item))
+
+    async def delete_record(self, scope, item, soft=False):
+        return await self._store.async_delete_record(json.dumps(scope), json.dumps(item), soft)
+
+    async def delete_event(self, scope, event_id, soft=False):
+        return await self.delete_record(scope, {"kind": "event", "id": event_id}, soft)
+
+    async def delete_fact(self, scope, fact_id, soft=False):
+        return await self.delete_record(scope, {"kind": "fact", "id": fact_id}, soft)
+
+    async def delete_episode(self, scope, episode_id, soft=False):
+        return await self.delete_record(scope, {"kind": "episode", "id": episode_id}, soft)
+
+    async def delete_procedure(self, scope, procedure_id, soft=False):
+        return await self.delete_record(scope, {"kind": "procedure", "id": procedure_id}, soft)
+
+    async def delete_insight(self, scope, insight_id, soft=False):
+        return await self.delete_record(scope, {"kind": "insight", "id": insight_id}, soft)
+
+    async def clear_scope(self, scope, soft=False):
+        return await self._store.async_clear_scope(json.dumps(scope), soft)
+
+    async def list_tombstones(self, scope, limit=None):
+        data = await self._store.async_list_tombstones(json.dumps(scope), limit)
+        return json.loads(data)
+
+    async def acquire_lease(self, scope, ttl_ms):
+        data = await self._store.async_acquire_lease(json.dumps(scope), ttl_ms)
+        return json.loads(data) if data is not None else None
+
+    async def renew_lease(self, lease, ttl_ms):
+        data = await self._store.async_renew_lease(json.dumps(lease), ttl_ms)
+        return json.loads(data) if data is not None else None
+
+    async def release_lease(self, lease):
+        return await self._store.async_release_lease(json.dumps(lease))
+
+    async def list_suppressions(self, scope):
+        data = await self._store.async_list_suppressions(json.dumps(scope))
+        return json.loads(data)
+
+    async def record_run_outcome(self, scope, outcome):
+        await self._store.async_record_run_outcome(json.dumps(scope), json.dumps(outcome))
+
+    async def list_run_outcomes(self, scope, limit=None):
+        data = await self._store.async_list_run_outcomes(json.dumps(scope), limit)
+        return json.loads(data)
+
+    async def set_source_credibility(self, tenant_id, credibility):
+        await self._store.async_set_source_credibility(tenant_id, json.dumps(credibility))
+
+    async def get_source_credibility(self, tenant_id):
+        data = await self._store.async_get_source_credibility(tenant_id)
+        return json.loads(data) if data is not None else None
+
+    async def set_user_locale(self, tenant_id, user_id, locale):
+        await self._store.async_set_user_locale(tenant_id, user_id, json.dumps(locale))
+
+    async def get_user_locale(self, tenant_id, user_id):
+        data = await self._store.async_get_user_locale(tenant_id, user_id)
+        return json.loads(data) if data is not None else None
+
+    async def resolve_provenance(self, scope, fact_id):
+        data = await self._store.async_resolve_provenance(json.dumps(scope), fact_id)
+        return json.loads(data)
 
     async def list_episodes(self, scope, episode_filter=None):
         payload = json.dumps(episode_filter) if episode_filter is not None else None
         data = await self._store.async_list_episodes(json.dumps(scope), payload)
         return json.loads(data)
 
+    async def search_episodes(self, scope, terms, time_range=None, limit=None):
+        query = {"terms": list(terms), "time_range": time_range, "limit": limit}
+        data = await self._store.async_search_episodes(json.dumps(scope), json.dumps(query))
+        return json.loads(data)
+
     async def append_episode(self, scope, episode):
         await self._store.async_append_episode(json.dumps(scope), json.dumps(episode))
 
+    async def append_episodes_bulk(self, scope, episodes):
+        await self._store.async_append_episodes_bulk(
+            json.dumps(scope), json.dumps(episodes)
+        )
+
     async def list_procedures(self, scope, task_type, limit=None):
         data = await self._store.async_list_procedures(
             json.dumps(scope), task_type, limit
@@ -149,6 +827,13 @@ class AsyncMemory:
     async def upsert_procedure(self, scope, procedure):
         await self._store.async_upsert_procedure(json.dumps(scope), json.dumps(procedure))
 
+    async def pin_tenant_procedure(self, tenant_id, procedure):
+        data = await self._store.async_pin_tenant_procedure(tenant_id, json.dumps(procedure))
+        return json.loads(data)
+
+    async def list_tenant_procedures(self, tenant_id):
+        return json.loads(await self._store.async_list_tenant_procedures(tenant_id))
+
     async def list_insights(self, scope, insight_filter=None):
         payload = json.dumps(insight_filter) if insight_filter is not None else None
         data = await self._store.async_list_insights(json.dumps(scope), payload)
@@ -157,13 +842,39 @@ class AsyncMemory:
     async def append_insight(self, scope, insight):
         await self._store.async_append_insight(json.dumps(scope), json.dumps(insight))
 
+    async def carry_forward_insights(self, from_scope, to_scope):
+        data = await self._store.async_carry_forward_insights(
+            json.dumps(from_scope), json.dumps(to_scope)
+        )
+        return json.loads(data)
+
+    async def insight_lineage(self, runs, insight_id):
+        data = await self._store.async_insight_lineage(json.dumps(runs), insight_id)
+        return json.loads(data)
+
     async def write_context_build(self, scope, packet):
         await self._store.async_write_context_build(json.dumps(scope), json.dumps(packet))
 
-    async def list_context_builds(self, scope, limit=None):
-        data = await self._store.async_list_context_builds(json.dumps(scope), limit)
+    async def list_context_builds(self, scope, limit=None, metadata_only=False):
+        data = await self._store.async_list_context_builds(
+            json.dumps(scope), limit, metadata_only
+        )
         return json.loads(data)
 
     async def build_memory_packet(self, request):
         data = await self._store.async_build_memory_packet(json.dumps(request))
+        return self._loads(data)
+
+    async def build_memory_packet_as_of(self, request, at):
+        data = await self._store.async_build_memory_packet_as_of(json.dumps(request), _rfc3339(at))
+        return self._loads(data)
+
+    async def estimate_packet_size(self, request):
+        data = await self._store.async_estimate_packet_size(json.dumps(request))
         return json.loads(data)
+
+    async def build_memory_packets_bulk(self, requests, concurrency=0):
+        data = await self._store.async_build_memory_packets_bulk(
+            json.dumps(requests), concurrency
+        )
+        return self._loads(data)